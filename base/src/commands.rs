@@ -105,6 +105,12 @@ pub use vendor::*;
 pub trait TpmCommand: Marshalable {
     /// The command code.
     const CMD_CODE: TpmCc;
+    /// Whether the first command parameter is a sized buffer, which a session with the `decrypt`
+    /// attribute encrypts.
+    const DECRYPT_PARAMETER: bool = false;
+    /// Whether the first response parameter is a sized buffer, which a session with the `encrypt`
+    /// attribute encrypts.
+    const ENCRYPT_PARAMETER: bool = false;
     /// The command handles type.
     type Handles: Marshalable + Default;
     /// The response parameters type.
@@ -1,5 +1,11 @@
 //! [TPM2.0 1.83] 14 Asymmetric Primitives
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmHandle};
+use crate::{
+    Tpm2bDigest, Tpm2bEccPoint, Tpm2bMaxBuffer, TpmiEccCurve, TpmsAlgorithmDetailEcc, TpmtKdfScheme,
+};
+
 /// [TPM2.0 1.83] 14.2 TPM2_RSA_Encrypt (Command)
 pub struct RsaEncryptCmd {}
 
@@ -13,13 +19,71 @@ pub struct EcdhKeyGenCmd {}
 pub struct EcdhZGenCmd {}
 
 /// [TPM2.0 1.83] 14.6 TPM2_ECC_Parameters (Command)
-pub struct EccParametersCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EccParametersCmd {
+    pub curve_id: TpmiEccCurve,
+}
+impl TpmCommand for EccParametersCmd {
+    const CMD_CODE: TpmCc = TpmCc::ECCParameters;
+    type Handles = ();
+    type RespT = EccParametersResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 14.6 TPM2_ECC_Parameters (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EccParametersResp {
+    pub parameters: TpmsAlgorithmDetailEcc,
+}
 
 /// [TPM2.0 1.83] 14.7 TPM2_ZGen_2Phase (Command)
 pub struct ZGen2PhaseCmd {}
 
 /// [TPM2.0 1.83] 14.8 TPM2_ECC_Encrypt (Command)
-pub struct EccEncryptCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EccEncryptCmd {
+    pub plain_text: Tpm2bMaxBuffer,
+    pub in_scheme: TpmtKdfScheme,
+}
+impl TpmCommand for EccEncryptCmd {
+    const CMD_CODE: TpmCc = TpmCc::ECCEncrypt;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    type Handles = TpmHandle;
+    type RespT = EccEncryptResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 14.8 TPM2_ECC_Encrypt (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EccEncryptResp {
+    pub c1: Tpm2bEccPoint,
+    pub c2: Tpm2bMaxBuffer,
+    pub c3: Tpm2bDigest,
+}
 
 /// [TPM2.0 1.83] 14.9 TPM2_ECC_Decrypt (Command)
-pub struct EccDecryptCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EccDecryptCmd {
+    pub c1: Tpm2bEccPoint,
+    pub c2: Tpm2bMaxBuffer,
+    pub c3: Tpm2bDigest,
+    pub in_scheme: TpmtKdfScheme,
+}
+impl TpmCommand for EccDecryptCmd {
+    const CMD_CODE: TpmCc = TpmCc::ECCDecrypt;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    type Handles = TpmHandle;
+    type RespT = EccDecryptResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 14.9 TPM2_ECC_Decrypt (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EccDecryptResp {
+    pub plain_text: Tpm2bMaxBuffer,
+}
@@ -1,22 +1,158 @@
 //! [TPM2.0 1.83] 18 Attestation Commands
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmHandle};
+use crate::{
+    Tpm2bAttest, Tpm2bData, Tpm2bDigest, TpmlPcrSelection, TpmtSigScheme, TpmtSignature,
+    TpmtTkCreation,
+};
+
 /// [TPM2.0 1.83] 18.2 TPM2_Certify (Command)
-pub struct CertifyCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct CertifyCmd {
+    pub qualifying_data: Tpm2bData,
+    pub in_scheme: TpmtSigScheme,
+}
+impl TpmCommand for CertifyCmd {
+    const CMD_CODE: TpmCc = TpmCc::Certify;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The object to certify and the key that signs the attestation.
+    type Handles = (TpmHandle, TpmHandle);
+    type RespT = CertifyResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 18.2 TPM2_Certify (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct CertifyResp {
+    pub certify_info: Tpm2bAttest,
+    pub signature: TpmtSignature,
+}
 
 /// [TPM2.0 1.83] 18.3 TPM2_CertifyCreation (Command)
-pub struct CertifyCreationCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct CertifyCreationCmd {
+    pub qualifying_data: Tpm2bData,
+    pub creation_hash: Tpm2bDigest,
+    pub in_scheme: TpmtSigScheme,
+    pub creation_ticket: TpmtTkCreation,
+}
+impl TpmCommand for CertifyCreationCmd {
+    const CMD_CODE: TpmCc = TpmCc::CertifyCreation;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The key that signs the attestation and the object that was created.
+    type Handles = (TpmHandle, TpmHandle);
+    type RespT = CertifyCreationResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 18.3 TPM2_CertifyCreation (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct CertifyCreationResp {
+    pub certify_info: Tpm2bAttest,
+    pub signature: TpmtSignature,
+}
 
 /// [TPM2.0 1.83] 18.4 TPM2_Quote (Command)
-pub struct QuoteCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct QuoteCmd {
+    pub qualifying_data: Tpm2bData,
+    pub in_scheme: TpmtSigScheme,
+    pub pcr_select: TpmlPcrSelection,
+}
+impl TpmCommand for QuoteCmd {
+    const CMD_CODE: TpmCc = TpmCc::Quote;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    type Handles = TpmHandle;
+    type RespT = QuoteResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 18.4 TPM2_Quote (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct QuoteResp {
+    pub quoted: Tpm2bAttest,
+    pub signature: TpmtSignature,
+}
 
 /// [TPM2.0 1.83] 18.5 TPM2_GetSessionAuditDigest (Command)
-pub struct GetSessionAuditDigestCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct GetSessionAuditDigestCmd {
+    pub qualifying_data: Tpm2bData,
+    pub in_scheme: TpmtSigScheme,
+}
+impl TpmCommand for GetSessionAuditDigestCmd {
+    const CMD_CODE: TpmCc = TpmCc::GetSessionAuditDigest;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The endorsement hierarchy, the key that signs the attestation and the audit session.
+    type Handles = (TpmHandle, TpmHandle, TpmHandle);
+    type RespT = GetSessionAuditDigestResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 18.5 TPM2_GetSessionAuditDigest (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct GetSessionAuditDigestResp {
+    pub audit_info: Tpm2bAttest,
+    pub signature: TpmtSignature,
+}
 
 /// [TPM2.0 1.83] 18.6 TPM2_GetCommandAuditDigest (Command)
-pub struct GetCommandAuditDigestCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct GetCommandAuditDigestCmd {
+    pub qualifying_data: Tpm2bData,
+    pub in_scheme: TpmtSigScheme,
+}
+impl TpmCommand for GetCommandAuditDigestCmd {
+    const CMD_CODE: TpmCc = TpmCc::GetCommandAuditDigest;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The endorsement hierarchy and the key that signs the attestation.
+    type Handles = (TpmHandle, TpmHandle);
+    type RespT = GetCommandAuditDigestResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 18.6 TPM2_GetCommandAuditDigest (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct GetCommandAuditDigestResp {
+    pub audit_info: Tpm2bAttest,
+    pub signature: TpmtSignature,
+}
 
 /// [TPM2.0 1.83] 18.7 TPM2_GetTime (Command)
-pub struct GetTimeCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct GetTimeCmd {
+    pub qualifying_data: Tpm2bData,
+    pub in_scheme: TpmtSigScheme,
+}
+impl TpmCommand for GetTimeCmd {
+    const CMD_CODE: TpmCc = TpmCc::GetTime;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The endorsement hierarchy, whose authorization reveals the clock, and the key that signs
+    // the attestation.
+    type Handles = (TpmHandle, TpmHandle);
+    type RespT = GetTimeResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 18.7 TPM2_GetTime (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct GetTimeResp {
+    pub time_info: Tpm2bAttest,
+    pub signature: TpmtSignature,
+}
 
 /// [TPM2.0 1.83] 18.8 TPM2_CertifyX509 (Command)
 pub struct CertifyX509Cmd {}
@@ -2,11 +2,11 @@
 
 use crate::commands::{Marshalable, TpmCommand};
 use crate::constants::{TpmCap, TpmCc, TpmPt};
-use crate::{TpmiYesNo, TpmsCapabilityData};
+use crate::{TpmiYesNo, TpmsCapabilityData, TpmtPublicParms};
 
 /// [TPM2.0 1.83] 30.2 TPM2_GetCapability (Command)
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct GetCapabilityCmd {
     pub capability: TpmCap,
     pub property: TpmPt,
@@ -28,7 +28,17 @@ pub struct GetCapabilityResp {
 }
 
 /// [TPM2.0 1.83] 30.3 TPM2_TestParms (Command)
-pub struct TestParmsCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct TestParmsCmd {
+    pub parameters: TpmtPublicParms,
+}
+impl TpmCommand for TestParmsCmd {
+    const CMD_CODE: TpmCc = TpmCc::TestParams;
+    type Handles = ();
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 30.4 TPM2_SetCapability (Command)
 pub struct SetCapabilityCmd {}
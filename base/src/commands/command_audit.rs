@@ -1,4 +1,21 @@
 //! [TPM2.0 1.83] 21 Command Audit
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmHandle};
+use crate::{TpmiAlgHash, TpmlCc};
+
 /// [TPM2.0 1.83] 21.2 TPM2_SetCommandCodeAuditStatus (Command)
-pub struct SetCommandCodeAuditStatusCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct SetCommandCodeAuditStatusCmd {
+    pub audit_alg: TpmiAlgHash,
+    pub set_list: TpmlCc,
+    pub clear_list: TpmlCc,
+}
+impl TpmCommand for SetCommandCodeAuditStatusCmd {
+    const CMD_CODE: TpmCc = TpmCc::SetCommandCodeAuditStatus;
+    // The owner or platform hierarchy. An `audit_alg` of TPM_ALG_NULL keeps the current one.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
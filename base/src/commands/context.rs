@@ -1,5 +1,8 @@
 //! [TPM2.0 1.83] 28 Context Management
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmHandle};
+
 /// [TPM2.0 1.83] 28.2 TPM2_ContextSave (Command)
 pub struct ContextSaveCmd {}
 
@@ -7,7 +10,17 @@ pub struct ContextSaveCmd {}
 pub struct ContextLoadCmd {}
 
 /// [TPM2.0 1.83] 28.4 TPM2_FlushContext (Command)
-pub struct FlushContextCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct FlushContextCmd {
+    pub flush_handle: TpmHandle,
+}
+impl TpmCommand for FlushContextCmd {
+    const CMD_CODE: TpmCc = TpmCc::FlushContext;
+    type Handles = ();
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 28.5 TPM2_EvictControl (Command)
 pub struct EvictControlCmd {}
@@ -1,55 +1,256 @@
 //! [TPM2.0 1.83] 23 Enhanced Authorization (EA) Commands
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmEo, TpmHandle};
+use crate::{
+    Tpm2bDigest, Tpm2bName, Tpm2bNonce, Tpm2bOperand, Tpm2bTimeout, TpmaLocality, TpmlDigest,
+    TpmlPcrSelection, TpmtSignature, TpmtTkAuth, TpmtTkVerified,
+};
+
 /// [TPM2.0 1.83] 23.3 TPM2_PolicySigned (Command)
-pub struct PolicySignedCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicySignedCmd {
+    pub nonce_tpm: Tpm2bNonce,
+    pub cp_hash_a: Tpm2bDigest,
+    pub policy_ref: Tpm2bNonce,
+    pub expiration: i32,
+    pub auth: TpmtSignature,
+}
+impl TpmCommand for PolicySignedCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicySigned;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The key that signed the authorization and the policy session.
+    type Handles = (TpmHandle, TpmHandle);
+    type RespT = PolicySignedResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 23.3 TPM2_PolicySigned (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicySignedResp {
+    pub timeout: Tpm2bTimeout,
+    pub policy_ticket: TpmtTkAuth,
+}
 
 /// [TPM2.0 1.83] 23.4 TPM2_PolicySecret (Command)
-pub struct PolicySecretCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicySecretCmd {
+    pub nonce_tpm: Tpm2bNonce,
+    pub cp_hash_a: Tpm2bDigest,
+    pub policy_ref: Tpm2bNonce,
+    pub expiration: i32,
+}
+impl TpmCommand for PolicySecretCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicySecret;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The entity whose authorization is checked and the policy session.
+    type Handles = (TpmHandle, TpmHandle);
+    type RespT = PolicySecretResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 23.4 TPM2_PolicySecret (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicySecretResp {
+    pub timeout: Tpm2bTimeout,
+    pub policy_ticket: TpmtTkAuth,
+}
 
 /// [TPM2.0 1.83] 23.5 TPM2_PolicyTicket (Command)
 pub struct PolicyTicketCmd {}
 
 /// [TPM2.0 1.83] 23.6 TPM2_PolicyOR (Command)
-pub struct PolicyOrCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyOrCmd {
+    pub p_hash_list: TpmlDigest,
+}
+impl TpmCommand for PolicyOrCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyOR;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.7 TPM2_PolicyPCR (Command)
-pub struct PolicyPcrCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyPcrCmd {
+    pub pcr_digest: Tpm2bDigest,
+    pub pcrs: TpmlPcrSelection,
+}
+impl TpmCommand for PolicyPcrCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyPCR;
+    const DECRYPT_PARAMETER: bool = true;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.8 TPM2_PolicyLocality (Command)
-pub struct PolicyLocalityCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyLocalityCmd {
+    pub locality: TpmaLocality,
+}
+impl TpmCommand for PolicyLocalityCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyLocality;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.9 TPM2_PolicyNV (Command)
-pub struct PolicyNvCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyNvCmd {
+    pub operand_b: Tpm2bOperand,
+    pub offset: u16,
+    pub operation: TpmEo,
+}
+impl TpmCommand for PolicyNvCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyNV;
+    const DECRYPT_PARAMETER: bool = true;
+    // The entity that authorizes reading the NV index, the NV index and the policy session.
+    type Handles = (TpmHandle, TpmHandle, TpmHandle);
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.10 TPM2_PolicyCounterTimer (Command)
-pub struct PolicyCounterTimerCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyCounterTimerCmd {
+    pub operand_b: Tpm2bOperand,
+    pub offset: u16,
+    pub operation: TpmEo,
+}
+impl TpmCommand for PolicyCounterTimerCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyCounterTimer;
+    const DECRYPT_PARAMETER: bool = true;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.11 TPM2_PolicyCommandCode (Command)
-pub struct PolicyCommandCodeCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyCommandCodeCmd {
+    pub code: TpmCc,
+}
+impl TpmCommand for PolicyCommandCodeCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyCommandCode;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.12 TPM2_PolicyPhysicalPresence (Command)
 pub struct PolicyPhysicalPresenceCmd {}
 
 /// [TPM2.0 1.83] 23.13 TPM2_PolicyCpHash (Command)
-pub struct PolicyCpHashCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyCpHashCmd {
+    pub cp_hash_a: Tpm2bDigest,
+}
+impl TpmCommand for PolicyCpHashCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyCpHash;
+    const DECRYPT_PARAMETER: bool = true;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.14 TPM2_PolicyNameHash (Command)
-pub struct PolicyNameHashCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyNameHashCmd {
+    pub name_hash: Tpm2bDigest,
+}
+impl TpmCommand for PolicyNameHashCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyNameHash;
+    const DECRYPT_PARAMETER: bool = true;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.15 TPM2_PolicyDuplicationSelect (Command)
 pub struct PolicyDuplicationSelectCmd {}
 
 /// [TPM2.0 1.83] 23.16 TPM2_PolicyAuthorize (Command)
-pub struct PolicyAuthorizeCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyAuthorizeCmd {
+    pub approved_policy: Tpm2bDigest,
+    pub policy_ref: Tpm2bNonce,
+    pub key_sign: Tpm2bName,
+    pub check_ticket: TpmtTkVerified,
+}
+impl TpmCommand for PolicyAuthorizeCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyAuthorize;
+    const DECRYPT_PARAMETER: bool = true;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.17 TPM2_PolicyAuthValue (Command)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct PolicyAuthValueCmd {}
+impl TpmCommand for PolicyAuthValueCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyAuthValue;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.18 TPM2_PolicyPassword (Command)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct PolicyPasswordCmd {}
+impl TpmCommand for PolicyPasswordCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyPassword;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
 
 /// [TPM2.0 1.83] 23.19 TPM2_PolicyGetDigest (Command)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct PolicyGetDigestCmd {}
+impl TpmCommand for PolicyGetDigestCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyGetDigest;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = PolicyGetDigestResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 23.19 TPM2_PolicyGetDigest (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct PolicyGetDigestResp {
+    pub policy_digest: Tpm2bDigest,
+}
 
 /// [TPM2.0 1.83] 23.20 TPM2_PolicyNvWritten (Command)
 pub struct PolicyNvWrittenCmd {}
@@ -1,5 +1,9 @@
 //! [TPM2.0 1.83] 31 Non-volatile Storage
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmHandle};
+use crate::{Tpm2bName, Tpm2bNvPublic};
+
 /// [TPM2.0 1.83] 31.3 TPM2_NV_DefineSpace (Command)
 pub struct NvDefineSpaceCmd {}
 
@@ -10,7 +14,24 @@ pub struct NvUndefineSpaceCmd {}
 pub struct NvUndefineSpaceSpecialCmd {}
 
 /// [TPM2.0 1.83] 31.6 TPM2_NV_ReadPublic (Command)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct NvReadPublicCmd {}
+impl TpmCommand for NvReadPublicCmd {
+    const CMD_CODE: TpmCc = TpmCc::NVReadPublic;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The NV index.
+    type Handles = TpmHandle;
+    type RespT = NvReadPublicResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 31.6 TPM2_NV_ReadPublic (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct NvReadPublicResp {
+    pub nv_public: Tpm2bNvPublic,
+    pub nv_name: Tpm2bName,
+}
 
 /// [TPM2.0 1.83] 31.7 TPM2_NV_Write (Command)
 pub struct NvWriteCmd {}
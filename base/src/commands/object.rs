@@ -1,5 +1,9 @@
 //! [TPM2.0 1.83] 12 Object Commands
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmHandle};
+use crate::{Tpm2bName, Tpm2bPublic, Tpm2bSensitive};
+
 /// [TPM2.0 1.83] 12.1 TPM2_Create (Command)
 pub struct CreateCmd {}
 
@@ -7,10 +11,48 @@ pub struct CreateCmd {}
 pub struct LoadCmd {}
 
 /// [TPM2.0 1.83] 12.3 TPM2_LoadExternal (Command)
-pub struct LoadExternalCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct LoadExternalCmd {
+    pub in_private: Tpm2bSensitive,
+    pub in_public: Tpm2bPublic,
+    pub hierarchy: TpmHandle,
+}
+impl TpmCommand for LoadExternalCmd {
+    const CMD_CODE: TpmCc = TpmCc::LoadExternal;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    type Handles = ();
+    type RespT = LoadExternalResp;
+    type RespHandles = TpmHandle;
+}
+/// [TPM2.0 1.83] 12.3 TPM2_LoadExternal (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct LoadExternalResp {
+    pub name: Tpm2bName,
+}
 
 /// [TPM2.0 1.83] 12.4 TPM2_ReadPublic (Command)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct ReadPublicCmd {}
+impl TpmCommand for ReadPublicCmd {
+    const CMD_CODE: TpmCc = TpmCc::ReadPublic;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The object.
+    type Handles = TpmHandle;
+    type RespT = ReadPublicResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 12.4 TPM2_ReadPublic (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct ReadPublicResp {
+    pub out_public: Tpm2bPublic,
+    pub name: Tpm2bName,
+    pub qualified_name: Tpm2bName,
+}
 
 /// [TPM2.0 1.83] 12.5 TPM2_ActivateCredential (Command)
 pub struct ActivateCredentialCmd {}
@@ -12,6 +12,7 @@ pub struct GetRandomCmd {
 }
 impl TpmCommand for GetRandomCmd {
     const CMD_CODE: TpmCc = TpmCc::GetRandom;
+    const ENCRYPT_PARAMETER: bool = true;
     type Handles = ();
     type RespT = GetRandomResp;
     type RespHandles = ();
@@ -1,7 +1,44 @@
 //! [TPM2.0 1.83] 11 Session Commands
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmHandle, TpmSe};
+use crate::{Tpm2bEncryptedSecret, Tpm2bNonce, TpmiAlgHash, TpmtSymDef};
+
 /// [TPM2.0 1.83] 11.1 TPM2_StartAuthSession (Command)
-pub struct StartAuthSessionCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct StartAuthSessionCmd {
+    pub nonce_caller: Tpm2bNonce,
+    pub encrypted_salt: Tpm2bEncryptedSecret,
+    pub session_type: TpmSe,
+    pub symmetric: TpmtSymDef,
+    pub auth_hash: TpmiAlgHash,
+}
+impl TpmCommand for StartAuthSessionCmd {
+    const CMD_CODE: TpmCc = TpmCc::StartAuthSession;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    // The key that decrypts the salt and the entity whose authorization value the session is
+    // bound to. Either may be TPM_RH_NULL.
+    type Handles = (TpmHandle, TpmHandle);
+    type RespT = StartAuthSessionResp;
+    type RespHandles = TpmHandle;
+}
+/// [TPM2.0 1.83] 11.1 TPM2_StartAuthSession (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct StartAuthSessionResp {
+    pub nonce_tpm: Tpm2bNonce,
+}
 
 /// [TPM2.0 1.83] 11.2 TPM2_PolicyRestart (Command)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct PolicyRestartCmd {}
+impl TpmCommand for PolicyRestartCmd {
+    const CMD_CODE: TpmCc = TpmCc::PolicyRestart;
+    // The policy session.
+    type Handles = TpmHandle;
+    type RespT = ();
+    type RespHandles = ();
+}
@@ -1,7 +1,48 @@
 //! [TPM2.0 1.83] 20 Signing and Signature Verification
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmHandle};
+use crate::{Tpm2bDigest, TpmtSigScheme, TpmtSignature, TpmtTkHashcheck, TpmtTkVerified};
+
 /// [TPM2.0 1.83] 20.1 TPM2_VerifySignature (Command)
-pub struct VerifySignatureCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct VerifySignatureCmd {
+    pub digest: Tpm2bDigest,
+    pub signature: TpmtSignature,
+}
+impl TpmCommand for VerifySignatureCmd {
+    const CMD_CODE: TpmCc = TpmCc::VerifySignature;
+    const DECRYPT_PARAMETER: bool = true;
+    type Handles = TpmHandle;
+    type RespT = VerifySignatureResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 20.1 TPM2_VerifySignature (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct VerifySignatureResp {
+    pub validation: TpmtTkVerified,
+}
 
 /// [TPM2.0 1.83] 20.2 TPM2_Sign (Command)
-pub struct SignCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct SignCmd {
+    pub digest: Tpm2bDigest,
+    pub in_scheme: TpmtSigScheme,
+    pub validation: TpmtTkHashcheck,
+}
+impl TpmCommand for SignCmd {
+    const CMD_CODE: TpmCc = TpmCc::Sign;
+    const DECRYPT_PARAMETER: bool = true;
+    type Handles = TpmHandle;
+    type RespT = SignResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 20.2 TPM2_Sign (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct SignResp {
+    pub signature: TpmtSignature,
+}
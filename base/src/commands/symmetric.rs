@@ -1,13 +1,83 @@
 //! [TPM2.0 1.83] 15 Symmetric Primitives
 
+use crate::commands::{Marshalable, TpmCommand};
+use crate::constants::{TpmCc, TpmHandle};
+use crate::{
+    Tpm2bDigest, Tpm2bIv, Tpm2bMaxBuffer, TpmiAlgHash, TpmiAlgSymMode, TpmiYesNo, TpmtTkHashcheck,
+};
+
 /// [TPM2.0 1.83] 15.2 TPM2_EncryptDecrypt (Command)
-pub struct EncryptDecryptCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EncryptDecryptCmd {
+    pub decrypt: TpmiYesNo,
+    pub mode: TpmiAlgSymMode,
+    pub iv_in: Tpm2bIv,
+    pub in_data: Tpm2bMaxBuffer,
+}
+impl TpmCommand for EncryptDecryptCmd {
+    const CMD_CODE: TpmCc = TpmCc::EncryptDecrypt;
+    const ENCRYPT_PARAMETER: bool = true;
+    type Handles = TpmHandle;
+    type RespT = EncryptDecryptResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 15.2 TPM2_EncryptDecrypt (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EncryptDecryptResp {
+    pub out_data: Tpm2bMaxBuffer,
+    pub iv_out: Tpm2bIv,
+}
 
 /// [TPM2.0 1.83] 15.3 TPM2_EncryptDecrypt2 (Command)
-pub struct EncryptDecrypt2Cmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EncryptDecrypt2Cmd {
+    pub in_data: Tpm2bMaxBuffer,
+    pub decrypt: TpmiYesNo,
+    pub mode: TpmiAlgSymMode,
+    pub iv_in: Tpm2bIv,
+}
+impl TpmCommand for EncryptDecrypt2Cmd {
+    const CMD_CODE: TpmCc = TpmCc::EncryptDecrypt2;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    type Handles = TpmHandle;
+    type RespT = EncryptDecrypt2Resp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 15.3 TPM2_EncryptDecrypt2 (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct EncryptDecrypt2Resp {
+    pub out_data: Tpm2bMaxBuffer,
+    pub iv_out: Tpm2bIv,
+}
 
 /// [TPM2.0 1.83] 15.4 TPM2_Hash (Command)
-pub struct HashCmd {}
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct HashCmd {
+    pub data: Tpm2bMaxBuffer,
+    pub hash_alg: TpmiAlgHash,
+    pub hierarchy: TpmHandle,
+}
+impl TpmCommand for HashCmd {
+    const CMD_CODE: TpmCc = TpmCc::Hash;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    type Handles = ();
+    type RespT = HashResp;
+    type RespHandles = ();
+}
+/// [TPM2.0 1.83] 15.4 TPM2_Hash (Response)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct HashResp {
+    pub out_hash: Tpm2bDigest,
+    pub validation: TpmtTkHashcheck,
+}
 
 /// [TPM2.0 1.83] 15.5 TPM2_HMAC (Command)
 pub struct HmacCmd {}
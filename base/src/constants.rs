@@ -221,12 +221,15 @@ pub enum TpmCc {
     PolicyACSendSelect = 0x00000196,
     CertifyX509 = 0x00000197,
     ACTSetTimeout = 0x00000198,
+    ECCEncrypt = 0x00000199,
+    ECCDecrypt = 0x0000019A,
 }
 
 // TpmRc represents a TPM_RC.
 // See definition in Part 2: Structures, section 6.6.
 #[open_enum]
 #[repr(u32)]
+#[rustfmt::skip] #[derive(Debug)] // Keep debug derivation separate for open_enum override.
 pub enum TpmRc {
     Success = 0x00000000,
     // FMT0 error codes
@@ -341,6 +344,8 @@ impl TpmRc {
 // See definition in Part 2: Structures, section 6.8.
 #[open_enum]
 #[repr(u16)]
+#[rustfmt::skip] #[derive(Debug)] // Keep debug derivation separate for open_enum override.
+#[derive(Copy, Clone, Default, Marshalable)]
 pub enum TpmEo {
     Eq = 0x0000,
     Neq = 0x0001,
@@ -398,6 +403,8 @@ pub enum TpmSu {
 // See definition in Part 2: Structures, section 6.11.
 #[open_enum]
 #[repr(u8)]
+#[rustfmt::skip] #[derive(Debug)] // Keep debug derivation separate for open_enum override.
+#[derive(Copy, Clone, Default, Marshalable)]
 pub enum TpmSe {
     HMAC = 0x00,
     Policy = 0x01,
@@ -729,6 +736,14 @@ impl TpmHc {
     pub fn is_policy_session(value: u32) -> bool {
         (TpmHc::PolicySessionFirst.0..=TpmHc::PolicySessionLast.0).contains(&value)
     }
+    /// The first transient object.
+    pub const TransientFirst: TpmHc = TpmHc::HRTransient;
+    /// The last transient object.
+    pub const TransientLast: TpmHc = TpmHc(TpmHc::HRTransient.0 + 0x00FFFFFF);
+    /// Returns true if the value is a transient object handle.
+    pub fn is_transient(value: u32) -> bool {
+        (TpmHc::TransientFirst.0..=TpmHc::TransientLast.0).contains(&value)
+    }
     /// The first persistent object.
     pub const PersistentFirst: TpmHc = TpmHc::HRPersistent;
     /// The last persistent object.
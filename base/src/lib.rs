@@ -49,7 +49,7 @@ bitflags! {
 impl TpmaLocality {
     const EXTENDED_LOCALITY_MASK: u8 = 0xE0;
     /// Returns whether this attribute indicates an extended locality.
-    fn is_extended(&self) -> bool {
+    pub fn is_extended(&self) -> bool {
         (self.0 & Self::EXTENDED_LOCALITY_MASK) != 0
     }
 }
@@ -198,6 +198,7 @@ pub enum TpmiAlgSymMode{
     CBC = TpmAlgId::CBC.0,
     CFB = TpmAlgId::CFB.0,
     ECB = TpmAlgId::ECB.0,
+    Null = TpmAlgId::Null.0,
 }
 
 /// TpmiAlgSymObject represents all of the symmetric algorithms that may be used as a companion encryption algortihm for an asymmetric object (TPMI_ALG_SYM_OBJECT).
@@ -314,6 +315,12 @@ impl TryFrom<u32> for TpmiRhNvIndex {
         }
     }
 }
+impl TpmiRhNvIndex {
+    /// Returns the NV index handle.
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+}
 
 /// TpmiShAuthSessions represents handles referring to an authorization session (TPMI_SH_AUTH_SESSION).
 /// See definition in Part 2: Structures, section 9.8.
@@ -336,13 +343,18 @@ impl TryFrom<u32> for TpmiShAuthSession {
 impl TpmiShAuthSession {
     /// A password authorization.
     pub const RS_PW: TpmiShAuthSession = TpmiShAuthSession(TpmHandle::RSPW.0);
+
+    /// Returns the session handle.
+    pub fn get(&self) -> u32 {
+        self.0
+    }
 }
 
 /// TpmiEccCurve represents an implemented ECC curve (TPMI_ECC_SCHEME).
 /// See definition in Part 2: Structures, section 11.2.5.5.
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Debug, Default, Marshalable)]
-pub struct TpmiEccCurve(TpmEccCurve);
+pub struct TpmiEccCurve(pub TpmEccCurve);
 
 /// TpmiYesNo is used in place of a boolean.
 /// See TPMI_YES_NO definition in Part 2: Structures, section 9.2.
@@ -375,19 +387,19 @@ pub enum TpmiStAttest {
 /// The number of bits in an AES key.
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Debug, Default, Marshalable)]
-pub struct TpmiAesKeyBits(u16);
+pub struct TpmiAesKeyBits(pub u16);
 /// The number of bits in an SM4 key.
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Debug, Default, Marshalable)]
-pub struct TpmiSm4KeyBits(u16);
+pub struct TpmiSm4KeyBits(pub u16);
 /// The number of bits in a Camellia key.
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Debug, Default, Marshalable)]
-pub struct TpmiCamelliaKeyBits(u16);
+pub struct TpmiCamelliaKeyBits(pub u16);
 /// The number of bits in an RSA key.
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Debug, Default, Marshalable)]
-pub struct TpmiRsaKeyBits(u16);
+pub struct TpmiRsaKeyBits(pub u16);
 
 /// TpmaObject indicates an object's use, authorization types, and relationship to other objects (TPMA_OBJECT).
 /// See definition in Part 2: Structures, section 8.3.
@@ -501,7 +513,7 @@ impl TpmaCc {
     const C_HANDLES_MASK: u32 = 0x7 << TpmaCc::C_HANDLES_SHIFT;
 
     /// Creates a TpmaCc with the command index field set to the provided value.
-    const fn command_index(index: u16) -> TpmaCc {
+    pub const fn command_index(index: u16) -> TpmaCc {
         TpmaCc(new_attribute_field(
             index as u32,
             Self::COMMAND_INDEX_MASK,
@@ -509,7 +521,7 @@ impl TpmaCc {
         ))
     }
     /// Creates a TpmaCc with the command handles field set to the provided value.
-    const fn c_handles(count: u32) -> TpmaCc {
+    pub const fn c_handles(count: u32) -> TpmaCc {
         TpmaCc(new_attribute_field(
             count,
             Self::C_HANDLES_MASK,
@@ -518,16 +530,16 @@ impl TpmaCc {
     }
 
     /// Returns the command being selected.
-    fn get_command_index(&self) -> u16 {
+    pub fn get_command_index(&self) -> u16 {
         get_attribute_field(self.0, Self::COMMAND_INDEX_MASK, Self::COMMAND_INDEX_SHIFT) as u16
     }
     /// Returns the number of handles in the handle area for this command.
-    fn get_c_handles(&self) -> u32 {
+    pub fn get_c_handles(&self) -> u32 {
         get_attribute_field(self.0, Self::C_HANDLES_MASK, Self::C_HANDLES_SHIFT)
     }
 
     /// Sets the command being selected.
-    fn set_command_index(&mut self, index: u16) {
+    pub fn set_command_index(&mut self, index: u16) {
         self.0 = set_attribute_field(
             self.0,
             index as u32,
@@ -536,7 +548,7 @@ impl TpmaCc {
         );
     }
     /// Sets the number of handles in the handle area for this command.
-    fn set_c_handles(&mut self, count: u32) {
+    pub fn set_c_handles(&mut self, count: u32) {
         self.0 = set_attribute_field(self.0, count, Self::C_HANDLES_MASK, Self::C_HANDLES_SHIFT);
     }
 }
@@ -591,7 +603,7 @@ enum TpmuName {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bDigest {
     size: u16,
@@ -602,7 +614,7 @@ pub type Tpm2bNonce = Tpm2bDigest;
 pub type Tpm2bOperand = Tpm2bDigest;
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bData {
     size: u16,
@@ -610,7 +622,15 @@ pub struct Tpm2bData {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
+#[marshalable(tpm2b_simple)]
+pub struct Tpm2bTimeout {
+    size: u16,
+    buffer: [u8; size_of::<u64>()],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bEvent {
     size: u16,
@@ -618,7 +638,7 @@ pub struct Tpm2bEvent {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bMaxBuffer {
     size: u16,
@@ -626,7 +646,7 @@ pub struct Tpm2bMaxBuffer {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bMaxNvBuffer {
     size: u16,
@@ -634,7 +654,7 @@ pub struct Tpm2bMaxNvBuffer {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bIv {
     size: u16,
@@ -642,7 +662,7 @@ pub struct Tpm2bIv {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bName {
     size: u16,
@@ -704,7 +724,7 @@ pub struct TpmsCertifyInfo {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct TpmsCommandAuditInfo {
     pub audit_counter: u64,
     pub digest_alg: u16,
@@ -742,7 +762,7 @@ pub struct TpmsNvCertifyInfo {
 }
 
 #[repr(C, u16)]
-#[derive(Clone, Copy, PartialEq, Discriminant, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Debug, Discriminant, Marshalable)]
 pub enum TpmuAttest {
     Certify(TpmsCertifyInfo) = TpmSt::AttestCertify.0,
     Creation(TpmsCreationInfo) = TpmSt::AttestCreation.0,
@@ -754,7 +774,7 @@ pub enum TpmuAttest {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct TpmsAttest {
     pub magic: TpmGenerated,
     pub qualified_signer: Tpm2bName,
@@ -795,7 +815,7 @@ impl Marshalable for TpmsAttest {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bAttest {
     size: u16,
@@ -803,7 +823,7 @@ pub struct Tpm2bAttest {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bSymKey {
     size: u16,
@@ -811,7 +831,7 @@ pub struct Tpm2bSymKey {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bLabel {
     size: u16,
@@ -839,7 +859,7 @@ enum TpmuSensitiveCreate {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bSensitiveData {
     size: u16,
@@ -856,7 +876,7 @@ pub struct TpmsSensitiveCreate {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable, Tpm2bStruct)]
+#[derive(Clone, Copy, PartialEq, Marshalable, Tpm2bStruct)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bSensitiveCreate {
     size: u16,
@@ -864,7 +884,7 @@ pub struct Tpm2bSensitiveCreate {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bPublicKeyRsa {
     size: u16,
@@ -872,7 +892,7 @@ pub struct Tpm2bPublicKeyRsa {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bPrivateKeyRsa {
     size: u16,
@@ -880,7 +900,7 @@ pub struct Tpm2bPrivateKeyRsa {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bEccParameter {
     size: u16,
@@ -895,7 +915,8 @@ pub struct TpmsEccPoint {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Marshalable, Tpm2bStruct)]
+#[marshalable(tpm2b_simple)]
 pub struct Tpm2bEccPoint {
     size: u16,
     point: [u8; size_of::<TpmsEccPoint>()],
@@ -911,7 +932,7 @@ enum TpmuEncryptedSecret {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bEncryptedSecret {
     size: u16,
@@ -951,6 +972,8 @@ pub enum TpmtSymDefObject {
     Null(TpmsEmpty, TpmsEmpty) = TpmAlgId::Null.0,
 }
 
+pub type TpmtSymDef = TpmtSymDefObject;
+
 #[repr(C)]
 #[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct TpmsSymCipherParms {
@@ -1005,6 +1028,47 @@ pub enum TpmtSigScheme {
     Null(TpmsEmpty) = TpmAlgId::Null.0,
 }
 
+/// TpmtTkCreation represents a ticket proving that the TPM created an object (TPMT_TK_CREATION).
+/// See definition in Part 2: Structures, section 10.7.3.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct TpmtTkCreation {
+    pub tag: TpmSt,
+    pub hierarchy: TpmHandle,
+    pub digest: Tpm2bDigest,
+}
+
+/// TpmtTkVerified represents a ticket produced by TPM2_VerifySignature (TPMT_TK_VERIFIED).
+/// See definition in Part 2: Structures, section 10.7.4.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct TpmtTkVerified {
+    pub tag: TpmSt,
+    pub hierarchy: TpmHandle,
+    pub digest: Tpm2bDigest,
+}
+
+/// TpmtTkAuth represents a ticket produced by TPM2_PolicySigned or TPM2_PolicySecret
+/// (TPMT_TK_AUTH).
+/// See definition in Part 2: Structures, section 10.7.5.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct TpmtTkAuth {
+    pub tag: TpmSt,
+    pub hierarchy: TpmHandle,
+    pub digest: Tpm2bDigest,
+}
+
+/// TpmtTkHashcheck represents a ticket proving that the TPM computed a digest (TPMT_TK_HASHCHECK).
+/// See definition in Part 2: Structures, section 10.7.6.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct TpmtTkHashcheck {
+    pub tag: TpmSt,
+    pub hierarchy: TpmHandle,
+    pub digest: Tpm2bDigest,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
 pub struct TpmsSchemeHash {
@@ -1090,6 +1154,22 @@ pub struct TpmsEccParms {
     pub kdf: TpmtKdfScheme,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+pub struct TpmsAlgorithmDetailEcc {
+    pub curve_id: TpmEccCurve,
+    pub key_size: u16,
+    pub kdf: TpmtKdfScheme,
+    pub sign: TpmtEccScheme,
+    pub p: Tpm2bEccParameter,
+    pub a: Tpm2bEccParameter,
+    pub b: Tpm2bEccParameter,
+    pub g_x: Tpm2bEccParameter,
+    pub g_y: Tpm2bEccParameter,
+    pub n: Tpm2bEccParameter,
+    pub h: Tpm2bEccParameter,
+}
+
 #[repr(C, u16)]
 #[derive(Clone, Copy, PartialEq, Debug, Discriminant, Marshalable)]
 pub enum TpmtAsymScheme {
@@ -1131,6 +1211,26 @@ pub enum PublicParmsAndId {
     Ecc(TpmsEccParms, TpmsEccPoint) = TpmAlgId::ECC.0,
 }
 
+#[repr(C, u16)]
+#[derive(Clone, Copy, PartialEq, Debug, Discriminant, Marshalable)]
+pub enum TpmtPublicParms {
+    KeyedHash(TpmsKeyedHashParms) = TpmAlgId::KeyedHash.0,
+    Sym(TpmsSymCipherParms) = TpmAlgId::SymCipher.0,
+    Rsa(TpmsRsaParms) = TpmAlgId::RSA.0,
+    Ecc(TpmsEccParms) = TpmAlgId::ECC.0,
+}
+
+impl From<&PublicParmsAndId> for TpmtPublicParms {
+    fn from(value: &PublicParmsAndId) -> Self {
+        match value {
+            PublicParmsAndId::KeyedHash(parms, _) => TpmtPublicParms::KeyedHash(*parms),
+            PublicParmsAndId::Sym(parms, _) => TpmtPublicParms::Sym(*parms),
+            PublicParmsAndId::Rsa(parms, _) => TpmtPublicParms::Rsa(*parms),
+            PublicParmsAndId::Ecc(parms, _) => TpmtPublicParms::Ecc(*parms),
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct TpmtPublic {
@@ -1167,7 +1267,7 @@ impl Marshalable for TpmtPublic {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable, Tpm2bStruct)]
+#[derive(Clone, Copy, PartialEq, Marshalable, Tpm2bStruct)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bPublic {
     size: u16,
@@ -1175,7 +1275,7 @@ pub struct Tpm2bPublic {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bTemplate {
     size: u16,
@@ -1183,7 +1283,7 @@ pub struct Tpm2bTemplate {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bPrivateVendorSpecific {
     size: u16,
@@ -1367,7 +1467,8 @@ pub struct TpmsAuthResponse {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Marshalable, Tpm2bStruct)]
+#[marshalable(tpm2b_simple)]
 pub struct Tpm2bSensitive {
     size: u16,
     sensitive_area: [u8; size_of::<TpmtSensitive>()],
@@ -1382,7 +1483,7 @@ pub struct _PRIVATE {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bPrivate {
     size: u16,
@@ -1397,7 +1498,7 @@ pub struct TpmsIdObject {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bIdObject {
     size: u16,
@@ -1415,14 +1516,15 @@ pub struct TpmsNvPublic {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Marshalable, Tpm2bStruct)]
+#[marshalable(tpm2b_simple)]
 pub struct Tpm2bNvPublic {
     size: u16,
     nv_public: [u8; size_of::<TpmsNvPublic>()],
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bContextSensitive {
     size: u16,
@@ -1437,7 +1539,7 @@ pub struct TpmsContextData {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable)]
+#[derive(Clone, Copy, PartialEq, Marshalable)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bContextData {
     size: u16,
@@ -1457,7 +1559,7 @@ pub struct TpmsCreationData {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Debug, Marshalable, Tpm2bStruct)]
+#[derive(Clone, Copy, PartialEq, Marshalable, Tpm2bStruct)]
 #[marshalable(tpm2b_simple)]
 pub struct Tpm2bCreationData {
     size: u16,
@@ -1536,6 +1638,7 @@ macro_rules! impl_tpml {
 impl_tpml! {TpmlPcrSelection, pcr_selections, TpmsPcrSelection, TPM2_NUM_PCR_BANKS}
 impl_tpml! {TpmlAlgProperty, alg_properties, TpmsAlgProperty, TPM2_MAX_CAP_ALGS}
 impl_tpml! {TpmlHandle, handle, TpmHandle, TPM2_MAX_CAP_HANDLES}
+impl_tpml! {TpmlCca, command_attributes, TpmaCc, TPM2_MAX_CAP_CC}
 impl_tpml! {TpmlCc, command_codes, TpmCc, TPM2_MAX_CAP_CC}
 impl_tpml! {TpmlTaggedTpmProperty, tpm_property, TpmsTaggedProperty, TPM2_MAX_TPM_PROPERTIES}
 impl_tpml! {TpmlTaggedPcrProperty, pcr_property, TpmsTaggedPcrSelect, TPM2_MAX_PCR_PROPERTIES}
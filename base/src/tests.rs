@@ -182,6 +182,11 @@ fn test_try_unmarshal_tpm2b_template() {
     impl_test_tpm2b_simple! {Tpm2bTemplate};
 }
 
+#[test]
+fn test_try_unmarshal_tpm2b_timeout() {
+    impl_test_tpm2b_simple! {Tpm2bTimeout};
+}
+
 #[test]
 fn test_impl_tpml_new() {
     let elements: Vec<TpmHandle> = (0..TPM2_MAX_CAP_HANDLES + 1)
@@ -312,6 +317,27 @@ fn test_marshal_tpmt_public() {
     );
 }
 
+#[test]
+fn test_marshal_tpmt_public_parms() {
+    let parms = TpmtPublicParms::Ecc(TpmsEccParms {
+        symmetric: TpmtSymDefObject::Null(TpmsEmpty {}, TpmsEmpty {}),
+        scheme: TpmtEccScheme::Ecdsa(TpmsSchemeHash {
+            hash_alg: TpmiAlgHash::SHA256,
+        }),
+        curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+        kdf: TpmtKdfScheme::Null(TpmsEmpty {}),
+    });
+    let mut buffer = [0u8; size_of::<TpmtPublicParms>()];
+    let bytes = parms.try_marshal(&mut buffer).unwrap();
+    assert_eq!(
+        buffer[..bytes],
+        [0x00, 0x23, 0x00, 0x10, 0x00, 0x18, 0x00, 0x0B, 0x00, 0x03, 0x00, 0x10]
+    );
+
+    let unmarshaled = TpmtPublicParms::try_unmarshal(&mut UnmarshalBuf::new(&buffer[..bytes]));
+    assert_eq!(unmarshaled.unwrap(), parms);
+}
+
 #[test]
 fn test_attributes_field() {
     let mut cc = TpmaCc::NV | TpmaCc::FLUSHED | TpmaCc::command_index(0x8);
@@ -359,3 +385,18 @@ fn test_2b_struct() {
     let out_creation_data = creation_data_2b.to_struct().unwrap();
     assert_eq!(creation_data, out_creation_data);
 }
+
+#[test]
+fn test_nv_public_2b_struct() {
+    let nv_public = TpmsNvPublic {
+        nv_index: TpmiRhNvIndex::try_from(TpmHc::NVIndexFirst.get() + 1).unwrap(),
+        name_alg: TpmiAlgHash::SHA256,
+        attributes: TpmaNv::AUTHWRITE | TpmaNv::AUTHREAD,
+        auth_policy: Tpm2bDigest::default(),
+        data_size: 32,
+    };
+    let nv_public_2b = Tpm2bNvPublic::from_struct(&nv_public).unwrap();
+    // TPMI_RH_NV_INDEX, TPMI_ALG_HASH, TPMA_NV, an empty TPM2B_DIGEST and UINT16.
+    assert_eq!(nv_public_2b.get_size(), 14);
+    assert_eq!(nv_public_2b.to_struct().unwrap(), nv_public);
+}
@@ -0,0 +1,85 @@
+//! Computation of the audit digests that `TPM2_GetCommandAuditDigest` and
+//! `TPM2_GetSessionAuditDigest` attest to, so that a verifier can compare them with the commands
+//! it expects to have been audited.
+
+use core::mem::size_of;
+use tpm2_rs_base::constants::TpmCc;
+use tpm2_rs_base::errors::{TssResult, TssTcsError};
+use tpm2_rs_base::{Tpm2bDigest, Tpm2bSimple, TpmiAlgHash};
+
+use crate::crypto::digest;
+pub use crate::crypto::{cp_hash, rp_hash};
+
+/// The largest number of command codes in a [`command_digest`].
+const MAX_AUDITED_COMMANDS: usize = 256;
+
+/// The size of the largest digest that the audit digests can use.
+const MAX_DIGEST_SIZE: usize = 64;
+
+/// Computes the `commandDigest` of a command audit attestation: the digest of the audited
+/// command codes in ascending order. Repeated command codes are only included once.
+pub fn command_digest(alg: TpmiAlgHash, command_codes: &[TpmCc]) -> TssResult<Tpm2bDigest> {
+    let mut marshaled = [0; MAX_AUDITED_COMMANDS * size_of::<u32>()];
+    let mut size = 0;
+    let mut previous = None;
+    while let Some(next) = command_codes
+        .iter()
+        .map(|cc| cc.0)
+        .filter(|cc| previous.is_none_or(|previous| *cc > previous))
+        .min()
+    {
+        marshaled
+            .get_mut(size..size + size_of::<u32>())
+            .ok_or(TssTcsError::OutOfMemory)?
+            .copy_from_slice(&next.to_be_bytes());
+        size += size_of::<u32>();
+        previous = Some(next);
+    }
+    digest(alg, &[&marshaled[..size]])
+}
+
+/// An audit digest that is extended with each audited command and its response, the same way
+/// the TPM does it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AuditDigest {
+    alg: TpmiAlgHash,
+    digest: Tpm2bDigest,
+}
+
+impl AuditDigest {
+    /// Starts a command audit digest, which is empty after it was cleared.
+    pub fn command_audit(alg: TpmiAlgHash) -> Self {
+        AuditDigest {
+            alg,
+            digest: Tpm2bDigest::default(),
+        }
+    }
+
+    /// Starts the audit digest of a session, which is a Zero Digest of the session's hash
+    /// algorithm after the session was started or its audit was reset.
+    pub fn session_audit(alg: TpmiAlgHash) -> TssResult<Self> {
+        let size = digest(alg, &[])?.get_size() as usize;
+        Ok(AuditDigest {
+            alg,
+            digest: Tpm2bDigest::from_bytes(&[0; MAX_DIGEST_SIZE][..size])?,
+        })
+    }
+
+    /// Extends the digest with the cpHash and rpHash of an audited command.
+    pub fn extend(&mut self, cp_hash: &Tpm2bDigest, rp_hash: &Tpm2bDigest) -> TssResult<()> {
+        self.digest = digest(
+            self.alg,
+            &[
+                self.digest.get_buffer(),
+                cp_hash.get_buffer(),
+                rp_hash.get_buffer(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the current digest.
+    pub fn digest(&self) -> &Tpm2bDigest {
+        &self.digest
+    }
+}
@@ -0,0 +1,242 @@
+//! Verification of the attestations that a TPM signs, such as the results of `TPM2_Quote`,
+//! `TPM2_Certify` and `TPM2_GetTime`.
+//!
+//! [`verify_attestation`] checks that an attestation was generated by a TPM, that it answers the
+//! nonce of the verifier, that a quote covers the expected PCR values and that it was signed by
+//! the expected key. Each check is reported separately in an [`AttestationVerdict`], so that the
+//! caller can tell why an attestation is not trustworthy.
+//!
+//! The [`audit`] module computes the audit digests that command and session audit attestations
+//! report.
+
+use ecdsa::elliptic_curve::sec1::{EncodedPoint, FromEncodedPoint, ModulusSize, ToEncodedPoint};
+use ecdsa::elliptic_curve::{AffinePoint, CurveArithmetic, FieldBytesSize, PrimeCurve};
+use ecdsa::hazmat::VerifyPrimitive;
+use ecdsa::signature::hazmat::PrehashVerifier;
+use ecdsa::{Signature, SignatureSize, VerifyingKey};
+use p256::NistP256;
+use p384::NistP384;
+use rsa::traits::PublicKeyParts;
+use rsa::{Pkcs1v15Sign, Pss, RsaPublicKey};
+use sha1::Sha1;
+use sha2::digest::{const_oid::AssociatedOid, DynDigest};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use tpm2_rs_base::constants::{TpmEccCurve, TpmGenerated};
+use tpm2_rs_base::errors::{TssError, TssResult, TssTcsError};
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bAttest, Tpm2bPublicKeyRsa, Tpm2bSimple, TpmiAlgHash, TpmsAttest,
+    TpmsEccPoint, TpmsSignatureEcc, TpmsSignatureRsa, TpmtHa, TpmtPublic, TpmtSignature,
+    TpmuAttest,
+};
+
+use crate::crypto::{digest, field_bytes, rsa_public_key};
+
+pub mod audit;
+
+/// The outcome of each check of an attestation by [`verify_attestation`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AttestationVerdict {
+    /// The parsed attestation. Its contents should only be trusted if
+    /// [`is_valid`](Self::is_valid) returns true.
+    pub attest: TpmsAttest,
+    /// Whether the attestation starts with `TPM_GENERATED_VALUE`, which a TPM only signs in
+    /// structures it created itself.
+    pub generated_by_tpm: bool,
+    /// Whether the `extraData` of the attestation is the expected nonce.
+    pub nonce_matches: bool,
+    /// Whether the PCR digest of a quote is the digest of the expected PCR values, or `None` if
+    /// the attestation is not a quote.
+    pub pcr_digest_matches: Option<bool>,
+    /// Whether the signature is a valid signature of the attestation by the signer.
+    pub signature_valid: bool,
+}
+
+impl AttestationVerdict {
+    /// Returns true if all checks passed.
+    pub fn is_valid(&self) -> bool {
+        self.generated_by_tpm
+            && self.nonce_matches
+            && self.pcr_digest_matches.unwrap_or(true)
+            && self.signature_valid
+    }
+}
+
+/// Verifies an attestation and its signature by the key `signer`.
+///
+/// `nonce` is the `qualifyingData` that was sent with the command. For quotes, `pcr_values` are
+/// the expected values of the quoted PCRs in the order of the quoted selection: banks in the order
+/// they are listed and PCRs in ascending order within each bank. They are ignored for other
+/// attestations.
+///
+/// Returns an error if the attestation cannot be parsed, if `signer` is not a valid public key or
+/// if the signature uses an algorithm that is not supported.
+pub fn verify_attestation(
+    attest: &Tpm2bAttest,
+    signature: &TpmtSignature,
+    signer: &TpmtPublic,
+    nonce: &[u8],
+    pcr_values: &[&[u8]],
+) -> TssResult<AttestationVerdict> {
+    let mut unmarsh = UnmarshalBuf::new(attest.get_buffer());
+    let parsed = TpmsAttest::try_unmarshal(&mut unmarsh)?;
+    if !unmarsh.is_empty() {
+        return Err(TssTcsError::BadParameter.into());
+    }
+
+    // The PCR digest of a quote is computed with the hash of the signing scheme.
+    let hash = signature_hash(signature);
+    let pcr_digest_matches = match (&parsed.attested, hash) {
+        (TpmuAttest::Quote(quote), Some(hash)) => {
+            Some(digest(hash, pcr_values)? == quote.pcr_digest)
+        }
+        (TpmuAttest::Quote(_), None) => Some(false),
+        _ => None,
+    };
+    let signature_valid = match hash {
+        Some(hash) => {
+            let attest_digest = digest(hash, &[attest.get_buffer()])?;
+            verify_signature(signer, signature, attest_digest.get_buffer())?
+        }
+        None => false,
+    };
+    Ok(AttestationVerdict {
+        attest: parsed,
+        generated_by_tpm: parsed.magic == TpmGenerated::VALUE,
+        nonce_matches: parsed.extra_data.get_buffer() == nonce,
+        pcr_digest_matches,
+        signature_valid,
+    })
+}
+
+/// Returns the hash algorithm of the message that `signature` signs, or `None` if it is a NULL
+/// signature.
+fn signature_hash(signature: &TpmtSignature) -> Option<TpmiAlgHash> {
+    match signature {
+        TpmtSignature::Rsassa(sig) | TpmtSignature::Rsapss(sig) => Some(sig.hash),
+        TpmtSignature::Ecdsa(sig)
+        | TpmtSignature::Ecdaa(sig)
+        | TpmtSignature::Sm2(sig)
+        | TpmtSignature::Ecschnorr(sig) => Some(sig.hash),
+        TpmtSignature::Hmac(ha) => Some(match ha {
+            TpmtHa::Sha1(_) => TpmiAlgHash::SHA1,
+            TpmtHa::Sha256(_) => TpmiAlgHash::SHA256,
+            TpmtHa::Sha384(_) => TpmiAlgHash::SHA384,
+            TpmtHa::Sha512(_) => TpmiAlgHash::SHA512,
+            TpmtHa::Sm3_256(_) => TpmiAlgHash::SM3256,
+        }),
+        TpmtSignature::Null(_) => None,
+    }
+}
+
+/// Checks that `signature` is a signature of `digest` by `signer`. A signature that does not
+/// belong to the type of the signer is invalid.
+fn verify_signature(
+    signer: &TpmtPublic,
+    signature: &TpmtSignature,
+    digest: &[u8],
+) -> TssResult<bool> {
+    match (&signer.parms_and_id, signature) {
+        (PublicParmsAndId::Rsa(parms, modulus), TpmtSignature::Rsassa(sig)) => {
+            verify_rsa(modulus, parms.exponent, sig, false, digest)
+        }
+        (PublicParmsAndId::Rsa(parms, modulus), TpmtSignature::Rsapss(sig)) => {
+            verify_rsa(modulus, parms.exponent, sig, true, digest)
+        }
+        (PublicParmsAndId::Ecc(parms, point), TpmtSignature::Ecdsa(sig)) => {
+            match parms.curve_id.0 {
+                TpmEccCurve::NistP256 => verify_ecdsa::<NistP256>(point, sig, digest),
+                TpmEccCurve::NistP384 => verify_ecdsa::<NistP384>(point, sig, digest),
+                _ => Err(TssTcsError::NotImplemented.into()),
+            }
+        }
+        // Verifying HMAC signatures requires the secret key, which only the TPM has.
+        (
+            PublicParmsAndId::Ecc(..),
+            TpmtSignature::Ecdaa(_) | TpmtSignature::Sm2(_) | TpmtSignature::Ecschnorr(_),
+        )
+        | (PublicParmsAndId::KeyedHash(..), TpmtSignature::Hmac(_)) => {
+            Err(TssTcsError::NotImplemented.into())
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Checks an RSASSA or RSAPSS signature.
+fn verify_rsa(
+    modulus: &Tpm2bPublicKeyRsa,
+    exponent: u32,
+    signature: &TpmsSignatureRsa,
+    pss: bool,
+    digest: &[u8],
+) -> TssResult<bool> {
+    let key = rsa_public_key(modulus, exponent)?;
+    match signature.hash {
+        TpmiAlgHash::SHA1 => Ok(verify_rsa_with::<Sha1>(&key, signature, pss, digest)),
+        TpmiAlgHash::SHA256 => Ok(verify_rsa_with::<Sha256>(&key, signature, pss, digest)),
+        TpmiAlgHash::SHA384 => Ok(verify_rsa_with::<Sha384>(&key, signature, pss, digest)),
+        TpmiAlgHash::SHA512 => Ok(verify_rsa_with::<Sha512>(&key, signature, pss, digest)),
+        _ => Err(TssTcsError::NotImplemented.into()),
+    }
+}
+
+/// Checks an RSA signature of a digest computed with `D`.
+fn verify_rsa_with<D>(
+    key: &RsaPublicKey,
+    signature: &TpmsSignatureRsa,
+    pss: bool,
+    digest: &[u8],
+) -> bool
+where
+    D: 'static + Digest + DynDigest + AssociatedOid + Send + Sync,
+{
+    let sig = signature.sig.get_buffer();
+    if pss {
+        // The TPM uses a salt as long as the digest, unless the key is too small for it.
+        let hash_len = <D as Digest>::output_size();
+        let salt_len = hash_len.min(key.size().saturating_sub(hash_len + 2));
+        key.verify(Pss::new_with_salt::<D>(salt_len), digest, sig)
+            .is_ok()
+    } else {
+        key.verify(Pkcs1v15Sign::new::<D>(), digest, sig).is_ok()
+    }
+}
+
+/// Checks an ECDSA signature.
+// The signature size of ecdsa 0.16 is still bounded with the deprecated generic-array 0.14.
+#[allow(deprecated)]
+fn verify_ecdsa<C>(
+    point: &TpmsEccPoint,
+    signature: &TpmsSignatureEcc,
+    digest: &[u8],
+) -> TssResult<bool>
+where
+    C: PrimeCurve + CurveArithmetic,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C> + VerifyPrimitive<C>,
+    FieldBytesSize<C>: ModulusSize,
+    SignatureSize<C>: ecdsa::elliptic_curve::generic_array::ArrayLength<u8>,
+{
+    let (Some(x), Some(y)) = (
+        field_bytes::<C>(point.x.get_buffer()),
+        field_bytes::<C>(point.y.get_buffer()),
+    ) else {
+        return Err(TssTcsError::BadParameter.into());
+    };
+    let encoded = EncodedPoint::<C>::from_affine_coordinates(&x, &y, false);
+    let key = VerifyingKey::<C>::from_encoded_point(&encoded)
+        .or(Err(TssError::from(TssTcsError::BadParameter)))?;
+
+    let (Some(r), Some(s)) = (
+        field_bytes::<C>(signature.signature_r.get_buffer()),
+        field_bytes::<C>(signature.signature_s.get_buffer()),
+    ) else {
+        return Ok(false);
+    };
+    let Ok(signature) = Signature::<C>::from_scalars(r, s) else {
+        return Ok(false);
+    };
+    Ok(key.verify_prehash(digest, &signature).is_ok())
+}
+
+#[cfg(test)]
+mod tests;
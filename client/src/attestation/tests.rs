@@ -0,0 +1,259 @@
+use hex_literal::hex;
+use p256::ecdsa::signature::hazmat::PrehashSigner;
+use p256::ecdsa::SigningKey;
+use tpm2_rs_base::constants::{TpmCc, TpmHandle};
+use tpm2_rs_base::{
+    Tpm2bData, Tpm2bDigest, Tpm2bEccParameter, Tpm2bName, TpmaObject, TpmiEccCurve, TpmiYesNo,
+    TpmlPcrSelection, TpmsCertifyInfo, TpmsClockInfo, TpmsEccParms, TpmsEmpty, TpmsPcrSelection,
+    TpmsQuoteInfo, TpmtEccScheme, TpmtKdfScheme, TpmtSymDefObject,
+};
+
+use super::*;
+
+/// The NIST P-256 key from [RFC 6979] A.2.5.
+///
+/// [RFC 6979]: https://www.rfc-editor.org/rfc/rfc6979
+const ECC_PRIVATE: [u8; 32] =
+    hex!("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+const ECC_PUBLIC_X: [u8; 32] =
+    hex!("60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6");
+const ECC_PUBLIC_Y: [u8; 32] =
+    hex!("7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299");
+
+const NONCE: &[u8] = b"nonce";
+const PCR_0: [u8; 32] = [0x00; 32];
+const PCR_17: [u8; 32] = [0xFF; 32];
+
+fn signer() -> TpmtPublic {
+    TpmtPublic {
+        name_alg: TpmiAlgHash::SHA256,
+        object_attributes: TpmaObject::SIGN_ENCRYPT,
+        auth_policy: Default::default(),
+        parms_and_id: PublicParmsAndId::Ecc(
+            TpmsEccParms {
+                symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+                scheme: TpmtEccScheme::Null(TpmsEmpty),
+                curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+                kdf: TpmtKdfScheme::Null(TpmsEmpty),
+            },
+            TpmsEccPoint {
+                x: Tpm2bEccParameter::from_bytes(&ECC_PUBLIC_X).unwrap(),
+                y: Tpm2bEccParameter::from_bytes(&ECC_PUBLIC_Y).unwrap(),
+            },
+        ),
+    }
+}
+
+/// Returns an attestation of `attested` as the TPM would generate it.
+fn attestation(attested: TpmuAttest) -> TpmsAttest {
+    TpmsAttest {
+        magic: TpmGenerated::VALUE,
+        qualified_signer: Tpm2bName::from_bytes(&TpmHandle::RHNull.0.to_be_bytes()).unwrap(),
+        extra_data: Tpm2bData::from_bytes(NONCE).unwrap(),
+        clock_info: TpmsClockInfo {
+            clock: 1000,
+            reset_count: 0,
+            restart_count: 0,
+            safe: TpmiYesNo::YES,
+        },
+        firmware_version: 0,
+        attested,
+    }
+}
+
+/// Returns a quote of PCRs 0 and 17 with the values they have after a reset.
+fn quote() -> TpmsAttest {
+    let pcr_select = TpmlPcrSelection::new(&[TpmsPcrSelection {
+        hash: TpmiAlgHash::SHA256,
+        sizeof_select: 3,
+        pcr_select: [0x01, 0x00, 0x02, 0x00],
+    }])
+    .unwrap();
+    attestation(TpmuAttest::Quote(TpmsQuoteInfo {
+        pcr_select,
+        pcr_digest: digest(TpmiAlgHash::SHA256, &[&PCR_0, &PCR_17]).unwrap(),
+    }))
+}
+
+/// Marshals `attest` and signs it with ECDSA and SHA-256.
+fn sign(attest: &TpmsAttest) -> (Tpm2bAttest, TpmtSignature) {
+    let mut buffer = [0; size_of::<TpmsAttest>()];
+    let size = attest.try_marshal(&mut buffer).unwrap();
+    let attest = Tpm2bAttest::from_bytes(&buffer[..size]).unwrap();
+    let key = SigningKey::from_slice(&ECC_PRIVATE).unwrap();
+    let attest_digest = digest(TpmiAlgHash::SHA256, &[attest.get_buffer()]).unwrap();
+    let signature: p256::ecdsa::Signature = key.sign_prehash(attest_digest.get_buffer()).unwrap();
+    let (r, s) = signature.split_bytes();
+    let signature = TpmtSignature::Ecdsa(TpmsSignatureEcc {
+        hash: TpmiAlgHash::SHA256,
+        signature_r: Tpm2bEccParameter::from_bytes(&r).unwrap(),
+        signature_s: Tpm2bEccParameter::from_bytes(&s).unwrap(),
+    });
+    (attest, signature)
+}
+
+#[test]
+fn test_verify_quote() {
+    let (attest, signature) = sign(&quote());
+    let verdict =
+        verify_attestation(&attest, &signature, &signer(), NONCE, &[&PCR_0, &PCR_17]).unwrap();
+    assert!(verdict.generated_by_tpm);
+    assert!(verdict.nonce_matches);
+    assert_eq!(verdict.pcr_digest_matches, Some(true));
+    assert!(verdict.signature_valid);
+    assert!(verdict.is_valid());
+    assert_eq!(verdict.attest, quote());
+}
+
+#[test]
+fn test_verify_quote_of_other_pcr_values() {
+    let (attest, signature) = sign(&quote());
+    let verdict =
+        verify_attestation(&attest, &signature, &signer(), NONCE, &[&PCR_17, &PCR_0]).unwrap();
+    assert_eq!(verdict.pcr_digest_matches, Some(false));
+    assert!(verdict.signature_valid);
+    assert!(!verdict.is_valid());
+}
+
+#[test]
+fn test_verify_stale_nonce() {
+    let (attest, signature) = sign(&quote());
+    let verdict =
+        verify_attestation(&attest, &signature, &signer(), b"other", &[&PCR_0, &PCR_17]).unwrap();
+    assert!(!verdict.nonce_matches);
+    assert!(!verdict.is_valid());
+}
+
+#[test]
+fn test_verify_not_generated_by_tpm() {
+    let attest = TpmsAttest {
+        magic: TpmGenerated(0),
+        ..quote()
+    };
+    let (attest, signature) = sign(&attest);
+    let verdict =
+        verify_attestation(&attest, &signature, &signer(), NONCE, &[&PCR_0, &PCR_17]).unwrap();
+    assert!(!verdict.generated_by_tpm);
+    assert!(verdict.signature_valid);
+    assert!(!verdict.is_valid());
+}
+
+#[test]
+fn test_verify_certify_without_pcrs() {
+    let name = Tpm2bName::from_bytes(&hex!("000b0102")).unwrap();
+    let (attest, signature) = sign(&attestation(TpmuAttest::Certify(TpmsCertifyInfo {
+        name,
+        qualified_name: name,
+    })));
+    let verdict = verify_attestation(&attest, &signature, &signer(), NONCE, &[]).unwrap();
+    assert_eq!(verdict.pcr_digest_matches, None);
+    assert!(verdict.is_valid());
+}
+
+#[test]
+fn test_verify_forged_signature() {
+    let (_, signature) = sign(&quote());
+    let forged = TpmsAttest {
+        firmware_version: 1,
+        ..quote()
+    };
+    let (attest, _) = sign(&forged);
+    let verdict =
+        verify_attestation(&attest, &signature, &signer(), NONCE, &[&PCR_0, &PCR_17]).unwrap();
+    assert!(!verdict.signature_valid);
+    assert!(!verdict.is_valid());
+
+    let verdict = verify_attestation(
+        &attest,
+        &TpmtSignature::Null(TpmsEmpty),
+        &signer(),
+        NONCE,
+        &[&PCR_0, &PCR_17],
+    )
+    .unwrap();
+    assert!(!verdict.signature_valid);
+}
+
+#[test]
+fn test_verify_unsupported_signature() {
+    let (attest, signature) = sign(&quote());
+    let TpmtSignature::Ecdsa(ecdsa) = signature else {
+        unreachable!()
+    };
+    assert_eq!(
+        verify_attestation(
+            &attest,
+            &TpmtSignature::Ecschnorr(ecdsa),
+            &signer(),
+            NONCE,
+            &[&PCR_0, &PCR_17],
+        ),
+        Err(TssTcsError::NotImplemented.into())
+    );
+}
+
+#[test]
+fn test_verify_malformed_attestation() {
+    let (attest, signature) = sign(&quote());
+    let truncated = Tpm2bAttest::from_bytes(&attest.get_buffer()[..10]).unwrap();
+    assert!(verify_attestation(&truncated, &signature, &signer(), NONCE, &[]).is_err());
+}
+
+#[test]
+fn test_cp_hash_and_rp_hash() {
+    let name = hex!("000b1234");
+    let cp_hash = audit::cp_hash(TpmiAlgHash::SHA256, TpmCc::Sign, &[&name], b"params").unwrap();
+    let expected = Sha256::new()
+        .chain_update(hex!("0000015d"))
+        .chain_update(name)
+        .chain_update(b"params")
+        .finalize();
+    assert_eq!(cp_hash.get_buffer(), &expected[..]);
+
+    let rp_hash = audit::rp_hash(TpmiAlgHash::SHA256, TpmCc::Sign, b"params").unwrap();
+    let expected = Sha256::digest(hex!("000000000000015d706172616d73"));
+    assert_eq!(rp_hash.get_buffer(), &expected[..]);
+
+    let names: [&[u8]; 4] = [&name; 4];
+    assert!(audit::cp_hash(TpmiAlgHash::SHA256, TpmCc::Sign, &names, &[]).is_err());
+}
+
+#[test]
+fn test_command_digest_sorts_command_codes() {
+    let digest = audit::command_digest(
+        TpmiAlgHash::SHA256,
+        &[
+            TpmCc::GetRandom,
+            TpmCc::SetCommandCodeAuditStatus,
+            TpmCc::GetRandom,
+        ],
+    )
+    .unwrap();
+    let expected = Sha256::digest(hex!("000001400000017b"));
+    assert_eq!(digest.get_buffer(), &expected[..]);
+}
+
+#[test]
+fn test_audit_digest() {
+    let cp_hash = Tpm2bDigest::from_bytes(&[1; 32]).unwrap();
+    let rp_hash = Tpm2bDigest::from_bytes(&[2; 32]).unwrap();
+
+    let mut command_audit = audit::AuditDigest::command_audit(TpmiAlgHash::SHA256);
+    assert_eq!(command_audit.digest().get_size(), 0);
+    command_audit.extend(&cp_hash, &rp_hash).unwrap();
+    let expected = Sha256::new()
+        .chain_update([1; 32])
+        .chain_update([2; 32])
+        .finalize();
+    assert_eq!(command_audit.digest().get_buffer(), &expected[..]);
+
+    let mut session_audit = audit::AuditDigest::session_audit(TpmiAlgHash::SHA384).unwrap();
+    assert_eq!(session_audit.digest().get_buffer(), &[0; 48]);
+    session_audit.extend(&cp_hash, &rp_hash).unwrap();
+    let expected = Sha384::new()
+        .chain_update([0; 48])
+        .chain_update([1; 32])
+        .chain_update([2; 32])
+        .finalize();
+    assert_eq!(session_audit.digest().get_buffer(), &expected[..]);
+}
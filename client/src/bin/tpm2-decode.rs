@@ -0,0 +1,87 @@
+//! Decodes TPM commands and responses for humans.
+//!
+//! Usage:
+//!
+//! ```text
+//! tpm2-decode TRACE...                 Decodes each record of the traces, `-` for stdin
+//! tpm2-decode COMMAND [RESPONSE]       Decodes a command and its response, in hexadecimal
+//! ```
+//!
+//! The traces are in the format that `RecordingConnection` writes.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process::ExitCode;
+
+use tpm2_rs_base::constants::TpmCc;
+use tpm2_rs_client::connection::read_trace;
+use tpm2_rs_client::decode::{Command, Response};
+
+fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let text: String = text.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    if text.is_empty()
+        || !text.len().is_multiple_of(2)
+        || !text.bytes().all(|c| c.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn print_exchange(command: &[u8], response: Option<&[u8]>) {
+    let command = Command::new(command);
+    println!("{command:#?}");
+    if let Some(response) = response {
+        let code = command.code().unwrap_or(TpmCc(0));
+        println!("{:#?}", Response::new(code, response));
+    }
+}
+
+fn decode_trace(path: &str) -> io::Result<()> {
+    let reader: Box<dyn BufRead> = match path {
+        "-" => Box::new(io::stdin().lock()),
+        _ => Box::new(BufReader::new(File::open(path)?)),
+    };
+    for (i, record) in read_trace(reader)?.iter().enumerate() {
+        println!(
+            "# {path} record {i} at {}.{:06}, locality {}",
+            record.timestamp.as_secs(),
+            record.timestamp.subsec_micros(),
+            record.locality
+        );
+        print_exchange(&record.command, Some(&record.response));
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        eprintln!("Usage: tpm2-decode TRACE...");
+        eprintln!("       tpm2-decode COMMAND [RESPONSE]");
+        return ExitCode::FAILURE;
+    }
+    if let Some(command) = parse_hex(&args[0]) {
+        let response = match &args[1..] {
+            [] => None,
+            [response] if parse_hex(response).is_some() => parse_hex(response),
+            _ => {
+                eprintln!("tpm2-decode: expected a command and a response in hexadecimal");
+                return ExitCode::FAILURE;
+            }
+        };
+        print_exchange(&command, response.as_deref());
+        return ExitCode::SUCCESS;
+    }
+    for path in &args {
+        if let Err(error) = decode_trace(path) {
+            eprintln!("tpm2-decode: {path}: {error}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
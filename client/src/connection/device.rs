@@ -0,0 +1,147 @@
+//! A connection to a TPM character device, such as `/dev/tpm0` or `/dev/tpmrm0` on Linux.
+//!
+//! This module provides the [`DeviceConnection`] struct, which implements the [`Connection`]
+//! trait by writing each command to the device and reading the response back from it.
+extern crate std;
+
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use rustix::event::{poll, PollFd, PollFlags, Timespec};
+
+use crate::connection::Connection;
+
+/// The TPM device of the Linux kernel, which only one process can open at a time.
+pub const TPM_DEVICE: &str = "/dev/tpm0";
+
+/// The TPM resource manager of the Linux kernel, which can be shared by several processes.
+pub const TPM_RM_DEVICE: &str = "/dev/tpmrm0";
+
+/// The size of the tag and the size at the start of each response.
+const RESPONSE_SIZE_END: usize = 6;
+
+/// A connection to a TPM through a character device.
+///
+/// This struct implements the [`Connection`] trait. By default, it waits for the TPM as long as it
+/// takes, because some commands such as key generation can take a long time.
+#[derive(Debug)]
+pub struct DeviceConnection {
+    device: File,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+}
+
+impl DeviceConnection {
+    /// Opens the TPM device at `path`, such as [`TPM_RM_DEVICE`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be opened for reading and writing.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<DeviceConnection> {
+        Ok(DeviceConnection {
+            device: OpenOptions::new().read(true).write(true).open(path)?,
+            read_timeout: None,
+            write_timeout: None,
+        })
+    }
+
+    /// Sets how long to wait for the response to a command, or `None` to wait indefinitely.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Returns how long to wait for the response to a command.
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    /// Sets how long to wait for the device to accept a command, or `None` to wait indefinitely.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    /// Returns how long to wait for the device to accept a command.
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write_timeout
+    }
+
+    /// Waits until the device is ready for `events` or `deadline` has passed.
+    fn wait(&self, events: PollFlags, deadline: Option<Instant>) -> Result<()> {
+        loop {
+            let timeout = deadline
+                .map(|deadline| {
+                    Timespec::try_from(deadline.saturating_duration_since(Instant::now()))
+                })
+                .transpose()
+                .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+            match poll(&mut [PollFd::new(&self.device, events)], timeout.as_ref()) {
+                Ok(0) => return Err(Error::new(ErrorKind::TimedOut, "timed out waiting for TPM")),
+                Ok(_) => return Ok(()),
+                Err(rustix::io::Errno::INTR) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Connection for DeviceConnection {
+    type Error = std::io::Error;
+    fn transact<'a>(&mut self, command: &[u8], response: &'a mut [u8]) -> Result<&'a mut [u8]> {
+        if response.len() < RESPONSE_SIZE_END {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "response buffer too small",
+            ));
+        }
+
+        // The device expects each command in a single write.
+        let deadline = self.write_timeout.map(|timeout| Instant::now() + timeout);
+        self.wait(PollFlags::OUT, deadline)?;
+        if self.device.write(command)? != command.len() {
+            return Err(Error::new(
+                ErrorKind::WriteZero,
+                "failed to write entire command to TPM",
+            ));
+        }
+
+        // The device usually returns the response in a single read, but it is read until it has
+        // the size from the response header.
+        let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+        let mut read = 0;
+        let mut size = RESPONSE_SIZE_END;
+        while read < size {
+            self.wait(PollFlags::IN, deadline)?;
+            let bytes_read = self.device.read(&mut response[read..])?;
+            if bytes_read == 0 {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "TPM closed the connection",
+                ));
+            }
+            read += bytes_read;
+            if read >= RESPONSE_SIZE_END {
+                let mut response_size = [0; 4];
+                response_size.copy_from_slice(&response[2..RESPONSE_SIZE_END]);
+                size = u32::from_be_bytes(response_size) as usize;
+                if size > response.len() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "response buffer too small",
+                    ));
+                }
+                if size < RESPONSE_SIZE_END || read > size {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "response size does not match the response",
+                    ));
+                }
+            }
+        }
+        Ok(&mut response[..size])
+    }
+}
+
+#[cfg(test)]
+mod tests;
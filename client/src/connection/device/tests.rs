@@ -0,0 +1,82 @@
+use std::fs::File;
+use std::thread;
+use std::vec::Vec;
+
+use rustix::pty::{grantpt, openpt, ptsname, unlockpt, OpenptFlags};
+use rustix::termios::{tcgetattr, tcsetattr, OptionalActions};
+
+use super::*;
+
+const COMMAND: [u8; 12] = [
+    0x80, 0x01, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x01, 0x44, 0x00, 0x00,
+];
+const RESPONSE: [u8; 10] = [0x80, 0x01, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00];
+
+/// Opens a pseudoterminal in raw mode, whose other end stands in for the TPM device.
+fn open_device() -> (DeviceConnection, File) {
+    let tpm = openpt(OpenptFlags::RDWR | OpenptFlags::NOCTTY).unwrap();
+    grantpt(&tpm).unwrap();
+    unlockpt(&tpm).unwrap();
+    let mut termios = tcgetattr(&tpm).unwrap();
+    termios.make_raw();
+    tcsetattr(&tpm, OptionalActions::Now, &termios).unwrap();
+    let path = ptsname(&tpm, Vec::new()).unwrap();
+    let device = DeviceConnection::open(path.to_str().unwrap()).unwrap();
+    (device, File::from(tpm))
+}
+
+/// Receives `COMMAND` as the TPM and responds with `response` in `chunks`.
+fn respond(mut tpm: File, response: &'static [u8], chunks: usize) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut command = [0; COMMAND.len()];
+        tpm.read_exact(&mut command).unwrap();
+        assert_eq!(command, COMMAND);
+        for chunk in response.chunks(response.len().div_ceil(chunks)) {
+            tpm.write_all(chunk).unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+    })
+}
+
+#[test]
+fn test_transact() {
+    let (mut device, tpm) = open_device();
+    let tpm = respond(tpm, &RESPONSE, 1);
+    let mut response = [0; 64];
+    assert_eq!(device.transact(&COMMAND, &mut response).unwrap(), RESPONSE);
+    tpm.join().unwrap();
+}
+
+#[test]
+fn test_transact_split_response() {
+    let (mut device, tpm) = open_device();
+    device.set_read_timeout(Some(Duration::from_secs(5)));
+    let tpm = respond(tpm, &RESPONSE, 3);
+    let mut response = [0; 64];
+    assert_eq!(device.transact(&COMMAND, &mut response).unwrap(), RESPONSE);
+    tpm.join().unwrap();
+}
+
+#[test]
+fn test_read_timeout() {
+    let (mut device, _tpm) = open_device();
+    device.set_read_timeout(Some(Duration::from_millis(50)));
+    assert_eq!(device.read_timeout(), Some(Duration::from_millis(50)));
+    let mut response = [0; 64];
+    assert_eq!(
+        device.transact(&COMMAND, &mut response).unwrap_err().kind(),
+        ErrorKind::TimedOut
+    );
+}
+
+#[test]
+fn test_response_too_large() {
+    let (mut device, tpm) = open_device();
+    let tpm = respond(tpm, &RESPONSE, 1);
+    let mut response = [0; 8];
+    assert_eq!(
+        device.transact(&COMMAND, &mut response).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+    tpm.join().unwrap();
+}
@@ -0,0 +1,80 @@
+//! An in-process connection to the TPM implemented by `tpm2-rs-server`.
+//!
+//! This module provides the [`LoopbackConnection`] struct, which implements the [`Connection`]
+//! trait by executing each command directly on a [`TpmContext`], without a socket or a simulator
+//! process in between. It also implements the [`AsyncConnection`] trait, whose futures execute
+//! the command and complete as soon as they are polled.
+
+use tpm2_rs_base::errors::{TpmRcError, TssError};
+use tpm2_rs_server::platform::TpmContextDeps;
+use tpm2_rs_server::{ServerError, TpmContext};
+
+use crate::connection::{AsyncConnection, Connection};
+
+/// A connection to a [`TpmContext`] in the same process.
+///
+/// This struct implements the [`Connection`] trait. The TPM lives as long as the connection, so
+/// its objects and sessions are lost when the connection is dropped.
+pub struct LoopbackConnection<Deps: TpmContextDeps<Request = [u8], Response = [u8]>> {
+    tpm: TpmContext<Deps>,
+}
+
+impl<Deps: TpmContextDeps<Request = [u8], Response = [u8]>> LoopbackConnection<Deps> {
+    /// Creates a connection to a new TPM with the platform dependencies `Deps`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the TPM cannot be created, such as when its DRBG fails to instantiate.
+    pub fn new() -> Result<Self, ServerError> {
+        Ok(Self::from_context(TpmContext::new()?))
+    }
+
+    /// Creates a connection to the existing TPM `tpm`.
+    pub fn from_context(tpm: TpmContext<Deps>) -> Self {
+        LoopbackConnection { tpm }
+    }
+
+    /// Returns the TPM of the connection.
+    pub fn into_context(self) -> TpmContext<Deps> {
+        self.tpm
+    }
+}
+
+impl<Deps: TpmContextDeps<Request = [u8], Response = [u8]>> Connection
+    for LoopbackConnection<Deps>
+{
+    type Error = TssError;
+    fn transact<'a>(
+        &mut self,
+        command: &[u8],
+        response: &'a mut [u8],
+    ) -> Result<&'a mut [u8], TssError> {
+        // The TPM writes nothing at all if the response does not fit, not even an error.
+        match self.tpm.execute_command_separate(command, response) {
+            0 => Err(TpmRcError::Size.into()),
+            size => Ok(&mut response[..size]),
+        }
+    }
+    fn locality(&self) -> u8 {
+        self.tpm.locality()
+    }
+}
+
+impl<Deps: TpmContextDeps<Request = [u8], Response = [u8]>> AsyncConnection
+    for LoopbackConnection<Deps>
+where
+    TpmContext<Deps>: Send,
+{
+    type Error = TssError;
+    /// Executes the command on the TPM when polled, since it never waits for I/O.
+    ///
+    /// The command runs on the thread that polls the future, which it blocks until the TPM is
+    /// done, like any other computation of the task.
+    async fn transact<'a>(
+        &mut self,
+        command: &[u8],
+        response: &'a mut [u8],
+    ) -> Result<&'a mut [u8], TssError> {
+        Connection::transact(self, command, response)
+    }
+}
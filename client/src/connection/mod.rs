@@ -1,11 +1,37 @@
 //! This module provides traits to communicate with a
 //! TPM via a particular medium. The top-level trait
-//! is [`Connection`].
+//! is [`Connection`], and [`AsyncConnection`] is its
+//! counterpart for async code.
 
 use core::error::Error;
+use core::future::Future;
 
+#[cfg(feature = "connection-device")]
+mod device;
+#[cfg(feature = "connection-loopback")]
+mod loopback;
+#[cfg(feature = "recording")]
+mod recording;
+#[cfg(feature = "resource-manager")]
+mod resource_manager;
+mod retry;
+#[cfg(feature = "connection-tcp")]
 mod tcp;
+#[cfg(any(feature = "connection-tcp", feature = "connection-device"))]
+mod worker;
+#[cfg(feature = "connection-device")]
+pub use device::*;
+#[cfg(feature = "connection-loopback")]
+pub use loopback::*;
+#[cfg(feature = "recording")]
+pub use recording::*;
+#[cfg(feature = "resource-manager")]
+pub use resource_manager::*;
+pub use retry::*;
+#[cfg(feature = "connection-tcp")]
 pub use tcp::*;
+#[cfg(any(feature = "connection-tcp", feature = "connection-device"))]
+pub use worker::*;
 
 /// Trait for communicating with a TPM.
 pub trait Connection {
@@ -23,4 +49,33 @@ pub trait Connection {
     /// still returns `Ok(...)`. `Err` is only returned when we are unable to
     /// get a response at all.
     fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> Result<&'a mut [u8], Self::Error>;
+    /// Returns the locality at which commands are sent to the TPM.
+    ///
+    /// Connections that cannot choose a locality send commands at locality 0.
+    fn locality(&self) -> u8 {
+        0
+    }
+}
+
+/// Trait for communicating with a TPM from async code.
+///
+/// This is the async counterpart of [`Connection`], which does not depend on
+/// any particular runtime. Implementations can be written with `async fn`, as
+/// long as their futures are [`Send`] so that multi-threaded runtimes can move
+/// the tasks that await them between threads.
+pub trait AsyncConnection {
+    /// The type returned if [`AsyncConnection::transact`] fails.
+    ///
+    /// As for [`Connection::Error`], this type does not include `TPM_RC`
+    /// errors.
+    type Error: Error;
+    /// Perform a command/response transaction with the TPM.
+    ///
+    /// Returns a slice of the response containing the bytes that were written.
+    /// See [`Connection::transact`].
+    fn transact<'a>(
+        &mut self,
+        cmd: &[u8],
+        rsp: &'a mut [u8],
+    ) -> impl Future<Output = Result<&'a mut [u8], Self::Error>> + Send;
 }
@@ -0,0 +1,267 @@
+//! Recording the commands sent to a TPM and replaying the responses.
+//!
+//! This module provides the [`RecordingConnection`] struct, which implements the [`Connection`]
+//! trait by passing each command to another connection and appending the command and its response
+//! to a trace, and the [`ReplayConnection`] struct, which serves the responses of a trace and
+//! checks that the commands match it.
+//!
+//! # Trace format
+//!
+//! A trace is a text file with one [`Record`] per line. Each line holds four fields separated by
+//! single spaces:
+//!
+//! 1. the time at which the response was received, as seconds and microseconds since the Unix
+//!    epoch separated by a `.`,
+//! 2. the locality of the command, in decimal,
+//! 3. the command bytes, in lowercase hexadecimal,
+//! 4. the response bytes, in lowercase hexadecimal.
+//!
+//! Empty lines and lines that start with `#` are ignored. For example:
+//!
+//! ```text
+//! # tpm2-rs trace
+//! 1760000000.000042 0 80010000000c0000017b0004 80010000001000000000000412345678
+//! ```
+//!
+//! Commands for which the connection returns an error are not recorded.
+extern crate std;
+
+use core::fmt::Write as _;
+use core::time::Duration;
+use std::format;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result, Write};
+use std::path::Path;
+use std::string::String;
+use std::time::SystemTime;
+use std::vec::Vec;
+
+use crate::connection::Connection;
+
+/// The first line of the traces written by a [`RecordingConnection`].
+const TRACE_HEADER: &str = "# tpm2-rs trace\n";
+
+/// A command and its response in a trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// The time at which the response was received, since the Unix epoch.
+    pub timestamp: Duration,
+    /// The locality of the command.
+    pub locality: u8,
+    /// The command bytes.
+    pub command: Vec<u8>,
+    /// The response bytes.
+    pub response: Vec<u8>,
+}
+
+fn write_hex(line: &mut String, bytes: &[u8]) {
+    for byte in bytes {
+        let _ = write!(line, "{byte:02x}");
+    }
+}
+
+fn parse_hex(field: &str) -> Option<Vec<u8>> {
+    if !field.len().is_multiple_of(2) || !field.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..field.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&field[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn parse_timestamp(field: &str) -> Option<Duration> {
+    let (seconds, micros) = field.split_once('.')?;
+    if micros.len() != 6 {
+        return None;
+    }
+    let micros: u32 = micros.parse().ok()?;
+    Some(Duration::new(seconds.parse().ok()?, micros * 1000))
+}
+
+impl Record {
+    /// Returns the line of the record in a trace, including the line break.
+    pub fn to_line(&self) -> String {
+        let mut line = format!(
+            "{}.{:06} {} ",
+            self.timestamp.as_secs(),
+            self.timestamp.subsec_micros(),
+            self.locality
+        );
+        write_hex(&mut line, &self.command);
+        line.push(' ');
+        write_hex(&mut line, &self.response);
+        line.push('\n');
+        line
+    }
+
+    /// Parses a line of a trace without its line break.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`ErrorKind::InvalidData`] error if the line is not a record.
+    pub fn parse(line: &str) -> Result<Self> {
+        let invalid = || Error::new(ErrorKind::InvalidData, format!("invalid record: {line}"));
+        let mut fields = line.split(' ');
+        let mut next = || fields.next().ok_or_else(invalid);
+        let record = Record {
+            timestamp: parse_timestamp(next()?).ok_or_else(invalid)?,
+            locality: next()?.parse().map_err(|_| invalid())?,
+            command: parse_hex(next()?).ok_or_else(invalid)?,
+            response: parse_hex(next()?).ok_or_else(invalid)?,
+        };
+        if fields.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(record)
+    }
+}
+
+/// Reads the records of the trace in `reader`.
+///
+/// # Errors
+///
+/// Returns an error if `reader` fails or an [`ErrorKind::InvalidData`] error if a line is not a
+/// record.
+pub fn read_trace<R: BufRead>(reader: R) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.is_empty() && !line.starts_with('#') {
+            records.push(Record::parse(&line)?);
+        }
+    }
+    Ok(records)
+}
+
+/// A connection that records each command and its response to a trace.
+///
+/// This struct implements the [`Connection`] trait. Each record is written to
+/// the trace with a single write as soon as the response is received, so a
+/// trace of a process that crashes is complete up to the last response.
+#[derive(Debug)]
+pub struct RecordingConnection<C: Connection<Error: Into<Error>>, W: Write> {
+    connection: C,
+    trace: W,
+}
+
+impl<C: Connection<Error: Into<Error>>> RecordingConnection<C, File> {
+    /// Records the commands sent over `connection` to a new trace file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub fn create<P: AsRef<Path>>(connection: C, path: P) -> Result<Self> {
+        Self::new(connection, File::create(path)?)
+    }
+}
+
+impl<C: Connection<Error: Into<Error>>, W: Write> RecordingConnection<C, W> {
+    /// Records the commands sent over `connection` to `trace`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header of the trace cannot be written.
+    pub fn new(connection: C, mut trace: W) -> Result<Self> {
+        trace.write_all(TRACE_HEADER.as_bytes())?;
+        trace.flush()?;
+        Ok(RecordingConnection { connection, trace })
+    }
+
+    /// Returns the wrapped connection and the trace.
+    pub fn into_inner(self) -> (C, W) {
+        (self.connection, self.trace)
+    }
+}
+
+impl<C: Connection<Error: Into<Error>>, W: Write> Connection for RecordingConnection<C, W> {
+    type Error = Error;
+    fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> Result<&'a mut [u8]> {
+        let response = self.connection.transact(cmd, rsp).map_err(Into::into)?;
+        let record = Record {
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default(),
+            locality: self.connection.locality(),
+            command: cmd.to_vec(),
+            response: response.to_vec(),
+        };
+        self.trace.write_all(record.to_line().as_bytes())?;
+        self.trace.flush()?;
+        Ok(response)
+    }
+    fn locality(&self) -> u8 {
+        self.connection.locality()
+    }
+}
+
+/// A connection that serves the responses of a trace.
+///
+/// This struct implements the [`Connection`] trait. Each command must be the
+/// same as the next one in the trace, byte for byte, so commands with sessions
+/// only match if their nonces are the same as when the trace was recorded.
+#[derive(Clone, Debug)]
+pub struct ReplayConnection {
+    records: Vec<Record>,
+    next: usize,
+}
+
+impl ReplayConnection {
+    /// Replays the trace file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a trace.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(read_trace(BufReader::new(File::open(path)?))?))
+    }
+
+    /// Replays `records`.
+    pub fn new(records: Vec<Record>) -> Self {
+        ReplayConnection { records, next: 0 }
+    }
+
+    /// Returns the records that have not been replayed yet.
+    pub fn remaining(&self) -> &[Record] {
+        &self.records[self.next..]
+    }
+}
+
+impl Connection for ReplayConnection {
+    type Error = Error;
+    /// Returns the response of the next record of the trace.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`ErrorKind::UnexpectedEof`] error if all records were replayed, an
+    /// [`ErrorKind::InvalidData`] error if the command does not match the next record, or an
+    /// [`ErrorKind::InvalidInput`] error if the response does not fit in `rsp`.
+    fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> Result<&'a mut [u8]> {
+        let Some(record) = self.records.get(self.next) else {
+            let message = format!("the trace has no record for command {}", self.next);
+            return Err(Error::new(ErrorKind::UnexpectedEof, message));
+        };
+        if record.command != cmd {
+            let mut message = format!("command {} is ", self.next);
+            write_hex(&mut message, cmd);
+            message.push_str(" but the trace has ");
+            write_hex(&mut message, &record.command);
+            return Err(Error::new(ErrorKind::InvalidData, message));
+        }
+        let response = rsp
+            .get_mut(..record.response.len())
+            .ok_or(Error::from(ErrorKind::InvalidInput))?;
+        response.copy_from_slice(&record.response);
+        self.next += 1;
+        Ok(response)
+    }
+    /// Returns the locality of the last replayed record.
+    fn locality(&self) -> u8 {
+        self.next
+            .checked_sub(1)
+            .map_or(0, |last| self.records[last].locality)
+    }
+}
+
+#[cfg(test)]
+mod tests;
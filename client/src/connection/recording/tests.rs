@@ -0,0 +1,134 @@
+use tpm2_rs_base::commands::GetRandomCmd;
+use tpm2_rs_base::errors::{TssError, TssResult};
+use tpm2_rs_base::{Tpm2bDigest, Tpm2bSimple};
+
+use super::*;
+use crate::run_command_with_handles;
+use std::string::ToString;
+use std::vec;
+
+const GET_RANDOM: [u8; 12] = [
+    0x80, 0x01, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x01, 0x7B, 0x00, 0x04,
+];
+const RANDOM: [u8; 16] = [
+    0x80, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x12, 0x34, 0x56, 0x78,
+];
+
+/// A TPM that responds to every command with the same random bytes at locality 3.
+struct FixedTpm;
+
+impl Connection for FixedTpm {
+    type Error = TssError;
+    fn transact<'a>(&mut self, _: &[u8], rsp: &'a mut [u8]) -> TssResult<&'a mut [u8]> {
+        rsp[..RANDOM.len()].copy_from_slice(&RANDOM);
+        Ok(&mut rsp[..RANDOM.len()])
+    }
+    fn locality(&self) -> u8 {
+        3
+    }
+}
+
+fn get_random<C: Connection<Error: From<TssError>>>(
+    tpm: &mut C,
+) -> core::result::Result<Vec<u8>, C::Error> {
+    let command = GetRandomCmd { bytes_requested: 4 };
+    let (response, ()) = run_command_with_handles(&command, (), (), tpm)?;
+    Ok(response.random_bytes.get_buffer().to_vec())
+}
+
+#[test]
+fn test_record_format() {
+    let record = Record {
+        timestamp: Duration::new(1760000000, 42_000),
+        locality: 0,
+        command: GET_RANDOM.to_vec(),
+        response: RANDOM.to_vec(),
+    };
+    let line = "1760000000.000042 0 80010000000c0000017b0004 80010000001000000000000412345678";
+    assert_eq!(record.to_line(), format!("{line}\n"));
+    assert_eq!(Record::parse(line).unwrap(), record);
+    for invalid in [
+        "1760000000 0 8001 8001",
+        "1760000000.000042 0 800 8001",
+        "1760000000.000042 0 8001",
+        "1760000000.000042 256 8001 8001",
+        "1760000000.000042 0 8001 8001 8001",
+        "1760000000.000042 0 +f01 8001",
+    ] {
+        assert_eq!(
+            Record::parse(invalid).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
+
+#[test]
+fn test_record_and_replay() {
+    let mut tpm = RecordingConnection::new(FixedTpm, Vec::new()).unwrap();
+    let expected = get_random(&mut tpm).unwrap();
+    get_random(&mut tpm).unwrap();
+    let (_, trace) = tpm.into_inner();
+
+    let trace = String::from_utf8(trace).unwrap();
+    assert!(trace.starts_with(TRACE_HEADER));
+    let records = read_trace(trace.as_bytes()).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].command, GET_RANDOM);
+    assert_eq!(records[0].response, RANDOM);
+    assert_eq!(records[0].locality, 3);
+
+    let mut replay = ReplayConnection::new(records);
+    assert_eq!(replay.locality(), 0);
+    assert_eq!(get_random(&mut replay).unwrap(), expected);
+    assert_eq!(replay.locality(), 3);
+    assert_eq!(replay.remaining().len(), 1);
+    assert_eq!(get_random(&mut replay).unwrap(), expected);
+    assert_eq!(
+        get_random(&mut replay).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+fn test_replay_command_mismatch() {
+    let mut record = Record::parse(
+        "1760000000.000042 0 80010000000c0000017b0008 80010000001000000000000412345678",
+    )
+    .unwrap();
+    let mut replay = ReplayConnection::new(vec![record.clone()]);
+    let error = get_random(&mut replay).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert_eq!(
+        error.to_string(),
+        "command 0 is 80010000000c0000017b0004 but the trace has 80010000000c0000017b0008"
+    );
+    assert_eq!(replay.remaining().len(), 1);
+
+    record.command = GET_RANDOM.to_vec();
+    let mut replay = ReplayConnection::new(vec![record]);
+    let mut response = [0; 8];
+    assert_eq!(
+        replay
+            .transact(&GET_RANDOM, &mut response)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn test_replay_trace_file() {
+    let path = std::env::temp_dir().join(format!("tpm2-rs-trace-{}", std::process::id()));
+    let mut tpm = RecordingConnection::create(FixedTpm, &path).unwrap();
+    let expected = get_random(&mut tpm).unwrap();
+    drop(tpm);
+
+    let mut replay = ReplayConnection::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(get_random(&mut replay).unwrap(), expected);
+    assert!(replay.remaining().is_empty());
+    assert_eq!(
+        expected,
+        Tpm2bDigest::from_bytes(&RANDOM[12..]).unwrap().get_buffer()
+    );
+}
@@ -0,0 +1,487 @@
+//! A resource manager that lets several users share the limited memory of a TPM.
+//!
+//! This module provides the [`ResourceManager`] struct, which implements the
+//! [`Connection`] trait on top of another connection. It gives each transient
+//! object a virtual handle that stays valid while the object is swapped in and
+//! out of the TPM with `TPM2_ContextSave`, `TPM2_ContextLoad` and
+//! `TPM2_FlushContext`, and swaps sessions the same way.
+extern crate std;
+
+use core::mem::size_of;
+use std::collections::BTreeMap;
+use std::vec;
+use std::vec::Vec;
+
+use tpm2_rs_base::commands::GetCapabilityCmd;
+use tpm2_rs_base::constants::{TpmCap, TpmCc, TpmHc, TpmPt, TpmSt};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError, TssError, TssTcsError};
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{TpmaCc, TpmaSession, TpmiYesNo, TpmsCapabilityData};
+
+use crate::connection::Connection;
+use crate::{get_capability, RESP_BUFFER_SIZE};
+
+/// The size of the tag, size and command or response code at the start of each command and
+/// response.
+const HEADER_SIZE: usize = 10;
+
+/// The size of a handle.
+const HANDLE_SIZE: usize = 4;
+
+/// The error positions of the handles of a command.
+const POSITIONS: [ErrorPosition; 3] = [
+    ErrorPosition::Pos1,
+    ErrorPosition::Pos2,
+    ErrorPosition::Pos3,
+];
+
+/// The first virtual handle of a transient object.
+const FIRST_VIRTUAL_HANDLE: u32 = 0x80FF_0000;
+
+/// The number of command attributes to ask the TPM for at a time.
+const COMMANDS_PER_QUERY: u32 = 256;
+
+/// Why the resource manager could not run a command.
+enum Failure<E> {
+    /// The connection to the TPM failed.
+    Connection(E),
+    /// The command failed with a response code.
+    Tpm(u32),
+}
+
+/// Where the context of an object or session is.
+enum Context {
+    /// The context is loaded in the TPM with the given handle.
+    Loaded(u32),
+    /// The context is saved outside of the TPM as a marshaled `TPMS_CONTEXT`.
+    Saved(Vec<u8>),
+}
+
+/// An object or session that the resource manager swaps in and out of the TPM.
+struct Entity {
+    context: Context,
+    /// When the entity was last used by a command, to pick the one to swap out.
+    last_used: u64,
+}
+
+/// A session in the authorization area of a command.
+struct AuthSession {
+    handle: u32,
+    continue_session: bool,
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buffer.get(offset..offset + size_of::<u32>())?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn write_u32(buffer: &mut [u8], offset: usize, value: u32) {
+    buffer[offset..offset + size_of::<u32>()].copy_from_slice(&value.to_be_bytes());
+}
+
+fn is_session(handle: u32) -> bool {
+    TpmHc::is_hmac_session(handle) || TpmHc::is_policy_session(handle)
+}
+
+/// Returns whether `rc` means that the TPM is out of memory for objects or sessions.
+fn is_memory_warning(rc: u32) -> bool {
+    [
+        TpmRcError::ObjectMemory,
+        TpmRcError::SessionMemory,
+        TpmRcError::Memory,
+    ]
+    .iter()
+    .any(|warning| warning.get() == rc)
+}
+
+/// Returns the sessions in the authorization area of `command`, which starts at `offset`.
+fn auth_sessions(command: &[u8], offset: usize) -> Option<Vec<AuthSession>> {
+    let mut sessions = Vec::new();
+    if read_u32(command, 0)? >> 16 != TpmSt::Sessions.0 as u32 {
+        return Some(sessions);
+    }
+    let mut buffer = UnmarshalBuf::new(command.get(offset..)?);
+    let size = u32::try_unmarshal(&mut buffer).ok()? as usize;
+    let mut buffer = UnmarshalBuf::new(buffer.get(size)?);
+    while !buffer.is_empty() {
+        let handle = u32::try_unmarshal(&mut buffer).ok()?;
+        let nonce_size = u16::try_unmarshal(&mut buffer).ok()?;
+        buffer.get(nonce_size as usize)?;
+        let attributes = TpmaSession(u8::try_unmarshal(&mut buffer).ok()?);
+        let hmac_size = u16::try_unmarshal(&mut buffer).ok()?;
+        buffer.get(hmac_size as usize)?;
+        sessions.push(AuthSession {
+            handle,
+            continue_session: attributes.contains(TpmaSession::CONTINUE_SESSION),
+        });
+    }
+    Some(sessions)
+}
+
+/// A connection that virtualizes the transient objects and sessions of the TPM behind another
+/// connection.
+///
+/// This struct implements the [`Connection`] trait. Commands see each transient
+/// object under a virtual handle, which the resource manager maps to the handle
+/// of the object while it is loaded. When the TPM runs out of memory for a
+/// command, the resource manager saves and flushes the least recently used
+/// objects and sessions that the command does not refer to and retries the
+/// command; the saved ones are loaded again by the next command that refers to
+/// them. Session handles are not virtualized because the TPM keeps them while a
+/// session is saved.
+///
+/// The resource manager finds the handles in commands and responses with the
+/// attributes of each command, so it answers commands that the TPM does not
+/// report with `TPM_RC_COMMAND_CODE`. The handles reported by other commands, such as
+/// `TPM2_GetCapability`, are not virtualized.
+pub struct ResourceManager<C: Connection<Error: From<TssError>>> {
+    connection: C,
+    attributes: Vec<TpmaCc>,
+    /// The transient objects by virtual handle.
+    objects: BTreeMap<u32, Entity>,
+    /// The sessions by handle.
+    sessions: BTreeMap<u32, Entity>,
+    next_handle: u32,
+    /// The number of commands run so far.
+    clock: u64,
+    /// The response buffer of the commands that the resource manager sends itself.
+    scratch: Vec<u8>,
+}
+
+impl<C: Connection<Error: From<TssError>>> ResourceManager<C> {
+    /// Creates a resource manager for the TPM behind `connection`, which it asks for the
+    /// attributes of the commands it supports.
+    ///
+    /// The resource manager assumes that it is the only user of the transient objects and
+    /// sessions of the TPM.
+    pub fn new(mut connection: C) -> Result<Self, C::Error> {
+        let mut attributes = Vec::new();
+        let mut property = TpmCc::NVUndefineSpaceSpecial.0;
+        loop {
+            let command = GetCapabilityCmd {
+                capability: TpmCap::Commands,
+                property: TpmPt(property),
+                property_count: COMMANDS_PER_QUERY,
+            };
+            let response = get_capability(&mut connection, &command)?;
+            let TpmsCapabilityData::Command(list) = response.capability_data else {
+                return Err(TssError::from(TssTcsError::TpmUnexpected).into());
+            };
+            attributes.extend_from_slice(list.command_attributes());
+            match list.command_attributes().last() {
+                Some(last) if response.more_data == TpmiYesNo::YES => {
+                    property = (last.0 & TpmaCc::V.0 | last.get_command_index() as u32) + 1;
+                }
+                _ => break,
+            }
+        }
+        Ok(Self::with_attributes(connection, attributes))
+    }
+
+    /// Creates a resource manager for the TPM behind `connection` that supports the commands
+    /// with `attributes`.
+    pub fn with_attributes(connection: C, attributes: Vec<TpmaCc>) -> Self {
+        ResourceManager {
+            connection,
+            attributes,
+            objects: BTreeMap::new(),
+            sessions: BTreeMap::new(),
+            next_handle: FIRST_VIRTUAL_HANDLE,
+            clock: 0,
+            scratch: vec![0; RESP_BUFFER_SIZE],
+        }
+    }
+
+    /// Returns the wrapped connection.
+    ///
+    /// The objects and sessions that are saved outside of the TPM are lost.
+    pub fn into_inner(self) -> C {
+        self.connection
+    }
+
+    /// Returns the attributes of the command with `code`.
+    fn lookup(&self, code: u32) -> Option<TpmaCc> {
+        self.attributes.iter().copied().find(|attributes| {
+            attributes.get_command_index() as u32 == code & 0xFFFF
+                && attributes.0 & TpmaCc::V.0 == code & TpmaCc::V.0
+        })
+    }
+
+    fn allocate_handle(&mut self) -> u32 {
+        loop {
+            let handle = self.next_handle;
+            self.next_handle = if handle == TpmHc::TransientLast.get() {
+                FIRST_VIRTUAL_HANDLE
+            } else {
+                handle + 1
+            };
+            if !self.objects.contains_key(&handle) {
+                return handle;
+            }
+        }
+    }
+
+    fn entities(&mut self, handle: u32) -> &mut BTreeMap<u32, Entity> {
+        if TpmHc::is_transient(handle) {
+            &mut self.objects
+        } else {
+            &mut self.sessions
+        }
+    }
+
+    /// Sends the command with `code` and `parameters` to the TPM and returns the size of the
+    /// successful response in the scratch buffer.
+    fn execute(&mut self, code: TpmCc, parameters: &[u8]) -> Result<usize, Failure<C::Error>> {
+        let mut command = vec![0; HEADER_SIZE];
+        command[..2].copy_from_slice(&TpmSt::NoSessions.0.to_be_bytes());
+        command.extend_from_slice(parameters);
+        let size = command.len() as u32;
+        write_u32(&mut command, 2, size);
+        write_u32(&mut command, 6, code.0);
+        let response = self
+            .connection
+            .transact(&command, &mut self.scratch)
+            .map_err(Failure::Connection)?;
+        match read_u32(response, 6) {
+            Some(0) => Ok(response.len()),
+            Some(rc) => Err(Failure::Tpm(rc)),
+            None => Err(Failure::Tpm(
+                TssError::from(TssTcsError::TpmUnexpected).get(),
+            )),
+        }
+    }
+
+    fn context_save(&mut self, handle: u32) -> Result<Vec<u8>, Failure<C::Error>> {
+        let size = self.execute(TpmCc::ContextSave, &handle.to_be_bytes())?;
+        Ok(self.scratch[HEADER_SIZE..size].to_vec())
+    }
+
+    fn context_load(&mut self, context: &[u8]) -> Result<u32, Failure<C::Error>> {
+        let size = self.execute(TpmCc::ContextLoad, context)?;
+        read_u32(&self.scratch[..size], HEADER_SIZE).ok_or(Failure::Tpm(
+            TssError::from(TssTcsError::TpmUnexpected).get(),
+        ))
+    }
+
+    /// Saves the least recently used entity that is loaded, is not `pinned`, and frees the
+    /// memory that `rc` says is missing. Returns `false` if there is none.
+    fn evict(&mut self, rc: u32, pinned: &[u32]) -> Result<bool, Failure<C::Error>> {
+        let least_recently_used = |entities: &BTreeMap<u32, Entity>| {
+            entities
+                .iter()
+                .filter(|(handle, entity)| {
+                    matches!(entity.context, Context::Loaded(_)) && !pinned.contains(handle)
+                })
+                .min_by_key(|(_, entity)| entity.last_used)
+                .map(|(handle, entity)| (*handle, entity.last_used))
+        };
+        let object = (rc != TpmRcError::SessionMemory.get())
+            .then(|| least_recently_used(&self.objects))
+            .flatten();
+        let session = (rc != TpmRcError::ObjectMemory.get())
+            .then(|| least_recently_used(&self.sessions))
+            .flatten();
+        let handle = match (object, session) {
+            (Some(object), Some(session)) if session.1 < object.1 => session.0,
+            (Some(object), _) => object.0,
+            (None, Some(session)) => session.0,
+            (None, None) => return Ok(false),
+        };
+        let Some(Context::Loaded(loaded)) = self.entities(handle).get(&handle).map(|e| &e.context)
+        else {
+            return Ok(false);
+        };
+        let loaded = *loaded;
+        let context = self.context_save(loaded)?;
+        // Saving a session removes it from the TPM, but an object has to be flushed.
+        if TpmHc::is_transient(handle) {
+            self.execute(TpmCc::FlushContext, &loaded.to_be_bytes())?;
+        }
+        if let Some(entity) = self.entities(handle).get_mut(&handle) {
+            entity.context = Context::Saved(context);
+        }
+        Ok(true)
+    }
+
+    /// Loads the object or session with `handle` if it is saved, swapping out others except
+    /// `pinned` ones as needed, and returns its handle in the TPM. Returns `None` if the resource
+    /// manager does not know `handle`.
+    fn load(&mut self, handle: u32, pinned: &[u32]) -> Result<Option<u32>, Failure<C::Error>> {
+        let clock = self.clock;
+        loop {
+            let context = match self.entities(handle).get_mut(&handle) {
+                None => return Ok(None),
+                Some(entity) => {
+                    entity.last_used = clock;
+                    match &entity.context {
+                        Context::Loaded(loaded) => return Ok(Some(*loaded)),
+                        Context::Saved(context) => context.clone(),
+                    }
+                }
+            };
+            match self.context_load(&context) {
+                Ok(loaded) => {
+                    if let Some(entity) = self.entities(handle).get_mut(&handle) {
+                        entity.context = Context::Loaded(loaded);
+                    }
+                    return Ok(Some(loaded));
+                }
+                Err(Failure::Tpm(rc)) if is_memory_warning(rc) && self.evict(rc, pinned)? => {}
+                Err(failure) => return Err(failure),
+            }
+        }
+    }
+
+    /// Runs `TPM2_FlushContext` for the object or session in `command`.
+    fn flush_context(
+        &mut self,
+        mut command: Vec<u8>,
+        rsp: &mut [u8],
+    ) -> Result<usize, Failure<C::Error>> {
+        let handle =
+            read_u32(&command, HEADER_SIZE).ok_or(Failure::Tpm(TpmRcError::CommandSize.get()))?;
+        if TpmHc::is_transient(handle) {
+            match self.objects.remove(&handle) {
+                Some(Entity {
+                    context: Context::Loaded(loaded),
+                    ..
+                }) => write_u32(&mut command, HEADER_SIZE, loaded),
+                // A saved object is only known to the resource manager.
+                Some(_) => return write_response(rsp, 0).map_err(Failure::Connection),
+                None => {
+                    let rc = TpmRcError::HandleFor(ErrorType::Parameter, ErrorPosition::Pos1);
+                    return Err(Failure::Tpm(rc.get()));
+                }
+            }
+        } else {
+            // The TPM flushes saved sessions too.
+            self.sessions.remove(&handle);
+        }
+        let response = self
+            .connection
+            .transact(&command, rsp)
+            .map_err(Failure::Connection)?;
+        Ok(response.len())
+    }
+
+    /// Runs `cmd` with the virtual handles replaced and returns the size of the response in `rsp`.
+    fn run(&mut self, cmd: &[u8], rsp: &mut [u8]) -> Result<usize, Failure<C::Error>> {
+        self.clock += 1;
+        let code = read_u32(cmd, 6).ok_or(Failure::Tpm(TpmRcError::CommandSize.get()))?;
+        let attributes = self
+            .lookup(code)
+            .ok_or(Failure::Tpm(TpmRcError::CommandCode.get()))?;
+        let handles_end = HEADER_SIZE + HANDLE_SIZE * attributes.get_c_handles() as usize;
+        if cmd.len() < handles_end {
+            return Err(Failure::Tpm(TpmRcError::CommandSize.get()));
+        }
+        let sessions =
+            auth_sessions(cmd, handles_end).ok_or(Failure::Tpm(TpmRcError::AuthSize.get()))?;
+        let handles: Vec<u32> = (HEADER_SIZE..handles_end)
+            .step_by(HANDLE_SIZE)
+            .filter_map(|offset| read_u32(cmd, offset))
+            .collect();
+        let mut command = cmd.to_vec();
+        if code == TpmCc::FlushContext.0 {
+            return self.flush_context(command, rsp);
+        }
+
+        // The objects and sessions of the command must stay loaded while it runs.
+        let pinned: Vec<u32> = handles
+            .iter()
+            .copied()
+            .chain(sessions.iter().map(|session| session.handle))
+            .filter(|&handle| TpmHc::is_transient(handle) || is_session(handle))
+            .collect();
+        for (i, &handle) in handles.iter().enumerate() {
+            if !TpmHc::is_transient(handle) && !is_session(handle) {
+                continue;
+            }
+            match self.load(handle, &pinned)? {
+                Some(loaded) => write_u32(&mut command, HEADER_SIZE + i * HANDLE_SIZE, loaded),
+                None if TpmHc::is_transient(handle) => {
+                    let position = POSITIONS.get(i).copied().unwrap_or(ErrorPosition::Pos1);
+                    let rc = TpmRcError::HandleFor(ErrorType::Handle, position);
+                    return Err(Failure::Tpm(rc.get()));
+                }
+                // Sessions that the resource manager does not know are left to the TPM.
+                None => {}
+            }
+        }
+        for session in &sessions {
+            if is_session(session.handle) {
+                self.load(session.handle, &pinned)?;
+            }
+        }
+
+        let size = loop {
+            let size = self
+                .connection
+                .transact(&command, rsp)
+                .map_err(Failure::Connection)?
+                .len();
+            match read_u32(&rsp[..size], 6) {
+                Some(rc) if is_memory_warning(rc) && self.evict(rc, &pinned)? => {}
+                _ => break size,
+            }
+        };
+        if read_u32(&rsp[..size], 6) != Some(0) {
+            return Ok(size);
+        }
+
+        if attributes.contains(TpmaCc::R_HANDLE) {
+            if let Some(handle) = read_u32(&rsp[..size], HEADER_SIZE) {
+                let entity = Entity {
+                    context: Context::Loaded(handle),
+                    last_used: self.clock,
+                };
+                if TpmHc::is_transient(handle) {
+                    let virtual_handle = self.allocate_handle();
+                    self.objects.insert(virtual_handle, entity);
+                    write_u32(rsp, HEADER_SIZE, virtual_handle);
+                } else if is_session(handle) {
+                    self.sessions.insert(handle, entity);
+                }
+            }
+        }
+        if attributes.contains(TpmaCc::FLUSHED) {
+            for handle in &handles {
+                self.objects.remove(handle);
+            }
+        }
+        for session in sessions.iter().filter(|session| !session.continue_session) {
+            self.sessions.remove(&session.handle);
+        }
+        Ok(size)
+    }
+}
+
+/// Writes a response without parameters and with `rc` to `rsp` and returns its size.
+fn write_response<E: From<TssError>>(rsp: &mut [u8], rc: u32) -> Result<usize, E> {
+    let response = rsp
+        .get_mut(..HEADER_SIZE)
+        .ok_or(TssError::from(TpmRcError::Size))?;
+    response[..2].copy_from_slice(&TpmSt::NoSessions.0.to_be_bytes());
+    write_u32(response, 2, HEADER_SIZE as u32);
+    write_u32(response, 6, rc);
+    Ok(HEADER_SIZE)
+}
+
+impl<C: Connection<Error: From<TssError>>> Connection for ResourceManager<C> {
+    type Error = C::Error;
+    fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> Result<&'a mut [u8], C::Error> {
+        let size = match self.run(cmd, rsp) {
+            Ok(size) => size,
+            Err(Failure::Connection(error)) => return Err(error),
+            // Errors of the resource manager are reported like errors of the TPM.
+            Err(Failure::Tpm(rc)) => write_response(rsp, rc)?,
+        };
+        Ok(&mut rsp[..size])
+    }
+    fn locality(&self) -> u8 {
+        self.connection.locality()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,328 @@
+use std::collections::BTreeSet;
+
+use tpm2_rs_base::commands::GetCapabilityResp;
+use tpm2_rs_base::errors::TssResult;
+use tpm2_rs_base::TpmlCca;
+
+use super::*;
+
+const TPM_RH_NULL: u32 = 0x4000_0007;
+const FIRST_OBJECT: u32 = 0x8000_0000;
+const FIRST_SESSION: u32 = 0x0200_0000;
+
+/// The tags of the contexts that [`SlotTpm`] saves.
+const OBJECT_CONTEXT: u8 = 0xAA;
+const SESSION_CONTEXT: u8 = 0x55;
+
+fn attributes() -> Vec<TpmaCc> {
+    [
+        (TpmCc::SequenceComplete, 1, TpmaCc::FLUSHED),
+        (TpmCc::ContextLoad, 0, TpmaCc::R_HANDLE),
+        (TpmCc::ContextSave, 1, TpmaCc::empty()),
+        (TpmCc::FlushContext, 0, TpmaCc::empty()),
+        (TpmCc::LoadExternal, 0, TpmaCc::R_HANDLE),
+        (TpmCc::ReadPublic, 1, TpmaCc::empty()),
+        (TpmCc::StartAuthSession, 2, TpmaCc::R_HANDLE),
+        (TpmCc::GetCapability, 0, TpmaCc::empty()),
+    ]
+    .map(|(code, handles, flags)| {
+        TpmaCc::command_index(code.0 as u16) | TpmaCc::c_handles(handles) | flags
+    })
+    .into()
+}
+
+/// A TPM with room for two objects and one session. Its objects hold a `u32` that `ReadPublic`
+/// returns.
+#[derive(Default)]
+struct SlotTpm {
+    objects: BTreeMap<u32, u32>,
+    sessions: BTreeSet<u32>,
+    next_session: u32,
+    commands: Vec<TpmCc>,
+}
+
+impl SlotTpm {
+    const OBJECT_SLOTS: u32 = 2;
+    const SESSION_SLOTS: usize = 1;
+
+    fn load_object(&mut self, value: u32) -> Result<u32, TpmRcError> {
+        let handle = (FIRST_OBJECT..FIRST_OBJECT + Self::OBJECT_SLOTS)
+            .find(|handle| !self.objects.contains_key(handle))
+            .ok_or(TpmRcError::ObjectMemory)?;
+        self.objects.insert(handle, value);
+        Ok(handle)
+    }
+
+    fn load_session(&mut self, handle: u32) -> Result<u32, TpmRcError> {
+        if self.sessions.len() == Self::SESSION_SLOTS {
+            return Err(TpmRcError::SessionMemory);
+        }
+        self.sessions.insert(handle);
+        Ok(handle)
+    }
+
+    /// Returns the response parameters to `command`.
+    fn execute(&mut self, code: TpmCc, command: &[u8]) -> Result<Vec<u8>, TpmRcError> {
+        let handle = read_u32(command, HEADER_SIZE).unwrap_or(0);
+        let handle_error = TpmRcError::HandleFor(ErrorType::Handle, ErrorPosition::Pos1);
+        match code {
+            TpmCc::GetCapability => {
+                let property = read_u32(command, HEADER_SIZE + 4).unwrap();
+                let count = read_u32(command, HEADER_SIZE + 8).unwrap();
+                // Report the commands three at a time.
+                let all = attributes();
+                let remaining: Vec<TpmaCc> = all
+                    .into_iter()
+                    .filter(|attributes| attributes.get_command_index() as u32 >= property)
+                    .collect();
+                let reported = &remaining[..remaining.len().min(count as usize).min(3)];
+                let response = GetCapabilityResp {
+                    more_data: if reported.len() < remaining.len() {
+                        TpmiYesNo::YES
+                    } else {
+                        TpmiYesNo::NO
+                    },
+                    capability_data: TpmsCapabilityData::Command(TpmlCca::new(reported).unwrap()),
+                };
+                let mut buffer = vec![0; 1024];
+                let size = response.try_marshal(&mut buffer).unwrap();
+                buffer.truncate(size);
+                Ok(buffer)
+            }
+            TpmCc::LoadExternal => Ok(self.load_object(handle)?.to_be_bytes().into()),
+            TpmCc::StartAuthSession => {
+                let session = self.load_session(FIRST_SESSION + self.next_session)?;
+                self.next_session += 1;
+                Ok(session.to_be_bytes().into())
+            }
+            TpmCc::ReadPublic => {
+                let value = *self.objects.get(&handle).ok_or(handle_error)?;
+                let sessions = auth_sessions(command, HEADER_SIZE + HANDLE_SIZE).unwrap();
+                for session in sessions {
+                    if !self.sessions.contains(&session.handle) {
+                        return Err(TpmRcError::ReferenceS0);
+                    }
+                    if !session.continue_session {
+                        self.sessions.remove(&session.handle);
+                    }
+                }
+                Ok(value.to_be_bytes().into())
+            }
+            TpmCc::SequenceComplete => {
+                self.objects.remove(&handle).ok_or(handle_error)?;
+                Ok(Vec::new())
+            }
+            TpmCc::ContextSave => {
+                let (tag, value) = if let Some(value) = self.objects.get(&handle) {
+                    (OBJECT_CONTEXT, *value)
+                } else if self.sessions.remove(&handle) {
+                    (SESSION_CONTEXT, handle)
+                } else {
+                    return Err(handle_error);
+                };
+                let mut context = vec![tag];
+                context.extend_from_slice(&value.to_be_bytes());
+                Ok(context)
+            }
+            TpmCc::ContextLoad => {
+                let value = read_u32(command, HEADER_SIZE + 1).unwrap();
+                let handle = match command[HEADER_SIZE] {
+                    OBJECT_CONTEXT => self.load_object(value)?,
+                    _ => self.load_session(value)?,
+                };
+                Ok(handle.to_be_bytes().into())
+            }
+            TpmCc::FlushContext => {
+                if self.objects.remove(&handle).is_none() && !self.sessions.remove(&handle) {
+                    return Err(TpmRcError::HandleFor(
+                        ErrorType::Parameter,
+                        ErrorPosition::Pos1,
+                    ));
+                }
+                Ok(Vec::new())
+            }
+            _ => Err(TpmRcError::CommandCode),
+        }
+    }
+}
+
+impl Connection for SlotTpm {
+    type Error = TssError;
+    fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> TssResult<&'a mut [u8]> {
+        let code = TpmCc(read_u32(cmd, 6).unwrap());
+        self.commands.push(code);
+        let (rc, parameters) = match self.execute(code, cmd) {
+            Ok(parameters) => (0, parameters),
+            Err(rc) => (rc.get(), Vec::new()),
+        };
+        let size = HEADER_SIZE + parameters.len();
+        write_response::<TssError>(rsp, rc)?;
+        write_u32(rsp, 2, size as u32);
+        rsp[HEADER_SIZE..size].copy_from_slice(&parameters);
+        Ok(&mut rsp[..size])
+    }
+}
+
+/// Returns a command with `handles`, sessions with handles and `continueSession` from
+/// `sessions`, and `parameters`.
+fn command(code: TpmCc, handles: &[u32], sessions: &[(u32, bool)], parameters: &[u8]) -> Vec<u8> {
+    let tag = if sessions.is_empty() {
+        TpmSt::NoSessions
+    } else {
+        TpmSt::Sessions
+    };
+    let mut command = vec![0; HEADER_SIZE];
+    command[..2].copy_from_slice(&tag.0.to_be_bytes());
+    write_u32(&mut command, 6, code.0);
+    for handle in handles {
+        command.extend_from_slice(&handle.to_be_bytes());
+    }
+    if !sessions.is_empty() {
+        let auth_size = sessions.len() as u32 * 9;
+        command.extend_from_slice(&auth_size.to_be_bytes());
+        for &(handle, continue_session) in sessions {
+            command.extend_from_slice(&handle.to_be_bytes());
+            command.extend_from_slice(&[0, 0, continue_session as u8, 0, 0]);
+        }
+    }
+    command.extend_from_slice(parameters);
+    let size = command.len() as u32;
+    write_u32(&mut command, 2, size);
+    command
+}
+
+/// Sends `command` and returns the response code and the rest of the response.
+fn send(tpm: &mut ResourceManager<SlotTpm>, command: &[u8]) -> (u32, Vec<u8>) {
+    let mut response = [0; 256];
+    let response = tpm.transact(command, &mut response).unwrap();
+    let size = read_u32(response, 2).unwrap() as usize;
+    assert_eq!(size, response.len());
+    (
+        read_u32(response, 6).unwrap(),
+        response[HEADER_SIZE..].to_vec(),
+    )
+}
+
+fn load_object(tpm: &mut ResourceManager<SlotTpm>, value: u32) -> u32 {
+    let (rc, handle) = send(
+        tpm,
+        &command(TpmCc::LoadExternal, &[], &[], &value.to_be_bytes()),
+    );
+    assert_eq!(rc, 0);
+    read_u32(&handle, 0).unwrap()
+}
+
+fn read_object(tpm: &mut ResourceManager<SlotTpm>, handle: u32, sessions: &[(u32, bool)]) -> u32 {
+    let (rc, value) = send(tpm, &command(TpmCc::ReadPublic, &[handle], sessions, &[]));
+    assert_eq!(rc, 0);
+    read_u32(&value, 0).unwrap()
+}
+
+fn start_session(tpm: &mut ResourceManager<SlotTpm>) -> u32 {
+    let handles = [TPM_RH_NULL; 2];
+    let (rc, handle) = send(tpm, &command(TpmCc::StartAuthSession, &handles, &[], &[]));
+    assert_eq!(rc, 0);
+    read_u32(&handle, 0).unwrap()
+}
+
+fn resource_manager() -> ResourceManager<SlotTpm> {
+    ResourceManager::with_attributes(SlotTpm::default(), attributes())
+}
+
+#[test]
+fn test_queries_command_attributes() {
+    let mut tpm = ResourceManager::new(SlotTpm::default()).unwrap();
+    assert_eq!(tpm.attributes, attributes());
+    let value = 0x1234;
+    let handle = load_object(&mut tpm, value);
+    assert_eq!(read_object(&mut tpm, handle, &[]), value);
+}
+
+#[test]
+fn test_unknown_command() {
+    let mut tpm = resource_manager();
+    let (rc, _) = send(&mut tpm, &command(TpmCc::GetRandom, &[], &[], &[0, 8]));
+    assert_eq!(rc, TpmRcError::CommandCode.get());
+    assert!(tpm.into_inner().commands.is_empty());
+}
+
+#[test]
+fn test_swaps_objects() {
+    let mut tpm = resource_manager();
+    let handles: Vec<u32> = (0..5).map(|value| load_object(&mut tpm, value)).collect();
+    for (value, &handle) in handles
+        .iter()
+        .enumerate()
+        .chain(handles.iter().enumerate().rev())
+    {
+        assert!(handle >= FIRST_VIRTUAL_HANDLE);
+        assert_eq!(read_object(&mut tpm, handle, &[]), value as u32);
+    }
+    let unknown = TpmRcError::HandleFor(ErrorType::Handle, ErrorPosition::Pos1);
+    let (rc, _) = send(
+        &mut tpm,
+        &command(TpmCc::ReadPublic, &[FIRST_OBJECT], &[], &[]),
+    );
+    assert_eq!(rc, unknown.get());
+
+    let slots = tpm.into_inner();
+    assert_eq!(slots.objects.len(), SlotTpm::OBJECT_SLOTS as usize);
+    assert!(slots.commands.contains(&TpmCc::ContextSave));
+    assert!(slots.commands.contains(&TpmCc::ContextLoad));
+}
+
+#[test]
+fn test_flushes_objects() {
+    let mut tpm = resource_manager();
+    let handles: Vec<u32> = (0..3).map(|value| load_object(&mut tpm, value)).collect();
+    // The first object was saved to make room for the last one, so only the resource manager
+    // knows it.
+    for handle in handles {
+        let (rc, _) = send(
+            &mut tpm,
+            &command(TpmCc::FlushContext, &[], &[], &handle.to_be_bytes()),
+        );
+        assert_eq!(rc, 0);
+        let (rc, _) = send(&mut tpm, &command(TpmCc::ReadPublic, &[handle], &[], &[]));
+        assert_eq!(
+            rc,
+            TpmRcError::HandleFor(ErrorType::Handle, ErrorPosition::Pos1).get()
+        );
+    }
+    let slots = tpm.into_inner();
+    assert!(slots.objects.is_empty());
+    let flushes = slots
+        .commands
+        .iter()
+        .filter(|&&code| code == TpmCc::FlushContext);
+    // One flush swapped out the first object.
+    assert_eq!(flushes.count(), 3);
+}
+
+#[test]
+fn test_forgets_flushed_objects() {
+    let mut tpm = resource_manager();
+    let handle = load_object(&mut tpm, 1);
+    let (rc, _) = send(
+        &mut tpm,
+        &command(TpmCc::SequenceComplete, &[handle], &[], &[]),
+    );
+    assert_eq!(rc, 0);
+    assert!(tpm.objects.is_empty());
+}
+
+#[test]
+fn test_swaps_sessions() {
+    let mut tpm = resource_manager();
+    let object = load_object(&mut tpm, 7);
+    let first = start_session(&mut tpm);
+    let second = start_session(&mut tpm);
+    assert_ne!(first, second);
+    for session in [first, second, first] {
+        assert_eq!(read_object(&mut tpm, object, &[(session, true)]), 7);
+    }
+    // Without continueSession, the TPM flushes the session after the command.
+    assert_eq!(read_object(&mut tpm, object, &[(second, false)]), 7);
+    assert!(!tpm.sessions.contains_key(&second));
+    assert_eq!(read_object(&mut tpm, object, &[(first, true)]), 7);
+}
@@ -0,0 +1,126 @@
+//! Resubmitting commands that the TPM asks to retry.
+//!
+//! This module provides the [`RetryConnection`] struct, which wraps another
+//! [`Connection`] and resubmits a command whenever the TPM responds with one of
+//! the [`RETRY_WARNINGS`], waiting in between as its [`RetryPolicy`] says.
+
+use core::time::Duration;
+
+use tpm2_rs_base::errors::TpmRcError;
+
+use crate::connection::Connection;
+
+/// The warnings after which the TPM may accept the same command later.
+pub const RETRY_WARNINGS: [TpmRcError; 4] = [
+    TpmRcError::Yielded,
+    TpmRcError::Testing,
+    TpmRcError::NvRate,
+    TpmRcError::Retry,
+];
+
+/// The size of the tag, size and response code at the start of each response.
+const RESPONSE_HEADER_SIZE: usize = 10;
+
+/// Decides whether and when a [`RetryConnection`] resubmits a command.
+pub trait RetryPolicy {
+    /// Called after the TPM responded to the `attempt`-th submission of a command, counting from
+    /// 1, with the warning `rc`.
+    ///
+    /// Returns `true` once the command should be resubmitted, or `false` to return the warning to
+    /// the caller.
+    fn backoff(&mut self, rc: TpmRcError, attempt: u32) -> bool;
+}
+
+/// A [`RetryPolicy`] with a limited number of retries and an exponentially growing delay.
+///
+/// The delay starts at `initial_delay`, doubles after each retry of the same
+/// command and is capped at `max_delay`. The policy waits by calling `delay`,
+/// such as `std::thread::sleep`, so it does not depend on `std`.
+#[derive(Clone, Debug)]
+pub struct Backoff<D: FnMut(Duration)> {
+    max_retries: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+    delay: D,
+}
+
+impl<D: FnMut(Duration)> Backoff<D> {
+    /// Creates a policy that retries a command up to `max_retries` times.
+    pub fn new(max_retries: u32, initial_delay: Duration, max_delay: Duration, delay: D) -> Self {
+        Backoff {
+            max_retries,
+            initial_delay,
+            max_delay,
+            delay,
+        }
+    }
+}
+
+impl<D: FnMut(Duration)> RetryPolicy for Backoff<D> {
+    fn backoff(&mut self, _: TpmRcError, attempt: u32) -> bool {
+        if attempt > self.max_retries {
+            return false;
+        }
+        let delay = 1u32
+            .checked_shl(attempt - 1)
+            .and_then(|factor| self.initial_delay.checked_mul(factor))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        (self.delay)(delay);
+        true
+    }
+}
+
+/// A connection that resubmits the same command when the TPM responds with one of the
+/// [`RETRY_WARNINGS`].
+///
+/// This struct implements the [`Connection`] trait. The command bytes are sent
+/// unchanged on each attempt, so commands with sessions are only resubmitted
+/// when the TPM did not consume their nonces, which is the case for these
+/// warnings.
+#[derive(Debug)]
+pub struct RetryConnection<C: Connection, P: RetryPolicy> {
+    connection: C,
+    policy: P,
+}
+
+impl<C: Connection, P: RetryPolicy> RetryConnection<C, P> {
+    /// Creates a connection that retries the commands sent over `connection` with `policy`.
+    pub fn new(connection: C, policy: P) -> Self {
+        RetryConnection { connection, policy }
+    }
+
+    /// Returns the wrapped connection.
+    pub fn into_inner(self) -> C {
+        self.connection
+    }
+}
+
+/// Returns the warning in `response` if the TPM may accept the command later.
+fn retry_warning(response: &[u8]) -> Option<TpmRcError> {
+    let rc = response.get(6..RESPONSE_HEADER_SIZE)?;
+    let rc = u32::from_be_bytes(rc.try_into().ok()?);
+    RETRY_WARNINGS
+        .into_iter()
+        .find(|warning| warning.get() == rc)
+}
+
+impl<C: Connection, P: RetryPolicy> Connection for RetryConnection<C, P> {
+    type Error = C::Error;
+    fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> Result<&'a mut [u8], C::Error> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let size = self.connection.transact(cmd, rsp)?.len();
+            match retry_warning(&rsp[..size]) {
+                Some(rc) if self.policy.backoff(rc, attempt) => continue,
+                _ => return Ok(&mut rsp[..size]),
+            }
+        }
+    }
+    fn locality(&self) -> u8 {
+        self.connection.locality()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,110 @@
+use core::cell::RefCell;
+
+use tpm2_rs_base::commands::GetRandomCmd;
+use tpm2_rs_base::errors::{TssError, TssResult};
+
+use super::*;
+use crate::run_command;
+
+/// A connection that responds to each command with the next response code of its script, or
+/// with an empty GetRandom response once the script runs out.
+struct ScriptedTpm<const N: usize> {
+    script: [u32; N],
+    commands: usize,
+    first_command: [u8; 64],
+}
+
+impl<const N: usize> ScriptedTpm<N> {
+    fn new(script: [u32; N]) -> Self {
+        ScriptedTpm {
+            script,
+            commands: 0,
+            first_command: [0; 64],
+        }
+    }
+}
+
+impl<const N: usize> Connection for ScriptedTpm<N> {
+    type Error = TssError;
+    fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> TssResult<&'a mut [u8]> {
+        if self.commands == 0 {
+            self.first_command[..cmd.len()].copy_from_slice(cmd);
+        } else {
+            // Each attempt resubmits the same bytes.
+            assert_eq!(&self.first_command[..cmd.len()], cmd);
+        }
+        let rc = self.script.get(self.commands).copied().unwrap_or(0);
+        self.commands += 1;
+        let response: &[u8] = if rc == 0 {
+            &[0x80, 0x01, 0, 0, 0, 12, 0, 0, 0, 0, 0, 0]
+        } else {
+            &[0x80, 0x01, 0, 0, 0, 10, 0, 0, 0, 0]
+        };
+        rsp[..response.len()].copy_from_slice(response);
+        rsp[6..10].copy_from_slice(&rc.to_be_bytes());
+        Ok(&mut rsp[..response.len()])
+    }
+}
+
+fn get_random<C: Connection<Error = TssError>>(tpm: &mut C) -> TssResult<()> {
+    run_command(&GetRandomCmd { bytes_requested: 0 }, tpm).map(|_| ())
+}
+
+#[test]
+fn test_retries_warnings_until_success() {
+    let delays = RefCell::new([Duration::ZERO; 8]);
+    let policy = Backoff::new(
+        8,
+        Duration::from_millis(10),
+        Duration::from_millis(30),
+        |delay| {
+            let mut delays = delays.borrow_mut();
+            let next = delays.iter().position(Duration::is_zero).unwrap();
+            delays[next] = delay;
+        },
+    );
+    let script = RETRY_WARNINGS.map(TpmRcError::get);
+    let mut tpm = RetryConnection::new(ScriptedTpm::new(script), policy);
+    assert_eq!(get_random(&mut tpm), Ok(()));
+    assert_eq!(tpm.into_inner().commands, 5);
+    assert_eq!(
+        &delays.borrow()[..5],
+        [10, 20, 30, 30, 0].map(Duration::from_millis)
+    );
+}
+
+#[test]
+fn test_gives_up_after_max_retries() {
+    let policy = Backoff::new(2, Duration::ZERO, Duration::ZERO, |_| {});
+    let script = [TpmRcError::Retry.get(); 4];
+    let mut tpm = RetryConnection::new(ScriptedTpm::new(script), policy);
+    assert_eq!(get_random(&mut tpm), Err(TpmRcError::Retry.into()));
+    assert_eq!(tpm.into_inner().commands, 3);
+}
+
+#[test]
+fn test_does_not_retry_errors() {
+    let policy = Backoff::new(2, Duration::ZERO, Duration::ZERO, |_| {});
+    for rc in [TpmRcError::Failure, TpmRcError::ObjectMemory] {
+        let mut tpm = RetryConnection::new(ScriptedTpm::new([rc.get()]), policy.clone());
+        assert_eq!(get_random(&mut tpm), Err(rc.into()));
+        assert_eq!(tpm.into_inner().commands, 1);
+    }
+}
+
+/// A policy that only retries the commands that the TPM could not start.
+struct RetryOnly;
+
+impl RetryPolicy for RetryOnly {
+    fn backoff(&mut self, rc: TpmRcError, _: u32) -> bool {
+        rc == TpmRcError::Retry
+    }
+}
+
+#[test]
+fn test_policy_chooses_warnings() {
+    let script = [TpmRcError::Retry.get(), TpmRcError::Testing.get()];
+    let mut tpm = RetryConnection::new(ScriptedTpm::new(script), RetryOnly);
+    assert_eq!(get_random(&mut tpm), Err(TpmRcError::Testing.into()));
+    assert_eq!(tpm.into_inner().commands, 2);
+}
@@ -1,9 +1,10 @@
-//! A connection to the TCG TPM 2.0 Simulator over TCP.
+//! A connection to the TCG TPM 2.0 Simulator over TCP or any other stream.
 //!
-//! This module provides the [`TcpConnection`] struct, which implements the
-//! [`Connection`] trait for communicating with a TPM simulator. It also
-//! provides a [`TcpSimulator`] struct that manages the lifetime of a TPM
-//! simulator process and a connection to it.
+//! This module provides the [`SimulatorConnection`] struct, which implements the
+//! [`Connection`] trait for communicating with a TPM simulator over any pair of
+//! streams, such as [`TcpConnection`] over TCP or [`UnixConnection`] over Unix
+//! domain sockets. It also provides a [`SimulatorProcess`] struct that manages
+//! the lifetime of a TPM simulator process and a connection to it.
 #![cfg(feature = "connection-tcp")]
 extern crate std;
 
@@ -11,7 +12,12 @@ use std::ffi::{OsStr, OsString};
 use std::format;
 use std::io::{Error, ErrorKind, IoSlice, Read, Result, Write};
 use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::Path;
 use std::process::{Child, Command};
+use std::vec::Vec;
 
 use zerocopy::network_endian::U32;
 use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
@@ -24,21 +30,28 @@ const SIMULATOR_DEFAULT_TPM_PORT: u16 = 2321;
 /// The default Platform port of the TPM simulator
 const SIMULATOR_DEFAULT_PLATFORM_PORT: u16 = 2322;
 
-/// A connection to a TPM over TCP, designed for use with the TCG TPM 2.0 Simulator.
+/// A connection to a TPM over a pair of streams, designed for use with the TCG TPM 2.0 Simulator.
 ///
 /// This struct implements the [`Connection`] trait.
 #[derive(Debug)]
-pub struct TcpConnection {
-    /// TCP connection to the TPM port of the TPM simulator
-    tpm_tcp: TcpStream,
+pub struct SimulatorConnection<S: Read + Write> {
+    /// Connection to the TPM port of the TPM simulator
+    tpm_stream: S,
 
-    /// TCP connection to the Platform port of the TPM simulator
-    plat_tcp: TcpStream,
+    /// Connection to the Platform port of the TPM simulator
+    plat_stream: S,
 
     /// The locality to use when sending commands to the TPM
     locality: u8,
 }
 
+/// A connection to a TPM simulator over TCP.
+pub type TcpConnection = SimulatorConnection<TcpStream>;
+
+/// A connection to a TPM simulator over Unix domain sockets.
+#[cfg(unix)]
+pub type UnixConnection = SimulatorConnection<UnixStream>;
+
 impl TcpConnection {
     /// Connects to an already running TPM simulator using the specified ports.
     ///
@@ -53,11 +66,10 @@ impl TcpConnection {
         let tpm_port = tpm_port.unwrap_or(SIMULATOR_DEFAULT_TPM_PORT);
         let plat_port = plat_port.unwrap_or(SIMULATOR_DEFAULT_PLATFORM_PORT);
 
-        Ok(TcpConnection {
-            tpm_tcp: TcpStream::connect((ip, tpm_port))?,
-            plat_tcp: TcpStream::connect((ip, plat_port))?,
-            locality: 0,
-        })
+        Ok(SimulatorConnection::new(
+            TcpStream::connect((ip, tpm_port))?,
+            TcpStream::connect((ip, plat_port))?,
+        ))
     }
 
     /// Connects to an already running TPM simulator with retries.
@@ -71,20 +83,83 @@ impl TcpConnection {
         tpm_port: Option<u16>,
         plat_port: Option<u16>,
     ) -> Result<TcpConnection> {
-        let mut attempts = 0;
-        let conn = loop {
-            attempts += 1;
-            match TcpConnection::connect(ip, tpm_port, plat_port) {
-                Ok(conn) => break conn,
-                Err(err) => {
-                    if attempts > 3 {
-                        return Err(err);
-                    }
-                    std::thread::sleep(std::time::Duration::from_secs(1));
+        retry_connect(|| TcpConnection::connect(ip, tpm_port, plat_port))
+    }
+}
+
+#[cfg(unix)]
+impl UnixConnection {
+    /// Connects to an already running TPM simulator listening on the Unix
+    /// domain sockets at `tpm_path` and `plat_path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection to the TPM or Platform socket fails.
+    pub fn connect<P: AsRef<Path>, Q: AsRef<Path>>(
+        tpm_path: P,
+        plat_path: Q,
+    ) -> Result<UnixConnection> {
+        Ok(SimulatorConnection::new(
+            UnixStream::connect(tpm_path)?,
+            UnixStream::connect(plat_path)?,
+        ))
+    }
+
+    /// Connects to an already running TPM simulator with retries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection to the TPM or Platform socket fails
+    /// after the number of retries has been exhausted.
+    pub fn connect_with_retries<P: AsRef<Path>, Q: AsRef<Path>>(
+        tpm_path: P,
+        plat_path: Q,
+    ) -> Result<UnixConnection> {
+        retry_connect(|| UnixConnection::connect(tpm_path.as_ref(), plat_path.as_ref()))
+    }
+}
+
+/// Calls `connect` until it succeeds, at most four times one second apart.
+fn retry_connect<T>(mut connect: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match connect() {
+            Ok(conn) => return Ok(conn),
+            Err(err) => {
+                if attempts > 3 {
+                    return Err(err);
                 }
+                std::thread::sleep(std::time::Duration::from_secs(1));
             }
-        };
-        Ok(conn)
+        }
+    }
+}
+
+/// Writes all of `parts` to `stream`, in a single write if the stream allows it.
+fn write_parts<S: Write>(stream: &mut S, parts: &[&[u8]]) -> Result<()> {
+    let slices: Vec<IoSlice> = parts.iter().map(|part| IoSlice::new(part)).collect();
+    let mut written = stream.write_vectored(&slices)?;
+    for part in parts {
+        if written >= part.len() {
+            written -= part.len();
+        } else {
+            stream.write_all(&part[written..])?;
+            written = 0;
+        }
+    }
+    Ok(())
+}
+
+impl<S: Read + Write> SimulatorConnection<S> {
+    /// Creates a connection over streams that are already connected to the
+    /// TPM and Platform ports of a TPM simulator, such as in-memory pipes.
+    pub fn new(tpm_stream: S, plat_stream: S) -> SimulatorConnection<S> {
+        SimulatorConnection {
+            tpm_stream,
+            plat_stream,
+            locality: 0,
+        }
     }
 
     /// Performs an H-CRTM Event Sequence against the TPM simulator.
@@ -96,31 +171,24 @@ impl TcpConnection {
     pub fn hcrtm_sequence(&mut self, data: &[u8]) -> Result<()> {
         // Send TPM_Hash_Start
         let cmd_code = U32::new(SimulatorTpmCommandCode::SignalHashStart as u32);
-        self.tpm_tcp.write_all(cmd_code.as_bytes())?;
-        Self::check_response_end(&mut self.tpm_tcp)?;
+        self.tpm_stream.write_all(cmd_code.as_bytes())?;
+        Self::check_response_end(&mut self.tpm_stream)?;
 
         // Send TPM_Hash_Data
         let cmd_code = U32::new(SimulatorTpmCommandCode::SignalHashData as u32);
         let cmd_hdr = &SignalHashDataRequestHeader {
             length: U32::new(data.len() as u32),
         };
-        let bytes_written = self.tpm_tcp.write_vectored(&[
-            IoSlice::new(cmd_code.as_bytes()),
-            IoSlice::new(cmd_hdr.as_bytes()),
-            IoSlice::new(data),
-        ])?;
-        if bytes_written != cmd_code.as_bytes().len() + cmd_hdr.as_bytes().len() + data.len() {
-            return Err(Error::new(
-                ErrorKind::WriteZero,
-                "failed to write entire command to TPM",
-            ));
-        }
-        Self::check_response_end(&mut self.tpm_tcp)?;
+        write_parts(
+            &mut self.tpm_stream,
+            &[cmd_code.as_bytes(), cmd_hdr.as_bytes(), data],
+        )?;
+        Self::check_response_end(&mut self.tpm_stream)?;
 
         // Send TPM_Hash_End
         let cmd_code = U32::new(SimulatorTpmCommandCode::SignalHashEnd as u32);
-        self.tpm_tcp.write_all(cmd_code.as_bytes())?;
-        Self::check_response_end(&mut self.tpm_tcp)?;
+        self.tpm_stream.write_all(cmd_code.as_bytes())?;
+        Self::check_response_end(&mut self.tpm_stream)?;
 
         Ok(())
     }
@@ -137,21 +205,15 @@ impl TcpConnection {
             client_version: U32::new(client_version),
         };
 
-        let bytes_written = self.tpm_tcp.write_vectored(&[
-            IoSlice::new(cmd_code.as_bytes()),
-            IoSlice::new(cmd_payload.as_bytes()),
-        ])?;
-        if bytes_written != cmd_code.as_bytes().len() + cmd_payload.as_bytes().len() {
-            return Err(Error::new(
-                ErrorKind::WriteZero,
-                "failed to write entire command to TPM",
-            ));
-        }
+        write_parts(
+            &mut self.tpm_stream,
+            &[cmd_code.as_bytes(), cmd_payload.as_bytes()],
+        )?;
 
         let mut resp: RemoteHandshakeResponse = RemoteHandshakeResponse::default();
-        self.tpm_tcp.read_exact(resp.as_mut_bytes())?;
+        self.tpm_stream.read_exact(resp.as_mut_bytes())?;
 
-        Self::check_response_end(&mut self.tpm_tcp)?;
+        Self::check_response_end(&mut self.tpm_stream)?;
 
         Ok(resp)
     }
@@ -168,18 +230,12 @@ impl TcpConnection {
             result: U32::new(result),
         };
 
-        let bytes_written = self.tpm_tcp.write_vectored(&[
-            IoSlice::new(cmd_code.as_bytes()),
-            IoSlice::new(cmd_payload.as_bytes()),
-        ])?;
-        if bytes_written != cmd_code.as_bytes().len() + cmd_payload.as_bytes().len() {
-            return Err(Error::new(
-                ErrorKind::WriteZero,
-                "failed to write entire command to TPM",
-            ));
-        }
+        write_parts(
+            &mut self.tpm_stream,
+            &[cmd_code.as_bytes(), cmd_payload.as_bytes()],
+        )?;
 
-        Self::check_response_end(&mut self.tpm_tcp)
+        Self::check_response_end(&mut self.tpm_stream)
     }
 
     /// Sends a signal to the Platform port of the TPM simulator.
@@ -190,9 +246,9 @@ impl TcpConnection {
     /// response terminator is invalid.
     pub fn platform_signal(&mut self, signal: SimulatorPlatformSignal) -> Result<()> {
         let cmd_code = U32::new(signal as u32);
-        self.plat_tcp.write_all(cmd_code.as_bytes())?;
+        self.plat_stream.write_all(cmd_code.as_bytes())?;
 
-        Self::check_response_end(&mut self.plat_tcp)
+        Self::check_response_end(&mut self.plat_stream)
     }
 
     /// Convenience function to reinitialize the TPM simulator, causing it to
@@ -217,9 +273,9 @@ impl TcpConnection {
     /// response terminator is invalid.
     pub fn test_failure_mode(&mut self) -> Result<()> {
         let cmd_code = U32::new(SimulatorPlatformCommandCode::TestFailureMode as u32);
-        self.plat_tcp.write_all(cmd_code.as_bytes())?;
+        self.plat_stream.write_all(cmd_code.as_bytes())?;
 
-        Self::check_response_end(&mut self.plat_tcp)
+        Self::check_response_end(&mut self.plat_stream)
     }
 
     /// Gets the largest command/response the simulator received. Once received,
@@ -231,12 +287,12 @@ impl TcpConnection {
     /// response terminator is invalid.
     pub fn get_command_response_sizes(&mut self) -> Result<GetCommandResponseSizesResponse> {
         let cmd_code = U32::new(SimulatorPlatformCommandCode::GetCommandResponseSizes as u32);
-        self.plat_tcp.write_all(cmd_code.as_bytes())?;
+        self.plat_stream.write_all(cmd_code.as_bytes())?;
 
         // The simulator sends the response as a variable-length byte array,
         // which is a `U32` length followed by the data.
         let mut length = U32::ZERO;
-        self.plat_tcp.read_exact(length.as_mut_bytes())?;
+        self.plat_stream.read_exact(length.as_mut_bytes())?;
         if length.get() as usize != std::mem::size_of::<GetCommandResponseSizesResponse>() {
             return Err(Error::new(
                 ErrorKind::InvalidData,
@@ -245,9 +301,9 @@ impl TcpConnection {
         }
 
         let mut resp: GetCommandResponseSizesResponse = GetCommandResponseSizesResponse::default();
-        self.plat_tcp.read_exact(resp.as_mut_bytes())?;
+        self.plat_stream.read_exact(resp.as_mut_bytes())?;
 
-        Self::check_response_end(&mut self.plat_tcp)?;
+        Self::check_response_end(&mut self.plat_stream)?;
 
         Ok(resp)
     }
@@ -264,21 +320,15 @@ impl TcpConnection {
             act_handle: U32::new(act_handle),
         };
 
-        let bytes_written = self.plat_tcp.write_vectored(&[
-            IoSlice::new(cmd_code.as_bytes()),
-            IoSlice::new(cmd_payload.as_bytes()),
-        ])?;
-        if bytes_written != cmd_code.as_bytes().len() + cmd_payload.as_bytes().len() {
-            return Err(Error::new(
-                ErrorKind::WriteZero,
-                "failed to write entire command to TPM",
-            ));
-        }
+        write_parts(
+            &mut self.plat_stream,
+            &[cmd_code.as_bytes(), cmd_payload.as_bytes()],
+        )?;
 
         let mut resp: ActGetSignaledResponse = ActGetSignaledResponse::default();
-        self.plat_tcp.read_exact(resp.as_mut_bytes())?;
+        self.plat_stream.read_exact(resp.as_mut_bytes())?;
 
-        Self::check_response_end(&mut self.plat_tcp)?;
+        Self::check_response_end(&mut self.plat_stream)?;
 
         Ok(u32::from(resp.signaled))
     }
@@ -295,18 +345,12 @@ impl TcpConnection {
             hash: U32::new(hash),
         };
 
-        let bytes_written = self.plat_tcp.write_vectored(&[
-            IoSlice::new(cmd_code.as_bytes()),
-            IoSlice::new(cmd_payload.as_bytes()),
-        ])?;
-        if bytes_written != cmd_code.as_bytes().len() + cmd_payload.as_bytes().len() {
-            return Err(Error::new(
-                ErrorKind::WriteZero,
-                "failed to write entire command to TPM",
-            ));
-        }
+        write_parts(
+            &mut self.plat_stream,
+            &[cmd_code.as_bytes(), cmd_payload.as_bytes()],
+        )?;
 
-        Self::check_response_end(&mut self.plat_tcp)
+        Self::check_response_end(&mut self.plat_stream)
     }
 
     /// Sets the firmware SVN of the TPM.
@@ -319,18 +363,12 @@ impl TcpConnection {
         let cmd_code = U32::new(SimulatorPlatformCommandCode::SetFirmwareSvn as u32);
         let cmd_payload = &SetFirmwareSvnRequest { svn: U32::new(svn) };
 
-        let bytes_written = self.plat_tcp.write_vectored(&[
-            IoSlice::new(cmd_code.as_bytes()),
-            IoSlice::new(cmd_payload.as_bytes()),
-        ])?;
-        if bytes_written != cmd_code.as_bytes().len() + cmd_payload.as_bytes().len() {
-            return Err(Error::new(
-                ErrorKind::WriteZero,
-                "failed to write entire command to TPM",
-            ));
-        }
+        write_parts(
+            &mut self.plat_stream,
+            &[cmd_code.as_bytes(), cmd_payload.as_bytes()],
+        )?;
 
-        Self::check_response_end(&mut self.plat_tcp)
+        Self::check_response_end(&mut self.plat_stream)
     }
 
     /// Sets the locality used when issuing subsequent commands.
@@ -346,12 +384,10 @@ impl TcpConnection {
     /// Returns an error if the communication with the TPM or Platform port fails.
     pub fn session_end(&mut self) -> Result<()> {
         let cmd_code = U32::new(SimulatorTpmCommandCode::SessionEnd as u32);
-        self.tpm_tcp.write_all(cmd_code.as_bytes())?;
-        self.tpm_tcp.shutdown(std::net::Shutdown::Both)?;
+        self.tpm_stream.write_all(cmd_code.as_bytes())?;
 
         let cmd_code = U32::new(SimulatorPlatformCommandCode::SessionEnd as u32);
-        self.plat_tcp.write_all(cmd_code.as_bytes())?;
-        self.plat_tcp.shutdown(std::net::Shutdown::Both)?;
+        self.plat_stream.write_all(cmd_code.as_bytes())?;
 
         Ok(())
     }
@@ -363,18 +399,16 @@ impl TcpConnection {
     /// Returns an error if the communication with the TPM or Platform port fails.
     pub fn stop_simulator(&mut self) -> Result<()> {
         let cmd_code = U32::new(SimulatorTpmCommandCode::Stop as u32);
-        self.tpm_tcp.write_all(cmd_code.as_bytes())?;
-        self.tpm_tcp.shutdown(std::net::Shutdown::Both)?;
+        self.tpm_stream.write_all(cmd_code.as_bytes())?;
 
         let cmd_code = U32::new(SimulatorPlatformCommandCode::Stop as u32);
-        self.plat_tcp.write_all(cmd_code.as_bytes())?;
-        self.plat_tcp.shutdown(std::net::Shutdown::Both)?;
+        self.plat_stream.write_all(cmd_code.as_bytes())?;
 
         Ok(())
     }
 
     /// Reads the trailing zero from the TPM simulator response.
-    fn check_response_end(stream: &mut TcpStream) -> Result<()> {
+    fn check_response_end(stream: &mut S) -> Result<()> {
         let mut resp_end = U32::ZERO;
         stream.read_exact(resp_end.as_mut_bytes())?;
         if resp_end != U32::ZERO {
@@ -387,7 +421,7 @@ impl TcpConnection {
     }
 }
 
-impl Connection for TcpConnection {
+impl<S: Read + Write> Connection for SimulatorConnection<S> {
     type Error = std::io::Error;
     fn transact<'a>(&mut self, command: &[u8], response: &'a mut [u8]) -> Result<&'a mut [u8]> {
         let cmd_size: u32 = command
@@ -401,33 +435,29 @@ impl Connection for TcpConnection {
             length: U32::new(cmd_size),
         };
 
-        let bytes_written = self.tpm_tcp.write_vectored(&[
-            IoSlice::new(cmd_code.as_bytes()),
-            IoSlice::new(cmd_hdr.as_bytes()),
-            IoSlice::new(command),
-        ])?;
-        if bytes_written != cmd_code.as_bytes().len() + cmd_hdr.as_bytes().len() + command.len() {
-            return Err(Error::new(
-                ErrorKind::WriteZero,
-                "failed to write entire command to TPM",
-            ));
-        };
+        write_parts(
+            &mut self.tpm_stream,
+            &[cmd_code.as_bytes(), cmd_hdr.as_bytes(), command],
+        )?;
 
         let mut resp_hdr: SendCommandResponseHeader = SendCommandResponseHeader::default();
-        self.tpm_tcp.read_exact(resp_hdr.as_mut_bytes())?;
+        self.tpm_stream.read_exact(resp_hdr.as_mut_bytes())?;
         if resp_hdr.length.get() as usize > response.len() {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
                 "response buffer too small",
             ));
         }
-        self.tpm_tcp
+        self.tpm_stream
             .read_exact(&mut response[..resp_hdr.length.get() as usize])?;
 
         // The TPM simulator completes each command with a u32 `0`.
-        Self::check_response_end(&mut self.tpm_tcp)?;
+        Self::check_response_end(&mut self.tpm_stream)?;
         Ok(&mut response[..resp_hdr.length.get() as usize])
     }
+    fn locality(&self) -> u8 {
+        self.locality
+    }
 }
 
 /// A command that can be sent to the TPM port of the TPM simulator
@@ -620,11 +650,18 @@ struct SetFirmwareSvnRequest {
 
 /// Structure to manage the subprocess used to spawn the TPM simulator.
 #[derive(Debug)]
-pub struct TcpSimulator {
+pub struct SimulatorProcess<S: Read + Write> {
     child: Child,
-    conn: TcpConnection,
+    conn: SimulatorConnection<S>,
 }
 
+/// A TPM simulator process that is connected to over TCP.
+pub type TcpSimulator = SimulatorProcess<TcpStream>;
+
+/// A TPM simulator process that is connected to over Unix domain sockets.
+#[cfg(unix)]
+pub type UnixSimulator = SimulatorProcess<UnixStream>;
+
 impl TcpSimulator {
     /// Starts the TPM simulator binary with the given arguments and connects to it.
     pub fn new<B: AsRef<OsStr>, A: AsRef<OsStr>>(
@@ -632,13 +669,43 @@ impl TcpSimulator {
         args: &[A],
         ip: &str,
     ) -> Result<TcpSimulator> {
+        SimulatorProcess::spawn(bin, args, || {
+            TcpConnection::connect_with_retries(ip, None, None)
+        })
+    }
+}
+
+#[cfg(unix)]
+impl UnixSimulator {
+    /// Starts the TPM simulator binary with the given arguments and connects to it on the Unix
+    /// domain sockets at `tpm_path` and `plat_path`, which the arguments usually name.
+    pub fn new<B: AsRef<OsStr>, A: AsRef<OsStr>, P: AsRef<Path>, Q: AsRef<Path>>(
+        bin: B,
+        args: &[A],
+        tpm_path: P,
+        plat_path: Q,
+    ) -> Result<UnixSimulator> {
+        SimulatorProcess::spawn(bin, args, || {
+            UnixConnection::connect_with_retries(tpm_path.as_ref(), plat_path.as_ref())
+        })
+    }
+}
+
+impl<S: Read + Write> SimulatorProcess<S> {
+    /// Starts the TPM simulator binary with the given arguments and connects to it with
+    /// `connect`. The simulator is stopped if the connection fails.
+    pub fn spawn<B: AsRef<OsStr>, A: AsRef<OsStr>>(
+        bin: B,
+        args: &[A],
+        connect: impl FnOnce() -> Result<SimulatorConnection<S>>,
+    ) -> Result<SimulatorProcess<S>> {
         let mut command = Command::new(bin.as_ref());
         command.current_dir("/");
         if !args.is_empty() {
             command.args(args);
         }
 
-        let child = command.spawn().map_err(|e| {
+        let mut child = command.spawn().map_err(|e| {
             let argstr = if !args.is_empty() {
                 // Ideally this would just be `args.join(" ")`, but the join
                 // impl for OsStr is a permanent unstable feature, see
@@ -653,37 +720,44 @@ impl TcpSimulator {
                 OsString::new()
             };
             Error::other(format!(
-                "failed to start TCP simulator \"{}{}\": {e}",
+                "failed to start simulator \"{}{}\": {e}",
                 bin.as_ref().to_string_lossy(),
                 argstr.to_string_lossy()
             ))
         })?;
 
-        let conn = TcpConnection::connect_with_retries(ip, None, None)?;
-
-        Ok(TcpSimulator { child, conn })
+        match connect() {
+            Ok(conn) => Ok(SimulatorProcess { child, conn }),
+            Err(e) => {
+                _ = child.kill();
+                Err(e)
+            }
+        }
     }
 
     /// Stops the TPM simulator process.
     pub fn stop(&mut self) -> Result<()> {
         self.child
             .kill()
-            .map_err(|e| Error::other(format!("failed to stop TCP simulator: {e}")))
+            .map_err(|e| Error::other(format!("failed to stop simulator: {e}")))
     }
 
-    /// Returns a reference to the underlying TCP connection.
-    pub fn connection(&self) -> &TcpConnection {
+    /// Returns a reference to the underlying connection.
+    pub fn connection(&self) -> &SimulatorConnection<S> {
         &self.conn
     }
 
-    /// Returns a mutable reference to the underlying TCP connection.
-    pub fn connection_mut(&mut self) -> &mut TcpConnection {
+    /// Returns a mutable reference to the underlying connection.
+    pub fn connection_mut(&mut self) -> &mut SimulatorConnection<S> {
         &mut self.conn
     }
 }
 
-impl Drop for TcpSimulator {
+impl<S: Read + Write> Drop for SimulatorProcess<S> {
     fn drop(&mut self) {
         _ = self.stop();
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,145 @@
+use std::os::unix::net::UnixListener;
+use std::thread;
+use std::vec;
+use std::vec::Vec;
+
+use super::*;
+
+const COMMAND: [u8; 12] = [
+    0x80, 0x01, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x01, 0x7B, 0x00, 0x04,
+];
+const RESPONSE: [u8; 10] = [0x80, 0x01, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00];
+
+fn read_u32(stream: &mut UnixStream) -> u32 {
+    let mut bytes = [0; 4];
+    stream.read_exact(&mut bytes).unwrap();
+    u32::from_be_bytes(bytes)
+}
+
+/// Plays the TPM port of a simulator on `stream`: expects `COMMAND` at `locality` and responds
+/// with `RESPONSE`.
+fn serve_command(mut stream: UnixStream, locality: u8) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        assert_eq!(
+            read_u32(&mut stream),
+            SimulatorTpmCommandCode::SendCommand as u32
+        );
+        let mut received = [0];
+        stream.read_exact(&mut received).unwrap();
+        assert_eq!(received[0], locality);
+        let mut command = vec![0; read_u32(&mut stream) as usize];
+        stream.read_exact(&mut command).unwrap();
+        assert_eq!(command, COMMAND);
+        stream
+            .write_all(&(RESPONSE.len() as u32).to_be_bytes())
+            .unwrap();
+        stream.write_all(&RESPONSE).unwrap();
+        stream.write_all(&[0; 4]).unwrap();
+    })
+}
+
+/// Plays the Platform port of a simulator on `stream`: acknowledges each signal and returns them
+/// once the client ends the session.
+fn serve_signals(mut stream: UnixStream) -> thread::JoinHandle<Vec<u32>> {
+    thread::spawn(move || {
+        let mut signals = Vec::new();
+        loop {
+            match read_u32(&mut stream) {
+                code if code == SimulatorPlatformCommandCode::SessionEnd as u32 => return signals,
+                code => signals.push(code),
+            }
+            stream.write_all(&[0; 4]).unwrap();
+        }
+    })
+}
+
+#[test]
+fn test_transact_over_stream_pair() {
+    let (tpm, tpm_simulator) = UnixStream::pair().unwrap();
+    let (plat, _plat_simulator) = UnixStream::pair().unwrap();
+    let mut conn = SimulatorConnection::new(tpm, plat);
+    conn.set_locality(3);
+    let simulator = serve_command(tpm_simulator, 3);
+
+    let mut response = [0; 64];
+    assert_eq!(conn.transact(&COMMAND, &mut response).unwrap(), RESPONSE);
+    simulator.join().unwrap();
+}
+
+#[test]
+fn test_transact_response_buffer_too_small() {
+    let (tpm, tpm_simulator) = UnixStream::pair().unwrap();
+    let (plat, _plat_simulator) = UnixStream::pair().unwrap();
+    let mut conn = SimulatorConnection::new(tpm, plat);
+    let simulator = serve_command(tpm_simulator, 0);
+
+    let mut response = [0; 8];
+    assert_eq!(
+        conn.transact(&COMMAND, &mut response).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+    simulator.join().unwrap();
+}
+
+#[test]
+fn test_platform_signals_over_unix_sockets() {
+    let dir = std::env::temp_dir().join(format!("tpm2-rs-sim-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let tpm_path = dir.join("tpm");
+    let plat_path = dir.join("platform");
+    let _ = std::fs::remove_file(&tpm_path);
+    let _ = std::fs::remove_file(&plat_path);
+    let tpm_listener = UnixListener::bind(&tpm_path).unwrap();
+    let plat_listener = UnixListener::bind(&plat_path).unwrap();
+
+    let mut conn = UnixConnection::connect(&tpm_path, &plat_path).unwrap();
+    let (_tpm_simulator, _) = tpm_listener.accept().unwrap();
+    let (plat_simulator, _) = plat_listener.accept().unwrap();
+    let simulator = serve_signals(plat_simulator);
+
+    conn.reinit().unwrap();
+    conn.platform_signal(SimulatorPlatformSignal::PhysicalPresenceOn)
+        .unwrap();
+    conn.session_end().unwrap();
+    assert_eq!(
+        simulator.join().unwrap(),
+        [
+            SimulatorPlatformSignal::NvOff as u32,
+            SimulatorPlatformSignal::PowerOff as u32,
+            SimulatorPlatformSignal::PowerOn as u32,
+            SimulatorPlatformSignal::NvOn as u32,
+            SimulatorPlatformSignal::PhysicalPresenceOn as u32,
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// A stream that accepts at most one byte per write, as some pipes and serial links do.
+struct OneByteWrites(UnixStream);
+
+impl Read for OneByteWrites {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for OneByteWrites {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.0.write(&buf[..buf.len().min(1)])
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.0.flush()
+    }
+}
+
+#[test]
+fn test_transact_over_short_writes() {
+    let (tpm, tpm_simulator) = UnixStream::pair().unwrap();
+    let (plat, _plat_simulator) = UnixStream::pair().unwrap();
+    let mut conn = SimulatorConnection::new(OneByteWrites(tpm), OneByteWrites(plat));
+    let simulator = serve_command(tpm_simulator, 0);
+
+    let mut response = [0; 64];
+    assert_eq!(conn.transact(&COMMAND, &mut response).unwrap(), RESPONSE);
+    simulator.join().unwrap();
+}
@@ -0,0 +1,185 @@
+//! An async adapter for blocking connections.
+//!
+//! This module provides the [`WorkerConnection`] struct, which implements the
+//! [`AsyncConnection`] trait for any blocking [`Connection`], such as a
+//! [`TcpConnection`](crate::connection::TcpConnection), by running it on a
+//! dedicated thread. The I/O of the connection itself stays blocking.
+extern crate std;
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use std::io::{Error, ErrorKind};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::vec;
+use std::vec::Vec;
+
+use crate::connection::{AsyncConnection, Connection};
+
+/// A command for the worker thread and where to send its response.
+struct Request {
+    command: Vec<u8>,
+    response_size: usize,
+    reply: Arc<Reply>,
+}
+
+/// The response to a [`Request`], once the worker thread has set it.
+struct Reply {
+    state: Mutex<ReplyState>,
+}
+
+struct ReplyState {
+    result: Option<Result<Vec<u8>, Error>>,
+    waker: Option<Waker>,
+    /// Whether the worker thread is done with the request, even if it did not reply.
+    done: bool,
+}
+
+impl Reply {
+    fn set(&self, result: Option<Result<Vec<u8>, Error>>) {
+        let mut state = self.state.lock().unwrap();
+        state.done = true;
+        state.result = result;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for Request {
+    fn drop(&mut self) {
+        // The worker thread only drops a request without replying when the connection panics.
+        if self.reply.state.lock().is_ok_and(|state| !state.done) {
+            self.reply.set(None);
+        }
+    }
+}
+
+/// The future of a [`Reply`].
+struct ReplyFuture(Arc<Reply>);
+
+impl Future for ReplyFuture {
+    type Output = Result<Vec<u8>, Error>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0.state.lock().unwrap();
+        if let Some(result) = state.result.take() {
+            return Poll::Ready(result);
+        }
+        if state.done {
+            return Poll::Ready(Err(worker_gone()));
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// The error of a command that the worker thread cannot run, because its
+/// connection panicked.
+fn worker_gone() -> Error {
+    Error::new(
+        ErrorKind::BrokenPipe,
+        "the connection of the worker thread panicked",
+    )
+}
+
+/// An async connection that runs a blocking [`Connection`] on a worker thread.
+///
+/// This struct implements the [`AsyncConnection`] trait. Commands are sent to
+/// the worker thread one at a time and the task that awaits a command is woken
+/// once its response is back, so the connection works with any async runtime
+/// without blocking its threads. This is not non-blocking I/O: each connection
+/// keeps its own OS thread, which blocks on the TPM for every command.
+///
+/// If the future of a command is dropped before it completes, the worker thread
+/// still sends the command to the TPM, so the TPM state stays consistent with
+/// the commands that were issued.
+pub struct WorkerConnection<C: Connection> {
+    requests: Option<Sender<Request>>,
+    worker: Option<JoinHandle<C>>,
+}
+
+impl<C: Connection<Error: Into<Error>> + Send + 'static> WorkerConnection<C> {
+    /// Moves `connection` to a new worker thread.
+    pub fn spawn(mut connection: C) -> Self {
+        let (requests, received) = channel::<Request>();
+        let worker = thread::spawn(move || {
+            for request in received {
+                let mut response = vec![0; request.response_size];
+                let result = connection
+                    .transact(&request.command, &mut response)
+                    .map(|written| written.len())
+                    .map_err(Into::into);
+                request.reply.set(Some(result.map(|size| {
+                    response.truncate(size);
+                    response
+                })));
+            }
+            connection
+        });
+        WorkerConnection {
+            requests: Some(requests),
+            worker: Some(worker),
+        }
+    }
+}
+
+impl<C: Connection> WorkerConnection<C> {
+    /// Stops the worker thread and returns its connection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the connection panicked on the worker thread.
+    pub fn into_inner(mut self) -> C {
+        self.requests = None;
+        let worker = self.worker.take().unwrap();
+        worker
+            .join()
+            .expect("the connection of the worker thread panicked")
+    }
+}
+
+impl<C: Connection> Drop for WorkerConnection<C> {
+    fn drop(&mut self) {
+        // Ends the loop of the worker thread, which drops the connection after any pending command.
+        self.requests = None;
+    }
+}
+
+impl<C: Connection + Send> AsyncConnection for WorkerConnection<C> {
+    type Error = Error;
+    /// Sends the command to the worker thread and waits for its response.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the connection converted to an [`Error`], or an
+    /// error of kind [`ErrorKind::BrokenPipe`] if the connection panicked on the
+    /// worker thread.
+    async fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> Result<&'a mut [u8], Error> {
+        let reply = Arc::new(Reply {
+            state: Mutex::new(ReplyState {
+                result: None,
+                waker: None,
+                done: false,
+            }),
+        });
+        let request = Request {
+            command: cmd.to_vec(),
+            response_size: rsp.len(),
+            reply: reply.clone(),
+        };
+        self.requests
+            .as_ref()
+            .unwrap()
+            .send(request)
+            .map_err(|_| worker_gone())?;
+        let response = ReplyFuture(reply).await?;
+        let written = &mut rsp[..response.len()];
+        written.copy_from_slice(&response);
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,99 @@
+use std::io::{Error, ErrorKind, Result};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::task::Wake;
+use std::thread::Thread;
+
+use super::*;
+
+/// Wakes a thread blocked in [`block_on`].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs `future` to completion on the current thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+/// A connection that responds with the command, once the test allows it.
+struct GatedEcho {
+    gate: Receiver<()>,
+    thread: Option<Thread>,
+}
+
+impl Connection for GatedEcho {
+    type Error = Error;
+    fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> Result<&'a mut [u8]> {
+        self.thread = Some(thread::current());
+        self.gate.recv().unwrap();
+        if cmd.is_empty() {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+        if cmd == b"panic" {
+            panic!("the test connection panicked");
+        }
+        let written = &mut rsp[..cmd.len()];
+        written.copy_from_slice(cmd);
+        Ok(written)
+    }
+}
+
+fn spawn_echo() -> (WorkerConnection<GatedEcho>, SyncSender<()>) {
+    let (gate, receiver) = sync_channel(1);
+    let echo = GatedEcho {
+        gate: receiver,
+        thread: None,
+    };
+    (WorkerConnection::spawn(echo), gate)
+}
+
+#[test]
+fn test_transact_on_worker_thread() {
+    let (mut conn, gate) = spawn_echo();
+    let mut response = [0; 16];
+    {
+        let mut transact = core::pin::pin!(conn.transact(b"command", &mut response));
+        // The future is pending until the connection responds.
+        assert!(transact
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+            .is_pending());
+        gate.send(()).unwrap();
+        assert_eq!(block_on(transact).unwrap(), b"command");
+    }
+
+    gate.send(()).unwrap();
+    assert_eq!(
+        block_on(conn.transact(b"", &mut response))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+
+    let echo = conn.into_inner();
+    assert_ne!(echo.thread.unwrap().id(), thread::current().id());
+}
+
+#[test]
+fn test_worker_panic_is_broken_pipe() {
+    let (mut conn, gate) = spawn_echo();
+    gate.send(()).unwrap();
+    let error = block_on(conn.transact(b"panic", &mut [0; 16])).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::BrokenPipe);
+
+    // The worker thread is gone, so later commands cannot be sent to it.
+    let error = block_on(conn.transact(b"command", &mut [0; 16])).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::BrokenPipe);
+}
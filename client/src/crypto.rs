@@ -0,0 +1,270 @@
+//! The cryptography that the client computes digests, HMACs and secrets with, backed by the
+//! RustCrypto crates.
+
+#[cfg(feature = "hmac-session")]
+use aes::{Aes128, Aes192, Aes256};
+#[cfg(feature = "hmac-session")]
+use hmac::{Mac, SimpleHmac};
+#[cfg(any(feature = "attestation", feature = "salted-session"))]
+use p256::elliptic_curve::{CurveArithmetic, FieldBytes};
+#[cfg(any(feature = "attestation", feature = "salted-session"))]
+use rsa::{BigUint, RsaPublicKey};
+use sha1::Sha1;
+#[cfg(feature = "hmac-session")]
+use sha2::digest::core_api::BlockSizeUser;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+#[cfg(any(feature = "attestation", feature = "hmac-session"))]
+use tpm2_rs_base::constants::TpmCc;
+#[cfg(any(feature = "attestation", feature = "salted-session"))]
+use tpm2_rs_base::errors::TssError;
+use tpm2_rs_base::errors::{TssResult, TssTcsError};
+#[cfg(any(feature = "attestation", feature = "salted-session"))]
+use tpm2_rs_base::Tpm2bPublicKeyRsa;
+use tpm2_rs_base::{Tpm2bDigest, Tpm2bSimple, TpmiAlgHash};
+
+/// The RSA public exponent of keys whose `exponent` is zero.
+#[cfg(any(feature = "attestation", feature = "salted-session"))]
+const DEFAULT_RSA_EXPONENT: u32 = 65537;
+
+/// Computes the digest of the concatenation of `data` with `alg`.
+pub fn digest(alg: TpmiAlgHash, data: &[&[u8]]) -> TssResult<Tpm2bDigest> {
+    fn digest_with<D: Digest>(data: &[&[u8]]) -> TssResult<Tpm2bDigest> {
+        let mut hasher = D::new();
+        for chunk in data {
+            hasher.update(chunk);
+        }
+        Ok(Tpm2bDigest::from_bytes(&hasher.finalize())?)
+    }
+    match alg {
+        TpmiAlgHash::SHA1 => digest_with::<Sha1>(data),
+        TpmiAlgHash::SHA256 => digest_with::<Sha256>(data),
+        TpmiAlgHash::SHA384 => digest_with::<Sha384>(data),
+        TpmiAlgHash::SHA512 => digest_with::<Sha512>(data),
+        _ => Err(TssTcsError::NotImplemented.into()),
+    }
+}
+
+/// Computes the HMAC of the concatenation of `data` with `alg`, keyed with `key`.
+#[cfg(feature = "hmac-session")]
+pub fn hmac(alg: TpmiAlgHash, key: &[u8], data: &[&[u8]]) -> TssResult<Tpm2bDigest> {
+    fn hmac_with<D: Digest + BlockSizeUser>(key: &[u8], data: &[&[u8]]) -> TssResult<Tpm2bDigest> {
+        // HMAC accepts keys of any size.
+        let mut mac = SimpleHmac::<D>::new_from_slice(key).or(Err(TssTcsError::GeneralFailure))?;
+        for chunk in data {
+            mac.update(chunk);
+        }
+        Ok(Tpm2bDigest::from_bytes(&mac.finalize().into_bytes())?)
+    }
+    match alg {
+        TpmiAlgHash::SHA1 => hmac_with::<Sha1>(key, data),
+        TpmiAlgHash::SHA256 => hmac_with::<Sha256>(key, data),
+        TpmiAlgHash::SHA384 => hmac_with::<Sha384>(key, data),
+        TpmiAlgHash::SHA512 => hmac_with::<Sha512>(key, data),
+        _ => Err(TssTcsError::NotImplemented.into()),
+    }
+}
+
+/// Returns whether `a` and `b` are equal in a time that only depends on their lengths, so that
+/// checking a response HMAC doesn't reveal how many of its leading bytes match.
+#[cfg(feature = "hmac-session")]
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let diff = a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y));
+    a.len() == b.len() && core::hint::black_box(diff) == 0
+}
+
+/// Fills `out` using KDFa from [TPM2.0 1.83] Part 1, 11.4.10.2, the counter mode KDF from
+/// SP800-108 with HMAC as the PRF.
+#[cfg(feature = "hmac-session")]
+pub fn kdfa(
+    alg: TpmiAlgHash,
+    key: &[u8],
+    label: &[u8],
+    context_u: &[u8],
+    context_v: &[u8],
+    out: &mut [u8],
+) -> TssResult<()> {
+    kdfa_with(alg, key, label, context_u, context_v, out, |block, prf| {
+        block.copy_from_slice(prf)
+    })
+}
+
+/// XORs `data` with a mask of the same size from KDFa, which is the XOR obfuscation of
+/// [TPM2.0 1.83] Part 1, 11.4.6.
+#[cfg(feature = "hmac-session")]
+pub fn kdfa_xor(
+    alg: TpmiAlgHash,
+    key: &[u8],
+    label: &[u8],
+    context_u: &[u8],
+    context_v: &[u8],
+    data: &mut [u8],
+) -> TssResult<()> {
+    kdfa_with(alg, key, label, context_u, context_v, data, |block, prf| {
+        block.iter_mut().zip(prf).for_each(|(b, m)| *b ^= m)
+    })
+}
+
+/// Runs KDFa for `out.len()` bytes, combining each block of its output into `out` with `combine`.
+#[cfg(feature = "hmac-session")]
+fn kdfa_with(
+    alg: TpmiAlgHash,
+    key: &[u8],
+    label: &[u8],
+    context_u: &[u8],
+    context_v: &[u8],
+    out: &mut [u8],
+    combine: fn(&mut [u8], &[u8]),
+) -> TssResult<()> {
+    let bits = u32::try_from(out.len() * 8).or(Err(TssTcsError::BadParameter))?;
+    // The label is terminated by a zero, unless it already ends with one.
+    let terminator: &[u8] = match label.last() {
+        Some(0) | None => &[],
+        Some(_) => &[0],
+    };
+    let block_size = digest(alg, &[])?.get_size() as usize;
+    for (counter, block) in (1u32..).zip(out.chunks_mut(block_size)) {
+        let prf = hmac(
+            alg,
+            key,
+            &[
+                &counter.to_be_bytes(),
+                label,
+                terminator,
+                context_u,
+                context_v,
+                &bits.to_be_bytes(),
+            ],
+        )?;
+        combine(block, &prf.get_buffer()[..block.len()]);
+    }
+    Ok(())
+}
+
+/// Fills `out` using KDFe from [TPM2.0 1.83] Part 1, 11.4.10.3, the single-step KDF from
+/// SP800-56A with a hash as the auxiliary function.
+#[cfg(feature = "salted-session")]
+pub fn kdfe(
+    alg: TpmiAlgHash,
+    z: &[u8],
+    label: &[u8],
+    party_u: &[u8],
+    party_v: &[u8],
+    out: &mut [u8],
+) -> TssResult<()> {
+    let terminator: &[u8] = match label.last() {
+        Some(0) | None => &[],
+        Some(_) => &[0],
+    };
+    let block_size = digest(alg, &[])?.get_size() as usize;
+    for (counter, block) in (1u32..).zip(out.chunks_mut(block_size)) {
+        let hash = digest(
+            alg,
+            &[
+                &counter.to_be_bytes(),
+                z,
+                label,
+                terminator,
+                party_u,
+                party_v,
+            ],
+        )?;
+        block.copy_from_slice(&hash.get_buffer()[..block.len()]);
+    }
+    Ok(())
+}
+
+/// Encrypts or decrypts `data` in place with AES in CFB mode, with a 128-bit feedback. The size of
+/// `key` selects AES-128, AES-192 or AES-256.
+#[cfg(feature = "hmac-session")]
+pub fn aes_cfb(key: &[u8], iv: &[u8], data: &mut [u8], decrypt: bool) -> TssResult<()> {
+    use aes::cipher::{Block, BlockEncrypt, KeyInit};
+
+    fn cfb_with<C: BlockEncrypt + KeyInit>(
+        key: &[u8],
+        iv: &[u8],
+        data: &mut [u8],
+        decrypt: bool,
+    ) -> TssResult<()> {
+        let cipher = C::new_from_slice(key).or(Err(TssTcsError::BadParameter))?;
+        let mut feedback = Block::<C>::default();
+        if iv.len() != feedback.len() {
+            return Err(TssTcsError::BadParameter.into());
+        }
+        feedback.copy_from_slice(iv);
+        for chunk in data.chunks_mut(feedback.len()) {
+            cipher.encrypt_block(&mut feedback);
+            for (byte, key_stream) in chunk.iter_mut().zip(feedback.iter_mut()) {
+                let input = *byte;
+                *byte ^= *key_stream;
+                // The cipher text is fed back into the next block.
+                *key_stream = if decrypt { input } else { *byte };
+            }
+        }
+        Ok(())
+    }
+    match key.len() {
+        16 => cfb_with::<Aes128>(key, iv, data, decrypt),
+        24 => cfb_with::<Aes192>(key, iv, data, decrypt),
+        32 => cfb_with::<Aes256>(key, iv, data, decrypt),
+        _ => Err(TssTcsError::BadParameter.into()),
+    }
+}
+
+/// Computes the cpHash of a command: the digest of its command code, the Names of its handles
+/// and its marshaled parameters.
+#[cfg(any(feature = "attestation", feature = "hmac-session"))]
+pub fn cp_hash(
+    alg: TpmiAlgHash,
+    command_code: TpmCc,
+    names: &[&[u8]],
+    parameters: &[u8],
+) -> TssResult<Tpm2bDigest> {
+    // A command has at most three handles.
+    if names.len() > 3 {
+        return Err(TssTcsError::BadParameter.into());
+    }
+    let command_code = command_code.0.to_be_bytes();
+    let mut data: [&[u8]; 5] = [&command_code, &[], &[], &[], &[]];
+    data[1..=names.len()].copy_from_slice(names);
+    data[names.len() + 1] = parameters;
+    digest(alg, &data[..names.len() + 2])
+}
+
+/// Computes the rpHash of a successful response: the digest of the response code, the command
+/// code and the marshaled response parameters.
+#[cfg(any(feature = "attestation", feature = "hmac-session"))]
+pub fn rp_hash(alg: TpmiAlgHash, command_code: TpmCc, parameters: &[u8]) -> TssResult<Tpm2bDigest> {
+    const SUCCESS: u32 = 0;
+    digest(
+        alg,
+        &[
+            &SUCCESS.to_be_bytes(),
+            &command_code.0.to_be_bytes(),
+            parameters,
+        ],
+    )
+}
+
+/// Returns the RSA public key with `modulus` and `exponent`, as a TPM describes it.
+#[cfg(any(feature = "attestation", feature = "salted-session"))]
+pub fn rsa_public_key(modulus: &Tpm2bPublicKeyRsa, exponent: u32) -> TssResult<RsaPublicKey> {
+    let exponent = match exponent {
+        0 => DEFAULT_RSA_EXPONENT,
+        exponent => exponent,
+    };
+    RsaPublicKey::new(
+        BigUint::from_bytes_be(modulus.get_buffer()),
+        BigUint::from(exponent),
+    )
+    .or(Err(TssError::from(TssTcsError::BadParameter)))
+}
+
+/// Copies a big-endian coordinate or scalar into the field size of `C`, restoring any leading
+/// zeros that were stripped.
+#[cfg(any(feature = "attestation", feature = "salted-session"))]
+pub fn field_bytes<C: CurveArithmetic>(bytes: &[u8]) -> Option<FieldBytes<C>> {
+    let mut field_bytes = FieldBytes::<C>::default();
+    let offset = field_bytes.len().checked_sub(bytes.len())?;
+    field_bytes[offset..].copy_from_slice(bytes);
+    Some(field_bytes)
+}
@@ -0,0 +1,424 @@
+//! Decoding raw TPM commands and responses for humans.
+//!
+//! [`Command`] and [`Response`] split the bytes of a command or response into
+//! its header, handles, authorization area and parameters, and unmarshal the
+//! parameters of the commands that this crate knows. Both implement [`Debug`],
+//! so `{:#?}` formats them as a tree. [`ResponseCode`] decodes a TPM_RC.
+//!
+//! Decoding never fails: the parts that cannot be decoded are formatted as
+//! hexadecimal, so truncated or corrupted bus captures can still be read.
+
+use core::fmt::{self, Debug, Display, Formatter};
+use core::marker::PhantomData;
+use core::mem::size_of;
+
+use tpm2_rs_base::commands::*;
+use tpm2_rs_base::constants::{TpmCc, TpmHandle, TpmRc, TpmSt};
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{TpmsAuthCommand, TpmsAuthResponse};
+
+/// Formats the parameters of a command or response.
+type FmtParameters = fn(&[u8], &mut Formatter<'_>) -> fmt::Result;
+
+/// What a command that this crate knows looks like.
+#[derive(Clone, Copy)]
+struct Layout {
+    handles: usize,
+    response_handles: usize,
+    parameters: FmtParameters,
+    response_parameters: FmtParameters,
+}
+
+/// Returns the number of handles in the handle type `H`.
+fn handle_count<H: Marshalable>() -> usize {
+    let handles = [0u8; 3 * size_of::<TpmHandle>()];
+    let mut buffer = UnmarshalBuf::new(&handles);
+    H::try_unmarshal(&mut buffer).map_or(0, |_| (handles.len() - buffer.len()) / size_of::<u32>())
+}
+
+/// Formats `bytes` as a `T` if they hold exactly one, or as hexadecimal otherwise.
+fn fmt_as<T: Marshalable + Debug>(bytes: &[u8], f: &mut Formatter<'_>) -> fmt::Result {
+    let mut buffer = UnmarshalBuf::new(bytes);
+    match T::try_unmarshal(&mut buffer) {
+        Ok(value) if buffer.is_empty() => value.fmt(f),
+        _ => Hex(bytes).fmt(f),
+    }
+}
+
+macro_rules! layouts {
+    ($($cmd:ty),* $(,)?) => {
+        /// Returns the layout of the command with `code`, if this crate knows it.
+        fn layout(code: TpmCc) -> Option<Layout> {
+            $(
+                if code == <$cmd as TpmCommand>::CMD_CODE {
+                    return Some(Layout {
+                        handles: handle_count::<<$cmd as TpmCommand>::Handles>(),
+                        response_handles: handle_count::<<$cmd as TpmCommand>::RespHandles>(),
+                        parameters: fmt_as::<$cmd>,
+                        response_parameters: fmt_as::<<$cmd as TpmCommand>::RespT>,
+                    });
+                }
+            )*
+            None
+        }
+    };
+}
+
+layouts! {
+    StartupCmd,
+    StartAuthSessionCmd,
+    PolicyRestartCmd,
+    LoadExternalCmd,
+    ReadPublicCmd,
+    EccEncryptCmd,
+    EccDecryptCmd,
+    EccParametersCmd,
+    EncryptDecryptCmd,
+    EncryptDecrypt2Cmd,
+    HashCmd,
+    GetRandomCmd,
+    CertifyCmd,
+    CertifyCreationCmd,
+    QuoteCmd,
+    GetSessionAuditDigestCmd,
+    GetCommandAuditDigestCmd,
+    GetTimeCmd,
+    VerifySignatureCmd,
+    SignCmd,
+    SetCommandCodeAuditStatusCmd,
+    PcrReadCmd,
+    PolicySignedCmd,
+    PolicySecretCmd,
+    PolicyOrCmd,
+    PolicyPcrCmd,
+    PolicyLocalityCmd,
+    PolicyNvCmd,
+    PolicyCounterTimerCmd,
+    PolicyCommandCodeCmd,
+    PolicyCpHashCmd,
+    PolicyNameHashCmd,
+    PolicyAuthorizeCmd,
+    PolicyAuthValueCmd,
+    PolicyPasswordCmd,
+    PolicyGetDigestCmd,
+    FlushContextCmd,
+    GetCapabilityCmd,
+    TestParmsCmd,
+    NvReadPublicCmd,
+}
+
+/// Bytes formatted as lowercase hexadecimal.
+struct Hex<'a>(&'a [u8]);
+
+impl Debug for Hex<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// A handle area formatted as a list of hexadecimal handles.
+struct Handles<'a>(&'a [u8]);
+
+impl Debug for Handles<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        for handle in self.0.chunks(size_of::<u32>()) {
+            match handle.try_into() {
+                Ok(handle) => list.entry(&format_args!("{:#010x}", u32::from_be_bytes(handle))),
+                Err(_) => list.entry(&Hex(handle)),
+            };
+        }
+        list.finish()
+    }
+}
+
+/// An authorization area formatted as a list of `T`.
+struct Sessions<'a, T>(&'a [u8], PhantomData<T>);
+
+impl<'a, T> Sessions<'a, T> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Sessions(bytes, PhantomData)
+    }
+}
+
+impl<T: Marshalable + Debug> Debug for Sessions<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        let mut buffer = UnmarshalBuf::new(self.0);
+        while !buffer.is_empty() {
+            let remaining = buffer.len();
+            let Ok(session) = T::try_unmarshal(&mut buffer) else {
+                list.entry(&Hex(&self.0[self.0.len() - remaining..]));
+                break;
+            };
+            list.entry(&session);
+        }
+        list.finish()
+    }
+}
+
+/// Parameters formatted with the layout of their command, if it is known.
+struct Parameters<'a>(&'a [u8], Option<FmtParameters>);
+
+impl Debug for Parameters<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            Some(fmt_parameters) => fmt_parameters(self.0, f),
+            None => Hex(self.0).fmt(f),
+        }
+    }
+}
+
+/// Takes `len` bytes from `buffer`, or all of them if there are fewer.
+fn take<'a>(buffer: &mut UnmarshalBuf<'a>, len: usize) -> (&'a [u8], bool) {
+    match buffer.get(len) {
+        Some(bytes) => (bytes, true),
+        None => (buffer.get(buffer.len()).unwrap_or_default(), false),
+    }
+}
+
+/// The header that commands and responses start with.
+struct Header<'a> {
+    tag: TpmSt,
+    size: u32,
+    code: u32,
+    /// The bytes after the header, up to the size in the header.
+    body: UnmarshalBuf<'a>,
+    /// The bytes after the size in the header.
+    trailing: &'a [u8],
+}
+
+impl<'a> Header<'a> {
+    fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let mut buffer = UnmarshalBuf::new(bytes);
+        let tag = TpmSt::try_unmarshal(&mut buffer).ok()?;
+        let size = u32::try_unmarshal(&mut buffer).ok()?;
+        let code = u32::try_unmarshal(&mut buffer).ok()?;
+        let end = (size as usize).clamp(bytes.len() - buffer.len(), bytes.len());
+        let start = bytes.len() - buffer.len();
+        Some(Header {
+            tag,
+            size,
+            code,
+            body: UnmarshalBuf::new(&bytes[start..end]),
+            trailing: &bytes[end..],
+        })
+    }
+}
+
+/// A raw TPM command that formats as a tree of its parts.
+#[derive(Clone, Copy)]
+pub struct Command<'a>(&'a [u8]);
+
+impl<'a> Command<'a> {
+    /// Decodes the command in `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Command(bytes)
+    }
+
+    /// Returns the command code, or `None` if the command is too short to have one.
+    pub fn code(&self) -> Option<TpmCc> {
+        Header::parse(self.0).map(|header| TpmCc(header.code))
+    }
+}
+
+impl Debug for Command<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(mut header) = Header::parse(self.0) else {
+            return f.debug_tuple("Command").field(&Hex(self.0)).finish();
+        };
+        let code = TpmCc(header.code);
+        let mut tree = f.debug_struct("Command");
+        tree.field("tag", &header.tag)
+            .field("size", &header.size)
+            .field("command_code", &code);
+        let body = &mut header.body;
+        match layout(code) {
+            Some(layout) => 'body: {
+                let (handles, complete) = take(body, layout.handles * size_of::<TpmHandle>());
+                tree.field("handles", &Handles(handles));
+                if !complete {
+                    break 'body;
+                }
+                if header.tag == TpmSt::Sessions {
+                    let Ok(auth_size) = u32::try_unmarshal(body) else {
+                        break 'body;
+                    };
+                    let (sessions, complete) = take(body, auth_size as usize);
+                    tree.field("sessions", &Sessions::<TpmsAuthCommand>::new(sessions));
+                    if !complete {
+                        break 'body;
+                    }
+                }
+                let (parameters, _) = take(body, body.len());
+                tree.field(
+                    "parameters",
+                    &Parameters(parameters, Some(layout.parameters)),
+                );
+            }
+            None => {
+                let (rest, _) = take(body, body.len());
+                tree.field("body", &Hex(rest));
+            }
+        }
+        if !body.is_empty() {
+            tree.field("truncated", &Hex(take(body, body.len()).0));
+        }
+        if !header.trailing.is_empty() {
+            tree.field("trailing", &Hex(header.trailing));
+        }
+        tree.finish()
+    }
+}
+
+/// A raw TPM response that formats as a tree of its parts.
+///
+/// The layout of a response depends on its command, so decoding its handles
+/// and parameters needs the command code.
+#[derive(Clone, Copy)]
+pub struct Response<'a> {
+    code: TpmCc,
+    bytes: &'a [u8],
+}
+
+impl<'a> Response<'a> {
+    /// Decodes the response in `bytes` to the command with `code`.
+    pub fn new(code: TpmCc, bytes: &'a [u8]) -> Self {
+        Response { code, bytes }
+    }
+
+    /// Returns the response code, or `None` if the response is too short to have one.
+    pub fn response_code(&self) -> Option<ResponseCode> {
+        Header::parse(self.bytes).map(|header| ResponseCode(header.code))
+    }
+}
+
+impl Debug for Response<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(mut header) = Header::parse(self.bytes) else {
+            return f.debug_tuple("Response").field(&Hex(self.bytes)).finish();
+        };
+        let mut tree = f.debug_struct("Response");
+        tree.field("tag", &header.tag)
+            .field("size", &header.size)
+            .field("response_code", &ResponseCode(header.code));
+        let body = &mut header.body;
+        match layout(self.code) {
+            // An error response has no body.
+            _ if header.code != 0 => {}
+            Some(layout) => 'body: {
+                let handles_size = layout.response_handles * size_of::<TpmHandle>();
+                let (handles, complete) = take(body, handles_size);
+                tree.field("handles", &Handles(handles));
+                if !complete {
+                    break 'body;
+                }
+                let parameters_size = if header.tag == TpmSt::Sessions {
+                    let Ok(size) = u32::try_unmarshal(body) else {
+                        break 'body;
+                    };
+                    size as usize
+                } else {
+                    body.len()
+                };
+                let (parameters, complete) = take(body, parameters_size);
+                let parameters = Parameters(parameters, Some(layout.response_parameters));
+                tree.field("parameters", &parameters);
+                if complete && header.tag == TpmSt::Sessions {
+                    let (sessions, _) = take(body, body.len());
+                    tree.field("sessions", &Sessions::<TpmsAuthResponse>::new(sessions));
+                }
+            }
+            None => {
+                let (rest, _) = take(body, body.len());
+                tree.field("body", &Hex(rest));
+            }
+        }
+        if !body.is_empty() {
+            tree.field("truncated", &Hex(take(body, body.len()).0));
+        }
+        if !header.trailing.is_empty() {
+            tree.field("trailing", &Hex(header.trailing));
+        }
+        tree.finish()
+    }
+}
+
+/// A TPM_RC, or an error of a TSS layer, that formats as its name and what it refers to.
+///
+/// For example, `0x000001c4` formats as `0x000001c4 Value (parameter 1)`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ResponseCode(pub u32);
+
+impl ResponseCode {
+    /// Returns the name of the common TSS return code `code`.
+    fn tss_name(code: u32) -> Option<&'static str> {
+        Some(match code {
+            2 => "GeneralFailure",
+            3 => "BadParameter",
+            4 => "InternalError",
+            5 => "OutOfMemory",
+            6 => "NotImplemented",
+            8 => "KeyAlredyRegistered",
+            16 => "TpmUnexpected",
+            17 => "CommFailure",
+            18 => "Timeout",
+            20 => "Unsupported",
+            22 => "Canceled",
+            _ => return None,
+        })
+    }
+}
+
+impl Display for ResponseCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let rc = self.0;
+        write!(f, "{rc:#010x} ")?;
+        let layer = match rc >> 12 {
+            0 => None,
+            1 => Some("TssTddlError"),
+            2 => Some("TssTcsError"),
+            3 => Some("TssTspError"),
+            _ => return f.write_str("unknown"),
+        };
+        if let Some(layer) = layer {
+            return match Self::tss_name(rc & 0xFFF) {
+                Some(name) => write!(f, "{layer}::{name}"),
+                None => write!(f, "{layer}({})", rc & 0xFFF),
+            };
+        }
+        if rc == 0 {
+            return f.write_str("Success");
+        }
+        if rc & TpmRc::RC_FMT_1 != 0 {
+            write!(f, "{:?}", TpmRc(rc & 0xBF))?;
+            let n = (rc >> 8) & 0xF;
+            return match n {
+                _ if rc & TpmRc::RC_P != 0 => write!(f, " (parameter {n})"),
+                0 => Ok(()),
+                _ if n & 0x8 != 0 => write!(f, " (session {})", n & 0x7),
+                _ => write!(f, " (handle {n})"),
+            };
+        }
+        if rc & TpmRc::RC_VER_1 == 0 {
+            return f.write_str("TPM 1.2 error");
+        }
+        // Bit 10 of a format-zero code marks it as defined by the vendor.
+        if rc & 0x400 != 0 {
+            return f.write_str("vendor-defined error");
+        }
+        if rc & TpmRc::RC_S != 0 {
+            f.write_str("warning ")?;
+        }
+        write!(f, "{:?}", TpmRc(rc))
+    }
+}
+
+impl Debug for ResponseCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,94 @@
+extern crate std;
+
+use hex_literal::hex;
+use std::format;
+
+use super::*;
+
+#[test]
+fn test_decode_command() {
+    let command = hex!("80010000000c0000017b0004");
+    assert_eq!(Command::new(&command).code(), Some(TpmCc::GetRandom));
+    assert_eq!(
+        format!("{:?}", Command::new(&command)),
+        "Command { tag: NoSessions, size: 12, command_code: GetRandom, handles: [], \
+         parameters: GetRandomCmd { bytes_requested: 4 } }"
+    );
+
+    // TPM2_ReadPublic of 0x80000001 with a password session and extra bytes after it.
+    let command = hex!("80020000001b00000173 80000001 00000009 40000009 0000 01 0000 ff");
+    assert_eq!(
+        format!("{:?}", Command::new(&command)),
+        "Command { tag: Sessions, size: 27, command_code: ReadPublic, handles: [0x80000001], \
+         sessions: [TpmsAuthCommand { session_handle: TpmiShAuthSession(1073741833), \
+         nonce: Tpm2bDigest(), session_attributes: TpmaSession(1), hmac: Tpm2bDigest() }], \
+         parameters: ReadPublicCmd, trailing: ff }"
+    );
+}
+
+#[test]
+fn test_decode_malformed_command() {
+    let command = hex!("80010000");
+    assert_eq!(Command::new(&command).code(), None);
+    assert_eq!(format!("{:?}", Command::new(&command)), "Command(80010000)");
+
+    // TPM2_NV_Read is not known, so its handles and parameters cannot be told apart.
+    let command = hex!("8001000000100000014e 01500000 0000");
+    assert_eq!(
+        format!("{:?}", Command::new(&command)),
+        "Command { tag: NoSessions, size: 16, command_code: NVRead, body: 015000000000 }"
+    );
+
+    // The size in the header says that the authorization area is cut short.
+    let command = hex!("80020000001b00000173 80000001 00000009 40000009");
+    assert_eq!(
+        format!("{:?}", Command::new(&command)),
+        "Command { tag: Sessions, size: 27, command_code: ReadPublic, handles: [0x80000001], \
+         sessions: [40000009] }"
+    );
+}
+
+#[test]
+fn test_decode_response() {
+    let response = hex!("80010000001000000000000412345678");
+    assert_eq!(
+        format!("{:?}", Response::new(TpmCc::GetRandom, &response)),
+        "Response { tag: NoSessions, size: 16, response_code: 0x00000000 Success, handles: [], \
+         parameters: GetRandomResp { random_bytes: Tpm2bDigest(12345678) } }"
+    );
+
+    let response = hex!("80020000001b00000000 00000006 000412345678 0000 01 0000");
+    assert_eq!(
+        format!("{:?}", Response::new(TpmCc::GetRandom, &response)),
+        "Response { tag: Sessions, size: 27, response_code: 0x00000000 Success, handles: [], \
+         parameters: GetRandomResp { random_bytes: Tpm2bDigest(12345678) }, \
+         sessions: [TpmsAuthResponse { nonce: Tpm2bDigest(), session_attributes: TpmaSession(1), \
+         hmac: Tpm2bData() }] }"
+    );
+
+    let response = hex!("80010000000a000001c4");
+    let decoded = Response::new(TpmCc::GetRandom, &response);
+    assert_eq!(decoded.response_code(), Some(ResponseCode(0x1C4)));
+    assert_eq!(
+        format!("{decoded:?}"),
+        "Response { tag: NoSessions, size: 10, response_code: 0x000001c4 Value (parameter 1) }"
+    );
+}
+
+#[test]
+fn test_response_code() {
+    for (rc, text) in [
+        (0x000, "0x00000000 Success"),
+        (0x101, "0x00000101 Failure"),
+        (0x18B, "0x0000018b Handle (handle 1)"),
+        (0x98E, "0x0000098e AuthFail (session 1)"),
+        (0x2C4, "0x000002c4 Value (parameter 2)"),
+        (0x084, "0x00000084 Value"),
+        (0x922, "0x00000922 warning Retry"),
+        (0x005, "0x00000005 TPM 1.2 error"),
+        (0x2003, "0x00002003 TssTcsError::BadParameter"),
+        (0x1007, "0x00001007 TssTddlError(7)"),
+    ] {
+        assert_eq!(format!("{}", ResponseCode(rc)), text);
+    }
+}
@@ -0,0 +1,299 @@
+//! Typed handles of the entities that commands refer to.
+//!
+//! Sessions authorize a command with the Names of its handles rather than the handles
+//! themselves. The Name of an object or NV index is the digest of its public area, while the
+//! Name of any other entity is its handle. Each handle type carries the Name of its entity and its
+//! authorization value, so that commands can pass the Names with [`run_command_with_names`] and
+//! sessions can be keyed with the right authorization value.
+//!
+//! [`run_command_with_names`]: crate::run_command_with_names
+
+use crate::connection::Connection;
+#[cfg(any(feature = "attestation", feature = "hmac-session", feature = "policy"))]
+use crate::crypto::digest;
+use crate::{nv_read_public, read_public};
+use core::mem::size_of;
+use tpm2_rs_base::commands::{NvReadPublicCmd, ReadPublicCmd};
+use tpm2_rs_base::constants::{TpmHandle, TpmHt};
+use tpm2_rs_base::errors::{TpmRcResult, TssError, TssResult, TssTcsError};
+#[cfg(any(feature = "attestation", feature = "hmac-session", feature = "policy"))]
+use tpm2_rs_base::marshal::Marshalable;
+use tpm2_rs_base::{Tpm2bAuth, Tpm2bName, Tpm2bSimple};
+#[cfg(any(feature = "attestation", feature = "hmac-session", feature = "policy"))]
+use tpm2_rs_base::{TpmiAlgHash, TpmsNvPublic, TpmtPublic};
+
+/// An entity at a handle, which sessions know by its Name.
+pub trait Entity {
+    /// Returns the handle of the entity.
+    fn handle(&self) -> TpmHandle;
+    /// Returns the Name of the entity.
+    fn name(&self) -> &[u8];
+    /// Returns the authorization value of the entity, which is empty if it is not known.
+    fn auth_value(&self) -> &[u8];
+}
+
+/// Returns the type of `handle`, which is its most significant byte.
+fn handle_type(handle: TpmHandle) -> TpmHt {
+    TpmHt((handle.0 >> 24) as u8)
+}
+
+/// Returns `handle` if it has one of the types `expected`.
+fn check_type(handle: TpmHandle, expected: &[TpmHt]) -> TssResult<TpmHandle> {
+    if expected.contains(&handle_type(handle)) {
+        Ok(handle)
+    } else {
+        Err(TssTcsError::BadParameter.into())
+    }
+}
+
+/// Computes the Name of an entity with the public area `public`, which is its `name_alg`
+/// followed by the digest of the marshaled area.
+#[cfg(any(feature = "attestation", feature = "hmac-session", feature = "policy"))]
+fn public_name<P: Marshalable>(name_alg: TpmiAlgHash, public: &P) -> TssResult<Tpm2bName> {
+    let mut buffer = [0u8; size_of::<TpmtPublic>()];
+    let size = public.try_marshal(&mut buffer)?;
+    let digest = digest(name_alg, &[&buffer[..size]])?;
+    let mut name = [0u8; size_of::<u16>() + size_of::<Tpm2bName>()];
+    name[..size_of::<u16>()].copy_from_slice(&name_alg.0.to_be_bytes());
+    let size = size_of::<u16>() + digest.get_size() as usize;
+    name[size_of::<u16>()..size].copy_from_slice(digest.get_buffer());
+    Ok(Tpm2bName::from_bytes(&name[..size])?)
+}
+
+/// A transient or persistent object.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObjectHandle {
+    handle: TpmHandle,
+    name: Tpm2bName,
+    auth_value: Tpm2bAuth,
+}
+
+impl ObjectHandle {
+    /// Creates the object at `handle` with the Name `name`, such as the one returned when the
+    /// object is loaded.
+    ///
+    /// # Errors:
+    /// Returns [TssTcsError::BadParameter] if `handle` is neither transient nor persistent, or
+    /// [TpmRcError::Size](tpm2_rs_base::errors::TpmRcError::Size) if `name` is too large.
+    pub fn new(handle: TpmHandle, name: &[u8]) -> TssResult<Self> {
+        Ok(ObjectHandle {
+            handle: check_type(handle, &[TpmHt::Transient, TpmHt::Persistent])?,
+            name: Tpm2bName::from_bytes(name)?,
+            auth_value: Tpm2bAuth::default(),
+        })
+    }
+
+    /// Creates the object at `handle` with the public area `public`, whose Name is computed.
+    #[cfg(any(feature = "attestation", feature = "hmac-session", feature = "policy"))]
+    pub fn from_public(handle: TpmHandle, public: &TpmtPublic) -> TssResult<Self> {
+        Self::new(handle, public_name(public.name_alg, public)?.get_buffer())
+    }
+
+    /// Creates the object at `handle` with the Name that the TPM returns for it.
+    pub fn read_public<T: Connection<Error: From<TssError>>>(
+        tpm: &mut T,
+        handle: TpmHandle,
+    ) -> Result<Self, T::Error> {
+        let resp = read_public(tpm, handle, &ReadPublicCmd {})?;
+        Ok(Self::new(handle, resp.name.get_buffer())?)
+    }
+
+    /// Sets the authorization value of the object.
+    pub fn set_auth_value<T: AsRef<[u8]> + ?Sized>(&mut self, auth_value: &T) -> TpmRcResult<()> {
+        self.auth_value = Tpm2bAuth::from_bytes(auth_value.as_ref())?;
+        Ok(())
+    }
+}
+
+impl Entity for ObjectHandle {
+    fn handle(&self) -> TpmHandle {
+        self.handle
+    }
+
+    fn name(&self) -> &[u8] {
+        self.name.get_buffer()
+    }
+
+    fn auth_value(&self) -> &[u8] {
+        self.auth_value.get_buffer()
+    }
+}
+
+/// An NV index. Its Name changes when its attributes do, such as when it is written for the first
+/// time, after which it has to be read or computed again.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NvIndexHandle {
+    handle: TpmHandle,
+    name: Tpm2bName,
+    auth_value: Tpm2bAuth,
+}
+
+impl NvIndexHandle {
+    /// Creates the NV index at `handle` with the Name `name`.
+    ///
+    /// # Errors:
+    /// Returns [TssTcsError::BadParameter] if `handle` is not an NV index, or
+    /// [TpmRcError::Size](tpm2_rs_base::errors::TpmRcError::Size) if `name` is too large.
+    pub fn new(handle: TpmHandle, name: &[u8]) -> TssResult<Self> {
+        Ok(NvIndexHandle {
+            handle: check_type(handle, &[TpmHt::NVIndex])?,
+            name: Tpm2bName::from_bytes(name)?,
+            auth_value: Tpm2bAuth::default(),
+        })
+    }
+
+    /// Creates the NV index with the public area `public`, whose Name is computed.
+    #[cfg(any(feature = "attestation", feature = "hmac-session", feature = "policy"))]
+    pub fn from_public(public: &TpmsNvPublic) -> TssResult<Self> {
+        let handle = TpmHandle(public.nv_index.get());
+        Self::new(handle, public_name(public.name_alg, public)?.get_buffer())
+    }
+
+    /// Creates the NV index at `handle` with the Name that the TPM returns for it.
+    pub fn read_public<T: Connection<Error: From<TssError>>>(
+        tpm: &mut T,
+        handle: TpmHandle,
+    ) -> Result<Self, T::Error> {
+        let resp = nv_read_public(tpm, handle, &NvReadPublicCmd {})?;
+        Ok(Self::new(handle, resp.nv_name.get_buffer())?)
+    }
+
+    /// Sets the authorization value of the NV index.
+    pub fn set_auth_value<T: AsRef<[u8]> + ?Sized>(&mut self, auth_value: &T) -> TpmRcResult<()> {
+        self.auth_value = Tpm2bAuth::from_bytes(auth_value.as_ref())?;
+        Ok(())
+    }
+}
+
+impl Entity for NvIndexHandle {
+    fn handle(&self) -> TpmHandle {
+        self.handle
+    }
+
+    fn name(&self) -> &[u8] {
+        self.name.get_buffer()
+    }
+
+    fn auth_value(&self) -> &[u8] {
+        self.auth_value.get_buffer()
+    }
+}
+
+/// A hierarchy or another permanent entity, such as [`TpmHandle::RHOwner`] or
+/// [`TpmHandle::RHLockout`], whose Name is its handle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HierarchyHandle {
+    name: [u8; size_of::<TpmHandle>()],
+    auth_value: Tpm2bAuth,
+}
+
+impl HierarchyHandle {
+    /// Creates the permanent entity at `handle`.
+    ///
+    /// # Errors:
+    /// Returns [TssTcsError::BadParameter] if `handle` is not a permanent handle.
+    pub fn new(handle: TpmHandle) -> TssResult<Self> {
+        Ok(HierarchyHandle {
+            name: check_type(handle, &[TpmHt::Permanent])?.0.to_be_bytes(),
+            auth_value: Tpm2bAuth::default(),
+        })
+    }
+
+    /// Sets the authorization value of the hierarchy.
+    pub fn set_auth_value<T: AsRef<[u8]> + ?Sized>(&mut self, auth_value: &T) -> TpmRcResult<()> {
+        self.auth_value = Tpm2bAuth::from_bytes(auth_value.as_ref())?;
+        Ok(())
+    }
+}
+
+impl Entity for HierarchyHandle {
+    fn handle(&self) -> TpmHandle {
+        TpmHandle(u32::from_be_bytes(self.name))
+    }
+
+    fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    fn auth_value(&self) -> &[u8] {
+        self.auth_value.get_buffer()
+    }
+}
+
+/// A PCR, whose Name is its handle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PcrHandle {
+    name: [u8; size_of::<TpmHandle>()],
+    auth_value: Tpm2bAuth,
+}
+
+impl PcrHandle {
+    /// Creates the PCR at `handle`, which is the index of the PCR.
+    ///
+    /// # Errors:
+    /// Returns [TssTcsError::BadParameter] if `handle` is not a PCR handle.
+    pub fn new(handle: TpmHandle) -> TssResult<Self> {
+        Ok(PcrHandle {
+            name: check_type(handle, &[TpmHt::PCR])?.0.to_be_bytes(),
+            auth_value: Tpm2bAuth::default(),
+        })
+    }
+
+    /// Sets the authorization value of the PCR, which is empty unless it was changed with
+    /// `TPM2_PCR_SetAuthValue`.
+    pub fn set_auth_value<T: AsRef<[u8]> + ?Sized>(&mut self, auth_value: &T) -> TpmRcResult<()> {
+        self.auth_value = Tpm2bAuth::from_bytes(auth_value.as_ref())?;
+        Ok(())
+    }
+}
+
+impl Entity for PcrHandle {
+    fn handle(&self) -> TpmHandle {
+        TpmHandle(u32::from_be_bytes(self.name))
+    }
+
+    fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    fn auth_value(&self) -> &[u8] {
+        self.auth_value.get_buffer()
+    }
+}
+
+/// An HMAC or policy session that a command refers to by its handle, such as the policy session
+/// of a policy command. Its Name is its handle and it has no authorization value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SessionHandle {
+    name: [u8; size_of::<TpmHandle>()],
+}
+
+impl SessionHandle {
+    /// Creates the session at `handle`.
+    ///
+    /// # Errors:
+    /// Returns [TssTcsError::BadParameter] if `handle` is not a session handle.
+    pub fn new(handle: TpmHandle) -> TssResult<Self> {
+        let handle = check_type(handle, &[TpmHt::HMACSession, TpmHt::PolicySession])?;
+        Ok(SessionHandle {
+            name: handle.0.to_be_bytes(),
+        })
+    }
+}
+
+impl Entity for SessionHandle {
+    fn handle(&self) -> TpmHandle {
+        TpmHandle(u32::from_be_bytes(self.name))
+    }
+
+    fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    fn auth_value(&self) -> &[u8] {
+        &[]
+    }
+}
+
+#[cfg(test)]
+mod tests;
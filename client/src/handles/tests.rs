@@ -0,0 +1,151 @@
+use crate::tests::FakeTpm;
+use tpm2_rs_base::commands::{NvReadPublicResp, ReadPublicResp};
+
+use super::*;
+
+const NAME: [u8; 6] = [0x00, 0x0B, 0x01, 0x02, 0x03, 0x04];
+
+#[test]
+fn test_handle_types() {
+    assert!(ObjectHandle::new(TpmHandle(0x80000001), &NAME).is_ok());
+    assert!(ObjectHandle::new(TpmHandle(0x81000001), &NAME).is_ok());
+    assert_eq!(
+        ObjectHandle::new(TpmHandle::RHOwner, &NAME),
+        Err(TssTcsError::BadParameter.into())
+    );
+    assert!(NvIndexHandle::new(TpmHandle(0x01000001), &NAME).is_ok());
+    assert_eq!(
+        NvIndexHandle::new(TpmHandle(0x80000001), &NAME),
+        Err(TssTcsError::BadParameter.into())
+    );
+    assert!(HierarchyHandle::new(TpmHandle::RHEndorsement).is_ok());
+    assert_eq!(
+        HierarchyHandle::new(TpmHandle(0x00000007)),
+        Err(TssTcsError::BadParameter.into())
+    );
+    assert!(PcrHandle::new(TpmHandle(0x00000007)).is_ok());
+    assert_eq!(
+        PcrHandle::new(TpmHandle::RHOwner),
+        Err(TssTcsError::BadParameter.into())
+    );
+    assert!(SessionHandle::new(TpmHandle(0x02000000)).is_ok());
+    assert!(SessionHandle::new(TpmHandle(0x03000000)).is_ok());
+    assert_eq!(
+        SessionHandle::new(TpmHandle::RSPW),
+        Err(TssTcsError::BadParameter.into())
+    );
+}
+
+#[test]
+fn test_handles_are_names() {
+    let mut owner = HierarchyHandle::new(TpmHandle::RHOwner).unwrap();
+    owner.set_auth_value(b"owner").unwrap();
+    assert_eq!(owner.handle(), TpmHandle::RHOwner);
+    assert_eq!(owner.name(), &[0x40, 0x00, 0x00, 0x01]);
+    assert_eq!(owner.auth_value(), b"owner");
+
+    let pcr = PcrHandle::new(TpmHandle(0x00000010)).unwrap();
+    assert_eq!(pcr.handle(), TpmHandle(0x00000010));
+    assert_eq!(pcr.name(), &[0x00, 0x00, 0x00, 0x10]);
+    assert_eq!(pcr.auth_value(), b"");
+
+    let session = SessionHandle::new(TpmHandle(0x03000001)).unwrap();
+    assert_eq!(session.handle(), TpmHandle(0x03000001));
+    assert_eq!(session.name(), &[0x03, 0x00, 0x00, 0x01]);
+}
+
+#[test]
+fn test_object_read_public() {
+    let mut tpm = FakeTpm::default();
+    tpm.add_to_response(&ReadPublicResp {
+        out_public: Default::default(),
+        name: Tpm2bName::from_bytes(&NAME).unwrap(),
+        qualified_name: Default::default(),
+    });
+    let mut object = ObjectHandle::read_public(&mut tpm, TpmHandle(0x80000002)).unwrap();
+    object.set_auth_value(b"key").unwrap();
+    assert_eq!(object.handle(), TpmHandle(0x80000002));
+    assert_eq!(object.name(), &NAME);
+    assert_eq!(object.auth_value(), b"key");
+}
+
+#[test]
+fn test_nv_read_public() {
+    let mut tpm = FakeTpm::default();
+    tpm.add_to_response(&NvReadPublicResp {
+        nv_public: Default::default(),
+        nv_name: Tpm2bName::from_bytes(&NAME).unwrap(),
+    });
+    let index = NvIndexHandle::read_public(&mut tpm, TpmHandle(0x01000002)).unwrap();
+    assert_eq!(index.handle(), TpmHandle(0x01000002));
+    assert_eq!(index.name(), &NAME);
+}
+
+#[cfg(any(feature = "attestation", feature = "hmac-session", feature = "policy"))]
+mod public_name {
+    use sha2::{Digest, Sha256};
+    use tpm2_rs_base::{
+        PublicParmsAndId, Tpm2bDigest, TpmaNv, TpmaObject, TpmiRhNvIndex, TpmsEmpty,
+        TpmsKeyedHashParms, TpmtKeyedHashScheme,
+    };
+
+    use super::*;
+
+    /// Returns the Name of a SHA-256 entity with the marshaled public area `public`.
+    fn sha256_name(public: &[u8]) -> [u8; 34] {
+        let mut name = [0; 34];
+        name[..2].copy_from_slice(&TpmiAlgHash::SHA256.0.to_be_bytes());
+        name[2..].copy_from_slice(&Sha256::digest(public));
+        name
+    }
+
+    #[test]
+    fn test_object_from_public() {
+        let public = TpmtPublic {
+            name_alg: TpmiAlgHash::SHA256,
+            object_attributes: TpmaObject::USER_WITH_AUTH,
+            auth_policy: Default::default(),
+            parms_and_id: PublicParmsAndId::KeyedHash(
+                TpmsKeyedHashParms {
+                    scheme: TpmtKeyedHashScheme::Null(TpmsEmpty),
+                },
+                Tpm2bDigest::from_bytes(&[0xAA; 32]).unwrap(),
+            ),
+        };
+        let mut buffer = [0; 256];
+        let size = public.try_marshal(&mut buffer).unwrap();
+        let object = ObjectHandle::from_public(TpmHandle(0x80000001), &public).unwrap();
+        assert_eq!(object.name(), &sha256_name(&buffer[..size]));
+    }
+
+    #[test]
+    fn test_nv_index_from_public() {
+        let public = TpmsNvPublic {
+            nv_index: TpmiRhNvIndex::try_from(0x01000003).unwrap(),
+            name_alg: TpmiAlgHash::SHA256,
+            attributes: TpmaNv::AUTHREAD | TpmaNv::AUTHWRITE,
+            auth_policy: Default::default(),
+            data_size: 8,
+        };
+        let mut buffer = [0; 64];
+        let size = public.try_marshal(&mut buffer).unwrap();
+        let index = NvIndexHandle::from_public(&public).unwrap();
+        assert_eq!(index.handle(), TpmHandle(0x01000003));
+        assert_eq!(index.name(), &sha256_name(&buffer[..size]));
+    }
+
+    #[test]
+    fn test_unsupported_name_alg() {
+        let public = TpmsNvPublic {
+            nv_index: TpmiRhNvIndex::try_from(0x01000003).unwrap(),
+            name_alg: TpmiAlgHash::SM3256,
+            attributes: TpmaNv::AUTHREAD,
+            auth_policy: Default::default(),
+            data_size: 8,
+        };
+        assert_eq!(
+            NvIndexHandle::from_public(&public),
+            Err(TssTcsError::NotImplemented.into())
+        );
+    }
+}
@@ -1,16 +1,29 @@
 #![forbid(unsafe_code)]
 #![no_std]
 
-use connection::Connection;
+use connection::{AsyncConnection, Connection};
 use core::mem::size_of;
-use sessions::{AuthorizationArea, Session};
+use sessions::{
+    AuthorizationArea, AuthorizationArea1Plus, AuthorizationArea2Plus, CommandData, ResponseData,
+    Session,
+};
 use tpm2_rs_base::commands::*;
-use tpm2_rs_base::constants::{TpmCc, TpmSt};
-use tpm2_rs_base::errors::{TssError, TssResult, TssTcsError};
+use tpm2_rs_base::constants::{TpmCap, TpmCc, TpmHandle, TpmPt, TpmSt};
+use tpm2_rs_base::errors::{TpmRcError, TssError, TssResult, TssTcsError};
 use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
-use tpm2_rs_base::{TpmiStCommandTag, TpmsAuthResponse};
+use tpm2_rs_base::{
+    TpmiStCommandTag, TpmsAuthResponse, TpmsCapabilityData, TpmtSignature, TpmtTkVerified,
+};
 
+#[cfg(feature = "attestation")]
+pub mod attestation;
 pub mod connection;
+#[cfg(any(feature = "attestation", feature = "hmac-session", feature = "policy"))]
+mod crypto;
+pub mod decode;
+pub mod handles;
+#[cfg(feature = "policy")]
+pub mod policy;
 pub mod sessions;
 
 pub const CMD_BUFFER_SIZE: usize = 4096;
@@ -23,6 +36,283 @@ pub fn get_capability<T: Connection<Error: From<TssError>>>(
     run_command(command, tpm)
 }
 
+/// Returns the sizes of the largest command and response of the TPM (`TPM_PT_MAX_COMMAND_SIZE`
+/// and `TPM_PT_MAX_RESPONSE_SIZE`), which are the sizes of buffers for
+/// [`run_command_with_buffers`] that fit any command and response.
+pub fn max_buffer_sizes<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+) -> Result<(usize, usize), T::Error> {
+    let command = GetCapabilityCmd {
+        capability: TpmCap::TPMProperties,
+        property: TpmPt::MaxCommandSize,
+        property_count: 2,
+    };
+    // The command and its response are small, so they do not need buffers of the default sizes.
+    let mut cmd_buffer = [0u8; 32];
+    let mut resp_buffer = [0u8; 64];
+    let (resp, ()) = run_command_with_buffers(
+        &command,
+        (),
+        &[],
+        (),
+        &mut cmd_buffer,
+        &mut resp_buffer,
+        tpm,
+    )?;
+    let TpmsCapabilityData::TpmProperties(properties) = resp.capability_data else {
+        return Err(TssError::from(TssTcsError::TpmUnexpected).into());
+    };
+    let property = |wanted: TpmPt| {
+        properties
+            .tpm_property()
+            .iter()
+            .find(|property| property.property == wanted)
+            .map(|property| property.value as usize)
+            .ok_or(TssError::from(TssTcsError::TpmUnexpected))
+    };
+    Ok((
+        property(TpmPt::MaxCommandSize)?,
+        property(TpmPt::MaxResponseSize)?,
+    ))
+}
+
+/// Returns the parameters of the ECC curve in `command`.
+pub fn ecc_parameters<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+    command: &EccParametersCmd,
+) -> Result<EccParametersResp, T::Error> {
+    run_command(command, tpm)
+}
+
+/// Checks that the TPM supports the algorithm parameters in `command`.
+pub fn test_parms<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+    command: &TestParmsCmd,
+) -> Result<(), T::Error> {
+    run_command(command, tpm)
+}
+
+/// Loads an object that the TPM did not create, such as a public key, and returns its handle.
+pub fn load_external<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+    command: &LoadExternalCmd,
+) -> Result<(TpmHandle, LoadExternalResp), T::Error> {
+    let (resp, handle) = run_command_with_handles(command, (), (), tpm)?;
+    Ok((handle, resp))
+}
+
+/// Reads the public area and the Name of the object at `object_handle`.
+pub fn read_public<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+    object_handle: TpmHandle,
+    command: &ReadPublicCmd,
+) -> Result<ReadPublicResp, T::Error> {
+    run_command_with_handles(command, object_handle, (), tpm).map(|(resp, _)| resp)
+}
+
+/// Reads the public area and the Name of the NV index at `nv_index`.
+pub fn nv_read_public<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+    nv_index: TpmHandle,
+    command: &NvReadPublicCmd,
+) -> Result<NvReadPublicResp, T::Error> {
+    run_command_with_handles(command, nv_index, (), tpm).map(|(resp, _)| resp)
+}
+
+/// Flushes the transient object or session in `command` from the TPM.
+pub fn flush_context<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+    command: &FlushContextCmd,
+) -> Result<(), T::Error> {
+    run_command(command, tpm)
+}
+
+/// Encrypts or decrypts with the symmetric key at `key_handle`. To process data in several
+/// calls, pass the returned `iv_out` as the `iv_in` of the next call.
+pub fn encrypt_decrypt<T: Connection<Error: From<TssError>>, X: Session, Y: Session, Z: Session>(
+    tpm: &mut T,
+    key_handle: TpmHandle,
+    sessions: impl AuthorizationArea1Plus<X, Y, Z>,
+    command: &EncryptDecryptCmd,
+) -> Result<EncryptDecryptResp, T::Error> {
+    run_command_with_handles(command, key_handle, sessions, tpm).map(|(resp, _)| resp)
+}
+
+/// Same as [`encrypt_decrypt`], but with the data as the first parameter so that it can be
+/// encrypted by a session.
+pub fn encrypt_decrypt2<
+    T: Connection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+>(
+    tpm: &mut T,
+    key_handle: TpmHandle,
+    sessions: impl AuthorizationArea1Plus<X, Y, Z>,
+    command: &EncryptDecrypt2Cmd,
+) -> Result<EncryptDecrypt2Resp, T::Error> {
+    run_command_with_handles(command, key_handle, sessions, tpm).map(|(resp, _)| resp)
+}
+
+/// Hashes data in a single call. The returned ticket allows signing the digest with a restricted
+/// key if the data does not start with `TPM_GENERATED_VALUE`.
+pub fn hash<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+    command: &HashCmd,
+) -> Result<HashResp, T::Error> {
+    run_command(command, tpm)
+}
+
+/// Signs a digest with the key at `key_handle`.
+pub fn sign<T: Connection<Error: From<TssError>>, X: Session, Y: Session, Z: Session>(
+    tpm: &mut T,
+    key_handle: TpmHandle,
+    sessions: impl AuthorizationArea1Plus<X, Y, Z>,
+    command: &SignCmd,
+) -> Result<TpmtSignature, T::Error> {
+    run_command_with_handles(command, key_handle, sessions, tpm).map(|(resp, _)| resp.signature)
+}
+
+/// Verifies a signature with the key at `key_handle`. Returns the ticket that proves the
+/// signature was valid, or the error `TPM_RC_SIGNATURE` if it was not.
+pub fn verify_signature<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+    key_handle: TpmHandle,
+    command: &VerifySignatureCmd,
+) -> Result<TpmtTkVerified, T::Error> {
+    run_command_with_handles(command, key_handle, (), tpm).map(|(resp, _)| resp.validation)
+}
+
+/// Certifies that the object at `object_handle` is loaded, signed with the key at `sign_handle`.
+/// The sessions authorize the object and then the signing key.
+pub fn certify<T: Connection<Error: From<TssError>>, X: Session, Y: Session, Z: Session>(
+    tpm: &mut T,
+    object_handle: TpmHandle,
+    sign_handle: TpmHandle,
+    sessions: impl AuthorizationArea2Plus<X, Y, Z>,
+    command: &CertifyCmd,
+) -> Result<CertifyResp, T::Error> {
+    run_command_with_handles(command, (object_handle, sign_handle), sessions, tpm)
+        .map(|(resp, _)| resp)
+}
+
+/// Certifies that the TPM created the object at `object_handle` with the creation data in the
+/// creation ticket, signed with the key at `sign_handle`.
+pub fn certify_creation<
+    T: Connection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+>(
+    tpm: &mut T,
+    sign_handle: TpmHandle,
+    object_handle: TpmHandle,
+    sessions: impl AuthorizationArea1Plus<X, Y, Z>,
+    command: &CertifyCreationCmd,
+) -> Result<CertifyCreationResp, T::Error> {
+    run_command_with_handles(command, (sign_handle, object_handle), sessions, tpm)
+        .map(|(resp, _)| resp)
+}
+
+/// Quotes the selected PCRs, signed with the key at `sign_handle`.
+pub fn quote<T: Connection<Error: From<TssError>>, X: Session, Y: Session, Z: Session>(
+    tpm: &mut T,
+    sign_handle: TpmHandle,
+    sessions: impl AuthorizationArea1Plus<X, Y, Z>,
+    command: &QuoteCmd,
+) -> Result<QuoteResp, T::Error> {
+    run_command_with_handles(command, sign_handle, sessions, tpm).map(|(resp, _)| resp)
+}
+
+/// Attests to the time and clock of the TPM, signed with the key at `sign_handle`. The sessions
+/// authorize the endorsement hierarchy and then the signing key.
+pub fn get_time<T: Connection<Error: From<TssError>>, X: Session, Y: Session, Z: Session>(
+    tpm: &mut T,
+    sign_handle: TpmHandle,
+    sessions: impl AuthorizationArea2Plus<X, Y, Z>,
+    command: &GetTimeCmd,
+) -> Result<GetTimeResp, T::Error> {
+    run_command_with_handles(
+        command,
+        (TpmHandle::RHEndorsement, sign_handle),
+        sessions,
+        tpm,
+    )
+    .map(|(resp, _)| resp)
+}
+
+/// Attests to the audit digest of the session at `session_handle`, signed with the key at
+/// `sign_handle`. The sessions authorize the endorsement hierarchy and then the signing key.
+pub fn get_session_audit_digest<
+    T: Connection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+>(
+    tpm: &mut T,
+    sign_handle: TpmHandle,
+    session_handle: TpmHandle,
+    sessions: impl AuthorizationArea2Plus<X, Y, Z>,
+    command: &GetSessionAuditDigestCmd,
+) -> Result<GetSessionAuditDigestResp, T::Error> {
+    run_command_with_handles(
+        command,
+        (TpmHandle::RHEndorsement, sign_handle, session_handle),
+        sessions,
+        tpm,
+    )
+    .map(|(resp, _)| resp)
+}
+
+/// Attests to the command audit digest, signed with the key at `sign_handle`. The sessions
+/// authorize the endorsement hierarchy and then the signing key. A signed attestation clears the
+/// audit digest.
+pub fn get_command_audit_digest<
+    T: Connection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+>(
+    tpm: &mut T,
+    sign_handle: TpmHandle,
+    sessions: impl AuthorizationArea2Plus<X, Y, Z>,
+    command: &GetCommandAuditDigestCmd,
+) -> Result<GetCommandAuditDigestResp, T::Error> {
+    run_command_with_handles(
+        command,
+        (TpmHandle::RHEndorsement, sign_handle),
+        sessions,
+        tpm,
+    )
+    .map(|(resp, _)| resp)
+}
+
+/// Selects the commands recorded in the command audit digest, or changes its hash algorithm.
+/// `auth` is the owner or platform hierarchy.
+pub fn set_command_code_audit_status<
+    T: Connection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+>(
+    tpm: &mut T,
+    auth: TpmHandle,
+    sessions: impl AuthorizationArea1Plus<X, Y, Z>,
+    command: &SetCommandCodeAuditStatusCmd,
+) -> Result<(), T::Error> {
+    run_command_with_handles(command, auth, sessions, tpm).map(|(resp, _)| resp)
+}
+
+/// Returns the current policy digest of the policy session at `policy_session`, for example to
+/// compare it with the digest of a policy that was computed offline.
+pub fn policy_get_digest<T: Connection<Error: From<TssError>>>(
+    tpm: &mut T,
+    policy_session: TpmHandle,
+    command: &PolicyGetDigestCmd,
+) -> Result<PolicyGetDigestResp, T::Error> {
+    run_command_with_handles(command, policy_session, (), tpm).map(|(resp, _)| resp)
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, PartialEq, Marshalable)]
 pub struct CmdHeader {
@@ -57,6 +347,18 @@ pub fn run_command<CmdT: TpmCommand, T: Connection<Error: From<TssError>>>(
     Ok(run_command_with_handles(cmd, CmdT::Handles::default(), (), tpm)?.0)
 }
 
+/// Runs a command with default/unset handles over an async connection.
+pub async fn run_command_async<CmdT: TpmCommand, T: AsyncConnection<Error: From<TssError>>>(
+    cmd: &CmdT,
+    tpm: &mut T,
+) -> Result<CmdT::RespT, T::Error> {
+    Ok(
+        run_command_with_handles_async(cmd, CmdT::Handles::default(), (), tpm)
+            .await?
+            .0,
+    )
+}
+
 /// This function serializes the size of the authorization area. `buffer` should
 /// point to the beginning of the authorization area, specifically to the location
 /// where the size of the authorization area will be serialized. The `auth_offset`
@@ -69,38 +371,39 @@ fn marshal_auth_size(auth_offset: usize, buffer: &mut [u8]) -> TssResult<usize>
     Ok(auth_offset)
 }
 
-/// Adds any command sessions to the command buffer.
+/// Adds any command sessions that authorize `command` to the command buffer.
 pub fn write_command_sessions<
     X: Session,
     Y: Session,
     Z: Session,
     AA: AuthorizationArea<X, Y, Z>,
 >(
-    sessions: &AA,
+    sessions: &mut AA,
+    command: &CommandData,
     buffer: &mut [u8],
 ) -> TssResult<usize> {
     if sessions.is_empty() {
         return Ok(0);
     }
     let mut auth_offset = size_of::<u32>();
-    let (s1, s2, s3) = sessions.decompose_ref();
+    let (s1, s2, s3) = sessions.decompose_mut();
     let Some(s1) = s1 else {
         return marshal_auth_size(auth_offset, buffer);
     };
     auth_offset += s1
-        .get_auth_command()
+        .get_auth_command(command)?
         .try_marshal(&mut buffer[auth_offset..])?;
     let Some(s2) = s2 else {
         return marshal_auth_size(auth_offset, buffer);
     };
     auth_offset += s2
-        .get_auth_command()
+        .get_auth_command(command)?
         .try_marshal(&mut buffer[auth_offset..])?;
     let Some(s3) = s3 else {
         return marshal_auth_size(auth_offset, buffer);
     };
     auth_offset += s3
-        .get_auth_command()
+        .get_auth_command(command)?
         .try_marshal(&mut buffer[auth_offset..])?;
     marshal_auth_size(auth_offset, buffer)
 }
@@ -115,30 +418,73 @@ pub fn read_response_header(buffer: &[u8]) -> TssResult<(RespHeader, usize)> {
     Ok((resp_header, buffer.len() - unmarsh.len()))
 }
 
-/// Unmarshals any response sessions.
+/// Unmarshals any response sessions and validates that they authorize `response`.
 pub fn read_response_sessions<
     X: Session,
     Y: Session,
     Z: Session,
     AA: AuthorizationArea<X, Y, Z>,
 >(
-    sessions: &AA,
+    sessions: &mut AA,
+    response: &ResponseData,
     buffer: &mut UnmarshalBuf,
 ) -> TssResult<()> {
-    let (s1, s2, s3) = sessions.decompose_ref();
+    let (s1, s2, s3) = sessions.decompose_mut();
     let Some(s1) = s1 else { return Ok(()) };
     let auth = TpmsAuthResponse::try_unmarshal(buffer)?;
-    s1.validate_auth_response(&auth)?;
+    s1.validate_auth_response(response, &auth)?;
     let Some(s2) = s2 else { return Ok(()) };
     let auth = TpmsAuthResponse::try_unmarshal(buffer)?;
-    s2.validate_auth_response(&auth)?;
+    s2.validate_auth_response(response, &auth)?;
     let Some(s3) = s3 else { return Ok(()) };
     let auth = TpmsAuthResponse::try_unmarshal(buffer)?;
-    s3.validate_auth_response(&auth)?;
+    s3.validate_auth_response(response, &auth)?;
     Ok(())
 }
 
-/// Runs a command with provided handles and sessions.
+/// Returns the contents of the sized buffer that `parameters` start with.
+fn first_sized_buffer(parameters: &mut [u8]) -> TssResult<&mut [u8]> {
+    let size = u16::try_unmarshal(&mut UnmarshalBuf::new(parameters))? as usize;
+    parameters
+        .get_mut(size_of::<u16>()..size_of::<u16>() + size)
+        .ok_or(TpmRcError::Memory.into())
+}
+
+/// Lets the sessions encrypt the first command parameter.
+fn encrypt_command_parameter<X: Session, Y: Session, Z: Session, AA: AuthorizationArea<X, Y, Z>>(
+    sessions: &mut AA,
+    parameter: &mut [u8],
+) -> TssResult<()> {
+    let (s1, s2, s3) = sessions.decompose_mut();
+    let Some(s1) = s1 else { return Ok(()) };
+    s1.encrypt_command_parameter(parameter)?;
+    let Some(s2) = s2 else { return Ok(()) };
+    s2.encrypt_command_parameter(parameter)?;
+    let Some(s3) = s3 else { return Ok(()) };
+    s3.encrypt_command_parameter(parameter)
+}
+
+/// Lets the sessions decrypt the first response parameter.
+fn decrypt_response_parameter<
+    X: Session,
+    Y: Session,
+    Z: Session,
+    AA: AuthorizationArea<X, Y, Z>,
+>(
+    sessions: &mut AA,
+    parameter: &mut [u8],
+) -> TssResult<()> {
+    let (s1, s2, s3) = sessions.decompose_mut();
+    let Some(s1) = s1 else { return Ok(()) };
+    s1.decrypt_response_parameter(parameter)?;
+    let Some(s2) = s2 else { return Ok(()) };
+    s2.decrypt_response_parameter(parameter)?;
+    let Some(s3) = s3 else { return Ok(()) };
+    s3.decrypt_response_parameter(parameter)
+}
+
+/// Runs a command with provided handles and sessions, in buffers of [`CMD_BUFFER_SIZE`] and
+/// [`RESP_BUFFER_SIZE`] bytes on the stack. See [`run_command_with_buffers`] for small stacks.
 pub fn run_command_with_handles<
     CmdT: TpmCommand,
     T: Connection<Error: From<TssError>>,
@@ -152,44 +498,277 @@ pub fn run_command_with_handles<
     cmd_sessions: AA,
     tpm: &mut T,
 ) -> Result<(CmdT::RespT, CmdT::RespHandles), T::Error> {
-    let mut cmd_buffer = [0u8; CMD_BUFFER_SIZE];
+    let (mut cmd_buffer, mut resp_buffer) = default_buffers();
+    run_command_with_buffers(
+        cmd,
+        cmd_handles,
+        &[],
+        cmd_sessions,
+        &mut cmd_buffer,
+        &mut resp_buffer,
+        tpm,
+    )
+}
+
+/// Runs a command like [`run_command_with_handles`], where the sessions authorize the handles with
+/// the Names in `names`, such as the ones of [`handles::Entity`]. Handles without a Name in
+/// `names` are their own Name, which is only correct for entities other than objects and NV
+/// indexes.
+pub fn run_command_with_names<
+    CmdT: TpmCommand,
+    T: Connection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+    AA: AuthorizationArea<X, Y, Z>,
+>(
+    cmd: &CmdT,
+    cmd_handles: CmdT::Handles,
+    cmd_names: &[&[u8]],
+    cmd_sessions: AA,
+    tpm: &mut T,
+) -> Result<(CmdT::RespT, CmdT::RespHandles), T::Error> {
+    let (mut cmd_buffer, mut resp_buffer) = default_buffers();
+    run_command_with_buffers(
+        cmd,
+        cmd_handles,
+        cmd_names,
+        cmd_sessions,
+        &mut cmd_buffer,
+        &mut resp_buffer,
+        tpm,
+    )
+}
+
+/// Runs a command like [`run_command_with_names`], with the command in `cmd_buffer` and the
+/// response in `resp_buffer` instead of in buffers of [`CMD_BUFFER_SIZE`] and [`RESP_BUFFER_SIZE`]
+/// bytes on the stack. Buffers of the sizes that [`max_buffer_sizes`] returns fit any command and
+/// response of the TPM.
+///
+/// The handles and authorization area are written in place in `cmd_buffer`, so running a command
+/// takes no other buffer of its size.
+pub fn run_command_with_buffers<
+    CmdT: TpmCommand,
+    T: Connection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+    AA: AuthorizationArea<X, Y, Z>,
+>(
+    cmd: &CmdT,
+    cmd_handles: CmdT::Handles,
+    cmd_names: &[&[u8]],
+    mut cmd_sessions: AA,
+    cmd_buffer: &mut [u8],
+    resp_buffer: &mut [u8],
+    tpm: &mut T,
+) -> Result<(CmdT::RespT, CmdT::RespHandles), T::Error> {
+    let written = write_command(cmd, cmd_handles, cmd_names, &mut cmd_sessions, cmd_buffer)?;
+    tpm.transact(&cmd_buffer[..written], resp_buffer)?;
+    Ok(read_response::<CmdT, _, _, _, _>(
+        &mut cmd_sessions,
+        resp_buffer,
+    )?)
+}
+
+/// Runs a command like [`run_command_with_handles`] over an async connection.
+pub async fn run_command_with_handles_async<
+    CmdT: TpmCommand,
+    T: AsyncConnection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+    AA: AuthorizationArea<X, Y, Z>,
+>(
+    cmd: &CmdT,
+    cmd_handles: CmdT::Handles,
+    cmd_sessions: AA,
+    tpm: &mut T,
+) -> Result<(CmdT::RespT, CmdT::RespHandles), T::Error> {
+    let (mut cmd_buffer, mut resp_buffer) = default_buffers();
+    run_command_with_buffers_async(
+        cmd,
+        cmd_handles,
+        &[],
+        cmd_sessions,
+        &mut cmd_buffer,
+        &mut resp_buffer,
+        tpm,
+    )
+    .await
+}
+
+/// Runs a command like [`run_command_with_names`] over an async connection.
+pub async fn run_command_with_names_async<
+    CmdT: TpmCommand,
+    T: AsyncConnection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+    AA: AuthorizationArea<X, Y, Z>,
+>(
+    cmd: &CmdT,
+    cmd_handles: CmdT::Handles,
+    cmd_names: &[&[u8]],
+    cmd_sessions: AA,
+    tpm: &mut T,
+) -> Result<(CmdT::RespT, CmdT::RespHandles), T::Error> {
+    let (mut cmd_buffer, mut resp_buffer) = default_buffers();
+    run_command_with_buffers_async(
+        cmd,
+        cmd_handles,
+        cmd_names,
+        cmd_sessions,
+        &mut cmd_buffer,
+        &mut resp_buffer,
+        tpm,
+    )
+    .await
+}
+
+/// Runs a command like [`run_command_with_buffers`] over an async connection.
+pub async fn run_command_with_buffers_async<
+    CmdT: TpmCommand,
+    T: AsyncConnection<Error: From<TssError>>,
+    X: Session,
+    Y: Session,
+    Z: Session,
+    AA: AuthorizationArea<X, Y, Z>,
+>(
+    cmd: &CmdT,
+    cmd_handles: CmdT::Handles,
+    cmd_names: &[&[u8]],
+    mut cmd_sessions: AA,
+    cmd_buffer: &mut [u8],
+    resp_buffer: &mut [u8],
+    tpm: &mut T,
+) -> Result<(CmdT::RespT, CmdT::RespHandles), T::Error> {
+    let written = write_command(cmd, cmd_handles, cmd_names, &mut cmd_sessions, cmd_buffer)?;
+    tpm.transact(&cmd_buffer[..written], resp_buffer).await?;
+    Ok(read_response::<CmdT, _, _, _, _>(
+        &mut cmd_sessions,
+        resp_buffer,
+    )?)
+}
+
+/// Returns the command and response buffers of the runners that do not take buffers from the
+/// caller.
+fn default_buffers() -> ([u8; CMD_BUFFER_SIZE], [u8; RESP_BUFFER_SIZE]) {
+    ([0; CMD_BUFFER_SIZE], [0; RESP_BUFFER_SIZE])
+}
+
+/// Marshals `cmd` with its handles and sessions into `cmd_buffer` and returns the size of the
+/// command.
+fn write_command<
+    CmdT: TpmCommand,
+    X: Session,
+    Y: Session,
+    Z: Session,
+    AA: AuthorizationArea<X, Y, Z>,
+>(
+    cmd: &CmdT,
+    cmd_handles: CmdT::Handles,
+    cmd_names: &[&[u8]],
+    cmd_sessions: &mut AA,
+    cmd_buffer: &mut [u8],
+) -> TssResult<usize> {
     let mut cmd_header = CmdHeader::new(cmd_sessions.is_empty(), CmdT::CMD_CODE);
-    let mut written = cmd_header
-        .try_marshal(&mut cmd_buffer)
-        .map_err(TssError::from)?;
-
-    written += cmd_handles
-        .try_marshal(&mut cmd_buffer[written..])
-        .map_err(TssError::from)?;
-    written += write_command_sessions(&cmd_sessions, &mut cmd_buffer[written..])?;
-    written += cmd
-        .try_marshal(&mut cmd_buffer[written..])
-        .map_err(TssError::from)?;
+    let header_size = cmd_header.try_marshal(cmd_buffer)?;
+    let handles_size = cmd_handles.try_marshal(&mut cmd_buffer[header_size..])?;
+    let params_start = header_size + handles_size;
+
+    // The sessions authorize the parameters, which come after them in the command. So the
+    // parameters are marshaled right after the handles first, and moved to the end of the buffer
+    // while the sessions are written in place after the handles.
+    let params_size = cmd.try_marshal(&mut cmd_buffer[params_start..])?;
+    let params_end = cmd_buffer.len();
+    let params_moved = params_end - params_size;
+    cmd_buffer.copy_within(params_start..params_start + params_size, params_moved);
+    let (front, params) = cmd_buffer.split_at_mut(params_moved);
+    if CmdT::DECRYPT_PARAMETER {
+        let parameter = first_sized_buffer(params)?;
+        encrypt_command_parameter(cmd_sessions, parameter)?;
+    }
+    let (front, auth_area) = front.split_at_mut(params_start);
+
+    // The Name of a handle is the handle itself, unless it refers to an object or NV index.
+    let handles = &front[header_size..];
+    let mut names: [&[u8]; 3] = [&[]; 3];
+    let names_count = handles.len() / size_of::<TpmHandle>();
+    if cmd_names.len() > names_count {
+        return Err(TssTcsError::BadParameter.into());
+    }
+    for (i, (name, handle)) in names
+        .iter_mut()
+        .zip(handles.chunks(size_of::<TpmHandle>()))
+        .enumerate()
+    {
+        *name = cmd_names.get(i).copied().unwrap_or(handle);
+    }
+    let command = CommandData {
+        command_code: CmdT::CMD_CODE,
+        names: &names[..names_count],
+        parameters: params,
+    };
+    let sessions_size = write_command_sessions(cmd_sessions, &command, auth_area)?;
+
+    let written = params_start + sessions_size + params_size;
+    cmd_buffer.copy_within(params_moved..params_end, params_start + sessions_size);
 
     // Update the command size
     cmd_header.size = written as u32;
-    let _ = cmd_header
-        .try_marshal(&mut cmd_buffer)
-        .map_err(TssError::from)?;
-
-    let mut resp_buffer = [0u8; RESP_BUFFER_SIZE];
-    tpm.transact(&cmd_buffer[..written], &mut resp_buffer)?;
+    cmd_header.try_marshal(cmd_buffer)?;
+    Ok(written)
+}
 
-    let (resp_header, read) = read_response_header(&resp_buffer)?;
+/// Unmarshals the response to `CmdT` in `resp_buffer` after the sessions validate it.
+fn read_response<
+    CmdT: TpmCommand,
+    X: Session,
+    Y: Session,
+    Z: Session,
+    AA: AuthorizationArea<X, Y, Z>,
+>(
+    cmd_sessions: &mut AA,
+    resp_buffer: &mut [u8],
+) -> TssResult<(CmdT::RespT, CmdT::RespHandles)> {
+    let (resp_header, read) = read_response_header(resp_buffer)?;
     let resp_size = resp_header.size as usize;
     if resp_size > resp_buffer.len() {
-        return Err(TssError::from(TssTcsError::OutOfMemory).into());
+        return Err(TssTcsError::OutOfMemory.into());
     }
-    let mut unmarsh = UnmarshalBuf::new(&resp_buffer[read..resp_size]);
-    let resp_handles = CmdT::RespHandles::try_unmarshal(&mut unmarsh).map_err(TssError::from)?;
-    if resp_header.tag == TpmSt::Sessions {
-        let _param_size = u32::try_unmarshal(&mut unmarsh).map_err(TssError::from)?;
+    let body = &resp_buffer[read..resp_size];
+    let mut unmarsh = UnmarshalBuf::new(body);
+    let resp_handles = CmdT::RespHandles::try_unmarshal(&mut unmarsh)?;
+    let params_size = if resp_header.tag == TpmSt::Sessions {
+        u32::try_unmarshal(&mut unmarsh)? as usize
+    } else {
+        // Without sessions, the size of the parameters is only known after unmarshaling them.
+        let mut params = UnmarshalBuf::new(&body[body.len() - unmarsh.len()..]);
+        CmdT::RespT::try_unmarshal(&mut params)?;
+        unmarsh.len() - params.len()
+    };
+    let params_start = resp_size - unmarsh.len();
+    let response = ResponseData {
+        command_code: CmdT::CMD_CODE,
+        parameters: unmarsh
+            .get(params_size)
+            .ok_or(TssError::from(TpmRcError::Memory))?,
+    };
+    read_response_sessions(cmd_sessions, &response, &mut unmarsh)?;
+    if !unmarsh.is_empty() {
+        return Err(TssTcsError::TpmUnexpected.into());
     }
-    let resp = CmdT::RespT::try_unmarshal(&mut unmarsh).map_err(TssError::from)?;
-    read_response_sessions(&cmd_sessions, &mut unmarsh)?;
 
+    // The sessions authorize the parameters as they were sent, so they are decrypted after.
+    let params = &mut resp_buffer[params_start..params_start + params_size];
+    if CmdT::ENCRYPT_PARAMETER && resp_header.tag == TpmSt::Sessions {
+        decrypt_response_parameter(cmd_sessions, first_sized_buffer(params)?)?;
+    }
+    let mut unmarsh = UnmarshalBuf::new(params);
+    let resp = CmdT::RespT::try_unmarshal(&mut unmarsh)?;
     if !unmarsh.is_empty() {
-        return Err(TssError::from(TssTcsError::TpmUnexpected).into());
+        return Err(TssTcsError::TpmUnexpected.into());
     }
     Ok((resp, resp_handles))
 }
@@ -0,0 +1,458 @@
+//! Offline computation of policy digests.
+//!
+//! A [`PolicyBuilder`] has a method for each policy command that extends its digest the same way
+//! the TPM extends the `policyDigest` of a policy session, so that the `authPolicy` of an object
+//! can be computed without a TPM. The builder remembers each assertion as a [`PolicyStep`], so
+//! that [`PolicyBuilder::replay`] can later satisfy the policy in a live policy session.
+//!
+//! Policies with alternatives are built from a builder for each branch: the digests of all
+//! branches are combined with [`PolicyBuilder::policy_or`] on the builder of the branch that is
+//! going to be replayed.
+
+use core::mem::size_of;
+use tpm2_rs_base::commands::{
+    PolicyAuthValueCmd, PolicyCommandCodeCmd, PolicyCounterTimerCmd, PolicyCpHashCmd,
+    PolicyLocalityCmd, PolicyNameHashCmd, PolicyNvCmd, PolicyOrCmd, PolicyPasswordCmd,
+    PolicyPcrCmd, TpmCommand,
+};
+use tpm2_rs_base::constants::{TpmCc, TpmEo, TpmHandle};
+use tpm2_rs_base::errors::{TssError, TssResult, TssTcsError};
+use tpm2_rs_base::marshal::Marshalable;
+use tpm2_rs_base::{
+    Tpm2bDigest, Tpm2bName, Tpm2bNonce, Tpm2bSimple, TpmiAlgHash, TpmlPcrSelection,
+};
+
+use crate::connection::Connection;
+use crate::crypto::digest;
+use crate::run_command_with_handles;
+
+/// The largest number of assertions in a [`PolicyBuilder`].
+pub const MAX_POLICY_STEPS: usize = 8;
+
+/// The largest number of branches of `TPM2_PolicyOR`.
+const MAX_OR_BRANCHES: usize = 8;
+
+/// The size of the largest digest that a policy can use.
+const MAX_DIGEST_SIZE: usize = 64;
+
+/// An assertion of a policy and the parameters of the policy command that asserts it.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PolicyStep {
+    /// `TPM2_PolicySigned` with a signature by the key with the Name `auth_object`.
+    Signed {
+        auth_object: Tpm2bName,
+        policy_ref: Tpm2bNonce,
+    },
+    /// `TPM2_PolicySecret` with the authorization of the entity with the Name `auth_handle`.
+    Secret {
+        auth_handle: Tpm2bName,
+        policy_ref: Tpm2bNonce,
+    },
+    /// `TPM2_PolicyOR`.
+    Or(PolicyOrCmd),
+    /// `TPM2_PolicyPCR`.
+    Pcr(PolicyPcrCmd),
+    /// `TPM2_PolicyLocality`.
+    Locality(PolicyLocalityCmd),
+    /// `TPM2_PolicyNV` on the NV index with the Name `nv_index`.
+    Nv {
+        nv_index: Tpm2bName,
+        command: PolicyNvCmd,
+    },
+    /// `TPM2_PolicyCounterTimer`.
+    CounterTimer(PolicyCounterTimerCmd),
+    /// `TPM2_PolicyCommandCode`.
+    CommandCode(PolicyCommandCodeCmd),
+    /// `TPM2_PolicyCpHash`.
+    CpHash(PolicyCpHashCmd),
+    /// `TPM2_PolicyNameHash`.
+    NameHash(PolicyNameHashCmd),
+    /// `TPM2_PolicyAuthorize` of a policy approved by the key with the Name `key_sign`.
+    Authorize {
+        key_sign: Tpm2bName,
+        policy_ref: Tpm2bNonce,
+    },
+    /// `TPM2_PolicyAuthValue`.
+    AuthValue,
+    /// `TPM2_PolicyPassword`.
+    Password,
+}
+
+/// Computes a policy digest from the assertions of the policy.
+///
+/// # Usage:
+/// ```
+/// use tpm2_rs_base::commands::PolicyCommandCodeCmd;
+/// use tpm2_rs_base::constants::TpmCc;
+/// use tpm2_rs_base::TpmiAlgHash;
+/// use tpm2_rs_client::policy::PolicyBuilder;
+///
+/// let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+/// policy
+///     .policy_auth_value()
+///     .unwrap()
+///     .policy_command_code(&PolicyCommandCodeCmd { code: TpmCc::Unseal })
+///     .unwrap();
+/// assert_eq!(policy.steps().len(), 2);
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PolicyBuilder {
+    alg: TpmiAlgHash,
+    digest: Tpm2bDigest,
+    steps: [PolicyStep; MAX_POLICY_STEPS],
+    count: usize,
+}
+
+impl PolicyBuilder {
+    /// Starts an empty policy, whose digest is a Zero Digest of `alg`.
+    ///
+    /// # Errors:
+    /// Returns [`TssTcsError::NotImplemented`] if `alg` is not supported.
+    pub fn new(alg: TpmiAlgHash) -> TssResult<Self> {
+        Ok(PolicyBuilder {
+            alg,
+            digest: zero_digest(alg)?,
+            steps: [PolicyStep::AuthValue; MAX_POLICY_STEPS],
+            count: 0,
+        })
+    }
+
+    /// Returns the hash algorithm of the policy.
+    pub fn alg(&self) -> TpmiAlgHash {
+        self.alg
+    }
+
+    /// Returns the digest of the policy, which is the `authPolicy` of the objects it protects.
+    pub fn digest(&self) -> &Tpm2bDigest {
+        &self.digest
+    }
+
+    /// Returns the assertions of the policy in the order they have to be replayed.
+    pub fn steps(&self) -> &[PolicyStep] {
+        &self.steps[..self.count]
+    }
+
+    /// Remembers `step` and extends the digest with the command code of the step followed by
+    /// `data`, which are its arguments.
+    fn extend(&mut self, step: PolicyStep, command_code: TpmCc, data: &[&[u8]]) -> TssResult<()> {
+        self.extend_from(self.digest, step, command_code, data)
+    }
+
+    /// Same as [`extend`](Self::extend), but replaces the digest with `start` first, as the
+    /// commands that start the policy over do.
+    fn extend_from(
+        &mut self,
+        start: Tpm2bDigest,
+        step: PolicyStep,
+        command_code: TpmCc,
+        data: &[&[u8]],
+    ) -> TssResult<()> {
+        let slot = self
+            .steps
+            .get_mut(self.count)
+            .ok_or(TssTcsError::OutOfMemory)?;
+        let command_code = command_code.0.to_be_bytes();
+        let mut parts = [&[][..]; MAX_OR_BRANCHES + 2];
+        parts[0] = start.get_buffer();
+        parts[1] = &command_code;
+        parts
+            .get_mut(2..data.len() + 2)
+            .ok_or(TssTcsError::BadParameter)?
+            .copy_from_slice(data);
+        let extended = digest(self.alg, &parts[..data.len() + 2])?;
+        *slot = step;
+        self.count += 1;
+        self.digest = extended;
+        Ok(())
+    }
+
+    /// Extends the digest with the `policyRef` of an authorization, which TPM2_PolicySigned,
+    /// TPM2_PolicySecret and TPM2_PolicyAuthorize do after extending it with their arguments.
+    fn extend_policy_ref(&mut self, policy_ref: &Tpm2bNonce) -> TssResult<()> {
+        self.digest = digest(
+            self.alg,
+            &[self.digest.get_buffer(), policy_ref.get_buffer()],
+        )?;
+        Ok(())
+    }
+
+    /// Checks that `digest` has the size of the digests of the policy.
+    fn check_size(&self, digest: &Tpm2bDigest) -> TssResult<()> {
+        if digest.get_size() != self.digest.get_size() {
+            return Err(TssTcsError::BadParameter.into());
+        }
+        Ok(())
+    }
+
+    /// Asserts `TPM2_PolicySigned` with a signature by the key named `auth_object`.
+    pub fn policy_signed(
+        &mut self,
+        auth_object: &Tpm2bName,
+        policy_ref: &Tpm2bNonce,
+    ) -> TssResult<&mut Self> {
+        let step = PolicyStep::Signed {
+            auth_object: *auth_object,
+            policy_ref: *policy_ref,
+        };
+        self.extend(step, TpmCc::PolicySigned, &[auth_object.get_buffer()])?;
+        self.extend_policy_ref(policy_ref)?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicySecret` with the authorization of the entity named `auth_handle`.
+    pub fn policy_secret(
+        &mut self,
+        auth_handle: &Tpm2bName,
+        policy_ref: &Tpm2bNonce,
+    ) -> TssResult<&mut Self> {
+        let step = PolicyStep::Secret {
+            auth_handle: *auth_handle,
+            policy_ref: *policy_ref,
+        };
+        self.extend(step, TpmCc::PolicySecret, &[auth_handle.get_buffer()])?;
+        self.extend_policy_ref(policy_ref)?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyOR` of the digests of at least two branches. When the policy is
+    /// replayed, the digest of this builder before the assertion has to be one of them.
+    pub fn policy_or(&mut self, command: &PolicyOrCmd) -> TssResult<&mut Self> {
+        let digests = command.p_hash_list.digests();
+        if digests.len() < 2 {
+            return Err(TssTcsError::BadParameter.into());
+        }
+        let mut data = [&[][..]; MAX_OR_BRANCHES];
+        for (part, digest) in data.iter_mut().zip(digests) {
+            self.check_size(digest)?;
+            *part = digest.get_buffer();
+        }
+        // The policy starts over from a Zero Digest.
+        self.extend_from(
+            zero_digest(self.alg)?,
+            PolicyStep::Or(*command),
+            TpmCc::PolicyOR,
+            &data[..digests.len()],
+        )?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyPCR` of the PCRs in `pcrs` with the digest of their values, which
+    /// [`pcr_digest`] computes. The digest can't be left empty, because the current PCR values
+    /// are not known offline.
+    pub fn policy_pcr(&mut self, command: &PolicyPcrCmd) -> TssResult<&mut Self> {
+        self.check_size(&command.pcr_digest)?;
+        let mut pcrs = [0; size_of::<TpmlPcrSelection>()];
+        let size = command.pcrs.try_marshal(&mut pcrs)?;
+        self.extend(
+            PolicyStep::Pcr(*command),
+            TpmCc::PolicyPCR,
+            &[&pcrs[..size], command.pcr_digest.get_buffer()],
+        )?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyLocality`.
+    pub fn policy_locality(&mut self, command: &PolicyLocalityCmd) -> TssResult<&mut Self> {
+        self.extend(
+            PolicyStep::Locality(*command),
+            TpmCc::PolicyLocality,
+            &[&[command.locality.0]],
+        )?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyNV` on the contents of the NV index named `nv_index`.
+    pub fn policy_nv(
+        &mut self,
+        nv_index: &Tpm2bName,
+        command: &PolicyNvCmd,
+    ) -> TssResult<&mut Self> {
+        let args = operand_digest(
+            self.alg,
+            command.operand_b.get_buffer(),
+            command.offset,
+            command.operation,
+        )?;
+        let step = PolicyStep::Nv {
+            nv_index: *nv_index,
+            command: *command,
+        };
+        self.extend(
+            step,
+            TpmCc::PolicyNV,
+            &[args.get_buffer(), nv_index.get_buffer()],
+        )?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyCounterTimer`.
+    pub fn policy_counter_timer(
+        &mut self,
+        command: &PolicyCounterTimerCmd,
+    ) -> TssResult<&mut Self> {
+        let args = operand_digest(
+            self.alg,
+            command.operand_b.get_buffer(),
+            command.offset,
+            command.operation,
+        )?;
+        self.extend(
+            PolicyStep::CounterTimer(*command),
+            TpmCc::PolicyCounterTimer,
+            &[args.get_buffer()],
+        )?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyCommandCode`.
+    pub fn policy_command_code(&mut self, command: &PolicyCommandCodeCmd) -> TssResult<&mut Self> {
+        self.extend(
+            PolicyStep::CommandCode(*command),
+            TpmCc::PolicyCommandCode,
+            &[&command.code.0.to_be_bytes()],
+        )?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyCpHash`.
+    pub fn policy_cp_hash(&mut self, command: &PolicyCpHashCmd) -> TssResult<&mut Self> {
+        self.check_size(&command.cp_hash_a)?;
+        self.extend(
+            PolicyStep::CpHash(*command),
+            TpmCc::PolicyCpHash,
+            &[command.cp_hash_a.get_buffer()],
+        )?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyNameHash`.
+    pub fn policy_name_hash(&mut self, command: &PolicyNameHashCmd) -> TssResult<&mut Self> {
+        self.check_size(&command.name_hash)?;
+        self.extend(
+            PolicyStep::NameHash(*command),
+            TpmCc::PolicyNameHash,
+            &[command.name_hash.get_buffer()],
+        )?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyAuthorize` of any policy that the key named `key_sign` approves with
+    /// `policy_ref`. The policy starts over, so the assertions before it only matter for replays.
+    pub fn policy_authorize(
+        &mut self,
+        key_sign: &Tpm2bName,
+        policy_ref: &Tpm2bNonce,
+    ) -> TssResult<&mut Self> {
+        let step = PolicyStep::Authorize {
+            key_sign: *key_sign,
+            policy_ref: *policy_ref,
+        };
+        self.extend_from(
+            zero_digest(self.alg)?,
+            step,
+            TpmCc::PolicyAuthorize,
+            &[key_sign.get_buffer()],
+        )?;
+        self.extend_policy_ref(policy_ref)?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyAuthValue`.
+    pub fn policy_auth_value(&mut self) -> TssResult<&mut Self> {
+        self.extend(PolicyStep::AuthValue, TpmCc::PolicyAuthValue, &[])?;
+        Ok(self)
+    }
+
+    /// Asserts `TPM2_PolicyPassword`, which results in the same digest as
+    /// [`policy_auth_value`](Self::policy_auth_value).
+    pub fn policy_password(&mut self) -> TssResult<&mut Self> {
+        self.extend(PolicyStep::Password, TpmCc::PolicyAuthValue, &[])?;
+        Ok(self)
+    }
+
+    /// Replays the assertions of the policy in the policy session `policy_session`.
+    ///
+    /// The assertions that need an authorization or other input from the caller, which are
+    /// [`PolicyStep::Signed`], [`PolicyStep::Secret`], [`PolicyStep::Nv`] and
+    /// [`PolicyStep::Authorize`], are passed to `authorize`, which has to send the policy
+    /// command itself. All other assertions are sent by this function.
+    pub fn replay<T, F>(
+        &self,
+        tpm: &mut T,
+        policy_session: TpmHandle,
+        mut authorize: F,
+    ) -> Result<(), T::Error>
+    where
+        T: Connection<Error: From<TssError>>,
+        F: FnMut(&mut T, &PolicyStep) -> Result<(), T::Error>,
+    {
+        for step in self.steps() {
+            match step {
+                PolicyStep::Signed { .. }
+                | PolicyStep::Secret { .. }
+                | PolicyStep::Nv { .. }
+                | PolicyStep::Authorize { .. } => authorize(tpm, step),
+                PolicyStep::Or(command) => run_policy_command(tpm, policy_session, command),
+                PolicyStep::Pcr(command) => run_policy_command(tpm, policy_session, command),
+                PolicyStep::Locality(command) => run_policy_command(tpm, policy_session, command),
+                PolicyStep::CounterTimer(command) => {
+                    run_policy_command(tpm, policy_session, command)
+                }
+                PolicyStep::CommandCode(command) => {
+                    run_policy_command(tpm, policy_session, command)
+                }
+                PolicyStep::CpHash(command) => run_policy_command(tpm, policy_session, command),
+                PolicyStep::NameHash(command) => run_policy_command(tpm, policy_session, command),
+                PolicyStep::AuthValue => {
+                    run_policy_command(tpm, policy_session, &PolicyAuthValueCmd {})
+                }
+                PolicyStep::Password => {
+                    run_policy_command(tpm, policy_session, &PolicyPasswordCmd {})
+                }
+            }?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the digest of the values of the PCRs that `TPM2_PolicyPCR` asserts, in the order of
+/// the selection: banks in the order they are listed and PCRs in ascending order within each bank.
+pub fn pcr_digest(alg: TpmiAlgHash, pcr_values: &[&[u8]]) -> TssResult<Tpm2bDigest> {
+    digest(alg, pcr_values)
+}
+
+/// Returns a digest of `alg` that is all zeros.
+fn zero_digest(alg: TpmiAlgHash) -> TssResult<Tpm2bDigest> {
+    let size = digest(alg, &[])?.get_size() as usize;
+    Ok(Tpm2bDigest::from_bytes(&[0; MAX_DIGEST_SIZE][..size])?)
+}
+
+/// Computes the digest of the arguments of `TPM2_PolicyNV` and `TPM2_PolicyCounterTimer`.
+fn operand_digest(
+    alg: TpmiAlgHash,
+    operand_b: &[u8],
+    offset: u16,
+    operation: TpmEo,
+) -> TssResult<Tpm2bDigest> {
+    digest(
+        alg,
+        &[operand_b, &offset.to_be_bytes(), &operation.0.to_be_bytes()],
+    )
+}
+
+/// Sends a policy command for `policy_session`, which must be the only handle of the command.
+fn run_policy_command<T, C>(
+    tpm: &mut T,
+    policy_session: TpmHandle,
+    command: &C,
+) -> Result<(), T::Error>
+where
+    T: Connection<Error: From<TssError>>,
+    C: TpmCommand<Handles = TpmHandle, RespT = (), RespHandles = ()>,
+{
+    run_command_with_handles(command, policy_session, (), tpm).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests;
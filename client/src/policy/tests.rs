@@ -0,0 +1,232 @@
+use hex_literal::hex;
+use sha2::{Digest, Sha256};
+use tpm2_rs_base::constants::TpmSt;
+use tpm2_rs_base::{TpmaLocality, TpmlDigest, TpmsPcrSelection};
+
+use super::*;
+
+/// The policy of objects that can only be used with their authorization value.
+const AUTH_VALUE_POLICY: [u8; 32] =
+    hex!("8fcd2169ab92694e0c633f1ab772842b8241bbc20288981fc7ac1eddc1fddb0e");
+/// The policy of the default endorsement key templates, which require the authorization of the
+/// endorsement hierarchy.
+const ENDORSEMENT_POLICY: [u8; 32] =
+    hex!("837197674484b3f81a90cc8d46a5d724fd52d76e06520b64f2a1da1b331469aa");
+
+const POLICY_SESSION: TpmHandle = TpmHandle(0x03000000);
+
+fn sha256(data: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in data {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn name(handle: TpmHandle) -> Tpm2bName {
+    Tpm2bName::from_bytes(&handle.0.to_be_bytes()).unwrap()
+}
+
+/// A TPM that records the command codes it receives and responds with success.
+#[derive(Default)]
+struct RecordingTpm {
+    commands: [u32; MAX_POLICY_STEPS],
+    count: usize,
+}
+impl Connection for RecordingTpm {
+    type Error = TssError;
+    fn transact<'a>(&mut self, cmd: &[u8], rsp: &'a mut [u8]) -> TssResult<&'a mut [u8]> {
+        let command_code = u32::from_be_bytes(cmd[6..10].try_into().unwrap());
+        self.commands[self.count] = command_code;
+        self.count += 1;
+        let size = TpmSt::NoSessions.try_marshal(rsp)?;
+        let size = size + 10u32.try_marshal(&mut rsp[size..])?;
+        let size = size + 0u32.try_marshal(&mut rsp[size..])?;
+        Ok(&mut rsp[..size])
+    }
+}
+impl RecordingTpm {
+    fn commands(&self) -> &[u32] {
+        &self.commands[..self.count]
+    }
+}
+
+#[test]
+fn test_empty_policy_is_zero_digest() {
+    let policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    assert_eq!(policy.digest().get_buffer(), &[0; 32]);
+    assert!(policy.steps().is_empty());
+}
+
+#[test]
+fn test_unsupported_alg() {
+    assert!(PolicyBuilder::new(TpmiAlgHash::SM3256).is_err());
+}
+
+#[test]
+fn test_auth_value_policy() {
+    let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    policy.policy_auth_value().unwrap();
+    assert_eq!(policy.digest().get_buffer(), &AUTH_VALUE_POLICY);
+
+    let mut password = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    password.policy_password().unwrap();
+    assert_eq!(password.digest(), policy.digest());
+    assert_eq!(password.steps(), &[PolicyStep::Password]);
+}
+
+#[test]
+fn test_endorsement_policy() {
+    let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    policy
+        .policy_secret(&name(TpmHandle::RHEndorsement), &Tpm2bNonce::default())
+        .unwrap();
+    assert_eq!(policy.digest().get_buffer(), &ENDORSEMENT_POLICY);
+}
+
+#[test]
+fn test_pcr_policy() {
+    let pcrs = TpmlPcrSelection::new(&[TpmsPcrSelection {
+        hash: TpmiAlgHash::SHA256,
+        sizeof_select: 3,
+        pcr_select: [0x01, 0x00, 0x00, 0x00],
+    }])
+    .unwrap();
+    let pcr_digest = pcr_digest(TpmiAlgHash::SHA256, &[&[0; 32]]).unwrap();
+    let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    policy
+        .policy_pcr(&PolicyPcrCmd { pcr_digest, pcrs })
+        .unwrap();
+
+    let expected = sha256(&[
+        &[0; 32],
+        &TpmCc::PolicyPCR.0.to_be_bytes(),
+        // One selection of 3 bytes for SHA-256 with PCR 0.
+        &hex!("00000001 000b 03 010000"),
+        pcr_digest.get_buffer(),
+    ]);
+    assert_eq!(policy.digest().get_buffer(), &expected);
+}
+
+#[test]
+fn test_digest_size_must_match() {
+    let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    let short = Tpm2bDigest::from_bytes(&[0; 20]).unwrap();
+    assert_eq!(
+        policy.policy_cp_hash(&PolicyCpHashCmd { cp_hash_a: short }),
+        Err(TssTcsError::BadParameter.into())
+    );
+    let or = PolicyOrCmd {
+        p_hash_list: TpmlDigest::new(&[*policy.digest(), short]).unwrap(),
+    };
+    assert_eq!(policy.policy_or(&or), Err(TssTcsError::BadParameter.into()));
+    assert!(policy.steps().is_empty());
+}
+
+#[test]
+fn test_or_starts_over() {
+    let mut branch = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    branch.policy_auth_value().unwrap();
+    let mut other = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    other
+        .policy_secret(&name(TpmHandle::RHEndorsement), &Tpm2bNonce::default())
+        .unwrap();
+    let or = PolicyOrCmd {
+        p_hash_list: TpmlDigest::new(&[*branch.digest(), *other.digest()]).unwrap(),
+    };
+
+    // Both branches result in the same digest.
+    branch.policy_or(&or).unwrap();
+    other.policy_or(&or).unwrap();
+    assert_eq!(branch.digest(), other.digest());
+    let expected = sha256(&[
+        &[0; 32],
+        &TpmCc::PolicyOR.0.to_be_bytes(),
+        &AUTH_VALUE_POLICY,
+        &ENDORSEMENT_POLICY,
+    ]);
+    assert_eq!(branch.digest().get_buffer(), &expected);
+
+    // A single branch is not an alternative.
+    let single = PolicyOrCmd {
+        p_hash_list: TpmlDigest::new(&[*branch.digest()]).unwrap(),
+    };
+    assert_eq!(
+        branch.policy_or(&single),
+        Err(TssTcsError::BadParameter.into())
+    );
+}
+
+#[test]
+fn test_authorize_starts_over() {
+    let key_sign = Tpm2bName::from_bytes(&[0x00, 0x0b, 0x01, 0x02]).unwrap();
+    let policy_ref = Tpm2bNonce::from_bytes(b"ref").unwrap();
+    let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    policy
+        .policy_auth_value()
+        .unwrap()
+        .policy_authorize(&key_sign, &policy_ref)
+        .unwrap();
+    let args = sha256(&[
+        &[0; 32],
+        &TpmCc::PolicyAuthorize.0.to_be_bytes(),
+        key_sign.get_buffer(),
+    ]);
+    let expected = sha256(&[&args, b"ref"]);
+    assert_eq!(policy.digest().get_buffer(), &expected);
+    assert_eq!(policy.steps().len(), 2);
+}
+
+#[test]
+fn test_sha1_policy() {
+    let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA1).unwrap();
+    policy
+        .policy_locality(&PolicyLocalityCmd {
+            locality: TpmaLocality::LOC_ZERO,
+        })
+        .unwrap();
+    assert_eq!(policy.digest().get_size(), 20);
+}
+
+#[test]
+fn test_too_many_steps() {
+    let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    for _ in 0..MAX_POLICY_STEPS {
+        policy.policy_auth_value().unwrap();
+    }
+    let digest = *policy.digest();
+    assert_eq!(
+        policy.policy_auth_value(),
+        Err(TssTcsError::OutOfMemory.into())
+    );
+    assert_eq!(policy.digest(), &digest);
+}
+
+#[test]
+fn test_replay() {
+    let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    policy
+        .policy_secret(&name(TpmHandle::RHEndorsement), &Tpm2bNonce::default())
+        .unwrap()
+        .policy_command_code(&PolicyCommandCodeCmd {
+            code: TpmCc::Unseal,
+        })
+        .unwrap()
+        .policy_password()
+        .unwrap();
+
+    let mut tpm = RecordingTpm::default();
+    let mut authorized = 0;
+    policy
+        .replay(&mut tpm, POLICY_SESSION, |_, step| {
+            assert!(matches!(step, PolicyStep::Secret { .. }));
+            authorized += 1;
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(authorized, 1);
+    assert_eq!(
+        tpm.commands(),
+        &[TpmCc::PolicyCommandCode.0, TpmCc::PolicyPassword.0]
+    );
+}
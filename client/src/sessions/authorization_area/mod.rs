@@ -9,6 +9,7 @@ use crate::sessions::Session;
 /// Check top level module documentation.
 pub trait AuthorizationArea<T: Session, U: Session, V: Session> {
     fn decompose_ref(&self) -> (Option<&T>, Option<&U>, Option<&V>);
+    fn decompose_mut(&mut self) -> (Option<&mut T>, Option<&mut U>, Option<&mut V>);
     fn is_empty(&self) -> bool {
         self.decompose_ref().0.is_none()
     }
@@ -6,6 +6,9 @@ impl<T: Session, U: Session> AuthorizationArea<T, U, NoSession> for (T, U) {
     fn decompose_ref(&self) -> (Option<&T>, Option<&U>, Option<&NoSession>) {
         (Some(&self.0), Some(&self.1), None)
     }
+    fn decompose_mut(&mut self) -> (Option<&mut T>, Option<&mut U>, Option<&mut NoSession>) {
+        (Some(&mut self.0), Some(&mut self.1), None)
+    }
 }
 
 impl<T: Session, U: Session> AuthorizationArea1Plus<T, U, NoSession> for (T, U) {
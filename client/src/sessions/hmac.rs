@@ -0,0 +1,479 @@
+use crate::connection::Connection;
+use crate::crypto::{aes_cfb, constant_time_eq, cp_hash, digest, hmac, kdfa, kdfa_xor, rp_hash};
+use crate::handles::Entity;
+use crate::sessions::{CommandData, ResponseData, Session, SessionSalt};
+use crate::{flush_context, run_command_with_handles};
+use tpm2_rs_base::commands::{FlushContextCmd, StartAuthSessionCmd};
+use tpm2_rs_base::constants::{TpmHandle, TpmHc, TpmSe};
+use tpm2_rs_base::errors::{TpmRcResult, TssError, TssResult, TssTcsError};
+use tpm2_rs_base::{
+    Tpm2bAuth, Tpm2bDigest, Tpm2bName, Tpm2bNonce, Tpm2bSimple, TpmaSession, TpmiAlgHash,
+    TpmiAlgSymMode, TpmiShAuthSession, TpmsAuthCommand, TpmsAuthResponse, TpmtSymDef,
+};
+
+/// The size of the largest digest that a session can use.
+const MAX_DIGEST_SIZE: usize = 64;
+
+/// An HMAC session, which proves knowledge of the authorization value of an entity without
+/// sending it to the TPM.
+///
+/// The session has to be passed by mutable reference to keep track of the nonces, which change
+/// with each command. The client has no source of randomness itself, so the caller provides the
+/// `nonce_source` that fills each new caller nonce with random bytes.
+///
+/// With the [`TpmaSession::DECRYPT`] and [`TpmaSession::ENCRYPT`] attributes, the session
+/// encrypts the first command and response parameter, if it is a sized buffer, with the
+/// symmetric algorithm that the session was started with.
+///
+/// # Usage:
+/// ```no_run
+/// # use tpm2_rs_base::commands::SetCommandCodeAuditStatusCmd;
+/// # use tpm2_rs_base::errors::TssError;
+/// # fn example<T: tpm2_rs_client::connection::Connection<Error = TssError>>(
+/// #     tpm: &mut T,
+/// #     fill_random: impl FnMut(&mut [u8]),
+/// #     command: &SetCommandCodeAuditStatusCmd,
+/// # ) -> Result<(), TssError> {
+/// use tpm2_rs_base::constants::TpmHandle;
+/// use tpm2_rs_base::{TpmiAlgHash, TpmsEmpty, TpmtSymDef};
+/// use tpm2_rs_client::set_command_code_audit_status;
+/// use tpm2_rs_client::sessions::HmacSession;
+///
+/// let symmetric = TpmtSymDef::Null(TpmsEmpty, TpmsEmpty);
+/// let mut session = HmacSession::start(tpm, TpmiAlgHash::SHA256, symmetric, fill_random)?;
+/// session.set_auth_value(b"owner password")?;
+/// set_command_code_audit_status(tpm, TpmHandle::RHOwner, &mut session, command)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct HmacSession<N> {
+    handle: TpmHandle,
+    auth_hash: TpmiAlgHash,
+    symmetric: TpmtSymDef,
+    session_key: Tpm2bDigest,
+    // The Name and the authorization value of the entity that the session is bound to.
+    bind_name: Tpm2bName,
+    bind_auth: Tpm2bAuth,
+    attributes: TpmaSession,
+    auth_value: Tpm2bAuth,
+    // Whether the authorization value is sent in the clear instead of an HMAC, which a policy
+    // session does after TPM2_PolicyPassword.
+    password: bool,
+    nonce_caller: Tpm2bNonce,
+    // Whether `nonce_caller` was sent in a command, so that the next command needs a new one.
+    nonce_used: bool,
+    nonce_tpm: Tpm2bNonce,
+    nonce_source: N,
+}
+
+impl<N: FnMut(&mut [u8])> HmacSession<N> {
+    /// Starts an HMAC session that is neither bound nor salted, whose HMACs and digests use
+    /// `auth_hash`. Parameters are encrypted with `symmetric`, which is either AES in CFB mode,
+    /// XOR obfuscation or null. `nonce_source` fills a new caller nonce as long as the digests of
+    /// `auth_hash` with random bytes for StartAuthSession and for every command in the session.
+    pub fn start<T: Connection<Error: From<TssError>>>(
+        tpm: &mut T,
+        auth_hash: TpmiAlgHash,
+        symmetric: TpmtSymDef,
+        nonce_source: N,
+    ) -> Result<Self, T::Error> {
+        Self::start_with(tpm, None, None, auth_hash, symmetric, nonce_source)
+    }
+
+    /// Starts an HMAC session like [`start`](Self::start), which is salted with `salt` and bound
+    /// to the entity `bind`. The session key is only known to callers who know the salt or the
+    /// authorization value of `bind`.
+    ///
+    /// When the session authorizes the bind entity itself, its authorization value is already part
+    /// of the session key, which [`set_entity`](Self::set_entity) takes into account.
+    pub fn start_with<T: Connection<Error: From<TssError>>>(
+        tpm: &mut T,
+        salt: Option<&SessionSalt>,
+        bind: Option<&dyn Entity>,
+        auth_hash: TpmiAlgHash,
+        symmetric: TpmtSymDef,
+        nonce_source: N,
+    ) -> Result<Self, T::Error> {
+        Self::start_session(
+            tpm,
+            TpmSe::HMAC,
+            salt,
+            bind,
+            auth_hash,
+            symmetric,
+            nonce_source,
+        )
+    }
+
+    /// Starts a session of `session_type`, which is salted with `salt` and bound to `bind`.
+    pub(crate) fn start_session<T: Connection<Error: From<TssError>>>(
+        tpm: &mut T,
+        session_type: TpmSe,
+        salt: Option<&SessionSalt>,
+        bind: Option<&dyn Entity>,
+        auth_hash: TpmiAlgHash,
+        symmetric: TpmtSymDef,
+        mut nonce_source: N,
+    ) -> Result<Self, T::Error> {
+        // Everything that can be checked up front is, so that a started session isn't wasted.
+        check_symmetric(symmetric)?;
+        let digest_size = digest(auth_hash, &[])?.get_size() as usize;
+        let mut nonce_caller = [0; MAX_DIGEST_SIZE];
+        let nonce_caller = &mut nonce_caller[..digest_size];
+        nonce_source(nonce_caller);
+        let nonce_caller = Tpm2bNonce::from_bytes(nonce_caller).map_err(TssError::from)?;
+        let command = StartAuthSessionCmd {
+            nonce_caller,
+            encrypted_salt: salt.map(|salt| *salt.encrypted_salt()).unwrap_or_default(),
+            session_type,
+            symmetric,
+            auth_hash,
+        };
+        let tpm_key = salt.map_or(TpmHandle::RHNull, SessionSalt::tpm_key);
+        let bind_handle = bind.map_or(TpmHandle::RHNull, |bind| bind.handle());
+        // The session key is derived from the authorization value of the bind entity followed by
+        // the salt.
+        let mut key_material = [0; Tpm2bAuth::MAX_BUFFER_SIZE + Tpm2bDigest::MAX_BUFFER_SIZE];
+        let bind_auth = bind.map_or(&[][..], |bind| bind.auth_value());
+        let bind_auth =
+            Tpm2bAuth::from_bytes(trim_trailing_zeros(bind_auth)).map_err(TssError::from)?;
+        let bind_name = bind.map_or(&[][..], |bind| bind.name());
+        let bind_name = Tpm2bName::from_bytes(bind_name).map_err(TssError::from)?;
+        let salt = salt.map_or(&[][..], SessionSalt::salt);
+        let auth_size = bind_auth.get_size() as usize;
+        let key_size = auth_size + salt.len();
+        key_material[..auth_size].copy_from_slice(bind_auth.get_buffer());
+        key_material[auth_size..key_size].copy_from_slice(salt);
+
+        let (resp, handle) = run_command_with_handles(&command, (tpm_key, bind_handle), (), tpm)?;
+        let session = Self::new(
+            handle,
+            session_type,
+            auth_hash,
+            symmetric,
+            &nonce_caller,
+            &resp.nonce_tpm,
+            &key_material[..key_size],
+            nonce_source,
+        );
+        let mut session = match session {
+            Ok(session) => session,
+            Err(error) => {
+                // The TPM has only a few session slots, so the session is flushed right away. The
+                // original error is more useful than any failure to flush.
+                let _ = flush_context(
+                    tpm,
+                    &FlushContextCmd {
+                        flush_handle: handle,
+                    },
+                );
+                return Err(error.into());
+            }
+        };
+        session.bind_name = bind_name;
+        session.bind_auth = bind_auth;
+        Ok(session)
+    }
+
+    /// Creates the session of `session_type` that the TPM started at `handle`. The session key is
+    /// derived from `key_material`, the authorization value of the bind entity followed by the
+    /// salt, with KDFa. It is empty if there is neither. The following caller nonces have the size
+    /// of `nonce_caller` and are filled by `nonce_source`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        handle: TpmHandle,
+        session_type: TpmSe,
+        auth_hash: TpmiAlgHash,
+        symmetric: TpmtSymDef,
+        nonce_caller: &Tpm2bNonce,
+        nonce_tpm: &Tpm2bNonce,
+        key_material: &[u8],
+        nonce_source: N,
+    ) -> TssResult<Self> {
+        let digest_size = digest(auth_hash, &[])?.get_size() as usize;
+        let session_key = if key_material.is_empty() {
+            Tpm2bDigest::default()
+        } else {
+            let mut session_key = [0; MAX_DIGEST_SIZE];
+            let session_key = &mut session_key[..digest_size];
+            kdfa(
+                auth_hash,
+                key_material,
+                b"ATH",
+                nonce_tpm.get_buffer(),
+                nonce_caller.get_buffer(),
+                session_key,
+            )?;
+            Tpm2bDigest::from_bytes(session_key)?
+        };
+        let expected_handle = match session_type {
+            TpmSe::HMAC => TpmHc::is_hmac_session(handle.0),
+            TpmSe::Policy | TpmSe::Trial => TpmHc::is_policy_session(handle.0),
+            _ => false,
+        };
+        if !expected_handle {
+            return Err(TssTcsError::TpmUnexpected.into());
+        }
+        check_symmetric(symmetric)?;
+        Ok(HmacSession {
+            handle,
+            auth_hash,
+            symmetric,
+            session_key,
+            bind_name: Tpm2bName::default(),
+            bind_auth: Tpm2bAuth::default(),
+            attributes: TpmaSession::CONTINUE_SESSION,
+            auth_value: Tpm2bAuth::default(),
+            password: false,
+            nonce_caller: *nonce_caller,
+            // The nonce of StartAuthSession is not used again.
+            nonce_used: true,
+            nonce_tpm: *nonce_tpm,
+            nonce_source,
+        })
+    }
+
+    /// Returns the handle of the session, which has to be flushed once the session is no longer
+    /// needed.
+    pub fn handle(&self) -> TpmHandle {
+        self.handle
+    }
+
+    /// Sets the authorization value of the entity that the session authorizes. Trailing zeros
+    /// are removed, like the TPM does.
+    ///
+    /// # Errors:
+    /// Returns [TpmRcError::Size](tpm2_rs_base::errors::TpmRcError::Size) if `auth_value` is
+    /// larger than [`Tpm2bAuth::MAX_BUFFER_SIZE`].
+    pub fn set_auth_value<T: AsRef<[u8]> + ?Sized>(&mut self, auth_value: &T) -> TpmRcResult<()> {
+        self.auth_value = Tpm2bAuth::from_bytes(trim_trailing_zeros(auth_value.as_ref()))?;
+        Ok(())
+    }
+
+    /// Sets the authorization value to the one of `entity`, which the session authorizes in the
+    /// following commands. If the session is bound to `entity`, its authorization value is
+    /// already part of the session key and is left out.
+    ///
+    /// # Errors:
+    /// Returns [TpmRcError::Size](tpm2_rs_base::errors::TpmRcError::Size) if the authorization
+    /// value of `entity` is too large.
+    pub fn set_entity<E: Entity + ?Sized>(&mut self, entity: &E) -> TpmRcResult<()> {
+        let auth_value = trim_trailing_zeros(entity.auth_value());
+        if !self.bind_name.get_buffer().is_empty()
+            && entity.name() == self.bind_name.get_buffer()
+            && auth_value == self.bind_auth.get_buffer()
+        {
+            return self.set_auth_value(&[]);
+        }
+        self.set_auth_value(auth_value)
+    }
+
+    /// Sets the attributes of the session in the following commands. The session is flushed
+    /// after the next command unless [`TpmaSession::CONTINUE_SESSION`] is set, which it is by
+    /// default.
+    pub fn set_attributes(&mut self, attributes: TpmaSession) {
+        self.attributes = attributes;
+    }
+
+    /// Returns the nonce of the TPM for the next command.
+    pub fn nonce_tpm(&self) -> &Tpm2bNonce {
+        &self.nonce_tpm
+    }
+
+    /// Sets whether the authorization value is sent in the clear instead of an HMAC.
+    pub(crate) fn set_password(&mut self, password: bool) {
+        self.password = password;
+    }
+
+    /// Replaces the caller nonce with a new one from the nonce source if the current one was
+    /// already sent. A response is only valid for the command with the nonce it answers, so
+    /// responses to earlier commands can't be replayed.
+    fn next_nonce(&mut self) -> TssResult<()> {
+        if self.nonce_used {
+            let mut nonce = [0; Tpm2bNonce::MAX_BUFFER_SIZE];
+            let nonce = &mut nonce[..self.nonce_caller.get_size() as usize];
+            (self.nonce_source)(nonce);
+            self.nonce_caller = Tpm2bNonce::from_bytes(nonce)?;
+            self.nonce_used = false;
+        }
+        Ok(())
+    }
+
+    /// Copies the session key followed by the authorization value into `buffer`, which keys the
+    /// HMACs and the parameter encryption of the session.
+    fn session_value<'a>(
+        &self,
+        buffer: &'a mut [u8; MAX_DIGEST_SIZE + Tpm2bAuth::MAX_BUFFER_SIZE],
+    ) -> &'a [u8] {
+        let session_key = self.session_key.get_buffer();
+        let auth_value = self.auth_value.get_buffer();
+        let size = session_key.len() + auth_value.len();
+        buffer[..session_key.len()].copy_from_slice(session_key);
+        buffer[session_key.len()..size].copy_from_slice(auth_value);
+        &buffer[..size]
+    }
+
+    /// Computes the HMAC of a command or response, keyed with the session key followed by the
+    /// authorization value. `nonce_newer` is the nonce of the sender.
+    fn hmac(
+        &self,
+        parameter_hash: &Tpm2bDigest,
+        nonce_newer: &Tpm2bNonce,
+        nonce_older: &Tpm2bNonce,
+        attributes: TpmaSession,
+    ) -> TssResult<Tpm2bDigest> {
+        let mut key = [0; MAX_DIGEST_SIZE + Tpm2bAuth::MAX_BUFFER_SIZE];
+        hmac(
+            self.auth_hash,
+            self.session_value(&mut key),
+            &[
+                parameter_hash.get_buffer(),
+                nonce_newer.get_buffer(),
+                nonce_older.get_buffer(),
+                &[attributes.0],
+            ],
+        )
+    }
+
+    /// Encrypts or decrypts a parameter with the symmetric algorithm of the session, as described
+    /// in [TPM2.0 1.83] Part 1, 21. `nonce_newer` is the nonce of the sender.
+    fn crypt_parameter(
+        &self,
+        parameter: &mut [u8],
+        nonce_newer: &Tpm2bNonce,
+        nonce_older: &Tpm2bNonce,
+        decrypt: bool,
+    ) -> TssResult<()> {
+        let mut key = [0; MAX_DIGEST_SIZE + Tpm2bAuth::MAX_BUFFER_SIZE];
+        let session_value = self.session_value(&mut key);
+        match self.symmetric {
+            TpmtSymDef::Aes(key_bits, _) => {
+                // The KDF provides the key followed by a 128-bit IV.
+                let mut key_and_iv = [0; 32 + 16];
+                let key_size = key_bits.0 as usize / 8;
+                let key_and_iv = key_and_iv
+                    .get_mut(..key_size + 16)
+                    .ok_or(TssTcsError::BadParameter)?;
+                kdfa(
+                    self.auth_hash,
+                    session_value,
+                    b"CFB",
+                    nonce_newer.get_buffer(),
+                    nonce_older.get_buffer(),
+                    key_and_iv,
+                )?;
+                let (key, iv) = key_and_iv.split_at(key_size);
+                aes_cfb(key, iv, parameter, decrypt)
+            }
+            TpmtSymDef::ExclusiveOr(hash, _) => kdfa_xor(
+                hash,
+                session_value,
+                b"XOR",
+                nonce_newer.get_buffer(),
+                nonce_older.get_buffer(),
+                parameter,
+            ),
+            // The TPM rejects the encryption attributes without a symmetric algorithm.
+            _ => Err(TssTcsError::BadParameter.into()),
+        }
+    }
+}
+
+/// Checks that the session can encrypt parameters with `symmetric`, which is AES in CFB mode, XOR
+/// obfuscation or null.
+fn check_symmetric(symmetric: TpmtSymDef) -> TssResult<()> {
+    match symmetric {
+        TpmtSymDef::Aes(_, TpmiAlgSymMode::CFB)
+        | TpmtSymDef::ExclusiveOr(..)
+        | TpmtSymDef::Null(..) => Ok(()),
+        _ => Err(TssTcsError::NotImplemented.into()),
+    }
+}
+
+/// Returns `auth_value` without its trailing zeros, which the TPM ignores in authorization values.
+fn trim_trailing_zeros(auth_value: &[u8]) -> &[u8] {
+    let size = auth_value
+        .iter()
+        .rposition(|&b| b != 0)
+        .map_or(0, |last| last + 1);
+    &auth_value[..size]
+}
+
+impl<N: FnMut(&mut [u8])> Session for HmacSession<N> {
+    fn encrypt_command_parameter(&mut self, parameter: &mut [u8]) -> TssResult<()> {
+        if !self.attributes.contains(TpmaSession::DECRYPT) {
+            return Ok(());
+        }
+        // The parameter is encrypted with the nonce that the command is sent with.
+        self.next_nonce()?;
+        self.crypt_parameter(parameter, &self.nonce_caller, &self.nonce_tpm, false)
+    }
+
+    fn get_auth_command(&mut self, command: &CommandData) -> TssResult<TpmsAuthCommand> {
+        let session_handle = TpmiShAuthSession::try_from(self.handle.0)?;
+        self.next_nonce()?;
+        self.nonce_used = true;
+        if self.password {
+            return Ok(TpmsAuthCommand {
+                session_handle,
+                nonce: self.nonce_caller,
+                session_attributes: self.attributes,
+                hmac: self.auth_value,
+            });
+        }
+        let cp_hash = cp_hash(
+            self.auth_hash,
+            command.command_code,
+            command.names,
+            command.parameters,
+        )?;
+        let hmac = self.hmac(
+            &cp_hash,
+            &self.nonce_caller,
+            &self.nonce_tpm,
+            self.attributes,
+        )?;
+        Ok(TpmsAuthCommand {
+            session_handle,
+            nonce: self.nonce_caller,
+            session_attributes: self.attributes,
+            hmac: Tpm2bAuth::from_bytes(hmac.get_buffer())?,
+        })
+    }
+
+    fn validate_auth_response(
+        &mut self,
+        response: &ResponseData,
+        auth: &TpmsAuthResponse,
+    ) -> TssResult<()> {
+        // The TPM does not authorize the response with a password.
+        if self.password {
+            if auth.hmac.get_size() != 0 {
+                return Err(TssTcsError::TpmUnexpected.into());
+            }
+            self.nonce_tpm = auth.nonce;
+            return Ok(());
+        }
+        let rp_hash = rp_hash(self.auth_hash, response.command_code, response.parameters)?;
+        let expected = self.hmac(
+            &rp_hash,
+            &auth.nonce,
+            &self.nonce_caller,
+            auth.session_attributes,
+        )?;
+        if !constant_time_eq(expected.get_buffer(), auth.hmac.get_buffer()) {
+            return Err(TssTcsError::TpmUnexpected.into());
+        }
+        self.nonce_tpm = auth.nonce;
+        Ok(())
+    }
+
+    fn decrypt_response_parameter(&mut self, parameter: &mut [u8]) -> TssResult<()> {
+        if !self.attributes.contains(TpmaSession::ENCRYPT) {
+            return Ok(());
+        }
+        // The nonce of the TPM was updated when the response was validated.
+        self.crypt_parameter(parameter, &self.nonce_tpm, &self.nonce_caller, true)
+    }
+}
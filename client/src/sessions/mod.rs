@@ -19,13 +19,25 @@
 //! depending on the command. The compiler should be able to catch cases where unsupported number
 //! of sessions is being passed and return a compile time error.
 mod authorization_area;
+#[cfg(feature = "hmac-session")]
+mod hmac;
 mod nosession;
 mod password;
+#[cfg(feature = "hmac-session")]
+mod policy;
+#[cfg(feature = "hmac-session")]
+mod salt;
 mod session;
 #[cfg(test)]
 mod tests;
 
 pub use authorization_area::*;
+#[cfg(feature = "hmac-session")]
+pub use hmac::*;
 pub use nosession::*;
 pub use password::*;
+#[cfg(feature = "hmac-session")]
+pub use policy::*;
+#[cfg(feature = "hmac-session")]
+pub use salt::*;
 pub use session::*;
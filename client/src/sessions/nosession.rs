@@ -1,4 +1,4 @@
-use crate::sessions::Session;
+use crate::sessions::{CommandData, ResponseData, Session};
 use tpm2_rs_base::{errors::TssResult, TpmsAuthCommand, TpmsAuthResponse};
 
 /// [`NoSession`] is not a standard TPM session and cannot be instantiated,
@@ -11,12 +11,12 @@ pub struct NoSession {
 }
 
 impl Session for NoSession {
-    fn validate_auth_response(&self, _: &TpmsAuthResponse) -> TssResult<()> {
+    fn validate_auth_response(&mut self, _: &ResponseData, _: &TpmsAuthResponse) -> TssResult<()> {
         // unreachable macro may interfere with #42. If it does we can just
         // replace it with a loop {}.
         unreachable!()
     }
-    fn get_auth_command(&self) -> TpmsAuthCommand {
+    fn get_auth_command(&mut self, _: &CommandData) -> TssResult<TpmsAuthCommand> {
         unreachable!()
     }
 }
@@ -1,4 +1,4 @@
-use crate::sessions::Session;
+use crate::sessions::{CommandData, ResponseData, Session};
 use tpm2_rs_base::errors::{TpmRcResult, TssResult, TssTcsError};
 use tpm2_rs_base::{
     Tpm2bAuth, Tpm2bNonce, Tpm2bSimple, TpmaSession, TpmiShAuthSession, TpmsAuthCommand,
@@ -52,15 +52,19 @@ impl PasswordSession {
 }
 
 impl Session for PasswordSession {
-    fn get_auth_command(&self) -> TpmsAuthCommand {
-        TpmsAuthCommand {
+    fn get_auth_command(&mut self, _: &CommandData) -> TssResult<TpmsAuthCommand> {
+        Ok(TpmsAuthCommand {
             session_handle: TpmiShAuthSession::RS_PW,
             nonce: Tpm2bNonce::default(),
             session_attributes: TpmaSession(0),
             hmac: self.auth,
-        }
+        })
     }
-    fn validate_auth_response(&self, auth: &TpmsAuthResponse) -> TssResult<()> {
+    fn validate_auth_response(
+        &mut self,
+        _: &ResponseData,
+        auth: &TpmsAuthResponse,
+    ) -> TssResult<()> {
         // Password response auth should have empty nonce/hmac and ContinueSession attribute.
         if auth.nonce.get_size() != 0
             || auth.session_attributes.0 != 0x1
@@ -0,0 +1,382 @@
+use crate::connection::Connection;
+use crate::handles::Entity;
+#[cfg(feature = "policy")]
+use crate::policy::{PolicyBuilder, PolicyStep};
+use crate::run_command_with_handles;
+use crate::sessions::{
+    AuthorizationArea1Plus, CommandData, HmacSession, ResponseData, Session, SessionSalt,
+};
+use tpm2_rs_base::commands::{
+    PolicyAuthValueCmd, PolicyAuthorizeCmd, PolicyCommandCodeCmd, PolicyCounterTimerCmd,
+    PolicyCpHashCmd, PolicyGetDigestCmd, PolicyLocalityCmd, PolicyNameHashCmd, PolicyNvCmd,
+    PolicyOrCmd, PolicyPasswordCmd, PolicyPcrCmd, PolicyRestartCmd, PolicySecretCmd,
+    PolicySecretResp, PolicySignedCmd, PolicySignedResp, TpmCommand,
+};
+use tpm2_rs_base::constants::{TpmHandle, TpmSe};
+use tpm2_rs_base::errors::{TpmRcResult, TssError, TssResult, TssTcsError};
+use tpm2_rs_base::{
+    Tpm2bAuth, Tpm2bDigest, Tpm2bNonce, Tpm2bSimple, TpmaSession, TpmiAlgHash, TpmsAuthCommand,
+    TpmsAuthResponse, TpmtSymDef,
+};
+
+/// A policy session, which authorizes an entity once the assertions of its `authPolicy` have been
+/// made with the policy commands of the session. A trial session accepts all assertions and only
+/// computes the policy digest.
+///
+/// After [`policy_auth_value`](Self::policy_auth_value), the session proves knowledge of the
+/// authorization value with an HMAC like an [`HmacSession`]. After
+/// [`policy_password`](Self::policy_password), it sends the authorization value in the clear.
+/// Otherwise, the authorization value is not needed.
+///
+/// # Usage:
+/// ```no_run
+/// # use tpm2_rs_base::commands::UnsealCmd;
+/// # use tpm2_rs_base::errors::TssError;
+/// # fn example<T: tpm2_rs_client::connection::Connection<Error = TssError>>(
+/// #     tpm: &mut T,
+/// #     fill_random: impl FnMut(&mut [u8]),
+/// # ) -> Result<(), TssError> {
+/// use tpm2_rs_base::commands::PolicyCommandCodeCmd;
+/// use tpm2_rs_base::constants::{TpmCc, TpmSe};
+/// use tpm2_rs_base::{TpmiAlgHash, TpmsEmpty, TpmtSymDef};
+/// use tpm2_rs_client::sessions::PolicySession;
+///
+/// let symmetric = TpmtSymDef::Null(TpmsEmpty, TpmsEmpty);
+/// let mut session =
+///     PolicySession::start(tpm, TpmSe::Policy, TpmiAlgHash::SHA256, symmetric, fill_random)?;
+/// session.policy_command_code(tpm, &PolicyCommandCodeCmd { code: TpmCc::Unseal })?;
+/// session.policy_auth_value(tpm)?;
+/// session.set_auth_value(b"object password")?;
+/// // The session now authorizes unsealing the object.
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct PolicySession<N> {
+    session: HmacSession<N>,
+    auth_value: Tpm2bAuth,
+    auth_value_needed: bool,
+    password_needed: bool,
+}
+
+impl<N: FnMut(&mut [u8])> PolicySession<N> {
+    /// Starts a policy session of `session_type`, [`TpmSe::Policy`] or [`TpmSe::Trial`], that is
+    /// neither bound nor salted. The other arguments are the same as for [`HmacSession::start`].
+    pub fn start<T: Connection<Error: From<TssError>>>(
+        tpm: &mut T,
+        session_type: TpmSe,
+        auth_hash: TpmiAlgHash,
+        symmetric: TpmtSymDef,
+        nonce_source: N,
+    ) -> Result<Self, T::Error> {
+        Self::start_with(
+            tpm,
+            session_type,
+            None,
+            None,
+            auth_hash,
+            symmetric,
+            nonce_source,
+        )
+    }
+
+    /// Starts a policy session like [`start`](Self::start), which is salted with `salt` and bound
+    /// to `bind` like [`HmacSession::start_with`].
+    pub fn start_with<T: Connection<Error: From<TssError>>>(
+        tpm: &mut T,
+        session_type: TpmSe,
+        salt: Option<&SessionSalt>,
+        bind: Option<&dyn Entity>,
+        auth_hash: TpmiAlgHash,
+        symmetric: TpmtSymDef,
+        nonce_source: N,
+    ) -> Result<Self, T::Error> {
+        if session_type != TpmSe::Policy && session_type != TpmSe::Trial {
+            return Err(TssError::from(TssTcsError::BadParameter).into());
+        }
+        let session = HmacSession::start_session(
+            tpm,
+            session_type,
+            salt,
+            bind,
+            auth_hash,
+            symmetric,
+            nonce_source,
+        )?;
+        Ok(Self::new(session))
+    }
+
+    /// Creates a policy session around `session`, which the TPM started as a policy session.
+    pub(crate) fn new(session: HmacSession<N>) -> Self {
+        PolicySession {
+            session,
+            auth_value: Tpm2bAuth::default(),
+            auth_value_needed: false,
+            password_needed: false,
+        }
+    }
+
+    /// Returns the handle of the session, which has to be flushed once the session is no longer
+    /// needed.
+    pub fn handle(&self) -> TpmHandle {
+        self.session.handle()
+    }
+
+    /// Returns the nonce of the TPM, which `TPM2_PolicySigned` can require the signature to cover.
+    pub fn nonce_tpm(&self) -> &Tpm2bNonce {
+        self.session.nonce_tpm()
+    }
+
+    /// Sets the authorization value of the entity that the session authorizes, which is only
+    /// used if the policy includes `TPM2_PolicyAuthValue` or `TPM2_PolicyPassword`.
+    ///
+    /// # Errors:
+    /// Returns [TpmRcError::Size](tpm2_rs_base::errors::TpmRcError::Size) if `auth_value` is
+    /// larger than [`Tpm2bAuth::MAX_BUFFER_SIZE`].
+    pub fn set_auth_value<T: AsRef<[u8]> + ?Sized>(&mut self, auth_value: &T) -> TpmRcResult<()> {
+        self.auth_value = Tpm2bAuth::from_bytes(auth_value.as_ref())?;
+        self.update_auth()
+    }
+
+    /// Sets the attributes of the session in the following commands, like
+    /// [`HmacSession::set_attributes`].
+    pub fn set_attributes(&mut self, attributes: TpmaSession) {
+        self.session.set_attributes(attributes);
+    }
+
+    /// Passes the authorization value to the underlying session if the policy needs it.
+    fn update_auth(&mut self) -> TpmRcResult<()> {
+        let auth_value = if self.auth_value_needed || self.password_needed {
+            self.auth_value.get_buffer()
+        } else {
+            &[]
+        };
+        self.session.set_auth_value(auth_value)?;
+        self.session.set_password(self.password_needed);
+        Ok(())
+    }
+
+    /// Sends a policy command whose only handle is the session.
+    fn run<T, C>(&self, tpm: &mut T, command: &C) -> Result<C::RespT, T::Error>
+    where
+        T: Connection<Error: From<TssError>>,
+        C: TpmCommand<Handles = TpmHandle, RespHandles = ()>,
+    {
+        run_command_with_handles(command, self.handle(), (), tpm).map(|(resp, _)| resp)
+    }
+
+    /// Asserts a signed authorization by the key at `auth_object`.
+    pub fn policy_signed<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+        auth_object: TpmHandle,
+        command: &PolicySignedCmd,
+    ) -> Result<PolicySignedResp, T::Error> {
+        run_command_with_handles(command, (auth_object, self.handle()), (), tpm)
+            .map(|(resp, _)| resp)
+    }
+
+    /// Asserts knowledge of the authorization value of the entity at `auth_handle`, which the
+    /// sessions authorize.
+    pub fn policy_secret<
+        T: Connection<Error: From<TssError>>,
+        X: Session,
+        Y: Session,
+        Z: Session,
+    >(
+        &mut self,
+        tpm: &mut T,
+        auth_handle: TpmHandle,
+        sessions: impl AuthorizationArea1Plus<X, Y, Z>,
+        command: &PolicySecretCmd,
+    ) -> Result<PolicySecretResp, T::Error> {
+        run_command_with_handles(command, (auth_handle, self.handle()), sessions, tpm)
+            .map(|(resp, _)| resp)
+    }
+
+    /// Asserts that the policy digest is one of the digests in the list.
+    pub fn policy_or<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+        command: &PolicyOrCmd,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, command)
+    }
+
+    /// Asserts the values of the selected PCRs.
+    pub fn policy_pcr<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+        command: &PolicyPcrCmd,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, command)
+    }
+
+    /// Asserts the locality of the command that is authorized.
+    pub fn policy_locality<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+        command: &PolicyLocalityCmd,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, command)
+    }
+
+    /// Asserts a comparison of the contents of the NV index at `nv_index`, which the sessions
+    /// authorize reading through `auth_handle`.
+    pub fn policy_nv<T: Connection<Error: From<TssError>>, X: Session, Y: Session, Z: Session>(
+        &mut self,
+        tpm: &mut T,
+        auth_handle: TpmHandle,
+        nv_index: TpmHandle,
+        sessions: impl AuthorizationArea1Plus<X, Y, Z>,
+        command: &PolicyNvCmd,
+    ) -> Result<(), T::Error> {
+        run_command_with_handles(
+            command,
+            (auth_handle, nv_index, self.handle()),
+            sessions,
+            tpm,
+        )
+        .map(|_| ())
+    }
+
+    /// Asserts a comparison of the time and clock of the TPM.
+    pub fn policy_counter_timer<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+        command: &PolicyCounterTimerCmd,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, command)
+    }
+
+    /// Asserts the command code of the command that is authorized.
+    pub fn policy_command_code<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+        command: &PolicyCommandCodeCmd,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, command)
+    }
+
+    /// Asserts the cpHash of the command that is authorized.
+    pub fn policy_cp_hash<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+        command: &PolicyCpHashCmd,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, command)
+    }
+
+    /// Asserts the digest of the Names of the handles of the command that is authorized.
+    pub fn policy_name_hash<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+        command: &PolicyNameHashCmd,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, command)
+    }
+
+    /// Replaces the policy digest with the approved policy, whose approval the ticket proves.
+    pub fn policy_authorize<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+        command: &PolicyAuthorizeCmd,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, command)
+    }
+
+    /// Requires an HMAC with the authorization value of the entity that is authorized.
+    pub fn policy_auth_value<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, &PolicyAuthValueCmd {})?;
+        self.set_auth_needed(true, false)?;
+        Ok(())
+    }
+
+    /// Requires the authorization value of the entity that is authorized in the clear.
+    pub fn policy_password<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, &PolicyPasswordCmd {})?;
+        self.set_auth_needed(false, true)?;
+        Ok(())
+    }
+
+    /// Resets the policy digest and all assertions of the session.
+    pub fn policy_restart<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+    ) -> Result<(), T::Error> {
+        self.run(tpm, &PolicyRestartCmd {})?;
+        self.set_auth_needed(false, false)?;
+        Ok(())
+    }
+
+    /// Returns the current policy digest of the session, which is the `authPolicy` that the
+    /// assertions satisfy.
+    pub fn policy_get_digest<T: Connection<Error: From<TssError>>>(
+        &mut self,
+        tpm: &mut T,
+    ) -> Result<Tpm2bDigest, T::Error> {
+        self.run(tpm, &PolicyGetDigestCmd {})
+            .map(|resp| resp.policy_digest)
+    }
+
+    /// Replays the assertions of `policy` in the session, like [`PolicyBuilder::replay`].
+    #[cfg(feature = "policy")]
+    pub fn replay<T, F>(
+        &mut self,
+        tpm: &mut T,
+        policy: &PolicyBuilder,
+        authorize: F,
+    ) -> Result<(), T::Error>
+    where
+        T: Connection<Error: From<TssError>>,
+        F: FnMut(&mut T, &PolicyStep) -> Result<(), T::Error>,
+    {
+        policy.replay(tpm, self.handle(), authorize)?;
+        // The last of the assertions about the authorization value applies.
+        for step in policy.steps() {
+            match step {
+                PolicyStep::AuthValue => self.set_auth_needed(true, false)?,
+                PolicyStep::Password => self.set_auth_needed(false, true)?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Records how the policy needs the authorization value.
+    fn set_auth_needed(&mut self, auth_value_needed: bool, password_needed: bool) -> TssResult<()> {
+        self.auth_value_needed = auth_value_needed;
+        self.password_needed = password_needed;
+        Ok(self.update_auth()?)
+    }
+}
+
+impl<N: FnMut(&mut [u8])> Session for PolicySession<N> {
+    fn encrypt_command_parameter(&mut self, parameter: &mut [u8]) -> TssResult<()> {
+        self.session.encrypt_command_parameter(parameter)
+    }
+
+    fn get_auth_command(&mut self, command: &CommandData) -> TssResult<TpmsAuthCommand> {
+        self.session.get_auth_command(command)
+    }
+
+    fn validate_auth_response(
+        &mut self,
+        response: &ResponseData,
+        auth: &TpmsAuthResponse,
+    ) -> TssResult<()> {
+        self.session.validate_auth_response(response, auth)?;
+        // The TPM resets the policy of a session that it keeps after an authorization.
+        self.set_auth_needed(false, false)
+    }
+
+    fn decrypt_response_parameter(&mut self, parameter: &mut [u8]) -> TssResult<()> {
+        self.session.decrypt_response_parameter(parameter)
+    }
+}
@@ -0,0 +1,46 @@
+use tpm2_rs_base::constants::TpmHandle;
+use tpm2_rs_base::errors::TpmRcResult;
+use tpm2_rs_base::{Tpm2bDigest, Tpm2bEncryptedSecret, Tpm2bSimple};
+
+#[cfg(feature = "salted-session")]
+mod generate;
+
+/// The salt of a session, which only the TPM key that it is encrypted to can recover. Salting a
+/// session keeps its session key secret from anyone who observes the commands, even if they know
+/// the authorization values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SessionSalt {
+    tpm_key: TpmHandle,
+    salt: Tpm2bDigest,
+    encrypted_salt: Tpm2bEncryptedSecret,
+}
+
+impl SessionSalt {
+    /// Creates a salt from `salt` and its encryption to the TPM key at `tpm_key`, for callers that
+    /// encrypt it themselves.
+    ///
+    /// # Errors:
+    /// Returns [TpmRcError::Size](tpm2_rs_base::errors::TpmRcError::Size) if either is too large.
+    pub fn new(tpm_key: TpmHandle, salt: &[u8], encrypted_salt: &[u8]) -> TpmRcResult<Self> {
+        Ok(SessionSalt {
+            tpm_key,
+            salt: Tpm2bDigest::from_bytes(salt)?,
+            encrypted_salt: Tpm2bEncryptedSecret::from_bytes(encrypted_salt)?,
+        })
+    }
+
+    /// Returns the handle of the TPM key that the salt is encrypted to.
+    pub fn tpm_key(&self) -> TpmHandle {
+        self.tpm_key
+    }
+
+    /// Returns the salt.
+    pub fn salt(&self) -> &[u8] {
+        self.salt.get_buffer()
+    }
+
+    /// Returns the encrypted salt that the TPM recovers the salt from.
+    pub fn encrypted_salt(&self) -> &Tpm2bEncryptedSecret {
+        &self.encrypted_salt
+    }
+}
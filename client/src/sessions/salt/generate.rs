@@ -0,0 +1,119 @@
+use p256::elliptic_curve::group::{Curve, Group};
+use p256::elliptic_curve::sec1::{EncodedPoint, FromEncodedPoint, ModulusSize, ToEncodedPoint};
+use p256::elliptic_curve::{AffinePoint, CurveArithmetic, FieldBytesSize, NonZeroScalar};
+use p256::NistP256;
+use p384::NistP384;
+use rand_core::CryptoRngCore;
+use rsa::Oaep;
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use tpm2_rs_base::constants::{TpmEccCurve, TpmHandle};
+use tpm2_rs_base::errors::{TssError, TssResult, TssTcsError};
+use tpm2_rs_base::marshal::Marshalable;
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bDigest, Tpm2bEccParameter, Tpm2bEncryptedSecret, Tpm2bSimple,
+    TpmiAlgHash, TpmsEccPoint, TpmtPublic,
+};
+
+use super::SessionSalt;
+use crate::crypto::{digest, field_bytes, kdfe, rsa_public_key};
+
+/// The label that salts are encrypted with, as described in [TPM2.0 1.83] Part 1, 11.4.9.2.
+const SECRET_LABEL: &str = "SECRET\0";
+
+impl SessionSalt {
+    /// Generates a salt as long as the digests of the name algorithm of the TPM key at `tpm_key`,
+    /// whose public area is `public`, and encrypts it to the key: with RSA-OAEP for RSA keys and
+    /// with an ephemeral ECDH key for ECC keys on the NIST P-256 and P-384 curves.
+    pub fn generate(
+        tpm_key: TpmHandle,
+        public: &TpmtPublic,
+        rng: &mut impl CryptoRngCore,
+    ) -> TssResult<Self> {
+        // The largest digest is 64 bytes.
+        let mut salt = [0; 64];
+        let salt = &mut salt[..digest(public.name_alg, &[])?.get_size() as usize];
+        let encrypted_salt = match &public.parms_and_id {
+            PublicParmsAndId::Rsa(parms, modulus) => {
+                rng.fill_bytes(salt);
+                let key = rsa_public_key(modulus, parms.exponent)?;
+                let padding = match public.name_alg {
+                    TpmiAlgHash::SHA1 => Oaep::new_with_label::<Sha1, _>(SECRET_LABEL),
+                    TpmiAlgHash::SHA256 => Oaep::new_with_label::<Sha256, _>(SECRET_LABEL),
+                    TpmiAlgHash::SHA384 => Oaep::new_with_label::<Sha384, _>(SECRET_LABEL),
+                    TpmiAlgHash::SHA512 => Oaep::new_with_label::<Sha512, _>(SECRET_LABEL),
+                    _ => return Err(TssTcsError::NotImplemented.into()),
+                };
+                let encrypted = key
+                    .encrypt(rng, padding, salt)
+                    .or(Err(TssError::from(TssTcsError::BadParameter)))?;
+                Tpm2bEncryptedSecret::from_bytes(&encrypted)?
+            }
+            PublicParmsAndId::Ecc(parms, point) => match parms.curve_id.0 {
+                TpmEccCurve::NistP256 => ecdh::<NistP256>(public.name_alg, point, rng, salt)?,
+                TpmEccCurve::NistP384 => ecdh::<NistP384>(public.name_alg, point, rng, salt)?,
+                _ => return Err(TssTcsError::NotImplemented.into()),
+            },
+            _ => return Err(TssTcsError::BadParameter.into()),
+        };
+        Ok(SessionSalt {
+            tpm_key,
+            salt: Tpm2bDigest::from_bytes(salt)?,
+            encrypted_salt,
+        })
+    }
+}
+
+/// Fills `salt` from the ECDH of an ephemeral key with the TPM key at `point`, as described in
+/// [TPM2.0 1.83] Part 1, C.6.1, and returns the ephemeral public point as the encrypted salt.
+fn ecdh<C>(
+    name_alg: TpmiAlgHash,
+    point: &TpmsEccPoint,
+    rng: &mut impl CryptoRngCore,
+    salt: &mut [u8],
+) -> TssResult<Tpm2bEncryptedSecret>
+where
+    C: CurveArithmetic,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let (Some(x), Some(y)) = (
+        field_bytes::<C>(point.x.get_buffer()),
+        field_bytes::<C>(point.y.get_buffer()),
+    ) else {
+        return Err(TssTcsError::BadParameter.into());
+    };
+    let encoded = EncodedPoint::<C>::from_affine_coordinates(&x, &y, false);
+    let tpm_point = Option::<AffinePoint<C>>::from(AffinePoint::<C>::from_encoded_point(&encoded))
+        .ok_or(TssError::from(TssTcsError::BadParameter))?;
+
+    let ephemeral = NonZeroScalar::<C>::random(rng);
+    let ephemeral_point = (C::ProjectivePoint::generator() * *ephemeral)
+        .to_affine()
+        .to_encoded_point(false);
+    let shared_point = (C::ProjectivePoint::from(tpm_point) * *ephemeral)
+        .to_affine()
+        .to_encoded_point(false);
+    // Neither point is the identity, because the scalar is not zero and the order is prime.
+    let (Some(ephemeral_x), Some(ephemeral_y), Some(z)) =
+        (ephemeral_point.x(), ephemeral_point.y(), shared_point.x())
+    else {
+        return Err(TssTcsError::GeneralFailure.into());
+    };
+    kdfe(
+        name_alg,
+        z,
+        SECRET_LABEL.as_bytes(),
+        ephemeral_x,
+        point.x.get_buffer(),
+        salt,
+    )?;
+
+    let ephemeral_point = TpmsEccPoint {
+        x: Tpm2bEccParameter::from_bytes(ephemeral_x)?,
+        y: Tpm2bEccParameter::from_bytes(ephemeral_y)?,
+    };
+    let mut buffer = [0; size_of::<TpmsEccPoint>()];
+    let size = ephemeral_point.try_marshal(&mut buffer)?;
+    Ok(Tpm2bEncryptedSecret::from_bytes(&buffer[..size])?)
+}
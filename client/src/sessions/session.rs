@@ -1,9 +1,71 @@
+use tpm2_rs_base::constants::TpmCc;
 use tpm2_rs_base::{errors::TssResult, TpmsAuthCommand, TpmsAuthResponse};
 
+/// The parts of a command that the authorization of a session covers.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CommandData<'a> {
+    /// The command code of the command.
+    pub command_code: TpmCc,
+    /// The Names of the handles of the command, in order.
+    pub names: &'a [&'a [u8]],
+    /// The marshaled parameters of the command.
+    pub parameters: &'a [u8],
+}
+
+/// The parts of a successful response that the authorization of a session covers.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ResponseData<'a> {
+    /// The command code of the command that was responded to.
+    pub command_code: TpmCc,
+    /// The marshaled parameters of the response.
+    pub parameters: &'a [u8],
+}
+
 /// Trait for types representing TPM sessions.
+///
+/// The sessions of a command are called in the order they appear in the authorization area:
+/// first to encrypt the command parameter, then to authorize the command, then to validate the
+/// response and last to decrypt the response parameter.
 pub trait Session {
-    /// Computes the authorization HMAC for this session.
-    fn get_auth_command(&self) -> TpmsAuthCommand;
-    /// Validates the authorization response for this session.
-    fn validate_auth_response(&self, auth: &TpmsAuthResponse) -> TssResult<()>;
+    /// Encrypts `parameter`, the contents of the first command parameter if it is a sized
+    /// buffer, before the command is authorized. Sessions that don't encrypt commands leave it
+    /// unchanged.
+    fn encrypt_command_parameter(&mut self, _parameter: &mut [u8]) -> TssResult<()> {
+        Ok(())
+    }
+    /// Computes the authorization HMAC of `command` for this session.
+    fn get_auth_command(&mut self, command: &CommandData) -> TssResult<TpmsAuthCommand>;
+    /// Validates the authorization of `response` for this session.
+    fn validate_auth_response(
+        &mut self,
+        response: &ResponseData,
+        auth: &TpmsAuthResponse,
+    ) -> TssResult<()>;
+    /// Decrypts `parameter`, the contents of the first response parameter if it is a sized
+    /// buffer, after the response was validated. Sessions that don't encrypt responses leave it
+    /// unchanged.
+    fn decrypt_response_parameter(&mut self, _parameter: &mut [u8]) -> TssResult<()> {
+        Ok(())
+    }
+}
+
+/// Sessions that keep state between commands, such as the nonces of an HMAC session, are passed
+/// by mutable reference so that they can be used again.
+impl<S: Session> Session for &mut S {
+    fn encrypt_command_parameter(&mut self, parameter: &mut [u8]) -> TssResult<()> {
+        (**self).encrypt_command_parameter(parameter)
+    }
+    fn get_auth_command(&mut self, command: &CommandData) -> TssResult<TpmsAuthCommand> {
+        (**self).get_auth_command(command)
+    }
+    fn validate_auth_response(
+        &mut self,
+        response: &ResponseData,
+        auth: &TpmsAuthResponse,
+    ) -> TssResult<()> {
+        (**self).validate_auth_response(response, auth)
+    }
+    fn decrypt_response_parameter(&mut self, parameter: &mut [u8]) -> TssResult<()> {
+        (**self).decrypt_response_parameter(parameter)
+    }
 }
@@ -1,12 +1,736 @@
+use tpm2_rs_base::constants::TpmCc;
 use tpm2_rs_base::{Tpm2bSimple, TpmiShAuthSession};
 
 use super::*;
 
+const COMMAND: CommandData = CommandData {
+    command_code: TpmCc::Unseal,
+    names: &[&[0x80, 0x00, 0x00, 0x01]],
+    parameters: &[],
+};
+
 #[test]
 fn test_password_get_auth_command() {
-    let session = PasswordSession::new("hello").unwrap();
-    let tpm_auth = session.get_auth_command();
+    let mut session = PasswordSession::new("hello").unwrap();
+    let tpm_auth = session.get_auth_command(&COMMAND).unwrap();
     assert_eq!(tpm_auth.session_handle, TpmiShAuthSession::RS_PW);
     assert_eq!(tpm_auth.hmac.get_size(), 5);
     assert_eq!(tpm_auth.hmac.get_buffer(), b"hello");
 }
+
+#[cfg(feature = "hmac-session")]
+mod hmac_session {
+    use ::hmac::{Mac, SimpleHmac};
+    use aes::cipher::{BlockEncrypt, KeyInit as _};
+    use aes::{Aes128, Block};
+    use sha2::{Digest, Sha256};
+    use tpm2_rs_base::constants::{TpmHandle, TpmSe};
+    use tpm2_rs_base::errors::{TssError, TssResult, TssTcsError};
+    use tpm2_rs_base::{
+        Tpm2bData, Tpm2bNonce, TpmaSession, TpmiAesKeyBits, TpmiAlgHash, TpmiAlgSymMode,
+        TpmsAuthResponse, TpmsEmpty, TpmtSymDef,
+    };
+
+    use super::*;
+    use crate::connection::Connection;
+    use crate::RespHeader;
+
+    const SESSION: TpmHandle = TpmHandle(0x02000000);
+    const NONCE_CALLER: [u8; 32] = [0x11; 32];
+    const NONCE_TPM: [u8; 32] = [0x22; 32];
+    const AUTH_VALUE: &[u8] = b"auth";
+    const RESPONSE: ResponseData = ResponseData {
+        command_code: TpmCc::Unseal,
+        parameters: &[0x00, 0x02, 0xAB, 0xCD],
+    };
+
+    fn sha256(data: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for part in data {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+
+    fn hmac_sha256(key: &[u8], data: &[&[u8]]) -> [u8; 32] {
+        let mut mac = <SimpleHmac<Sha256> as Mac>::new_from_slice(key).unwrap();
+        for part in data {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().into()
+    }
+
+    /// A nonce source that always returns the same nonce, so that HMACs can be checked against
+    /// known values.
+    type FixedNonce = fn(&mut [u8]);
+
+    fn fixed_nonce(nonce: &mut [u8]) {
+        nonce.copy_from_slice(&NONCE_CALLER);
+    }
+
+    fn session(key_material: &[u8]) -> HmacSession<FixedNonce> {
+        session_with(TpmtSymDef::Null(TpmsEmpty, TpmsEmpty), key_material)
+    }
+
+    fn session_with(symmetric: TpmtSymDef, key_material: &[u8]) -> HmacSession<FixedNonce> {
+        let mut session = HmacSession::new(
+            SESSION,
+            TpmSe::HMAC,
+            TpmiAlgHash::SHA256,
+            symmetric,
+            &Tpm2bNonce::from_bytes(&NONCE_CALLER).unwrap(),
+            &Tpm2bNonce::from_bytes(&NONCE_TPM).unwrap(),
+            key_material,
+            fixed_nonce as FixedNonce,
+        )
+        .unwrap();
+        session.set_auth_value(AUTH_VALUE).unwrap();
+        session
+    }
+
+    /// Returns the authorization of `RESPONSE` as the TPM computes it with an unbound, unsalted
+    /// session.
+    fn response_auth(nonce_tpm: &[u8]) -> TpmsAuthResponse {
+        response_auth_to(&NONCE_CALLER, nonce_tpm)
+    }
+
+    /// Returns the authorization of `RESPONSE` like [`response_auth`] for a command that was sent
+    /// with `nonce_caller`.
+    fn response_auth_to(nonce_caller: &[u8], nonce_tpm: &[u8]) -> TpmsAuthResponse {
+        let rp_hash = sha256(&[&[0; 4], &TpmCc::Unseal.0.to_be_bytes(), RESPONSE.parameters]);
+        let attributes = TpmaSession::CONTINUE_SESSION;
+        let hmac = hmac_sha256(
+            AUTH_VALUE,
+            &[&rp_hash, nonce_tpm, nonce_caller, &[attributes.0]],
+        );
+        TpmsAuthResponse {
+            nonce: Tpm2bNonce::from_bytes(nonce_tpm).unwrap(),
+            session_attributes: attributes,
+            hmac: Tpm2bData::from_bytes(&hmac).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_hmac_get_auth_command() {
+        let auth = session(&[]).get_auth_command(&COMMAND).unwrap();
+        assert_eq!(
+            auth.session_handle,
+            TpmiShAuthSession::try_from(SESSION.0).unwrap()
+        );
+        assert_eq!(auth.nonce.get_buffer(), &NONCE_CALLER);
+        assert_eq!(auth.session_attributes, TpmaSession::CONTINUE_SESSION);
+
+        let cp_hash = sha256(&[&TpmCc::Unseal.0.to_be_bytes(), COMMAND.names[0]]);
+        let expected = hmac_sha256(AUTH_VALUE, &[&cp_hash, &NONCE_CALLER, &NONCE_TPM, &[0x01]]);
+        assert_eq!(auth.hmac.get_buffer(), &expected);
+    }
+
+    #[test]
+    fn test_hmac_session_key() {
+        let key_material = b"bind auth";
+        let mut session = session(key_material);
+        // KDFa with a single block of 256 bits.
+        let session_key = hmac_sha256(
+            key_material,
+            &[
+                &1u32.to_be_bytes(),
+                b"ATH\0",
+                &NONCE_TPM,
+                &NONCE_CALLER,
+                &256u32.to_be_bytes(),
+            ],
+        );
+        let auth = session.get_auth_command(&COMMAND).unwrap();
+        let cp_hash = sha256(&[&TpmCc::Unseal.0.to_be_bytes(), COMMAND.names[0]]);
+        let expected = hmac_sha256(
+            &[&session_key[..], AUTH_VALUE].concat(),
+            &[&cp_hash, &NONCE_CALLER, &NONCE_TPM, &[0x01]],
+        );
+        assert_eq!(auth.hmac.get_buffer(), &expected);
+    }
+
+    #[test]
+    fn test_hmac_validate_auth_response() {
+        let mut session = session(&[]);
+        let next_nonce = [0x33; 32];
+        let auth = response_auth(&next_nonce);
+        session.validate_auth_response(&RESPONSE, &auth).unwrap();
+
+        // The next command uses the nonce of the response.
+        let command_auth = session.get_auth_command(&COMMAND).unwrap();
+        let cp_hash = sha256(&[&TpmCc::Unseal.0.to_be_bytes(), COMMAND.names[0]]);
+        let expected = hmac_sha256(AUTH_VALUE, &[&cp_hash, &NONCE_CALLER, &next_nonce, &[0x01]]);
+        assert_eq!(command_auth.hmac.get_buffer(), &expected);
+    }
+
+    #[test]
+    fn test_hmac_rejects_wrong_response() {
+        let mut session = session(&[]);
+        let mut auth = response_auth(&[0x33; 32]);
+        auth.session_attributes = TpmaSession(0);
+        assert_eq!(
+            session.validate_auth_response(&RESPONSE, &auth),
+            Err(TssTcsError::TpmUnexpected.into())
+        );
+        // A rejected response does not change the nonce of the TPM.
+        let command_auth = session.get_auth_command(&COMMAND).unwrap();
+        let cp_hash = sha256(&[&TpmCc::Unseal.0.to_be_bytes(), COMMAND.names[0]]);
+        let expected = hmac_sha256(AUTH_VALUE, &[&cp_hash, &NONCE_CALLER, &NONCE_TPM, &[0x01]]);
+        assert_eq!(command_auth.hmac.get_buffer(), &expected);
+    }
+
+    #[test]
+    fn test_hmac_rejects_replayed_response() {
+        let mut counter = 0;
+        let mut session = HmacSession::new(
+            SESSION,
+            TpmSe::HMAC,
+            TpmiAlgHash::SHA256,
+            TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+            &Tpm2bNonce::from_bytes(&NONCE_CALLER).unwrap(),
+            &Tpm2bNonce::from_bytes(&NONCE_TPM).unwrap(),
+            &[],
+            |nonce: &mut [u8]| {
+                counter += 1;
+                nonce.fill(counter);
+            },
+        )
+        .unwrap();
+        session.set_auth_value(AUTH_VALUE).unwrap();
+
+        let first = session.get_auth_command(&COMMAND).unwrap();
+        assert_eq!(first.nonce.get_buffer(), &[1; 32]);
+        let response = response_auth_to(first.nonce.get_buffer(), &[0x33; 32]);
+        session
+            .validate_auth_response(&RESPONSE, &response)
+            .unwrap();
+
+        // Each command has a new nonce, which the response to an earlier command doesn't cover.
+        let second = session.get_auth_command(&COMMAND).unwrap();
+        assert_eq!(second.nonce.get_buffer(), &[2; 32]);
+        assert_eq!(
+            session.validate_auth_response(&RESPONSE, &response),
+            Err(TssTcsError::TpmUnexpected.into())
+        );
+        let response = response_auth_to(second.nonce.get_buffer(), &[0x44; 32]);
+        session
+            .validate_auth_response(&RESPONSE, &response)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_hmac_rejects_password_handle() {
+        assert!(HmacSession::new(
+            TpmHandle::RSPW,
+            TpmSe::HMAC,
+            TpmiAlgHash::SHA256,
+            TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+            &Tpm2bNonce::from_bytes(&NONCE_CALLER).unwrap(),
+            &Tpm2bNonce::from_bytes(&NONCE_TPM).unwrap(),
+            &[],
+            fixed_nonce,
+        )
+        .is_err());
+    }
+
+    /// A TPM that starts every session at `handle` and remembers the sessions that it was asked
+    /// to start and flush.
+    struct StartSessionTpm {
+        handle: TpmHandle,
+        started: usize,
+        flushed: Option<TpmHandle>,
+    }
+
+    impl StartSessionTpm {
+        fn new(handle: TpmHandle) -> Self {
+            StartSessionTpm {
+                handle,
+                started: 0,
+                flushed: None,
+            }
+        }
+    }
+
+    impl Connection for StartSessionTpm {
+        type Error = TssError;
+        fn transact<'a>(
+            &mut self,
+            command: &[u8],
+            response: &'a mut [u8],
+        ) -> TssResult<&'a mut [u8]> {
+            use tpm2_rs_base::commands::StartAuthSessionResp;
+            use tpm2_rs_base::constants::TpmSt;
+            use tpm2_rs_base::marshal::Marshalable;
+
+            let mut size = 10;
+            match TpmCc(u32::from_be_bytes(command[6..10].try_into().unwrap())) {
+                TpmCc::StartAuthSession => {
+                    self.started += 1;
+                    size += self.handle.try_marshal(&mut response[size..])?;
+                    let resp = StartAuthSessionResp {
+                        nonce_tpm: Tpm2bNonce::from_bytes(&NONCE_TPM).unwrap(),
+                    };
+                    size += resp.try_marshal(&mut response[size..])?;
+                }
+                TpmCc::FlushContext => {
+                    let handle = u32::from_be_bytes(command[10..14].try_into().unwrap());
+                    self.flushed = Some(TpmHandle(handle));
+                }
+                code => panic!("unexpected command {code:?}"),
+            }
+            let header = RespHeader {
+                tag: TpmSt::NoSessions,
+                size: size as u32,
+                rc: 0,
+            };
+            header.try_marshal(response)?;
+            Ok(&mut response[..size])
+        }
+    }
+
+    #[test]
+    fn test_hmac_start_checks_symmetric_first() {
+        let mut tpm = StartSessionTpm::new(SESSION);
+        assert_eq!(
+            HmacSession::start(
+                &mut tpm,
+                TpmiAlgHash::SHA256,
+                TpmtSymDef::Aes(TpmiAesKeyBits(128), TpmiAlgSymMode::CTR),
+                fixed_nonce as FixedNonce,
+            ),
+            Err(TssTcsError::NotImplemented.into())
+        );
+        assert_eq!(tpm.started, 0);
+    }
+
+    #[test]
+    fn test_hmac_start_flushes_unexpected_session() {
+        // The TPM started a policy session instead of an HMAC session.
+        let policy_session = TpmHandle(0x03000000);
+        let mut tpm = StartSessionTpm::new(policy_session);
+        assert_eq!(
+            HmacSession::start(
+                &mut tpm,
+                TpmiAlgHash::SHA256,
+                TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+                fixed_nonce as FixedNonce,
+            ),
+            Err(TssTcsError::TpmUnexpected.into())
+        );
+        assert_eq!(tpm.started, 1);
+        assert_eq!(tpm.flushed, Some(policy_session));
+
+        let mut tpm = StartSessionTpm::new(SESSION);
+        let session = HmacSession::start(
+            &mut tpm,
+            TpmiAlgHash::SHA256,
+            TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+            fixed_nonce,
+        )
+        .unwrap();
+        assert_eq!(session.handle(), SESSION);
+        assert_eq!(tpm.flushed, None);
+    }
+
+    #[test]
+    fn test_hmac_ignores_trailing_zeros() {
+        let mut padded = session(&[]);
+        padded.set_auth_value(b"auth\0\0").unwrap();
+        assert_eq!(padded, session(&[]));
+    }
+
+    #[test]
+    fn test_hmac_rejects_unsupported_symmetric() {
+        let symmetric = TpmtSymDef::Aes(TpmiAesKeyBits(128), TpmiAlgSymMode::CTR);
+        assert_eq!(
+            HmacSession::new(
+                SESSION,
+                TpmSe::HMAC,
+                TpmiAlgHash::SHA256,
+                symmetric,
+                &Tpm2bNonce::from_bytes(&NONCE_CALLER).unwrap(),
+                &Tpm2bNonce::from_bytes(&NONCE_TPM).unwrap(),
+                &[],
+                fixed_nonce as FixedNonce,
+            ),
+            Err(TssTcsError::NotImplemented.into())
+        );
+    }
+
+    #[test]
+    fn test_hmac_xor_parameters() {
+        let symmetric = TpmtSymDef::ExclusiveOr(TpmiAlgHash::SHA256, TpmsEmpty);
+        let mut session = session_with(symmetric, &[]);
+        let plain_text: [u8; 20] = core::array::from_fn(|i| i as u8);
+
+        // Without the attributes, the parameters are left as they are.
+        let mut parameter = plain_text;
+        session.encrypt_command_parameter(&mut parameter).unwrap();
+        assert_eq!(parameter, plain_text);
+
+        session.set_attributes(
+            TpmaSession::CONTINUE_SESSION | TpmaSession::DECRYPT | TpmaSession::ENCRYPT,
+        );
+        session.encrypt_command_parameter(&mut parameter).unwrap();
+        // KDFa with a single block of 160 bits.
+        let bits = 160u32.to_be_bytes();
+        let mask = hmac_sha256(
+            AUTH_VALUE,
+            &[
+                &1u32.to_be_bytes(),
+                b"XOR\0",
+                &NONCE_CALLER,
+                &NONCE_TPM,
+                &bits,
+            ],
+        );
+        let expected: [u8; 20] = core::array::from_fn(|i| plain_text[i] ^ mask[i]);
+        assert_eq!(parameter, expected);
+
+        // Responses are masked with the new nonce of the TPM first.
+        let next_nonce = [0x33; 32];
+        session
+            .validate_auth_response(&RESPONSE, &response_auth(&next_nonce))
+            .unwrap();
+        let mask = hmac_sha256(
+            AUTH_VALUE,
+            &[
+                &1u32.to_be_bytes(),
+                b"XOR\0",
+                &next_nonce,
+                &NONCE_CALLER,
+                &bits,
+            ],
+        );
+        let mut parameter: [u8; 20] = core::array::from_fn(|i| plain_text[i] ^ mask[i]);
+        session.decrypt_response_parameter(&mut parameter).unwrap();
+        assert_eq!(parameter, plain_text);
+    }
+
+    #[test]
+    fn test_hmac_cfb_parameters() {
+        let symmetric = TpmtSymDef::Aes(TpmiAesKeyBits(128), TpmiAlgSymMode::CFB);
+        let mut session = session_with(symmetric, &[]);
+        session.set_attributes(TpmaSession::CONTINUE_SESSION | TpmaSession::DECRYPT);
+        let plain_text: [u8; 20] = core::array::from_fn(|i| i as u8);
+        let mut parameter = plain_text;
+        session.encrypt_command_parameter(&mut parameter).unwrap();
+
+        // KDFa with a single block of 256 bits, for the key and the IV.
+        let key_and_iv = hmac_sha256(
+            AUTH_VALUE,
+            &[
+                &1u32.to_be_bytes(),
+                b"CFB\0",
+                &NONCE_CALLER,
+                &NONCE_TPM,
+                &256u32.to_be_bytes(),
+            ],
+        );
+        let aes = Aes128::new_from_slice(&key_and_iv[..16]).unwrap();
+        let mut key_stream = Block::from(<[u8; 16]>::try_from(&key_and_iv[16..]).unwrap());
+        aes.encrypt_block(&mut key_stream);
+        let first: [u8; 16] = core::array::from_fn(|i| plain_text[i] ^ key_stream[i]);
+        assert_eq!(parameter[..16], first);
+        let mut key_stream = Block::from(first);
+        aes.encrypt_block(&mut key_stream);
+        let last: [u8; 4] = core::array::from_fn(|i| plain_text[16 + i] ^ key_stream[i]);
+        assert_eq!(parameter[16..], last);
+    }
+
+    #[test]
+    fn test_hmac_encryption_needs_symmetric() {
+        let mut session = session(&[]);
+        session.set_attributes(TpmaSession::DECRYPT);
+        assert_eq!(
+            session.encrypt_command_parameter(&mut [0; 4]),
+            Err(TssTcsError::BadParameter.into())
+        );
+    }
+
+    #[test]
+    fn test_hmac_bound_session() {
+        use crate::handles::HierarchyHandle;
+        use crate::tests::FakeTpm;
+        use tpm2_rs_base::commands::StartAuthSessionResp;
+
+        let mut tpm = FakeTpm::default();
+        tpm.add_to_response(&SESSION);
+        tpm.add_to_response(&StartAuthSessionResp {
+            nonce_tpm: Tpm2bNonce::from_bytes(&NONCE_TPM).unwrap(),
+        });
+        let mut owner = HierarchyHandle::new(TpmHandle::RHOwner).unwrap();
+        owner.set_auth_value(AUTH_VALUE).unwrap();
+        let mut session = HmacSession::start_with(
+            &mut tpm,
+            None,
+            Some(&owner),
+            TpmiAlgHash::SHA256,
+            TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+            fixed_nonce,
+        )
+        .unwrap();
+        let session_key = hmac_sha256(
+            AUTH_VALUE,
+            &[
+                &1u32.to_be_bytes(),
+                b"ATH\0",
+                &NONCE_TPM,
+                &NONCE_CALLER,
+                &256u32.to_be_bytes(),
+            ],
+        );
+        let name = TpmHandle::RHOwner.0.to_be_bytes();
+        let command = CommandData {
+            names: &[&name],
+            ..COMMAND
+        };
+        let cp_hash = sha256(&[&TpmCc::Unseal.0.to_be_bytes(), &name]);
+
+        // The authorization value of the bind entity is only in the session key.
+        session.set_entity(&owner).unwrap();
+        let auth = session.get_auth_command(&command).unwrap();
+        let expected = hmac_sha256(
+            &session_key,
+            &[&cp_hash, &NONCE_CALLER, &NONCE_TPM, &[0x01]],
+        );
+        assert_eq!(auth.hmac.get_buffer(), &expected);
+
+        // Once the authorization value changes, the entity is no longer the bind entity.
+        owner.set_auth_value(b"changed").unwrap();
+        session.set_entity(&owner).unwrap();
+        let auth = session.get_auth_command(&command).unwrap();
+        let expected = hmac_sha256(
+            &[&session_key[..], b"changed"].concat(),
+            &[&cp_hash, &NONCE_CALLER, &NONCE_TPM, &[0x01]],
+        );
+        assert_eq!(auth.hmac.get_buffer(), &expected);
+    }
+
+    mod policy_session {
+        use crate::tests::FakeTpm;
+
+        use super::*;
+
+        fn policy_session() -> PolicySession<FixedNonce> {
+            let session = HmacSession::new(
+                TpmHandle(0x03000000),
+                TpmSe::Policy,
+                TpmiAlgHash::SHA256,
+                TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+                &Tpm2bNonce::from_bytes(&NONCE_CALLER).unwrap(),
+                &Tpm2bNonce::from_bytes(&NONCE_TPM).unwrap(),
+                &[],
+                fixed_nonce as FixedNonce,
+            )
+            .unwrap();
+            let mut session = PolicySession::new(session);
+            session.set_auth_value(AUTH_VALUE).unwrap();
+            session
+        }
+
+        fn expected_hmac(key: &[u8]) -> [u8; 32] {
+            let cp_hash = sha256(&[&TpmCc::Unseal.0.to_be_bytes(), COMMAND.names[0]]);
+            hmac_sha256(key, &[&cp_hash, &NONCE_CALLER, &NONCE_TPM, &[0x01]])
+        }
+
+        #[test]
+        fn test_policy_hmac_without_auth_value() {
+            let auth = policy_session().get_auth_command(&COMMAND).unwrap();
+            assert_eq!(auth.hmac.get_buffer(), &expected_hmac(&[]));
+        }
+
+        #[test]
+        fn test_policy_auth_value() {
+            let mut session = policy_session();
+            session.policy_auth_value(&mut FakeTpm::default()).unwrap();
+            let auth = session.get_auth_command(&COMMAND).unwrap();
+            assert_eq!(auth.hmac.get_buffer(), &expected_hmac(AUTH_VALUE));
+
+            session.policy_restart(&mut FakeTpm::default()).unwrap();
+            let auth = session.get_auth_command(&COMMAND).unwrap();
+            assert_eq!(auth.hmac.get_buffer(), &expected_hmac(&[]));
+        }
+
+        #[test]
+        fn test_policy_password() {
+            let mut session = policy_session();
+            session.policy_password(&mut FakeTpm::default()).unwrap();
+            let auth = session.get_auth_command(&COMMAND).unwrap();
+            assert_eq!(auth.hmac.get_buffer(), AUTH_VALUE);
+
+            // The TPM answers a password with an empty HMAC.
+            let mut response = response_auth(&[0x33; 32]);
+            assert_eq!(
+                session.validate_auth_response(&RESPONSE, &response),
+                Err(TssTcsError::TpmUnexpected.into())
+            );
+            response.hmac = Tpm2bData::default();
+            session
+                .validate_auth_response(&RESPONSE, &response)
+                .unwrap();
+
+            // The policy starts over after an authorization.
+            let auth = session.get_auth_command(&COMMAND).unwrap();
+            let cp_hash = sha256(&[&TpmCc::Unseal.0.to_be_bytes(), COMMAND.names[0]]);
+            let expected = hmac_sha256(&[], &[&cp_hash, &NONCE_CALLER, &[0x33; 32], &[0x01]]);
+            assert_eq!(auth.hmac.get_buffer(), &expected);
+        }
+
+        #[test]
+        fn test_policy_rejects_hmac_session_type() {
+            assert_eq!(
+                PolicySession::start(
+                    &mut FakeTpm::default(),
+                    TpmSe::HMAC,
+                    TpmiAlgHash::SHA256,
+                    TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+                    fixed_nonce as FixedNonce,
+                ),
+                Err(TssTcsError::BadParameter.into())
+            );
+        }
+    }
+}
+
+#[cfg(feature = "salted-session")]
+mod salted_session {
+    use hex_literal::hex;
+    use p256::elliptic_curve::sec1::FromEncodedPoint;
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+    use p256::{AffinePoint, EncodedPoint, NonZeroScalar, ProjectivePoint};
+    use rand_core::{CryptoRng, RngCore};
+    use rsa::traits::PublicKeyParts;
+    use rsa::{Oaep, RsaPrivateKey};
+    use sha2::{Digest, Sha256};
+    use tpm2_rs_base::constants::{TpmEccCurve, TpmHandle};
+    use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+    use tpm2_rs_base::{
+        PublicParmsAndId, Tpm2bDigest, Tpm2bEccParameter, Tpm2bPublicKeyRsa, TpmaObject,
+        TpmiAlgHash, TpmiEccCurve, TpmiRsaKeyBits, TpmsEccParms, TpmsEccPoint, TpmsEmpty,
+        TpmsKeyedHashParms, TpmsRsaParms, TpmtEccScheme, TpmtKdfScheme, TpmtKeyedHashScheme,
+        TpmtPublic, TpmtRsaScheme, TpmtSymDefObject,
+    };
+
+    use super::*;
+
+    const TPM_KEY: TpmHandle = TpmHandle(0x80000001);
+    /// The NIST P-256 key from [RFC 6979] A.2.5.
+    ///
+    /// [RFC 6979]: https://www.rfc-editor.org/rfc/rfc6979
+    const ECC_PRIVATE: [u8; 32] =
+        hex!("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+    const ECC_PUBLIC_X: [u8; 32] =
+        hex!("60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6");
+    const ECC_PUBLIC_Y: [u8; 32] =
+        hex!("7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299");
+
+    /// A deterministic xorshift generator, which is only good enough for tests.
+    struct TestRng(u64);
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest)
+        }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+    impl CryptoRng for TestRng {}
+
+    fn public(parms_and_id: PublicParmsAndId) -> TpmtPublic {
+        TpmtPublic {
+            name_alg: TpmiAlgHash::SHA256,
+            object_attributes: TpmaObject::DECRYPT,
+            auth_policy: Tpm2bDigest::default(),
+            parms_and_id,
+        }
+    }
+
+    #[test]
+    fn test_ecc_salt() {
+        let public = public(PublicParmsAndId::Ecc(
+            TpmsEccParms {
+                symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+                scheme: TpmtEccScheme::Null(TpmsEmpty),
+                curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+                kdf: TpmtKdfScheme::Null(TpmsEmpty),
+            },
+            TpmsEccPoint {
+                x: Tpm2bEccParameter::from_bytes(&ECC_PUBLIC_X).unwrap(),
+                y: Tpm2bEccParameter::from_bytes(&ECC_PUBLIC_Y).unwrap(),
+            },
+        ));
+        let salt = SessionSalt::generate(TPM_KEY, &public, &mut TestRng(1)).unwrap();
+        assert_eq!(salt.tpm_key(), TPM_KEY);
+
+        // The TPM recovers the salt from the ephemeral point with its private key.
+        let mut encrypted_salt = UnmarshalBuf::new(salt.encrypted_salt().get_buffer());
+        let ephemeral = TpmsEccPoint::try_unmarshal(&mut encrypted_salt).unwrap();
+        assert!(encrypted_salt.is_empty());
+        let ephemeral_point = EncodedPoint::from_affine_coordinates(
+            ephemeral.x.get_buffer().into(),
+            ephemeral.y.get_buffer().into(),
+            false,
+        );
+        let ephemeral_point = AffinePoint::from_encoded_point(&ephemeral_point).unwrap();
+        let private = NonZeroScalar::try_from(&ECC_PRIVATE[..]).unwrap();
+        let shared_point = (ProjectivePoint::from(ephemeral_point) * *private)
+            .to_affine()
+            .to_encoded_point(false);
+        // KDFe with a single block of 256 bits.
+        let expected = Sha256::new()
+            .chain_update(1u32.to_be_bytes())
+            .chain_update(shared_point.x().unwrap())
+            .chain_update(b"SECRET\0")
+            .chain_update(ephemeral.x.get_buffer())
+            .chain_update(ECC_PUBLIC_X)
+            .finalize();
+        assert_eq!(salt.salt(), &expected[..]);
+    }
+
+    #[test]
+    fn test_rsa_salt() {
+        let mut rng = TestRng(1);
+        let private = RsaPrivateKey::new(&mut rng, 1024).unwrap();
+        let public = public(PublicParmsAndId::Rsa(
+            TpmsRsaParms {
+                symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+                scheme: TpmtRsaScheme::Null(TpmsEmpty),
+                key_bits: TpmiRsaKeyBits(1024),
+                exponent: 0,
+            },
+            Tpm2bPublicKeyRsa::from_bytes(&private.n().to_bytes_be()).unwrap(),
+        ));
+        let salt = SessionSalt::generate(TPM_KEY, &public, &mut rng).unwrap();
+        assert_eq!(salt.salt().len(), 32);
+
+        let decrypted = private
+            .decrypt(
+                Oaep::new_with_label::<Sha256, _>("SECRET\0"),
+                salt.encrypted_salt().get_buffer(),
+            )
+            .unwrap();
+        assert_eq!(decrypted, salt.salt());
+    }
+
+    #[test]
+    fn test_salt_needs_asymmetric_key() {
+        let public = public(PublicParmsAndId::KeyedHash(
+            TpmsKeyedHashParms {
+                scheme: TpmtKeyedHashScheme::Null(TpmsEmpty),
+            },
+            Tpm2bDigest::default(),
+        ));
+        assert!(SessionSalt::generate(TPM_KEY, &public, &mut TestRng(1)).is_err());
+    }
+}
@@ -1,9 +1,14 @@
-use crate::sessions::{PasswordSession, Session};
+use crate::sessions::{CommandData, PasswordSession, ResponseData, Session};
 
 use super::*;
+use core::future::Future;
+use core::task::{Context, Poll, Waker};
 use tpm2_rs_base::constants::TpmHandle;
 use tpm2_rs_base::errors::TpmRcError;
-use tpm2_rs_base::TpmaSession;
+use tpm2_rs_base::{
+    Tpm2bData, Tpm2bName, Tpm2bSimple, TpmaSession, TpmiShAuthSession, TpmiYesNo,
+    TpmlTaggedTpmProperty, TpmsAuthCommand, TpmsTaggedProperty,
+};
 
 // A Tpm that just returns a general failure error.
 struct ErrorTpm();
@@ -55,6 +60,26 @@ fn test_tpm_error() {
     );
 }
 
+#[test]
+fn test_command_too_large_for_default_buffers() {
+    let too_large = HugeFakeCommand([0; CMD_BUFFER_SIZE]);
+    let mut cmd_buffer = [0u8; 2 * CMD_BUFFER_SIZE];
+    let mut resp_buffer = [0u8; 16];
+    // The command reaches the TPM.
+    assert_eq!(
+        run_command_with_buffers(
+            &too_large,
+            (),
+            &[],
+            (),
+            &mut cmd_buffer,
+            &mut resp_buffer,
+            &mut ErrorTpm()
+        ),
+        Err(TssTcsError::GeneralFailure.into())
+    );
+}
+
 // FakeU32LoopbackTpm reads/stores the command header and a u32 "command".
 // It responds with a response header and the same u32 "response".
 struct FakeU32LoopbackTpm {
@@ -95,6 +120,67 @@ fn test_fake_command() {
     assert_eq!(result.unwrap(), cmd.0);
 }
 
+impl AsyncConnection for FakeU32LoopbackTpm {
+    type Error = TssError;
+    async fn transact<'a>(
+        &mut self,
+        command: &[u8],
+        response: &'a mut [u8],
+    ) -> TssResult<&'a mut [u8]> {
+        Connection::transact(self, command, response)
+    }
+}
+
+#[test]
+fn test_fake_command_in_exact_buffers() {
+    let mut fake_tpm = FakeU32LoopbackTpm {
+        rxed_header: None,
+        rxed_bytes: 0,
+    };
+    let cmd = TestCommand(56789);
+    let mut cmd_buffer = [0u8; 14];
+    let mut resp_buffer = [0u8; 14];
+    let result = run_command_with_buffers(
+        &cmd,
+        (),
+        &[],
+        (),
+        &mut cmd_buffer,
+        &mut resp_buffer,
+        &mut fake_tpm,
+    );
+    assert_eq!(result, Ok((cmd.0, ())));
+    assert_eq!(fake_tpm.rxed_bytes, cmd_buffer.len());
+
+    let result = run_command_with_buffers(
+        &cmd,
+        (),
+        &[],
+        (),
+        &mut cmd_buffer[..13],
+        &mut resp_buffer,
+        &mut fake_tpm,
+    );
+    assert_eq!(result, Err(TpmRcError::Memory.into()));
+}
+
+#[test]
+fn test_fake_command_async() {
+    let mut fake_tpm = FakeU32LoopbackTpm {
+        rxed_header: None,
+        rxed_bytes: 0,
+    };
+    let cmd = TestCommand(56789);
+    // Multi-threaded runtimes can only spawn tasks whose futures are Send.
+    fn assert_send<F: Future + Send>(future: F) -> F {
+        future
+    }
+    let result = core::pin::pin!(assert_send(run_command_async(&cmd, &mut fake_tpm)))
+        .poll(&mut Context::from_waker(Waker::noop()));
+    assert_eq!(result, Poll::Ready(Ok(cmd.0)));
+    assert_eq!(fake_tpm.rxed_header.unwrap().code, TestCommand::CMD_CODE);
+}
+
 // EvilSizeTpm writes a reponse header with a size value that is larger than the reponse buffer.
 struct EvilSizeTpm();
 impl Connection for EvilSizeTpm {
@@ -154,7 +240,7 @@ impl Connection for FakeTpm {
     }
 }
 impl FakeTpm {
-    fn add_to_response<M: Marshalable>(&mut self, val: &M) {
+    pub(crate) fn add_to_response<M: Marshalable>(&mut self, val: &M) {
         self.len += val.try_marshal(&mut self.response[self.len..]).unwrap()
     }
 }
@@ -202,7 +288,12 @@ fn test_response_session_fails_validation() {
         session_attributes: TpmaSession(0xf),
         ..Default::default()
     };
-    let validation_failure = PasswordSession::default().validate_auth_response(&invalid_auth);
+    let response = ResponseData {
+        command_code: TestHandlesCommand::CMD_CODE,
+        parameters: &[],
+    };
+    let validation_failure =
+        PasswordSession::default().validate_auth_response(&response, &invalid_auth);
     assert!(validation_failure.is_err());
     fake_tpm.add_to_response(&invalid_auth);
 
@@ -213,3 +304,202 @@ fn test_response_session_fails_validation() {
         Err(validation_failure.err().unwrap())
     );
 }
+
+// A session that inverts the bits of the first parameter of commands and responses.
+struct InvertingSession;
+impl Session for InvertingSession {
+    fn encrypt_command_parameter(&mut self, parameter: &mut [u8]) -> TssResult<()> {
+        parameter.iter_mut().for_each(|b| *b = !*b);
+        Ok(())
+    }
+    fn get_auth_command(&mut self, _: &CommandData) -> TssResult<TpmsAuthCommand> {
+        Ok(TpmsAuthCommand {
+            session_handle: TpmiShAuthSession::RS_PW,
+            ..Default::default()
+        })
+    }
+    fn validate_auth_response(&mut self, _: &ResponseData, _: &TpmsAuthResponse) -> TssResult<()> {
+        Ok(())
+    }
+    fn decrypt_response_parameter(&mut self, parameter: &mut [u8]) -> TssResult<()> {
+        self.encrypt_command_parameter(parameter)
+    }
+}
+
+#[derive(Marshalable)]
+#[repr(C)]
+struct TestEchoCommand(Tpm2bData);
+impl TpmCommand for TestEchoCommand {
+    const CMD_CODE: TpmCc = TpmCc::NVUndefineSpaceSpecial;
+    const DECRYPT_PARAMETER: bool = true;
+    const ENCRYPT_PARAMETER: bool = true;
+    type Handles = ();
+    type RespT = Tpm2bData;
+    type RespHandles = ();
+}
+
+// EchoParameterTpm responds to a TestEchoCommand with the parameter that it received.
+#[derive(Default)]
+struct EchoParameterTpm {
+    received: Tpm2bData,
+}
+impl Connection for EchoParameterTpm {
+    type Error = TssError;
+    fn transact<'a>(&mut self, command: &[u8], response: &'a mut [u8]) -> TssResult<&'a mut [u8]> {
+        let mut buf = UnmarshalBuf::new(command);
+        CmdHeader::try_unmarshal(&mut buf)?;
+        let auth_size = u32::try_unmarshal(&mut buf)?;
+        buf.get(auth_size as usize).ok_or(TpmRcError::Memory)?;
+        self.received = Tpm2bData::try_unmarshal(&mut buf)?;
+
+        let mut tx_header = RespHeader {
+            tag: TpmSt::Sessions,
+            size: 0,
+            rc: 0,
+        };
+        let mut written = tx_header.try_marshal(response)?;
+        let param_size = self.received.get_size() as u32 + 2;
+        written += param_size.try_marshal(&mut response[written..])?;
+        written += self.received.try_marshal(&mut response[written..])?;
+        written += TpmsAuthResponse::default().try_marshal(&mut response[written..])?;
+        tx_header.size = written as u32;
+        tx_header.try_marshal(response)?;
+        Ok(&mut response[..written])
+    }
+}
+
+#[test]
+fn test_sessions_transform_first_parameter() {
+    let mut fake_tpm = EchoParameterTpm::default();
+    let cmd = TestEchoCommand(Tpm2bData::from_bytes(&[0x01, 0x02, 0x03]).unwrap());
+    let (resp, ()) = run_command_with_handles(&cmd, (), InvertingSession, &mut fake_tpm).unwrap();
+    assert_eq!(fake_tpm.received.get_buffer(), &[0xFE, 0xFD, 0xFC]);
+    assert_eq!(resp.get_buffer(), &[0x01, 0x02, 0x03]);
+}
+
+#[test]
+fn test_sessions_in_exact_buffers() {
+    let mut fake_tpm = EchoParameterTpm::default();
+    let cmd = TestEchoCommand(Tpm2bData::from_bytes(&[0x01, 0x02, 0x03]).unwrap());
+    // The header, the authorization area with one password session, and the parameter.
+    let mut cmd_buffer = [0u8; 10 + 4 + 9 + 5];
+    let mut resp_buffer = [0u8; 24];
+    let (resp, ()) = run_command_with_buffers(
+        &cmd,
+        (),
+        &[],
+        InvertingSession,
+        &mut cmd_buffer,
+        &mut resp_buffer,
+        &mut fake_tpm,
+    )
+    .unwrap();
+    assert_eq!(fake_tpm.received.get_buffer(), &[0xFE, 0xFD, 0xFC]);
+    assert_eq!(resp.get_buffer(), &[0x01, 0x02, 0x03]);
+
+    // The authorization area does not fit next to the parameter.
+    let result = run_command_with_buffers(
+        &cmd,
+        (),
+        &[],
+        InvertingSession,
+        &mut cmd_buffer[..27],
+        &mut resp_buffer,
+        &mut fake_tpm,
+    );
+    assert_eq!(result, Err(TpmRcError::Memory.into()));
+}
+
+// A session that remembers the Names of the handles of the last command that it authorized.
+#[derive(Default)]
+struct NamesSession {
+    names: [Tpm2bName; 3],
+    count: usize,
+}
+impl Session for NamesSession {
+    fn get_auth_command(&mut self, command: &CommandData) -> TssResult<TpmsAuthCommand> {
+        for (name, command_name) in self.names.iter_mut().zip(command.names) {
+            *name = Tpm2bName::from_bytes(command_name)?;
+        }
+        self.count = command.names.len();
+        PasswordSession::default().get_auth_command(command)
+    }
+    fn validate_auth_response(&mut self, _: &ResponseData, _: &TpmsAuthResponse) -> TssResult<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_handles_are_their_own_names() {
+    let mut session = NamesSession::default();
+    let handle = TpmHandle(0x80000001);
+    // The response is missing its sessions, but the command has been authorized by then.
+    let _ = run_command_with_handles(
+        &TestHandlesCommand(),
+        handle,
+        &mut session,
+        &mut FakeTpm::default(),
+    );
+    assert_eq!(session.count, 1);
+    assert_eq!(session.names[0].get_buffer(), &handle.0.to_be_bytes());
+}
+
+#[test]
+fn test_run_command_with_names() {
+    let mut session = NamesSession::default();
+    let name = [0x00, 0x0B, 0x01, 0x02, 0x03];
+    let _ = run_command_with_names(
+        &TestHandlesCommand(),
+        TpmHandle(0x80000001),
+        &[&name],
+        &mut session,
+        &mut FakeTpm::default(),
+    );
+    assert_eq!(session.count, 1);
+    assert_eq!(session.names[0].get_buffer(), &name);
+}
+
+#[test]
+fn test_more_names_than_handles() {
+    assert_eq!(
+        run_command_with_names(
+            &TestHandlesCommand(),
+            TpmHandle(0x80000001),
+            &[&[0x00, 0x0B], &[0x00, 0x0B]],
+            NamesSession::default(),
+            &mut FakeTpm::default(),
+        ),
+        Err(TssTcsError::BadParameter.into())
+    );
+}
+
+fn tpm_properties(properties: &[TpmsTaggedProperty]) -> FakeTpm {
+    let mut fake_tpm = FakeTpm::default();
+    fake_tpm.add_to_response(&GetCapabilityResp {
+        more_data: TpmiYesNo::NO,
+        capability_data: TpmsCapabilityData::TpmProperties(
+            TpmlTaggedTpmProperty::new(properties).unwrap(),
+        ),
+    });
+    fake_tpm
+}
+
+#[test]
+fn test_max_buffer_sizes() {
+    let max_command_size = TpmsTaggedProperty {
+        property: TpmPt::MaxCommandSize,
+        value: 0x1000,
+    };
+    let max_response_size = TpmsTaggedProperty {
+        property: TpmPt::MaxResponseSize,
+        value: 0x2000,
+    };
+    let mut fake_tpm = tpm_properties(&[max_command_size, max_response_size]);
+    assert_eq!(max_buffer_sizes(&mut fake_tpm), Ok((0x1000, 0x2000)));
+
+    let mut fake_tpm = tpm_properties(&[max_command_size]);
+    assert_eq!(
+        max_buffer_sizes(&mut fake_tpm),
+        Err(TssTcsError::TpmUnexpected.into())
+    );
+}
@@ -0,0 +1,37 @@
+use crate::{get_started_tpm, tpm_error};
+use tpm2_rs_base::commands::EccParametersCmd;
+use tpm2_rs_base::constants::TpmEccCurve;
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::TpmiEccCurve;
+use tpm2_rs_client::ecc_parameters;
+
+#[test]
+fn test_ecc_parameters_nist_p256() {
+    let mut tpm = get_started_tpm();
+
+    let command = EccParametersCmd {
+        curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+    };
+    let resp = ecc_parameters(tpm.connection_mut(), &command).expect("Failed running command.");
+
+    let parameters = resp.parameters;
+    assert_eq!(parameters.curve_id, TpmEccCurve::NistP256);
+    assert_eq!(parameters.key_size, 256);
+    assert_eq!(parameters.p.as_ref().len(), 32);
+    assert_eq!(parameters.n.as_ref().len(), 32);
+    assert_eq!(parameters.h.as_ref(), [0x01]);
+}
+
+#[test]
+fn test_ecc_parameters_unsupported_curve() {
+    let mut tpm = get_started_tpm();
+
+    let command = EccParametersCmd {
+        curve_id: TpmiEccCurve(TpmEccCurve::None),
+    };
+    let error = ecc_parameters(tpm.connection_mut(), &command).expect_err("Command should fail.");
+    assert_eq!(
+        tpm_error(&error),
+        Some(&TpmRcError::CurveFor(ErrorType::Parameter, ErrorPosition::Pos1).into())
+    );
+}
@@ -0,0 +1,160 @@
+use crate::commands::signature::load_ecc_key;
+use crate::get_started_tpm;
+use crate::TestConnection;
+use sha2::{Digest, Sha256};
+use tpm2_rs_base::commands::{
+    GetCommandAuditDigestCmd, GetRandomCmd, GetTimeCmd, QuoteCmd, SetCommandCodeAuditStatusCmd,
+    VerifySignatureCmd,
+};
+use tpm2_rs_base::constants::{TpmAlgId, TpmCc, TpmGenerated, TpmHandle};
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{
+    Tpm2bAttest, Tpm2bData, Tpm2bDigest, Tpm2bSimple, TpmiAlgHash, TpmlCc, TpmlPcrSelection,
+    TpmsAttest, TpmsEmpty, TpmsPcrSelection, TpmtSigScheme, TpmuAttest,
+};
+use tpm2_rs_client::sessions::PasswordSession;
+use tpm2_rs_client::{
+    get_command_audit_digest, get_time, quote, run_command, set_command_code_audit_status,
+    verify_signature,
+};
+
+const QUALIFYING_DATA: &[u8] = b"attestation nonce";
+
+fn parse_attest(attest: &Tpm2bAttest) -> TpmsAttest {
+    TpmsAttest::try_unmarshal(&mut UnmarshalBuf::new(attest.get_buffer()))
+        .expect("Failed parsing attestation.")
+}
+
+/// Returns the SHA-256 digest of `attest`, which is what the TPM signed.
+fn attest_digest(attest: &Tpm2bAttest) -> Tpm2bDigest {
+    Tpm2bDigest::from_bytes(&Sha256::digest(attest.get_buffer())).unwrap()
+}
+
+/// Loads the public part of the test key into the owner hierarchy to verify attestations with.
+fn load_verifying_key(tpm: &mut TestConnection) -> TpmHandle {
+    load_ecc_key(tpm, false, TpmHandle::RHOwner)
+}
+
+#[test]
+fn test_quote_pcrs() {
+    let mut tpm = get_started_tpm();
+    let signing_key = load_ecc_key(tpm.connection_mut(), true, TpmHandle::RHNull);
+    let command = QuoteCmd {
+        qualifying_data: Tpm2bData::from_bytes(QUALIFYING_DATA).unwrap(),
+        in_scheme: TpmtSigScheme::Null(TpmsEmpty),
+        pcr_select: TpmlPcrSelection::new(&[TpmsPcrSelection {
+            hash: TpmiAlgHash::SHA256,
+            sizeof_select: 3,
+            pcr_select: [0x03, 0x00, 0x00, 0x00],
+        }])
+        .unwrap(),
+    };
+    let resp = quote(
+        tpm.connection_mut(),
+        signing_key,
+        PasswordSession::default(),
+        &command,
+    )
+    .expect("Failed quoting.");
+
+    let attest = parse_attest(&resp.quoted);
+    assert_eq!(attest.magic, TpmGenerated::VALUE);
+    assert_eq!(attest.extra_data.get_buffer(), QUALIFYING_DATA);
+    let TpmuAttest::Quote(info) = attest.attested else {
+        panic!("Unexpected attestation {:?}", attest.attested);
+    };
+    assert_eq!(info.pcr_select, command.pcr_select);
+    // PCRs 0 and 1 are zero after a reset.
+    assert_eq!(info.pcr_digest.get_buffer(), &Sha256::digest([0; 64])[..]);
+
+    let verifying_key = load_verifying_key(tpm.connection_mut());
+    let command = VerifySignatureCmd {
+        digest: attest_digest(&resp.quoted),
+        signature: resp.signature,
+    };
+    verify_signature(tpm.connection_mut(), verifying_key, &command).expect("Failed verifying.");
+}
+
+#[test]
+fn test_get_time() {
+    let mut tpm = get_started_tpm();
+    let signing_key = load_ecc_key(tpm.connection_mut(), true, TpmHandle::RHNull);
+    let command = GetTimeCmd {
+        qualifying_data: Tpm2bData::from_bytes(QUALIFYING_DATA).unwrap(),
+        in_scheme: TpmtSigScheme::Null(TpmsEmpty),
+    };
+    let resp = get_time(
+        tpm.connection_mut(),
+        signing_key,
+        (PasswordSession::default(), PasswordSession::default()),
+        &command,
+    )
+    .expect("Failed getting time.");
+
+    let attest = parse_attest(&resp.time_info);
+    assert_eq!(attest.magic, TpmGenerated::VALUE);
+    let TpmuAttest::Time(info) = attest.attested else {
+        panic!("Unexpected attestation {:?}", attest.attested);
+    };
+    // The clock is only obfuscated outside of the attested time.
+    assert_eq!(info.time.clock_info.clock, attest.clock_info.clock);
+
+    let verifying_key = load_verifying_key(tpm.connection_mut());
+    let command = VerifySignatureCmd {
+        digest: attest_digest(&resp.time_info),
+        signature: resp.signature,
+    };
+    verify_signature(tpm.connection_mut(), verifying_key, &command).expect("Failed verifying.");
+}
+
+#[test]
+fn test_command_audit_digest() {
+    let mut tpm = get_started_tpm();
+    let command = SetCommandCodeAuditStatusCmd {
+        audit_alg: TpmiAlgHash::SHA256,
+        set_list: TpmlCc::new(&[]).unwrap(),
+        clear_list: TpmlCc::new(&[]).unwrap(),
+    };
+    set_command_code_audit_status(
+        tpm.connection_mut(),
+        TpmHandle::RHOwner,
+        PasswordSession::default(),
+        &command,
+    )
+    .expect("Failed setting the audit algorithm.");
+    let command = SetCommandCodeAuditStatusCmd {
+        audit_alg: TpmiAlgHash(TpmAlgId::Null.0),
+        set_list: TpmlCc::new(&[TpmCc::GetRandom]).unwrap(),
+        clear_list: TpmlCc::new(&[]).unwrap(),
+    };
+    set_command_code_audit_status(
+        tpm.connection_mut(),
+        TpmHandle::RHOwner,
+        PasswordSession::default(),
+        &command,
+    )
+    .expect("Failed selecting commands for audit.");
+    run_command(&GetRandomCmd { bytes_requested: 8 }, tpm.connection_mut())
+        .expect("Failed getting random bytes.");
+
+    let command = GetCommandAuditDigestCmd {
+        qualifying_data: Tpm2bData::from_bytes(QUALIFYING_DATA).unwrap(),
+        in_scheme: TpmtSigScheme::Null(TpmsEmpty),
+    };
+    let resp = get_command_audit_digest(
+        tpm.connection_mut(),
+        TpmHandle::RHNull,
+        (PasswordSession::default(), PasswordSession::default()),
+        &command,
+    )
+    .expect("Failed getting the command audit digest.");
+
+    let attest = parse_attest(&resp.audit_info);
+    assert_eq!(attest.magic, TpmGenerated::VALUE);
+    let TpmuAttest::CommandAudit(info) = attest.attested else {
+        panic!("Unexpected attestation {:?}", attest.attested);
+    };
+    assert_eq!(info.digest_alg, TpmAlgId::SHA256.0);
+    // At least TPM2_GetRandom was recorded with the selected algorithm.
+    assert_eq!(info.audit_digest.get_size(), 32);
+}
@@ -1,39 +1,56 @@
-use crate::get_started_tpm;
-use tpm2_rs_base::commands::GetCapabilityCmd;
-use tpm2_rs_base::constants::{TpmCap, TpmPt};
-use tpm2_rs_base::{TpmlTaggedTpmProperty, TpmsCapabilityData, TpmsTaggedProperty};
-use tpm2_rs_client::run_command;
+use crate::{get_started_tpm, tpm_error};
+use tpm2_rs_base::commands::TestParmsCmd;
+use tpm2_rs_base::constants::TpmEccCurve;
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{
+    TpmiAesKeyBits, TpmiAlgHash, TpmiAlgSymMode, TpmiEccCurve, TpmiRsaKeyBits, TpmsEccParms,
+    TpmsEmpty, TpmsRsaParms, TpmsSchemeHash, TpmtEccScheme, TpmtKdfScheme, TpmtPublicParms,
+    TpmtRsaScheme, TpmtSymDefObject,
+};
+use tpm2_rs_client::test_parms;
 
 #[test]
-fn test_get_capability_manufacturer_id() {
+fn test_test_parms_supported() {
     let mut tpm = get_started_tpm();
 
-    let mut expected = TpmlTaggedTpmProperty {
-        count: 1,
-        tpm_property: [TpmsTaggedProperty::default(); 127],
+    let rsa = TestParmsCmd {
+        parameters: TpmtPublicParms::Rsa(TpmsRsaParms {
+            symmetric: TpmtSymDefObject::Aes(TpmiAesKeyBits(128), TpmiAlgSymMode::CFB),
+            scheme: TpmtRsaScheme::Null(TpmsEmpty),
+            key_bits: TpmiRsaKeyBits(2048),
+            exponent: 0,
+        }),
     };
+    test_parms(tpm.connection_mut(), &rsa).expect("RSA 2048 should be supported.");
 
-    expected.tpm_property[0] = TpmsTaggedProperty {
-        property: TpmPt::Manufacturer,
-        value: 0x58595A20,
-    };
-
-    let command = GetCapabilityCmd {
-        capability: TpmCap::TPMProperties,
-        property: TpmPt::Manufacturer,
-        property_count: 1,
+    let ecc = TestParmsCmd {
+        parameters: TpmtPublicParms::Ecc(TpmsEccParms {
+            symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+            scheme: TpmtEccScheme::Ecdsa(TpmsSchemeHash {
+                hash_alg: TpmiAlgHash::SHA256,
+            }),
+            curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+            kdf: TpmtKdfScheme::Null(TpmsEmpty),
+        }),
     };
+    test_parms(tpm.connection_mut(), &ecc).expect("NIST P256 should be supported.");
+}
 
-    // We allow panic in test cases.
-    let resp = run_command(&command, tpm.connection_mut()).expect("Failed running command.");
+#[test]
+fn test_test_parms_unsupported_curve() {
+    let mut tpm = get_started_tpm();
 
-    // Extract the TpmlTaggedTpmProperty data form the response.
-    let TpmsCapabilityData::TpmProperties(received) = resp.capability_data else {
-        panic!("Unexpected variant data.")
+    let command = TestParmsCmd {
+        parameters: TpmtPublicParms::Ecc(TpmsEccParms {
+            symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+            scheme: TpmtEccScheme::Null(TpmsEmpty),
+            curve_id: TpmiEccCurve(TpmEccCurve(0x7FFF)),
+            kdf: TpmtKdfScheme::Null(TpmsEmpty),
+        }),
     };
-
+    let error = test_parms(tpm.connection_mut(), &command).expect_err("Command should fail.");
     assert_eq!(
-        received, expected,
-        "Received did not match the expected response."
+        tpm_error(&error),
+        Some(&TpmRcError::CurveFor(ErrorType::Parameter, ErrorPosition::Pos1).into())
     );
 }
@@ -0,0 +1,58 @@
+use crate::get_started_tpm;
+use crate::TestConnection;
+use tpm2_rs_base::commands::{
+    PolicyCommandCodeCmd, PolicyGetDigestCmd, PolicyPcrCmd, StartAuthSessionCmd,
+};
+use tpm2_rs_base::constants::{TpmCc, TpmHandle, TpmSe};
+use tpm2_rs_base::{
+    Tpm2bEncryptedSecret, Tpm2bNonce, Tpm2bSimple, TpmiAlgHash, TpmlPcrSelection, TpmsEmpty,
+    TpmsPcrSelection, TpmtSymDef,
+};
+use tpm2_rs_client::policy::{pcr_digest, PolicyBuilder};
+use tpm2_rs_client::{policy_get_digest, run_command_with_handles};
+
+/// Starts a trial policy session, which computes the policy digest without checking the
+/// assertions.
+fn start_trial_session(tpm: &mut TestConnection) -> TpmHandle {
+    let command = StartAuthSessionCmd {
+        nonce_caller: Tpm2bNonce::from_bytes(&[0x55; 16]).unwrap(),
+        encrypted_salt: Tpm2bEncryptedSecret::default(),
+        session_type: TpmSe::Trial,
+        symmetric: TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+        auth_hash: TpmiAlgHash::SHA256,
+    };
+    let (_, handle) =
+        run_command_with_handles(&command, (TpmHandle::RHNull, TpmHandle::RHNull), (), tpm)
+            .expect("Failed starting trial session.");
+    handle
+}
+
+#[test]
+fn test_replayed_policy_matches_offline_digest() {
+    let mut tpm = get_started_tpm();
+    let session = start_trial_session(tpm.connection_mut());
+
+    // PCR 0 has its reset value.
+    let pcrs = TpmlPcrSelection::new(&[TpmsPcrSelection {
+        hash: TpmiAlgHash::SHA256,
+        sizeof_select: 3,
+        pcr_select: [0x01, 0x00, 0x00, 0x00],
+    }])
+    .unwrap();
+    let pcr_digest = pcr_digest(TpmiAlgHash::SHA256, &[&[0; 32]]).unwrap();
+    let mut policy = PolicyBuilder::new(TpmiAlgHash::SHA256).unwrap();
+    policy
+        .policy_pcr(&PolicyPcrCmd { pcr_digest, pcrs })
+        .unwrap()
+        .policy_command_code(&PolicyCommandCodeCmd { code: TpmCc::Sign })
+        .unwrap()
+        .policy_auth_value()
+        .unwrap();
+
+    policy
+        .replay(tpm.connection_mut(), session, |_, _| unreachable!())
+        .expect("Failed replaying policy.");
+    let resp = policy_get_digest(tpm.connection_mut(), session, &PolicyGetDigestCmd {})
+        .expect("Failed getting policy digest.");
+    assert_eq!(&resp.policy_digest, policy.digest());
+}
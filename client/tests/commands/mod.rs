@@ -6,5 +6,13 @@
 //! <chaptername>/mod.rs
 //! <chaptername>/<commandname_1>.rs
 //! <chaptername>/<commandname_2>.rs
+pub mod asymmetric;
+pub mod attestation;
 pub mod capability;
+#[cfg(feature = "policy")]
+pub mod enhanced_auth;
 pub mod random;
+#[cfg(feature = "hmac-session")]
+pub mod session;
+pub mod signature;
+pub mod symmetric;
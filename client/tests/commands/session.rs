@@ -0,0 +1,196 @@
+use crate::{get_started_tpm, tpm_error};
+use tpm2_rs_base::commands::{FlushContextCmd, SetCommandCodeAuditStatusCmd};
+use tpm2_rs_base::constants::{TpmCc, TpmHandle};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{TpmiAlgHash, TpmlCc, TpmsEmpty, TpmtSymDef};
+use tpm2_rs_client::sessions::HmacSession;
+use tpm2_rs_client::{flush_context, set_command_code_audit_status};
+
+const NULL_SYMMETRIC: TpmtSymDef = TpmtSymDef::Null(TpmsEmpty, TpmsEmpty);
+
+fn audit_command() -> SetCommandCodeAuditStatusCmd {
+    SetCommandCodeAuditStatusCmd {
+        audit_alg: TpmiAlgHash::SHA256,
+        set_list: TpmlCc::new(&[TpmCc::GetRandom]).unwrap(),
+        clear_list: TpmlCc::new(&[]).unwrap(),
+    }
+}
+
+/// Fills nonces with a counter, which is good enough for a test but not random.
+pub(crate) fn counter_nonces() -> impl FnMut(&mut [u8]) {
+    let mut counter = 0u8;
+    move |nonce| {
+        counter = counter.wrapping_add(1);
+        nonce.fill(counter);
+    }
+}
+
+#[test]
+fn test_hmac_session_authorizes_commands() {
+    let mut tpm = get_started_tpm();
+    let mut session = HmacSession::start(
+        tpm.connection_mut(),
+        TpmiAlgHash::SHA256,
+        NULL_SYMMETRIC,
+        counter_nonces(),
+    )
+    .expect("Failed starting HMAC session.");
+
+    // The nonce of the TPM changes after each command, which the session keeps up with.
+    for _ in 0..2 {
+        set_command_code_audit_status(
+            tpm.connection_mut(),
+            TpmHandle::RHOwner,
+            &mut session,
+            &audit_command(),
+        )
+        .expect("Failed authorizing with HMAC session.");
+    }
+    flush_context(
+        tpm.connection_mut(),
+        &FlushContextCmd {
+            flush_handle: session.handle(),
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_hmac_session_wrong_auth_value() {
+    let mut tpm = get_started_tpm();
+    let mut session = HmacSession::start(
+        tpm.connection_mut(),
+        TpmiAlgHash::SHA256,
+        NULL_SYMMETRIC,
+        counter_nonces(),
+    )
+    .expect("Failed starting HMAC session.");
+    session.set_auth_value(b"not the owner password").unwrap();
+
+    let error = set_command_code_audit_status(
+        tpm.connection_mut(),
+        TpmHandle::RHOwner,
+        &mut session,
+        &audit_command(),
+    )
+    .expect_err("Authorization should fail.");
+    // The owner hierarchy is exempt from dictionary attack protection, so the failure is
+    // TPM_RC_BAD_AUTH rather than TPM_RC_AUTH_FAIL.
+    assert_eq!(
+        tpm_error(&error),
+        Some(&TpmRcError::BadAuthFor(ErrorType::Session, ErrorPosition::Pos1).into())
+    );
+}
+
+/// A deterministic xorshift generator, which is only good enough for tests.
+#[cfg(feature = "salted-session")]
+struct TestRng(u64);
+#[cfg(feature = "salted-session")]
+impl rand_core::RngCore for TestRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+#[cfg(feature = "salted-session")]
+impl rand_core::CryptoRng for TestRng {}
+
+#[cfg(feature = "salted-session")]
+#[test]
+fn test_salted_bound_session() {
+    use hex_literal::hex;
+    use tpm2_rs_base::commands::LoadExternalCmd;
+    use tpm2_rs_base::constants::TpmEccCurve;
+    use tpm2_rs_base::{
+        PublicParmsAndId, Tpm2bEccParameter, Tpm2bPublic, Tpm2bSensitive, Tpm2bSimple, Tpm2bStruct,
+        TpmaObject, TpmiEccCurve, TpmsEccParms, TpmsEccPoint, TpmtEccScheme, TpmtKdfScheme,
+        TpmtPublic, TpmtSensitive, TpmuSensitiveComposite,
+    };
+    use tpm2_rs_client::handles::{Entity, HierarchyHandle};
+    use tpm2_rs_client::load_external;
+    use tpm2_rs_client::sessions::SessionSalt;
+
+    // The NIST P-256 key from RFC 6979 A.2.5.
+    let private = hex!("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+    let public = TpmtPublic {
+        name_alg: TpmiAlgHash::SHA256,
+        object_attributes: TpmaObject::DECRYPT | TpmaObject::USER_WITH_AUTH,
+        auth_policy: Default::default(),
+        parms_and_id: PublicParmsAndId::Ecc(
+            TpmsEccParms {
+                symmetric: NULL_SYMMETRIC,
+                scheme: TpmtEccScheme::Null(TpmsEmpty),
+                curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+                kdf: TpmtKdfScheme::Null(TpmsEmpty),
+            },
+            TpmsEccPoint {
+                x: Tpm2bEccParameter::from_bytes(&hex!(
+                    "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6"
+                ))
+                .unwrap(),
+                y: Tpm2bEccParameter::from_bytes(&hex!(
+                    "7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299"
+                ))
+                .unwrap(),
+            },
+        ),
+    };
+    let sensitive = TpmtSensitive {
+        auth_value: Default::default(),
+        seed_value: Default::default(),
+        sensitive: TpmuSensitiveComposite::Ecc(Tpm2bEccParameter::from_bytes(&private).unwrap()),
+    };
+
+    let mut tpm = get_started_tpm();
+    let (tpm_key, _) = load_external(
+        tpm.connection_mut(),
+        &LoadExternalCmd {
+            in_private: Tpm2bSensitive::from_struct(&sensitive).unwrap(),
+            in_public: Tpm2bPublic::from_struct(&public).unwrap(),
+            hierarchy: TpmHandle::RHNull,
+        },
+    )
+    .expect("Failed loading key.");
+    let salt = SessionSalt::generate(tpm_key, &public, &mut TestRng(1)).unwrap();
+    // The owner authorization is empty, but it is part of the session key all the same.
+    let owner = HierarchyHandle::new(TpmHandle::RHOwner).unwrap();
+    let mut session = HmacSession::start_with(
+        tpm.connection_mut(),
+        Some(&salt),
+        Some(&owner),
+        TpmiAlgHash::SHA256,
+        NULL_SYMMETRIC,
+        counter_nonces(),
+    )
+    .expect("Failed starting salted and bound session.");
+    session.set_entity(&owner).unwrap();
+
+    set_command_code_audit_status(
+        tpm.connection_mut(),
+        owner.handle(),
+        &mut session,
+        &audit_command(),
+    )
+    .expect("Failed authorizing with salted and bound session.");
+    for handle in [session.handle(), tpm_key] {
+        flush_context(
+            tpm.connection_mut(),
+            &FlushContextCmd {
+                flush_handle: handle,
+            },
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use crate::TestConnection;
+use crate::{get_started_tpm, tpm_error};
+use hex_literal::hex;
+use tpm2_rs_base::commands::{HashCmd, LoadExternalCmd, SignCmd, VerifySignatureCmd};
+use tpm2_rs_base::constants::{TpmEccCurve, TpmHandle, TpmSt};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bDigest, Tpm2bEccParameter, Tpm2bMaxBuffer, Tpm2bPublic, Tpm2bSensitive,
+    Tpm2bSimple, Tpm2bStruct, TpmaObject, TpmiAlgHash, TpmiEccCurve, TpmsEccParms, TpmsEccPoint,
+    TpmsEmpty, TpmsSchemeHash, TpmtEccScheme, TpmtKdfScheme, TpmtPublic, TpmtSensitive,
+    TpmtSigScheme, TpmtSignature, TpmtSymDefObject, TpmtTkHashcheck, TpmuSensitiveComposite,
+};
+use tpm2_rs_client::sessions::PasswordSession;
+use tpm2_rs_client::{hash, load_external, sign, verify_signature};
+
+/// The NIST P-256 key from [RFC 6979] A.2.5.
+///
+/// [RFC 6979]: https://www.rfc-editor.org/rfc/rfc6979
+const ECC_PRIVATE: [u8; 32] =
+    hex!("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+const ECC_PUBLIC_X: [u8; 32] =
+    hex!("60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6");
+const ECC_PUBLIC_Y: [u8; 32] =
+    hex!("7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299");
+
+fn sha256() -> TpmsSchemeHash {
+    TpmsSchemeHash {
+        hash_alg: TpmiAlgHash::SHA256,
+    }
+}
+
+/// Loads the ECDSA test key into `hierarchy`, with its private part if `private` is set.
+pub fn load_ecc_key(tpm: &mut TestConnection, private: bool, hierarchy: TpmHandle) -> TpmHandle {
+    let public = TpmtPublic {
+        name_alg: TpmiAlgHash::SHA256,
+        object_attributes: TpmaObject::SIGN_ENCRYPT | TpmaObject::USER_WITH_AUTH,
+        auth_policy: Default::default(),
+        parms_and_id: PublicParmsAndId::Ecc(
+            TpmsEccParms {
+                symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+                scheme: TpmtEccScheme::Ecdsa(sha256()),
+                curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+                kdf: TpmtKdfScheme::Null(TpmsEmpty),
+            },
+            TpmsEccPoint {
+                x: Tpm2bEccParameter::from_bytes(&ECC_PUBLIC_X).unwrap(),
+                y: Tpm2bEccParameter::from_bytes(&ECC_PUBLIC_Y).unwrap(),
+            },
+        ),
+    };
+    let in_private = if private {
+        let sensitive = TpmtSensitive {
+            auth_value: Default::default(),
+            seed_value: Default::default(),
+            sensitive: TpmuSensitiveComposite::Ecc(
+                Tpm2bEccParameter::from_bytes(&ECC_PRIVATE).unwrap(),
+            ),
+        };
+        Tpm2bSensitive::from_struct(&sensitive).unwrap()
+    } else {
+        Tpm2bSensitive::default()
+    };
+    let command = LoadExternalCmd {
+        in_private,
+        in_public: Tpm2bPublic::from_struct(&public).unwrap(),
+        hierarchy,
+    };
+    load_external(tpm, &command).expect("Failed loading key.").0
+}
+
+/// Hashes `data` in the owner hierarchy and returns the digest and its ticket.
+fn hash_data(tpm: &mut TestConnection, data: &[u8]) -> (Tpm2bDigest, TpmtTkHashcheck) {
+    let command = HashCmd {
+        data: Tpm2bMaxBuffer::from_bytes(data).unwrap(),
+        hash_alg: TpmiAlgHash::SHA256,
+        hierarchy: TpmHandle::RHOwner,
+    };
+    let resp = hash(tpm, &command).expect("Failed hashing.");
+    (resp.out_hash, resp.validation)
+}
+
+#[test]
+fn test_sign_and_verify_ecdsa() {
+    let mut tpm = get_started_tpm();
+    let signing_key = load_ecc_key(tpm.connection_mut(), true, TpmHandle::RHNull);
+    let verifying_key = load_ecc_key(tpm.connection_mut(), false, TpmHandle::RHOwner);
+    let (digest, validation) = hash_data(tpm.connection_mut(), b"sample");
+    assert_eq!(validation.hierarchy, TpmHandle::RHOwner);
+
+    let command = SignCmd {
+        digest,
+        in_scheme: TpmtSigScheme::Null(TpmsEmpty),
+        validation,
+    };
+    let signature = sign(
+        tpm.connection_mut(),
+        signing_key,
+        PasswordSession::default(),
+        &command,
+    )
+    .expect("Failed signing.");
+    let TpmtSignature::Ecdsa(ecdsa) = signature else {
+        panic!("Unexpected signature {signature:?}");
+    };
+    assert_eq!(ecdsa.hash, TpmiAlgHash::SHA256);
+
+    let command = VerifySignatureCmd { digest, signature };
+    let ticket =
+        verify_signature(tpm.connection_mut(), verifying_key, &command).expect("Failed verifying.");
+    assert_eq!(ticket.tag, TpmSt::Verified);
+    assert_eq!(ticket.hierarchy, TpmHandle::RHOwner);
+    assert_eq!(ticket.digest.get_size(), 32);
+}
+
+#[test]
+fn test_verify_signature_of_other_digest() {
+    let mut tpm = get_started_tpm();
+    let key = load_ecc_key(tpm.connection_mut(), true, TpmHandle::RHNull);
+    let (digest, validation) = hash_data(tpm.connection_mut(), b"sample");
+    let command = SignCmd {
+        digest,
+        in_scheme: TpmtSigScheme::Ecdsa(sha256()),
+        validation,
+    };
+    let signature = sign(
+        tpm.connection_mut(),
+        key,
+        PasswordSession::default(),
+        &command,
+    )
+    .expect("Failed signing.");
+
+    let (digest, _) = hash_data(tpm.connection_mut(), b"test");
+    let command = VerifySignatureCmd { digest, signature };
+    let error = verify_signature(tpm.connection_mut(), key, &command)
+        .expect_err("Verification should fail.");
+    assert_eq!(
+        tpm_error(&error),
+        Some(&TpmRcError::SignatureFor(ErrorType::Parameter, ErrorPosition::Pos2).into())
+    );
+}
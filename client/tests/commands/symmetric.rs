@@ -0,0 +1,272 @@
+use crate::TestConnection;
+use crate::{get_started_tpm, tpm_error};
+use hex_literal::hex;
+use sha2::{Digest, Sha256};
+use tpm2_rs_base::commands::{EncryptDecrypt2Cmd, EncryptDecryptCmd, LoadExternalCmd};
+use tpm2_rs_base::constants::TpmHandle;
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bAuth, Tpm2bDigest, Tpm2bIv, Tpm2bMaxBuffer, Tpm2bPublic, Tpm2bSensitive,
+    Tpm2bSimple, Tpm2bStruct, Tpm2bSymKey, TpmaObject, TpmiAesKeyBits, TpmiAlgHash, TpmiAlgSymMode,
+    TpmiYesNo, TpmsSymCipherParms, TpmtPublic, TpmtSensitive, TpmtSymDefObject,
+    TpmuSensitiveComposite,
+};
+use tpm2_rs_client::handles::{Entity, ObjectHandle};
+use tpm2_rs_client::sessions::PasswordSession;
+use tpm2_rs_client::{encrypt_decrypt, encrypt_decrypt2, load_external};
+
+/// The AES-128 key, IV and data from [NIST SP 800-38A] F.3.13.
+const KEY: [u8; 16] = hex!("2b7e151628aed2a6abf7158809cf4f3c");
+const IV: [u8; 16] = hex!("000102030405060708090a0b0c0d0e0f");
+const PLAIN_TEXT: [u8; 32] =
+    hex!("6bc1bee22e409f96e93d7e117393172a" "ae2d8a571e03ac9c9eb76fac45af8e51");
+const CFB_CIPHER_TEXT: [u8; 32] =
+    hex!("3b3fd92eb72dad20333449f8e83cfb4a" "c8a64537a0b3a93fcde3cdad9f1ce58b");
+
+fn load_aes_key(tpm: &mut TestConnection, mode: TpmiAlgSymMode) -> TpmHandle {
+    load_aes_key_with(
+        tpm,
+        mode,
+        TpmaObject::USER_WITH_AUTH,
+        Default::default(),
+        &[],
+    )
+    .handle()
+}
+
+fn load_aes_key_with(
+    tpm: &mut TestConnection,
+    mode: TpmiAlgSymMode,
+    attributes: TpmaObject,
+    auth_policy: Tpm2bDigest,
+    auth_value: &[u8],
+) -> ObjectHandle {
+    let public = TpmtPublic {
+        name_alg: TpmiAlgHash::SHA256,
+        object_attributes: TpmaObject::DECRYPT | TpmaObject::SIGN_ENCRYPT | attributes,
+        auth_policy,
+        parms_and_id: PublicParmsAndId::Sym(
+            TpmsSymCipherParms {
+                sym: TpmtSymDefObject::Aes(TpmiAesKeyBits(128), mode),
+            },
+            Tpm2bDigest::from_bytes(&Sha256::digest(KEY)).unwrap(),
+        ),
+    };
+    let sensitive = TpmtSensitive {
+        auth_value: Tpm2bAuth::from_bytes(auth_value).unwrap(),
+        seed_value: Default::default(),
+        sensitive: TpmuSensitiveComposite::Sym(Tpm2bSymKey::from_bytes(&KEY).unwrap()),
+    };
+    let command = LoadExternalCmd {
+        in_private: Tpm2bSensitive::from_struct(&sensitive).unwrap(),
+        in_public: Tpm2bPublic::from_struct(&public).unwrap(),
+        hierarchy: TpmHandle::RHNull,
+    };
+    let (handle, resp) = load_external(tpm, &command).expect("Failed loading key.");
+    let mut key = ObjectHandle::new(handle, resp.name.get_buffer()).unwrap();
+    key.set_auth_value(auth_value).unwrap();
+    key
+}
+
+#[test]
+fn test_encrypt_decrypt2_aes_cfb() {
+    let mut tpm = get_started_tpm();
+    let handle = load_aes_key(tpm.connection_mut(), TpmiAlgSymMode::Null);
+
+    let command = EncryptDecrypt2Cmd {
+        in_data: Tpm2bMaxBuffer::from_bytes(&PLAIN_TEXT).unwrap(),
+        decrypt: TpmiYesNo::NO,
+        mode: TpmiAlgSymMode::CFB,
+        iv_in: Tpm2bIv::from_bytes(&IV).unwrap(),
+    };
+    let resp = encrypt_decrypt2(
+        tpm.connection_mut(),
+        handle,
+        PasswordSession::default(),
+        &command,
+    )
+    .expect("Failed encrypting.");
+    assert_eq!(resp.out_data.get_buffer(), CFB_CIPHER_TEXT);
+
+    let command = EncryptDecrypt2Cmd {
+        in_data: resp.out_data,
+        decrypt: TpmiYesNo::YES,
+        ..command
+    };
+    let resp = encrypt_decrypt2(
+        tpm.connection_mut(),
+        handle,
+        PasswordSession::default(),
+        &command,
+    )
+    .expect("Failed decrypting.");
+    assert_eq!(resp.out_data.get_buffer(), PLAIN_TEXT);
+}
+
+#[test]
+fn test_encrypt_decrypt_chained_iv() {
+    let mut tpm = get_started_tpm();
+    let handle = load_aes_key(tpm.connection_mut(), TpmiAlgSymMode::CFB);
+
+    let mut iv_in = Tpm2bIv::from_bytes(&IV).unwrap();
+    for (plain, expected) in PLAIN_TEXT.chunks(16).zip(CFB_CIPHER_TEXT.chunks(16)) {
+        let command = EncryptDecryptCmd {
+            decrypt: TpmiYesNo::NO,
+            mode: TpmiAlgSymMode::Null,
+            iv_in,
+            in_data: Tpm2bMaxBuffer::from_bytes(plain).unwrap(),
+        };
+        let resp = encrypt_decrypt(
+            tpm.connection_mut(),
+            handle,
+            PasswordSession::default(),
+            &command,
+        )
+        .expect("Failed encrypting.");
+        assert_eq!(resp.out_data.get_buffer(), expected);
+        iv_in = resp.iv_out;
+    }
+}
+
+#[test]
+fn test_encrypt_decrypt_mode_mismatch() {
+    let mut tpm = get_started_tpm();
+    let handle = load_aes_key(tpm.connection_mut(), TpmiAlgSymMode::CFB);
+
+    let command = EncryptDecryptCmd {
+        decrypt: TpmiYesNo::NO,
+        mode: TpmiAlgSymMode::CBC,
+        iv_in: Tpm2bIv::from_bytes(&IV).unwrap(),
+        in_data: Tpm2bMaxBuffer::from_bytes(&PLAIN_TEXT).unwrap(),
+    };
+    let error = encrypt_decrypt(
+        tpm.connection_mut(),
+        handle,
+        PasswordSession::default(),
+        &command,
+    )
+    .expect_err("Command should fail.");
+    assert_eq!(
+        tpm_error(&error),
+        Some(&TpmRcError::ModeFor(ErrorType::Parameter, ErrorPosition::Pos2).into())
+    );
+}
+
+#[cfg(feature = "hmac-session")]
+#[test]
+fn test_encrypt_decrypt2_encrypted_session() {
+    use crate::commands::session::counter_nonces;
+    use tpm2_rs_base::commands::FlushContextCmd;
+    use tpm2_rs_base::{TpmaSession, TpmtSymDef};
+    use tpm2_rs_client::sessions::HmacSession;
+    use tpm2_rs_client::{flush_context, run_command_with_names};
+
+    let mut tpm = get_started_tpm();
+    let key = load_aes_key_with(
+        tpm.connection_mut(),
+        TpmiAlgSymMode::Null,
+        TpmaObject::USER_WITH_AUTH,
+        Default::default(),
+        &[],
+    );
+    let mut session = HmacSession::start(
+        tpm.connection_mut(),
+        TpmiAlgHash::SHA256,
+        TpmtSymDef::Aes(TpmiAesKeyBits(128), TpmiAlgSymMode::CFB),
+        counter_nonces(),
+    )
+    .expect("Failed starting HMAC session.");
+    session.set_attributes(
+        TpmaSession::CONTINUE_SESSION | TpmaSession::DECRYPT | TpmaSession::ENCRYPT,
+    );
+
+    // The data and the result only cross the connection encrypted by the session, whose HMAC
+    // covers the Name of the key.
+    let command = EncryptDecrypt2Cmd {
+        in_data: Tpm2bMaxBuffer::from_bytes(&PLAIN_TEXT).unwrap(),
+        decrypt: TpmiYesNo::NO,
+        mode: TpmiAlgSymMode::CFB,
+        iv_in: Tpm2bIv::from_bytes(&IV).unwrap(),
+    };
+    let (resp, _) = run_command_with_names(
+        &command,
+        key.handle(),
+        &[key.name()],
+        (PasswordSession::default(), &mut session),
+        tpm.connection_mut(),
+    )
+    .expect("Failed encrypting with an encrypted session.");
+    assert_eq!(resp.out_data.get_buffer(), CFB_CIPHER_TEXT);
+    flush_context(
+        tpm.connection_mut(),
+        &FlushContextCmd {
+            flush_handle: session.handle(),
+        },
+    )
+    .unwrap();
+}
+
+#[cfg(feature = "hmac-session")]
+#[test]
+fn test_encrypt_decrypt2_policy_session() {
+    use crate::commands::session::counter_nonces;
+    use tpm2_rs_base::commands::FlushContextCmd;
+    use tpm2_rs_base::constants::TpmSe;
+    use tpm2_rs_base::{TpmsEmpty, TpmtSymDef};
+    use tpm2_rs_client::sessions::PolicySession;
+    use tpm2_rs_client::{flush_context, run_command_with_names};
+
+    let mut tpm = get_started_tpm();
+    let start = |tpm: &mut TestConnection, session_type| {
+        PolicySession::start(
+            tpm,
+            session_type,
+            TpmiAlgHash::SHA256,
+            TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+            counter_nonces(),
+        )
+        .expect("Failed starting policy session.")
+    };
+    let flush = |tpm: &mut TestConnection, session: &PolicySession<_>| {
+        flush_context(
+            tpm,
+            &FlushContextCmd {
+                flush_handle: session.handle(),
+            },
+        )
+        .unwrap();
+    };
+
+    // Only a policy session that proves knowledge of the authorization value can use the key.
+    let mut trial = start(tpm.connection_mut(), TpmSe::Trial);
+    trial.policy_auth_value(tpm.connection_mut()).unwrap();
+    let auth_policy = trial.policy_get_digest(tpm.connection_mut()).unwrap();
+    flush(tpm.connection_mut(), &trial);
+    let key = load_aes_key_with(
+        tpm.connection_mut(),
+        TpmiAlgSymMode::Null,
+        TpmaObject::empty(),
+        auth_policy,
+        b"secret",
+    );
+
+    let mut session = start(tpm.connection_mut(), TpmSe::Policy);
+    session.policy_auth_value(tpm.connection_mut()).unwrap();
+    session.set_auth_value(key.auth_value()).unwrap();
+    let command = EncryptDecrypt2Cmd {
+        in_data: Tpm2bMaxBuffer::from_bytes(&PLAIN_TEXT).unwrap(),
+        decrypt: TpmiYesNo::NO,
+        mode: TpmiAlgSymMode::CFB,
+        iv_in: Tpm2bIv::from_bytes(&IV).unwrap(),
+    };
+    let (resp, _) = run_command_with_names(
+        &command,
+        key.handle(),
+        &[key.name()],
+        &mut session,
+        tpm.connection_mut(),
+    )
+    .expect("Failed encrypting with a policy session.");
+    assert_eq!(resp.out_data.get_buffer(), CFB_CIPHER_TEXT);
+    flush(tpm.connection_mut(), &session);
+}
@@ -0,0 +1,437 @@
+/// These tests run the client against the TPM of `tpm2-rs-server` in the same process, so they
+/// need neither a simulator nor a socket. Run them with:
+///
+/// ```shell
+/// cargo test -p tpm2-rs-client --features connection-loopback --test loopback
+/// ```
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+use std::time::Instant;
+
+use sha2::{Digest, Sha256};
+use tpm2_rs_base::commands::{EccParametersCmd, HashCmd};
+use tpm2_rs_base::constants::{TpmEccCurve, TpmHandle};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError, TssError};
+use tpm2_rs_base::{Tpm2bMaxBuffer, Tpm2bSimple, TpmiAlgHash, TpmiEccCurve};
+use tpm2_rs_client::connection::{Connection, LoopbackConnection};
+use tpm2_rs_client::{ecc_parameters, hash, run_command_async};
+use tpm2_rs_server::platform::crypto::drbg_helpers::{next_u32_via_fill, next_u64_via_fill};
+use tpm2_rs_server::platform::crypto::rustcrypto::{
+    RustCryptoCipher, RustCryptoEcc, RustCryptoHash, RustCryptoRsa,
+};
+use tpm2_rs_server::platform::crypto::{Drbg, DrbgError, EntropySource};
+use tpm2_rs_server::platform::{Clock, TpmContextDeps};
+
+/// A deterministic DRBG that counts up from its entropy, which is only good enough for tests.
+struct CounterDrbg(u8);
+
+impl Drbg for CounterDrbg {
+    type Entropy = [u8; 1];
+    type Nonce = [u8; 0];
+    fn instantiate(entropy_input: &[u8; 1], _: &[u8; 0], _: &[u8]) -> Result<Self, DrbgError> {
+        Ok(CounterDrbg(entropy_input[0]))
+    }
+    fn reseed(&mut self, entropy_input: &[u8; 1], _: &[u8]) -> Result<(), DrbgError> {
+        self.0 = self.0.wrapping_add(entropy_input[0]);
+        Ok(())
+    }
+    fn next_u32(&mut self, additional_input: &[u8]) -> Result<u32, DrbgError> {
+        next_u32_via_fill(self, additional_input)
+    }
+    fn next_u64(&mut self, additional_input: &[u8]) -> Result<u64, DrbgError> {
+        next_u64_via_fill(self, additional_input)
+    }
+    fn fill_bytes(&mut self, _: &[u8], dest: &mut [u8]) -> Result<(), DrbgError> {
+        for byte in dest {
+            self.0 = self.0.wrapping_add(1);
+            *byte = self.0;
+        }
+        Ok(())
+    }
+    fn requires_reseeding(&mut self) -> bool {
+        false
+    }
+}
+
+/// An entropy source with a fixed output.
+struct FixedEntropy;
+
+impl EntropySource for FixedEntropy {
+    fn instantiate() -> Self {
+        FixedEntropy
+    }
+    fn fill_entropy(&mut self, dest: &mut [u8]) {
+        dest.fill(0x5A);
+    }
+}
+
+/// A clock that counts the milliseconds since it was created.
+struct StdClock(Instant);
+
+impl Clock for StdClock {
+    fn instantiate() -> Self {
+        StdClock(Instant::now())
+    }
+    fn now_ms(&mut self) -> u64 {
+        self.0.elapsed().as_millis() as u64
+    }
+}
+
+/// The platform of the TPM under test.
+struct TestDeps;
+
+impl TpmContextDeps for TestDeps {
+    type Drbg = CounterDrbg;
+    type EntropySource = FixedEntropy;
+    type Hash = RustCryptoHash;
+    type Ecc = RustCryptoEcc;
+    type Rsa = RustCryptoRsa;
+    type Cipher = RustCryptoCipher;
+    type Clock = StdClock;
+    type Request = [u8];
+    type Response = [u8];
+}
+
+fn connect() -> LoopbackConnection<TestDeps> {
+    LoopbackConnection::new().expect("Failed creating TPM.")
+}
+
+// Include the command-specific tests of the simulator, which run against the loopback TPM too.
+mod commands;
+
+/// The connection that the command-specific tests run over.
+type TestConnection = LoopbackConnection<TestDeps>;
+
+/// Returns the TPM error that a command over the [`TestConnection`] failed with, which is any
+/// error of the loopback connection.
+fn tpm_error(error: &TssError) -> Option<&TssError> {
+    Some(error)
+}
+
+/// The loopback TPM, with the accessor of the simulator that the command-specific tests use.
+struct LoopbackTpm(TestConnection);
+
+impl LoopbackTpm {
+    fn connection_mut(&mut self) -> &mut TestConnection {
+        &mut self.0
+    }
+}
+
+/// The server TPM starts up when it is created, so it doesn't need TPM2_Startup.
+fn get_started_tpm() -> LoopbackTpm {
+    LoopbackTpm(connect())
+}
+
+#[test]
+fn test_ecc_parameters() {
+    let mut tpm = connect();
+    let command = EccParametersCmd {
+        curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+    };
+    let resp = ecc_parameters(&mut tpm, &command).unwrap();
+    assert_eq!(resp.parameters.curve_id, TpmEccCurve::NistP256);
+    assert_eq!(resp.parameters.key_size, 256);
+}
+
+#[test]
+fn test_hash() {
+    let mut tpm = connect();
+    let command = HashCmd {
+        data: Tpm2bMaxBuffer::from_bytes(b"abc").unwrap(),
+        hash_alg: TpmiAlgHash::SHA256,
+        hierarchy: TpmHandle::RHNull,
+    };
+    let resp = hash(&mut tpm, &command).unwrap();
+    assert_eq!(resp.out_hash.get_buffer(), &Sha256::digest(b"abc")[..]);
+}
+
+#[test]
+fn test_tpm_error() {
+    let mut tpm = connect();
+    let command = HashCmd {
+        data: Tpm2bMaxBuffer::from_bytes(b"abc").unwrap(),
+        hash_alg: TpmiAlgHash::SM3256,
+        hierarchy: TpmHandle::RHNull,
+    };
+    assert_eq!(
+        hash(&mut tpm, &command),
+        Err(TpmRcError::HashFor(ErrorType::Parameter, ErrorPosition::Pos2).into())
+    );
+}
+
+#[test]
+fn test_response_buffer_too_small() {
+    let mut tpm = connect();
+    let command = [
+        0x80, 0x01, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x01, 0x7B, 0x00, 0x10,
+    ];
+    let mut response = [0; 8];
+    assert_eq!(
+        tpm.transact(&command, &mut response),
+        Err(TpmRcError::Size.into())
+    );
+}
+
+#[test]
+fn test_async_hash() {
+    let mut tpm = connect();
+    let command = HashCmd {
+        data: Tpm2bMaxBuffer::from_bytes(b"abc").unwrap(),
+        hash_alg: TpmiAlgHash::SHA256,
+        hierarchy: TpmHandle::RHNull,
+    };
+    // The TPM runs in the same process, so the command completes as soon as it is polled.
+    let Poll::Ready(resp) =
+        pin!(run_command_async(&command, &mut tpm)).poll(&mut Context::from_waker(Waker::noop()))
+    else {
+        panic!("the loopback command did not complete");
+    };
+    assert_eq!(
+        resp.unwrap().out_hash.get_buffer(),
+        &Sha256::digest(b"abc")[..]
+    );
+}
+
+#[cfg(feature = "hmac-session")]
+mod hmac_session {
+    use tpm2_rs_base::commands::{FlushContextCmd, SetCommandCodeAuditStatusCmd};
+    use tpm2_rs_base::constants::TpmCc;
+    use tpm2_rs_base::{
+        TpmaSession, TpmiAesKeyBits, TpmiAlgSymMode, TpmlCc, TpmsEmpty, TpmtSymDef,
+    };
+    use tpm2_rs_client::sessions::HmacSession;
+    use tpm2_rs_client::{flush_context, run_command_with_handles, set_command_code_audit_status};
+
+    use super::*;
+
+    /// Returns a nonce source that fills each nonce with the next value of a counter. Distinct
+    /// nonces are enough for the tests.
+    fn counter_nonces() -> impl FnMut(&mut [u8]) {
+        let mut counter = 0u8;
+        move |nonce| {
+            counter += 1;
+            nonce.fill(counter);
+        }
+    }
+
+    #[test]
+    fn test_hmac_session_authorizes_commands() {
+        let mut tpm = connect();
+        let mut session = HmacSession::start(
+            &mut tpm,
+            TpmiAlgHash::SHA256,
+            TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+            counter_nonces(),
+        )
+        .unwrap();
+        let command = SetCommandCodeAuditStatusCmd {
+            audit_alg: TpmiAlgHash::SHA256,
+            set_list: TpmlCc::new(&[TpmCc::GetRandom]).unwrap(),
+            clear_list: TpmlCc::new(&[]).unwrap(),
+        };
+        for _ in 0..2 {
+            set_command_code_audit_status(&mut tpm, TpmHandle::RHOwner, &mut session, &command)
+                .unwrap();
+        }
+        flush_context(
+            &mut tpm,
+            &FlushContextCmd {
+                flush_handle: session.handle(),
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_hmac_session_encrypts_parameters() {
+        // The data is not a multiple of the AES block size.
+        let data = [0x5A; 40];
+        let command = HashCmd {
+            data: Tpm2bMaxBuffer::from_bytes(&data).unwrap(),
+            hash_alg: TpmiAlgHash::SHA256,
+            hierarchy: TpmHandle::RHNull,
+        };
+        for symmetric in [
+            TpmtSymDef::ExclusiveOr(TpmiAlgHash::SHA256, TpmsEmpty),
+            TpmtSymDef::Aes(TpmiAesKeyBits(128), TpmiAlgSymMode::CFB),
+            TpmtSymDef::Aes(TpmiAesKeyBits(256), TpmiAlgSymMode::CFB),
+        ] {
+            let mut tpm = connect();
+            let mut session =
+                HmacSession::start(&mut tpm, TpmiAlgHash::SHA256, symmetric, counter_nonces())
+                    .unwrap();
+            session.set_attributes(
+                TpmaSession::CONTINUE_SESSION | TpmaSession::DECRYPT | TpmaSession::ENCRYPT,
+            );
+            // The TPM only hashes the right data if it decrypted it, and the client only gets the
+            // right digest back if both encrypted it the same way.
+            for _ in 0..2 {
+                let (resp, _) =
+                    run_command_with_handles(&command, (), &mut session, &mut tpm).unwrap();
+                assert_eq!(resp.out_hash.get_buffer(), &Sha256::digest(data)[..]);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "salted-session")]
+mod salted_session {
+    use hex_literal::hex;
+    use tpm2_rs_base::commands::{FlushContextCmd, LoadExternalCmd};
+    use tpm2_rs_base::{
+        PublicParmsAndId, Tpm2bEccParameter, Tpm2bPrivateKeyRsa, Tpm2bPublic, Tpm2bPublicKeyRsa,
+        Tpm2bSensitive, Tpm2bStruct, TpmaObject, TpmaSession, TpmiAesKeyBits, TpmiAlgSymMode,
+        TpmiRsaKeyBits, TpmsEccParms, TpmsEccPoint, TpmsEmpty, TpmsRsaParms, TpmtEccScheme,
+        TpmtKdfScheme, TpmtPublic, TpmtRsaScheme, TpmtSensitive, TpmtSymDef, TpmtSymDefObject,
+        TpmuSensitiveComposite,
+    };
+    use tpm2_rs_client::sessions::{HmacSession, SessionSalt};
+    use tpm2_rs_client::{flush_context, load_external, run_command_with_handles};
+
+    use super::*;
+
+    /// A deterministic xorshift generator, which is only good enough for tests.
+    struct TestRng(u64);
+
+    impl rand_core::RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest)
+        }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl rand_core::CryptoRng for TestRng {}
+
+    fn decrypt_key(parms_and_id: PublicParmsAndId) -> TpmtPublic {
+        TpmtPublic {
+            name_alg: TpmiAlgHash::SHA256,
+            object_attributes: TpmaObject::DECRYPT | TpmaObject::USER_WITH_AUTH,
+            auth_policy: Default::default(),
+            parms_and_id,
+        }
+    }
+
+    /// Returns the public and sensitive areas of the NIST P-256 key from RFC 6979 A.2.5.
+    fn ecc_key() -> (TpmtPublic, TpmtSensitive) {
+        let public = decrypt_key(PublicParmsAndId::Ecc(
+            TpmsEccParms {
+                symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+                scheme: TpmtEccScheme::Null(TpmsEmpty),
+                curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+                kdf: TpmtKdfScheme::Null(TpmsEmpty),
+            },
+            TpmsEccPoint {
+                x: Tpm2bEccParameter::from_bytes(&hex!(
+                    "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6"
+                ))
+                .unwrap(),
+                y: Tpm2bEccParameter::from_bytes(&hex!(
+                    "7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299"
+                ))
+                .unwrap(),
+            },
+        ));
+        let private = hex!("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+        let sensitive =
+            TpmuSensitiveComposite::Ecc(Tpm2bEccParameter::from_bytes(&private).unwrap());
+        (public, sensitive_area(sensitive))
+    }
+
+    /// Returns the public and sensitive areas of a 1024-bit RSA key.
+    fn rsa_key() -> (TpmtPublic, TpmtSensitive) {
+        let modulus = hex!(
+            "bb5df88f38ea1c250b0f46091b6f79986f6e391978721e0087e22175ad6ce036"
+            "9f05ae1b944100de7f967a292ce0df0075dea31371b555279e5ca53b9d3b3da2"
+            "e2f5c97f9e356eddc5a2500caa76c04f52669dbc41cbecfe35248ac588e00143"
+            "dfcd7e1154db37caf042bd7764187aba8bcf24becd5f5de971ceef89b308e699"
+        );
+        let prime = hex!(
+            "f2f64b89415a99ca69b101162a838fd73b9f72e3e6685e6fe4ebcaabdf7c2fcc"
+            "080102fcc4c4d0db5062bc0952985901655fd89a3d1f41507a74a434f66c7bb7"
+        );
+        let public = decrypt_key(PublicParmsAndId::Rsa(
+            TpmsRsaParms {
+                symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+                scheme: TpmtRsaScheme::Null(TpmsEmpty),
+                key_bits: TpmiRsaKeyBits(1024),
+                exponent: 0,
+            },
+            Tpm2bPublicKeyRsa::from_bytes(&modulus).unwrap(),
+        ));
+        let sensitive =
+            TpmuSensitiveComposite::Rsa(Tpm2bPrivateKeyRsa::from_bytes(&prime).unwrap());
+        (public, sensitive_area(sensitive))
+    }
+
+    fn sensitive_area(sensitive: TpmuSensitiveComposite) -> TpmtSensitive {
+        TpmtSensitive {
+            auth_value: Default::default(),
+            seed_value: Default::default(),
+            sensitive,
+        }
+    }
+
+    #[test]
+    fn test_salted_session_encrypts_parameters() {
+        // The data is not a multiple of the AES block size.
+        let data = [0x5A; 40];
+        let command = HashCmd {
+            data: Tpm2bMaxBuffer::from_bytes(&data).unwrap(),
+            hash_alg: TpmiAlgHash::SHA256,
+            hierarchy: TpmHandle::RHNull,
+        };
+        for (public, sensitive) in [ecc_key(), rsa_key()] {
+            let mut tpm = connect();
+            let (tpm_key, _) = load_external(
+                &mut tpm,
+                &LoadExternalCmd {
+                    in_private: Tpm2bSensitive::from_struct(&sensitive).unwrap(),
+                    in_public: Tpm2bPublic::from_struct(&public).unwrap(),
+                    hierarchy: TpmHandle::RHNull,
+                },
+            )
+            .unwrap();
+            let salt = SessionSalt::generate(tpm_key, &public, &mut TestRng(1)).unwrap();
+            let mut nonce_rng = TestRng(2);
+            let mut session = HmacSession::start_with(
+                &mut tpm,
+                Some(&salt),
+                None,
+                TpmiAlgHash::SHA256,
+                TpmtSymDef::Aes(TpmiAesKeyBits(128), TpmiAlgSymMode::CFB),
+                move |nonce: &mut [u8]| rand_core::RngCore::fill_bytes(&mut nonce_rng, nonce),
+            )
+            .unwrap();
+            session.set_attributes(
+                TpmaSession::CONTINUE_SESSION | TpmaSession::DECRYPT | TpmaSession::ENCRYPT,
+            );
+            // The session key is derived from the salt alone, so the TPM only hashes the right
+            // data and the response only verifies if the TPM recovered the salt.
+            for _ in 0..2 {
+                let (resp, _) =
+                    run_command_with_handles(&command, (), &mut session, &mut tpm).unwrap();
+                assert_eq!(resp.out_hash.get_buffer(), &Sha256::digest(data)[..]);
+            }
+            flush_context(
+                &mut tpm,
+                &FlushContextCmd {
+                    flush_handle: session.handle(),
+                },
+            )
+            .unwrap();
+        }
+    }
+}
@@ -9,14 +9,24 @@
 /// These tests must be run with `--test-threads=1`, because they use a single TCP port.
 use std::io::Result;
 
-use tpm2_rs_base::commands::StartupCmd;
-use tpm2_rs_base::constants::TpmSu;
-use tpm2_rs_client::connection::TcpSimulator;
+use tpm2_rs_base::commands::{GetCapabilityCmd, StartupCmd};
+use tpm2_rs_base::constants::{TpmCap, TpmPt, TpmSu};
+use tpm2_rs_base::errors::TssError;
+use tpm2_rs_base::{TpmlTaggedTpmProperty, TpmsCapabilityData, TpmsTaggedProperty};
+use tpm2_rs_client::connection::{TcpConnection, TcpSimulator};
 use tpm2_rs_client::run_command;
 
 // Include the command-specific tests
 mod commands;
 
+/// The connection that the command-specific tests run over.
+type TestConnection = TcpConnection;
+
+/// Returns the TPM error that a command over the [`TestConnection`] failed with, if any.
+fn tpm_error(error: &std::io::Error) -> Option<&TssError> {
+    error.get_ref().and_then(|e| e.downcast_ref::<TssError>())
+}
+
 /// Environment variable used to connect to the TPM simulator over TCP.
 const ENV_VAR_SIMULATOR_IP: &str = "SIMULATOR_IP";
 
@@ -101,3 +111,38 @@ fn get_started_tpm() -> TcpSimulator {
     run_command(&startup, simulator.connection_mut()).unwrap();
     simulator
 }
+
+// The manufacturer is that of the reference simulator, and the server TPM has no GetCapability.
+#[test]
+fn test_get_capability_manufacturer_id() {
+    let mut tpm = get_started_tpm();
+
+    let mut expected = TpmlTaggedTpmProperty {
+        count: 1,
+        tpm_property: [TpmsTaggedProperty::default(); 127],
+    };
+
+    expected.tpm_property[0] = TpmsTaggedProperty {
+        property: TpmPt::Manufacturer,
+        value: 0x58595A20,
+    };
+
+    let command = GetCapabilityCmd {
+        capability: TpmCap::TPMProperties,
+        property: TpmPt::Manufacturer,
+        property_count: 1,
+    };
+
+    // We allow panic in test cases.
+    let resp = run_command(&command, tpm.connection_mut()).expect("Failed running command.");
+
+    // Extract the TpmlTaggedTpmProperty data form the response.
+    let TpmsCapabilityData::TpmProperties(received) = resp.capability_data else {
+        panic!("Unexpected variant data.")
+    };
+
+    assert_eq!(
+        received, expected,
+        "Received did not match the expected response."
+    );
+}
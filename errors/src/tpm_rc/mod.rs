@@ -25,6 +25,24 @@ impl TpmRcError {
         Self::new(Self::Asymmetric.0.get() | on.to_mask() | pos.to_mask())
     }
 
+    /// Inconsistent attributes (`TPM_RC_ATTRIBUTES`).
+    pub const Attributes: Self = Self::new(Self::RC_FMT1 + 0x002);
+
+    /// Inconsistent attributes for the specified parameters (`TPM_RC_ATTRIBUTES`).
+    #[allow(non_snake_case)]
+    pub const fn AttributesFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Attributes.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Hash algorithm not supported or not appropriate (`TPM_RC_HASH`).
+    pub const Hash: Self = Self::new(Self::RC_FMT1 + 0x003);
+
+    /// Hash algorithm not supported or not appropriate for the specified parameters (`TPM_RC_HASH`).
+    #[allow(non_snake_case)]
+    pub const fn HashFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Hash.0.get() | on.to_mask() | pos.to_mask())
+    }
+
     /// Value is out of range or is not correct for the context (`TPM_RC_VALUE`).
     pub const Value: Self = Self::new(Self::RC_FMT1 + 0x004);
 
@@ -35,6 +53,96 @@ impl TpmRcError {
         Self::new(Self::Value.0.get() | on.to_mask() | pos.to_mask())
     }
 
+    /// Hierarchy is not enabled or is not correct for the use (`TPM_RC_HIERARCHY`).
+    pub const Hierarchy: Self = Self::new(Self::RC_FMT1 + 0x005);
+
+    /// Hierarchy is not enabled or is not correct for the use for the specified parameters (`TPM_RC_HIERARCHY`).
+    #[allow(non_snake_case)]
+    pub const fn HierarchyFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Hierarchy.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Key size not supported (`TPM_RC_KEY_SIZE`).
+    pub const KeySize: Self = Self::new(Self::RC_FMT1 + 0x007);
+
+    /// Key size not supported for the specified parameters (`TPM_RC_KEY_SIZE`).
+    #[allow(non_snake_case)]
+    pub const fn KeySizeFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::KeySize.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Mode of operation not supported (`TPM_RC_MODE`).
+    pub const Mode: Self = Self::new(Self::RC_FMT1 + 0x009);
+
+    /// Mode of operation not supported for the specified parameters (`TPM_RC_MODE`).
+    #[allow(non_snake_case)]
+    pub const fn ModeFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Mode.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// The type of the value is not appropriate for the use (`TPM_RC_TYPE`).
+    pub const Type: Self = Self::new(Self::RC_FMT1 + 0x00A);
+
+    /// The type of the value is not appropriate for the use for the specified parameters (`TPM_RC_TYPE`).
+    #[allow(non_snake_case)]
+    pub const fn TypeFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Type.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// The handle is not correct for the use (`TPM_RC_HANDLE`).
+    pub const Handle: Self = Self::new(Self::RC_FMT1 + 0x00B);
+
+    /// The handle is not correct for the use for the specified parameters (`TPM_RC_HANDLE`).
+    #[allow(non_snake_case)]
+    pub const fn HandleFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Handle.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Unsupported key derivation function or function not appropriate for use (`TPM_RC_KDF`).
+    pub const Kdf: Self = Self::new(Self::RC_FMT1 + 0x00C);
+
+    /// Unsupported key derivation function or function not appropriate for use for the specified parameters (`TPM_RC_KDF`).
+    #[allow(non_snake_case)]
+    pub const fn KdfFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Kdf.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Value was out of allowed range (`TPM_RC_RANGE`).
+    pub const Range: Self = Self::new(Self::RC_FMT1 + 0x00D);
+
+    /// Value was out of allowed range for the specified parameters (`TPM_RC_RANGE`).
+    #[allow(non_snake_case)]
+    pub const fn RangeFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Range.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// The authorization HMAC check failed and DA counter incremented (`TPM_RC_AUTH_FAIL`).
+    pub const AuthFail: Self = Self::new(Self::RC_FMT1 + 0x00E);
+
+    /// The authorization HMAC check failed and DA counter incremented for the specified parameters (`TPM_RC_AUTH_FAIL`).
+    #[allow(non_snake_case)]
+    pub const fn AuthFailFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::AuthFail.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Invalid nonce size or nonce value mismatch (`TPM_RC_NONCE`).
+    pub const Nonce: Self = Self::new(Self::RC_FMT1 + 0x00F);
+
+    /// Invalid nonce size or nonce value mismatch for the specified parameters (`TPM_RC_NONCE`).
+    #[allow(non_snake_case)]
+    pub const fn NonceFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Nonce.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Unsupported or incompatible scheme (`TPM_RC_SCHEME`).
+    pub const Scheme: Self = Self::new(Self::RC_FMT1 + 0x012);
+
+    /// Unsupported or incompatible scheme for the specified parameters (`TPM_RC_SCHEME`).
+    #[allow(non_snake_case)]
+    pub const fn SchemeFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Scheme.0.get() | on.to_mask() | pos.to_mask())
+    }
+
     /// Structure is the wrong size (`TPM_RC_SIZE`).
     pub const Size: Self = Self::new(Self::RC_FMT1 + 0x015);
 
@@ -44,6 +152,15 @@ impl TpmRcError {
         Self::new(Self::Size.0.get() | on.to_mask() | pos.to_mask())
     }
 
+    /// Unsupported symmetric algorithm or key size, or not appropriate for instance (`TPM_RC_SYMMETRIC`).
+    pub const Symmetric: Self = Self::new(Self::RC_FMT1 + 0x016);
+
+    /// Unsupported symmetric algorithm or key size, or not appropriate for instance for the specified parameters (`TPM_RC_SYMMETRIC`).
+    #[allow(non_snake_case)]
+    pub const fn SymmetricFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Symmetric.0.get() | on.to_mask() | pos.to_mask())
+    }
+
     /// Union selector is incorrect (`TPM_RC_SELECTOR`).
     pub const Selector: Self = Self::new(Self::RC_FMT1 + 0x018);
 
@@ -53,6 +170,105 @@ impl TpmRcError {
         Self::new(Self::Selector.0.get() | on.to_mask() | pos.to_mask())
     }
 
+    /// The TPM was unable to unmarshal a value because there were not enough octets in the input buffer (`TPM_RC_INSUFFICIENT`).
+    pub const Insufficient: Self = Self::new(Self::RC_FMT1 + 0x01A);
+
+    /// The TPM was unable to unmarshal a value because there were not enough octets in the input buffer for the specified parameters (`TPM_RC_INSUFFICIENT`).
+    #[allow(non_snake_case)]
+    pub const fn InsufficientFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Insufficient.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// The signature is not valid (`TPM_RC_SIGNATURE`).
+    pub const Signature: Self = Self::new(Self::RC_FMT1 + 0x01B);
+
+    /// The signature is not valid for the specified parameters (`TPM_RC_SIGNATURE`).
+    #[allow(non_snake_case)]
+    pub const fn SignatureFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Signature.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Key fields are not compatible with the selected use (`TPM_RC_KEY`).
+    pub const Key: Self = Self::new(Self::RC_FMT1 + 0x01C);
+
+    /// Key fields are not compatible with the selected use for the specified parameters (`TPM_RC_KEY`).
+    #[allow(non_snake_case)]
+    pub const fn KeyFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Key.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Invalid ticket (`TPM_RC_TICKET`).
+    pub const Ticket: Self = Self::new(Self::RC_FMT1 + 0x020);
+
+    /// Invalid ticket for the specified parameters (`TPM_RC_TICKET`).
+    #[allow(non_snake_case)]
+    pub const fn TicketFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Ticket.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// A policy check failed (`TPM_RC_POLICY_FAIL`).
+    pub const PolicyFail: Self = Self::new(Self::RC_FMT1 + 0x01D);
+
+    /// A policy check failed for the specified session (`TPM_RC_POLICY_FAIL`).
+    #[allow(non_snake_case)]
+    pub const fn PolicyFailFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::PolicyFail.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Authorization failure without DA implications (`TPM_RC_BAD_AUTH`).
+    pub const BadAuth: Self = Self::new(Self::RC_FMT1 + 0x022);
+
+    /// Authorization failure without DA implications for the specified parameters (`TPM_RC_BAD_AUTH`).
+    #[allow(non_snake_case)]
+    pub const fn BadAuthFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::BadAuth.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// The policy has expired (`TPM_RC_EXPIRED`).
+    pub const Expired: Self = Self::new(Self::RC_FMT1 + 0x023);
+
+    /// The policy has expired for the specified parameters (`TPM_RC_EXPIRED`).
+    #[allow(non_snake_case)]
+    pub const fn ExpiredFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Expired.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// The command code in a policy is not the command code of the command, or the command code in a policy command references a command that is not implemented (`TPM_RC_POLICY_CC`).
+    pub const PolicyCc: Self = Self::new(Self::RC_FMT1 + 0x024);
+
+    /// The command code in the policy of the specified session is not the command code of the command (`TPM_RC_POLICY_CC`).
+    #[allow(non_snake_case)]
+    pub const fn PolicyCcFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::PolicyCc.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// The public and sensitive portions of an object are not cryptographically bound (`TPM_RC_BINDING`).
+    pub const Binding: Self = Self::new(Self::RC_FMT1 + 0x025);
+
+    /// The public and sensitive portions of an object are not cryptographically bound for the specified parameters (`TPM_RC_BINDING`).
+    #[allow(non_snake_case)]
+    pub const fn BindingFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Binding.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Curve not supported (`TPM_RC_CURVE`).
+    pub const Curve: Self = Self::new(Self::RC_FMT1 + 0x026);
+
+    /// Curve not supported for the specified parameters (`TPM_RC_CURVE`).
+    #[allow(non_snake_case)]
+    pub const fn CurveFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::Curve.0.get() | on.to_mask() | pos.to_mask())
+    }
+
+    /// Point is not on the required curve (`TPM_RC_ECC_POINT`).
+    pub const EccPoint: Self = Self::new(Self::RC_FMT1 + 0x027);
+
+    /// Point is not on the required curve for the specified parameters (`TPM_RC_ECC_POINT`).
+    #[allow(non_snake_case)]
+    pub const fn EccPointFor(on: ErrorType, pos: ErrorPosition) -> Self {
+        Self::new(Self::EccPoint.0.get() | on.to_mask() | pos.to_mask())
+    }
+
     /// The tag is bad (`TPM_RC_BAD_TAG`).
     pub const BadTag: Self = Self::new(0x1e);
 
@@ -63,6 +279,24 @@ impl TpmRcError {
     /// Improper use of a sequence handle (`TPM_RC_SEQUENCE`).
     pub const Sequence: Self = Self::new(0x102);
 
+    /// The command failed because an audit sequence required exclusivity (`TPM_RC_EXCLUSIVE`).
+    pub const Exclusive: Self = Self::new(0x121);
+
+    /// The authorization session is not of the type required by the command
+    /// (`TPM_RC_AUTH_TYPE`).
+    pub const AuthType: Self = Self::new(0x124);
+
+    /// The command must have an authorization session for a handle and it is not present
+    /// (`TPM_RC_AUTH_MISSING`).
+    pub const AuthMissing: Self = Self::new(0x125);
+
+    /// The policy session does not allow the command to be executed (`TPM_RC_POLICY`).
+    pub const Policy: Self = Self::new(0x126);
+
+    /// Authorization for the object is not available with the type of authorization session
+    /// used (`TPM_RC_AUTH_UNAVAILABLE`).
+    pub const AuthUnavailable: Self = Self::new(0x12F);
+
     /// Command commandSize value is inconsistent with contents of the command buffer; either the
     /// size is not the same as the octets loaded by the hardware interface layer or the value is
     /// not large enough to hold a command header (`TPM_RC_COMMAND_SIZE`).
@@ -71,6 +305,22 @@ impl TpmRcError {
     /// Command code not supported (`TPM_RC_COMMAND_CODE`).
     pub const CommandCode: Self = Self::new(0x143);
 
+    /// The value of authorizationSize is out of range or the number of octets in the
+    /// Authorization Area is greater than required (`TPM_RC_AUTHSIZE`).
+    pub const AuthSize: Self = Self::new(0x144);
+
+    /// Use of an authorization session with a context command or another command that cannot
+    /// have an authorization session (`TPM_RC_AUTH_CONTEXT`).
+    pub const AuthContext: Self = Self::new(0x145);
+
+    /// The cpHash or nameHash of a policy session is already set to a different value
+    /// (`TPM_RC_CPHASH`).
+    pub const CpHash: Self = Self::new(0x151);
+
+    /// The TPM was not able to produce a result, e.g. because of a random value that could not be
+    /// used (`TPM_RC_NO_RESULT`).
+    pub const NoResult: Self = Self::new(0x154);
+
     /// Gap for context ID is too large (`TPM_RC_CONTEXT_GAP`).
     pub const ContextGap: Self = Self::new(0x901);
 
@@ -83,6 +333,32 @@ impl TpmRcError {
     /// Out of shared object/session memory or need space for internal operations (`TPM_RC_MEMORY`).
     pub const Memory: Self = Self::new(0x904);
 
+    /// The command is not allowed at the locality of the command (`TPM_RC_LOCALITY`).
+    pub const Locality: Self = Self::new(0x907);
+
+    /// The TPM has suspended operation on the command; forward progress was made and the command
+    /// may be retried (`TPM_RC_YIELDED`).
+    pub const Yielded: Self = Self::new(0x908);
+
+    /// The TPM is performing self-tests (`TPM_RC_TESTING`).
+    pub const Testing: Self = Self::new(0x90A);
+
+    /// The first session in the authorization area is not loaded (`TPM_RC_REFERENCE_S0`).
+    pub const ReferenceS0: Self = Self::new(0x918);
+
+    /// The TPM is rate-limiting accesses to prevent wearout of NV (`TPM_RC_NV_RATE`).
+    pub const NvRate: Self = Self::new(0x920);
+
+    /// The TPM was not able to start the command (`TPM_RC_RETRY`).
+    pub const Retry: Self = Self::new(0x922);
+
+    /// The session at the specified position in the authorization area is not loaded
+    /// (`TPM_RC_REFERENCE_S0` through `TPM_RC_REFERENCE_S6`).
+    #[allow(non_snake_case)]
+    pub const fn ReferenceFor(pos: ErrorPosition) -> Self {
+        Self::new(Self::ReferenceS0.0.get() + pos as u32 - 1)
+    }
+
     /// Returns the underlying non-zero `u32`.
     pub const fn get(self) -> u32 {
         self.0.get()
@@ -16,4 +16,40 @@ fn test_format1() {
 fn test_warning() {
     let error = TpmRcError::Memory;
     assert!(error.is_warning());
+    for error in [
+        TpmRcError::Yielded,
+        TpmRcError::Testing,
+        TpmRcError::NvRate,
+        TpmRcError::Retry,
+    ] {
+        assert!(error.is_warning());
+    }
+}
+
+#[test]
+fn test_session_reference() {
+    assert_eq!(
+        TpmRcError::ReferenceFor(ErrorPosition::Pos1),
+        TpmRcError::ReferenceS0
+    );
+    assert_eq!(TpmRcError::ReferenceFor(ErrorPosition::Pos3).get(), 0x91A);
+    assert!(TpmRcError::ReferenceFor(ErrorPosition::Pos2).is_warning());
+}
+
+#[test]
+fn test_policy_errors() {
+    assert_eq!(
+        TpmRcError::PolicyFailFor(ErrorType::Session, ErrorPosition::Pos1).get(),
+        0x99D
+    );
+    assert_eq!(
+        TpmRcError::PolicyCcFor(ErrorType::Session, ErrorPosition::Pos1).get(),
+        0x9A4
+    );
+    assert_eq!(
+        TpmRcError::ExpiredFor(ErrorType::Parameter, ErrorPosition::Pos4).get(),
+        0x4E3
+    );
+    assert_eq!(TpmRcError::CpHash.get(), 0x151);
+    assert!(TpmRcError::Locality.is_warning());
 }
@@ -251,6 +251,18 @@ fn derive_tpm2b_simple(
             }
         }
 
+        // Only the used part of the buffer is formatted, in hexadecimal.
+        impl core::fmt::Debug for #tpm2b_outer_struct_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(stringify!(#tpm2b_outer_struct_name))?;
+                f.write_str("(")?;
+                for byte in self.as_ref() {
+                    write!(f, "{byte:02x}")?;
+                }
+                f.write_str(")")
+            }
+        }
+
         impl Marshalable for #tpm2b_outer_struct_name {
             fn try_unmarshal(buffer: &mut UnmarshalBuf) -> tpm2_rs_marshalable::Result<Self> {
                 let got_size = u16::try_unmarshal(buffer)?;
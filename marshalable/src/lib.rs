@@ -128,6 +128,27 @@ impl Marshalable for () {
     }
 }
 
+// Helper to define Marshalable for tuples, which are marshaled as their elements in order. This is
+// used for the handle areas of commands with several handles.
+// Each $T is the type of an element and $idx is its index in the tuple.
+macro_rules! impl_tuple_marshalable {
+    ($($T:ident $idx:tt),+) => {
+        impl<$($T: Marshalable),+> Marshalable for ($($T,)+) {
+            fn try_unmarshal(buffer: &mut UnmarshalBuf) -> Result<Self> {
+                Ok(($($T::try_unmarshal(buffer)?,)+))
+            }
+
+            fn try_marshal(&self, buffer: &mut [u8]) -> Result<usize> {
+                let mut written = 0;
+                $(written += self.$idx.try_marshal(&mut buffer[written..])?;)+
+                Ok(written)
+            }
+        }
+    };
+}
+impl_tuple_marshalable! {A 0, B 1}
+impl_tuple_marshalable! {A 0, B 1, C 2}
+
 impl<const M: usize> Marshalable for [u8; M] {
     fn try_unmarshal(buffer: &mut UnmarshalBuf) -> Result<Self> {
         if let Some(mine) = buffer.get(M) {
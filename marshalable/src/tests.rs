@@ -254,3 +254,14 @@ fn test_derive_enum() -> Result<(), TpmRcError> {
 
     Ok(())
 }
+
+#[test]
+fn test_tuple() {
+    let value: (u32, u16, u8) = (0x01020304, 0x0506, 0x07);
+    let mut buffer = [0u8; 7];
+    assert_eq!(value.try_marshal(&mut buffer), Ok(7));
+    assert_eq!(buffer, [1, 2, 3, 4, 5, 6, 7]);
+    let unmarshaled = <(u32, u16, u8)>::try_unmarshal(&mut UnmarshalBuf::new(&buffer));
+    assert_eq!(unmarshaled, Ok(value));
+    assert!((0u32, 0u32).try_marshal(&mut buffer).is_err());
+}
@@ -0,0 +1,120 @@
+use tpm2_rs_base::constants::TpmCc;
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::{Tpm2bDigest, Tpm2bSimple, TpmiAlgHash};
+
+use crate::command::CommandAttributes;
+use crate::crypto::hash::{digest, Hasher};
+use crate::platform::crypto::Hash;
+
+/// The command codes that can be selected for audit, which are all command codes of
+/// [TPM2.0 1.83] Part 2, 6.5.2.
+const FIRST_COMMAND: u32 = TpmCc::NVUndefineSpaceSpecial.0;
+const LAST_COMMAND: u32 = TpmCc::ECCDecrypt.0;
+
+/// Keeps track of the commands selected for audit and the digest of their executions, see
+/// [TPM2.0 1.83] Part 1, 33.
+///
+/// This TPM has no non-volatile memory to persist the audit state in, so the audit counter and
+/// the selected commands start over whenever the TPM is created.
+pub struct CommandAudit {
+    /// The hash algorithm of the audit digest.
+    alg: TpmiAlgHash,
+    /// One bit for each command code starting at [`FIRST_COMMAND`] that is set if the command is
+    /// audited.
+    audited: u128,
+    /// The digest of the audited commands and their responses, which is empty when it was cleared.
+    digest: Tpm2bDigest,
+    /// The number of times the audit digest was started after it was cleared.
+    counter: u64,
+}
+
+impl CommandAudit {
+    /// Starts with only TPM2_SetCommandCodeAuditStatus being audited.
+    pub fn new() -> Self {
+        let mut audit = Self {
+            alg: TpmiAlgHash::SHA256,
+            audited: 0,
+            digest: Tpm2bDigest::default(),
+            counter: 0,
+        };
+        audit.set(TpmCc::SetCommandCodeAuditStatus, true);
+        audit
+    }
+
+    /// Returns the bit of `command_code` in the audited commands, or `None` if the command can't be
+    /// audited by this TPM.
+    fn bit(command_code: TpmCc) -> Option<u128> {
+        if !(FIRST_COMMAND..=LAST_COMMAND).contains(&command_code.0) {
+            return None;
+        }
+        CommandAttributes::lookup(command_code)?;
+        Some(1 << (command_code.0 - FIRST_COMMAND))
+    }
+
+    /// Returns the hash algorithm of the audit digest.
+    pub fn alg(&self) -> TpmiAlgHash {
+        self.alg
+    }
+
+    /// Returns the audit digest.
+    pub fn digest(&self) -> &Tpm2bDigest {
+        &self.digest
+    }
+
+    /// Returns the audit counter.
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    /// Returns whether the execution of `command_code` is recorded in the audit digest.
+    pub fn is_audited(&self, command_code: TpmCc) -> bool {
+        Self::bit(command_code).is_some_and(|bit| self.audited & bit != 0)
+    }
+
+    /// Adds `command_code` to or removes it from the audited commands. Commands that are not
+    /// implemented are ignored and TPM2_SetCommandCodeAuditStatus is always audited.
+    pub fn set(&mut self, command_code: TpmCc, audited: bool) {
+        let Some(bit) = Self::bit(command_code) else {
+            return;
+        };
+        if audited || command_code == TpmCc::SetCommandCodeAuditStatus {
+            self.audited |= bit;
+        } else {
+            self.audited &= !bit;
+        }
+    }
+
+    /// Changes the hash algorithm of the audit digest, which clears the digest.
+    pub fn set_alg(&mut self, alg: TpmiAlgHash) {
+        self.alg = alg;
+        self.clear();
+    }
+
+    /// Clears the audit digest. The audit counter is incremented when the next audited command
+    /// starts a new digest.
+    pub fn clear(&mut self) {
+        self.digest = Tpm2bDigest::default();
+    }
+
+    /// Computes the digest of the audited command codes in ascending order.
+    pub fn command_digest<H: Hash>(&self) -> Result<Tpm2bDigest, TpmRcError> {
+        let mut hasher = Hasher::<H>::start(self.alg)?;
+        for offset in 0..=(LAST_COMMAND - FIRST_COMMAND) {
+            if self.audited & (1 << offset) != 0 {
+                hasher.update(&(FIRST_COMMAND + offset).to_be_bytes());
+            }
+        }
+        Tpm2bDigest::from_bytes(hasher.finish().as_ref()).or(Err(TpmRcError::Failure))
+    }
+
+    /// Extends the audit digest with an audited command and its response, whose cpHash and rpHash
+    /// were computed with [`CommandAudit::alg`].
+    pub fn extend<H: Hash>(&mut self, cp_hash: &[u8], rp_hash: &[u8]) -> Result<(), TpmRcError> {
+        if self.digest.get_size() == 0 {
+            self.counter = self.counter.wrapping_add(1);
+        }
+        let extended = digest::<H>(self.alg, &[self.digest.get_buffer(), cp_hash, rp_hash])?;
+        self.digest = Tpm2bDigest::from_bytes(extended.as_ref()).or(Err(TpmRcError::Failure))?;
+        Ok(())
+    }
+}
@@ -38,4 +38,17 @@ impl<W: TpmWriteBuffer + ?Sized> TpmReadBuffer for InOutBuffer<'_, W> {
         }
         self.buffer.read_into(offset, out)
     }
+
+    fn read_callback<R>(
+        &self,
+        offset: usize,
+        size: usize,
+        callback: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, ReadOutOfBounds> {
+        // Limit the read view to only the request portion of the in-place buffer
+        if self.len < offset + size {
+            return Err(ReadOutOfBounds);
+        }
+        self.buffer.read_callback(offset, size, callback)
+    }
 }
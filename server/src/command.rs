@@ -0,0 +1,101 @@
+use tpm2_rs_base::constants::TpmCc;
+use tpm2_rs_base::errors::ErrorPosition;
+
+/// The largest number of handles in the handle area of any command.
+pub const MAX_HANDLES: usize = 3;
+
+/// The largest number of sessions in the authorization area of any command.
+pub const MAX_SESSIONS: usize = 3;
+
+/// The error positions of the handles and sessions of a command.
+pub const POSITIONS: [ErrorPosition; MAX_HANDLES] = [
+    ErrorPosition::Pos1,
+    ErrorPosition::Pos2,
+    ErrorPosition::Pos3,
+];
+
+/// Describes the handle areas and the parameters that sessions may encrypt of a command supported by this TPM as listed in [TPM2.0 1.83] Part 3.
+#[derive(Clone, Copy)]
+pub struct CommandAttributes {
+    /// The number of handles in the handle area of the command.
+    pub handles: usize,
+    /// The number of handles that require authorization. These are always the first handles of
+    /// the handle area.
+    pub auth_handles: usize,
+    /// The number of handles in the handle area of the response.
+    pub response_handles: usize,
+    /// Whether the first command parameter is a TPM2B that a session may encrypt.
+    pub decrypt: bool,
+    /// Whether the first response parameter is a TPM2B that a session may encrypt.
+    pub encrypt: bool,
+}
+
+impl CommandAttributes {
+    const fn new(handles: usize, auth_handles: usize, response_handles: usize) -> Self {
+        Self {
+            handles,
+            auth_handles,
+            response_handles,
+            decrypt: false,
+            encrypt: false,
+        }
+    }
+
+    /// Allows sessions to encrypt the first command parameter.
+    const fn decrypt(self) -> Self {
+        Self {
+            decrypt: true,
+            ..self
+        }
+    }
+
+    /// Allows sessions to encrypt the first response parameter.
+    const fn encrypt(self) -> Self {
+        Self {
+            encrypt: true,
+            ..self
+        }
+    }
+
+    /// Returns the attributes of the command, or `None` if the command is not supported.
+    pub fn lookup(command_code: TpmCc) -> Option<Self> {
+        let attributes = match command_code {
+            TpmCc::Certify => Self::new(2, 2, 0).decrypt().encrypt(),
+            TpmCc::CertifyCreation => Self::new(2, 1, 0).decrypt().encrypt(),
+            TpmCc::ECCDecrypt => Self::new(1, 1, 0).decrypt().encrypt(),
+            TpmCc::ECCEncrypt => Self::new(1, 0, 0).decrypt().encrypt(),
+            TpmCc::ECCParameters => Self::new(0, 0, 0),
+            TpmCc::EncryptDecrypt => Self::new(1, 1, 0).encrypt(),
+            TpmCc::EncryptDecrypt2 => Self::new(1, 1, 0).decrypt().encrypt(),
+            TpmCc::FlushContext => Self::new(0, 0, 0),
+            TpmCc::GetCommandAuditDigest => Self::new(2, 2, 0).decrypt().encrypt(),
+            TpmCc::GetRandom => Self::new(0, 0, 0),
+            TpmCc::GetSessionAuditDigest => Self::new(3, 2, 0).decrypt().encrypt(),
+            TpmCc::GetTime => Self::new(2, 2, 0).decrypt().encrypt(),
+            TpmCc::Hash => Self::new(0, 0, 0).decrypt().encrypt(),
+            TpmCc::LoadExternal => Self::new(0, 0, 1).decrypt().encrypt(),
+            TpmCc::PolicyAuthValue => Self::new(1, 0, 0),
+            TpmCc::PolicyAuthorize => Self::new(1, 0, 0).decrypt(),
+            TpmCc::PolicyCommandCode => Self::new(1, 0, 0),
+            TpmCc::PolicyCounterTimer => Self::new(1, 0, 0).decrypt(),
+            TpmCc::PolicyCpHash => Self::new(1, 0, 0).decrypt(),
+            TpmCc::PolicyGetDigest => Self::new(1, 0, 0).encrypt(),
+            TpmCc::PolicyLocality => Self::new(1, 0, 0),
+            TpmCc::PolicyNameHash => Self::new(1, 0, 0).decrypt(),
+            TpmCc::PolicyOR => Self::new(1, 0, 0),
+            TpmCc::PolicyPassword => Self::new(1, 0, 0),
+            TpmCc::PolicyPCR => Self::new(1, 0, 0).decrypt(),
+            TpmCc::PolicyRestart => Self::new(1, 0, 0),
+            TpmCc::PolicySecret => Self::new(2, 1, 0).decrypt().encrypt(),
+            TpmCc::PolicySigned => Self::new(2, 0, 0).decrypt().encrypt(),
+            TpmCc::Quote => Self::new(1, 1, 0).decrypt().encrypt(),
+            TpmCc::SetCommandCodeAuditStatus => Self::new(1, 1, 0),
+            TpmCc::Sign => Self::new(1, 1, 0).decrypt(),
+            TpmCc::StartAuthSession => Self::new(2, 0, 1).decrypt().encrypt(),
+            TpmCc::TestParams => Self::new(0, 0, 0),
+            TpmCc::VerifySignature => Self::new(1, 0, 0).decrypt(),
+            _ => return None,
+        };
+        Some(attributes)
+    }
+}
@@ -0,0 +1,141 @@
+//! Checks whether algorithms and their parameters are supported by this TPM.
+
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{
+    TpmiAesKeyBits, TpmiAlgHash, TpmiAlgKdf, TpmiAlgSymMode, TpmtEccScheme, TpmtKdfScheme,
+    TpmtKeyedHashScheme, TpmtPublicParms, TpmtRsaScheme, TpmtSymDef, TpmtSymDefObject,
+};
+
+use crate::crypto::ecc::EccCurve;
+
+/// The handle, parameter or session that a failed check is reported against.
+pub type ErrorAt = (ErrorType, ErrorPosition);
+
+/// The RSA key sizes in bits that this TPM supports.
+const SUPPORTED_RSA_KEY_BITS: [u16; 4] = [1024, 2048, 3072, 4096];
+
+/// The AES key sizes in bits that this TPM supports.
+const SUPPORTED_AES_KEY_BITS: [u16; 3] = [128, 192, 256];
+
+/// Checks that the hash algorithm is supported.
+pub fn check_hash(hash: TpmiAlgHash, at: ErrorAt) -> Result<(), TpmRcError> {
+    match hash {
+        TpmiAlgHash::SHA1 | TpmiAlgHash::SHA256 | TpmiAlgHash::SHA384 | TpmiAlgHash::SHA512 => {
+            Ok(())
+        }
+        _ => Err(TpmRcError::HashFor(at.0, at.1)),
+    }
+}
+
+/// Checks that the AES key size is supported.
+fn check_aes_key_bits(key_bits: TpmiAesKeyBits, at: ErrorAt) -> Result<(), TpmRcError> {
+    if !SUPPORTED_AES_KEY_BITS.contains(&key_bits.0) {
+        return Err(TpmRcError::KeySizeFor(at.0, at.1));
+    }
+    Ok(())
+}
+
+/// Checks that the block cipher mode is supported.
+pub fn check_mode(mode: TpmiAlgSymMode, at: ErrorAt) -> Result<(), TpmRcError> {
+    match mode {
+        TpmiAlgSymMode::CTR
+        | TpmiAlgSymMode::OFB
+        | TpmiAlgSymMode::CBC
+        | TpmiAlgSymMode::CFB
+        | TpmiAlgSymMode::ECB => Ok(()),
+        _ => Err(TpmRcError::ModeFor(at.0, at.1)),
+    }
+}
+
+/// Checks that the symmetric definition of an object is supported.
+pub fn check_symmetric(symmetric: &TpmtSymDefObject, at: ErrorAt) -> Result<(), TpmRcError> {
+    match symmetric {
+        TpmtSymDefObject::Aes(key_bits, mode) => {
+            check_aes_key_bits(*key_bits, at)?;
+            check_mode(*mode, at)
+        }
+        TpmtSymDefObject::Null(..) => Ok(()),
+        _ => Err(TpmRcError::SymmetricFor(at.0, at.1)),
+    }
+}
+
+/// Checks that the symmetric definition of a session is supported, which is AES in CFB mode, XOR
+/// obfuscation or null.
+pub fn check_session_symmetric(symmetric: &TpmtSymDef, at: ErrorAt) -> Result<(), TpmRcError> {
+    match symmetric {
+        TpmtSymDef::Aes(key_bits, mode) => {
+            check_aes_key_bits(*key_bits, at)?;
+            // Parameters are only ever encrypted in CFB mode.
+            if *mode != TpmiAlgSymMode::CFB {
+                return Err(TpmRcError::ModeFor(at.0, at.1));
+            }
+            Ok(())
+        }
+        TpmtSymDef::ExclusiveOr(hash, _) => check_hash(*hash, at),
+        TpmtSymDef::Null(..) => Ok(()),
+        _ => Err(TpmRcError::SymmetricFor(at.0, at.1)),
+    }
+}
+
+/// Checks that the KDF scheme is supported.
+pub fn check_kdf(kdf: &TpmtKdfScheme, at: ErrorAt) -> Result<(), TpmRcError> {
+    match kdf {
+        TpmtKdfScheme::Mgf1(scheme)
+        | TpmtKdfScheme::Kdf1Sp800_56a(scheme)
+        | TpmtKdfScheme::Kdf1Sp800_108(scheme) => check_hash(scheme.hash_alg, at),
+        TpmtKdfScheme::Null(_) => Ok(()),
+        _ => Err(TpmRcError::KdfFor(at.0, at.1)),
+    }
+}
+
+/// Checks that the algorithm parameters of an object are supported.
+pub fn check_public_parms(parameters: &TpmtPublicParms, at: ErrorAt) -> Result<(), TpmRcError> {
+    match parameters {
+        TpmtPublicParms::KeyedHash(parms) => match parms.scheme {
+            TpmtKeyedHashScheme::Hmac(scheme) => check_hash(scheme.hash_alg, at),
+            TpmtKeyedHashScheme::ExclusiveOr(scheme) => {
+                if scheme.kdf != TpmiAlgKdf::KDF1SP800108 {
+                    return Err(TpmRcError::KdfFor(at.0, at.1));
+                }
+                check_hash(scheme.hash_alg, at)
+            }
+            TpmtKeyedHashScheme::Null(_) => Ok(()),
+        },
+        TpmtPublicParms::Sym(parms) => match parms.sym {
+            TpmtSymDefObject::Null(..) => Err(TpmRcError::SymmetricFor(at.0, at.1)),
+            // A symmetric cipher key may leave the mode to be selected by each command.
+            TpmtSymDefObject::Aes(key_bits, TpmiAlgSymMode::Null) => {
+                check_aes_key_bits(key_bits, at)
+            }
+            sym => check_symmetric(&sym, at),
+        },
+        TpmtPublicParms::Rsa(parms) => {
+            check_symmetric(&parms.symmetric, at)?;
+            match parms.scheme {
+                TpmtRsaScheme::Rsapss(scheme)
+                | TpmtRsaScheme::Rsassa(scheme)
+                | TpmtRsaScheme::Oaep(scheme) => check_hash(scheme.hash_alg, at)?,
+                TpmtRsaScheme::Rsaes(_) | TpmtRsaScheme::Null(_) => {}
+                _ => return Err(TpmRcError::SchemeFor(at.0, at.1)),
+            }
+            if !SUPPORTED_RSA_KEY_BITS.contains(&parms.key_bits.0) {
+                return Err(TpmRcError::KeySizeFor(at.0, at.1));
+            }
+            Ok(())
+        }
+        TpmtPublicParms::Ecc(parms) => {
+            check_symmetric(&parms.symmetric, at)?;
+            match parms.scheme {
+                TpmtEccScheme::Ecdsa(scheme)
+                | TpmtEccScheme::Ecschnorr(scheme)
+                | TpmtEccScheme::Ecdh(scheme) => check_hash(scheme.hash_alg, at)?,
+                TpmtEccScheme::Null(_) => {}
+                _ => return Err(TpmRcError::SchemeFor(at.0, at.1)),
+            }
+            if EccCurve::find(parms.curve_id.0).is_none() {
+                return Err(TpmRcError::CurveFor(at.0, at.1));
+            }
+            check_kdf(&parms.kdf, at)
+        }
+    }
+}
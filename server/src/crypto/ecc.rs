@@ -0,0 +1,543 @@
+use hex_literal::hex;
+use tpm2_rs_base::constants::{TpmEccCurve, TPM2_MAX_DIGEST_BUFFER};
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::{
+    Tpm2bEccParameter, Tpm2bSimple, TpmiAlgHash, TpmsAlgorithmDetailEcc, TpmsEccPoint, TpmsEmpty,
+    TpmtEccScheme, TpmtKdfScheme,
+};
+
+use crate::crypto::hash::{Digest, Hasher};
+use crate::crypto::kdf::{kdfa, kdfe, mgf1};
+use crate::crypto::{constant_time_eq, Crypto};
+use crate::platform::crypto::Ecc;
+use crate::platform::TpmContextDeps;
+
+/// The byte size of the largest curve supported by this TPM.
+pub const MAX_ECC_KEY_BYTES: usize = 48;
+
+/// The maximum number of attempts to draw a random scalar before giving up. Each attempt fails
+/// with a probability of less than 2^-32 for the supported curves.
+const MAX_SCALAR_ATTEMPTS: usize = 16;
+
+/// The domain parameters of an ECC curve that is supported by this TPM.
+pub struct EccCurve {
+    /// The TCG identifier of the curve.
+    pub curve_id: TpmEccCurve,
+    /// The size of the curve in bits.
+    pub key_size: u16,
+    /// The field prime.
+    pub p: &'static [u8],
+    /// The `a` coefficient of the curve equation.
+    pub a: &'static [u8],
+    /// The `b` coefficient of the curve equation.
+    pub b: &'static [u8],
+    /// The x coordinate of the base point.
+    pub g_x: &'static [u8],
+    /// The y coordinate of the base point.
+    pub g_y: &'static [u8],
+    /// The order of the base point.
+    pub n: &'static [u8],
+    /// The cofactor of the curve.
+    pub h: &'static [u8],
+}
+
+/// [FIPS 186-5] NIST P-256.
+const NIST_P256: EccCurve = EccCurve {
+    curve_id: TpmEccCurve::NistP256,
+    key_size: 256,
+    p: &hex!("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff"),
+    a: &hex!("ffffffff00000001000000000000000000000000fffffffffffffffffffffffc"),
+    b: &hex!("5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b"),
+    g_x: &hex!("6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296"),
+    g_y: &hex!("4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"),
+    n: &hex!("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551"),
+    h: &hex!("01"),
+};
+
+/// [FIPS 186-5] NIST P-384.
+const NIST_P384: EccCurve = EccCurve {
+    curve_id: TpmEccCurve::NistP384,
+    key_size: 384,
+    p: &hex!(
+        "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe"
+        "ffffffff0000000000000000ffffffff"
+    ),
+    a: &hex!(
+        "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe"
+        "ffffffff0000000000000000fffffffc"
+    ),
+    b: &hex!(
+        "b3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875a"
+        "c656398d8a2ed19d2a85c8edd3ec2aef"
+    ),
+    g_x: &hex!(
+        "aa87ca22be8b05378eb1c71ef320ad746e1d3b628ba79b9859f741e082542a38"
+        "5502f25dbf55296c3a545e3872760ab7"
+    ),
+    g_y: &hex!(
+        "3617de4a96262c6f5d9e98bf9292dc29f8f41dbd289a147ce9da3113b5f0b8c0"
+        "0a60b1ce1d7e819d7a431d7c90ea0e5f"
+    ),
+    n: &hex!(
+        "ffffffffffffffffffffffffffffffffffffffffffffffffc7634d81f4372ddf"
+        "581a0db248b0a77aecec196accc52973"
+    ),
+    h: &hex!("01"),
+};
+
+/// All of the ECC curves supported by this TPM.
+const SUPPORTED_CURVES: [&EccCurve; 2] = [&NIST_P256, &NIST_P384];
+
+impl EccCurve {
+    /// Finds the domain parameters for the specified curve. Returns `None` if the curve is not
+    /// supported by this TPM.
+    pub fn find(curve_id: TpmEccCurve) -> Option<&'static EccCurve> {
+        SUPPORTED_CURVES
+            .into_iter()
+            .find(|curve| curve.curve_id == curve_id)
+    }
+
+    /// Returns the size in bytes of the curve's field elements and scalars.
+    pub fn key_bytes(&self) -> usize {
+        self.key_size.div_ceil(8) as usize
+    }
+
+    /// Returns the curve parameters as reported by `TPM2_ECC_Parameters`. The curves supported by
+    /// this TPM do not mandate a KDF or signing scheme, so both are reported as `TPM_ALG_NULL`.
+    pub fn details(&self) -> Result<TpmsAlgorithmDetailEcc, TpmRcError> {
+        let param = |bytes| Tpm2bEccParameter::from_bytes(bytes).or(Err(TpmRcError::Failure));
+        Ok(TpmsAlgorithmDetailEcc {
+            curve_id: self.curve_id,
+            key_size: self.key_size,
+            kdf: TpmtKdfScheme::Null(TpmsEmpty),
+            sign: TpmtEccScheme::Null(TpmsEmpty),
+            p: param(self.p)?,
+            a: param(self.a)?,
+            b: param(self.b)?,
+            g_x: param(self.g_x)?,
+            g_y: param(self.g_y)?,
+            n: param(self.n)?,
+            h: param(self.h)?,
+        })
+    }
+}
+
+/// A fixed-size big-endian integer on a specific curve, e.g. a coordinate or a scalar.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EccInteger {
+    size: usize,
+    buffer: [u8; MAX_ECC_KEY_BYTES],
+}
+
+impl EccInteger {
+    /// Creates an integer of the curve's byte size from `bytes`, adding leading zeros if needed.
+    /// Returns `None` if `bytes` has more significant bytes than the curve allows.
+    pub fn new(curve: &EccCurve, bytes: &[u8]) -> Option<Self> {
+        let size = curve.key_bytes();
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        let bytes = &bytes[start..];
+        if bytes.len() > size {
+            return None;
+        }
+        let mut buffer = [0; MAX_ECC_KEY_BYTES];
+        buffer[size - bytes.len()..size].copy_from_slice(bytes);
+        Some(Self { size, buffer })
+    }
+
+    fn zero(curve: &EccCurve) -> Self {
+        Self {
+            size: curve.key_bytes(),
+            buffer: [0; MAX_ECC_KEY_BYTES],
+        }
+    }
+
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer[..self.size]
+    }
+
+    /// Converts this integer into a [`Tpm2bEccParameter`].
+    pub fn to_parameter(self) -> Result<Tpm2bEccParameter, TpmRcError> {
+        Tpm2bEccParameter::from_bytes(self.as_ref()).or(Err(TpmRcError::Failure))
+    }
+}
+
+impl AsRef<[u8]> for EccInteger {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer[..self.size]
+    }
+}
+
+/// An affine point on a specific curve.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EccPoint {
+    pub x: EccInteger,
+    pub y: EccInteger,
+}
+
+impl EccPoint {
+    /// Creates a point from the TPM representation without checking that it is on the curve.
+    /// Returns `None` if a coordinate does not fit the curve.
+    pub fn new(curve: &EccCurve, point: &TpmsEccPoint) -> Option<Self> {
+        Some(Self {
+            x: EccInteger::new(curve, point.x.get_buffer())?,
+            y: EccInteger::new(curve, point.y.get_buffer())?,
+        })
+    }
+
+    /// Returns the base point of the curve.
+    pub fn generator(curve: &EccCurve) -> Self {
+        Self {
+            x: EccInteger::new(curve, curve.g_x).unwrap_or(EccInteger::zero(curve)),
+            y: EccInteger::new(curve, curve.g_y).unwrap_or(EccInteger::zero(curve)),
+        }
+    }
+
+    /// Converts this point into its TPM representation.
+    pub fn to_tpms(self) -> Result<TpmsEccPoint, TpmRcError> {
+        Ok(TpmsEccPoint {
+            x: self.x.to_parameter()?,
+            y: self.y.to_parameter()?,
+        })
+    }
+}
+
+impl EccCurve {
+    /// Checks that `point` is on this curve.
+    pub fn validate_point<E: Ecc>(&self, point: &EccPoint) -> Result<(), TpmRcError> {
+        E::validate_point(self.curve_id, point.x.as_ref(), point.y.as_ref())
+            .or(Err(TpmRcError::EccPoint))
+    }
+
+    /// Computes `[scalar]point` on this curve.
+    pub fn point_mul<E: Ecc>(
+        &self,
+        scalar: &EccInteger,
+        point: &EccPoint,
+    ) -> Result<EccPoint, TpmRcError> {
+        let mut result = EccPoint {
+            x: EccInteger::zero(self),
+            y: EccInteger::zero(self),
+        };
+        E::point_mul(
+            self.curve_id,
+            scalar.as_ref(),
+            point.x.as_ref(),
+            point.y.as_ref(),
+            result.x.as_mut(),
+            result.y.as_mut(),
+        )
+        .or(Err(TpmRcError::EccPoint))?;
+        Ok(result)
+    }
+
+    /// Returns true if `scalar` is in the range `[1, n - 1]`.
+    pub fn is_valid_scalar(&self, scalar: &EccInteger) -> bool {
+        let scalar = scalar.as_ref();
+        scalar.iter().any(|b| *b != 0) && scalar < self.n
+    }
+
+    /// Computes `[u1]G + [u2]point` on this curve, where `G` is the base point.
+    pub fn point_mul_add<E: Ecc>(
+        &self,
+        u1: &EccInteger,
+        u2: &EccInteger,
+        point: &EccPoint,
+    ) -> Result<EccPoint, TpmRcError> {
+        let mut result = EccPoint {
+            x: EccInteger::zero(self),
+            y: EccInteger::zero(self),
+        };
+        E::point_mul_add(
+            self.curve_id,
+            u1.as_ref(),
+            u2.as_ref(),
+            point.x.as_ref(),
+            point.y.as_ref(),
+            result.x.as_mut(),
+            result.y.as_mut(),
+        )
+        .or(Err(TpmRcError::EccPoint))?;
+        Ok(result)
+    }
+
+    /// Converts a digest into a scalar as done by the ECC signing schemes: the digest is
+    /// truncated to the byte size of the order and reduced modulo the order.
+    pub fn digest_to_scalar<E: Ecc>(&self, digest: &[u8]) -> Result<EccInteger, TpmRcError> {
+        let truncated = &digest[..digest.len().min(self.key_bytes())];
+        let value = EccInteger::new(self, truncated).ok_or(TpmRcError::Failure)?;
+        self.reduce::<E>(&value)
+    }
+
+    /// Reduces `value` modulo the order of this curve.
+    pub fn reduce<E: Ecc>(&self, value: &EccInteger) -> Result<EccInteger, TpmRcError> {
+        let mut result = EccInteger::zero(self);
+        E::scalar_reduce(self.curve_id, value.as_ref(), result.as_mut())
+            .or(Err(TpmRcError::Failure))?;
+        Ok(result)
+    }
+
+    /// Computes `a * b + c` modulo the order of this curve.
+    pub fn mul_add<E: Ecc>(
+        &self,
+        a: &EccInteger,
+        b: &EccInteger,
+        c: &EccInteger,
+    ) -> Result<EccInteger, TpmRcError> {
+        let mut result = EccInteger::zero(self);
+        E::scalar_mul_add(
+            self.curve_id,
+            a.as_ref(),
+            b.as_ref(),
+            c.as_ref(),
+            result.as_mut(),
+        )
+        .or(Err(TpmRcError::Failure))?;
+        Ok(result)
+    }
+
+    /// Computes `-scalar` modulo the order of this curve.
+    pub fn negate<E: Ecc>(&self, scalar: &EccInteger) -> Result<EccInteger, TpmRcError> {
+        let mut result = EccInteger::zero(self);
+        E::scalar_negate(self.curve_id, scalar.as_ref(), result.as_mut())
+            .or(Err(TpmRcError::Failure))?;
+        Ok(result)
+    }
+
+    /// Computes `scalar^-1` modulo the order of this curve.
+    pub fn invert<E: Ecc>(&self, scalar: &EccInteger) -> Result<EccInteger, TpmRcError> {
+        let mut result = EccInteger::zero(self);
+        E::scalar_invert(self.curve_id, scalar.as_ref(), result.as_mut())
+            .or(Err(TpmRcError::Failure))?;
+        Ok(result)
+    }
+}
+
+/// Returns the hash algorithm used by a KDF scheme, or `None` for `TPM_ALG_NULL`.
+pub fn kdf_scheme_hash(scheme: &TpmtKdfScheme) -> Option<TpmiAlgHash> {
+    match scheme {
+        TpmtKdfScheme::Mgf1(details)
+        | TpmtKdfScheme::Kdf1Sp800_56a(details)
+        | TpmtKdfScheme::Kdf2(details)
+        | TpmtKdfScheme::Kdf1Sp800_108(details) => Some(details.hash_alg),
+        TpmtKdfScheme::Null(_) => None,
+    }
+}
+
+/// An ECC signature.
+pub struct EccSignature {
+    pub r: EccInteger,
+    pub s: EccInteger,
+}
+
+/// The output of the ECC encryption scheme in addition to the masked data.
+pub struct EccCipherText {
+    /// The ephemeral public point.
+    pub c1: EccPoint,
+    /// The integrity digest over the plaintext.
+    pub c3: Digest,
+}
+
+impl<Deps: TpmContextDeps> Crypto<Deps> {
+    /// Applies the KDF of `scheme` to the shared point `z` and XORs the result into `data`.
+    /// Returns [`TpmRcError::NoResult`] if the derived mask is all zero.
+    fn ecc_crypt_mask(
+        scheme: &TpmtKdfScheme,
+        z: &EccPoint,
+        data: &mut [u8],
+    ) -> Result<(), TpmRcError> {
+        let mut shared = [0; 2 * MAX_ECC_KEY_BYTES];
+        let x = z.x.as_ref();
+        let y = z.y.as_ref();
+        shared[..x.len()].copy_from_slice(x);
+        shared[x.len()..x.len() + y.len()].copy_from_slice(y);
+        let shared = &shared[..x.len() + y.len()];
+
+        let mut mask = [0; TPM2_MAX_DIGEST_BUFFER as usize];
+        let mask = mask.get_mut(..data.len()).ok_or(TpmRcError::Size)?;
+        match scheme {
+            TpmtKdfScheme::Mgf1(details) => mgf1::<Deps::Hash>(details.hash_alg, shared, mask),
+            TpmtKdfScheme::Kdf1Sp800_56a(details) => {
+                kdfe::<Deps::Hash>(details.hash_alg, shared, &[], &[], &[], mask)
+            }
+            TpmtKdfScheme::Kdf1Sp800_108(details) => {
+                kdfa::<Deps::Hash>(details.hash_alg, shared, &[], &[], &[], mask)
+            }
+            _ => Err(TpmRcError::Scheme),
+        }?;
+        if !data.is_empty() && mask.iter().all(|b| *b == 0) {
+            return Err(TpmRcError::NoResult);
+        }
+        for (byte, mask) in data.iter_mut().zip(mask.iter()) {
+            *byte ^= mask;
+        }
+        Ok(())
+    }
+
+    /// Computes the integrity digest `H(x2 || M || y2)`.
+    fn ecc_crypt_digest(
+        scheme: &TpmtKdfScheme,
+        z: &EccPoint,
+        plain_text: &[u8],
+    ) -> Result<Digest, TpmRcError> {
+        let mut hasher =
+            Hasher::<Deps::Hash>::start(kdf_scheme_hash(scheme).ok_or(TpmRcError::Scheme)?)?;
+        hasher.update(z.x.as_ref());
+        hasher.update(plain_text);
+        hasher.update(z.y.as_ref());
+        Ok(hasher.finish())
+    }
+
+    /// Draws a random scalar in the range `[1, n - 1]` of `curve`.
+    pub fn random_scalar(&mut self, curve: &EccCurve) -> Result<EccInteger, TpmRcError> {
+        let mut scalar = EccInteger::zero(curve);
+        for _ in 0..MAX_SCALAR_ATTEMPTS {
+            self.fill_random(scalar.as_mut())
+                .or(Err(TpmRcError::Failure))?;
+            if curve.is_valid_scalar(&scalar) {
+                return Ok(scalar);
+            }
+        }
+        Err(TpmRcError::NoResult)
+    }
+
+    /// Encrypts `data` in place for the owner of `public` using `scheme` as described in
+    /// [TPM2.0 1.83] Part 1, 11.4.11.
+    pub fn ecc_encrypt(
+        &mut self,
+        curve: &EccCurve,
+        public: &EccPoint,
+        scheme: &TpmtKdfScheme,
+        data: &mut [u8],
+    ) -> Result<EccCipherText, TpmRcError> {
+        let k = self.random_scalar(curve)?;
+        let c1 = curve.point_mul::<Deps::Ecc>(&k, &EccPoint::generator(curve))?;
+        let z = curve.point_mul::<Deps::Ecc>(&k, public)?;
+        let c3 = Self::ecc_crypt_digest(scheme, &z, data)?;
+        Self::ecc_crypt_mask(scheme, &z, data)?;
+        Ok(EccCipherText { c1, c3 })
+    }
+
+    /// Decrypts `data` in place with the private scalar `d` using `scheme`. Returns
+    /// [`TpmRcError::Value`] if the integrity digest `c3` does not match the decrypted data.
+    pub fn ecc_decrypt(
+        curve: &EccCurve,
+        d: &EccInteger,
+        scheme: &TpmtKdfScheme,
+        c1: &EccPoint,
+        c3: &[u8],
+        data: &mut [u8],
+    ) -> Result<(), TpmRcError> {
+        let z = curve.point_mul::<Deps::Ecc>(d, c1)?;
+        Self::ecc_crypt_mask(scheme, &z, data)?;
+        let digest = Self::ecc_crypt_digest(scheme, &z, data)?;
+        if !constant_time_eq(digest.as_ref(), c3) {
+            return Err(TpmRcError::Value);
+        }
+        Ok(())
+    }
+
+    /// Signs `digest` with the private scalar `d` using ECDSA as described in [FIPS 186-5] 6.4.1.
+    pub fn ecdsa_sign(
+        &mut self,
+        curve: &EccCurve,
+        d: &EccInteger,
+        digest: &[u8],
+    ) -> Result<EccSignature, TpmRcError> {
+        let e = curve.digest_to_scalar::<Deps::Ecc>(digest)?;
+        let zero = EccInteger::zero(curve);
+        for _ in 0..MAX_SCALAR_ATTEMPTS {
+            let k = self.random_scalar(curve)?;
+            let point = curve.point_mul::<Deps::Ecc>(&k, &EccPoint::generator(curve))?;
+            let r = curve.reduce::<Deps::Ecc>(&point.x)?;
+            if r == zero {
+                continue;
+            }
+            let k_inv = curve.invert::<Deps::Ecc>(&k)?;
+            let t = curve.mul_add::<Deps::Ecc>(&r, d, &e)?;
+            let s = curve.mul_add::<Deps::Ecc>(&k_inv, &t, &zero)?;
+            if s != zero {
+                return Ok(EccSignature { r, s });
+            }
+        }
+        Err(TpmRcError::NoResult)
+    }
+
+    /// Verifies the ECDSA signature of `digest` for the public point `q` as described in
+    /// [FIPS 186-5] 6.4.2.
+    pub fn ecdsa_verify(
+        curve: &EccCurve,
+        q: &EccPoint,
+        digest: &[u8],
+        signature: &EccSignature,
+    ) -> Result<bool, TpmRcError> {
+        if !curve.is_valid_scalar(&signature.r) || !curve.is_valid_scalar(&signature.s) {
+            return Ok(false);
+        }
+        let e = curve.digest_to_scalar::<Deps::Ecc>(digest)?;
+        let zero = EccInteger::zero(curve);
+        let w = curve.invert::<Deps::Ecc>(&signature.s)?;
+        let u1 = curve.mul_add::<Deps::Ecc>(&e, &w, &zero)?;
+        let u2 = curve.mul_add::<Deps::Ecc>(&signature.r, &w, &zero)?;
+        let Ok(point) = curve.point_mul_add::<Deps::Ecc>(&u1, &u2, q) else {
+            return Ok(false);
+        };
+        Ok(curve.reduce::<Deps::Ecc>(&point.x)? == signature.r)
+    }
+
+    /// Computes the challenge `H(R.x || digest)` of the EC-Schnorr scheme as a scalar.
+    fn ecschnorr_challenge(
+        curve: &EccCurve,
+        hash_alg: TpmiAlgHash,
+        point: &EccPoint,
+        digest: &[u8],
+    ) -> Result<EccInteger, TpmRcError> {
+        let mut hasher = Hasher::<Deps::Hash>::start(hash_alg)?;
+        hasher.update(point.x.as_ref());
+        hasher.update(digest);
+        curve.digest_to_scalar::<Deps::Ecc>(hasher.finish().as_ref())
+    }
+
+    /// Signs `digest` with the private scalar `d` using the EC-Schnorr scheme of the TPM
+    /// reference implementation, i.e. `r = H(R.x || digest) mod n` and `s = k + r * d mod n` with
+    /// `R = [k]G`.
+    pub fn ecschnorr_sign(
+        &mut self,
+        curve: &EccCurve,
+        hash_alg: TpmiAlgHash,
+        d: &EccInteger,
+        digest: &[u8],
+    ) -> Result<EccSignature, TpmRcError> {
+        let zero = EccInteger::zero(curve);
+        for _ in 0..MAX_SCALAR_ATTEMPTS {
+            let k = self.random_scalar(curve)?;
+            let point = curve.point_mul::<Deps::Ecc>(&k, &EccPoint::generator(curve))?;
+            let r = Self::ecschnorr_challenge(curve, hash_alg, &point, digest)?;
+            if r == zero {
+                continue;
+            }
+            let s = curve.mul_add::<Deps::Ecc>(&r, d, &k)?;
+            if s != zero {
+                return Ok(EccSignature { r, s });
+            }
+        }
+        Err(TpmRcError::NoResult)
+    }
+
+    /// Verifies the EC-Schnorr signature of `digest` for the public point `q`.
+    pub fn ecschnorr_verify(
+        curve: &EccCurve,
+        hash_alg: TpmiAlgHash,
+        q: &EccPoint,
+        digest: &[u8],
+        signature: &EccSignature,
+    ) -> Result<bool, TpmRcError> {
+        if !curve.is_valid_scalar(&signature.r) || !curve.is_valid_scalar(&signature.s) {
+            return Ok(false);
+        }
+        let minus_r = curve.negate::<Deps::Ecc>(&signature.r)?;
+        let Ok(point) = curve.point_mul_add::<Deps::Ecc>(&signature.s, &minus_r, q) else {
+            return Ok(false);
+        };
+        Ok(Self::ecschnorr_challenge(curve, hash_alg, &point, digest)? == signature.r)
+    }
+}
@@ -0,0 +1,115 @@
+use tpm2_rs_base::constants::{
+    TPM2_SHA1_DIGEST_SIZE, TPM2_SHA256_DIGEST_SIZE, TPM2_SHA384_DIGEST_SIZE,
+    TPM2_SHA512_DIGEST_SIZE,
+};
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::{TpmiAlgHash, TpmtHa};
+
+use crate::platform::crypto::Hash;
+
+/// The size of the largest digest of any hash algorithm supported by this TPM.
+pub const MAX_DIGEST_SIZE: usize = TPM2_SHA512_DIGEST_SIZE as usize;
+
+/// The size of the largest message block of any hash algorithm supported by this TPM.
+pub const MAX_BLOCK_SIZE: usize = 128;
+
+/// Returns the digest size in bytes of the specified hash algorithm, or `None` if the algorithm is
+/// not supported by this TPM.
+pub fn digest_size(alg: TpmiAlgHash) -> Option<usize> {
+    let size = match alg {
+        TpmiAlgHash::SHA1 => TPM2_SHA1_DIGEST_SIZE,
+        TpmiAlgHash::SHA256 => TPM2_SHA256_DIGEST_SIZE,
+        TpmiAlgHash::SHA384 => TPM2_SHA384_DIGEST_SIZE,
+        TpmiAlgHash::SHA512 => TPM2_SHA512_DIGEST_SIZE,
+        _ => return None,
+    };
+    Some(size as usize)
+}
+
+/// Returns the message block size in bytes of the specified hash algorithm, or `None` if the
+/// algorithm is not supported by this TPM.
+pub fn block_size(alg: TpmiAlgHash) -> Option<usize> {
+    match alg {
+        TpmiAlgHash::SHA1 | TpmiAlgHash::SHA256 => Some(64),
+        TpmiAlgHash::SHA384 | TpmiAlgHash::SHA512 => Some(128),
+        _ => None,
+    }
+}
+
+/// A digest produced by one of the hash algorithms supported by this TPM.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Digest {
+    size: usize,
+    buffer: [u8; MAX_DIGEST_SIZE],
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer[..self.size]
+    }
+}
+
+/// A running digest computation that remembers which algorithm it was started with.
+pub struct Hasher<H: Hash> {
+    size: usize,
+    hash: H,
+}
+
+impl<H: Hash> Hasher<H> {
+    /// Starts a new digest computation. Returns [`TpmRcError::Hash`] if the algorithm is not
+    /// supported by this TPM or by the platform.
+    pub fn start(alg: TpmiAlgHash) -> Result<Self, TpmRcError> {
+        let size = digest_size(alg).ok_or(TpmRcError::Hash)?;
+        let hash = H::start(alg).ok_or(TpmRcError::Hash)?;
+        Ok(Self { size, hash })
+    }
+
+    /// Adds `data` to the digest computation.
+    pub fn update(&mut self, data: &[u8]) {
+        self.hash.update(data);
+    }
+
+    /// Completes the digest computation.
+    pub fn finish(self) -> Digest {
+        let mut digest = Digest {
+            size: self.size,
+            buffer: [0; MAX_DIGEST_SIZE],
+        };
+        self.hash.finish(&mut digest.buffer[..self.size]);
+        digest
+    }
+}
+
+/// Computes the digest of the concatenation of all `data` slices.
+pub fn digest<H: Hash>(alg: TpmiAlgHash, data: &[&[u8]]) -> Result<Digest, TpmRcError> {
+    let mut hasher = Hasher::<H>::start(alg)?;
+    for part in data {
+        hasher.update(part);
+    }
+    Ok(hasher.finish())
+}
+
+/// Converts a digest computed with `alg` into a [`TpmtHa`]. Returns [`TpmRcError::Hash`] if the
+/// algorithm is not supported or [`TpmRcError::Size`] if the digest has the wrong size.
+pub fn to_tpmt_ha(alg: TpmiAlgHash, digest: &[u8]) -> Result<TpmtHa, TpmRcError> {
+    let size = TpmRcError::Size;
+    let ha = match alg {
+        TpmiAlgHash::SHA1 => TpmtHa::Sha1(digest.try_into().or(Err(size))?),
+        TpmiAlgHash::SHA256 => TpmtHa::Sha256(digest.try_into().or(Err(size))?),
+        TpmiAlgHash::SHA384 => TpmtHa::Sha384(digest.try_into().or(Err(size))?),
+        TpmiAlgHash::SHA512 => TpmtHa::Sha512(digest.try_into().or(Err(size))?),
+        _ => return Err(TpmRcError::Hash),
+    };
+    Ok(ha)
+}
+
+/// Returns the hash algorithm and the digest of a [`TpmtHa`].
+pub fn from_tpmt_ha(ha: &TpmtHa) -> (TpmiAlgHash, &[u8]) {
+    match ha {
+        TpmtHa::Sha1(digest) => (TpmiAlgHash::SHA1, digest),
+        TpmtHa::Sha256(digest) => (TpmiAlgHash::SHA256, digest),
+        TpmtHa::Sha384(digest) => (TpmiAlgHash::SHA384, digest),
+        TpmtHa::Sha512(digest) => (TpmiAlgHash::SHA512, digest),
+        TpmtHa::Sm3_256(digest) => (TpmiAlgHash::SM3256, digest),
+    }
+}
@@ -0,0 +1,73 @@
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::TpmiAlgHash;
+
+use crate::crypto::hash::{block_size, Digest, Hasher, MAX_BLOCK_SIZE};
+use crate::platform::crypto::Hash;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5C;
+
+/// A running [RFC 2104] HMAC computation over one of the hash algorithms supported by this TPM.
+///
+/// [RFC 2104]: https://www.rfc-editor.org/rfc/rfc2104
+pub struct Hmac<H: Hash> {
+    alg: TpmiAlgHash,
+    inner: Hasher<H>,
+    key_block: [u8; MAX_BLOCK_SIZE],
+    block_size: usize,
+}
+
+impl<H: Hash> Hmac<H> {
+    /// Starts a new HMAC computation with the specified key.
+    pub fn start(alg: TpmiAlgHash, key: &[u8]) -> Result<Self, TpmRcError> {
+        let block_size = block_size(alg).ok_or(TpmRcError::Hash)?;
+        let mut key_block = [0; MAX_BLOCK_SIZE];
+        if key.len() > block_size {
+            let mut hasher = Hasher::<H>::start(alg)?;
+            hasher.update(key);
+            let digest = hasher.finish();
+            key_block[..digest.as_ref().len()].copy_from_slice(digest.as_ref());
+        } else {
+            key_block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Hasher::start(alg)?;
+        for byte in &mut key_block[..block_size] {
+            *byte ^= IPAD;
+        }
+        inner.update(&key_block[..block_size]);
+        // Leave the key block padded for the outer hash.
+        for byte in &mut key_block[..block_size] {
+            *byte ^= IPAD ^ OPAD;
+        }
+        Ok(Self {
+            alg,
+            inner,
+            key_block,
+            block_size,
+        })
+    }
+
+    /// Adds `data` to the HMAC computation.
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// Completes the HMAC computation.
+    pub fn finish(self) -> Result<Digest, TpmRcError> {
+        let inner = self.inner.finish();
+        let mut outer = Hasher::<H>::start(self.alg)?;
+        outer.update(&self.key_block[..self.block_size]);
+        outer.update(inner.as_ref());
+        Ok(outer.finish())
+    }
+}
+
+/// Computes the HMAC of the concatenation of all `data` slices.
+pub fn hmac<H: Hash>(alg: TpmiAlgHash, key: &[u8], data: &[&[u8]]) -> Result<Digest, TpmRcError> {
+    let mut hmac = Hmac::<H>::start(alg, key)?;
+    for part in data {
+        hmac.update(part);
+    }
+    hmac.finish()
+}
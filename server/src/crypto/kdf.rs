@@ -0,0 +1,136 @@
+//! Key derivation and mask generation functions from [TPM2.0 1.83] Part 1, 11.4.10.
+
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::TpmiAlgHash;
+
+use crate::crypto::{hash::Hasher, hmac::Hmac};
+use crate::platform::crypto::Hash;
+
+/// Returns the terminating zero that has to follow `label` if it is not already included.
+fn label_terminator(label: &[u8]) -> &'static [u8] {
+    match label.last() {
+        Some(0) | None => &[],
+        Some(_) => &[0],
+    }
+}
+
+/// Fills `out` by calling `next_block` with a counter starting at `first` and combining as much of
+/// each resulting block as still fits into `out` with `combine`.
+fn fill_blocks<D: AsRef<[u8]>>(
+    out: &mut [u8],
+    first: u32,
+    combine: impl Fn(&mut [u8], &[u8]),
+    mut next_block: impl FnMut(u32) -> Result<D, TpmRcError>,
+) -> Result<(), TpmRcError> {
+    let mut counter = first;
+    let mut offset = 0;
+    while offset < out.len() {
+        let block = next_block(counter)?;
+        let block = block.as_ref();
+        let size = block.len().min(out.len() - offset);
+        combine(&mut out[offset..offset + size], &block[..size]);
+        offset += size;
+        counter = counter.checked_add(1).ok_or(TpmRcError::Size)?;
+    }
+    Ok(())
+}
+
+/// Returns the size of `out` in bits as used by the KDFs.
+fn out_bits(out: &[u8]) -> Result<u32, TpmRcError> {
+    u32::try_from(out.len())
+        .ok()
+        .and_then(|len| len.checked_mul(8))
+        .ok_or(TpmRcError::Size)
+}
+
+/// Fills `out` using KDFa, the counter mode KDF from SP800-108 with HMAC as the PRF.
+pub fn kdfa<H: Hash>(
+    alg: TpmiAlgHash,
+    key: &[u8],
+    label: &[u8],
+    context_u: &[u8],
+    context_v: &[u8],
+    out: &mut [u8],
+) -> Result<(), TpmRcError> {
+    kdfa_with::<H>(
+        alg,
+        key,
+        label,
+        context_u,
+        context_v,
+        out,
+        <[u8]>::copy_from_slice,
+    )
+}
+
+/// XORs `data` with a mask of the same size from KDFa, which is the XOR obfuscation of
+/// [TPM2.0 1.83] Part 1, 11.4.6.
+pub fn kdfa_xor<H: Hash>(
+    alg: TpmiAlgHash,
+    key: &[u8],
+    label: &[u8],
+    context_u: &[u8],
+    context_v: &[u8],
+    data: &mut [u8],
+) -> Result<(), TpmRcError> {
+    kdfa_with::<H>(alg, key, label, context_u, context_v, data, |data, mask| {
+        for (byte, mask) in data.iter_mut().zip(mask) {
+            *byte ^= mask;
+        }
+    })
+}
+
+/// Runs KDFa for `out.len()` bytes, combining each block of its output into `out` with `combine`.
+fn kdfa_with<H: Hash>(
+    alg: TpmiAlgHash,
+    key: &[u8],
+    label: &[u8],
+    context_u: &[u8],
+    context_v: &[u8],
+    out: &mut [u8],
+    combine: impl Fn(&mut [u8], &[u8]),
+) -> Result<(), TpmRcError> {
+    let bits = out_bits(out)?.to_be_bytes();
+    fill_blocks(out, 1, combine, |counter| {
+        let mut hmac = Hmac::<H>::start(alg, key)?;
+        hmac.update(&counter.to_be_bytes());
+        hmac.update(label);
+        hmac.update(label_terminator(label));
+        hmac.update(context_u);
+        hmac.update(context_v);
+        hmac.update(&bits);
+        hmac.finish()
+    })
+}
+
+/// Fills `out` using KDFe, the single-step KDF from SP800-56A with a hash as the auxiliary
+/// function.
+pub fn kdfe<H: Hash>(
+    alg: TpmiAlgHash,
+    z: &[u8],
+    label: &[u8],
+    party_u: &[u8],
+    party_v: &[u8],
+    out: &mut [u8],
+) -> Result<(), TpmRcError> {
+    fill_blocks(out, 1, <[u8]>::copy_from_slice, |counter| {
+        let mut hasher = Hasher::<H>::start(alg)?;
+        hasher.update(&counter.to_be_bytes());
+        hasher.update(z);
+        hasher.update(label);
+        hasher.update(label_terminator(label));
+        hasher.update(party_u);
+        hasher.update(party_v);
+        Ok(hasher.finish())
+    })
+}
+
+/// Fills `out` using MGF1 from IEEE Std 1363a-2004.
+pub fn mgf1<H: Hash>(alg: TpmiAlgHash, seed: &[u8], out: &mut [u8]) -> Result<(), TpmRcError> {
+    fill_blocks(out, 0, <[u8]>::copy_from_slice, |counter| {
+        let mut hasher = Hasher::<H>::start(alg)?;
+        hasher.update(seed);
+        hasher.update(&counter.to_be_bytes());
+        Ok(hasher.finish())
+    })
+}
@@ -0,0 +1,53 @@
+pub mod algorithms;
+pub mod ecc;
+pub mod hash;
+pub mod hmac;
+pub mod kdf;
+pub mod rsa;
+pub mod symmetric;
+
+use crate::{
+    platform::{
+        crypto::{Drbg, EntropySource},
+        TpmContextDeps,
+    },
+    ServerError,
+};
+
+/// Returns whether `a` and `b` are equal in a time that only depends on their lengths, so that
+/// comparing secret-derived values like HMACs doesn't reveal how many leading bytes match.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let diff = a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y));
+    a.len() == b.len() && core::hint::black_box(diff) == 0
+}
+
+pub struct Crypto<Deps: TpmContextDeps> {
+    pub drbg: Deps::Drbg,
+    pub entropy: Deps::EntropySource,
+}
+
+impl<Deps: TpmContextDeps> Crypto<Deps> {
+    pub fn new() -> Result<Self, ServerError> {
+        let mut entropy_source: <Deps as TpmContextDeps>::EntropySource =
+            Deps::EntropySource::instantiate();
+        let mut entropy_input = <Deps::Drbg as Drbg>::Entropy::default();
+        let mut nonce = <Deps::Drbg as Drbg>::Nonce::default();
+        entropy_source.fill_entropy(entropy_input.as_mut());
+        entropy_source.fill_entropy(nonce.as_mut());
+        let drbg = Deps::Drbg::instantiate(&entropy_input, &nonce, &[])?;
+        Ok(Self {
+            drbg,
+            entropy: entropy_source,
+        })
+    }
+
+    /// Fills `buffer` with random bytes from the DRBG, reseeding it first if required.
+    pub fn fill_random(&mut self, buffer: &mut [u8]) -> Result<(), ServerError> {
+        if self.drbg.requires_reseeding() {
+            let mut seed = <Deps::Drbg as Drbg>::Entropy::default();
+            self.entropy.fill_entropy(seed.as_mut());
+            self.drbg.reseed(&seed, &[])?;
+        }
+        self.drbg.fill_bytes(&[], buffer).map_err(Into::into)
+    }
+}
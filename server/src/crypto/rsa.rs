@@ -0,0 +1,296 @@
+use hex_literal::hex;
+use tpm2_rs_base::constants::TPM2_MAX_RSA_KEY_BYTES;
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::{TpmiAlgHash, TpmsRsaParms};
+
+use crate::crypto::hash::{digest, digest_size, Digest, MAX_DIGEST_SIZE};
+use crate::crypto::kdf::mgf1;
+use crate::crypto::{constant_time_eq, Crypto};
+use crate::platform::crypto::Rsa;
+use crate::platform::TpmContextDeps;
+
+/// The public exponent of RSA keys that specify an exponent of zero.
+pub const DEFAULT_RSA_EXPONENT: u32 = 65537;
+
+/// The byte size of the largest RSA modulus.
+const MAX_RSA_KEY_BYTES: usize = TPM2_MAX_RSA_KEY_BYTES as usize;
+
+/// The trailer byte of an EMSA-PSS encoded message.
+const PSS_TRAILER: u8 = 0xBC;
+
+/// Returns the DER encoding of the `DigestInfo` header that precedes a digest in an
+/// EMSA-PKCS1-v1_5 encoded message, see [RFC 8017] 9.2.
+///
+/// [RFC 8017]: https://www.rfc-editor.org/rfc/rfc8017
+fn digest_info_prefix(alg: TpmiAlgHash) -> Option<&'static [u8]> {
+    let prefix: &'static [u8] = match alg {
+        TpmiAlgHash::SHA1 => &hex!("3021300906052b0e03021a05000414"),
+        TpmiAlgHash::SHA256 => &hex!("3031300d060960864801650304020105000420"),
+        TpmiAlgHash::SHA384 => &hex!("3041300d060960864801650304020205000430"),
+        TpmiAlgHash::SHA512 => &hex!("3051300d060960864801650304020305000440"),
+        _ => return None,
+    };
+    Some(prefix)
+}
+
+/// An RSA key given by its modulus, public exponent and, if the private part is available, one of
+/// its prime factors.
+pub struct RsaKey<'a> {
+    pub n: &'a [u8],
+    pub e: u32,
+    pub prime: Option<&'a [u8]>,
+}
+
+impl<'a> RsaKey<'a> {
+    /// Creates a key from its public parameters, its modulus and optionally its prime factor.
+    pub fn new(parms: &TpmsRsaParms, n: &'a [u8], prime: Option<&'a [u8]>) -> Self {
+        let e = match parms.exponent {
+            0 => DEFAULT_RSA_EXPONENT,
+            e => e,
+        };
+        Self { n, e, prime }
+    }
+
+    /// Returns the size of the modulus in bits.
+    fn modulus_bits(&self) -> usize {
+        let start = self.n.iter().position(|b| *b != 0).unwrap_or(self.n.len());
+        match self.n.get(start) {
+            Some(top) => (self.n.len() - start) * 8 - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// Applies the public key to `signature` and writes the recovered message, which is as long as
+    /// the modulus, to `out`. Returns `false` if the signature is not a valid input.
+    fn public_op<R: Rsa>(&self, signature: &[u8], out: &mut [u8]) -> bool {
+        signature.len() == self.n.len() && R::public_op(self.n, self.e, signature, out).is_ok()
+    }
+}
+
+/// Writes the EMSA-PKCS1-v1_5 encoding of `digest` from [RFC 8017] 9.2 to `em`.
+///
+/// [RFC 8017]: https://www.rfc-editor.org/rfc/rfc8017
+fn rsassa_encode(alg: TpmiAlgHash, digest: &[u8], em: &mut [u8]) -> Result<(), TpmRcError> {
+    let prefix = digest_info_prefix(alg).ok_or(TpmRcError::Hash)?;
+    let t_len = prefix.len() + digest.len();
+    // The padding must consist of at least 8 bytes.
+    let padding = em
+        .len()
+        .checked_sub(t_len + 3)
+        .filter(|padding| *padding >= 8)
+        .ok_or(TpmRcError::Value)?;
+    em[0] = 0x00;
+    em[1] = 0x01;
+    em[2..2 + padding].fill(0xFF);
+    em[2 + padding] = 0x00;
+    em[3 + padding..3 + padding + prefix.len()].copy_from_slice(prefix);
+    em[3 + padding + prefix.len()..].copy_from_slice(digest);
+    Ok(())
+}
+
+/// Computes `H(0x00 * 8 || digest || salt)`, the hash of the EMSA-PSS message `M'`.
+fn pss_hash<Deps: TpmContextDeps>(
+    alg: TpmiAlgHash,
+    digest: &[u8],
+    salt: &[u8],
+) -> Result<Digest, TpmRcError> {
+    self::digest::<Deps::Hash>(alg, &[&[0; 8], digest, salt])
+}
+
+impl<Deps: TpmContextDeps> Crypto<Deps> {
+    /// Applies the private key to `input` and writes the result, which is as long as the modulus,
+    /// to `out`. The operation is blinded with bytes from the DRBG.
+    fn rsa_private_op(
+        &mut self,
+        key: &RsaKey,
+        input: &[u8],
+        out: &mut [u8],
+    ) -> Result<(), TpmRcError> {
+        let prime = key.prime.ok_or(TpmRcError::Key)?;
+        let mut drbg_failed = false;
+        let result = Deps::Rsa::private_op(key.n, key.e, prime, input, out, &mut |buffer| {
+            drbg_failed |= self.fill_random(buffer).is_err();
+        });
+        if drbg_failed {
+            return Err(TpmRcError::Failure);
+        }
+        result.or(Err(TpmRcError::Value))
+    }
+
+    /// Signs `digest` with the private key using RSASSA-PKCS1-v1_5 and writes the signature,
+    /// which is as long as the modulus, to `signature`.
+    pub fn rsassa_sign(
+        &mut self,
+        key: &RsaKey,
+        alg: TpmiAlgHash,
+        digest: &[u8],
+        signature: &mut [u8],
+    ) -> Result<(), TpmRcError> {
+        let mut em = [0; MAX_RSA_KEY_BYTES];
+        let em = em.get_mut(..key.n.len()).ok_or(TpmRcError::Key)?;
+        rsassa_encode(alg, digest, em)?;
+        self.rsa_private_op(key, em, signature)
+    }
+
+    /// Verifies the RSASSA-PKCS1-v1_5 signature of `digest` with the public key.
+    pub fn rsassa_verify(
+        key: &RsaKey,
+        alg: TpmiAlgHash,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, TpmRcError> {
+        let mut em = [0; MAX_RSA_KEY_BYTES];
+        let mut expected = [0; MAX_RSA_KEY_BYTES];
+        let size = key.n.len();
+        if size > MAX_RSA_KEY_BYTES || !key.public_op::<Deps::Rsa>(signature, &mut em[..size]) {
+            return Ok(false);
+        }
+        if rsassa_encode(alg, digest, &mut expected[..size]).is_err() {
+            return Ok(false);
+        }
+        Ok(em[..size] == expected[..size])
+    }
+
+    /// Signs `digest` with the private key using RSASSA-PSS and writes the signature, which is as
+    /// long as the modulus, to `signature`. The salt is as long as the digest unless the key is
+    /// too small for that.
+    pub fn rsapss_sign(
+        &mut self,
+        key: &RsaKey,
+        alg: TpmiAlgHash,
+        digest: &[u8],
+        signature: &mut [u8],
+    ) -> Result<(), TpmRcError> {
+        let h_len = digest_size(alg).ok_or(TpmRcError::Hash)?;
+        let em_bits = key.modulus_bits().saturating_sub(1);
+        let em_len = em_bits.div_ceil(8);
+        if key.n.len() > MAX_RSA_KEY_BYTES || em_len < h_len + 2 {
+            return Err(TpmRcError::Key);
+        }
+        let salt_len = h_len.min(em_len - h_len - 2);
+        let mut salt = [0; MAX_DIGEST_SIZE];
+        let salt = &mut salt[..salt_len];
+        self.fill_random(salt).or(Err(TpmRcError::Failure))?;
+        let h = pss_hash::<Deps>(alg, digest, salt)?;
+
+        // EM = maskedDB || H || 0xBC, preceded by zeros up to the size of the modulus.
+        let mut encoded = [0; MAX_RSA_KEY_BYTES];
+        let encoded = &mut encoded[..key.n.len()];
+        let offset = encoded.len() - em_len;
+        let em = &mut encoded[offset..];
+        let db_len = em_len - h_len - 1;
+        let (db, rest) = em.split_at_mut(db_len);
+        mgf1::<Deps::Hash>(alg, h.as_ref(), db)?;
+        db[db_len - salt_len - 1] ^= 0x01;
+        for (byte, salt) in db[db_len - salt_len..].iter_mut().zip(salt.iter()) {
+            *byte ^= salt;
+        }
+        db[0] &= 0xFF >> (8 * em_len - em_bits);
+        rest[..h_len].copy_from_slice(h.as_ref());
+        rest[h_len] = PSS_TRAILER;
+        self.rsa_private_op(key, encoded, signature)
+    }
+
+    /// Verifies the RSASSA-PSS signature of `digest` with the public key. Any salt length is
+    /// accepted.
+    pub fn rsapss_verify(
+        key: &RsaKey,
+        alg: TpmiAlgHash,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, TpmRcError> {
+        let h_len = digest_size(alg).ok_or(TpmRcError::Hash)?;
+        let em_bits = key.modulus_bits().saturating_sub(1);
+        let em_len = em_bits.div_ceil(8);
+        let size = key.n.len();
+        let mut encoded = [0; MAX_RSA_KEY_BYTES];
+        if size > MAX_RSA_KEY_BYTES
+            || em_len < h_len + 2
+            || !key.public_op::<Deps::Rsa>(signature, &mut encoded[..size])
+        {
+            return Ok(false);
+        }
+        let (leading, em) = encoded[..size].split_at_mut(size - em_len);
+        let top_bits = !(0xFFu8 >> (8 * em_len - em_bits));
+        if leading.iter().any(|b| *b != 0) || em[em_len - 1] != PSS_TRAILER || em[0] & top_bits != 0
+        {
+            return Ok(false);
+        }
+
+        let db_len = em_len - h_len - 1;
+        let (masked_db, rest) = em.split_at_mut(db_len);
+        let h = &rest[..h_len];
+        let mut db = [0; MAX_RSA_KEY_BYTES];
+        let db = &mut db[..db_len];
+        mgf1::<Deps::Hash>(alg, h, db)?;
+        for (byte, masked) in db.iter_mut().zip(masked_db.iter()) {
+            *byte ^= masked;
+        }
+        db[0] &= !top_bits;
+        let Some(separator) = db.iter().position(|b| *b != 0) else {
+            return Ok(false);
+        };
+        if db[separator] != 0x01 {
+            return Ok(false);
+        }
+        let salt = &db[separator + 1..];
+        Ok(pss_hash::<Deps>(alg, digest, salt)?.as_ref() == h)
+    }
+
+    /// Decrypts `cipher_text` with the private key using RSAES-OAEP from [RFC 8017] 7.1.2 with
+    /// `label` and writes the message to the start of `message`. Returns the size of the message,
+    /// or [`TpmRcError::Value`] if `cipher_text` is not a valid encryption or the message doesn't
+    /// fit.
+    ///
+    /// [RFC 8017]: https://www.rfc-editor.org/rfc/rfc8017
+    pub fn rsaes_oaep_decrypt(
+        &mut self,
+        key: &RsaKey,
+        alg: TpmiAlgHash,
+        label: &[u8],
+        cipher_text: &[u8],
+        message: &mut [u8],
+    ) -> Result<usize, TpmRcError> {
+        let h_len = digest_size(alg).ok_or(TpmRcError::Hash)?;
+        let size = key.n.len();
+        if size > MAX_RSA_KEY_BYTES || cipher_text.len() != size || size < 2 * h_len + 2 {
+            return Err(TpmRcError::Value);
+        }
+        let mut em = [0; MAX_RSA_KEY_BYTES];
+        let em = &mut em[..size];
+        self.rsa_private_op(key, cipher_text, em)?;
+
+        // EM = 0x00 || maskedSeed || maskedDB
+        let (masked_seed, masked_db) = em[1..].split_at(h_len);
+        let mut seed = [0; MAX_DIGEST_SIZE];
+        let seed = &mut seed[..h_len];
+        mgf1::<Deps::Hash>(alg, masked_db, seed)?;
+        for (byte, masked) in seed.iter_mut().zip(masked_seed) {
+            *byte ^= masked;
+        }
+        let mut db = [0; MAX_RSA_KEY_BYTES];
+        let db = &mut db[..masked_db.len()];
+        mgf1::<Deps::Hash>(alg, seed, db)?;
+        for (byte, masked) in db.iter_mut().zip(masked_db) {
+            *byte ^= masked;
+        }
+
+        // DB = lHash || PS || 0x01 || M, where PS are zeros. All checks are made before failing,
+        // so that the error doesn't reveal which one failed.
+        let label_hash = digest::<Deps::Hash>(alg, &[label])?;
+        let (db_label_hash, rest) = db.split_at(h_len);
+        let separator = rest.iter().position(|b| *b != 0);
+        let valid = (em[0] == 0)
+            & constant_time_eq(db_label_hash, label_hash.as_ref())
+            & separator.is_some_and(|separator| rest[separator] == 0x01);
+        let Some(separator) = separator.filter(|_| valid) else {
+            return Err(TpmRcError::Value);
+        };
+        let decrypted = &rest[separator + 1..];
+        message
+            .get_mut(..decrypted.len())
+            .ok_or(TpmRcError::Value)?
+            .copy_from_slice(decrypted);
+        Ok(decrypted.len())
+    }
+}
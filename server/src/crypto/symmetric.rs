@@ -0,0 +1,118 @@
+//! Block cipher modes of operation from [TPM2.0 1.83] Part 1, C.8 and C.9.
+
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::TpmiAlgSymMode;
+
+use crate::platform::crypto::BlockCipher;
+
+/// The block size of all block ciphers supported by this TPM.
+pub const SYM_BLOCK_SIZE: usize = 16;
+
+/// Whether a symmetric operation encrypts or decrypts.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+/// Increments the big-endian counter in `block`, wrapping around on overflow.
+fn increment(block: &mut [u8]) {
+    for byte in block.iter_mut().rev() {
+        *byte = byte.wrapping_add(1);
+        if *byte != 0 {
+            break;
+        }
+    }
+}
+
+/// XORs `mask` into `data`. `data` may be shorter than `mask`.
+fn xor(data: &mut [u8], mask: &[u8]) {
+    for (byte, mask) in data.iter_mut().zip(mask) {
+        *byte ^= mask;
+    }
+}
+
+/// Encrypts or decrypts `data` in place with `mode`. For every mode but ECB, `iv` holds the
+/// initial chaining value and is updated so that a following call continues the operation.
+///
+/// Returns [`TpmRcError::Size`] if ECB or CBC is used with data that is not a multiple of the
+/// block size, or if `iv` is not a block long, and [`TpmRcError::Mode`] for unsupported modes.
+pub fn sym_crypt<C: BlockCipher>(
+    cipher: &C,
+    mode: TpmiAlgSymMode,
+    direction: Direction,
+    iv: &mut [u8],
+    data: &mut [u8],
+) -> Result<(), TpmRcError> {
+    if mode == TpmiAlgSymMode::ECB {
+        if !data.len().is_multiple_of(SYM_BLOCK_SIZE) {
+            return Err(TpmRcError::Size);
+        }
+        for block in data.chunks_exact_mut(SYM_BLOCK_SIZE) {
+            match direction {
+                Direction::Encrypt => cipher.encrypt_block(block),
+                Direction::Decrypt => cipher.decrypt_block(block),
+            }
+        }
+        return Ok(());
+    }
+
+    let iv: &mut [u8; SYM_BLOCK_SIZE] = iv.try_into().or(Err(TpmRcError::Size))?;
+    match mode {
+        TpmiAlgSymMode::CBC => {
+            if !data.len().is_multiple_of(SYM_BLOCK_SIZE) {
+                return Err(TpmRcError::Size);
+            }
+            for block in data.chunks_exact_mut(SYM_BLOCK_SIZE) {
+                match direction {
+                    Direction::Encrypt => {
+                        xor(block, iv);
+                        cipher.encrypt_block(block);
+                        iv.copy_from_slice(block);
+                    }
+                    Direction::Decrypt => {
+                        let mut cipher_text = [0; SYM_BLOCK_SIZE];
+                        cipher_text.copy_from_slice(block);
+                        cipher.decrypt_block(block);
+                        xor(block, iv);
+                        *iv = cipher_text;
+                    }
+                }
+            }
+        }
+        TpmiAlgSymMode::CFB => {
+            for block in data.chunks_mut(SYM_BLOCK_SIZE) {
+                cipher.encrypt_block(iv);
+                let mut cipher_text = [0; SYM_BLOCK_SIZE];
+                match direction {
+                    Direction::Encrypt => {
+                        xor(block, iv);
+                        cipher_text[..block.len()].copy_from_slice(block);
+                    }
+                    Direction::Decrypt => {
+                        cipher_text[..block.len()].copy_from_slice(block);
+                        xor(block, iv);
+                    }
+                }
+                // A partial last block leaves the rest of the chaining value zero.
+                *iv = cipher_text;
+            }
+        }
+        TpmiAlgSymMode::CTR => {
+            for block in data.chunks_mut(SYM_BLOCK_SIZE) {
+                let mut key_stream = *iv;
+                cipher.encrypt_block(&mut key_stream);
+                increment(iv);
+                xor(block, &key_stream);
+            }
+        }
+        TpmiAlgSymMode::OFB => {
+            for block in data.chunks_mut(SYM_BLOCK_SIZE) {
+                cipher.encrypt_block(iv);
+                xor(block, iv);
+            }
+        }
+        _ => return Err(TpmRcError::Mode),
+    }
+    Ok(())
+}
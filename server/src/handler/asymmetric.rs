@@ -0,0 +1,170 @@
+use tpm2_rs_base::commands::{
+    EccDecryptCmd, EccDecryptResp, EccEncryptCmd, EccEncryptResp, EccParametersCmd,
+    EccParametersResp,
+};
+use tpm2_rs_base::constants::{TpmHandle, TPM2_MAX_DIGEST_BUFFER};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bDigest, Tpm2bEccPoint, Tpm2bMaxBuffer, Tpm2bSimple, Tpm2bStruct,
+    TpmaObject, TpmsEccParms, TpmtKdfScheme, TpmuSensitiveComposite,
+};
+
+use crate::{
+    crypto::{
+        algorithms::{check_kdf, ErrorAt},
+        ecc::{EccCurve, EccInteger, EccPoint},
+        Crypto,
+    },
+    handler::CommandHandler,
+    object::Object,
+    platform::{TpmBuffers, TpmContextDeps},
+    req_resp::{unmarshal_error, RequestThenResponse},
+};
+
+const KEY_HANDLE: ErrorAt = (ErrorType::Handle, ErrorPosition::Pos1);
+
+/// Selects the KDF for ECC encryption from the scheme of the key and the scheme of the command.
+/// If the key has a scheme, the command may only repeat it.
+fn select_kdf(
+    key_scheme: &TpmtKdfScheme,
+    in_scheme: &TpmtKdfScheme,
+    at: ErrorAt,
+) -> Result<TpmtKdfScheme, TpmRcError> {
+    let scheme = match (key_scheme, in_scheme) {
+        (TpmtKdfScheme::Null(_), TpmtKdfScheme::Null(_)) => {
+            return Err(TpmRcError::SchemeFor(at.0, at.1))
+        }
+        (TpmtKdfScheme::Null(_), scheme) | (scheme, TpmtKdfScheme::Null(_)) => *scheme,
+        (key_scheme, in_scheme) if key_scheme == in_scheme => *key_scheme,
+        _ => return Err(TpmRcError::SchemeFor(at.0, at.1)),
+    };
+    check_kdf(&scheme, at)?;
+    Ok(scheme)
+}
+
+/// Returns the ECC parameters and public point of a loaded key that may be used for encryption.
+fn ecc_encryption_key(
+    object: &Object,
+) -> Result<(&TpmsEccParms, &'static EccCurve, EccPoint), TpmRcError> {
+    let PublicParmsAndId::Ecc(parms, point) = &object.public.parms_and_id else {
+        return Err(TpmRcError::KeyFor(KEY_HANDLE.0, KEY_HANDLE.1));
+    };
+    if !object
+        .public
+        .object_attributes
+        .contains(TpmaObject::DECRYPT)
+    {
+        return Err(TpmRcError::AttributesFor(KEY_HANDLE.0, KEY_HANDLE.1));
+    }
+    // The key was validated when it was loaded.
+    let curve = EccCurve::find(parms.curve_id.0).ok_or(TpmRcError::Failure)?;
+    let point = EccPoint::new(curve, point).ok_or(TpmRcError::Failure)?;
+    Ok((parms, curve, point))
+}
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Handles the [TpmCc::ECCParameters] (`0x178`) command.
+    pub fn ecc_parameters(
+        &mut self,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: EccParametersCmd = request.unmarshal()?;
+        let curve = EccCurve::find(command.curve_id.0).ok_or(TpmRcError::CurveFor(
+            ErrorType::Parameter,
+            ErrorPosition::Pos1,
+        ))?;
+
+        let mut response = request.into_response();
+        response.marshal(&EccParametersResp {
+            parameters: curve.details()?,
+        })
+    }
+
+    /// Handles the [TpmCc::ECCEncrypt] (`0x199`) command.
+    pub fn ecc_encrypt(
+        &mut self,
+        key_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: EccEncryptCmd = request.unmarshal()?;
+        let object = self
+            .objects
+            .get(key_handle)
+            .ok_or(TpmRcError::HandleFor(KEY_HANDLE.0, KEY_HANDLE.1))?;
+        let (parms, curve, public) = ecc_encryption_key(object)?;
+        let scheme = select_kdf(
+            &parms.kdf,
+            &command.in_scheme,
+            (ErrorType::Parameter, ErrorPosition::Pos2),
+        )?;
+
+        let mut data = [0; TPM2_MAX_DIGEST_BUFFER as usize];
+        let data = &mut data[..command.plain_text.get_size() as usize];
+        data.copy_from_slice(command.plain_text.get_buffer());
+        let cipher_text = self.crypto.ecc_encrypt(curve, &public, &scheme, data)?;
+
+        let mut response = request.into_response();
+        response.marshal(&EccEncryptResp {
+            c1: Tpm2bEccPoint::from_struct(&cipher_text.c1.to_tpms()?)
+                .or(Err(TpmRcError::Failure))?,
+            c2: Tpm2bMaxBuffer::from_bytes(data).or(Err(TpmRcError::Failure))?,
+            c3: Tpm2bDigest::from_bytes(cipher_text.c3.as_ref()).or(Err(TpmRcError::Failure))?,
+        })
+    }
+
+    /// Handles the [TpmCc::ECCDecrypt] (`0x19A`) command.
+    pub fn ecc_decrypt(
+        &mut self,
+        key_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: EccDecryptCmd = request.unmarshal()?;
+        let object = self
+            .objects
+            .get(key_handle)
+            .ok_or(TpmRcError::HandleFor(KEY_HANDLE.0, KEY_HANDLE.1))?;
+        let (parms, curve, _) = ecc_encryption_key(object)?;
+        if object
+            .public
+            .object_attributes
+            .contains(TpmaObject::RESTRICTED)
+        {
+            return Err(TpmRcError::AttributesFor(KEY_HANDLE.0, KEY_HANDLE.1));
+        }
+        let Some(TpmuSensitiveComposite::Ecc(d)) = object.sensitive.as_ref().map(|s| s.sensitive)
+        else {
+            return Err(TpmRcError::KeyFor(KEY_HANDLE.0, KEY_HANDLE.1));
+        };
+        let d = EccInteger::new(curve, d.get_buffer()).ok_or(TpmRcError::Failure)?;
+        let scheme = select_kdf(
+            &parms.kdf,
+            &command.in_scheme,
+            (ErrorType::Parameter, ErrorPosition::Pos4),
+        )?;
+
+        let c1 = command.c1.to_struct().map_err(unmarshal_error)?;
+        let c1 = EccPoint::new(curve, &c1).ok_or(TpmRcError::EccPointFor(
+            ErrorType::Parameter,
+            ErrorPosition::Pos1,
+        ))?;
+        curve
+            .validate_point::<Deps::Ecc>(&c1)
+            .or(Err(TpmRcError::EccPointFor(
+                ErrorType::Parameter,
+                ErrorPosition::Pos1,
+            )))?;
+
+        let mut data = [0; TPM2_MAX_DIGEST_BUFFER as usize];
+        let data = &mut data[..command.c2.get_size() as usize];
+        data.copy_from_slice(command.c2.get_buffer());
+        Crypto::<Deps>::ecc_decrypt(curve, &d, &scheme, &c1, command.c3.get_buffer(), data)?;
+
+        let mut response = request.into_response();
+        response.marshal(&EccDecryptResp {
+            plain_text: Tpm2bMaxBuffer::from_bytes(data).or(Err(TpmRcError::Failure))?,
+        })
+    }
+}
@@ -0,0 +1,373 @@
+use tpm2_rs_base::commands::{
+    CertifyCmd, CertifyCreationCmd, CertifyCreationResp, CertifyResp, GetCommandAuditDigestCmd,
+    GetCommandAuditDigestResp, GetSessionAuditDigestCmd, GetSessionAuditDigestResp, GetTimeCmd,
+    GetTimeResp, QuoteCmd, QuoteResp,
+};
+use tpm2_rs_base::constants::{TpmGenerated, TpmHandle, TpmSt};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::marshal::Marshalable;
+use tpm2_rs_base::{
+    Tpm2bAttest, Tpm2bData, Tpm2bDigest, Tpm2bName, Tpm2bSimple, TpmaObject, TpmiAlgHash,
+    TpmiYesNo, TpmsAttest, TpmsCertifyInfo, TpmsCommandAuditInfo, TpmsCreationInfo, TpmsEmpty,
+    TpmsQuoteInfo, TpmsSessionAuditInfo, TpmsTimeAttestInfo, TpmsTimeInfo, TpmtSigScheme,
+    TpmtSignature, TpmuAttest,
+};
+
+use crate::{
+    crypto::{algorithms::ErrorAt, hash::digest},
+    handler::{signature::select_sig_scheme, CommandHandler},
+    pcr::Pcrs,
+    platform::{TpmBuffers, TpmContextDeps},
+    req_resp::RequestThenResponse,
+};
+
+const HANDLE_1: ErrorAt = (ErrorType::Handle, ErrorPosition::Pos1);
+const HANDLE_2: ErrorAt = (ErrorType::Handle, ErrorPosition::Pos2);
+const HANDLE_3: ErrorAt = (ErrorType::Handle, ErrorPosition::Pos3);
+const PARAMETER_2: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos2);
+const PARAMETER_3: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos3);
+const PARAMETER_4: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos4);
+
+/// Parses a decimal version number at compile time.
+const fn parse_version(digits: &str) -> u64 {
+    let digits = digits.as_bytes();
+    let mut value = 0;
+    let mut index = 0;
+    while index < digits.len() {
+        value = value * 10 + (digits[index] - b'0') as u64;
+        index += 1;
+    }
+    value
+}
+
+/// The firmware version reported in attestations, which is the version of this crate with the
+/// major, minor and patch versions in the upper three 16-bit words.
+const FIRMWARE_VERSION: u64 = (parse_version(env!("CARGO_PKG_VERSION_MAJOR")) << 48)
+    | (parse_version(env!("CARGO_PKG_VERSION_MINOR")) << 32)
+    | (parse_version(env!("CARGO_PKG_VERSION_PATCH")) << 16);
+
+/// The signing scheme of an attestation, which is `None` if the signing key is `TPM_RH_NULL`.
+type AttestationScheme = Option<(TpmtSigScheme, TpmiAlgHash)>;
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Checks that the key at `sign_handle` can sign attestations and selects the scheme it signs
+    /// with.
+    fn select_attestation_scheme(
+        &self,
+        sign_handle: TpmHandle,
+        sign_at: ErrorAt,
+        in_scheme: &TpmtSigScheme,
+        scheme_at: ErrorAt,
+    ) -> Result<AttestationScheme, TpmRcError> {
+        if sign_handle == TpmHandle::RHNull {
+            return Ok(None);
+        }
+        let object = self
+            .objects
+            .get(sign_handle)
+            .ok_or(TpmRcError::HandleFor(sign_at.0, sign_at.1))?;
+        if !object
+            .public
+            .object_attributes
+            .contains(TpmaObject::SIGN_ENCRYPT)
+        {
+            return Err(TpmRcError::KeyFor(sign_at.0, sign_at.1));
+        }
+        select_sig_scheme(&object.public, in_scheme, sign_at, scheme_at).map(Some)
+    }
+
+    /// Builds the attestation of `attested` with the common fields filled in and signs it with
+    /// the key at `sign_handle`.
+    fn sign_attestation(
+        &mut self,
+        sign_handle: TpmHandle,
+        sign_at: ErrorAt,
+        scheme: AttestationScheme,
+        qualifying_data: Tpm2bData,
+        time_info: &TpmsTimeInfo,
+        attested: TpmuAttest,
+    ) -> Result<(Tpm2bAttest, TpmtSignature), TpmRcError> {
+        let (qualified_signer, hierarchy) = match self.objects.get(sign_handle) {
+            Some(object) if scheme.is_some() => (object.qualified_name, object.hierarchy),
+            // The Name of a permanent handle is the handle itself.
+            _ => (
+                Tpm2bName::from_bytes(&sign_handle.0.to_be_bytes()).or(Err(TpmRcError::Failure))?,
+                sign_handle,
+            ),
+        };
+        let mut attest = TpmsAttest {
+            magic: TpmGenerated::VALUE,
+            qualified_signer,
+            extra_data: qualifying_data,
+            clock_info: time_info.clock_info,
+            firmware_version: FIRMWARE_VERSION,
+            attested,
+        };
+        // Keys outside of the platform and endorsement hierarchies may not learn how often the
+        // TPM was reset or which firmware it runs, which could be used to correlate keys.
+        if hierarchy != TpmHandle::RHPlatform && hierarchy != TpmHandle::RHEndorsement {
+            let (firmware, reset, restart) =
+                self.proofs.obfuscation::<Deps::Hash>(&qualified_signer)?;
+            attest.firmware_version = attest.firmware_version.wrapping_add(firmware);
+            attest.clock_info.reset_count = attest.clock_info.reset_count.wrapping_add(reset);
+            attest.clock_info.restart_count = attest.clock_info.restart_count.wrapping_add(restart);
+        }
+
+        let mut marshaled = [0; size_of::<TpmsAttest>()];
+        let size = attest
+            .try_marshal(&mut marshaled)
+            .or(Err(TpmRcError::Failure))?;
+        let marshaled = &marshaled[..size];
+        let signature = match scheme {
+            Some((scheme, hash)) => {
+                let attest_digest = digest::<Deps::Hash>(hash, &[marshaled])?;
+                self.sign_digest(sign_handle, sign_at, &scheme, hash, attest_digest.as_ref())?
+            }
+            None => TpmtSignature::Null(TpmsEmpty),
+        };
+        let attest = Tpm2bAttest::from_bytes(marshaled).or(Err(TpmRcError::Failure))?;
+        Ok((attest, signature))
+    }
+
+    /// Handles the [TpmCc::Certify] (`0x148`) command.
+    pub fn certify(
+        &mut self,
+        object_handle: TpmHandle,
+        sign_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: CertifyCmd = request.unmarshal()?;
+        let object = self
+            .objects
+            .get(object_handle)
+            .ok_or(TpmRcError::HandleFor(HANDLE_1.0, HANDLE_1.1))?;
+        let attested = TpmuAttest::Certify(TpmsCertifyInfo {
+            name: object.name,
+            qualified_name: object.qualified_name,
+        });
+        let scheme =
+            self.select_attestation_scheme(sign_handle, HANDLE_2, &command.in_scheme, PARAMETER_2)?;
+        let time_info = self.time.time_info();
+        let (certify_info, signature) = self.sign_attestation(
+            sign_handle,
+            HANDLE_2,
+            scheme,
+            command.qualifying_data,
+            &time_info,
+            attested,
+        )?;
+        let mut response = request.into_response();
+        response.marshal(&CertifyResp {
+            certify_info,
+            signature,
+        })
+    }
+
+    /// Handles the [TpmCc::CertifyCreation] (`0x14A`) command.
+    pub fn certify_creation(
+        &mut self,
+        sign_handle: TpmHandle,
+        object_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: CertifyCreationCmd = request.unmarshal()?;
+        let object = self
+            .objects
+            .get(object_handle)
+            .ok_or(TpmRcError::HandleFor(HANDLE_2.0, HANDLE_2.1))?;
+        let object_name = object.name;
+        let scheme =
+            self.select_attestation_scheme(sign_handle, HANDLE_1, &command.in_scheme, PARAMETER_3)?;
+
+        // The ticket proves that this TPM created the object with the given creation data.
+        let ticket = &command.creation_ticket;
+        if ticket.tag != TpmSt::Creation {
+            return Err(TpmRcError::TicketFor(PARAMETER_4.0, PARAMETER_4.1));
+        }
+        let expected = self.proofs.creation_ticket::<Deps::Hash>(
+            ticket.hierarchy,
+            &object_name,
+            command.creation_hash.get_buffer(),
+        )?;
+        if expected.digest.get_size() == 0 || expected.digest != ticket.digest {
+            return Err(TpmRcError::TicketFor(PARAMETER_4.0, PARAMETER_4.1));
+        }
+
+        let attested = TpmuAttest::Creation(TpmsCreationInfo {
+            object_name,
+            creation_hash: command.creation_hash,
+        });
+        let time_info = self.time.time_info();
+        let (certify_info, signature) = self.sign_attestation(
+            sign_handle,
+            HANDLE_1,
+            scheme,
+            command.qualifying_data,
+            &time_info,
+            attested,
+        )?;
+        let mut response = request.into_response();
+        response.marshal(&CertifyCreationResp {
+            certify_info,
+            signature,
+        })
+    }
+
+    /// Handles the [TpmCc::Quote] (`0x158`) command.
+    pub fn quote(
+        &mut self,
+        sign_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: QuoteCmd = request.unmarshal()?;
+        let scheme =
+            self.select_attestation_scheme(sign_handle, HANDLE_1, &command.in_scheme, PARAMETER_2)?;
+
+        // The PCRs are digested with the hash of the signing scheme, so there is no digest if
+        // the quote is not signed.
+        let pcr_select = Pcrs::filter(&command.pcr_select)?;
+        let pcr_digest = match scheme {
+            Some((_, hash)) => self.pcrs.digest::<Deps::Hash>(hash, &pcr_select)?,
+            None => Tpm2bDigest::default(),
+        };
+        let attested = TpmuAttest::Quote(TpmsQuoteInfo {
+            pcr_select,
+            pcr_digest,
+        });
+        let time_info = self.time.time_info();
+        let (quoted, signature) = self.sign_attestation(
+            sign_handle,
+            HANDLE_1,
+            scheme,
+            command.qualifying_data,
+            &time_info,
+            attested,
+        )?;
+        let mut response = request.into_response();
+        response.marshal(&QuoteResp { quoted, signature })
+    }
+
+    /// Handles the [TpmCc::GetSessionAuditDigest] (`0x14D`) command.
+    pub fn get_session_audit_digest(
+        &mut self,
+        privacy_admin_handle: TpmHandle,
+        sign_handle: TpmHandle,
+        session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: GetSessionAuditDigestCmd = request.unmarshal()?;
+        if privacy_admin_handle != TpmHandle::RHEndorsement {
+            return Err(TpmRcError::ValueFor(HANDLE_1.0, HANDLE_1.1));
+        }
+        let session = self
+            .sessions
+            .get(session_handle)
+            .ok_or(TpmRcError::HandleFor(HANDLE_3.0, HANDLE_3.1))?;
+        let exclusive_session = if self.sessions.is_exclusive_audit(session_handle) {
+            TpmiYesNo::YES
+        } else {
+            TpmiYesNo::NO
+        };
+        let attested = TpmuAttest::SessionAudit(TpmsSessionAuditInfo {
+            exclusive_session,
+            session_digest: session.audit_digest,
+        });
+        let scheme =
+            self.select_attestation_scheme(sign_handle, HANDLE_2, &command.in_scheme, PARAMETER_2)?;
+        let time_info = self.time.time_info();
+        let (audit_info, signature) = self.sign_attestation(
+            sign_handle,
+            HANDLE_2,
+            scheme,
+            command.qualifying_data,
+            &time_info,
+            attested,
+        )?;
+        let mut response = request.into_response();
+        response.marshal(&GetSessionAuditDigestResp {
+            audit_info,
+            signature,
+        })
+    }
+
+    /// Handles the [TpmCc::GetCommandAuditDigest] (`0x133`) command.
+    pub fn get_command_audit_digest(
+        &mut self,
+        privacy_handle: TpmHandle,
+        sign_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: GetCommandAuditDigestCmd = request.unmarshal()?;
+        if privacy_handle != TpmHandle::RHEndorsement {
+            return Err(TpmRcError::ValueFor(HANDLE_1.0, HANDLE_1.1));
+        }
+        let scheme =
+            self.select_attestation_scheme(sign_handle, HANDLE_2, &command.in_scheme, PARAMETER_2)?;
+        let attested = TpmuAttest::CommandAudit(TpmsCommandAuditInfo {
+            audit_counter: self.audit.counter(),
+            digest_alg: self.audit.alg().0,
+            audit_digest: *self.audit.digest(),
+            command_digest: self.audit.command_digest::<Deps::Hash>()?,
+        });
+        let time_info = self.time.time_info();
+        let (audit_info, signature) = self.sign_attestation(
+            sign_handle,
+            HANDLE_2,
+            scheme,
+            command.qualifying_data,
+            &time_info,
+            attested,
+        )?;
+        // A signed audit digest starts over, so that the next one can be verified on its own.
+        if scheme.is_some() {
+            self.audit.clear();
+        }
+        let mut response = request.into_response();
+        response.marshal(&GetCommandAuditDigestResp {
+            audit_info,
+            signature,
+        })
+    }
+
+    /// Handles the [TpmCc::GetTime] (`0x14C`) command.
+    pub fn get_time(
+        &mut self,
+        privacy_admin_handle: TpmHandle,
+        sign_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: GetTimeCmd = request.unmarshal()?;
+        if privacy_admin_handle != TpmHandle::RHEndorsement {
+            return Err(TpmRcError::ValueFor(HANDLE_1.0, HANDLE_1.1));
+        }
+        let scheme =
+            self.select_attestation_scheme(sign_handle, HANDLE_2, &command.in_scheme, PARAMETER_2)?;
+
+        // The privacy administrator authorized revealing the clock, so only the copy in the
+        // common fields is obfuscated.
+        let time_info = self.time.time_info();
+        let attested = TpmuAttest::Time(TpmsTimeAttestInfo {
+            time: time_info,
+            firmware_version: FIRMWARE_VERSION,
+        });
+        let (time_info, signature) = self.sign_attestation(
+            sign_handle,
+            HANDLE_2,
+            scheme,
+            command.qualifying_data,
+            &time_info,
+            attested,
+        )?;
+        let mut response = request.into_response();
+        response.marshal(&GetTimeResp {
+            time_info,
+            signature,
+        })
+    }
+}
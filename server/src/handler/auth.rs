@@ -0,0 +1,120 @@
+use tpm2_rs_base::constants::TpmHandle;
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::{Tpm2bName, Tpm2bSimple, TpmaObject};
+
+use crate::{crypto::constant_time_eq, handler::CommandHandler, platform::TpmContextDeps};
+
+/// Removes the trailing zeros that are not significant in authorization values.
+fn trim_auth(auth: &[u8]) -> &[u8] {
+    let len = auth
+        .iter()
+        .rposition(|b| *b != 0)
+        .map_or(0, |last| last + 1);
+    &auth[..len]
+}
+
+/// Checks `password` against an authorization value and whether its entity is exempt from
+/// dictionary attack protections.
+fn check_password((auth_value, no_da): (&[u8], bool), password: &[u8]) -> Result<(), AuthError> {
+    if constant_time_eq(auth_value, trim_auth(password)) {
+        Ok(())
+    } else {
+        Err(AuthError::wrong_auth(no_da))
+    }
+}
+
+/// The reason an authorization failed.
+pub enum AuthError {
+    /// The handle does not reference an entity that is known to the TPM.
+    Handle,
+    /// The entity can't be authorized with its authorization value, e.g. because its sensitive
+    /// area is not loaded.
+    Unavailable,
+    /// The password or HMAC is wrong and the entity is subject to dictionary attack protections.
+    AuthFail,
+    /// The password or HMAC is wrong and the entity is not subject to dictionary attack
+    /// protections.
+    BadAuth,
+}
+
+impl AuthError {
+    /// Returns the error for a wrong password or HMAC for an entity with the `no_da` attribute.
+    pub fn wrong_auth(no_da: bool) -> Self {
+        if no_da {
+            AuthError::BadAuth
+        } else {
+            AuthError::AuthFail
+        }
+    }
+}
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Returns the authorization value of the entity referenced by `handle` with its trailing
+    /// zeros removed, and whether the entity is exempt from dictionary attack protections.
+    /// `with_auth` requires that the entity allows the authorization value to be used directly.
+    fn entity_auth_value(
+        &self,
+        handle: TpmHandle,
+        with_auth: bool,
+    ) -> Result<(&[u8], bool), AuthError> {
+        match handle {
+            // Hierarchy authorization values can't be changed yet, so they are always empty.
+            TpmHandle::RHOwner
+            | TpmHandle::RHEndorsement
+            | TpmHandle::RHPlatform
+            | TpmHandle::RHNull => Ok((&[][..], true)),
+            TpmHandle::RHLockout => Ok((&[][..], false)),
+            handle => {
+                let object = self.objects.get(handle).ok_or(AuthError::Handle)?;
+                let attributes = object.public.object_attributes;
+                if with_auth && !attributes.contains(TpmaObject::USER_WITH_AUTH) {
+                    return Err(AuthError::Unavailable);
+                }
+                let sensitive = object.sensitive.as_ref().ok_or(AuthError::Unavailable)?;
+                Ok((
+                    trim_auth(sensitive.auth_value.get_buffer()),
+                    attributes.contains(TpmaObject::NO_DA),
+                ))
+            }
+        }
+    }
+
+    /// Returns the authorization value of the entity referenced by `handle` for an action in the
+    /// USER role with its trailing zeros removed, and whether the entity is exempt from dictionary
+    /// attack protections.
+    pub fn auth_value(&self, handle: TpmHandle) -> Result<(&[u8], bool), AuthError> {
+        self.entity_auth_value(handle, true)
+    }
+
+    /// Returns the authorization value of the entity referenced by `handle` like
+    /// [`Self::auth_value`], but for a policy session that asserted TPM2_PolicyAuthValue or
+    /// TPM2_PolicyPassword, which the entity does not need to allow.
+    pub fn policy_entity_auth_value(&self, handle: TpmHandle) -> Result<(&[u8], bool), AuthError> {
+        self.entity_auth_value(handle, false)
+    }
+
+    /// Checks `password` against the authorization value of the entity referenced by `handle`
+    /// for an action in the USER role.
+    pub fn authorize_password(&self, handle: TpmHandle, password: &[u8]) -> Result<(), AuthError> {
+        check_password(self.auth_value(handle)?, password)
+    }
+
+    /// Checks `password` against the authorization value of the entity referenced by `handle`
+    /// for a policy session that asserted TPM2_PolicyPassword.
+    pub fn authorize_policy_password(
+        &self,
+        handle: TpmHandle,
+        password: &[u8],
+    ) -> Result<(), AuthError> {
+        check_password(self.policy_entity_auth_value(handle)?, password)
+    }
+
+    /// Returns the Name of the entity referenced by `handle`, which is the handle itself for
+    /// entities other than objects.
+    pub fn entity_name(&self, handle: TpmHandle) -> Result<Tpm2bName, TpmRcError> {
+        match self.objects.get(handle) {
+            Some(object) => Ok(object.name),
+            None => Tpm2bName::from_bytes(&handle.0.to_be_bytes()).or(Err(TpmRcError::Failure)),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use tpm2_rs_base::commands::TestParmsCmd;
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+
+use crate::{
+    crypto::algorithms::check_public_parms,
+    handler::CommandHandler,
+    platform::{TpmBuffers, TpmContextDeps},
+    req_resp::RequestThenResponse,
+};
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Handles the [TpmCc::TestParams] (`0x18A`) command.
+    pub fn test_parms(
+        &mut self,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: TestParmsCmd = request.unmarshal()?;
+        check_public_parms(
+            &command.parameters,
+            (ErrorType::Parameter, ErrorPosition::Pos1),
+        )
+    }
+}
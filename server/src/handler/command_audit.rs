@@ -0,0 +1,81 @@
+use tpm2_rs_base::commands::SetCommandCodeAuditStatusCmd;
+use tpm2_rs_base::constants::{TpmAlgId, TpmCc, TpmHandle};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::TpmiAlgHash;
+
+use crate::{
+    crypto::hash::digest_size,
+    handler::CommandHandler,
+    platform::{TpmBuffers, TpmContextDeps},
+    req_resp::RequestThenResponse,
+};
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Returns the hash algorithm of the audit digest.
+    pub fn audit_alg(&self) -> TpmiAlgHash {
+        self.audit.alg()
+    }
+
+    /// Returns the hash algorithm that a successful execution of `command_code` with `parameters`
+    /// is recorded in the audit digest with, or `None` if the command is not audited.
+    pub fn command_audit_alg(&self, command_code: TpmCc, parameters: &[u8]) -> Option<TpmiAlgHash> {
+        if !self.audit.is_audited(command_code) {
+            return None;
+        }
+        // TPM2_SetCommandCodeAuditStatus is recorded with the algorithm it selects.
+        if command_code == TpmCc::SetCommandCodeAuditStatus {
+            let alg = parameters
+                .first_chunk()
+                .map(|alg| TpmiAlgHash(u16::from_be_bytes(*alg)));
+            if let Some(alg) = alg.filter(|alg| digest_size(*alg).is_some()) {
+                return Some(alg);
+            }
+        }
+        Some(self.audit.alg())
+    }
+
+    /// Records a successful execution of an audited command in the audit digest.
+    pub fn audit_command(&mut self, cp_hash: &[u8], rp_hash: &[u8]) -> Result<(), TpmRcError> {
+        self.audit.extend::<Deps::Hash>(cp_hash, rp_hash)
+    }
+
+    /// Handles the [TpmCc::SetCommandCodeAuditStatus] (`0x140`) command.
+    pub fn set_command_code_audit_status(
+        &mut self,
+        auth: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: SetCommandCodeAuditStatusCmd = request.unmarshal()?;
+        if auth != TpmHandle::RHOwner && auth != TpmHandle::RHPlatform {
+            return Err(TpmRcError::ValueFor(ErrorType::Handle, ErrorPosition::Pos1));
+        }
+
+        // The algorithm and the audited commands can't be changed at the same time.
+        if command.audit_alg.0 != TpmAlgId::Null.0 && command.audit_alg != self.audit.alg() {
+            if digest_size(command.audit_alg).is_none() {
+                return Err(TpmRcError::HashFor(
+                    ErrorType::Parameter,
+                    ErrorPosition::Pos1,
+                ));
+            }
+            if command.set_list.count() != 0 || command.clear_list.count() != 0 {
+                return Err(TpmRcError::SizeFor(
+                    ErrorType::Parameter,
+                    ErrorPosition::Pos1,
+                ));
+            }
+            self.audit.set_alg(command.audit_alg);
+            return Ok(());
+        }
+
+        // Commands in both lists end up not being audited.
+        for command_code in command.set_list.command_codes() {
+            self.audit.set(*command_code, true);
+        }
+        for command_code in command.clear_list.command_codes() {
+            self.audit.set(*command_code, false);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use tpm2_rs_base::commands::FlushContextCmd;
+use tpm2_rs_base::constants::TpmHc;
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+
+use crate::{
+    handler::CommandHandler,
+    platform::{TpmBuffers, TpmContextDeps},
+    req_resp::RequestThenResponse,
+};
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Handles the [TpmCc::FlushContext] (`0x165`) command.
+    pub fn flush_context(
+        &mut self,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: FlushContextCmd = request.unmarshal()?;
+        let handle = command.flush_handle.0;
+        let flushed = if TpmHc::is_hmac_session(handle) || TpmHc::is_policy_session(handle) {
+            self.sessions.remove(command.flush_handle).is_some()
+        } else {
+            self.objects.remove(command.flush_handle).is_some()
+        };
+        if !flushed {
+            return Err(TpmRcError::HandleFor(
+                ErrorType::Parameter,
+                ErrorPosition::Pos1,
+            ));
+        }
+        Ok(())
+    }
+}
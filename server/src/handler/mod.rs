@@ -1,19 +1,55 @@
+mod asymmetric;
+mod attestation;
+mod auth;
+mod capability;
+mod command_audit;
+mod context;
+mod object;
+mod policy;
 mod random;
+mod session;
+mod signature;
+mod symmetric;
 
-use crate::{crypto::Crypto, platform::TpmContextDeps, ServerError};
+pub use auth::AuthError;
+
+use crate::{
+    audit::CommandAudit, crypto::Crypto, object::ObjectSlots, pcr::Pcrs, platform::TpmContextDeps,
+    session::SessionSlots, ticket::HierarchyProofs, time::TpmTime, ServerError,
+};
 
 /// The context that all command handler functions are given access to in order for them to process
 /// their given command.
 pub struct CommandHandler<Deps: TpmContextDeps> {
     /// Gives access to cryptographic operations.
     crypto: Crypto<Deps>,
+    /// The transient objects that are currently loaded.
+    objects: ObjectSlots,
+    /// The authorization sessions that are currently loaded.
+    sessions: SessionSlots,
+    /// The secrets that the tickets of each hierarchy are computed with.
+    proofs: HierarchyProofs,
+    /// The platform configuration registers.
+    pcrs: Pcrs,
+    /// The TPM's notion of time.
+    time: TpmTime<Deps::Clock>,
+    /// The commands selected for audit and the digest of their executions.
+    audit: CommandAudit,
 }
 
 impl<Deps: TpmContextDeps> CommandHandler<Deps> {
     /// Creates a new [`TpmContext`] object that processes incoming TPM requests.
     pub fn new() -> Result<Self, ServerError> {
+        let mut crypto = Crypto::new()?;
+        let proofs = HierarchyProofs::generate(&mut crypto);
         Ok(Self {
-            crypto: Crypto::new()?,
+            crypto,
+            objects: ObjectSlots::new(),
+            sessions: SessionSlots::new(),
+            proofs,
+            pcrs: Pcrs::new(),
+            time: TpmTime::new(),
+            audit: CommandAudit::new(),
         })
     }
 }
@@ -0,0 +1,157 @@
+use tpm2_rs_base::commands::{LoadExternalCmd, LoadExternalResp};
+use tpm2_rs_base::constants::TpmHandle;
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bSimple, Tpm2bStruct, TpmaObject, TpmtPublic, TpmtSensitive,
+    TpmtSymDefObject, TpmuSensitiveComposite,
+};
+
+use crate::{
+    crypto::{
+        algorithms::{check_hash, check_public_parms},
+        ecc::{EccCurve, EccInteger, EccPoint},
+        hash::digest,
+        rsa::RsaKey,
+    },
+    handler::CommandHandler,
+    object::{compute_name, compute_qualified_name, Object},
+    platform::{crypto::Rsa, TpmBuffers, TpmContextDeps},
+    req_resp::{unmarshal_error, RequestThenResponse},
+};
+
+const IN_PRIVATE: (ErrorType, ErrorPosition) = (ErrorType::Parameter, ErrorPosition::Pos1);
+const IN_PUBLIC: (ErrorType, ErrorPosition) = (ErrorType::Parameter, ErrorPosition::Pos2);
+const HIERARCHY: (ErrorType, ErrorPosition) = (ErrorType::Parameter, ErrorPosition::Pos3);
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Checks that the public area describes a key that this TPM can use.
+    fn check_public(public: &TpmtPublic) -> Result<(), TpmRcError> {
+        check_hash(public.name_alg, IN_PUBLIC)?;
+        check_public_parms(&(&public.parms_and_id).into(), IN_PUBLIC)?;
+        if let PublicParmsAndId::Rsa(parms, n) = &public.parms_and_id {
+            if n.get_size() as usize * 8 != parms.key_bits.0 as usize {
+                return Err(TpmRcError::KeyFor(IN_PUBLIC.0, IN_PUBLIC.1));
+            }
+        }
+        if let PublicParmsAndId::Ecc(parms, point) = &public.parms_and_id {
+            let curve = EccCurve::find(parms.curve_id.0)
+                .ok_or(TpmRcError::CurveFor(IN_PUBLIC.0, IN_PUBLIC.1))?;
+            let point = EccPoint::new(curve, point)
+                .ok_or(TpmRcError::EccPointFor(IN_PUBLIC.0, IN_PUBLIC.1))?;
+            curve
+                .validate_point::<Deps::Ecc>(&point)
+                .or(Err(TpmRcError::EccPointFor(IN_PUBLIC.0, IN_PUBLIC.1)))?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the sensitive area is the private part of the key described by the public
+    /// area.
+    fn check_binding(public: &TpmtPublic, sensitive: &TpmtSensitive) -> Result<(), TpmRcError> {
+        let binding = TpmRcError::BindingFor(IN_PRIVATE.0, IN_PRIVATE.1);
+        match (&public.parms_and_id, &sensitive.sensitive) {
+            (PublicParmsAndId::Ecc(parms, point), TpmuSensitiveComposite::Ecc(d)) => {
+                let curve = EccCurve::find(parms.curve_id.0)
+                    .ok_or(TpmRcError::CurveFor(IN_PUBLIC.0, IN_PUBLIC.1))?;
+                let d = EccInteger::new(curve, d.get_buffer())
+                    .filter(|d| curve.is_valid_scalar(d))
+                    .ok_or(TpmRcError::KeySizeFor(IN_PRIVATE.0, IN_PRIVATE.1))?;
+                let expected = curve.point_mul::<Deps::Ecc>(&d, &EccPoint::generator(curve))?;
+                if EccPoint::new(curve, point) != Some(expected) {
+                    return Err(binding);
+                }
+            }
+            (PublicParmsAndId::KeyedHash(_, unique), TpmuSensitiveComposite::Bits(bits)) => {
+                Self::check_unique(public, sensitive, bits.get_buffer(), unique.get_buffer())?
+            }
+            (PublicParmsAndId::Sym(parms, unique), TpmuSensitiveComposite::Sym(key)) => {
+                if let TpmtSymDefObject::Aes(key_bits, _) = parms.sym {
+                    if key.get_size() as usize * 8 != key_bits.0 as usize {
+                        return Err(TpmRcError::KeySizeFor(IN_PRIVATE.0, IN_PRIVATE.1));
+                    }
+                }
+                Self::check_unique(public, sensitive, key.get_buffer(), unique.get_buffer())?
+            }
+            (PublicParmsAndId::Rsa(parms, n), TpmuSensitiveComposite::Rsa(prime)) => {
+                if prime.get_size() as usize * 16 != parms.key_bits.0 as usize {
+                    return Err(TpmRcError::KeySizeFor(IN_PRIVATE.0, IN_PRIVATE.1));
+                }
+                let key = RsaKey::new(parms, n.get_buffer(), Some(prime.get_buffer()));
+                Deps::Rsa::check_key(key.n, key.e, prime.get_buffer()).or(Err(binding))?;
+            }
+            _ => return Err(TpmRcError::TypeFor(IN_PRIVATE.0, IN_PRIVATE.1)),
+        }
+        Ok(())
+    }
+
+    /// Checks that the unique field of a symmetric object is `H(seedValue || sensitive)`.
+    fn check_unique(
+        public: &TpmtPublic,
+        sensitive: &TpmtSensitive,
+        secret: &[u8],
+        unique: &[u8],
+    ) -> Result<(), TpmRcError> {
+        let expected = digest::<Deps::Hash>(
+            public.name_alg,
+            &[sensitive.seed_value.get_buffer(), secret],
+        )?;
+        if expected.as_ref() != unique {
+            return Err(TpmRcError::BindingFor(IN_PRIVATE.0, IN_PRIVATE.1));
+        }
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::LoadExternal] (`0x167`) command.
+    pub fn load_external(
+        &mut self,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: LoadExternalCmd = request.unmarshal()?;
+        let public = command.in_public.to_struct().map_err(unmarshal_error)?;
+        let sensitive = if command.in_private.get_size() == 0 {
+            None
+        } else {
+            Some(command.in_private.to_struct().map_err(unmarshal_error)?)
+        };
+
+        match command.hierarchy {
+            TpmHandle::RHOwner
+            | TpmHandle::RHEndorsement
+            | TpmHandle::RHPlatform
+            | TpmHandle::RHNull => {}
+            _ => return Err(TpmRcError::ValueFor(HIERARCHY.0, HIERARCHY.1)),
+        }
+        Self::check_public(&public)?;
+        if let Some(sensitive) = &sensitive {
+            // An external private key can't be vouched for by any hierarchy of this TPM.
+            if command.hierarchy != TpmHandle::RHNull {
+                return Err(TpmRcError::HierarchyFor(HIERARCHY.0, HIERARCHY.1));
+            }
+            if public
+                .object_attributes
+                .intersects(TpmaObject::FIXED_TPM | TpmaObject::FIXED_PARENT)
+            {
+                return Err(TpmRcError::AttributesFor(IN_PUBLIC.0, IN_PUBLIC.1));
+            }
+            Self::check_binding(&public, sensitive)?;
+        }
+
+        let name = compute_name::<Deps::Hash>(&public)?;
+        let qualified_name = compute_qualified_name::<Deps::Hash>(
+            public.name_alg,
+            &command.hierarchy.0.to_be_bytes(),
+            &name,
+        )?;
+        let handle = self.objects.insert(Object {
+            public,
+            sensitive,
+            name,
+            qualified_name,
+            hierarchy: command.hierarchy,
+        })?;
+        let mut response = request.into_response();
+        response.write_handle(0, handle)?;
+        response.marshal(&LoadExternalResp { name })
+    }
+}
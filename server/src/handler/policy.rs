@@ -0,0 +1,594 @@
+use core::mem::size_of;
+use tpm2_rs_base::commands::{
+    PolicyAuthValueCmd, PolicyAuthorizeCmd, PolicyCommandCodeCmd, PolicyCounterTimerCmd,
+    PolicyCpHashCmd, PolicyGetDigestCmd, PolicyGetDigestResp, PolicyLocalityCmd, PolicyNameHashCmd,
+    PolicyOrCmd, PolicyPasswordCmd, PolicyPcrCmd, PolicyRestartCmd, PolicySecretCmd,
+    PolicySecretResp, PolicySignedCmd, PolicySignedResp,
+};
+use tpm2_rs_base::constants::{TpmCc, TpmHandle, TpmSt};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::marshal::Marshalable;
+use tpm2_rs_base::{
+    Tpm2bDigest, Tpm2bName, Tpm2bNonce, Tpm2bSimple, Tpm2bTimeout, TpmaLocality, TpmiAlgHash,
+    TpmlPcrSelection, TpmsTimeInfo, TpmtTkAuth,
+};
+
+use crate::{
+    command::CommandAttributes,
+    crypto::{
+        algorithms::{check_hash, ErrorAt},
+        hash::{digest, digest_size, Hasher},
+    },
+    handler::{signature::signature_hash, CommandHandler},
+    pcr::Pcrs,
+    platform::{crypto::Hash, TpmBuffers, TpmContextDeps},
+    policy::{compare, zero_digest, PolicyState},
+    req_resp::RequestThenResponse,
+    session::{Session, SessionSlots},
+};
+
+const HANDLE_1: ErrorAt = (ErrorType::Handle, ErrorPosition::Pos1);
+const HANDLE_2: ErrorAt = (ErrorType::Handle, ErrorPosition::Pos2);
+const PARAMETER_1: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos1);
+const PARAMETER_2: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos2);
+const PARAMETER_3: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos3);
+const PARAMETER_4: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos4);
+const PARAMETER_5: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos5);
+
+/// Gets the policy or trial session referenced by `handle`.
+fn policy_session(
+    sessions: &mut SessionSlots,
+    handle: TpmHandle,
+    at: ErrorAt,
+) -> Result<&mut Session, TpmRcError> {
+    sessions
+        .get_mut(handle)
+        .filter(|session| session.is_policy())
+        .ok_or(TpmRcError::HandleFor(at.0, at.1))
+}
+
+/// Checks the `nonceTPM`, `cpHashA` and `expiration` parameters of TPM2_PolicySigned and
+/// TPM2_PolicySecret, which are the first, second and fourth parameter of both. Returns the
+/// value of `Time` at which the authorization expires, if any.
+fn auth_timeout(
+    session: &Session,
+    now: u64,
+    nonce_tpm: &Tpm2bNonce,
+    cp_hash_a: &Tpm2bDigest,
+    expiration: i32,
+) -> Result<Option<u64>, TpmRcError> {
+    let has_nonce = nonce_tpm.get_size() != 0;
+    if has_nonce && *nonce_tpm != session.nonce_tpm {
+        return Err(TpmRcError::NonceFor(PARAMETER_1.0, PARAMETER_1.1));
+    }
+    if cp_hash_a.get_size() != 0
+        && digest_size(session.auth_hash) != Some(cp_hash_a.get_size() as usize)
+    {
+        return Err(TpmRcError::SizeFor(PARAMETER_2.0, PARAMETER_2.1));
+    }
+    if expiration == 0 {
+        return Ok(None);
+    }
+    // The expiration is relative to the start of the policy if the authorization is bound to the
+    // session by its nonce, and to now otherwise.
+    let start = if has_nonce {
+        session.policy.start_time
+    } else {
+        now
+    };
+    let timeout = start + expiration.unsigned_abs() as u64 * 1000;
+    if timeout < now {
+        return Err(TpmRcError::ExpiredFor(PARAMETER_4.0, PARAMETER_4.1));
+    }
+    Ok(Some(timeout))
+}
+
+/// Updates the policy of `session` with an authorization by the entity with `name`, as
+/// TPM2_PolicySigned and TPM2_PolicySecret do.
+fn update_auth_policy<H: Hash>(
+    session: &mut Session,
+    command_code: TpmCc,
+    name: &Tpm2bName,
+    policy_ref: &Tpm2bNonce,
+    cp_hash_a: &Tpm2bDigest,
+    timeout: Option<u64>,
+) -> Result<(), TpmRcError> {
+    if cp_hash_a.get_size() != 0 {
+        session.policy.set_cp_hash(cp_hash_a)?;
+    }
+    if let Some(timeout) = timeout {
+        session.policy.set_timeout(timeout);
+    }
+    let alg = session.auth_hash;
+    session
+        .policy
+        .extend::<H>(alg, &[&command_code.0.to_be_bytes(), name.get_buffer()])?;
+    session.policy.extend::<H>(alg, &[policy_ref.get_buffer()])
+}
+
+/// Returns the ticket of TPM2_PolicySigned and TPM2_PolicySecret. TPM2_PolicyTicket is not
+/// supported, so it is always the NULL ticket.
+fn null_auth_ticket(tag: TpmSt) -> TpmtTkAuth {
+    TpmtTkAuth {
+        tag,
+        hierarchy: TpmHandle::RHNull,
+        digest: Tpm2bDigest::default(),
+    }
+}
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Returns the current value of `Time`, which the timeouts of policy sessions refer to.
+    pub fn time(&mut self) -> u64 {
+        self.time.time()
+    }
+
+    /// Returns the name algorithm and authorization policy of the entity referenced by `handle`,
+    /// or `None` if the entity has no policy.
+    pub fn auth_policy(&self, handle: TpmHandle) -> Option<(TpmiAlgHash, &Tpm2bDigest)> {
+        // Hierarchy policies can't be set yet, so only objects have policies.
+        let object = self.objects.get(handle)?;
+        let policy = &object.public.auth_policy;
+        (policy.get_size() != 0).then_some((object.public.name_alg, policy))
+    }
+
+    /// Starts the policy of the policy session referenced by `handle` over, which happens after
+    /// TPM2_PolicyRestart and after the session authorized a command.
+    pub fn restart_policy(&mut self, handle: TpmHandle) -> Result<(), TpmRcError> {
+        let now = self.time.time();
+        let session = self.sessions.get_mut(handle).ok_or(TpmRcError::Failure)?;
+        session.policy = PolicyState::new(session.auth_hash, now)?;
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicySigned] (`0x160`) command.
+    pub fn policy_signed(
+        &mut self,
+        auth_object: TpmHandle,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicySignedCmd = request.unmarshal()?;
+        let now = self.time.time();
+        let object = self
+            .objects
+            .get(auth_object)
+            .ok_or(TpmRcError::HandleFor(HANDLE_1.0, HANDLE_1.1))?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_2)?;
+        let timeout = auth_timeout(
+            session,
+            now,
+            &command.nonce_tpm,
+            &command.cp_hash_a,
+            command.expiration,
+        )?;
+        // Trial sessions only compute the policy, so the signature is not checked.
+        if !session.is_trial() {
+            let hash_alg = signature_hash(&command.auth)
+                .ok_or(TpmRcError::SchemeFor(PARAMETER_5.0, PARAMETER_5.1))?;
+            check_hash(hash_alg, PARAMETER_5)?;
+            let a_hash = digest::<Deps::Hash>(
+                hash_alg,
+                &[
+                    command.nonce_tpm.get_buffer(),
+                    &command.expiration.to_be_bytes(),
+                    command.cp_hash_a.get_buffer(),
+                    command.policy_ref.get_buffer(),
+                ],
+            )?;
+            if !Self::check_signature(
+                object,
+                a_hash.as_ref(),
+                &command.auth,
+                HANDLE_1,
+                PARAMETER_5,
+            )? {
+                return Err(TpmRcError::SignatureFor(PARAMETER_5.0, PARAMETER_5.1));
+            }
+        }
+        update_auth_policy::<Deps::Hash>(
+            session,
+            TpmCc::PolicySigned,
+            &object.name,
+            &command.policy_ref,
+            &command.cp_hash_a,
+            timeout,
+        )?;
+        let mut response = request.into_response();
+        response.marshal(&PolicySignedResp {
+            timeout: Tpm2bTimeout::default(),
+            policy_ticket: null_auth_ticket(TpmSt::AuthSigned),
+        })
+    }
+
+    /// Handles the [TpmCc::PolicySecret] (`0x151`) command.
+    pub fn policy_secret(
+        &mut self,
+        auth_handle: TpmHandle,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicySecretCmd = request.unmarshal()?;
+        let now = self.time.time();
+        // The authorization of the entity was already checked.
+        let name = self.entity_name(auth_handle)?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_2)?;
+        let timeout = auth_timeout(
+            session,
+            now,
+            &command.nonce_tpm,
+            &command.cp_hash_a,
+            command.expiration,
+        )?;
+        update_auth_policy::<Deps::Hash>(
+            session,
+            TpmCc::PolicySecret,
+            &name,
+            &command.policy_ref,
+            &command.cp_hash_a,
+            timeout,
+        )?;
+        let mut response = request.into_response();
+        response.marshal(&PolicySecretResp {
+            timeout: Tpm2bTimeout::default(),
+            policy_ticket: null_auth_ticket(TpmSt::AuthSecret),
+        })
+    }
+
+    /// Handles the [TpmCc::PolicyOR] (`0x171`) command.
+    pub fn policy_or(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicyOrCmd = request.unmarshal()?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        let digests = command.p_hash_list.digests();
+        if digests.len() < 2 {
+            return Err(TpmRcError::ValueFor(PARAMETER_1.0, PARAMETER_1.1));
+        }
+        if !session.is_trial() && !digests.contains(&session.policy.digest) {
+            return Err(TpmRcError::ValueFor(PARAMETER_1.0, PARAMETER_1.1));
+        }
+        // The policy starts over from a Zero Digest extended with all alternatives.
+        let alg = session.auth_hash;
+        let mut hasher = Hasher::<Deps::Hash>::start(alg)?;
+        hasher.update(zero_digest(alg)?.get_buffer());
+        hasher.update(&TpmCc::PolicyOR.0.to_be_bytes());
+        for digest in digests {
+            hasher.update(digest.get_buffer());
+        }
+        session.policy.digest =
+            Tpm2bDigest::from_bytes(hasher.finish().as_ref()).or(Err(TpmRcError::Failure))?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyPCR] (`0x17F`) command.
+    pub fn policy_pcr(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicyPcrCmd = request.unmarshal()?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        let alg = session.auth_hash;
+        let selection = Pcrs::filter(&command.pcrs)?;
+        let pcr_digest = self.pcrs.digest::<Deps::Hash>(alg, &selection)?;
+        // A trial session takes the digest of the caller, which need not match the current PCRs.
+        let digest = if command.pcr_digest.get_size() == 0 {
+            pcr_digest
+        } else if session.is_trial() || command.pcr_digest == pcr_digest {
+            command.pcr_digest
+        } else {
+            return Err(TpmRcError::ValueFor(PARAMETER_1.0, PARAMETER_1.1));
+        };
+        let mut pcrs = [0; size_of::<TpmlPcrSelection>()];
+        let pcrs_size = command
+            .pcrs
+            .try_marshal(&mut pcrs)
+            .or(Err(TpmRcError::Failure))?;
+        session.policy.extend::<Deps::Hash>(
+            alg,
+            &[
+                &TpmCc::PolicyPCR.0.to_be_bytes(),
+                &pcrs[..pcrs_size],
+                digest.get_buffer(),
+            ],
+        )?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyLocality] (`0x16F`) command.
+    pub fn policy_locality(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicyLocalityCmd = request.unmarshal()?;
+        let range = TpmRcError::RangeFor(PARAMETER_1.0, PARAMETER_1.1);
+        let locality = command.locality;
+        if locality.0 == 0 {
+            return Err(range);
+        }
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        // Localities can only be narrowed down, and an extended locality only be repeated.
+        let allowed = match session.policy.locality {
+            None => locality,
+            Some(previous) if previous.is_extended() || locality.is_extended() => {
+                if previous != locality {
+                    return Err(range);
+                }
+                locality
+            }
+            Some(previous) => match TpmaLocality(previous.0 & locality.0) {
+                TpmaLocality(0) => return Err(range),
+                allowed => allowed,
+            },
+        };
+        session.policy.locality = Some(allowed);
+        let alg = session.auth_hash;
+        session.policy.extend::<Deps::Hash>(
+            alg,
+            &[&TpmCc::PolicyLocality.0.to_be_bytes(), &[locality.0]],
+        )?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyCounterTimer] (`0x16D`) command.
+    pub fn policy_counter_timer(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicyCounterTimerCmd = request.unmarshal()?;
+        let mut time_info = [0; size_of::<TpmsTimeInfo>()];
+        let time_info_size = self
+            .time
+            .time_info()
+            .try_marshal(&mut time_info)
+            .or(Err(TpmRcError::Failure))?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        let operand_b = command.operand_b.get_buffer();
+        let offset = command.offset as usize;
+        let operand_a = time_info[..time_info_size]
+            .get(offset..offset + operand_b.len())
+            .ok_or(TpmRcError::RangeFor(PARAMETER_2.0, PARAMETER_2.1))?;
+        let result = compare(operand_a, operand_b, command.operation)
+            .ok_or(TpmRcError::ValueFor(PARAMETER_3.0, PARAMETER_3.1))?;
+        if !session.is_trial() && !result {
+            return Err(TpmRcError::Policy);
+        }
+        let alg = session.auth_hash;
+        let args = digest::<Deps::Hash>(
+            alg,
+            &[
+                operand_b,
+                &command.offset.to_be_bytes(),
+                &command.operation.0.to_be_bytes(),
+            ],
+        )?;
+        session.policy.extend::<Deps::Hash>(
+            alg,
+            &[&TpmCc::PolicyCounterTimer.0.to_be_bytes(), args.as_ref()],
+        )?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyCommandCode] (`0x16C`) command.
+    pub fn policy_command_code(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicyCommandCodeCmd = request.unmarshal()?;
+        if CommandAttributes::lookup(command.code).is_none() {
+            return Err(TpmRcError::PolicyCcFor(PARAMETER_1.0, PARAMETER_1.1));
+        }
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        if session
+            .policy
+            .command_code
+            .is_some_and(|code| code != command.code)
+        {
+            return Err(TpmRcError::ValueFor(PARAMETER_1.0, PARAMETER_1.1));
+        }
+        session.policy.command_code = Some(command.code);
+        let alg = session.auth_hash;
+        session.policy.extend::<Deps::Hash>(
+            alg,
+            &[
+                &TpmCc::PolicyCommandCode.0.to_be_bytes(),
+                &command.code.0.to_be_bytes(),
+            ],
+        )?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyCpHash] (`0x16E`) command.
+    pub fn policy_cp_hash(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicyCpHashCmd = request.unmarshal()?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        let alg = session.auth_hash;
+        if digest_size(alg) != Some(command.cp_hash_a.get_size() as usize) {
+            return Err(TpmRcError::SizeFor(PARAMETER_1.0, PARAMETER_1.1));
+        }
+        session.policy.set_cp_hash(&command.cp_hash_a)?;
+        session.policy.extend::<Deps::Hash>(
+            alg,
+            &[
+                &TpmCc::PolicyCpHash.0.to_be_bytes(),
+                command.cp_hash_a.get_buffer(),
+            ],
+        )?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyNameHash] (`0x170`) command.
+    pub fn policy_name_hash(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicyNameHashCmd = request.unmarshal()?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        let alg = session.auth_hash;
+        if digest_size(alg) != Some(command.name_hash.get_size() as usize) {
+            return Err(TpmRcError::SizeFor(PARAMETER_1.0, PARAMETER_1.1));
+        }
+        if session.policy.cp_hash.is_some() || session.policy.name_hash.is_some() {
+            return Err(TpmRcError::CpHash);
+        }
+        session.policy.name_hash = Some(command.name_hash);
+        session.policy.extend::<Deps::Hash>(
+            alg,
+            &[
+                &TpmCc::PolicyNameHash.0.to_be_bytes(),
+                command.name_hash.get_buffer(),
+            ],
+        )?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyAuthorize] (`0x16A`) command.
+    pub fn policy_authorize(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: PolicyAuthorizeCmd = request.unmarshal()?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        if !session.is_trial() {
+            if command.approved_policy != session.policy.digest {
+                return Err(TpmRcError::ValueFor(PARAMETER_1.0, PARAMETER_1.1));
+            }
+            // The approval is signed with the name algorithm of the key.
+            let key_sign = command.key_sign.get_buffer();
+            let name_alg = match key_sign {
+                [high, low, digest @ ..] => Some(TpmiAlgHash(u16::from_be_bytes([*high, *low])))
+                    .filter(|alg| digest_size(*alg) == Some(digest.len())),
+                _ => None,
+            }
+            .ok_or(TpmRcError::HashFor(PARAMETER_3.0, PARAMETER_3.1))?;
+            let a_hash = digest::<Deps::Hash>(
+                name_alg,
+                &[
+                    command.approved_policy.get_buffer(),
+                    command.policy_ref.get_buffer(),
+                ],
+            )?;
+            let ticket = command.check_ticket;
+            let expected = self.proofs.verified_ticket::<Deps::Hash>(
+                ticket.hierarchy,
+                a_hash.as_ref(),
+                &command.key_sign,
+            )?;
+            if ticket.tag != TpmSt::Verified
+                || expected.digest.get_size() == 0
+                || expected.digest != ticket.digest
+            {
+                return Err(TpmRcError::TicketFor(PARAMETER_4.0, PARAMETER_4.1));
+            }
+        }
+        let alg = session.auth_hash;
+        session.policy.digest = zero_digest(alg)?;
+        session.policy.extend::<Deps::Hash>(
+            alg,
+            &[
+                &TpmCc::PolicyAuthorize.0.to_be_bytes(),
+                command.key_sign.get_buffer(),
+            ],
+        )?;
+        session
+            .policy
+            .extend::<Deps::Hash>(alg, &[command.policy_ref.get_buffer()])?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyAuthValue] (`0x16B`) command.
+    pub fn policy_auth_value(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let _command: PolicyAuthValueCmd = request.unmarshal()?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        session.policy.auth_value_needed = true;
+        session.policy.password_needed = false;
+        let alg = session.auth_hash;
+        session
+            .policy
+            .extend::<Deps::Hash>(alg, &[&TpmCc::PolicyAuthValue.0.to_be_bytes()])?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyPassword] (`0x18C`) command.
+    pub fn policy_password(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let _command: PolicyPasswordCmd = request.unmarshal()?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        session.policy.password_needed = true;
+        session.policy.auth_value_needed = false;
+        // The policy is the same as that of TPM2_PolicyAuthValue, so either can satisfy it.
+        let alg = session.auth_hash;
+        session
+            .policy
+            .extend::<Deps::Hash>(alg, &[&TpmCc::PolicyAuthValue.0.to_be_bytes()])?;
+        request.into_response();
+        Ok(())
+    }
+
+    /// Handles the [TpmCc::PolicyGetDigest] (`0x189`) command.
+    pub fn policy_get_digest(
+        &mut self,
+        policy_session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let _command: PolicyGetDigestCmd = request.unmarshal()?;
+        let session = policy_session(&mut self.sessions, policy_session_handle, HANDLE_1)?;
+        let policy_digest = session.policy.digest;
+        let mut response = request.into_response();
+        response.marshal(&PolicyGetDigestResp { policy_digest })
+    }
+
+    /// Handles the [TpmCc::PolicyRestart] (`0x180`) command.
+    pub fn policy_restart(
+        &mut self,
+        session_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let _command: PolicyRestartCmd = request.unmarshal()?;
+        policy_session(&mut self.sessions, session_handle, HANDLE_1)?;
+        self.restart_policy(session_handle)?;
+        request.into_response();
+        Ok(())
+    }
+}
@@ -1,39 +1,32 @@
 use tpm2_rs_base::errors::TpmRcError;
 
 use crate::{
+    crypto::hash::MAX_DIGEST_SIZE,
     handler::CommandHandler,
-    platform::{
-        crypto::{Drbg, EntropySource},
-        TpmBuffers, TpmContextDeps,
-    },
+    platform::{TpmBuffers, TpmContextDeps},
     req_resp::RequestThenResponse,
-    ServerError,
 };
 
 impl<Deps: TpmContextDeps> CommandHandler<Deps> {
-    fn try_get_random(&mut self, buffer: &mut [u8]) -> Result<(), ServerError> {
-        if self.crypto.drbg.requires_reseeding() {
-            let mut seed = <Deps::Drbg as Drbg>::Entropy::default();
-            self.crypto.entropy.fill_entropy(seed.as_mut());
-            self.crypto.drbg.reseed(&seed, &[])?;
-        }
-        self.crypto.drbg.fill_bytes(&[], buffer).map_err(Into::into)
-    }
-
     fn get_random_or_faiure_mode(&mut self, buffer: &mut [u8]) {
-        if self.try_get_random(buffer).is_err() {
+        if self.crypto.fill_random(buffer).is_err() {
             todo!() // goto failure mode
         }
     }
-    /// Handles the [TpmCc::GetRandom] (`0x17B`) command.
+    /// Handles the [TpmCc::GetRandom] (`0x17B`) command. At most as many bytes as the largest
+    /// digest are returned, like [TPM2.0 1.83] Part 3, 16.1 allows.
     pub fn get_random(
         &mut self,
         request_response: RequestThenResponse<impl TpmBuffers>,
     ) -> Result<(), TpmRcError> {
         let mut request = request_response;
         let requested_bytes = request.read_be_u16().ok_or(TpmRcError::CommandSize)? as usize;
+        let requested_bytes = requested_bytes.min(MAX_DIGEST_SIZE);
 
         let mut response = request.into_response();
+        response
+            .write(&(requested_bytes as u16).to_be_bytes())
+            .map_err(|_| TpmRcError::Memory)?;
         response
             .write_callback(requested_bytes, |buffer| {
                 self.get_random_or_faiure_mode(buffer)
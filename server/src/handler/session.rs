@@ -0,0 +1,238 @@
+use tpm2_rs_base::commands::{StartAuthSessionCmd, StartAuthSessionResp};
+use tpm2_rs_base::constants::{TpmHandle, TpmSe};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bAuth, Tpm2bDigest, Tpm2bNonce, Tpm2bSimple, TpmaObject, TpmiAlgHash,
+    TpmsEccPoint, TpmuSensitiveComposite,
+};
+
+use crate::{
+    crypto::{
+        algorithms::{check_session_symmetric, ErrorAt},
+        ecc::{EccCurve, EccInteger, EccPoint},
+        hash::{digest_size, MAX_DIGEST_SIZE},
+        kdf::{kdfa, kdfe},
+        rsa::RsaKey,
+    },
+    handler::CommandHandler,
+    platform::{TpmBuffers, TpmContextDeps},
+    policy::PolicyState,
+    req_resp::RequestThenResponse,
+    session::{Session, MIN_NONCE_SIZE},
+};
+
+const TPM_KEY: ErrorAt = (ErrorType::Handle, ErrorPosition::Pos1);
+const ENCRYPTED_SALT: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos2);
+
+/// The label that salts are encrypted with, as described in [TPM2.0 1.83] Part 1, 11.4.9.2.
+const SECRET_LABEL: &[u8] = b"SECRET\0";
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Generates a random nonce as large as the digests of `alg`.
+    pub fn random_nonce(&mut self, alg: TpmiAlgHash) -> Result<Tpm2bNonce, TpmRcError> {
+        let mut nonce = [0; MAX_DIGEST_SIZE];
+        let nonce = &mut nonce[..digest_size(alg).ok_or(TpmRcError::Hash)?];
+        self.crypto
+            .fill_random(nonce)
+            .or(Err(TpmRcError::Failure))?;
+        Tpm2bNonce::from_bytes(nonce).or(Err(TpmRcError::Failure))
+    }
+
+    /// Gets the session referenced by `handle`, if any.
+    pub fn session(&self, handle: TpmHandle) -> Option<&Session> {
+        self.sessions.get(handle)
+    }
+
+    /// Gets the session referenced by `handle` for modification, if any.
+    pub fn session_mut(&mut self, handle: TpmHandle) -> Option<&mut Session> {
+        self.sessions.get_mut(handle)
+    }
+
+    /// Ends the session referenced by `handle`.
+    pub fn end_session(&mut self, handle: TpmHandle) {
+        self.sessions.remove(handle);
+    }
+
+    /// Returns whether the session referenced by `handle` audited every command since its audit
+    /// digest was reset.
+    pub fn is_exclusive_audit(&self, handle: TpmHandle) -> bool {
+        self.sessions.is_exclusive_audit(handle)
+    }
+
+    /// Updates the exclusive audit session after a successful command. `audit_session` is the
+    /// handle of the session that audited the command, if any, and whether its audit digest is
+    /// reset. Any command that is not audited by the exclusive session ends its exclusivity.
+    pub fn update_audit_exclusivity(
+        &mut self,
+        audit_session: Option<(TpmHandle, bool)>,
+    ) -> Result<(), TpmRcError> {
+        match audit_session {
+            Some((handle, true)) => {
+                self.sessions
+                    .get_mut(handle)
+                    .ok_or(TpmRcError::Failure)?
+                    .reset_audit()?;
+                self.sessions.set_exclusive_audit(Some(handle));
+            }
+            Some((handle, false)) if self.sessions.is_exclusive_audit(handle) => {}
+            _ => self.sessions.set_exclusive_audit(None),
+        }
+        Ok(())
+    }
+
+    /// Recovers the salt of a session from `encrypted_salt` with the decryption key at `tpm_key`,
+    /// as described in [TPM2.0 1.83] Part 1, 11.4.9.2 and C.6.1. Returns the size of the salt.
+    fn decrypt_salt(
+        &mut self,
+        tpm_key: TpmHandle,
+        encrypted_salt: &[u8],
+        salt: &mut [u8; MAX_DIGEST_SIZE],
+    ) -> Result<usize, TpmRcError> {
+        let object = self
+            .objects
+            .get(tpm_key)
+            .ok_or(TpmRcError::HandleFor(TPM_KEY.0, TPM_KEY.1))?;
+        let public = &object.public;
+        if !public.object_attributes.contains(TpmaObject::DECRYPT) {
+            return Err(TpmRcError::AttributesFor(TPM_KEY.0, TPM_KEY.1));
+        }
+        let sensitive = object
+            .sensitive
+            .as_ref()
+            .ok_or(TpmRcError::HandleFor(TPM_KEY.0, TPM_KEY.1))?;
+        let invalid = TpmRcError::ValueFor(ENCRYPTED_SALT.0, ENCRYPTED_SALT.1);
+        let salt_size = digest_size(public.name_alg).ok_or(TpmRcError::Failure)?;
+        match (&public.parms_and_id, &sensitive.sensitive) {
+            (PublicParmsAndId::Rsa(parms, n), TpmuSensitiveComposite::Rsa(prime)) => {
+                let key = RsaKey::new(parms, n.get_buffer(), Some(prime.get_buffer()));
+                self.crypto
+                    .rsaes_oaep_decrypt(&key, public.name_alg, SECRET_LABEL, encrypted_salt, salt)
+                    .or(Err(invalid))
+            }
+            (PublicParmsAndId::Ecc(parms, q), TpmuSensitiveComposite::Ecc(d)) => {
+                // The key was validated when it was loaded.
+                let curve = EccCurve::find(parms.curve_id.0).ok_or(TpmRcError::Failure)?;
+                let d = EccInteger::new(curve, d.get_buffer()).ok_or(TpmRcError::Failure)?;
+                let ephemeral = TpmsEccPoint::try_unmarshal(&mut UnmarshalBuf::new(encrypted_salt))
+                    .or(Err(invalid))?;
+                let point = EccPoint::new(curve, &ephemeral)
+                    .ok_or(TpmRcError::EccPointFor(ENCRYPTED_SALT.0, ENCRYPTED_SALT.1))?;
+                curve
+                    .validate_point::<Deps::Ecc>(&point)
+                    .or(Err(TpmRcError::EccPointFor(
+                        ENCRYPTED_SALT.0,
+                        ENCRYPTED_SALT.1,
+                    )))?;
+                let z = curve.point_mul::<Deps::Ecc>(&d, &point)?;
+                kdfe::<Deps::Hash>(
+                    public.name_alg,
+                    z.x.as_ref(),
+                    SECRET_LABEL,
+                    ephemeral.x.get_buffer(),
+                    q.x.get_buffer(),
+                    &mut salt[..salt_size],
+                )?;
+                Ok(salt_size)
+            }
+            _ => Err(TpmRcError::KeyFor(TPM_KEY.0, TPM_KEY.1)),
+        }
+    }
+
+    /// Handles the [TpmCc::StartAuthSession] (`0x176`) command.
+    pub fn start_auth_session(
+        &mut self,
+        tpm_key: TpmHandle,
+        bind: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: StartAuthSessionCmd = request.unmarshal()?;
+        let mut salt = [0; MAX_DIGEST_SIZE];
+        let salt = match (tpm_key, command.encrypted_salt.get_size()) {
+            (TpmHandle::RHNull, 0) => &[][..],
+            (TpmHandle::RHNull, _) => {
+                return Err(TpmRcError::ValueFor(ENCRYPTED_SALT.0, ENCRYPTED_SALT.1))
+            }
+            (_, 0) => return Err(TpmRcError::HandleFor(TPM_KEY.0, TPM_KEY.1)),
+            (tpm_key, _) => {
+                let size =
+                    self.decrypt_salt(tpm_key, command.encrypted_salt.get_buffer(), &mut salt)?;
+                &salt[..size]
+            }
+        };
+        if ![TpmSe::HMAC, TpmSe::Policy, TpmSe::Trial].contains(&command.session_type) {
+            return Err(TpmRcError::ValueFor(
+                ErrorType::Parameter,
+                ErrorPosition::Pos3,
+            ));
+        }
+        check_session_symmetric(
+            &command.symmetric,
+            (ErrorType::Parameter, ErrorPosition::Pos4),
+        )?;
+        let digest_size = digest_size(command.auth_hash).ok_or(TpmRcError::HashFor(
+            ErrorType::Parameter,
+            ErrorPosition::Pos5,
+        ))?;
+        let nonce_caller = command.nonce_caller.get_buffer();
+        if nonce_caller.len() < MIN_NONCE_SIZE || nonce_caller.len() > digest_size {
+            return Err(TpmRcError::SizeFor(
+                ErrorType::Parameter,
+                ErrorPosition::Pos1,
+            ));
+        }
+
+        let bind = match bind {
+            TpmHandle::RHNull => None,
+            bind => {
+                let name = self.entity_name(bind)?;
+                let (auth_value, _) = self.auth_value(bind).or(Err(TpmRcError::HandleFor(
+                    ErrorType::Handle,
+                    ErrorPosition::Pos2,
+                )))?;
+                let auth_value = Tpm2bAuth::from_bytes(auth_value).or(Err(TpmRcError::Failure))?;
+                Some((name, auth_value))
+            }
+        };
+        let nonce_tpm = self.random_nonce(command.auth_hash)?;
+
+        // The session key is derived from the bind authorization value and the salt. It is empty
+        // if there is neither.
+        let bind_auth = bind.as_ref().map_or(&[][..], |(_, auth)| auth.get_buffer());
+        let session_key = if bind_auth.is_empty() && salt.is_empty() {
+            Tpm2bDigest::default()
+        } else {
+            let mut key = [0; 2 * MAX_DIGEST_SIZE];
+            key[..bind_auth.len()].copy_from_slice(bind_auth);
+            key[bind_auth.len()..][..salt.len()].copy_from_slice(salt);
+            let mut session_key = [0; MAX_DIGEST_SIZE];
+            let session_key = &mut session_key[..digest_size];
+            kdfa::<Deps::Hash>(
+                command.auth_hash,
+                &key[..bind_auth.len() + salt.len()],
+                b"ATH",
+                nonce_tpm.get_buffer(),
+                nonce_caller,
+                session_key,
+            )?;
+            Tpm2bDigest::from_bytes(session_key).or(Err(TpmRcError::Failure))?
+        };
+        let mut session = Session {
+            session_type: command.session_type,
+            auth_hash: command.auth_hash,
+            symmetric: command.symmetric,
+            session_key,
+            nonce_tpm,
+            bind,
+            audit_digest: Tpm2bDigest::default(),
+            policy: PolicyState::new(command.auth_hash, self.time.time())?,
+        };
+        session.reset_audit()?;
+        let session_handle = self.sessions.insert(session)?;
+
+        let mut response = request.into_response();
+        response.write_handle(0, session_handle)?;
+        response.marshal(&StartAuthSessionResp { nonce_tpm })
+    }
+}
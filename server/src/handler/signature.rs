@@ -0,0 +1,375 @@
+use tpm2_rs_base::commands::{SignCmd, SignResp, VerifySignatureCmd, VerifySignatureResp};
+use tpm2_rs_base::constants::{TpmHandle, TpmSt, TPM2_MAX_RSA_KEY_BYTES};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bPublicKeyRsa, Tpm2bSimple, TpmaObject, TpmiAlgHash, TpmsSignatureEcc,
+    TpmsSignatureRsa, TpmtEccScheme, TpmtKeyedHashScheme, TpmtPublic, TpmtRsaScheme, TpmtSigScheme,
+    TpmtSignature, TpmuSensitiveComposite,
+};
+
+use crate::{
+    crypto::{
+        algorithms::{check_hash, ErrorAt},
+        constant_time_eq,
+        ecc::{EccCurve, EccInteger, EccPoint, EccSignature},
+        hash::{digest_size, from_tpmt_ha, to_tpmt_ha},
+        hmac::hmac,
+        rsa::RsaKey,
+        Crypto,
+    },
+    handler::CommandHandler,
+    object::Object,
+    platform::{TpmBuffers, TpmContextDeps},
+    req_resp::RequestThenResponse,
+    ticket::null_verified_ticket,
+};
+
+const KEY_HANDLE: ErrorAt = (ErrorType::Handle, ErrorPosition::Pos1);
+const DIGEST: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos1);
+const IN_SCHEME: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos2);
+const VALIDATION: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos3);
+const SIGNATURE: ErrorAt = (ErrorType::Parameter, ErrorPosition::Pos2);
+
+/// Returns the hash algorithm of a signing scheme, or `None` for schemes this TPM does not
+/// support.
+fn sig_scheme_hash(scheme: &TpmtSigScheme) -> Option<TpmiAlgHash> {
+    match scheme {
+        TpmtSigScheme::Rsassa(details)
+        | TpmtSigScheme::Rsapss(details)
+        | TpmtSigScheme::Ecdsa(details)
+        | TpmtSigScheme::Ecschnorr(details)
+        | TpmtSigScheme::Hmac(details) => Some(details.hash_alg),
+        _ => None,
+    }
+}
+
+/// Returns the hash algorithm of the digest that `signature` signs, or `None` for signatures
+/// this TPM does not support.
+pub fn signature_hash(signature: &TpmtSignature) -> Option<TpmiAlgHash> {
+    match signature {
+        TpmtSignature::Rsassa(sig) | TpmtSignature::Rsapss(sig) => Some(sig.hash),
+        TpmtSignature::Ecdsa(sig) | TpmtSignature::Ecschnorr(sig) => Some(sig.hash),
+        TpmtSignature::Hmac(ha) => Some(from_tpmt_ha(ha).0),
+        _ => None,
+    }
+}
+
+/// Returns the signing scheme of a key, which is `TPM_ALG_NULL` if the key leaves the scheme to
+/// each command.
+fn key_sig_scheme(
+    public: &TpmtPublic,
+    key_at: ErrorAt,
+    at: ErrorAt,
+) -> Result<TpmtSigScheme, TpmRcError> {
+    let scheme = match &public.parms_and_id {
+        PublicParmsAndId::Rsa(parms, _) => match parms.scheme {
+            TpmtRsaScheme::Rsassa(details) => TpmtSigScheme::Rsassa(details),
+            TpmtRsaScheme::Rsapss(details) => TpmtSigScheme::Rsapss(details),
+            TpmtRsaScheme::Null(empty) => TpmtSigScheme::Null(empty),
+            _ => return Err(TpmRcError::SchemeFor(at.0, at.1)),
+        },
+        PublicParmsAndId::Ecc(parms, _) => match parms.scheme {
+            TpmtEccScheme::Ecdsa(details) => TpmtSigScheme::Ecdsa(details),
+            TpmtEccScheme::Ecschnorr(details) => TpmtSigScheme::Ecschnorr(details),
+            TpmtEccScheme::Null(empty) => TpmtSigScheme::Null(empty),
+            _ => return Err(TpmRcError::SchemeFor(at.0, at.1)),
+        },
+        PublicParmsAndId::KeyedHash(parms, _) => match parms.scheme {
+            TpmtKeyedHashScheme::Hmac(details) => TpmtSigScheme::Hmac(details),
+            TpmtKeyedHashScheme::Null(empty) => TpmtSigScheme::Null(empty),
+            _ => return Err(TpmRcError::SchemeFor(at.0, at.1)),
+        },
+        PublicParmsAndId::Sym(..) => return Err(TpmRcError::KeyFor(key_at.0, key_at.1)),
+    };
+    Ok(scheme)
+}
+
+/// Selects the signing scheme from the scheme of the key and the scheme of the command. If the
+/// key has a scheme, the command may only repeat it.
+pub fn select_sig_scheme(
+    public: &TpmtPublic,
+    in_scheme: &TpmtSigScheme,
+    key_at: ErrorAt,
+    at: ErrorAt,
+) -> Result<(TpmtSigScheme, TpmiAlgHash), TpmRcError> {
+    let scheme = match (key_sig_scheme(public, key_at, at)?, in_scheme) {
+        (TpmtSigScheme::Null(_), TpmtSigScheme::Null(_)) => {
+            return Err(TpmRcError::SchemeFor(at.0, at.1))
+        }
+        (TpmtSigScheme::Null(_), scheme) => *scheme,
+        (scheme, TpmtSigScheme::Null(_)) => scheme,
+        (key_scheme, in_scheme) if key_scheme == *in_scheme => key_scheme,
+        _ => return Err(TpmRcError::SchemeFor(at.0, at.1)),
+    };
+    match (&public.parms_and_id, &scheme) {
+        (PublicParmsAndId::Rsa(..), TpmtSigScheme::Rsassa(_) | TpmtSigScheme::Rsapss(_))
+        | (PublicParmsAndId::Ecc(..), TpmtSigScheme::Ecdsa(_) | TpmtSigScheme::Ecschnorr(_))
+        | (PublicParmsAndId::KeyedHash(..), TpmtSigScheme::Hmac(_)) => {}
+        _ => return Err(TpmRcError::SchemeFor(at.0, at.1)),
+    }
+    let hash_alg = sig_scheme_hash(&scheme).ok_or(TpmRcError::SchemeFor(at.0, at.1))?;
+    check_hash(hash_alg, at)?;
+    Ok((scheme, hash_alg))
+}
+
+/// Returns the private part of a loaded key, which is required for signing and for verifying HMAC
+/// signatures.
+fn sensitive(object: &Object, key_at: ErrorAt) -> Result<&TpmuSensitiveComposite, TpmRcError> {
+    object
+        .sensitive
+        .as_ref()
+        .map(|sensitive| &sensitive.sensitive)
+        .ok_or(TpmRcError::KeyFor(key_at.0, key_at.1))
+}
+
+/// Returns the curve and public point of a loaded ECC key.
+fn ecc_key(object: &Object, key_at: ErrorAt) -> Result<(&'static EccCurve, EccPoint), TpmRcError> {
+    let PublicParmsAndId::Ecc(parms, point) = &object.public.parms_and_id else {
+        return Err(TpmRcError::KeyFor(key_at.0, key_at.1));
+    };
+    // The key was validated when it was loaded.
+    let curve = EccCurve::find(parms.curve_id.0).ok_or(TpmRcError::Failure)?;
+    let point = EccPoint::new(curve, point).ok_or(TpmRcError::Failure)?;
+    Ok((curve, point))
+}
+
+/// Returns the RSA key of a loaded object, including its prime factor if it is loaded.
+fn rsa_key(object: &Object, key_at: ErrorAt) -> Result<RsaKey<'_>, TpmRcError> {
+    let PublicParmsAndId::Rsa(parms, n) = &object.public.parms_and_id else {
+        return Err(TpmRcError::KeyFor(key_at.0, key_at.1));
+    };
+    let prime = match object
+        .sensitive
+        .as_ref()
+        .map(|sensitive| &sensitive.sensitive)
+    {
+        Some(TpmuSensitiveComposite::Rsa(prime)) => Some(prime.get_buffer()),
+        _ => None,
+    };
+    Ok(RsaKey::new(parms, n.get_buffer(), prime))
+}
+
+/// Converts the `r` and `s` values of an ECC signature into the TPM representation.
+fn ecc_signature(
+    hash: TpmiAlgHash,
+    signature: EccSignature,
+) -> Result<TpmsSignatureEcc, TpmRcError> {
+    Ok(TpmsSignatureEcc {
+        hash,
+        signature_r: signature.r.to_parameter()?,
+        signature_s: signature.s.to_parameter()?,
+    })
+}
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Signs `digest` with a loaded key using a scheme returned by [`select_sig_scheme`]. Errors
+    /// about the key are reported at `key_at`.
+    pub fn sign_digest(
+        &mut self,
+        key_handle: TpmHandle,
+        key_at: ErrorAt,
+        scheme: &TpmtSigScheme,
+        hash: TpmiAlgHash,
+        digest: &[u8],
+    ) -> Result<TpmtSignature, TpmRcError> {
+        let object = self.objects.get(key_handle).ok_or(TpmRcError::Failure)?;
+        let signature = match scheme {
+            TpmtSigScheme::Rsassa(_) | TpmtSigScheme::Rsapss(_) => {
+                let key = rsa_key(object, key_at)?;
+                if key.prime.is_none() {
+                    return Err(TpmRcError::KeyFor(key_at.0, key_at.1));
+                }
+                let mut sig = [0; TPM2_MAX_RSA_KEY_BYTES as usize];
+                let sig = &mut sig[..key.n.len()];
+                if let TpmtSigScheme::Rsassa(_) = scheme {
+                    self.crypto.rsassa_sign(&key, hash, digest, sig)?;
+                } else {
+                    self.crypto.rsapss_sign(&key, hash, digest, sig)?;
+                }
+                let signature = TpmsSignatureRsa {
+                    hash,
+                    sig: Tpm2bPublicKeyRsa::from_bytes(sig).or(Err(TpmRcError::Failure))?,
+                };
+                match scheme {
+                    TpmtSigScheme::Rsassa(_) => TpmtSignature::Rsassa(signature),
+                    _ => TpmtSignature::Rsapss(signature),
+                }
+            }
+            TpmtSigScheme::Ecdsa(_) | TpmtSigScheme::Ecschnorr(_) => {
+                let (curve, _) = ecc_key(object, key_at)?;
+                let TpmuSensitiveComposite::Ecc(d) = sensitive(object, key_at)? else {
+                    return Err(TpmRcError::KeyFor(key_at.0, key_at.1));
+                };
+                let d = EccInteger::new(curve, d.get_buffer()).ok_or(TpmRcError::Failure)?;
+                if let TpmtSigScheme::Ecdsa(_) = scheme {
+                    let signature = self.crypto.ecdsa_sign(curve, &d, digest)?;
+                    TpmtSignature::Ecdsa(ecc_signature(hash, signature)?)
+                } else {
+                    let signature = self.crypto.ecschnorr_sign(curve, hash, &d, digest)?;
+                    TpmtSignature::Ecschnorr(ecc_signature(hash, signature)?)
+                }
+            }
+            TpmtSigScheme::Hmac(_) => {
+                let TpmuSensitiveComposite::Bits(key) = sensitive(object, key_at)? else {
+                    return Err(TpmRcError::KeyFor(key_at.0, key_at.1));
+                };
+                let hmac = hmac::<Deps::Hash>(hash, key.get_buffer(), &[digest])?;
+                TpmtSignature::Hmac(to_tpmt_ha(hash, hmac.as_ref())?)
+            }
+            // The scheme was checked by `select_sig_scheme`.
+            _ => return Err(TpmRcError::Failure),
+        };
+        Ok(signature)
+    }
+
+    /// Checks `signature` of `digest` with a loaded key. Returns `false` if the signature does
+    /// not match. Errors refer to the key at `key_at` and to the signature at `at`.
+    pub fn check_signature(
+        object: &Object,
+        digest: &[u8],
+        signature: &TpmtSignature,
+        key_at: ErrorAt,
+        at: ErrorAt,
+    ) -> Result<bool, TpmRcError> {
+        let scheme = TpmRcError::SchemeFor(at.0, at.1);
+        match signature {
+            TpmtSignature::Rsassa(sig) | TpmtSignature::Rsapss(sig) => {
+                let PublicParmsAndId::Rsa(..) = object.public.parms_and_id else {
+                    return Err(scheme);
+                };
+                check_hash(sig.hash, at)?;
+                if digest_size(sig.hash) != Some(digest.len()) {
+                    return Ok(false);
+                }
+                let key = rsa_key(object, key_at)?;
+                let sig_bytes = sig.sig.get_buffer();
+                match signature {
+                    TpmtSignature::Rsassa(_) => {
+                        Crypto::<Deps>::rsassa_verify(&key, sig.hash, digest, sig_bytes)
+                    }
+                    _ => Crypto::<Deps>::rsapss_verify(&key, sig.hash, digest, sig_bytes),
+                }
+            }
+            TpmtSignature::Ecdsa(sig) | TpmtSignature::Ecschnorr(sig) => {
+                let PublicParmsAndId::Ecc(..) = object.public.parms_and_id else {
+                    return Err(scheme);
+                };
+                check_hash(sig.hash, at)?;
+                let (curve, q) = ecc_key(object, key_at)?;
+                let (Some(r), Some(s)) = (
+                    EccInteger::new(curve, sig.signature_r.get_buffer()),
+                    EccInteger::new(curve, sig.signature_s.get_buffer()),
+                ) else {
+                    return Ok(false);
+                };
+                let signature_rs = EccSignature { r, s };
+                match signature {
+                    TpmtSignature::Ecdsa(_) => {
+                        Crypto::<Deps>::ecdsa_verify(curve, &q, digest, &signature_rs)
+                    }
+                    _ => {
+                        Crypto::<Deps>::ecschnorr_verify(curve, sig.hash, &q, digest, &signature_rs)
+                    }
+                }
+            }
+            TpmtSignature::Hmac(ha) => {
+                let PublicParmsAndId::KeyedHash(..) = object.public.parms_and_id else {
+                    return Err(scheme);
+                };
+                let (hash, expected) = from_tpmt_ha(ha);
+                check_hash(hash, at)?;
+                let TpmuSensitiveComposite::Bits(key) = sensitive(object, key_at)? else {
+                    return Err(TpmRcError::KeyFor(key_at.0, key_at.1));
+                };
+                let hmac = hmac::<Deps::Hash>(hash, key.get_buffer(), &[digest])?;
+                Ok(constant_time_eq(hmac.as_ref(), expected))
+            }
+            _ => Err(scheme),
+        }
+    }
+
+    /// Handles the [TpmCc::Sign] (`0x15D`) command.
+    pub fn sign(
+        &mut self,
+        key_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: SignCmd = request.unmarshal()?;
+        let object = self
+            .objects
+            .get(key_handle)
+            .ok_or(TpmRcError::HandleFor(KEY_HANDLE.0, KEY_HANDLE.1))?;
+        let attributes = object.public.object_attributes;
+        if !attributes.contains(TpmaObject::SIGN_ENCRYPT) {
+            return Err(TpmRcError::KeyFor(KEY_HANDLE.0, KEY_HANDLE.1));
+        }
+        let (scheme, hash) =
+            select_sig_scheme(&object.public, &command.in_scheme, KEY_HANDLE, IN_SCHEME)?;
+        if Some(command.digest.get_size() as usize) != digest_size(hash) {
+            return Err(TpmRcError::SizeFor(DIGEST.0, DIGEST.1));
+        }
+
+        // A restricted key only signs digests that the TPM computed itself, which is proven by a
+        // ticket. Any other key only checks a ticket if one is given.
+        let validation = &command.validation;
+        if validation.tag != TpmSt::HashCheck {
+            return Err(TpmRcError::TicketFor(VALIDATION.0, VALIDATION.1));
+        }
+        if attributes.contains(TpmaObject::RESTRICTED) || validation.digest.get_size() != 0 {
+            let expected = self.proofs.hashcheck_ticket::<Deps::Hash>(
+                validation.hierarchy,
+                hash,
+                command.digest.get_buffer(),
+            )?;
+            if expected.digest.get_size() == 0 || expected.digest != validation.digest {
+                return Err(TpmRcError::TicketFor(VALIDATION.0, VALIDATION.1));
+            }
+        }
+
+        let signature = self.sign_digest(
+            key_handle,
+            KEY_HANDLE,
+            &scheme,
+            hash,
+            command.digest.get_buffer(),
+        )?;
+        let mut response = request.into_response();
+        response.marshal(&SignResp { signature })
+    }
+
+    /// Handles the [TpmCc::VerifySignature] (`0x177`) command.
+    pub fn verify_signature(
+        &mut self,
+        key_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: VerifySignatureCmd = request.unmarshal()?;
+        let object = self
+            .objects
+            .get(key_handle)
+            .ok_or(TpmRcError::HandleFor(KEY_HANDLE.0, KEY_HANDLE.1))?;
+        if !object
+            .public
+            .object_attributes
+            .contains(TpmaObject::SIGN_ENCRYPT)
+        {
+            return Err(TpmRcError::AttributesFor(KEY_HANDLE.0, KEY_HANDLE.1));
+        }
+        let digest = command.digest.get_buffer();
+        if !Self::check_signature(object, digest, &command.signature, KEY_HANDLE, SIGNATURE)? {
+            return Err(TpmRcError::SignatureFor(SIGNATURE.0, SIGNATURE.1));
+        }
+
+        let validation = match object.hierarchy {
+            TpmHandle::RHNull => null_verified_ticket(),
+            hierarchy => {
+                self.proofs
+                    .verified_ticket::<Deps::Hash>(hierarchy, digest, &object.name)?
+            }
+        };
+        let mut response = request.into_response();
+        response.marshal(&VerifySignatureResp { validation })
+    }
+}
@@ -0,0 +1,226 @@
+use tpm2_rs_base::commands::{
+    EncryptDecrypt2Cmd, EncryptDecrypt2Resp, EncryptDecryptCmd, EncryptDecryptResp, HashCmd,
+    HashResp,
+};
+use tpm2_rs_base::constants::{TpmGenerated, TpmHandle, TPM2_MAX_DIGEST_BUFFER};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bDigest, Tpm2bIv, Tpm2bMaxBuffer, Tpm2bSimple, TpmaObject,
+    TpmiAlgSymMode, TpmiAlgSymObject, TpmiYesNo, TpmtSymDefObject, TpmuSensitiveComposite,
+};
+
+use crate::{
+    crypto::{
+        algorithms::{check_hash, check_mode, ErrorAt},
+        hash::digest,
+        symmetric::{sym_crypt, Direction, SYM_BLOCK_SIZE},
+    },
+    handler::CommandHandler,
+    platform::{crypto::BlockCipher, TpmBuffers, TpmContextDeps},
+    req_resp::RequestThenResponse,
+    ticket::null_hashcheck_ticket,
+};
+
+const KEY_HANDLE: ErrorAt = (ErrorType::Handle, ErrorPosition::Pos1);
+
+/// The parameters shared by `TPM2_EncryptDecrypt` and `TPM2_EncryptDecrypt2`, which only differ
+/// in the order of their parameters.
+struct EncryptDecryptParams<'a> {
+    decrypt: TpmiYesNo,
+    mode: TpmiAlgSymMode,
+    iv_in: &'a Tpm2bIv,
+    in_data: &'a Tpm2bMaxBuffer,
+}
+
+/// The positions of the [`EncryptDecryptParams`] in the command for error reporting.
+struct EncryptDecryptPositions {
+    decrypt: ErrorPosition,
+    mode: ErrorPosition,
+    iv_in: ErrorPosition,
+    in_data: ErrorPosition,
+}
+
+impl<Deps: TpmContextDeps> CommandHandler<Deps> {
+    /// Encrypts or decrypts `params.in_data` with the symmetric key at `key_handle`.
+    fn encrypt_decrypt_common(
+        &self,
+        key_handle: TpmHandle,
+        params: EncryptDecryptParams,
+        positions: EncryptDecryptPositions,
+    ) -> Result<(Tpm2bMaxBuffer, Tpm2bIv), TpmRcError> {
+        let parameter = |pos| (ErrorType::Parameter, pos);
+        let object = self
+            .objects
+            .get(key_handle)
+            .ok_or(TpmRcError::HandleFor(KEY_HANDLE.0, KEY_HANDLE.1))?;
+        let (PublicParmsAndId::Sym(parms, _), Some(TpmuSensitiveComposite::Sym(key))) = (
+            &object.public.parms_and_id,
+            object
+                .sensitive
+                .as_ref()
+                .map(|sensitive| &sensitive.sensitive),
+        ) else {
+            return Err(TpmRcError::KeyFor(KEY_HANDLE.0, KEY_HANDLE.1));
+        };
+        let TpmtSymDefObject::Aes(_, key_mode) = parms.sym else {
+            return Err(TpmRcError::KeyFor(KEY_HANDLE.0, KEY_HANDLE.1));
+        };
+
+        let attributes = object.public.object_attributes;
+        let (direction, required) = match params.decrypt {
+            TpmiYesNo::YES => (Direction::Decrypt, TpmaObject::DECRYPT),
+            TpmiYesNo::NO => (Direction::Encrypt, TpmaObject::SIGN_ENCRYPT),
+            _ => {
+                let at = parameter(positions.decrypt);
+                return Err(TpmRcError::ValueFor(at.0, at.1));
+            }
+        };
+        if attributes.contains(TpmaObject::RESTRICTED) || !attributes.contains(required) {
+            return Err(TpmRcError::AttributesFor(KEY_HANDLE.0, KEY_HANDLE.1));
+        }
+
+        // A key with a mode may only be used with that mode.
+        let mode_at = parameter(positions.mode);
+        let mode = match (key_mode, params.mode) {
+            (TpmiAlgSymMode::Null, mode) => mode,
+            (key_mode, TpmiAlgSymMode::Null) => key_mode,
+            (key_mode, mode) if key_mode == mode => mode,
+            _ => return Err(TpmRcError::ModeFor(mode_at.0, mode_at.1)),
+        };
+        check_mode(mode, mode_at)?;
+
+        let data_at = parameter(positions.in_data);
+        if (mode == TpmiAlgSymMode::ECB || mode == TpmiAlgSymMode::CBC)
+            && !(params.in_data.get_size() as usize).is_multiple_of(SYM_BLOCK_SIZE)
+        {
+            return Err(TpmRcError::SizeFor(data_at.0, data_at.1));
+        }
+        let mut iv = [0; SYM_BLOCK_SIZE];
+        let iv = if mode == TpmiAlgSymMode::ECB {
+            &mut iv[..0]
+        } else {
+            if params.iv_in.get_size() as usize != SYM_BLOCK_SIZE {
+                let at = parameter(positions.iv_in);
+                return Err(TpmRcError::SizeFor(at.0, at.1));
+            }
+            iv.copy_from_slice(params.iv_in.get_buffer());
+            &mut iv[..]
+        };
+
+        // The key size was checked when the key was loaded.
+        let cipher = Deps::Cipher::new(TpmiAlgSymObject::AES, key.get_buffer())
+            .ok_or(TpmRcError::Failure)?;
+        let mut data = [0; TPM2_MAX_DIGEST_BUFFER as usize];
+        let data = &mut data[..params.in_data.get_size() as usize];
+        data.copy_from_slice(params.in_data.get_buffer());
+        sym_crypt(&cipher, mode, direction, iv, data)?;
+
+        Ok((
+            Tpm2bMaxBuffer::from_bytes(data).or(Err(TpmRcError::Failure))?,
+            Tpm2bIv::from_bytes(iv).or(Err(TpmRcError::Failure))?,
+        ))
+    }
+
+    /// Handles the [TpmCc::EncryptDecrypt] (`0x164`) command.
+    pub fn encrypt_decrypt(
+        &mut self,
+        key_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: EncryptDecryptCmd = request.unmarshal()?;
+        let (out_data, iv_out) = self.encrypt_decrypt_common(
+            key_handle,
+            EncryptDecryptParams {
+                decrypt: command.decrypt,
+                mode: command.mode,
+                iv_in: &command.iv_in,
+                in_data: &command.in_data,
+            },
+            EncryptDecryptPositions {
+                decrypt: ErrorPosition::Pos1,
+                mode: ErrorPosition::Pos2,
+                iv_in: ErrorPosition::Pos3,
+                in_data: ErrorPosition::Pos4,
+            },
+        )?;
+
+        let mut response = request.into_response();
+        response.marshal(&EncryptDecryptResp { out_data, iv_out })
+    }
+
+    /// Handles the [TpmCc::EncryptDecrypt2] (`0x193`) command.
+    pub fn encrypt_decrypt2(
+        &mut self,
+        key_handle: TpmHandle,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: EncryptDecrypt2Cmd = request.unmarshal()?;
+        let (out_data, iv_out) = self.encrypt_decrypt_common(
+            key_handle,
+            EncryptDecryptParams {
+                decrypt: command.decrypt,
+                mode: command.mode,
+                iv_in: &command.iv_in,
+                in_data: &command.in_data,
+            },
+            EncryptDecryptPositions {
+                decrypt: ErrorPosition::Pos2,
+                mode: ErrorPosition::Pos3,
+                iv_in: ErrorPosition::Pos4,
+                in_data: ErrorPosition::Pos1,
+            },
+        )?;
+
+        let mut response = request.into_response();
+        response.marshal(&EncryptDecrypt2Resp { out_data, iv_out })
+    }
+
+    /// Handles the [TpmCc::Hash] (`0x17D`) command.
+    pub fn hash(
+        &mut self,
+        request_response: RequestThenResponse<impl TpmBuffers>,
+    ) -> Result<(), TpmRcError> {
+        let mut request = request_response;
+        let command: HashCmd = request.unmarshal()?;
+        check_hash(
+            command.hash_alg,
+            (ErrorType::Parameter, ErrorPosition::Pos2),
+        )?;
+        match command.hierarchy {
+            TpmHandle::RHOwner
+            | TpmHandle::RHEndorsement
+            | TpmHandle::RHPlatform
+            | TpmHandle::RHNull => {}
+            _ => {
+                return Err(TpmRcError::ValueFor(
+                    ErrorType::Parameter,
+                    ErrorPosition::Pos3,
+                ))
+            }
+        }
+
+        let data = command.data.get_buffer();
+        let out_hash = digest::<Deps::Hash>(command.hash_alg, &[data])?;
+        // Data that looks like a TPM-generated structure must not be vouched for, or it could be
+        // used to forge attestations.
+        let validation = if command.hierarchy == TpmHandle::RHNull
+            || data.starts_with(&TpmGenerated::VALUE.0.to_be_bytes())
+        {
+            null_hashcheck_ticket()
+        } else {
+            self.proofs.hashcheck_ticket::<Deps::Hash>(
+                command.hierarchy,
+                command.hash_alg,
+                out_hash.as_ref(),
+            )?
+        };
+
+        let mut response = request.into_response();
+        response.marshal(&HashResp {
+            out_hash: Tpm2bDigest::from_bytes(out_hash.as_ref()).or(Err(TpmRcError::Failure))?,
+            validation,
+        })
+    }
+}
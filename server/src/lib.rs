@@ -2,14 +2,24 @@
 #![forbid(unsafe_code)]
 #![allow(dead_code)] // rustc >= 1.90.0 (1159e78c4 2025-09-14)
 
+mod audit;
 mod buffers;
+mod command;
 mod crypto;
 mod error;
 mod handler;
+mod object;
+mod pcr;
 pub mod platform;
+mod policy;
 mod req_resp;
+mod session;
+#[cfg(feature = "simulator")]
+pub mod simulator;
 #[cfg(test)]
 mod tests;
+mod ticket;
+mod time;
 mod tpmctx;
 pub use error::ServerError;
 pub use tpmctx::TpmContext;
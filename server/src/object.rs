@@ -0,0 +1,101 @@
+use tpm2_rs_base::constants::{TpmHandle, TpmHc};
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::marshal::Marshalable;
+use tpm2_rs_base::{Tpm2bName, Tpm2bSimple, TpmiAlgHash, TpmtPublic, TpmtSensitive};
+
+use crate::crypto::hash::{digest, MAX_DIGEST_SIZE};
+use crate::platform::crypto::Hash;
+
+/// The number of transient objects that can be loaded at the same time.
+pub const MAX_LOADED_OBJECTS: usize = 3;
+
+/// An object that has been loaded into the TPM.
+pub struct Object {
+    /// The public area of the object.
+    pub public: TpmtPublic,
+    /// The sensitive area of the object, if it was loaded.
+    pub sensitive: Option<TpmtSensitive>,
+    /// The Name of the object.
+    pub name: Tpm2bName,
+    /// The Qualified Name of the object, which also identifies all of its ancestors.
+    pub qualified_name: Tpm2bName,
+    /// The hierarchy the object belongs to.
+    pub hierarchy: TpmHandle,
+}
+
+/// Computes the Name of an object, which is its `nameAlg` followed by the digest of its marshaled
+/// public area.
+pub fn compute_name<H: Hash>(public: &TpmtPublic) -> Result<Tpm2bName, TpmRcError> {
+    let mut marshaled = [0; size_of::<TpmtPublic>()];
+    let size = public
+        .try_marshal(&mut marshaled)
+        .or(Err(TpmRcError::Failure))?;
+    hashed_name::<H>(public.name_alg, &[&marshaled[..size]])
+}
+
+/// Computes the Qualified Name of an object, which is its `nameAlg` followed by the digest of the
+/// Qualified Name of its parent and its own Name. The Qualified Name of a hierarchy is its handle.
+pub fn compute_qualified_name<H: Hash>(
+    name_alg: TpmiAlgHash,
+    parent_qualified_name: &[u8],
+    name: &Tpm2bName,
+) -> Result<Tpm2bName, TpmRcError> {
+    hashed_name::<H>(name_alg, &[parent_qualified_name, name.get_buffer()])
+}
+
+/// Builds a Name from `name_alg` and the digest of `data`.
+fn hashed_name<H: Hash>(name_alg: TpmiAlgHash, data: &[&[u8]]) -> Result<Tpm2bName, TpmRcError> {
+    let data_digest = digest::<H>(name_alg, data)?;
+    let mut name = [0; size_of::<u16>() + MAX_DIGEST_SIZE];
+    let alg = name_alg.0.to_be_bytes();
+    name[..alg.len()].copy_from_slice(&alg);
+    let size = alg.len() + data_digest.as_ref().len();
+    name[alg.len()..size].copy_from_slice(data_digest.as_ref());
+    Tpm2bName::from_bytes(&name[..size]).or(Err(TpmRcError::Failure))
+}
+
+/// The slots that hold the transient objects currently loaded into the TPM. The handle of an
+/// object is derived from the index of its slot.
+pub struct ObjectSlots {
+    slots: [Option<Object>; MAX_LOADED_OBJECTS],
+}
+
+impl ObjectSlots {
+    /// Creates the object slots with no objects loaded.
+    pub fn new() -> Self {
+        Self {
+            slots: [const { None }; MAX_LOADED_OBJECTS],
+        }
+    }
+
+    fn index(handle: TpmHandle) -> Option<usize> {
+        if !TpmHc::is_transient(handle.0) {
+            return None;
+        }
+        let index = (handle.0 - TpmHc::TransientFirst.get()) as usize;
+        (index < MAX_LOADED_OBJECTS).then_some(index)
+    }
+
+    /// Loads `object` into a free slot and returns its handle. Returns
+    /// [`TpmRcError::ObjectMemory`] if all slots are in use.
+    pub fn insert(&mut self, object: Object) -> Result<TpmHandle, TpmRcError> {
+        let (index, slot) = self
+            .slots
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| slot.is_none())
+            .ok_or(TpmRcError::ObjectMemory)?;
+        *slot = Some(object);
+        Ok(TpmHandle(TpmHc::TransientFirst.get() + index as u32))
+    }
+
+    /// Gets the object referenced by `handle`, if any.
+    pub fn get(&self, handle: TpmHandle) -> Option<&Object> {
+        self.slots.get(Self::index(handle)?)?.as_ref()
+    }
+
+    /// Unloads the object referenced by `handle`. Returns `None` if no object was loaded.
+    pub fn remove(&mut self, handle: TpmHandle) -> Option<Object> {
+        self.slots.get_mut(Self::index(handle)?)?.take()
+    }
+}
@@ -0,0 +1,73 @@
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::{Tpm2bDigest, Tpm2bSimple, TpmiAlgHash, TpmlPcrSelection};
+
+use crate::crypto::hash::Hasher;
+use crate::platform::crypto::Hash;
+
+/// The number of PCRs in each bank.
+pub const PCR_COUNT: usize = 24;
+
+/// The hash algorithm of the only PCR bank of this TPM.
+pub const PCR_BANK: TpmiAlgHash = TpmiAlgHash::SHA256;
+
+/// The size of the PCRs in [`PCR_BANK`].
+const PCR_SIZE: usize = 32;
+
+/// The PCRs that are reset to all ones rather than zeros when the TPM is reset, because they
+/// belong to a dynamic root of trust that has not been launched.
+const DRTM_PCRS: core::ops::RangeInclusive<usize> = 17..=22;
+
+/// The platform configuration registers of the TPM.
+pub struct Pcrs {
+    values: [[u8; PCR_SIZE]; PCR_COUNT],
+}
+
+impl Pcrs {
+    /// Creates the PCRs with their reset values.
+    pub fn new() -> Self {
+        let mut values = [[0; PCR_SIZE]; PCR_COUNT];
+        values[DRTM_PCRS].fill([0xFF; PCR_SIZE]);
+        Self { values }
+    }
+
+    /// Removes the PCRs that are not implemented from `selection`. The selection of a bank that
+    /// is not allocated is cleared but stays in the list.
+    pub fn filter(selection: &TpmlPcrSelection) -> Result<TpmlPcrSelection, TpmRcError> {
+        let mut filtered = TpmlPcrSelection::default();
+        for bank in selection.pcr_selections() {
+            let mut bank = *bank;
+            let size = bank.sizeof_select as usize;
+            for (index, byte) in bank.pcr_select[..size].iter_mut().enumerate() {
+                if bank.hash != PCR_BANK {
+                    *byte = 0;
+                } else {
+                    let implemented = PCR_COUNT.saturating_sub(index * 8).min(8);
+                    *byte &= ((1u16 << implemented) - 1) as u8;
+                }
+            }
+            filtered.add(&bank).or(Err(TpmRcError::Failure))?;
+        }
+        Ok(filtered)
+    }
+
+    /// Computes the digest of the values of the PCRs in a filtered `selection` with `alg`, in
+    /// the order in which they are selected.
+    pub fn digest<H: Hash>(
+        &self,
+        alg: TpmiAlgHash,
+        selection: &TpmlPcrSelection,
+    ) -> Result<Tpm2bDigest, TpmRcError> {
+        let mut hasher = Hasher::<H>::start(alg)?;
+        for bank in selection.pcr_selections() {
+            let size = bank.sizeof_select as usize;
+            for (index, byte) in bank.pcr_select[..size].iter().enumerate() {
+                for bit in 0..8 {
+                    if byte & (1 << bit) != 0 {
+                        hasher.update(&self.values[index * 8 + bit]);
+                    }
+                }
+            }
+        }
+        Tpm2bDigest::from_bytes(hasher.finish().as_ref()).or(Err(TpmRcError::Failure))
+    }
+}
@@ -16,6 +16,17 @@ pub trait TpmReadBuffer {
     // would go beyond the length of the buffer.
     fn read_into(&self, offset: usize, out: &mut [u8]) -> Result<(), ReadOutOfBounds>;
 
+    /// Gets a slice from the [`TpmReadBuffer`] that can be read in place via the provided
+    /// callback. The callback will be given a slice that has length `size` and its result is
+    /// returned. If the read operation would have read past the bounds of this [`TpmReadBuffer`],
+    /// then [`ReadOutOfBounds`] is returned instead and the callback is never called.
+    fn read_callback<R>(
+        &self,
+        offset: usize,
+        size: usize,
+        callback: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, ReadOutOfBounds>;
+
     /// Reads a `u16` encoded in big endian at the specified offset. Specific implementors may
     /// provide a more optimized version.
     fn read_be_u16(&self, offset: usize) -> Result<u16, ReadOutOfBounds> {
@@ -66,6 +77,17 @@ impl TpmReadBuffer for [u8] {
         out.copy_from_slice(read_from);
         Ok(())
     }
+    fn read_callback<R>(
+        &self,
+        offset: usize,
+        size: usize,
+        callback: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, ReadOutOfBounds> {
+        let Some(buffer) = self.get(offset..offset + size) else {
+            return Err(ReadOutOfBounds);
+        };
+        Ok(callback(buffer))
+    }
 }
 
 impl TpmWriteBuffer for [u8] {
@@ -0,0 +1,8 @@
+/// This trait provides the time source that the TPM's `Time` and `Clock` are derived from.
+pub trait Clock {
+    /// Creates a new clock instance.
+    fn instantiate() -> Self;
+    /// Returns the number of milliseconds since an arbitrary point in the past. The returned
+    /// value must never decrease.
+    fn now_ms(&mut self) -> u64;
+}
@@ -0,0 +1,14 @@
+use tpm2_rs_base::TpmiAlgSymObject;
+
+/// This trait wraps an expanded key for one of the block ciphers implemented by the platform.
+pub trait BlockCipher: Sized {
+    /// Expands `key` for the specified algorithm. Returns `None` if the algorithm or the key size
+    /// is not implemented by the platform.
+    fn new(alg: TpmiAlgSymObject, key: &[u8]) -> Option<Self>;
+    /// Encrypts a single block in place. The length of `block` is always the block size of the
+    /// algorithm.
+    fn encrypt_block(&self, block: &mut [u8]);
+    /// Decrypts a single block in place. The length of `block` is always the block size of the
+    /// algorithm.
+    fn decrypt_block(&self, block: &mut [u8]);
+}
@@ -0,0 +1,54 @@
+use tpm2_rs_base::constants::TpmEccCurve;
+
+/// Error indicating that an elliptic curve operation failed, e.g. because the curve is not
+/// implemented, a point is not on the curve or the result is the point at infinity.
+#[derive(Debug)]
+pub struct EccError;
+
+/// This trait wraps the elliptic curve arithmetic implemented by the platform.
+///
+/// All coordinates and scalars are unsigned big-endian integers that are exactly as long as the
+/// byte size of the curve's order.
+pub trait Ecc {
+    /// Checks that `(x, y)` is a point on the specified curve.
+    fn validate_point(curve: TpmEccCurve, x: &[u8], y: &[u8]) -> Result<(), EccError>;
+    /// Computes `[scalar](x, y)` on the specified curve and writes the coordinates of the result
+    /// to `out_x` and `out_y`. `scalar` must be in the range `[1, n - 1]`.
+    fn point_mul(
+        curve: TpmEccCurve,
+        scalar: &[u8],
+        x: &[u8],
+        y: &[u8],
+        out_x: &mut [u8],
+        out_y: &mut [u8],
+    ) -> Result<(), EccError>;
+    /// Computes `[u1]G + [u2](x, y)` on the specified curve, where `G` is the base point, and
+    /// writes the coordinates of the result to `out_x` and `out_y`. `u1` and `u2` must be in the
+    /// range `[0, n - 1]`.
+    fn point_mul_add(
+        curve: TpmEccCurve,
+        u1: &[u8],
+        u2: &[u8],
+        x: &[u8],
+        y: &[u8],
+        out_x: &mut [u8],
+        out_y: &mut [u8],
+    ) -> Result<(), EccError>;
+    /// Reduces `value` modulo the order `n` of the specified curve and writes the result to
+    /// `out`.
+    fn scalar_reduce(curve: TpmEccCurve, value: &[u8], out: &mut [u8]) -> Result<(), EccError>;
+    /// Computes `a * b + c mod n` on the specified curve and writes the result to `out`. All
+    /// inputs must be in the range `[0, n - 1]`.
+    fn scalar_mul_add(
+        curve: TpmEccCurve,
+        a: &[u8],
+        b: &[u8],
+        c: &[u8],
+        out: &mut [u8],
+    ) -> Result<(), EccError>;
+    /// Computes `-scalar mod n` on the specified curve and writes the result to `out`.
+    fn scalar_negate(curve: TpmEccCurve, scalar: &[u8], out: &mut [u8]) -> Result<(), EccError>;
+    /// Computes `scalar^-1 mod n` on the specified curve and writes the result to `out`.
+    /// `scalar` must be in the range `[1, n - 1]`.
+    fn scalar_invert(curve: TpmEccCurve, scalar: &[u8], out: &mut [u8]) -> Result<(), EccError>;
+}
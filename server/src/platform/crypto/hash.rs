@@ -0,0 +1,14 @@
+use tpm2_rs_base::TpmiAlgHash;
+
+/// This trait wraps a running digest computation for one of the hash algorithms implemented by the
+/// platform.
+pub trait Hash: Sized {
+    /// Starts a new digest computation with the specified algorithm. Returns `None` if the
+    /// algorithm is not implemented by the platform.
+    fn start(alg: TpmiAlgHash) -> Option<Self>;
+    /// Adds `data` to the digest computation.
+    fn update(&mut self, data: &[u8]);
+    /// Completes the digest computation and writes the digest to `digest`. The length of `digest`
+    /// is always the digest size of the algorithm the computation was started with.
+    fn finish(self, digest: &mut [u8]);
+}
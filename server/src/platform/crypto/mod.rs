@@ -1,5 +1,15 @@
+mod cipher;
 mod drbg;
+mod ecc;
 mod entropy;
+mod hash;
+mod rsa;
+#[cfg(any(test, feature = "rustcrypto"))]
+pub mod rustcrypto;
 
+pub use cipher::BlockCipher;
 pub use drbg::{helpers as drbg_helpers, Drbg, DrbgError};
+pub use ecc::{Ecc, EccError};
 pub use entropy::EntropySource;
+pub use hash::Hash;
+pub use rsa::{Rsa, RsaError};
@@ -0,0 +1,27 @@
+/// Error indicating that an RSA operation failed, e.g. because the key is not valid or the input
+/// is not smaller than the modulus.
+#[derive(Debug)]
+pub struct RsaError;
+
+/// This trait wraps the RSA arithmetic implemented by the platform.
+///
+/// All integers are unsigned big-endian. The public exponent is given as its actual value, i.e. it
+/// is never zero. Results are exactly as long as the modulus.
+pub trait Rsa {
+    /// Checks that `prime` is a factor of the modulus `n` and that the key formed by `n`, the
+    /// public exponent `e` and `prime` is valid.
+    fn check_key(n: &[u8], e: u32, prime: &[u8]) -> Result<(), RsaError>;
+    /// Computes `input^e mod n` and writes the result to `out`.
+    fn public_op(n: &[u8], e: u32, input: &[u8], out: &mut [u8]) -> Result<(), RsaError>;
+    /// Computes `input^d mod n`, where `d` is the private exponent of the key formed by `n`, the
+    /// public exponent `e` and `prime`, and writes the result to `out`. The computation must be
+    /// blinded with bytes from `random` so that its timing doesn't depend on `d`.
+    fn private_op(
+        n: &[u8],
+        e: u32,
+        prime: &[u8],
+        input: &[u8],
+        out: &mut [u8],
+        random: &mut dyn FnMut(&mut [u8]),
+    ) -> Result<(), RsaError>;
+}
@@ -0,0 +1,48 @@
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::{Aes128, Aes192, Aes256, Block};
+use tpm2_rs_base::TpmiAlgSymObject;
+
+use crate::platform::crypto::BlockCipher;
+
+/// Implements [`BlockCipher`] for AES.
+pub enum RustCryptoCipher {
+    Aes128(Aes128),
+    Aes192(Aes192),
+    Aes256(Aes256),
+}
+
+impl BlockCipher for RustCryptoCipher {
+    fn new(alg: TpmiAlgSymObject, key: &[u8]) -> Option<Self> {
+        if alg != TpmiAlgSymObject::AES {
+            return None;
+        }
+        match key.len() {
+            16 => Aes128::new_from_slice(key).ok().map(Self::Aes128),
+            24 => Aes192::new_from_slice(key).ok().map(Self::Aes192),
+            32 => Aes256::new_from_slice(key).ok().map(Self::Aes256),
+            _ => None,
+        }
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let mut aes_block = Block::default();
+        aes_block.copy_from_slice(block);
+        match self {
+            Self::Aes128(cipher) => cipher.encrypt_block(&mut aes_block),
+            Self::Aes192(cipher) => cipher.encrypt_block(&mut aes_block),
+            Self::Aes256(cipher) => cipher.encrypt_block(&mut aes_block),
+        }
+        block.copy_from_slice(&aes_block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let mut aes_block = Block::default();
+        aes_block.copy_from_slice(block);
+        match self {
+            Self::Aes128(cipher) => cipher.decrypt_block(&mut aes_block),
+            Self::Aes192(cipher) => cipher.decrypt_block(&mut aes_block),
+            Self::Aes256(cipher) => cipher.decrypt_block(&mut aes_block),
+        }
+        block.copy_from_slice(&aes_block);
+    }
+}
@@ -0,0 +1,213 @@
+use p256::elliptic_curve::{
+    generic_array::typenum::Unsigned,
+    group::{Curve, Group},
+    ops::{Invert, Reduce},
+    sec1::{EncodedPoint, FromEncodedPoint, ModulusSize, ToEncodedPoint},
+    CurveArithmetic, FieldBytes, FieldBytesSize, PrimeField,
+};
+use p256::NistP256;
+use p384::NistP384;
+use tpm2_rs_base::constants::TpmEccCurve;
+
+use crate::platform::crypto::{Ecc, EccError};
+
+/// Implements [`Ecc`] for the NIST P-256 and P-384 curves.
+pub struct RustCryptoEcc;
+
+fn field_bytes<C: CurveArithmetic>(bytes: &[u8]) -> Result<FieldBytes<C>, EccError> {
+    if bytes.len() != FieldBytesSize::<C>::USIZE {
+        return Err(EccError);
+    }
+    let mut field_bytes = FieldBytes::<C>::default();
+    field_bytes.copy_from_slice(bytes);
+    Ok(field_bytes)
+}
+
+fn affine_point<C>(x: &[u8], y: &[u8]) -> Result<C::AffinePoint, EccError>
+where
+    C: CurveArithmetic,
+    C::AffinePoint: FromEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let encoded = EncodedPoint::<C>::from_affine_coordinates(
+        &field_bytes::<C>(x)?,
+        &field_bytes::<C>(y)?,
+        false,
+    );
+    Option::from(C::AffinePoint::from_encoded_point(&encoded)).ok_or(EccError)
+}
+
+fn scalar<C: CurveArithmetic>(bytes: &[u8]) -> Result<C::Scalar, EccError> {
+    Option::from(C::Scalar::from_repr(field_bytes::<C>(bytes)?)).ok_or(EccError)
+}
+
+fn write_scalar<C: CurveArithmetic>(scalar: C::Scalar, out: &mut [u8]) -> Result<(), EccError> {
+    let repr = scalar.to_repr();
+    if out.len() != repr.len() {
+        return Err(EccError);
+    }
+    out.copy_from_slice(&repr);
+    Ok(())
+}
+
+fn write_point<C>(
+    point: C::ProjectivePoint,
+    out_x: &mut [u8],
+    out_y: &mut [u8],
+) -> Result<(), EccError>
+where
+    C: CurveArithmetic,
+    C::AffinePoint: ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let encoded = point.to_affine().to_encoded_point(false);
+    let (Some(result_x), Some(result_y)) = (encoded.x(), encoded.y()) else {
+        // The result is the point at infinity.
+        return Err(EccError);
+    };
+    if out_x.len() != result_x.len() || out_y.len() != result_y.len() {
+        return Err(EccError);
+    }
+    out_x.copy_from_slice(result_x);
+    out_y.copy_from_slice(result_y);
+    Ok(())
+}
+
+fn point_mul<C>(
+    scalar: &[u8],
+    x: &[u8],
+    y: &[u8],
+    out_x: &mut [u8],
+    out_y: &mut [u8],
+) -> Result<(), EccError>
+where
+    C: CurveArithmetic,
+    C::AffinePoint: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let point = affine_point::<C>(x, y)?;
+    let scalar = self::scalar::<C>(scalar)?;
+    write_point::<C>(C::ProjectivePoint::from(point) * scalar, out_x, out_y)
+}
+
+fn point_mul_add<C>(
+    u1: &[u8],
+    u2: &[u8],
+    x: &[u8],
+    y: &[u8],
+    out_x: &mut [u8],
+    out_y: &mut [u8],
+) -> Result<(), EccError>
+where
+    C: CurveArithmetic,
+    C::AffinePoint: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let point = affine_point::<C>(x, y)?;
+    let result = C::ProjectivePoint::generator() * scalar::<C>(u1)?
+        + C::ProjectivePoint::from(point) * scalar::<C>(u2)?;
+    write_point::<C>(result, out_x, out_y)
+}
+
+fn scalar_reduce<C: CurveArithmetic>(value: &[u8], out: &mut [u8]) -> Result<(), EccError> {
+    let reduced = <C::Scalar as Reduce<C::Uint>>::reduce_bytes(&field_bytes::<C>(value)?);
+    write_scalar::<C>(reduced, out)
+}
+
+fn scalar_mul_add<C: CurveArithmetic>(
+    a: &[u8],
+    b: &[u8],
+    c: &[u8],
+    out: &mut [u8],
+) -> Result<(), EccError> {
+    let result = scalar::<C>(a)? * scalar::<C>(b)? + scalar::<C>(c)?;
+    write_scalar::<C>(result, out)
+}
+
+fn scalar_negate<C: CurveArithmetic>(value: &[u8], out: &mut [u8]) -> Result<(), EccError> {
+    write_scalar::<C>(-scalar::<C>(value)?, out)
+}
+
+fn scalar_invert<C: CurveArithmetic>(value: &[u8], out: &mut [u8]) -> Result<(), EccError> {
+    let inverse = Option::from(Invert::invert(&scalar::<C>(value)?)).ok_or(EccError)?;
+    write_scalar::<C>(inverse, out)
+}
+
+impl Ecc for RustCryptoEcc {
+    fn validate_point(curve: TpmEccCurve, x: &[u8], y: &[u8]) -> Result<(), EccError> {
+        match curve {
+            TpmEccCurve::NistP256 => affine_point::<NistP256>(x, y).map(|_| ()),
+            TpmEccCurve::NistP384 => affine_point::<NistP384>(x, y).map(|_| ()),
+            _ => Err(EccError),
+        }
+    }
+
+    fn point_mul(
+        curve: TpmEccCurve,
+        scalar: &[u8],
+        x: &[u8],
+        y: &[u8],
+        out_x: &mut [u8],
+        out_y: &mut [u8],
+    ) -> Result<(), EccError> {
+        match curve {
+            TpmEccCurve::NistP256 => point_mul::<NistP256>(scalar, x, y, out_x, out_y),
+            TpmEccCurve::NistP384 => point_mul::<NistP384>(scalar, x, y, out_x, out_y),
+            _ => Err(EccError),
+        }
+    }
+
+    fn point_mul_add(
+        curve: TpmEccCurve,
+        u1: &[u8],
+        u2: &[u8],
+        x: &[u8],
+        y: &[u8],
+        out_x: &mut [u8],
+        out_y: &mut [u8],
+    ) -> Result<(), EccError> {
+        match curve {
+            TpmEccCurve::NistP256 => point_mul_add::<NistP256>(u1, u2, x, y, out_x, out_y),
+            TpmEccCurve::NistP384 => point_mul_add::<NistP384>(u1, u2, x, y, out_x, out_y),
+            _ => Err(EccError),
+        }
+    }
+
+    fn scalar_reduce(curve: TpmEccCurve, value: &[u8], out: &mut [u8]) -> Result<(), EccError> {
+        match curve {
+            TpmEccCurve::NistP256 => scalar_reduce::<NistP256>(value, out),
+            TpmEccCurve::NistP384 => scalar_reduce::<NistP384>(value, out),
+            _ => Err(EccError),
+        }
+    }
+
+    fn scalar_mul_add(
+        curve: TpmEccCurve,
+        a: &[u8],
+        b: &[u8],
+        c: &[u8],
+        out: &mut [u8],
+    ) -> Result<(), EccError> {
+        match curve {
+            TpmEccCurve::NistP256 => scalar_mul_add::<NistP256>(a, b, c, out),
+            TpmEccCurve::NistP384 => scalar_mul_add::<NistP384>(a, b, c, out),
+            _ => Err(EccError),
+        }
+    }
+
+    fn scalar_negate(curve: TpmEccCurve, scalar: &[u8], out: &mut [u8]) -> Result<(), EccError> {
+        match curve {
+            TpmEccCurve::NistP256 => scalar_negate::<NistP256>(scalar, out),
+            TpmEccCurve::NistP384 => scalar_negate::<NistP384>(scalar, out),
+            _ => Err(EccError),
+        }
+    }
+
+    fn scalar_invert(curve: TpmEccCurve, scalar: &[u8], out: &mut [u8]) -> Result<(), EccError> {
+        match curve {
+            TpmEccCurve::NistP256 => scalar_invert::<NistP256>(scalar, out),
+            TpmEccCurve::NistP384 => scalar_invert::<NistP384>(scalar, out),
+            _ => Err(EccError),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use tpm2_rs_base::TpmiAlgHash;
+
+use crate::platform::crypto::Hash;
+
+/// Implements [`Hash`] for SHA-1 and the SHA-2 family.
+pub enum RustCryptoHash {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+
+impl Hash for RustCryptoHash {
+    fn start(alg: TpmiAlgHash) -> Option<Self> {
+        match alg {
+            TpmiAlgHash::SHA1 => Some(Self::Sha1(Sha1::new())),
+            TpmiAlgHash::SHA256 => Some(Self::Sha256(Sha256::new())),
+            TpmiAlgHash::SHA384 => Some(Self::Sha384(Sha384::new())),
+            TpmiAlgHash::SHA512 => Some(Self::Sha512(Sha512::new())),
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha1(hash) => hash.update(data),
+            Self::Sha256(hash) => hash.update(data),
+            Self::Sha384(hash) => hash.update(data),
+            Self::Sha512(hash) => hash.update(data),
+        }
+    }
+
+    fn finish(self, digest: &mut [u8]) {
+        match self {
+            Self::Sha1(hash) => digest.copy_from_slice(&hash.finalize()),
+            Self::Sha256(hash) => digest.copy_from_slice(&hash.finalize()),
+            Self::Sha384(hash) => digest.copy_from_slice(&hash.finalize()),
+            Self::Sha512(hash) => digest.copy_from_slice(&hash.finalize()),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! Software implementations of the platform crypto traits backed by the
+//! [RustCrypto](https://github.com/RustCrypto) crates. These are meant for testing and for
+//! simulating a TPM on a host, not for production devices.
+
+mod cipher;
+mod ecc;
+mod hash;
+mod rsa;
+
+pub use cipher::RustCryptoCipher;
+pub use ecc::RustCryptoEcc;
+pub use hash::RustCryptoHash;
+pub use rsa::RustCryptoRsa;
@@ -0,0 +1,92 @@
+use rsa::hazmat::rsa_decrypt_and_check;
+use rsa::rand_core::{CryptoRng, Error, RngCore};
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, RsaPrivateKey, RsaPublicKey};
+
+use crate::platform::crypto::{Rsa, RsaError};
+
+/// Implements [`Rsa`] with the arbitrary-precision arithmetic of the `rsa` crate. The `rsa` crate
+/// allocates, so the platform has to provide a global allocator.
+pub struct RustCryptoRsa;
+
+fn private_key(n: &[u8], e: u32, prime: &[u8]) -> Result<RsaPrivateKey, RsaError> {
+    let n = BigUint::from_bytes_be(n);
+    let p = BigUint::from_bytes_be(prime);
+    if p <= BigUint::from(1u32) || &n % &p != BigUint::default() {
+        return Err(RsaError);
+    }
+    let q = &n / &p;
+    let key = RsaPrivateKey::from_p_q(p, q, BigUint::from(e)).or(Err(RsaError))?;
+    key.validate().or(Err(RsaError))?;
+    Ok(key)
+}
+
+/// Adapts the random source given to [`Rsa::private_op`] to the RNG traits of the `rsa` crate.
+struct BlindingRng<'a>(&'a mut dyn FnMut(&mut [u8]));
+
+impl RngCore for BlindingRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_be_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_be_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        (self.0)(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for BlindingRng<'_> {}
+
+fn write_result(result: BigUint, out: &mut [u8]) -> Result<(), RsaError> {
+    let bytes = result.to_bytes_be();
+    let padding = out.len().checked_sub(bytes.len()).ok_or(RsaError)?;
+    out[..padding].fill(0);
+    out[padding..].copy_from_slice(&bytes);
+    Ok(())
+}
+
+impl Rsa for RustCryptoRsa {
+    fn check_key(n: &[u8], e: u32, prime: &[u8]) -> Result<(), RsaError> {
+        private_key(n, e, prime).map(|_| ())
+    }
+
+    fn public_op(n: &[u8], e: u32, input: &[u8], out: &mut [u8]) -> Result<(), RsaError> {
+        let key =
+            RsaPublicKey::new(BigUint::from_bytes_be(n), BigUint::from(e)).or(Err(RsaError))?;
+        let input = BigUint::from_bytes_be(input);
+        if &input >= key.n() {
+            return Err(RsaError);
+        }
+        write_result(input.modpow(key.e(), key.n()), out)
+    }
+
+    fn private_op(
+        n: &[u8],
+        e: u32,
+        prime: &[u8],
+        input: &[u8],
+        out: &mut [u8],
+        random: &mut dyn FnMut(&mut [u8]),
+    ) -> Result<(), RsaError> {
+        let key = private_key(n, e, prime)?;
+        let input = BigUint::from_bytes_be(input);
+        if &input >= key.n() {
+            return Err(RsaError);
+        }
+        let result = rsa_decrypt_and_check(&key, Some(&mut BlindingRng(random)), &input)
+            .or(Err(RsaError))?;
+        write_result(result, out)
+    }
+}
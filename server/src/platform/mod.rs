@@ -1,8 +1,10 @@
 mod buffer;
+mod clock;
 pub mod crypto;
 
 pub use buffer::*;
-use crypto::{Drbg, EntropySource};
+pub use clock::Clock;
+use crypto::{BlockCipher, Drbg, Ecc, EntropySource, Hash, Rsa};
 
 /// Specifies all of the dependent types for [`TpmContext`].
 ///
@@ -12,6 +14,16 @@ pub trait TpmContextDeps {
     type Drbg: Drbg;
     /// Types for getting real entropy input
     type EntropySource: EntropySource;
+    /// Type for computing digests
+    type Hash: Hash;
+    /// Type for elliptic curve arithmetic
+    type Ecc: Ecc;
+    /// Type for RSA arithmetic
+    type Rsa: Rsa;
+    /// Type for encrypting and decrypting with symmetric block ciphers
+    type Cipher: BlockCipher;
+    /// Type for measuring the passage of time
+    type Clock: Clock;
     /// The type of the input request buffer for command processing.
     type Request: TpmReadBuffer + ?Sized;
     /// The type of the output response buffer for command processing.
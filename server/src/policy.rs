@@ -0,0 +1,128 @@
+use core::cmp::Ordering;
+
+use tpm2_rs_base::constants::{TpmCc, TpmEo};
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::{Tpm2bDigest, Tpm2bSimple, TpmaLocality, TpmiAlgHash};
+
+use crate::crypto::hash::{digest_size, Hasher, MAX_DIGEST_SIZE};
+use crate::platform::crypto::Hash;
+
+/// The assertions that the policy commands of a policy session accumulated, see [TPM2.0 1.83]
+/// Part 1, 19.7. They are checked when the session authorizes a command.
+#[derive(Clone, Copy)]
+pub struct PolicyState {
+    /// The digest of the policy commands executed in the session.
+    pub digest: Tpm2bDigest,
+    /// The value of `Time` when the policy was started, which the expiration of
+    /// TPM2_PolicySigned and TPM2_PolicySecret can be relative to.
+    pub start_time: u64,
+    /// The command the session may authorize, if restricted by TPM2_PolicyCommandCode.
+    pub command_code: Option<TpmCc>,
+    /// The cpHash of the command the session may authorize, if restricted.
+    pub cp_hash: Option<Tpm2bDigest>,
+    /// The digest of the Names of the handles of the command the session may authorize, if
+    /// restricted by TPM2_PolicyNameHash.
+    pub name_hash: Option<Tpm2bDigest>,
+    /// The localities at which the session may authorize commands, if restricted by
+    /// TPM2_PolicyLocality.
+    pub locality: Option<TpmaLocality>,
+    /// The value of `Time` after which the session can no longer authorize commands, if any.
+    pub timeout: Option<u64>,
+    /// Whether the HMAC of the session is keyed with the authorization value of the entity, as
+    /// required by TPM2_PolicyAuthValue.
+    pub auth_value_needed: bool,
+    /// Whether the HMAC of the session holds the authorization value of the entity in plain
+    /// text, as required by TPM2_PolicyPassword.
+    pub password_needed: bool,
+}
+
+impl PolicyState {
+    /// Starts a policy with a Zero Digest of `alg` at `start_time`.
+    pub fn new(alg: TpmiAlgHash, start_time: u64) -> Result<Self, TpmRcError> {
+        Ok(Self {
+            digest: zero_digest(alg)?,
+            start_time,
+            command_code: None,
+            cp_hash: None,
+            name_hash: None,
+            locality: None,
+            timeout: None,
+            auth_value_needed: false,
+            password_needed: false,
+        })
+    }
+
+    /// Returns whether the policy allows authorizing a command sent at `locality`.
+    pub fn allows_locality(&self, locality: u8) -> bool {
+        match self.locality {
+            None => true,
+            Some(allowed) if allowed.is_extended() => allowed.0 == locality,
+            // Localities 0 to 4 are the low bits of the attribute.
+            Some(allowed) => {
+                (allowed.0.checked_shr(locality.into())).is_some_and(|bits| bits & 1 == 1)
+            }
+        }
+    }
+
+    /// Extends the policy digest with `data`, which is usually the command code of a policy
+    /// command followed by its arguments.
+    pub fn extend<H: Hash>(&mut self, alg: TpmiAlgHash, data: &[&[u8]]) -> Result<(), TpmRcError> {
+        let mut hasher = Hasher::<H>::start(alg)?;
+        hasher.update(self.digest.get_buffer());
+        for part in data {
+            hasher.update(part);
+        }
+        self.digest =
+            Tpm2bDigest::from_bytes(hasher.finish().as_ref()).or(Err(TpmRcError::Failure))?;
+        Ok(())
+    }
+
+    /// Sets the cpHash that the authorized command must have. Returns [`TpmRcError::CpHash`] if
+    /// a different cpHash or a nameHash was set before.
+    pub fn set_cp_hash(&mut self, cp_hash: &Tpm2bDigest) -> Result<(), TpmRcError> {
+        if self.name_hash.is_some() || self.cp_hash.is_some_and(|set| set != *cp_hash) {
+            return Err(TpmRcError::CpHash);
+        }
+        self.cp_hash = Some(*cp_hash);
+        Ok(())
+    }
+
+    /// Shortens the timeout of the session to `timeout` if it is earlier.
+    pub fn set_timeout(&mut self, timeout: u64) {
+        self.timeout = Some(self.timeout.map_or(timeout, |set| set.min(timeout)));
+    }
+}
+
+/// Returns a digest of `alg` that is all zeros.
+pub fn zero_digest(alg: TpmiAlgHash) -> Result<Tpm2bDigest, TpmRcError> {
+    let size = digest_size(alg).ok_or(TpmRcError::Hash)?;
+    Tpm2bDigest::from_bytes(&[0; MAX_DIGEST_SIZE][..size]).or(Err(TpmRcError::Failure))
+}
+
+/// Compares `operand_a` with `operand_b` of the same size, both big-endian integers, as
+/// TPM2_PolicyCounterTimer and TPM2_PolicyNV do. Returns `None` for an unknown `operation`.
+pub fn compare(operand_a: &[u8], operand_b: &[u8], operation: TpmEo) -> Option<bool> {
+    let unsigned = operand_a.cmp(operand_b);
+    // Two's complement integers with the same sign compare like unsigned ones.
+    let signed = match (operand_a.first(), operand_b.first()) {
+        (Some(a), Some(b)) if (a ^ b) & 0x80 != 0 => b.cmp(a),
+        _ => unsigned,
+    };
+    let mut bits = operand_a.iter().zip(operand_b);
+    let result = match operation {
+        TpmEo::Eq => unsigned == Ordering::Equal,
+        TpmEo::Neq => unsigned != Ordering::Equal,
+        TpmEo::SignedGT => signed == Ordering::Greater,
+        TpmEo::UnsignedGT => unsigned == Ordering::Greater,
+        TpmEo::SignedLT => signed == Ordering::Less,
+        TpmEo::UnsignedLT => unsigned == Ordering::Less,
+        TpmEo::SignedGE => signed != Ordering::Less,
+        TpmEo::UnsignedGE => unsigned != Ordering::Less,
+        TpmEo::SignedLE => signed != Ordering::Greater,
+        TpmEo::UnsignedLE => unsigned != Ordering::Greater,
+        TpmEo::BitSet => bits.all(|(a, b)| a & b == *b),
+        TpmEo::BitClear => bits.all(|(a, b)| a & b == 0),
+        _ => return None,
+    };
+    Some(result)
+}
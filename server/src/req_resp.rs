@@ -1,4 +1,21 @@
-use crate::platform::{TpmBuffers, TpmReadBuffer, TpmWriteBuffer, WriteOutOfBounds};
+use crate::platform::{
+    ReadOutOfBounds, TpmBuffers, TpmReadBuffer, TpmWriteBuffer, WriteOutOfBounds,
+};
+use tpm2_rs_base::constants::TpmHandle;
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::marshal::{self, Marshalable, UnmarshalBuf};
+
+/// The size of the header at the start of every response.
+pub const RESPONSE_HEADER_SIZE: usize = 10;
+
+/// Converts a failure to unmarshal part of the request into the corresponding [`TpmRcError`].
+pub fn unmarshal_error(error: marshal::Error) -> TpmRcError {
+    match error {
+        marshal::Error::ArrayLengthExceeded => TpmRcError::Size,
+        marshal::Error::UnexpectedEndOfBuffer => TpmRcError::Insufficient,
+        marshal::Error::UnknownSelector => TpmRcError::Selector,
+    }
+}
 
 /// Provides access to the TPM command request object and then a one-way conversion to the mutable
 /// response object for the TPM command.
@@ -11,28 +28,98 @@ impl<'a, B: TpmBuffers> RequestThenResponse<'a, B> {
     /// last position past this field. Returns `None` if the read would have read past the end of
     /// the request.
     pub fn read_be_u16(&mut self) -> Option<u16> {
-        let result = self
-            .buffers
-            .buffers
-            .get_request()
-            .read_be_u16(self.buffers.request_offset)
+        let mut bytes = [0; size_of::<u16>()];
+        self.buffers
+            .read_request(self.buffers.request_offset, bytes.len(), |data| {
+                bytes.copy_from_slice(data)
+            })
             .ok()?;
-        self.buffers.request_offset += core::mem::size_of::<u16>();
-        Some(result)
+        self.buffers.request_offset += bytes.len();
+        Some(u16::from_be_bytes(bytes))
     }
 
     /// Reads a `u32` encoded in big endian from the request's last read position. Increments the
     /// last position past this field. Returns `None` if the read would have read past the end of
     /// the request.
     pub fn read_be_u32(&mut self) -> Option<u32> {
-        let result = self
+        let mut bytes = [0; size_of::<u32>()];
+        self.buffers
+            .read_request(self.buffers.request_offset, bytes.len(), |data| {
+                bytes.copy_from_slice(data)
+            })
+            .ok()?;
+        self.buffers.request_offset += bytes.len();
+        Some(u32::from_be_bytes(bytes))
+    }
+
+    /// Unmarshals a `T` from the request's last read position. Increments the last position past the
+    /// bytes that were consumed.
+    pub fn unmarshal<T: Marshalable>(&mut self) -> Result<T, TpmRcError> {
+        let offset = self.buffers.request_offset;
+        let remaining = self.buffers.request_len().saturating_sub(offset);
+        let (value, consumed) = self
             .buffers
+            .read_request(offset, remaining, |data| -> Result<_, TpmRcError> {
+                let mut buffer = UnmarshalBuf::new(data);
+                let value = T::try_unmarshal(&mut buffer).map_err(unmarshal_error)?;
+                Ok((value, data.len() - buffer.len()))
+            })
+            .or(Err(TpmRcError::CommandSize))??;
+        self.buffers.request_offset += consumed;
+        Ok(value)
+    }
+
+    /// Passes the rest of the request after the last read position to `callback` without
+    /// consuming it, e.g. to compute the cpHash of the command parameters before they are
+    /// overwritten by the response.
+    pub fn read_remaining<R>(
+        &mut self,
+        callback: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, TpmRcError> {
+        let offset = self.buffers.request_offset;
+        let remaining = self.buffers.request_len().saturating_sub(offset);
+        self.buffers
+            .read_request(offset, remaining, callback)
+            .or(Err(TpmRcError::CommandSize))
+    }
+
+    /// Passes the data of the TPM2B parameter at the request's last read position to `callback`,
+    /// which modifies it in place, e.g. to decrypt it, without consuming it. The request may be
+    /// read-only, so it is copied to the response buffer first, which has to be at least as large,
+    /// and read from there as if it was processed in place.
+    pub fn modify_parameter(
+        &mut self,
+        callback: impl FnOnce(&mut [u8]) -> Result<(), TpmRcError>,
+    ) -> Result<(), TpmRcError> {
+        let offset = self.buffers.request_offset;
+        let mut size = [0; size_of::<u16>()];
+        self.buffers
+            .read_request(offset, size.len(), |data| size.copy_from_slice(data))
+            .or(Err(TpmRcError::Insufficient))?;
+        let start = offset + size.len();
+        let size = u16::from_be_bytes(size) as usize;
+        if start + size > self.buffers.request_len() {
+            return Err(TpmRcError::Size);
+        }
+        self.buffers.move_request_to_response()?;
+        let mut result = Err(TpmRcError::Failure);
+        self.buffers
             .buffers
-            .get_request()
-            .read_be_u32(self.buffers.request_offset)
-            .ok()?;
-        self.buffers.request_offset += core::mem::size_of::<u32>();
-        Some(result)
+            .get_response()
+            .write_callback(start, size, |data| result = callback(data))
+            .or(Err(TpmRcError::Failure))?;
+        result
+    }
+
+    /// Returns the request's last read position.
+    pub fn position(&self) -> usize {
+        self.buffers.request_offset
+    }
+
+    /// Skips `size` bytes of the response, e.g. to leave room for the response handles. These
+    /// bytes are not counted as written until they are skipped by this call.
+    pub fn reserve_response(&mut self, size: usize) {
+        self.buffers.response_offset += size;
     }
 
     /// Converts this request view into a mutable response that can be written to.
@@ -52,10 +139,6 @@ impl<B: TpmBuffers> Response<'_, B> {
     /// Writes the specified `data` at the last written location and updates the internal
     /// last written location. Returns [`WriteOutOfBounds`] if write would have written past the the
     /// of the underlying [`TpmWriteBuffer`].
-    #[expect(
-        dead_code,
-        reason = "This function may be used later in the future, but it is not yet"
-    )]
     pub fn write(&mut self, data: &[u8]) -> Result<(), WriteOutOfBounds> {
         self.buffers
             .buffers
@@ -81,6 +164,34 @@ impl<B: TpmBuffers> Response<'_, B> {
         self.buffers.response_offset += size;
         Ok(())
     }
+
+    /// Writes the response handle at `index` in the response handle area, which starts right
+    /// after the response header and must have been reserved before the response parameters.
+    pub fn write_handle(&mut self, index: usize, handle: TpmHandle) -> Result<(), TpmRcError> {
+        let offset = RESPONSE_HEADER_SIZE + index * core::mem::size_of::<u32>();
+        self.buffers
+            .buffers
+            .get_response()
+            .write(offset, &handle.0.to_be_bytes())
+            .or(Err(TpmRcError::Memory))
+    }
+
+    /// Marshals `value` at the last written location and updates the last written location. Returns
+    /// [`TpmRcError::Memory`] if `value` does not fit in the rest of the underlying
+    /// [`TpmWriteBuffer`].
+    pub fn marshal(&mut self, value: &impl Marshalable) -> Result<(), TpmRcError> {
+        let offset = self.buffers.response_offset;
+        let response = self.buffers.buffers.get_response();
+        let remaining = response.len().saturating_sub(offset);
+        let mut written = Err(TpmRcError::Memory);
+        response
+            .write_callback(offset, remaining, |buffer| {
+                written = value.try_marshal(buffer).or(Err(TpmRcError::Memory));
+            })
+            .or(Err(TpmRcError::Memory))?;
+        self.buffers.response_offset += written?;
+        Ok(())
+    }
 }
 
 /// Provides access to request and response while along tracking most recent read and written
@@ -89,6 +200,9 @@ pub struct RequestResponseCursor<B: TpmBuffers> {
     buffers: B,
     request_offset: usize,
     response_offset: usize,
+    /// Whether the request was copied to the start of the response buffer to be modified there, in
+    /// which case it is read from the response buffer.
+    request_in_response: bool,
 }
 
 impl<B: TpmBuffers> RequestResponseCursor<B> {
@@ -99,9 +213,61 @@ impl<B: TpmBuffers> RequestResponseCursor<B> {
             buffers,
             request_offset: 0,
             response_offset,
+            request_in_response: false,
         }
     }
 
+    /// Returns the size of the request.
+    fn request_len(&self) -> usize {
+        self.buffers.get_request().len()
+    }
+
+    /// Passes `size` bytes of the request at `offset` to `callback`.
+    fn read_request<R>(
+        &mut self,
+        offset: usize,
+        size: usize,
+        callback: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, ReadOutOfBounds> {
+        if !self.request_in_response {
+            return self
+                .buffers
+                .get_request()
+                .read_callback(offset, size, callback);
+        }
+        // Only the part of the response buffer that holds the request may be read.
+        if offset + size > self.request_len() {
+            return Err(ReadOutOfBounds);
+        }
+        self.buffers
+            .get_response()
+            .read_callback(offset, size, callback)
+    }
+
+    /// Copies the request to the start of the response buffer, unless it already is there. Like
+    /// a request processed in place, it is overwritten by the response from then on.
+    fn move_request_to_response(&mut self) -> Result<(), TpmRcError> {
+        const CHUNK_SIZE: usize = 64;
+        if self.request_in_response {
+            return Ok(());
+        }
+        let mut chunk = [0; CHUNK_SIZE];
+        let len = self.request_len();
+        for offset in (0..len).step_by(CHUNK_SIZE) {
+            let chunk = &mut chunk[..CHUNK_SIZE.min(len - offset)];
+            self.buffers
+                .get_request()
+                .read_into(offset, chunk)
+                .or(Err(TpmRcError::Failure))?;
+            self.buffers
+                .get_response()
+                .write(offset, chunk)
+                .or(Err(TpmRcError::Memory))?;
+        }
+        self.request_in_response = true;
+        Ok(())
+    }
+
     /// Gets the [`RequestThenResponse`] that can access the request, then be converted into a
     /// response view.
     pub fn request(&mut self) -> RequestThenResponse<'_, B> {
@@ -0,0 +1,331 @@
+use tpm2_rs_base::constants::{TpmCc, TpmHandle, TpmHc, TpmSe, TPM2_MAX_SYM_KEY_BYTES};
+use tpm2_rs_base::errors::{ErrorPosition, ErrorType, TpmRcError};
+use tpm2_rs_base::{
+    Tpm2bAuth, Tpm2bDigest, Tpm2bName, Tpm2bNonce, Tpm2bSimple, TpmaSession, TpmiAlgHash,
+    TpmiAlgSymObject, TpmtSymDef,
+};
+
+use crate::crypto::constant_time_eq;
+use crate::crypto::hash::{digest, digest_size, Digest, Hasher, MAX_DIGEST_SIZE};
+use crate::crypto::hmac::hmac;
+use crate::crypto::kdf::{kdfa, kdfa_xor};
+use crate::crypto::symmetric::{sym_crypt, Direction, SYM_BLOCK_SIZE};
+use crate::platform::crypto::{BlockCipher, Hash};
+use crate::policy::PolicyState;
+
+/// The number of sessions that can be loaded at the same time.
+pub const MAX_LOADED_SESSIONS: usize = 3;
+
+/// The smallest nonce a caller may use in a session.
+pub const MIN_NONCE_SIZE: usize = 16;
+
+/// An authorization session that was started with TPM2_StartAuthSession.
+pub struct Session {
+    /// The type of the session.
+    pub session_type: TpmSe,
+    /// The hash algorithm of the session's HMACs and digests.
+    pub auth_hash: TpmiAlgHash,
+    /// The algorithm that encrypts the parameters of the commands using the session.
+    pub symmetric: TpmtSymDef,
+    /// The key derived from the bind authorization value and the salt, which is empty if the
+    /// session is neither bound nor salted.
+    pub session_key: Tpm2bDigest,
+    /// The nonce the TPM generated for the last response in the session.
+    pub nonce_tpm: Tpm2bNonce,
+    /// The Name and authorization value of the entity the session is bound to, if any.
+    pub bind: Option<(Tpm2bName, Tpm2bAuth)>,
+    /// The digest of the commands and responses audited by the session.
+    pub audit_digest: Tpm2bDigest,
+    /// The policy accumulated by a policy or trial session, which is unused in HMAC sessions.
+    pub policy: PolicyState,
+}
+
+impl Session {
+    /// Returns whether the session is a policy session, including trial sessions.
+    pub fn is_policy(&self) -> bool {
+        self.session_type == TpmSe::Policy || self.is_trial()
+    }
+
+    /// Returns whether the session is a trial session, which only computes a policy digest.
+    pub fn is_trial(&self) -> bool {
+        self.session_type == TpmSe::Trial
+    }
+
+    /// Returns whether the session is bound to the entity with `name` and `auth_value`, in which
+    /// case the authorization value is already part of the session key.
+    pub fn is_bound_to(&self, name: &Tpm2bName, auth_value: &[u8]) -> bool {
+        self.bind.as_ref().is_some_and(|(bind_name, bind_auth)| {
+            bind_name == name && constant_time_eq(bind_auth.get_buffer(), auth_value)
+        })
+    }
+
+    /// Copies the session key followed by `auth_value` into `buffer`, which keys the HMACs and the
+    /// parameter encryption of the session.
+    fn session_value<'a>(
+        &self,
+        auth_value: &[u8],
+        buffer: &'a mut [u8; 2 * MAX_DIGEST_SIZE],
+    ) -> &'a [u8] {
+        let session_key = self.session_key.get_buffer();
+        let size = session_key.len() + auth_value.len();
+        buffer[..session_key.len()].copy_from_slice(session_key);
+        buffer[session_key.len()..size].copy_from_slice(auth_value);
+        &buffer[..size]
+    }
+
+    /// Computes the HMAC of a command or response in the session, keyed with the session key
+    /// followed by `auth_value`. `nonce_newer` is the nonce of the sender.
+    pub fn hmac<H: Hash>(
+        &self,
+        auth_value: &[u8],
+        parameter_hash: &[u8],
+        nonce_newer: &[u8],
+        nonce_older: &[u8],
+        attributes: TpmaSession,
+    ) -> Result<Digest, TpmRcError> {
+        let mut key = [0; 2 * MAX_DIGEST_SIZE];
+        hmac::<H>(
+            self.auth_hash,
+            self.session_value(auth_value, &mut key),
+            &[parameter_hash, nonce_newer, nonce_older, &[attributes.0]],
+        )
+    }
+
+    /// Encrypts or decrypts the data of a parameter in place with the symmetric algorithm of the
+    /// session, keyed with the session key followed by `auth_value`, as described in
+    /// [TPM2.0 1.83] Part 1, 21. `nonce_newer` is the nonce of the sender.
+    pub fn crypt_parameter<H: Hash, C: BlockCipher>(
+        &self,
+        auth_value: &[u8],
+        nonce_newer: &[u8],
+        nonce_older: &[u8],
+        direction: Direction,
+        data: &mut [u8],
+    ) -> Result<(), TpmRcError> {
+        let mut key = [0; 2 * MAX_DIGEST_SIZE];
+        let session_value = self.session_value(auth_value, &mut key);
+        match self.symmetric {
+            TpmtSymDef::Aes(key_bits, mode) => {
+                // KDFa provides the key followed by the IV.
+                let key_size = key_bits.0 as usize / 8;
+                let mut key_and_iv = [0; TPM2_MAX_SYM_KEY_BYTES as usize + SYM_BLOCK_SIZE];
+                let key_and_iv = key_and_iv
+                    .get_mut(..key_size + SYM_BLOCK_SIZE)
+                    .ok_or(TpmRcError::Failure)?;
+                kdfa::<H>(
+                    self.auth_hash,
+                    session_value,
+                    b"CFB",
+                    nonce_newer,
+                    nonce_older,
+                    key_and_iv,
+                )?;
+                let (key, iv) = key_and_iv.split_at_mut(key_size);
+                let cipher = C::new(TpmiAlgSymObject::AES, key).ok_or(TpmRcError::Failure)?;
+                sym_crypt(&cipher, mode, direction, iv, data)
+            }
+            TpmtSymDef::ExclusiveOr(hash, _) => {
+                kdfa_xor::<H>(hash, session_value, b"XOR", nonce_newer, nonce_older, data)
+            }
+            // Sessions without a symmetric algorithm can't encrypt parameters.
+            _ => Err(TpmRcError::Symmetric),
+        }
+    }
+
+    /// Checks that the policy of the session allows authorizing an entity with `entity_policy`,
+    /// its name algorithm and authorization policy, for the command with `command_code`,
+    /// `cp_hash` and the Names of its handles at `now`. `at` is the position of the session in
+    /// the authorization area.
+    pub fn check_policy<H: Hash>(
+        &self,
+        entity_policy: Option<(TpmiAlgHash, &Tpm2bDigest)>,
+        command_code: TpmCc,
+        cp_hash: &[u8],
+        names: &[Tpm2bName],
+        now: u64,
+        at: ErrorPosition,
+    ) -> Result<(), TpmRcError> {
+        // Trial sessions only compute policies and never authorize anything.
+        if self.is_trial() {
+            return Err(TpmRcError::AttributesFor(ErrorType::Session, at));
+        }
+        let policy_fail = TpmRcError::PolicyFailFor(ErrorType::Session, at);
+        let policy = &self.policy;
+        match entity_policy {
+            Some((name_alg, digest)) if name_alg == self.auth_hash && *digest == policy.digest => {}
+            _ => return Err(policy_fail),
+        }
+        if policy.command_code.is_some_and(|code| code != command_code) {
+            return Err(TpmRcError::PolicyCcFor(ErrorType::Session, at));
+        }
+        if policy
+            .cp_hash
+            .is_some_and(|expected| expected.get_buffer() != cp_hash)
+        {
+            return Err(policy_fail);
+        }
+        if let Some(expected) = policy.name_hash {
+            let mut hasher = Hasher::<H>::start(self.auth_hash)?;
+            for name in names {
+                hasher.update(name.get_buffer());
+            }
+            if hasher.finish().as_ref() != expected.get_buffer() {
+                return Err(policy_fail);
+            }
+        }
+        if policy.timeout.is_some_and(|timeout| now > timeout) {
+            return Err(TpmRcError::ExpiredFor(ErrorType::Session, at));
+        }
+        Ok(())
+    }
+
+    /// Clears the audit digest of the session to a Zero Digest.
+    pub fn reset_audit(&mut self) -> Result<(), TpmRcError> {
+        let zeros = [0; MAX_DIGEST_SIZE];
+        let size = digest_size(self.auth_hash).ok_or(TpmRcError::Hash)?;
+        self.audit_digest = Tpm2bDigest::from_bytes(&zeros[..size]).or(Err(TpmRcError::Failure))?;
+        Ok(())
+    }
+
+    /// Extends the audit digest of the session with a command and its response.
+    pub fn extend_audit<H: Hash>(
+        &mut self,
+        cp_hash: &[u8],
+        rp_hash: &[u8],
+    ) -> Result<(), TpmRcError> {
+        let extended = digest::<H>(
+            self.auth_hash,
+            &[self.audit_digest.get_buffer(), cp_hash, rp_hash],
+        )?;
+        self.audit_digest =
+            Tpm2bDigest::from_bytes(extended.as_ref()).or(Err(TpmRcError::Failure))?;
+        Ok(())
+    }
+}
+
+/// Computes the cpHash of a command, which is the digest of its command code, the Names of its
+/// handles and its parameters.
+pub fn cp_hash<H: Hash>(
+    alg: TpmiAlgHash,
+    command_code: TpmCc,
+    names: &[Tpm2bName],
+    parameters: &[u8],
+) -> Result<Digest, TpmRcError> {
+    let mut hasher = Hasher::<H>::start(alg)?;
+    hasher.update(&command_code.0.to_be_bytes());
+    for name in names {
+        hasher.update(name.get_buffer());
+    }
+    hasher.update(parameters);
+    Ok(hasher.finish())
+}
+
+/// Computes the rpHash of a successful response, which is the digest of the response code, the
+/// command code and the response parameters.
+pub fn rp_hash<H: Hash>(
+    alg: TpmiAlgHash,
+    command_code: TpmCc,
+    parameters: &[u8],
+) -> Result<Digest, TpmRcError> {
+    const SUCCESS_STATUS: u32 = 0;
+    digest::<H>(
+        alg,
+        &[
+            &SUCCESS_STATUS.to_be_bytes(),
+            &command_code.0.to_be_bytes(),
+            parameters,
+        ],
+    )
+}
+
+/// The slots that hold the sessions currently loaded into the TPM. The handle of a session is
+/// derived from the index of its slot and whether it is a policy session.
+pub struct SessionSlots {
+    slots: [Option<Session>; MAX_LOADED_SESSIONS],
+    /// The session that audited every command since its audit digest was reset, if any.
+    exclusive_audit: Option<TpmHandle>,
+}
+
+impl SessionSlots {
+    /// Creates the session slots with no sessions loaded.
+    pub fn new() -> Self {
+        Self {
+            slots: [const { None }; MAX_LOADED_SESSIONS],
+            exclusive_audit: None,
+        }
+    }
+
+    fn index(handle: TpmHandle) -> Option<usize> {
+        let first = if TpmHc::is_hmac_session(handle.0) {
+            TpmHc::HmacSessionFirst
+        } else if TpmHc::is_policy_session(handle.0) {
+            TpmHc::PolicySessionFirst
+        } else {
+            return None;
+        };
+        let index = (handle.0 - first.get()) as usize;
+        (index < MAX_LOADED_SESSIONS).then_some(index)
+    }
+
+    /// Returns the handle of `session` in the slot at `index`.
+    fn handle(index: usize, session: &Session) -> TpmHandle {
+        let first = if session.is_policy() {
+            TpmHc::PolicySessionFirst
+        } else {
+            TpmHc::HmacSessionFirst
+        };
+        TpmHandle(first.get() + index as u32)
+    }
+
+    /// Returns the index of the slot holding the session referenced by `handle`, if any.
+    fn loaded_index(&self, handle: TpmHandle) -> Option<usize> {
+        let index = Self::index(handle)?;
+        let session = self.slots[index].as_ref()?;
+        (Self::handle(index, session) == handle).then_some(index)
+    }
+
+    /// Loads `session` into a free slot and returns its handle. Returns
+    /// [`TpmRcError::SessionMemory`] if all slots are in use.
+    pub fn insert(&mut self, session: Session) -> Result<TpmHandle, TpmRcError> {
+        let (index, slot) = self
+            .slots
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| slot.is_none())
+            .ok_or(TpmRcError::SessionMemory)?;
+        let handle = Self::handle(index, &session);
+        *slot = Some(session);
+        Ok(handle)
+    }
+
+    /// Gets the session referenced by `handle`, if any.
+    pub fn get(&self, handle: TpmHandle) -> Option<&Session> {
+        self.slots[self.loaded_index(handle)?].as_ref()
+    }
+
+    /// Gets the session referenced by `handle` for modification, if any.
+    pub fn get_mut(&mut self, handle: TpmHandle) -> Option<&mut Session> {
+        let index = self.loaded_index(handle)?;
+        self.slots[index].as_mut()
+    }
+
+    /// Ends the session referenced by `handle`. Returns `None` if no session was loaded.
+    pub fn remove(&mut self, handle: TpmHandle) -> Option<Session> {
+        if self.exclusive_audit == Some(handle) {
+            self.exclusive_audit = None;
+        }
+        let index = self.loaded_index(handle)?;
+        self.slots[index].take()
+    }
+
+    /// Returns whether the session referenced by `handle` is the exclusive audit session.
+    pub fn is_exclusive_audit(&self, handle: TpmHandle) -> bool {
+        self.exclusive_audit == Some(handle)
+    }
+
+    /// Makes the session referenced by `handle` the exclusive audit session, or makes no session
+    /// exclusive if `handle` is `None`.
+    pub fn set_exclusive_audit(&mut self, handle: Option<TpmHandle>) {
+        self.exclusive_audit = handle;
+    }
+}
@@ -0,0 +1,381 @@
+//! A frontend that serves a [`TpmContext`] over the protocol of the TCG TPM 2.0 Simulator.
+//!
+//! The simulator protocol has two channels: the TPM port (2321 by default) carries TPM commands
+//! and the platform port (2322 by default) carries the signals of the platform, such as power and
+//! NV availability. Tools that drive the reference simulator, such as the `mssim` TCTI of
+//! tpm2-tools or the `TcpConnection` of `tpm2-rs-client`, can drive this TPM through [`serve`].
+//!
+//! The TPM keeps no state across power cycles, so powering it on always creates a new
+//! [`TpmContext`]. Physical presence, cancellation and NV availability are tracked, but no
+//! command of this TPM depends on them yet.
+extern crate std;
+
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::net::TcpListener;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::vec;
+
+use tpm2_rs_base::constants::TpmSt;
+use tpm2_rs_base::errors::TpmRcError;
+
+use crate::platform::TpmContextDeps;
+use crate::TpmContext;
+
+/// The default TPM port of the simulator.
+pub const DEFAULT_TPM_PORT: u16 = 2321;
+
+/// The default platform port of the simulator.
+pub const DEFAULT_PLATFORM_PORT: u16 = 2322;
+
+/// The largest command or response that the simulator exchanges.
+const MAX_BUFFER_SIZE: usize = 4096;
+
+/// The version of the simulator protocol that this frontend speaks.
+const SERVER_VERSION: u32 = 1;
+
+/// The endpoint information returned by the remote handshake: the TPM plays nice, does not use
+/// TBS, is in raw mode and supports physical presence.
+const ENDPOINT_INFO: u32 = 0x0F;
+
+// The commands and signals of the simulator protocol, which are sent as big-endian `u32`s.
+const SIGNAL_POWER_ON: u32 = 1;
+const SIGNAL_POWER_OFF: u32 = 2;
+const SIGNAL_PHYS_PRES_ON: u32 = 3;
+const SIGNAL_PHYS_PRES_OFF: u32 = 4;
+/// Starts the H-CRTM sequence, which is accepted but does not extend any PCR.
+const SIGNAL_HASH_START: u32 = 5;
+/// Hashes data in the H-CRTM sequence, which is read and dropped.
+const SIGNAL_HASH_DATA: u32 = 6;
+/// Ends the H-CRTM sequence, which is accepted but does not extend any PCR.
+const SIGNAL_HASH_END: u32 = 7;
+const SEND_COMMAND: u32 = 8;
+const SIGNAL_CANCEL_ON: u32 = 9;
+const SIGNAL_CANCEL_OFF: u32 = 10;
+const SIGNAL_NV_ON: u32 = 11;
+const SIGNAL_NV_OFF: u32 = 12;
+const SIGNAL_KEY_CACHE_ON: u32 = 13;
+const SIGNAL_KEY_CACHE_OFF: u32 = 14;
+const REMOTE_HANDSHAKE: u32 = 15;
+const SET_ALTERNATIVE_RESULT: u32 = 16;
+/// Resets the TPM. Without state that survives a power cycle, this is a power cycle, like
+/// [`SIGNAL_RESTART`].
+const SIGNAL_RESET: u32 = 17;
+/// Restarts the TPM, which is a power cycle like [`SIGNAL_RESET`].
+const SIGNAL_RESTART: u32 = 18;
+const SESSION_END: u32 = 20;
+const STOP: u32 = 21;
+/// Returns the sizes and codes of the largest command and response. The sizes are in the byte
+/// order of the simulator and the codes in big endian.
+const GET_COMMAND_RESPONSE_SIZES: u32 = 25;
+/// Returns whether an Authenticated Countdown Timer was signaled. This TPM has no Authenticated
+/// Countdown Timers, so none ever is.
+const ACT_GET_SIGNALED: u32 = 26;
+const TEST_FAILURE_MODE: u32 = 30;
+const SET_FIRMWARE_HASH: u32 = 35;
+const SET_FIRMWARE_SVN: u32 = 36;
+
+/// How a connection to one of the ports of the simulator ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Disconnect {
+    /// The client ended its session or closed the connection, after which the simulator waits for
+    /// the next client.
+    SessionEnd,
+    /// The client asked the simulator to stop.
+    Stop,
+}
+
+/// The state of a simulated TPM and of the platform around it.
+///
+/// The cancel and NV signals only set the flags that [`Simulator::cancel`] and
+/// [`Simulator::nv_available`] return: `SIGNAL_CANCEL_ON` does not cancel the current command and
+/// `SIGNAL_NV_OFF` does not make the commands that use NV memory fail.
+pub struct Simulator<Deps: TpmContextDeps<Request = [u8], Response = [u8]>> {
+    /// The TPM while it is powered on.
+    tpm: Option<TpmContext<Deps>>,
+    /// Whether the NV memory of the TPM is available.
+    nv_available: bool,
+    /// Whether physical presence is asserted.
+    physical_presence: bool,
+    /// Whether the platform asks the TPM to cancel the current command.
+    cancel: bool,
+    /// Whether the TPM was forced into failure mode, in which it fails every command.
+    failure_mode: bool,
+    /// The size and code of the largest command since they were last read.
+    largest_command: (u32, u32),
+    /// The size and response code of the largest response since they were last read.
+    largest_response: (u32, u32),
+}
+
+impl<Deps: TpmContextDeps<Request = [u8], Response = [u8]>> Default for Simulator<Deps> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Deps: TpmContextDeps<Request = [u8], Response = [u8]>> Simulator<Deps> {
+    /// Creates a simulator whose TPM is powered off.
+    pub fn new() -> Self {
+        Simulator {
+            tpm: None,
+            nv_available: false,
+            physical_presence: false,
+            cancel: false,
+            failure_mode: false,
+            largest_command: (0, 0),
+            largest_response: (0, 0),
+        }
+    }
+
+    /// Powers the TPM on, which creates a new TPM if it was off.
+    pub fn power_on(&mut self) -> Result<()> {
+        if self.tpm.is_none() {
+            self.tpm = Some(TpmContext::new().map_err(Error::other)?);
+            self.failure_mode = false;
+        }
+        Ok(())
+    }
+
+    /// Powers the TPM off, which loses all of its state.
+    pub fn power_off(&mut self) {
+        self.tpm = None;
+    }
+
+    /// Returns whether the TPM is powered on.
+    pub fn is_powered_on(&self) -> bool {
+        self.tpm.is_some()
+    }
+
+    /// Returns whether the NV memory of the TPM is available.
+    pub fn nv_available(&self) -> bool {
+        self.nv_available
+    }
+
+    /// Returns whether physical presence is asserted.
+    pub fn physical_presence(&self) -> bool {
+        self.physical_presence
+    }
+
+    /// Returns whether the platform asks the TPM to cancel the current command.
+    pub fn cancel(&self) -> bool {
+        self.cancel
+    }
+
+    /// Executes `command` at `locality` and writes the response in `response`. Returns the size
+    /// of the response, which is a `TPM_RC_FAILURE` error if the TPM is off or in failure mode.
+    pub fn execute_command(&mut self, locality: u8, command: &[u8], response: &mut [u8]) -> usize {
+        let size = match self.tpm.as_mut() {
+            Some(tpm) if !self.failure_mode => {
+                tpm.set_locality(locality);
+                tpm.execute_command_separate(command, response)
+            }
+            _ => failure_response(response),
+        };
+        if command.len() > self.largest_command.0 as usize {
+            self.largest_command = (command.len() as u32, read_u32(command, 6));
+        }
+        if size > self.largest_response.0 as usize {
+            self.largest_response = (size as u32, read_u32(&response[..size], 6));
+        }
+        size
+    }
+
+    /// Serves the TPM port of the simulator on `stream` until the client disconnects.
+    pub fn serve_tpm<S: Read + Write>(
+        simulator: &Mutex<Self>,
+        mut stream: S,
+    ) -> Result<Disconnect> {
+        let mut command = vec![0; MAX_BUFFER_SIZE];
+        let mut response = vec![0; MAX_BUFFER_SIZE];
+        loop {
+            let Some(code) = read_code(&mut stream)? else {
+                return Ok(Disconnect::SessionEnd);
+            };
+            match code {
+                SEND_COMMAND => {
+                    let mut locality = [0];
+                    stream.read_exact(&mut locality)?;
+                    let command = read_buffer(&mut stream, &mut command)?;
+                    let size = lock(simulator).execute_command(locality[0], command, &mut response);
+                    write_buffer(&mut stream, &response[..size])?;
+                }
+                REMOTE_HANDSHAKE => {
+                    let client_version = read_be_u32(&mut stream)?;
+                    if client_version < SERVER_VERSION {
+                        return Err(Error::new(
+                            ErrorKind::Unsupported,
+                            "unsupported client version",
+                        ));
+                    }
+                    stream.write_all(&SERVER_VERSION.to_be_bytes())?;
+                    stream.write_all(&ENDPOINT_INFO.to_be_bytes())?;
+                }
+                SIGNAL_HASH_START | SIGNAL_HASH_END => {}
+                SIGNAL_HASH_DATA => {
+                    read_buffer(&mut stream, &mut command)?;
+                }
+                SET_ALTERNATIVE_RESULT => {
+                    read_be_u32(&mut stream)?;
+                }
+                SESSION_END => return Ok(Disconnect::SessionEnd),
+                STOP => return Ok(Disconnect::Stop),
+                code => lock(simulator).signal(code, &mut stream)?,
+            }
+            stream.write_all(&0u32.to_be_bytes())?;
+        }
+    }
+
+    /// Serves the platform port of the simulator on `stream` until the client disconnects.
+    pub fn serve_platform<S: Read + Write>(
+        simulator: &Mutex<Self>,
+        mut stream: S,
+    ) -> Result<Disconnect> {
+        loop {
+            let Some(code) = read_code(&mut stream)? else {
+                return Ok(Disconnect::SessionEnd);
+            };
+            match code {
+                SESSION_END => return Ok(Disconnect::SessionEnd),
+                STOP => return Ok(Disconnect::Stop),
+                code => lock(simulator).signal(code, &mut stream)?,
+            }
+            stream.write_all(&0u32.to_be_bytes())?;
+        }
+    }
+
+    /// Handles the platform signal or command `code`, which is accepted on both ports. Any
+    /// parameters are read from `stream` and any response is written to it.
+    fn signal<S: Read + Write>(&mut self, code: u32, stream: &mut S) -> Result<()> {
+        match code {
+            SIGNAL_POWER_ON => self.power_on()?,
+            SIGNAL_POWER_OFF => self.power_off(),
+            SIGNAL_RESET | SIGNAL_RESTART => {
+                self.power_off();
+                self.power_on()?;
+            }
+            SIGNAL_PHYS_PRES_ON => self.physical_presence = true,
+            SIGNAL_PHYS_PRES_OFF => self.physical_presence = false,
+            SIGNAL_CANCEL_ON => self.cancel = true,
+            SIGNAL_CANCEL_OFF => self.cancel = false,
+            SIGNAL_NV_ON => self.nv_available = true,
+            SIGNAL_NV_OFF => self.nv_available = false,
+            SIGNAL_KEY_CACHE_ON | SIGNAL_KEY_CACHE_OFF => {}
+            TEST_FAILURE_MODE => self.failure_mode = true,
+            GET_COMMAND_RESPONSE_SIZES => {
+                let (command_size, command_code) = core::mem::take(&mut self.largest_command);
+                let (response_size, response_code) = core::mem::take(&mut self.largest_response);
+                stream.write_all(&16u32.to_be_bytes())?;
+                stream.write_all(&command_size.to_ne_bytes())?;
+                stream.write_all(&command_code.to_be_bytes())?;
+                stream.write_all(&response_size.to_ne_bytes())?;
+                stream.write_all(&response_code.to_be_bytes())?;
+            }
+            ACT_GET_SIGNALED => {
+                read_be_u32(stream)?;
+                stream.write_all(&0u32.to_be_bytes())?;
+            }
+            SET_FIRMWARE_HASH | SET_FIRMWARE_SVN => {
+                read_be_u32(stream)?;
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "unknown simulator command",
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serves `simulator` on the TPM port `tpm_listener` and the platform port `platform_listener`
+/// until a client stops it. Clients connect to both ports, and are served one after the other.
+pub fn serve<Deps>(
+    simulator: Simulator<Deps>,
+    tpm_listener: &TcpListener,
+    platform_listener: &TcpListener,
+) -> Result<()>
+where
+    Deps: TpmContextDeps<Request = [u8], Response = [u8]>,
+    TpmContext<Deps>: Send,
+{
+    let simulator = Mutex::new(simulator);
+    loop {
+        let (tpm_stream, _) = tpm_listener.accept()?;
+        let (platform_stream, _) = platform_listener.accept()?;
+        let (tpm, platform) = thread::scope(|scope| {
+            let platform = scope.spawn(|| Simulator::serve_platform(&simulator, platform_stream));
+            let tpm = Simulator::serve_tpm(&simulator, tpm_stream);
+            let platform = platform
+                .join()
+                .unwrap_or_else(|_| Err(Error::other("platform port panicked")));
+            (tpm, platform)
+        });
+        if tpm? == Disconnect::Stop || platform? == Disconnect::Stop {
+            return Ok(());
+        }
+    }
+}
+
+/// Locks `simulator`, which stays consistent even if another port panicked.
+fn lock<T>(simulator: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    simulator.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Writes a `TPM_RC_FAILURE` response in `response` and returns its size.
+fn failure_response(response: &mut [u8]) -> usize {
+    const ERROR_RESPONSE_SIZE: u32 = 10;
+    let fields = [
+        &TpmSt::NoSessions.0.to_be_bytes()[..],
+        &ERROR_RESPONSE_SIZE.to_be_bytes(),
+        &TpmRcError::Failure.get().to_be_bytes(),
+    ];
+    let mut offset = 0;
+    for field in fields {
+        let Some(out) = response.get_mut(offset..offset + field.len()) else {
+            return 0;
+        };
+        out.copy_from_slice(field);
+        offset += field.len();
+    }
+    offset
+}
+
+/// Reads the big-endian `u32` at `offset` of `buffer`, or 0 if `buffer` is too short.
+fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+    buffer
+        .get(offset..offset + 4)
+        .map_or(0, |bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a big-endian `u32` from `stream`.
+fn read_be_u32<S: Read>(stream: &mut S) -> Result<u32> {
+    let mut bytes = [0; 4];
+    stream.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+/// Reads the code of the next command from `stream`, or `None` if the client closed it.
+fn read_code<S: Read>(stream: &mut S) -> Result<Option<u32>> {
+    match read_be_u32(stream) {
+        Ok(code) => Ok(Some(code)),
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Reads a buffer preceded by its big-endian `u32` size from `stream` into `buffer`.
+fn read_buffer<'a, S: Read>(stream: &mut S, buffer: &'a mut [u8]) -> Result<&'a [u8]> {
+    let size = read_be_u32(stream)? as usize;
+    let buffer = buffer
+        .get_mut(..size)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "command too large"))?;
+    stream.read_exact(buffer)?;
+    Ok(buffer)
+}
+
+/// Writes `buffer` preceded by its big-endian `u32` size to `stream`.
+fn write_buffer<S: Write>(stream: &mut S, buffer: &[u8]) -> Result<()> {
+    stream.write_all(&(buffer.len() as u32).to_be_bytes())?;
+    stream.write_all(buffer)
+}
@@ -0,0 +1,238 @@
+use super::object::{ecc_public, load_ecc_key};
+use super::{build_request, execute, execute_on, parse_response, response_code, TestDeps};
+use crate::tpmctx::TpmContext;
+use hex_literal::hex;
+extern crate std;
+use std::vec::Vec;
+use tpm2_rs_base::commands::{EccDecryptCmd, EccEncryptCmd, EccParametersResp};
+use tpm2_rs_base::constants::{TpmEccCurve, TpmHandle};
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{
+    Tpm2bDigest, Tpm2bEccParameter, Tpm2bEccPoint, Tpm2bMaxBuffer, Tpm2bSimple, Tpm2bStruct,
+    TpmaObject, TpmiAlgHash, TpmsEccPoint, TpmsEmpty, TpmsSchemeHash, TpmtKdfScheme,
+};
+
+#[test]
+fn ecc_parameters_nist_p256() {
+    let request = hex!(
+        "8001" // tag
+        "0000000c" // size
+        "00000178" // command code
+        "0003" // TPM_ECC_NIST_P256
+    );
+    let response = execute(&request);
+    assert_eq!(
+        &response[..10],
+        hex!(
+            "8001" // session
+            "000000e1" // size
+            "00000000" // successful response
+        )
+    );
+
+    let resp = EccParametersResp::try_unmarshal(&mut UnmarshalBuf::new(&response[10..])).unwrap();
+    let parameters = resp.parameters;
+    assert_eq!(parameters.curve_id, TpmEccCurve::NistP256);
+    assert_eq!(parameters.key_size, 256);
+    assert_eq!(
+        parameters.g_x.as_ref(),
+        hex!("6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296")
+    );
+    assert_eq!(
+        parameters.n.as_ref(),
+        hex!("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551")
+    );
+    assert_eq!(parameters.h.as_ref(), hex!("01"));
+}
+
+#[test]
+fn ecc_parameters_unsupported_curve() {
+    let request = hex!(
+        "8001" // tag
+        "0000000c" // size
+        "00000178" // command code
+        "0001" // TPM_ECC_NIST_P192
+    );
+    assert_eq!(
+        execute(&request),
+        hex!(
+            "8001" // session
+            "0000000a" // size
+            "000001e6" // TPM_RC_CURVE + TPM_RC_P + TPM_RC_1
+        )
+    );
+}
+
+#[test]
+fn ecc_parameters_missing_curve() {
+    let request = hex!(
+        "8001" // tag
+        "0000000a" // size
+        "00000178" // command code
+    );
+    assert_eq!(
+        execute(&request),
+        hex!(
+            "8001" // session
+            "0000000a" // size
+            "0000009a" // TPM_RC_INSUFFICIENT
+        )
+    );
+}
+
+/// Encrypts `plain_text` with the key at `handle` and returns the response.
+fn ecc_encrypt(
+    tpm: &mut TpmContext<TestDeps>,
+    handle: TpmHandle,
+    plain_text: &[u8],
+    in_scheme: TpmtKdfScheme,
+) -> Vec<u8> {
+    let command = EccEncryptCmd {
+        plain_text: Tpm2bMaxBuffer::from_bytes(plain_text).unwrap(),
+        in_scheme,
+    };
+    execute_on(tpm, &build_request(&handle, &[], &command))
+}
+
+fn mgf1_sha256() -> TpmtKdfScheme {
+    TpmtKdfScheme::Mgf1(TpmsSchemeHash {
+        hash_alg: TpmiAlgHash::SHA256,
+    })
+}
+
+fn decrypt_key(tpm: &mut TpmContext<TestDeps>, kdf: TpmtKdfScheme) -> TpmHandle {
+    let attributes = TpmaObject::DECRYPT | TpmaObject::USER_WITH_AUTH;
+    load_ecc_key(tpm, &ecc_public(attributes, kdf), b"secret")
+}
+
+fn ecc_round_trip(key_kdf: TpmtKdfScheme, in_scheme: TpmtKdfScheme) {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let handle = decrypt_key(&mut tpm, key_kdf);
+    let plain_text = b"attack at dawn";
+    let encrypted = ecc_encrypt(&mut tpm, handle, plain_text, in_scheme);
+    let (_, encrypted) = parse_response::<EccEncryptCmd>(&encrypted);
+    assert_ne!(encrypted.c2.get_buffer(), plain_text);
+
+    let command = EccDecryptCmd {
+        c1: encrypted.c1,
+        c2: encrypted.c2,
+        c3: encrypted.c3,
+        in_scheme,
+    };
+    let response = execute_on(&mut tpm, &build_request(&handle, &[b"secret"], &command));
+    let (_, decrypted) = parse_response::<EccDecryptCmd>(&response);
+    assert_eq!(decrypted.plain_text.get_buffer(), plain_text);
+}
+
+#[test]
+fn ecc_encrypt_decrypt_mgf1() {
+    ecc_round_trip(TpmtKdfScheme::Null(TpmsEmpty), mgf1_sha256());
+}
+
+#[test]
+fn ecc_encrypt_decrypt_kdf_sp800_56a_from_key() {
+    let kdf = TpmtKdfScheme::Kdf1Sp800_56a(TpmsSchemeHash {
+        hash_alg: TpmiAlgHash::SHA384,
+    });
+    ecc_round_trip(kdf, TpmtKdfScheme::Null(TpmsEmpty));
+}
+
+#[test]
+fn ecc_encrypt_decrypt_kdf_sp800_108() {
+    let kdf = TpmtKdfScheme::Kdf1Sp800_108(TpmsSchemeHash {
+        hash_alg: TpmiAlgHash::SHA256,
+    });
+    ecc_round_trip(kdf, kdf);
+}
+
+#[test]
+fn ecc_encrypt_without_scheme() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let handle = decrypt_key(&mut tpm, TpmtKdfScheme::Null(TpmsEmpty));
+    let response = ecc_encrypt(&mut tpm, handle, b"data", TpmtKdfScheme::Null(TpmsEmpty));
+    // TPM_RC_SCHEME + TPM_RC_P + TPM_RC_2
+    assert_eq!(response_code(&response), 0x2D2);
+}
+
+#[test]
+fn ecc_encrypt_scheme_differs_from_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let kdf = TpmtKdfScheme::Kdf1Sp800_108(TpmsSchemeHash {
+        hash_alg: TpmiAlgHash::SHA256,
+    });
+    let handle = decrypt_key(&mut tpm, kdf);
+    let response = ecc_encrypt(&mut tpm, handle, b"data", mgf1_sha256());
+    // TPM_RC_SCHEME + TPM_RC_P + TPM_RC_2
+    assert_eq!(response_code(&response), 0x2D2);
+}
+
+#[test]
+fn ecc_encrypt_unloaded_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let response = ecc_encrypt(&mut tpm, TpmHandle(0x80000000), b"data", mgf1_sha256());
+    // TPM_RC_HANDLE + TPM_RC_1
+    assert_eq!(response_code(&response), 0x18B);
+}
+
+/// Encrypts a message with a fresh key and returns the decryption request for it.
+fn ecc_decrypt_request(
+    tpm: &mut TpmContext<TestDeps>,
+    password: Option<&[u8]>,
+    tamper: impl FnOnce(&mut EccDecryptCmd),
+) -> Vec<u8> {
+    let handle = decrypt_key(tpm, mgf1_sha256());
+    let encrypted = ecc_encrypt(tpm, handle, b"data", TpmtKdfScheme::Null(TpmsEmpty));
+    let (_, encrypted) = parse_response::<EccEncryptCmd>(&encrypted);
+    let mut command = EccDecryptCmd {
+        c1: encrypted.c1,
+        c2: encrypted.c2,
+        c3: encrypted.c3,
+        in_scheme: TpmtKdfScheme::Null(TpmsEmpty),
+    };
+    tamper(&mut command);
+    let passwords: &[&[u8]] = match &password {
+        Some(password) => core::slice::from_ref(password),
+        None => &[],
+    };
+    build_request(&handle, passwords, &command)
+}
+
+#[test]
+fn ecc_decrypt_tampered_digest() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let request = ecc_decrypt_request(&mut tpm, Some(b"secret"), |command| {
+        command.c3 = Tpm2bDigest::from_bytes(&[0; 32]).unwrap();
+    });
+    // TPM_RC_VALUE
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x084);
+}
+
+#[test]
+fn ecc_decrypt_point_not_on_curve() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let request = ecc_decrypt_request(&mut tpm, Some(b"secret"), |command| {
+        let point = TpmsEccPoint {
+            x: Tpm2bEccParameter::from_bytes(&[1]).unwrap(),
+            y: Tpm2bEccParameter::from_bytes(&[1]).unwrap(),
+        };
+        command.c1 = Tpm2bEccPoint::from_struct(&point).unwrap();
+    });
+    // TPM_RC_ECC_POINT + TPM_RC_P + TPM_RC_1
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x1E7);
+}
+
+#[test]
+fn ecc_decrypt_wrong_password() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let request = ecc_decrypt_request(&mut tpm, Some(b"guess"), |_| {});
+    // TPM_RC_AUTH_FAIL + TPM_RC_S + TPM_RC_1
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x98E);
+}
+
+#[test]
+fn ecc_decrypt_without_authorization() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let request = ecc_decrypt_request(&mut tpm, None, |_| {});
+    // TPM_RC_AUTH_MISSING
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x125);
+}
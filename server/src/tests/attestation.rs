@@ -0,0 +1,281 @@
+extern crate std;
+use super::object::{ecc_public, load_ecc_key, load_external_request};
+use super::{build_request, execute_on, parse_response, response_code, TestDeps};
+use crate::crypto::hmac::hmac;
+use crate::platform::crypto::rustcrypto::RustCryptoHash;
+use crate::tpmctx::TpmContext;
+use hex_literal::hex;
+use sha2::{Digest, Sha256};
+use tpm2_rs_base::commands::{
+    CertifyCmd, CertifyCreationCmd, GetTimeCmd, LoadExternalCmd, QuoteCmd, VerifySignatureCmd,
+};
+use tpm2_rs_base::constants::{TpmGenerated, TpmHandle, TpmSt};
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{
+    Tpm2bAttest, Tpm2bData, Tpm2bDigest, Tpm2bSimple, TpmaObject, TpmiAlgHash, TpmlPcrSelection,
+    TpmsAttest, TpmsEmpty, TpmsPcrSelection, TpmsSchemeHash, TpmtKdfScheme, TpmtSigScheme,
+    TpmtSignature, TpmtTkCreation, TpmuAttest,
+};
+
+const QUALIFYING_DATA: [u8; 8] = hex!("0123456789abcdef");
+
+fn sign_attributes() -> TpmaObject {
+    TpmaObject::SIGN_ENCRYPT | TpmaObject::USER_WITH_AUTH
+}
+
+fn ecdsa_sha256() -> TpmtSigScheme {
+    TpmtSigScheme::Ecdsa(TpmsSchemeHash {
+        hash_alg: TpmiAlgHash::SHA256,
+    })
+}
+
+/// Loads the public part of the ECC test key into `hierarchy` and returns its handle.
+fn load_public_key(tpm: &mut TpmContext<TestDeps>, hierarchy: TpmHandle) -> TpmHandle {
+    let public = ecc_public(sign_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let request = load_external_request(&public, None, hierarchy);
+    parse_response::<LoadExternalCmd>(&execute_on(tpm, &request)).0
+}
+
+/// Loads the ECC test key pair as a signing key and returns its handle.
+fn load_signing_key(tpm: &mut TpmContext<TestDeps>) -> TpmHandle {
+    let public = ecc_public(sign_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    load_ecc_key(tpm, &public, b"")
+}
+
+fn parse_attest(attest: &Tpm2bAttest) -> TpmsAttest {
+    TpmsAttest::try_unmarshal(&mut UnmarshalBuf::new(attest.get_buffer())).unwrap()
+}
+
+/// Checks `signature` of `attest` with the key at `key` and returns the response code.
+fn verify_attestation(
+    tpm: &mut TpmContext<TestDeps>,
+    key: TpmHandle,
+    attest: &Tpm2bAttest,
+    signature: TpmtSignature,
+) -> u32 {
+    let command = VerifySignatureCmd {
+        digest: Tpm2bDigest::from_bytes(&Sha256::digest(attest.get_buffer())).unwrap(),
+        signature,
+    };
+    response_code(&execute_on(tpm, &build_request(&key, &[], &command)))
+}
+
+fn pcr_selection(hash: TpmiAlgHash, pcr_select: [u8; 4]) -> TpmsPcrSelection {
+    TpmsPcrSelection {
+        hash,
+        sizeof_select: 3,
+        pcr_select,
+    }
+}
+
+#[test]
+fn quote_selected_pcrs() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let key = load_signing_key(&mut tpm);
+    let command = QuoteCmd {
+        qualifying_data: Tpm2bData::from_bytes(&QUALIFYING_DATA).unwrap(),
+        in_scheme: ecdsa_sha256(),
+        pcr_select: TpmlPcrSelection::new(&[
+            // PCRs 0, 1 and 17.
+            pcr_selection(TpmiAlgHash::SHA256, [0x03, 0x00, 0x02, 0x00]),
+            // There is no SHA-1 bank.
+            pcr_selection(TpmiAlgHash::SHA1, [0xFF, 0x00, 0x00, 0x00]),
+        ])
+        .unwrap(),
+    };
+    let request = build_request(&key, &[b""], &command);
+    let (_, resp) = parse_response::<QuoteCmd>(&execute_on(&mut tpm, &request));
+
+    let attest = parse_attest(&resp.quoted);
+    assert_eq!(attest.magic, TpmGenerated::VALUE);
+    assert_eq!(attest.extra_data.get_buffer(), QUALIFYING_DATA);
+    assert_eq!(attest.clock_info.safe, tpm2_rs_base::TpmiYesNo::YES);
+    let TpmuAttest::Quote(quote) = attest.attested else {
+        panic!("unexpected attestation {:?}", attest.attested);
+    };
+    assert_eq!(
+        quote.pcr_select.pcr_selections(),
+        [
+            pcr_selection(TpmiAlgHash::SHA256, [0x03, 0x00, 0x02, 0x00]),
+            pcr_selection(TpmiAlgHash::SHA1, [0x00, 0x00, 0x00, 0x00]),
+        ]
+    );
+    // PCRs 0 and 1 are reset to zeros and PCR 17 to ones.
+    let pcrs = [[0x00; 32], [0x00; 32], [0xFF; 32]].concat();
+    assert_eq!(quote.pcr_digest.get_buffer(), &Sha256::digest(pcrs)[..]);
+    assert_eq!(
+        verify_attestation(&mut tpm, key, &resp.quoted, resp.signature),
+        0
+    );
+}
+
+#[test]
+fn quote_without_signing_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let command = QuoteCmd {
+        qualifying_data: Tpm2bData::default(),
+        in_scheme: ecdsa_sha256(),
+        pcr_select: TpmlPcrSelection::new(&[pcr_selection(
+            TpmiAlgHash::SHA256,
+            [0x01, 0x00, 0x00, 0x00],
+        )])
+        .unwrap(),
+    };
+    let request = build_request(&TpmHandle::RHNull, &[b""], &command);
+    let (_, resp) = parse_response::<QuoteCmd>(&execute_on(&mut tpm, &request));
+    assert_eq!(resp.signature, TpmtSignature::Null(TpmsEmpty));
+    let attest = parse_attest(&resp.quoted);
+    assert_eq!(attest.qualified_signer.get_buffer(), hex!("40000007"));
+    let TpmuAttest::Quote(quote) = attest.attested else {
+        panic!("unexpected attestation {:?}", attest.attested);
+    };
+    assert_eq!(quote.pcr_digest.get_size(), 0);
+}
+
+#[test]
+fn quote_with_decryption_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let attributes = TpmaObject::DECRYPT | TpmaObject::USER_WITH_AUTH;
+    let key = load_ecc_key(
+        &mut tpm,
+        &ecc_public(attributes, TpmtKdfScheme::Null(TpmsEmpty)),
+        b"",
+    );
+    let command = QuoteCmd {
+        qualifying_data: Tpm2bData::default(),
+        in_scheme: ecdsa_sha256(),
+        pcr_select: TpmlPcrSelection::default(),
+    };
+    let request = build_request(&key, &[b""], &command);
+    // TPM_RC_KEY + TPM_RC_H + TPM_RC_1
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x19C);
+}
+
+#[test]
+fn certify_signing_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let key = load_signing_key(&mut tpm);
+    let command = CertifyCmd {
+        qualifying_data: Tpm2bData::from_bytes(&QUALIFYING_DATA).unwrap(),
+        in_scheme: TpmtSigScheme::Null(TpmsEmpty),
+    };
+    // The key certifies itself, since objects without a private part cannot be authorized.
+    let request = build_request(&(key, key), &[b"", b""], &command);
+    // Neither the key nor the command selects a scheme.
+    // TPM_RC_SCHEME + TPM_RC_P + TPM_RC_2
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x2D2);
+
+    let command = CertifyCmd {
+        in_scheme: ecdsa_sha256(),
+        ..command
+    };
+    let request = build_request(&(key, key), &[b"", b""], &command);
+    let (_, resp) = parse_response::<CertifyCmd>(&execute_on(&mut tpm, &request));
+    let attest = parse_attest(&resp.certify_info);
+    let TpmuAttest::Certify(info) = attest.attested else {
+        panic!("unexpected attestation {:?}", attest.attested);
+    };
+    let name = info.name.get_buffer();
+    assert_eq!(&name[..2], hex!("000b"));
+    let mut qualified_name = hex!("000b").to_vec();
+    qualified_name.extend_from_slice(&Sha256::digest([&hex!("40000007")[..], name].concat()));
+    assert_eq!(info.qualified_name.get_buffer(), qualified_name);
+    assert_eq!(attest.qualified_signer, info.qualified_name);
+    assert_eq!(
+        verify_attestation(&mut tpm, key, &resp.certify_info, resp.signature),
+        0
+    );
+}
+
+#[test]
+fn certify_creation_checks_ticket() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let key = load_signing_key(&mut tpm);
+    let request = load_external_request(
+        &ecc_public(sign_attributes(), TpmtKdfScheme::Null(TpmsEmpty)),
+        None,
+        TpmHandle::RHOwner,
+    );
+    let (object, loaded) = parse_response::<LoadExternalCmd>(&execute_on(&mut tpm, &request));
+    let creation_hash = [0x5A; 32];
+
+    // The fake entropy source makes every hierarchy proof the bytes 0 to 31.
+    let proof: std::vec::Vec<u8> = (0..32).collect();
+    let ticket_digest = hmac::<RustCryptoHash>(
+        TpmiAlgHash::SHA256,
+        &proof,
+        &[
+            &TpmSt::Creation.0.to_be_bytes(),
+            loaded.name.get_buffer(),
+            &creation_hash,
+        ],
+    )
+    .unwrap();
+    let ticket = TpmtTkCreation {
+        tag: TpmSt::Creation,
+        hierarchy: TpmHandle::RHOwner,
+        digest: Tpm2bDigest::from_bytes(ticket_digest.as_ref()).unwrap(),
+    };
+    let command = CertifyCreationCmd {
+        qualifying_data: Tpm2bData::default(),
+        creation_hash: Tpm2bDigest::from_bytes(&creation_hash).unwrap(),
+        in_scheme: ecdsa_sha256(),
+        creation_ticket: ticket,
+    };
+    let request = build_request(&(key, object), &[b""], &command);
+    let (_, resp) = parse_response::<CertifyCreationCmd>(&execute_on(&mut tpm, &request));
+    let attest = parse_attest(&resp.certify_info);
+    let TpmuAttest::Creation(info) = attest.attested else {
+        panic!("unexpected attestation {:?}", attest.attested);
+    };
+    assert_eq!(info.object_name, loaded.name);
+    assert_eq!(info.creation_hash, command.creation_hash);
+
+    let command = CertifyCreationCmd {
+        creation_hash: Tpm2bDigest::from_bytes(&[0xA5; 32]).unwrap(),
+        ..command
+    };
+    let request = build_request(&(key, object), &[b""], &command);
+    // TPM_RC_TICKET + TPM_RC_P + TPM_RC_4
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x4E0);
+}
+
+#[test]
+fn get_time_reveals_clock_to_privacy_admin() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let key = load_signing_key(&mut tpm);
+    let command = GetTimeCmd {
+        qualifying_data: Tpm2bData::from_bytes(&QUALIFYING_DATA).unwrap(),
+        in_scheme: ecdsa_sha256(),
+    };
+    let request = build_request(&(TpmHandle::RHOwner, key), &[b"", b""], &command);
+    // TPM_RC_VALUE + TPM_RC_H + TPM_RC_1
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x184);
+
+    let request = build_request(&(TpmHandle::RHEndorsement, key), &[b"", b""], &command);
+    let (_, resp) = parse_response::<GetTimeCmd>(&execute_on(&mut tpm, &request));
+    let attest = parse_attest(&resp.time_info);
+    let TpmuAttest::Time(info) = attest.attested else {
+        panic!("unexpected attestation {:?}", attest.attested);
+    };
+    assert!(info.time.time > 0);
+    assert_eq!(info.time.clock_info.clock, info.time.time);
+    assert_eq!(info.time.clock_info.reset_count, 0);
+    assert_eq!(info.time.clock_info.restart_count, 0);
+    assert_eq!(info.firmware_version, 0x0000_0001_0000_0000);
+    // The signing key is in the NULL hierarchy, so the common fields are obfuscated.
+    assert_eq!(attest.clock_info.clock, info.time.clock_info.clock);
+    assert_ne!(attest.firmware_version, info.firmware_version);
+    assert_ne!(
+        (
+            attest.clock_info.reset_count,
+            attest.clock_info.restart_count
+        ),
+        (0, 0)
+    );
+    let verifier = load_public_key(&mut tpm, TpmHandle::RHNull);
+    assert_eq!(
+        verify_attestation(&mut tpm, verifier, &resp.time_info, resp.signature),
+        0
+    );
+}
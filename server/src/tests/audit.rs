@@ -0,0 +1,280 @@
+extern crate std;
+use super::object::{ecc_public, load_ecc_key};
+use super::session::{cp_hash, rp_hash, split_response, start_session, TestSession};
+use super::{
+    build_request, build_session_request, execute_on, parse_response, response_code, TestDeps,
+};
+use crate::tpmctx::TpmContext;
+use sha2::{Digest, Sha256, Sha384};
+use std::vec::Vec;
+use tpm2_rs_base::commands::{
+    GetCommandAuditDigestCmd, GetRandomCmd, GetSessionAuditDigestCmd, SetCommandCodeAuditStatusCmd,
+};
+use tpm2_rs_base::constants::{TpmAlgId, TpmCc, TpmHandle};
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{
+    Tpm2bAttest, Tpm2bData, Tpm2bSimple, TpmaObject, TpmaSession, TpmiAlgHash, TpmiYesNo, TpmlCc,
+    TpmsAttest, TpmsCommandAuditInfo, TpmsEmpty, TpmsSchemeHash, TpmsSessionAuditInfo,
+    TpmtKdfScheme, TpmtSigScheme, TpmtSignature, TpmuAttest,
+};
+
+const GET_RANDOM: GetRandomCmd = GetRandomCmd { bytes_requested: 8 };
+
+fn null_alg() -> TpmiAlgHash {
+    TpmiAlgHash(TpmAlgId::Null.0)
+}
+
+fn set_audit_status(
+    alg: TpmiAlgHash,
+    set: &[TpmCc],
+    clear: &[TpmCc],
+) -> SetCommandCodeAuditStatusCmd {
+    SetCommandCodeAuditStatusCmd {
+        audit_alg: alg,
+        set_list: TpmlCc::new(set).unwrap(),
+        clear_list: TpmlCc::new(clear).unwrap(),
+    }
+}
+
+/// Runs `command` with the owner's authorization and returns the response.
+fn run_set_audit_status(
+    tpm: &mut TpmContext<TestDeps>,
+    command: &SetCommandCodeAuditStatusCmd,
+) -> Vec<u8> {
+    execute_on(tpm, &build_request(&TpmHandle::RHOwner, &[b""], command))
+}
+
+/// Returns the command audit information, signed with `sign_handle`.
+fn command_audit_info(
+    tpm: &mut TpmContext<TestDeps>,
+    sign_handle: TpmHandle,
+) -> (TpmsCommandAuditInfo, TpmtSignature) {
+    let in_scheme = match sign_handle {
+        TpmHandle::RHNull => TpmtSigScheme::Null(TpmsEmpty),
+        _ => TpmtSigScheme::Ecdsa(TpmsSchemeHash {
+            hash_alg: TpmiAlgHash::SHA256,
+        }),
+    };
+    let command = GetCommandAuditDigestCmd {
+        qualifying_data: Tpm2bData::from_bytes(&[]).unwrap(),
+        in_scheme,
+    };
+    let request = build_request(
+        &(TpmHandle::RHEndorsement, sign_handle),
+        &[b"", b""],
+        &command,
+    );
+    let response = parse_response::<GetCommandAuditDigestCmd>(&execute_on(tpm, &request)).1;
+    let TpmuAttest::CommandAudit(info) = parse_attest(&response.audit_info).attested else {
+        panic!("not a command audit");
+    };
+    (info, response.signature)
+}
+
+fn parse_attest(attest: &Tpm2bAttest) -> TpmsAttest {
+    TpmsAttest::try_unmarshal(&mut UnmarshalBuf::new(attest.get_buffer())).unwrap()
+}
+
+/// Runs TPM2_GetRandom without sessions and returns its cpHash and rpHash.
+fn audited_get_random(tpm: &mut TpmContext<TestDeps>) -> (Vec<u8>, Vec<u8>) {
+    let response = execute_on(tpm, &build_request(&(), &[], &GET_RANDOM));
+    assert_eq!(response_code(&response), 0);
+    (
+        cp_hash(&[], &GET_RANDOM),
+        rp_hash(TpmCc::GetRandom, &response[10..]),
+    )
+}
+
+#[test]
+fn command_audit_records_selected_commands() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let set_command = set_audit_status(null_alg(), &[TpmCc::GetRandom], &[]);
+    let response = run_set_audit_status(&mut tpm, &set_command);
+    let (parameters, _) = split_response(&response, 0);
+    let set_cp_hash = cp_hash(&[&TpmHandle::RHOwner.0.to_be_bytes()], &set_command);
+    let set_rp_hash = rp_hash(TpmCc::SetCommandCodeAuditStatus, parameters);
+    let (random_cp_hash, random_rp_hash) = audited_get_random(&mut tpm);
+
+    let (info, signature) = command_audit_info(&mut tpm, TpmHandle::RHNull);
+    let first = Sha256::new()
+        .chain_update(&set_cp_hash)
+        .chain_update(&set_rp_hash)
+        .finalize();
+    let expected = Sha256::new()
+        .chain_update(first)
+        .chain_update(&random_cp_hash)
+        .chain_update(&random_rp_hash)
+        .finalize();
+    assert_eq!(info.audit_counter, 1);
+    assert_eq!(info.digest_alg, TpmAlgId::SHA256.0);
+    assert_eq!(info.audit_digest.get_buffer(), &expected[..]);
+    // TPM2_SetCommandCodeAuditStatus (0x140) and TPM2_GetRandom (0x17B) in ascending order.
+    let commands = Sha256::digest([0, 0, 0x01, 0x40, 0, 0, 0x01, 0x7B]);
+    assert_eq!(info.command_digest.get_buffer(), &commands[..]);
+    assert_eq!(signature, TpmtSignature::Null(TpmsEmpty));
+
+    // Commands that are not audited leave the digest alone.
+    let response = run_set_audit_status(
+        &mut tpm,
+        &set_audit_status(null_alg(), &[], &[TpmCc::GetRandom]),
+    );
+    assert_eq!(response_code(&response), 0);
+    let digest = command_audit_info(&mut tpm, TpmHandle::RHNull)
+        .0
+        .audit_digest;
+    audited_get_random(&mut tpm);
+    assert_eq!(
+        command_audit_info(&mut tpm, TpmHandle::RHNull)
+            .0
+            .audit_digest,
+        digest
+    );
+}
+
+#[test]
+fn command_audit_algorithm_change() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    // TPM_RC_SIZE for the first parameter, as the algorithm and the list can't change together.
+    let command = set_audit_status(TpmiAlgHash::SHA384, &[TpmCc::GetRandom], &[]);
+    assert_eq!(
+        response_code(&run_set_audit_status(&mut tpm, &command)),
+        0x1D5
+    );
+
+    // The command that changes the algorithm starts the new digest.
+    let command = set_audit_status(TpmiAlgHash::SHA384, &[], &[]);
+    let response = run_set_audit_status(&mut tpm, &command);
+    let (parameters, _) = split_response(&response, 0);
+    let mut marshaled = [0; 1024];
+    let size = command.try_marshal(&mut marshaled).unwrap();
+    let cp_hash = Sha384::new()
+        .chain_update(TpmCc::SetCommandCodeAuditStatus.0.to_be_bytes())
+        .chain_update(TpmHandle::RHOwner.0.to_be_bytes())
+        .chain_update(&marshaled[..size])
+        .finalize();
+    let rp_hash = Sha384::new()
+        .chain_update(0u32.to_be_bytes())
+        .chain_update(TpmCc::SetCommandCodeAuditStatus.0.to_be_bytes())
+        .chain_update(parameters)
+        .finalize();
+    let expected = Sha384::new()
+        .chain_update(cp_hash)
+        .chain_update(rp_hash)
+        .finalize();
+
+    let (info, _) = command_audit_info(&mut tpm, TpmHandle::RHNull);
+    assert_eq!(info.digest_alg, TpmAlgId::SHA384.0);
+    assert_eq!(info.audit_counter, 1);
+    assert_eq!(info.audit_digest.get_buffer(), &expected[..]);
+}
+
+#[test]
+fn signed_command_audit_digest_starts_over() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(
+        TpmaObject::SIGN_ENCRYPT | TpmaObject::USER_WITH_AUTH,
+        TpmtKdfScheme::Null(TpmsEmpty),
+    );
+    let key = load_ecc_key(&mut tpm, &public, b"");
+    let command = set_audit_status(null_alg(), &[TpmCc::GetRandom], &[]);
+    assert_eq!(response_code(&run_set_audit_status(&mut tpm, &command)), 0);
+
+    let (signed, signature) = command_audit_info(&mut tpm, key);
+    assert_eq!(signed.audit_counter, 1);
+    assert_ne!(signed.audit_digest.get_size(), 0);
+    assert!(matches!(signature, TpmtSignature::Ecdsa(_)));
+
+    let (cleared, _) = command_audit_info(&mut tpm, TpmHandle::RHNull);
+    assert_eq!(cleared.audit_counter, 1);
+    assert_eq!(cleared.audit_digest.get_size(), 0);
+
+    // The next audited command starts a new digest.
+    let (cp_hash, rp_hash) = audited_get_random(&mut tpm);
+    let (restarted, _) = command_audit_info(&mut tpm, TpmHandle::RHNull);
+    let expected = Sha256::new()
+        .chain_update(&cp_hash)
+        .chain_update(&rp_hash)
+        .finalize();
+    assert_eq!(restarted.audit_counter, 2);
+    assert_eq!(restarted.audit_digest.get_buffer(), &expected[..]);
+}
+
+/// Runs TPM2_GetRandom audited by `session` and returns its response.
+fn get_random_in_session(
+    tpm: &mut TpmContext<TestDeps>,
+    session: &TestSession,
+    attributes: TpmaSession,
+) -> Vec<u8> {
+    let auth = session.authorize(attributes, b"", &cp_hash(&[], &GET_RANDOM));
+    execute_on(tpm, &build_session_request(&(), &[auth], &GET_RANDOM))
+}
+
+/// Returns the audit information of `session`.
+fn session_audit_info(
+    tpm: &mut TpmContext<TestDeps>,
+    session: &TestSession,
+) -> TpmsSessionAuditInfo {
+    let command = GetSessionAuditDigestCmd {
+        qualifying_data: Tpm2bData::from_bytes(&[]).unwrap(),
+        in_scheme: TpmtSigScheme::Null(TpmsEmpty),
+    };
+    let handles = (TpmHandle::RHEndorsement, TpmHandle::RHNull, session.handle);
+    let request = build_request(&handles, &[b"", b""], &command);
+    let response = parse_response::<GetSessionAuditDigestCmd>(&execute_on(tpm, &request)).1;
+    let TpmuAttest::SessionAudit(info) = parse_attest(&response.audit_info).attested else {
+        panic!("not a session audit");
+    };
+    info
+}
+
+#[test]
+fn session_audit_digest() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let mut session = start_session(&mut tpm, TpmHandle::RHNull, b"");
+    let audit = TpmaSession::CONTINUE_SESSION | TpmaSession::AUDIT;
+
+    let mut expected = [0; 32].to_vec();
+    for attributes in [
+        audit | TpmaSession::AUDIT_RESET,
+        audit | TpmaSession::AUDIT_EXCLUSIVE,
+    ] {
+        let response = get_random_in_session(&mut tpm, &session, attributes);
+        let (parameters, sessions) = split_response(&response, 0);
+        assert_eq!(
+            sessions[0].session_attributes,
+            audit | TpmaSession::AUDIT_EXCLUSIVE
+        );
+        let rp_hash = rp_hash(TpmCc::GetRandom, parameters);
+        session.check_response(&sessions[0], b"", &rp_hash);
+        expected = Sha256::new()
+            .chain_update(&expected)
+            .chain_update(cp_hash(&[], &GET_RANDOM))
+            .chain_update(&rp_hash)
+            .finalize()
+            .to_vec();
+    }
+
+    let info = session_audit_info(&mut tpm, &session);
+    assert_eq!(info.exclusive_session, TpmiYesNo::YES);
+    assert_eq!(info.session_digest.get_buffer(), &expected[..]);
+
+    // The last command was not audited by the session, which ended its exclusivity.
+    let response = get_random_in_session(&mut tpm, &session, audit | TpmaSession::AUDIT_EXCLUSIVE);
+    assert_eq!(response_code(&response), 0x121);
+    assert_eq!(
+        session_audit_info(&mut tpm, &session).exclusive_session,
+        TpmiYesNo::NO
+    );
+}
+
+#[test]
+fn session_audit_attributes_require_audit() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let session = start_session(&mut tpm, TpmHandle::RHNull, b"");
+    let attributes = TpmaSession::CONTINUE_SESSION | TpmaSession::AUDIT_RESET;
+    // TPM_RC_ATTRIBUTES for the first session.
+    assert_eq!(
+        response_code(&get_random_in_session(&mut tpm, &session, attributes)),
+        0x982
+    );
+}
@@ -0,0 +1,106 @@
+use super::execute;
+use hex_literal::hex;
+
+#[test]
+fn test_parms_rsa_2048() {
+    let request = hex!(
+        "8001" // tag
+        "0000001a" // size
+        "0000018a" // command code
+        "0001" // TPM_ALG_RSA
+        "0006" "0080" "0043" // AES-128-CFB
+        "0010" // TPM_ALG_NULL scheme
+        "0800" // 2048 key bits
+        "00000000" // default exponent
+    );
+    assert_eq!(
+        execute(&request),
+        hex!(
+            "8001" // session
+            "0000000a" // size
+            "00000000" // successful response
+        )
+    );
+}
+
+#[test]
+fn test_parms_ecc_nist_p384() {
+    let request = hex!(
+        "8001" // tag
+        "00000016" // size
+        "0000018a" // command code
+        "0023" // TPM_ALG_ECC
+        "0010" // TPM_ALG_NULL symmetric
+        "0018" "000c" // ECDSA with SHA384
+        "0004" // TPM_ECC_NIST_P384
+        "0010" // TPM_ALG_NULL kdf
+    );
+    assert_eq!(
+        execute(&request),
+        hex!(
+            "8001" // session
+            "0000000a" // size
+            "00000000" // successful response
+        )
+    );
+}
+
+#[test]
+fn test_parms_unsupported_rsa_key_size() {
+    let request = hex!(
+        "8001" // tag
+        "00000016" // size
+        "0000018a" // command code
+        "0001" // TPM_ALG_RSA
+        "0010" // TPM_ALG_NULL symmetric
+        "0010" // TPM_ALG_NULL scheme
+        "0200" // 512 key bits
+        "00000000" // default exponent
+    );
+    assert_eq!(
+        execute(&request),
+        hex!(
+            "8001" // session
+            "0000000a" // size
+            "000001c7" // TPM_RC_KEY_SIZE + TPM_RC_P + TPM_RC_1
+        )
+    );
+}
+
+#[test]
+fn test_parms_unsupported_symmetric_mode() {
+    let request = hex!(
+        "8001" // tag
+        "00000012" // size
+        "0000018a" // command code
+        "0025" // TPM_ALG_SYMCIPHER
+        "0006" "0100" "003f" // AES-256-CMAC
+    );
+    assert_eq!(
+        execute(&request),
+        hex!(
+            "8001" // session
+            "0000000a" // size
+            "000001c9" // TPM_RC_MODE + TPM_RC_P + TPM_RC_1
+        )
+    );
+}
+
+#[test]
+fn test_parms_keyed_hash_unsupported_hash() {
+    let request = hex!(
+        "8001" // tag
+        "00000010" // size
+        "0000018a" // command code
+        "0008" // TPM_ALG_KEYEDHASH
+        "0005" "0012" // HMAC with SM3_256
+    );
+    assert_eq!(
+        execute(&request),
+        hex!(
+            "8001" // session
+            "0000000a" // size
+            "000001c3" // TPM_RC_HASH + TPM_RC_P + TPM_RC_1
+        )
+    );
+}
@@ -0,0 +1,17 @@
+use crate::platform::Clock;
+
+/// A clock that advances by one second every time it is read.
+pub struct FakeClock {
+    now: u64,
+}
+
+impl Clock for FakeClock {
+    fn instantiate() -> Self {
+        Self { now: 0 }
+    }
+
+    fn now_ms(&mut self) -> u64 {
+        self.now += 1000;
+        self.now
+    }
+}
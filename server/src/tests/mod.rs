@@ -1,16 +1,37 @@
 //! Unit tests for the base crate (uses std)
 extern crate std;
+use std::vec;
 use std::vec::Vec;
 
+use crate::platform::crypto::rustcrypto::{
+    RustCryptoCipher, RustCryptoEcc, RustCryptoHash, RustCryptoRsa,
+};
 use crate::platform::TpmContextDeps;
 
 use super::tpmctx::*;
+use clock::FakeClock;
 use drbg::FakeDrbg;
 use entropy::FakeEntropy;
 use hex_literal::hex;
+use tpm2_rs_base::commands::TpmCommand;
+use tpm2_rs_base::constants::TpmSt;
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{Tpm2bAuth, Tpm2bSimple, TpmaSession, TpmiShAuthSession, TpmsAuthCommand};
 
+mod asymmetric;
+mod attestation;
+mod audit;
+mod capability;
+pub mod clock;
 pub mod drbg;
 pub mod entropy;
+mod object;
+mod policy;
+mod session;
+mod signature;
+#[cfg(feature = "simulator")]
+mod simulator;
+mod symmetric;
 
 /// Contains all of the test dependencies to create a [`TpmContext`] for unit testing
 struct TestDeps;
@@ -18,10 +39,97 @@ struct TestDeps;
 impl TpmContextDeps for TestDeps {
     type Drbg = FakeDrbg;
     type EntropySource = FakeEntropy;
+    type Hash = RustCryptoHash;
+    type Ecc = RustCryptoEcc;
+    type Rsa = RustCryptoRsa;
+    type Cipher = RustCryptoCipher;
+    type Clock = FakeClock;
     type Request = [u8];
     type Response = [u8];
 }
 
+/// Executes `request` against `tpm` and returns the full response.
+fn execute_on(tpm: &mut TpmContext<TestDeps>, request: &[u8]) -> Vec<u8> {
+    let mut response = vec![0xFF; 4096];
+    let size = tpm.execute_command_separate(request, &mut response);
+    response.truncate(size);
+    response
+}
+
+/// Executes `request` against a fresh [`TpmContext`] and returns the full response.
+fn execute(request: &[u8]) -> Vec<u8> {
+    execute_on(&mut TpmContext::new().unwrap(), request)
+}
+
+/// Builds the request for `command`. If `passwords` is not empty, the request has an
+/// authorization area with a password session for each entry.
+fn build_request<C: TpmCommand>(handles: &C::Handles, passwords: &[&[u8]], command: &C) -> Vec<u8> {
+    let sessions: Vec<_> = passwords
+        .iter()
+        .map(|password| TpmsAuthCommand {
+            session_handle: TpmiShAuthSession::RS_PW,
+            session_attributes: TpmaSession::CONTINUE_SESSION,
+            hmac: Tpm2bAuth::from_bytes(password).unwrap(),
+            ..Default::default()
+        })
+        .collect();
+    build_session_request(handles, &sessions, command)
+}
+
+/// Builds the request for `command`. If `sessions` is not empty, the request has an authorization
+/// area with these sessions.
+fn build_session_request<C: TpmCommand>(
+    handles: &C::Handles,
+    sessions: &[TpmsAuthCommand],
+    command: &C,
+) -> Vec<u8> {
+    let mut request = vec![0; 4096];
+    let mut size = 10;
+    size += handles.try_marshal(&mut request[size..]).unwrap();
+    if !sessions.is_empty() {
+        let auth_size_offset = size;
+        size += 4;
+        for session in sessions {
+            size += session.try_marshal(&mut request[size..]).unwrap();
+        }
+        let auth_size = (size - auth_size_offset - 4) as u32;
+        request[auth_size_offset..auth_size_offset + 4].copy_from_slice(&auth_size.to_be_bytes());
+    }
+    size += command.try_marshal(&mut request[size..]).unwrap();
+
+    let tag = if sessions.is_empty() {
+        TpmSt::NoSessions
+    } else {
+        TpmSt::Sessions
+    };
+    request[..2].copy_from_slice(&tag.0.to_be_bytes());
+    request[2..6].copy_from_slice(&(size as u32).to_be_bytes());
+    request[6..10].copy_from_slice(&C::CMD_CODE.0.to_be_bytes());
+    request.truncate(size);
+    request
+}
+
+/// Parses a successful response to a `C` command. Panics if the response is an error.
+fn parse_response<C: TpmCommand>(response: &[u8]) -> (C::RespHandles, C::RespT) {
+    assert_eq!(
+        &response[6..10],
+        &[0; 4],
+        "unexpected response {response:02x?}"
+    );
+    let sessions = response[..2] == TpmSt::Sessions.0.to_be_bytes();
+    let mut buffer = UnmarshalBuf::new(&response[10..]);
+    let handles = C::RespHandles::try_unmarshal(&mut buffer).unwrap();
+    if sessions {
+        u32::try_unmarshal(&mut buffer).unwrap();
+    }
+    (handles, C::RespT::try_unmarshal(&mut buffer).unwrap())
+}
+
+/// Returns the response code of `response`.
+fn response_code(response: &[u8]) -> u32 {
+    u32::from_be_bytes(response[6..10].try_into().unwrap())
+}
+
 #[test]
 fn get_random_in_place() {
     let mut tpm: TpmContext<TestDeps> = TpmContext::new().unwrap();
@@ -34,8 +142,9 @@ fn get_random_in_place() {
     );
     let expected_response = &hex!(
         "8001" // session
-        "00000016" // size
+        "00000018" // size
         "00000000" // successful response
+        "000c" // size of the random bytes
         "0102030405060708090a0b0c" // random bytes
     );
 
@@ -57,8 +166,9 @@ fn get_random_separate() {
     );
     let expected_response = hex!(
         "8001" // session
-        "00000016" // size
+        "00000018" // size
         "00000000" // successful response
+        "000c" // size of the random bytes
         "0102030405060708090a0b0c" // random bytes
     );
 
@@ -67,3 +177,20 @@ fn get_random_separate() {
     let size = tpm.execute_command_separate(&request, &mut response);
     assert_eq!(&response[..size], expected_response);
 }
+
+#[test]
+fn get_random_returns_at_most_the_largest_digest() {
+    let mut tpm: TpmContext<TestDeps> = TpmContext::new().unwrap();
+
+    let request = hex!(
+        "8001" // tag
+        "0000000c" // size
+        "0000017B" // command code
+        "ffff" // requested random bytes
+    );
+    let mut response = [0; 256];
+    let size = tpm.execute_command_separate(&request, &mut response);
+    assert_eq!(response_code(&response), 0);
+    assert_eq!(&response[10..12], &64u16.to_be_bytes());
+    assert_eq!(size, 12 + 64);
+}
@@ -0,0 +1,175 @@
+extern crate std;
+use super::{build_request, execute_on, parse_response, response_code, TestDeps};
+use crate::tpmctx::TpmContext;
+use hex_literal::hex;
+use std::vec::Vec;
+use tpm2_rs_base::commands::{FlushContextCmd, LoadExternalCmd};
+use tpm2_rs_base::constants::{TpmEccCurve, TpmHandle};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bAuth, Tpm2bEccParameter, Tpm2bPublic, Tpm2bSensitive, Tpm2bSimple,
+    Tpm2bStruct, TpmaObject, TpmiAlgHash, TpmiEccCurve, TpmsEccParms, TpmsEccPoint, TpmsEmpty,
+    TpmtEccScheme, TpmtKdfScheme, TpmtPublic, TpmtSensitive, TpmtSymDefObject,
+    TpmuSensitiveComposite,
+};
+
+/// A NIST P-256 private key from [RFC 6979] A.2.5.
+pub const ECC_PRIVATE: [u8; 32] =
+    hex!("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+/// The public point that belongs to [`ECC_PRIVATE`].
+pub const ECC_PUBLIC_X: [u8; 32] =
+    hex!("60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6");
+pub const ECC_PUBLIC_Y: [u8; 32] =
+    hex!("7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299");
+
+/// Returns the public area of the NIST P-256 test key with the given attributes and KDF.
+pub fn ecc_public(object_attributes: TpmaObject, kdf: TpmtKdfScheme) -> TpmtPublic {
+    TpmtPublic {
+        name_alg: TpmiAlgHash::SHA256,
+        object_attributes,
+        auth_policy: Default::default(),
+        parms_and_id: PublicParmsAndId::Ecc(
+            TpmsEccParms {
+                symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+                scheme: TpmtEccScheme::Null(TpmsEmpty),
+                curve_id: TpmiEccCurve(TpmEccCurve::NistP256),
+                kdf,
+            },
+            TpmsEccPoint {
+                x: Tpm2bEccParameter::from_bytes(&ECC_PUBLIC_X).unwrap(),
+                y: Tpm2bEccParameter::from_bytes(&ECC_PUBLIC_Y).unwrap(),
+            },
+        ),
+    }
+}
+
+/// Returns the sensitive area of the NIST P-256 test key with the given authorization value.
+pub fn ecc_sensitive(auth_value: &[u8]) -> TpmtSensitive {
+    TpmtSensitive {
+        auth_value: Tpm2bAuth::from_bytes(auth_value).unwrap(),
+        seed_value: Default::default(),
+        sensitive: TpmuSensitiveComposite::Ecc(
+            Tpm2bEccParameter::from_bytes(&ECC_PRIVATE).unwrap(),
+        ),
+    }
+}
+
+/// Builds a `TPM2_LoadExternal` request.
+pub fn load_external_request(
+    public: &TpmtPublic,
+    sensitive: Option<&TpmtSensitive>,
+    hierarchy: TpmHandle,
+) -> Vec<u8> {
+    let command = LoadExternalCmd {
+        in_private: match sensitive {
+            Some(sensitive) => Tpm2bSensitive::from_struct(sensitive).unwrap(),
+            None => Tpm2bSensitive::from_bytes(&[]).unwrap(),
+        },
+        in_public: Tpm2bPublic::from_struct(public).unwrap(),
+        hierarchy,
+    };
+    build_request(&(), &[], &command)
+}
+
+/// Loads the NIST P-256 test key pair into `tpm` and returns its handle.
+pub fn load_ecc_key(tpm: &mut TpmContext<TestDeps>, public: &TpmtPublic, auth: &[u8]) -> TpmHandle {
+    let request = load_external_request(public, Some(&ecc_sensitive(auth)), TpmHandle::RHNull);
+    parse_response::<LoadExternalCmd>(&execute_on(tpm, &request)).0
+}
+
+fn decrypt_attributes() -> TpmaObject {
+    TpmaObject::DECRYPT | TpmaObject::USER_WITH_AUTH
+}
+
+#[test]
+fn load_external_ecc_key_pair() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(decrypt_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let request = load_external_request(&public, Some(&ecc_sensitive(b"")), TpmHandle::RHNull);
+    let (handle, resp) = parse_response::<LoadExternalCmd>(&execute_on(&mut tpm, &request));
+    assert_eq!(handle, TpmHandle(0x80000000));
+    // The Name is the SHA-256 algorithm ID followed by a SHA-256 digest.
+    assert_eq!(resp.name.get_size(), 34);
+    assert_eq!(&resp.name.get_buffer()[..2], hex!("000b"));
+}
+
+#[test]
+fn load_external_public_key_in_owner_hierarchy() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(decrypt_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let request = load_external_request(&public, None, TpmHandle::RHOwner);
+    let (handle, _) = parse_response::<LoadExternalCmd>(&execute_on(&mut tpm, &request));
+    assert_eq!(handle, TpmHandle(0x80000000));
+}
+
+#[test]
+fn load_external_private_key_in_owner_hierarchy() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(decrypt_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let request = load_external_request(&public, Some(&ecc_sensitive(b"")), TpmHandle::RHOwner);
+    // TPM_RC_HIERARCHY + TPM_RC_P + TPM_RC_3
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x3C5);
+}
+
+#[test]
+fn load_external_fixed_tpm_private_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let attributes = decrypt_attributes() | TpmaObject::FIXED_TPM;
+    let public = ecc_public(attributes, TpmtKdfScheme::Null(TpmsEmpty));
+    let request = load_external_request(&public, Some(&ecc_sensitive(b"")), TpmHandle::RHNull);
+    // TPM_RC_ATTRIBUTES + TPM_RC_P + TPM_RC_2
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x2C2);
+}
+
+#[test]
+fn load_external_mismatched_private_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(decrypt_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let mut sensitive = ecc_sensitive(b"");
+    sensitive.sensitive = TpmuSensitiveComposite::Ecc(Tpm2bEccParameter::from_bytes(&[7]).unwrap());
+    let request = load_external_request(&public, Some(&sensitive), TpmHandle::RHNull);
+    // TPM_RC_BINDING + TPM_RC_P + TPM_RC_1
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x1E5);
+}
+
+#[test]
+fn load_external_point_not_on_curve() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let mut public = ecc_public(decrypt_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    if let PublicParmsAndId::Ecc(_, point) = &mut public.parms_and_id {
+        point.y = Tpm2bEccParameter::from_bytes(&ECC_PUBLIC_X).unwrap();
+    }
+    let request = load_external_request(&public, None, TpmHandle::RHOwner);
+    // TPM_RC_ECC_POINT + TPM_RC_P + TPM_RC_2
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x2E7);
+}
+
+#[test]
+fn load_external_out_of_object_memory() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(decrypt_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let request = load_external_request(&public, None, TpmHandle::RHOwner);
+    for handle in 0x80000000..0x80000003 {
+        let (loaded, _) = parse_response::<LoadExternalCmd>(&execute_on(&mut tpm, &request));
+        assert_eq!(loaded, TpmHandle(handle));
+    }
+    // TPM_RC_OBJECT_MEMORY
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x902);
+}
+
+#[test]
+fn flush_context_unloads_object() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(decrypt_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let handle = load_ecc_key(&mut tpm, &public, b"");
+
+    let flush = build_request(
+        &(),
+        &[],
+        &FlushContextCmd {
+            flush_handle: handle,
+        },
+    );
+    parse_response::<FlushContextCmd>(&execute_on(&mut tpm, &flush));
+    // TPM_RC_HANDLE + TPM_RC_P + TPM_RC_1
+    assert_eq!(response_code(&execute_on(&mut tpm, &flush)), 0x1CB);
+}
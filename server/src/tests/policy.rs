@@ -0,0 +1,414 @@
+extern crate std;
+use super::object::{ecc_public, load_ecc_key, load_external_request};
+use super::session::{
+    cp_hash, rp_hash, sign_command, split_response, start_auth_session_command, TestSession,
+    KEY_AUTH, NONCE_CALLER,
+};
+use super::{
+    build_request, build_session_request, execute_on, parse_response, response_code, TestDeps,
+};
+use crate::object::compute_name;
+use crate::platform::crypto::rustcrypto::RustCryptoHash;
+use crate::tpmctx::TpmContext;
+use sha2::{Digest, Sha256};
+use std::vec::Vec;
+use tpm2_rs_base::commands::{
+    LoadExternalCmd, PolicyAuthValueCmd, PolicyAuthorizeCmd, PolicyCommandCodeCmd,
+    PolicyCounterTimerCmd, PolicyGetDigestCmd, PolicyLocalityCmd, PolicyOrCmd, PolicyPasswordCmd,
+    PolicySecretCmd, PolicySignedCmd, SignCmd, StartAuthSessionCmd, TpmCommand, VerifySignatureCmd,
+};
+use tpm2_rs_base::constants::{TpmCc, TpmEo, TpmHandle, TpmSe};
+use tpm2_rs_base::{
+    Tpm2bAuth, Tpm2bDigest, Tpm2bName, Tpm2bNonce, Tpm2bOperand, Tpm2bSimple, TpmaLocality,
+    TpmaObject, TpmaSession, TpmiShAuthSession, TpmlDigest, TpmsAuthCommand, TpmsEmpty,
+    TpmtKdfScheme, TpmtSignature, TpmtTkVerified,
+};
+
+/// Starts an unbound, unsalted SHA-256 session of `session_type`.
+fn start_policy_session(tpm: &mut TpmContext<TestDeps>, session_type: TpmSe) -> TestSession {
+    let command = StartAuthSessionCmd {
+        session_type,
+        ..start_auth_session_command()
+    };
+    let request = build_request(&(TpmHandle::RHNull, TpmHandle::RHNull), &[], &command);
+    let (handle, response) = parse_response::<StartAuthSessionCmd>(&execute_on(tpm, &request));
+    TestSession {
+        handle,
+        session_key: Vec::new(),
+        nonce_tpm: response.nonce_tpm.get_buffer().to_vec(),
+    }
+}
+
+/// Executes a policy command without authorizations and returns the response code.
+fn run<C: TpmCommand>(tpm: &mut TpmContext<TestDeps>, handles: &C::Handles, command: &C) -> u32 {
+    response_code(&execute_on(tpm, &build_request(handles, &[], command)))
+}
+
+/// Returns the current policy digest of `session`.
+fn policy_digest(tpm: &mut TpmContext<TestDeps>, session: &TestSession) -> Vec<u8> {
+    let request = build_request(&session.handle, &[], &PolicyGetDigestCmd {});
+    let (_, response) = parse_response::<PolicyGetDigestCmd>(&execute_on(tpm, &request));
+    response.policy_digest.get_buffer().to_vec()
+}
+
+/// Extends a SHA-256 policy digest with `data`.
+fn extend(digest: &[u8], data: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Sha256::new().chain_update(digest);
+    for part in data {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// The policy of TPM2_PolicyAuthValue and TPM2_PolicyPassword.
+fn auth_value_policy() -> Vec<u8> {
+    extend(&[0; 32], &[&TpmCc::PolicyAuthValue.0.to_be_bytes()])
+}
+
+/// Loads the ECC test key with [`KEY_AUTH`] that can only be used with `policy` and returns its
+/// handle and Name.
+fn load_policy_key(tpm: &mut TpmContext<TestDeps>, policy: &[u8]) -> (TpmHandle, Tpm2bName) {
+    let mut public = ecc_public(TpmaObject::SIGN_ENCRYPT, TpmtKdfScheme::Null(TpmsEmpty));
+    public.auth_policy = Tpm2bDigest::from_bytes(policy).unwrap();
+    let key = load_ecc_key(tpm, &public, KEY_AUTH);
+    (key, compute_name::<RustCryptoHash>(&public).unwrap())
+}
+
+/// Signs `digest` with the ECC test key loaded at `key` with [`KEY_AUTH`].
+fn sign_digest(tpm: &mut TpmContext<TestDeps>, key: TpmHandle, digest: &[u8]) -> TpmtSignature {
+    let command = SignCmd {
+        digest: Tpm2bDigest::from_bytes(digest).unwrap(),
+        ..sign_command()
+    };
+    let request = build_request(&key, &[KEY_AUTH], &command);
+    parse_response::<SignCmd>(&execute_on(tpm, &request))
+        .1
+        .signature
+}
+
+fn policy_signed_command(session: &TestSession, signature: TpmtSignature) -> PolicySignedCmd {
+    PolicySignedCmd {
+        nonce_tpm: Tpm2bNonce::from_bytes(&session.nonce_tpm).unwrap(),
+        cp_hash_a: Tpm2bDigest::default(),
+        policy_ref: Tpm2bNonce::from_bytes(b"ref").unwrap(),
+        expiration: 0,
+        auth: signature,
+    }
+}
+
+/// The digest that TPM2_PolicySigned expects `command` to be signed over.
+fn policy_signed_digest(command: &PolicySignedCmd) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(command.nonce_tpm.get_buffer())
+        .chain_update(command.expiration.to_be_bytes())
+        .chain_update(command.cp_hash_a.get_buffer())
+        .chain_update(command.policy_ref.get_buffer())
+        .finalize()
+        .to_vec()
+}
+
+#[test]
+fn policy_auth_value_authorizes_command() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let (key, name) = load_policy_key(&mut tpm, &auth_value_policy());
+    let mut session = start_policy_session(&mut tpm, TpmSe::Policy);
+    let command_hash = cp_hash(&[name.get_buffer()], &sign_command());
+
+    assert_eq!(run(&mut tpm, &session.handle, &PolicyAuthValueCmd {}), 0);
+    assert_eq!(policy_digest(&mut tpm, &session), auth_value_policy());
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, KEY_AUTH, &command_hash);
+    let response = execute_on(
+        &mut tpm,
+        &build_session_request(&key, &[auth], &sign_command()),
+    );
+    let (parameters, sessions) = split_response(&response, 0);
+    session.check_response(&sessions[0], KEY_AUTH, &rp_hash(TpmCc::Sign, parameters));
+
+    // The policy starts over after each use.
+    assert_eq!(policy_digest(&mut tpm, &session), [0; 32]);
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, KEY_AUTH, &command_hash);
+    let request = build_session_request(&key, &[auth], &sign_command());
+    // TPM_RC_POLICY_FAIL for the first session.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x99D);
+}
+
+#[test]
+fn policy_password_authorizes_command() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let (key, _) = load_policy_key(&mut tpm, &auth_value_policy());
+    let session = start_policy_session(&mut tpm, TpmSe::Policy);
+    let password_auth = |password: &[u8]| TpmsAuthCommand {
+        session_handle: TpmiShAuthSession::try_from(session.handle.0).unwrap(),
+        nonce: Tpm2bNonce::from_bytes(&NONCE_CALLER).unwrap(),
+        session_attributes: TpmaSession::CONTINUE_SESSION,
+        hmac: Tpm2bAuth::from_bytes(password).unwrap(),
+    };
+
+    assert_eq!(run(&mut tpm, &session.handle, &PolicyPasswordCmd {}), 0);
+    assert_eq!(policy_digest(&mut tpm, &session), auth_value_policy());
+    let request = build_session_request(&key, &[password_auth(b"wrong")], &sign_command());
+    // TPM_RC_AUTH_FAIL for the first session.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x98E);
+
+    let request = build_session_request(&key, &[password_auth(KEY_AUTH)], &sign_command());
+    let response = execute_on(&mut tpm, &request);
+    let (_, sessions) = split_response(&response, 0);
+    assert_eq!(sessions[0].hmac.get_size(), 0);
+}
+
+#[test]
+fn policy_command_code_restricts_command() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let code = TpmCc::Quote;
+    let policy = extend(
+        &[0; 32],
+        &[
+            &TpmCc::PolicyCommandCode.0.to_be_bytes(),
+            &code.0.to_be_bytes(),
+        ],
+    );
+    let (key, name) = load_policy_key(&mut tpm, &policy);
+    let session = start_policy_session(&mut tpm, TpmSe::Policy);
+
+    let command = PolicyCommandCodeCmd { code };
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0);
+    // TPM_RC_VALUE for the first parameter, as the command was already set.
+    let command = PolicyCommandCodeCmd { code: TpmCc::Sign };
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0x1C4);
+
+    let command_hash = cp_hash(&[name.get_buffer()], &sign_command());
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, b"", &command_hash);
+    let request = build_session_request(&key, &[auth], &sign_command());
+    // TPM_RC_POLICY_CC for the first session.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x9A4);
+}
+
+#[test]
+fn policy_locality_restricts_command() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let locality = TpmaLocality::LOC_ONE;
+    let policy = extend(
+        &[0; 32],
+        &[&TpmCc::PolicyLocality.0.to_be_bytes(), &[locality.0]],
+    );
+    let (key, name) = load_policy_key(&mut tpm, &policy);
+    let session = start_policy_session(&mut tpm, TpmSe::Policy);
+    assert_eq!(
+        run(&mut tpm, &session.handle, &PolicyLocalityCmd { locality }),
+        0
+    );
+
+    let command_hash = cp_hash(&[name.get_buffer()], &sign_command());
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, b"", &command_hash);
+    let request = build_session_request(&key, &[auth], &sign_command());
+    // TPM_RC_LOCALITY, as commands are sent at locality 0 by default.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x907);
+    tpm.set_locality(1);
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0);
+}
+
+#[test]
+fn policy_or_requires_current_digest() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let session = start_policy_session(&mut tpm, TpmSe::Policy);
+    let other = Tpm2bDigest::from_bytes(&[0xAA; 32]).unwrap();
+
+    let command = PolicyOrCmd {
+        p_hash_list: TpmlDigest::new(&[other, other]).unwrap(),
+    };
+    // TPM_RC_VALUE for the first parameter.
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0x1C4);
+
+    let current = Tpm2bDigest::from_bytes(&[0; 32]).unwrap();
+    let command = PolicyOrCmd {
+        p_hash_list: TpmlDigest::new(&[other, current]).unwrap(),
+    };
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0);
+    assert_eq!(
+        policy_digest(&mut tpm, &session),
+        extend(
+            &[0; 32],
+            &[&TpmCc::PolicyOR.0.to_be_bytes(), &[0xAA; 32], &[0; 32]]
+        )
+    );
+}
+
+#[test]
+fn trial_session_skips_checks() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(TpmaObject::SIGN_ENCRYPT, TpmtKdfScheme::Null(TpmsEmpty));
+    let key = load_ecc_key(&mut tpm, &public, KEY_AUTH);
+    let name = compute_name::<RustCryptoHash>(&public).unwrap();
+    let session = start_policy_session(&mut tpm, TpmSe::Trial);
+    let other = Tpm2bDigest::from_bytes(&[0xAA; 32]).unwrap();
+
+    // Neither the signature nor the alternatives of the policy are checked.
+    let command = policy_signed_command(&session, TpmtSignature::Null(TpmsEmpty));
+    assert_eq!(run(&mut tpm, &(key, session.handle), &command), 0);
+    let command = PolicyOrCmd {
+        p_hash_list: TpmlDigest::new(&[other, other]).unwrap(),
+    };
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0);
+    assert_eq!(
+        policy_digest(&mut tpm, &session),
+        extend(
+            &[0; 32],
+            &[&TpmCc::PolicyOR.0.to_be_bytes(), &[0xAA; 32], &[0xAA; 32]]
+        )
+    );
+
+    // Trial sessions can't authorize anything.
+    let command_hash = cp_hash(&[name.get_buffer()], &sign_command());
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, b"", &command_hash);
+    let request = build_session_request(&key, &[auth], &sign_command());
+    // TPM_RC_ATTRIBUTES for the first session.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x982);
+}
+
+#[test]
+fn policy_signed_checks_signature() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(
+        TpmaObject::SIGN_ENCRYPT | TpmaObject::USER_WITH_AUTH,
+        TpmtKdfScheme::Null(TpmsEmpty),
+    );
+    let key = load_ecc_key(&mut tpm, &public, KEY_AUTH);
+    let name = compute_name::<RustCryptoHash>(&public).unwrap();
+    let session = start_policy_session(&mut tpm, TpmSe::Policy);
+
+    let unsigned = policy_signed_command(&session, TpmtSignature::Null(TpmsEmpty));
+    let signature = sign_digest(&mut tpm, key, &policy_signed_digest(&unsigned));
+    let command = PolicySignedCmd {
+        policy_ref: Tpm2bNonce::from_bytes(b"other").unwrap(),
+        ..policy_signed_command(&session, signature)
+    };
+    // TPM_RC_SIGNATURE for the fifth parameter.
+    assert_eq!(run(&mut tpm, &(key, session.handle), &command), 0x5DB);
+    let command = PolicySignedCmd {
+        nonce_tpm: Tpm2bNonce::from_bytes(&[0; 32]).unwrap(),
+        ..policy_signed_command(&session, signature)
+    };
+    // TPM_RC_NONCE for the first parameter.
+    assert_eq!(run(&mut tpm, &(key, session.handle), &command), 0x1CF);
+
+    let command = policy_signed_command(&session, signature);
+    assert_eq!(run(&mut tpm, &(key, session.handle), &command), 0);
+    assert_eq!(
+        policy_digest(&mut tpm, &session),
+        extend(
+            &extend(
+                &[0; 32],
+                &[&TpmCc::PolicySigned.0.to_be_bytes(), name.get_buffer()],
+            ),
+            &[b"ref"],
+        )
+    );
+}
+
+#[test]
+fn policy_authorize_checks_ticket() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(
+        TpmaObject::SIGN_ENCRYPT | TpmaObject::USER_WITH_AUTH,
+        TpmtKdfScheme::Null(TpmsEmpty),
+    );
+    let key = load_ecc_key(&mut tpm, &public, KEY_AUTH);
+    // Only keys outside of the NULL hierarchy produce tickets that can be checked.
+    let request = load_external_request(&public, None, TpmHandle::RHOwner);
+    let (verify_key, _) = parse_response::<LoadExternalCmd>(&execute_on(&mut tpm, &request));
+    let name = compute_name::<RustCryptoHash>(&public).unwrap();
+    let session = start_policy_session(&mut tpm, TpmSe::Policy);
+    assert_eq!(run(&mut tpm, &session.handle, &PolicyAuthValueCmd {}), 0);
+
+    let approved_policy = Tpm2bDigest::from_bytes(&auth_value_policy()).unwrap();
+    let approval = Sha256::new()
+        .chain_update(approved_policy.get_buffer())
+        .chain_update(b"ref")
+        .finalize();
+    let signature = sign_digest(&mut tpm, key, &approval);
+    let command = VerifySignatureCmd {
+        digest: Tpm2bDigest::from_bytes(&approval).unwrap(),
+        signature,
+    };
+    let request = build_request(&verify_key, &[], &command);
+    let (_, response) = parse_response::<VerifySignatureCmd>(&execute_on(&mut tpm, &request));
+
+    let command = PolicyAuthorizeCmd {
+        approved_policy,
+        policy_ref: Tpm2bNonce::from_bytes(b"ref").unwrap(),
+        key_sign: name,
+        check_ticket: TpmtTkVerified {
+            digest: Tpm2bDigest::from_bytes(&[0; 32]).unwrap(),
+            ..response.validation
+        },
+    };
+    // TPM_RC_TICKET for the fourth parameter.
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0x4E0);
+    let command = PolicyAuthorizeCmd {
+        check_ticket: response.validation,
+        ..command
+    };
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0);
+    assert_eq!(
+        policy_digest(&mut tpm, &session),
+        extend(
+            &extend(
+                &[0; 32],
+                &[&TpmCc::PolicyAuthorize.0.to_be_bytes(), name.get_buffer()],
+            ),
+            &[b"ref"],
+        )
+    );
+}
+
+#[test]
+fn policy_secret_extends_with_name() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let session = start_policy_session(&mut tpm, TpmSe::Policy);
+    let command = PolicySecretCmd {
+        nonce_tpm: Tpm2bNonce::from_bytes(&session.nonce_tpm).unwrap(),
+        cp_hash_a: Tpm2bDigest::default(),
+        policy_ref: Tpm2bNonce::default(),
+        expiration: 0,
+    };
+    let request = build_request(&(TpmHandle::RHOwner, session.handle), &[b""], &command);
+    parse_response::<PolicySecretCmd>(&execute_on(&mut tpm, &request));
+    assert_eq!(
+        policy_digest(&mut tpm, &session),
+        extend(
+            &extend(
+                &[0; 32],
+                &[
+                    &TpmCc::PolicySecret.0.to_be_bytes(),
+                    &TpmHandle::RHOwner.0.to_be_bytes(),
+                ],
+            ),
+            &[],
+        )
+    );
+}
+
+#[test]
+fn policy_counter_timer_compares_time() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let session = start_policy_session(&mut tpm, TpmSe::Policy);
+    // `Time` is the first field of the time info and is never zero after the session started.
+    let command = PolicyCounterTimerCmd {
+        operand_b: Tpm2bOperand::from_bytes(&0u64.to_be_bytes()).unwrap(),
+        offset: 0,
+        operation: TpmEo::UnsignedGT,
+    };
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0);
+    let command = PolicyCounterTimerCmd {
+        operation: TpmEo::Eq,
+        ..command
+    };
+    // TPM_RC_POLICY
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0x126);
+    let command = PolicyCounterTimerCmd {
+        offset: 24,
+        ..command
+    };
+    // TPM_RC_RANGE for the second parameter.
+    assert_eq!(run(&mut tpm, &session.handle, &command), 0x2CD);
+}
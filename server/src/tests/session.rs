@@ -0,0 +1,719 @@
+extern crate std;
+use super::object::{ecc_public, load_ecc_key, load_external_request, ECC_PUBLIC_X, ECC_PUBLIC_Y};
+use super::signature::{rsa_public, rsa_sensitive, RSA_MODULUS};
+use super::{
+    build_request, build_session_request, execute_on, parse_response, response_code, TestDeps,
+};
+use crate::crypto::{
+    hmac::hmac,
+    kdf::{kdfa, kdfe},
+};
+use crate::object::compute_name;
+use crate::platform::crypto::rustcrypto::RustCryptoHash;
+use crate::tpmctx::TpmContext;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::{Aes128, Block};
+use p256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use p256::{AffinePoint, EncodedPoint, NonZeroScalar, ProjectivePoint};
+use rsa::rand_core::{self, CryptoRng, RngCore};
+use rsa::{BigUint, Oaep, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use std::vec::Vec;
+use tpm2_rs_base::commands::{
+    FlushContextCmd, GetRandomCmd, HashCmd, HashResp, LoadExternalCmd, SignCmd,
+    StartAuthSessionCmd, TpmCommand,
+};
+use tpm2_rs_base::constants::{TpmCc, TpmHandle, TpmSe, TpmSt};
+use tpm2_rs_base::marshal::{Marshalable, UnmarshalBuf};
+use tpm2_rs_base::{
+    Tpm2bAuth, Tpm2bDigest, Tpm2bEccParameter, Tpm2bEncryptedSecret, Tpm2bMaxBuffer, Tpm2bName,
+    Tpm2bNonce, Tpm2bSimple, TpmaObject, TpmaSession, TpmiAesKeyBits, TpmiAlgHash, TpmiAlgSymMode,
+    TpmiShAuthSession, TpmsAuthCommand, TpmsAuthResponse, TpmsEccPoint, TpmsEmpty, TpmsSchemeHash,
+    TpmtKdfScheme, TpmtRsaScheme, TpmtSigScheme, TpmtSymDef, TpmtTkHashcheck,
+};
+
+/// The nonce the tests use as the caller in every session.
+pub const NONCE_CALLER: [u8; 16] = [0x11; 16];
+
+pub const KEY_AUTH: &[u8] = b"secret";
+
+/// A session as the caller keeps track of it.
+pub struct TestSession {
+    pub handle: TpmHandle,
+    pub session_key: Vec<u8>,
+    pub nonce_tpm: Vec<u8>,
+}
+
+impl TestSession {
+    /// Builds the authorization of a command with `cp_hash` in the session. `auth_value` is the
+    /// authorization value of the authorized entity unless the session is bound to it.
+    pub fn authorize(
+        &self,
+        attributes: TpmaSession,
+        auth_value: &[u8],
+        cp_hash: &[u8],
+    ) -> TpmsAuthCommand {
+        let key = [&self.session_key[..], auth_value].concat();
+        let hmac = hmac::<RustCryptoHash>(
+            TpmiAlgHash::SHA256,
+            &key,
+            &[cp_hash, &NONCE_CALLER, &self.nonce_tpm, &[attributes.0]],
+        )
+        .unwrap();
+        TpmsAuthCommand {
+            session_handle: TpmiShAuthSession::try_from(self.handle.0).unwrap(),
+            nonce: Tpm2bNonce::from_bytes(&NONCE_CALLER).unwrap(),
+            session_attributes: attributes,
+            hmac: Tpm2bAuth::from_bytes(hmac.as_ref()).unwrap(),
+        }
+    }
+
+    /// Checks the HMAC of the response to a command in the session and remembers the new nonce.
+    pub fn check_response(
+        &mut self,
+        response: &TpmsAuthResponse,
+        auth_value: &[u8],
+        rp_hash: &[u8],
+    ) {
+        let key = [&self.session_key[..], auth_value].concat();
+        let hmac = hmac::<RustCryptoHash>(
+            TpmiAlgHash::SHA256,
+            &key,
+            &[
+                rp_hash,
+                response.nonce.get_buffer(),
+                &NONCE_CALLER,
+                &[response.session_attributes.0],
+            ],
+        )
+        .unwrap();
+        assert_eq!(response.hmac.get_buffer(), hmac.as_ref());
+        assert_ne!(response.nonce.get_buffer(), &self.nonce_tpm[..]);
+        self.nonce_tpm = response.nonce.get_buffer().to_vec();
+    }
+}
+
+/// Starts an unsalted SHA-256 HMAC session bound to `bind`, whose authorization value is
+/// `bind_auth`.
+pub fn start_session(
+    tpm: &mut TpmContext<TestDeps>,
+    bind: TpmHandle,
+    bind_auth: &[u8],
+) -> TestSession {
+    start_session_with(
+        tpm,
+        (TpmHandle::RHNull, bind),
+        bind_auth,
+        &start_auth_session_command(),
+    )
+}
+
+/// Starts a session with `command` salted with the key at `tpm_key` and bound to `bind`. The
+/// session key is derived from `secret`, which is the authorization value of `bind` followed by
+/// the salt.
+fn start_session_with(
+    tpm: &mut TpmContext<TestDeps>,
+    (tpm_key, bind): (TpmHandle, TpmHandle),
+    secret: &[u8],
+    command: &StartAuthSessionCmd,
+) -> TestSession {
+    let request = build_request(&(tpm_key, bind), &[], command);
+    let (handle, response) = parse_response::<StartAuthSessionCmd>(&execute_on(tpm, &request));
+    let nonce_tpm = response.nonce_tpm.get_buffer().to_vec();
+    let session_key = if secret.is_empty() {
+        Vec::new()
+    } else {
+        let mut session_key = [0; 32];
+        kdfa::<RustCryptoHash>(
+            TpmiAlgHash::SHA256,
+            secret,
+            b"ATH",
+            &nonce_tpm,
+            &NONCE_CALLER,
+            &mut session_key,
+        )
+        .unwrap();
+        session_key.to_vec()
+    };
+    TestSession {
+        handle,
+        session_key,
+        nonce_tpm,
+    }
+}
+
+pub fn start_auth_session_command() -> StartAuthSessionCmd {
+    StartAuthSessionCmd {
+        nonce_caller: Tpm2bNonce::from_bytes(&NONCE_CALLER).unwrap(),
+        encrypted_salt: Tpm2bEncryptedSecret::from_bytes(&[]).unwrap(),
+        session_type: TpmSe::HMAC,
+        symmetric: TpmtSymDef::Null(TpmsEmpty, TpmsEmpty),
+        auth_hash: TpmiAlgHash::SHA256,
+    }
+}
+
+/// Computes the SHA-256 cpHash of `command` with the given handle Names.
+pub fn cp_hash<C: TpmCommand>(names: &[&[u8]], command: &C) -> Vec<u8> {
+    let mut parameters = [0; 4096];
+    let size = command.try_marshal(&mut parameters).unwrap();
+    let mut hasher = Sha256::new();
+    hasher.update(C::CMD_CODE.0.to_be_bytes());
+    for name in names {
+        hasher.update(name);
+    }
+    hasher.update(&parameters[..size]);
+    hasher.finalize().to_vec()
+}
+
+/// Computes the SHA-256 rpHash of a successful response with `parameters`.
+pub fn rp_hash(command_code: TpmCc, parameters: &[u8]) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(0u32.to_be_bytes())
+        .chain_update(command_code.0.to_be_bytes())
+        .chain_update(parameters)
+        .finalize()
+        .to_vec()
+}
+
+/// Splits a successful response with `handles` response handles and an authorization area into
+/// its parameters and session responses.
+pub fn split_response(response: &[u8], handles: usize) -> (&[u8], Vec<TpmsAuthResponse>) {
+    assert_eq!(
+        response_code(response),
+        0,
+        "unexpected response {response:02x?}"
+    );
+    assert_eq!(response[..2], TpmSt::Sessions.0.to_be_bytes());
+    let start = 10 + 4 * handles + 4;
+    let size = u32::from_be_bytes(response[start - 4..start].try_into().unwrap()) as usize;
+    let mut buffer = UnmarshalBuf::new(&response[start + size..]);
+    let mut sessions = Vec::new();
+    while !buffer.is_empty() {
+        sessions.push(TpmsAuthResponse::try_unmarshal(&mut buffer).unwrap());
+    }
+    (&response[start..start + size], sessions)
+}
+
+pub fn sign_command() -> SignCmd {
+    SignCmd {
+        digest: Tpm2bDigest::from_bytes(&[0xAB; 32]).unwrap(),
+        in_scheme: TpmtSigScheme::Ecdsa(TpmsSchemeHash {
+            hash_alg: TpmiAlgHash::SHA256,
+        }),
+        validation: TpmtTkHashcheck {
+            tag: TpmSt::HashCheck,
+            hierarchy: TpmHandle::RHNull,
+            digest: Tpm2bDigest::from_bytes(&[]).unwrap(),
+        },
+    }
+}
+
+/// Loads the ECC test key with [`KEY_AUTH`] and returns its handle and Name.
+fn load_signing_key(tpm: &mut TpmContext<TestDeps>) -> (TpmHandle, Tpm2bName) {
+    let public = ecc_public(
+        TpmaObject::SIGN_ENCRYPT | TpmaObject::USER_WITH_AUTH,
+        TpmtKdfScheme::Null(TpmsEmpty),
+    );
+    let key = load_ecc_key(tpm, &public, KEY_AUTH);
+    (key, compute_name::<RustCryptoHash>(&public).unwrap())
+}
+
+/// Signs with `key` authorized by `auth` and returns the response.
+fn sign_in_session(
+    tpm: &mut TpmContext<TestDeps>,
+    key: TpmHandle,
+    auth: TpmsAuthCommand,
+) -> Vec<u8> {
+    execute_on(tpm, &build_session_request(&key, &[auth], &sign_command()))
+}
+
+#[test]
+fn start_auth_session_returns_nonce() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let first = start_session(&mut tpm, TpmHandle::RHNull, b"");
+    let second = start_session(&mut tpm, TpmHandle::RHNull, b"");
+    assert_eq!(first.handle, TpmHandle(0x02000000));
+    assert_eq!(second.handle, TpmHandle(0x02000001));
+    assert_eq!(first.nonce_tpm.len(), 32);
+    assert_ne!(first.nonce_tpm, second.nonce_tpm);
+}
+
+#[test]
+fn start_auth_session_rejects_short_nonce() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let command = StartAuthSessionCmd {
+        nonce_caller: Tpm2bNonce::from_bytes(&[0x11; 15]).unwrap(),
+        ..start_auth_session_command()
+    };
+    let request = build_request(&(TpmHandle::RHNull, TpmHandle::RHNull), &[], &command);
+    // TPM_RC_SIZE for the first parameter.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x1D5);
+}
+
+#[test]
+fn start_salted_session_needs_salt() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let (key, _) = load_signing_key(&mut tpm);
+    let request = build_request(
+        &(key, TpmHandle::RHNull),
+        &[],
+        &start_auth_session_command(),
+    );
+    // TPM_RC_HANDLE for the first handle.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x18B);
+}
+
+/// The label that salts are encrypted with.
+const SECRET_LABEL: &str = "SECRET\0";
+
+/// A deterministic RNG for RSA-OAEP, which is only good enough for tests.
+struct CounterRng(u8);
+
+impl RngCore for CounterRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest {
+            self.0 = self.0.wrapping_add(1);
+            *byte = self.0;
+        }
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for CounterRng {}
+
+fn salt_key_attributes() -> TpmaObject {
+    TpmaObject::DECRYPT | TpmaObject::USER_WITH_AUTH
+}
+
+/// Loads the RSA test key for decryption and encrypts `salt` to it with RSA-OAEP. Returns the
+/// handle of the key and the encrypted salt.
+fn rsa_salt(tpm: &mut TpmContext<TestDeps>, salt: &[u8]) -> (TpmHandle, Vec<u8>) {
+    let public = rsa_public(salt_key_attributes(), TpmtRsaScheme::Null(TpmsEmpty));
+    let request = load_external_request(&public, Some(&rsa_sensitive()), TpmHandle::RHNull);
+    let (key, _) = parse_response::<LoadExternalCmd>(&execute_on(tpm, &request));
+    let public_key = RsaPublicKey::new(
+        BigUint::from_bytes_be(&RSA_MODULUS),
+        BigUint::from(65537u32),
+    )
+    .unwrap();
+    let padding = Oaep::new_with_label::<Sha256, _>(SECRET_LABEL);
+    let encrypted_salt = public_key
+        .encrypt(&mut CounterRng(0), padding, salt)
+        .unwrap();
+    (key, encrypted_salt)
+}
+
+/// Loads the NIST P-256 test key for decryption and derives a salt from it with an ephemeral
+/// key. Returns the handle of the key, the salt and the ephemeral public point.
+fn ecc_salt(tpm: &mut TpmContext<TestDeps>) -> (TpmHandle, Vec<u8>, TpmsEccPoint) {
+    let key = load_ecc_key(
+        tpm,
+        &ecc_public(salt_key_attributes(), TpmtKdfScheme::Null(TpmsEmpty)),
+        b"",
+    );
+    let ephemeral = NonZeroScalar::from_repr([0x42; 32].into()).unwrap();
+    let tpm_point =
+        EncodedPoint::from_affine_coordinates(&ECC_PUBLIC_X.into(), &ECC_PUBLIC_Y.into(), false);
+    let tpm_point = AffinePoint::from_encoded_point(&tpm_point).unwrap();
+    let point = (ProjectivePoint::GENERATOR * *ephemeral)
+        .to_affine()
+        .to_encoded_point(false);
+    let z = (ProjectivePoint::from(tpm_point) * *ephemeral)
+        .to_affine()
+        .to_encoded_point(false);
+    let mut salt = [0; 32];
+    kdfe::<RustCryptoHash>(
+        TpmiAlgHash::SHA256,
+        z.x().unwrap(),
+        SECRET_LABEL.as_bytes(),
+        point.x().unwrap(),
+        &ECC_PUBLIC_X,
+        &mut salt,
+    )
+    .unwrap();
+    let point = TpmsEccPoint {
+        x: Tpm2bEccParameter::from_bytes(point.x().unwrap()).unwrap(),
+        y: Tpm2bEccParameter::from_bytes(point.y().unwrap()).unwrap(),
+    };
+    (key, salt.to_vec(), point)
+}
+
+fn encrypted_ecc_salt(point: &TpmsEccPoint) -> Tpm2bEncryptedSecret {
+    let mut buffer = [0; 256];
+    let size = point.try_marshal(&mut buffer).unwrap();
+    Tpm2bEncryptedSecret::from_bytes(&buffer[..size]).unwrap()
+}
+
+#[test]
+fn salted_session_authorizes_command() {
+    for rsa in [false, true] {
+        for bound in [false, true] {
+            let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+            let (key, name) = load_signing_key(&mut tpm);
+            let (tpm_key, salt, encrypted_salt) = if rsa {
+                let salt = [0x5A; 32];
+                let (tpm_key, encrypted_salt) = rsa_salt(&mut tpm, &salt);
+                let encrypted_salt = Tpm2bEncryptedSecret::from_bytes(&encrypted_salt).unwrap();
+                (tpm_key, salt.to_vec(), encrypted_salt)
+            } else {
+                let (tpm_key, salt, point) = ecc_salt(&mut tpm);
+                (tpm_key, salt, encrypted_ecc_salt(&point))
+            };
+            let (bind, bind_auth, auth_value) = if bound {
+                (key, KEY_AUTH, &b""[..])
+            } else {
+                (TpmHandle::RHNull, &b""[..], KEY_AUTH)
+            };
+            let command = StartAuthSessionCmd {
+                encrypted_salt,
+                ..start_auth_session_command()
+            };
+            let secret = [bind_auth, &salt].concat();
+            let mut session = start_session_with(&mut tpm, (tpm_key, bind), &secret, &command);
+
+            // Only a TPM that recovered the salt can compute the session key.
+            let command_hash = cp_hash(&[name.get_buffer()], &sign_command());
+            let auth = session.authorize(TpmaSession::CONTINUE_SESSION, auth_value, &command_hash);
+            let response = sign_in_session(&mut tpm, key, auth);
+            let (parameters, sessions) = split_response(&response, 0);
+            session.check_response(&sessions[0], auth_value, &rp_hash(TpmCc::Sign, parameters));
+        }
+    }
+}
+
+/// Starts a session salted with the key at `tpm_key` and returns the response code.
+fn start_salted_session(
+    tpm: &mut TpmContext<TestDeps>,
+    tpm_key: TpmHandle,
+    encrypted_salt: Tpm2bEncryptedSecret,
+) -> u32 {
+    let command = StartAuthSessionCmd {
+        encrypted_salt,
+        ..start_auth_session_command()
+    };
+    let request = build_request(&(tpm_key, TpmHandle::RHNull), &[], &command);
+    response_code(&execute_on(tpm, &request))
+}
+
+#[test]
+fn start_salted_session_checks_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let (signing_key, _) = load_signing_key(&mut tpm);
+    let public = ecc_public(salt_key_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let request = load_external_request(&public, None, TpmHandle::RHNull);
+    let (public_key, _) = parse_response::<LoadExternalCmd>(&execute_on(&mut tpm, &request));
+    let (_, _, point) = ecc_salt(&mut tpm);
+    let salt = encrypted_ecc_salt(&point);
+
+    // TPM_RC_VALUE for the second parameter, as there is no key to decrypt the salt.
+    assert_eq!(
+        start_salted_session(&mut tpm, TpmHandle::RHNull, salt),
+        0x2C4
+    );
+    // TPM_RC_ATTRIBUTES for the first handle, as the key can't decrypt.
+    assert_eq!(start_salted_session(&mut tpm, signing_key, salt), 0x182);
+    // TPM_RC_HANDLE for the first handle, as the private part of the key is not loaded.
+    assert_eq!(start_salted_session(&mut tpm, public_key, salt), 0x18B);
+}
+
+#[test]
+fn start_salted_session_checks_salt() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let (rsa_key, mut encrypted_salt) = rsa_salt(&mut tpm, &[0x5A; 32]);
+    let (ecc_key, _, mut point) = ecc_salt(&mut tpm);
+
+    // TPM_RC_VALUE for the second parameter if the OAEP padding is wrong.
+    encrypted_salt[0] ^= 1;
+    let salt = Tpm2bEncryptedSecret::from_bytes(&encrypted_salt).unwrap();
+    assert_eq!(start_salted_session(&mut tpm, rsa_key, salt), 0x2C4);
+    // TPM_RC_ECC_POINT for the second parameter if the point is not on the curve.
+    let mut y = point.y.get_buffer().to_vec();
+    y[31] ^= 1;
+    point.y = Tpm2bEccParameter::from_bytes(&y).unwrap();
+    let salt = encrypted_ecc_salt(&point);
+    assert_eq!(start_salted_session(&mut tpm, ecc_key, salt), 0x2E7);
+}
+
+#[test]
+fn hmac_session_authorizes_command() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let (key, name) = load_signing_key(&mut tpm);
+    let mut session = start_session(&mut tpm, TpmHandle::RHNull, b"");
+    let command_hash = cp_hash(&[name.get_buffer()], &sign_command());
+
+    // The nonces change with every command, so each command needs a new HMAC.
+    for _ in 0..2 {
+        let auth = session.authorize(TpmaSession::CONTINUE_SESSION, KEY_AUTH, &command_hash);
+        let response = sign_in_session(&mut tpm, key, auth);
+        let (parameters, sessions) = split_response(&response, 0);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(
+            sessions[0].session_attributes,
+            TpmaSession::CONTINUE_SESSION
+        );
+        session.check_response(&sessions[0], KEY_AUTH, &rp_hash(TpmCc::Sign, parameters));
+    }
+}
+
+#[test]
+fn hmac_session_rejects_wrong_hmac() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let (key, name) = load_signing_key(&mut tpm);
+    let mut session = start_session(&mut tpm, TpmHandle::RHNull, b"");
+    let command_hash = cp_hash(&[name.get_buffer()], &sign_command());
+
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, b"wrong", &command_hash);
+    // TPM_RC_AUTH_FAIL for the first session.
+    assert_eq!(response_code(&sign_in_session(&mut tpm, key, auth)), 0x98E);
+
+    // A replayed authorization is rejected because the TPM moved on to a new nonce.
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, KEY_AUTH, &command_hash);
+    let response = sign_in_session(&mut tpm, key, auth);
+    let (parameters, sessions) = split_response(&response, 0);
+    session.check_response(&sessions[0], KEY_AUTH, &rp_hash(TpmCc::Sign, parameters));
+    assert_eq!(response_code(&sign_in_session(&mut tpm, key, auth)), 0x98E);
+}
+
+#[test]
+fn bound_session_omits_auth_value() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let (key, name) = load_signing_key(&mut tpm);
+    let mut session = start_session(&mut tpm, key, KEY_AUTH);
+    let command_hash = cp_hash(&[name.get_buffer()], &sign_command());
+
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, b"", &command_hash);
+    let response = sign_in_session(&mut tpm, key, auth);
+    let (parameters, sessions) = split_response(&response, 0);
+    session.check_response(&sessions[0], b"", &rp_hash(TpmCc::Sign, parameters));
+
+    // The session key alone is not enough for other entities. A copy of the bind key would have
+    // the same Name, so the other key differs in its attributes.
+    let public = ecc_public(
+        TpmaObject::SIGN_ENCRYPT | TpmaObject::USER_WITH_AUTH | TpmaObject::NO_DA,
+        TpmtKdfScheme::Null(TpmsEmpty),
+    );
+    let other = load_ecc_key(&mut tpm, &public, KEY_AUTH);
+    let other_name = compute_name::<RustCryptoHash>(&public).unwrap();
+    let command_hash = cp_hash(&[other_name.get_buffer()], &sign_command());
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, b"", &command_hash);
+    // TPM_RC_BAD_AUTH for the first session, as the other key is exempt from dictionary attacks.
+    assert_eq!(
+        response_code(&sign_in_session(&mut tpm, other, auth)),
+        0x9A2
+    );
+    let auth = session.authorize(TpmaSession::CONTINUE_SESSION, KEY_AUTH, &command_hash);
+    let response = sign_in_session(&mut tpm, other, auth);
+    let (parameters, sessions) = split_response(&response, 0);
+    session.check_response(&sessions[0], KEY_AUTH, &rp_hash(TpmCc::Sign, parameters));
+}
+
+#[test]
+fn session_ends_without_continue_session() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let (key, name) = load_signing_key(&mut tpm);
+    let session = start_session(&mut tpm, TpmHandle::RHNull, b"");
+    let command_hash = cp_hash(&[name.get_buffer()], &sign_command());
+
+    let auth = session.authorize(TpmaSession::empty(), KEY_AUTH, &command_hash);
+    let response = sign_in_session(&mut tpm, key, auth);
+    let (_, sessions) = split_response(&response, 0);
+    assert_eq!(sessions[0].session_attributes, TpmaSession::empty());
+    // TPM_RC_REFERENCE_S0 now that the session is gone.
+    assert_eq!(response_code(&sign_in_session(&mut tpm, key, auth)), 0x918);
+}
+
+#[test]
+fn flush_context_ends_session() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let session = start_session(&mut tpm, TpmHandle::RHNull, b"");
+    let flush = FlushContextCmd {
+        flush_handle: session.handle,
+    };
+    let request = build_request(&(), &[], &flush);
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0);
+    // TPM_RC_HANDLE for the first parameter.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x1CB);
+}
+
+/// The symmetric definitions that sessions encrypt parameters with.
+const XOR_SHA256: TpmtSymDef = TpmtSymDef::ExclusiveOr(TpmiAlgHash::SHA256, TpmsEmpty);
+const AES_128_CFB: TpmtSymDef = TpmtSymDef::Aes(TpmiAesKeyBits(128), TpmiAlgSymMode::CFB);
+
+/// Encrypts or decrypts `data` like `session` does with `symmetric`, for a command that it does
+/// not authorize. `nonce_newer` is the nonce of the sender.
+fn crypt_parameter(
+    session: &TestSession,
+    symmetric: TpmtSymDef,
+    nonce_newer: &[u8],
+    nonce_older: &[u8],
+    data: &mut [u8],
+    decrypt: bool,
+) {
+    let key = &session.session_key;
+    if symmetric == XOR_SHA256 {
+        let mut mask = std::vec![0; data.len()];
+        kdfa::<RustCryptoHash>(
+            TpmiAlgHash::SHA256,
+            key,
+            b"XOR",
+            nonce_newer,
+            nonce_older,
+            &mut mask,
+        )
+        .unwrap();
+        data.iter_mut()
+            .zip(mask)
+            .for_each(|(byte, mask)| *byte ^= mask);
+        return;
+    }
+    assert_eq!(symmetric, AES_128_CFB);
+    let mut key_and_iv = [0; 32];
+    kdfa::<RustCryptoHash>(
+        TpmiAlgHash::SHA256,
+        key,
+        b"CFB",
+        nonce_newer,
+        nonce_older,
+        &mut key_and_iv,
+    )
+    .unwrap();
+    let (key, iv) = key_and_iv.split_at(16);
+    let cipher = Aes128::new_from_slice(key).unwrap();
+    let mut feedback = Block::default();
+    feedback.copy_from_slice(iv);
+    for block in data.chunks_mut(16) {
+        let mut key_stream = feedback;
+        cipher.encrypt_block(&mut key_stream);
+        if decrypt {
+            feedback[..block.len()].copy_from_slice(block);
+        }
+        block
+            .iter_mut()
+            .zip(key_stream)
+            .for_each(|(byte, key)| *byte ^= key);
+        if !decrypt {
+            feedback[..block.len()].copy_from_slice(block);
+        }
+    }
+}
+
+/// Starts a session bound to the signing key that encrypts parameters with `symmetric`.
+fn start_encrypting_session(tpm: &mut TpmContext<TestDeps>, symmetric: TpmtSymDef) -> TestSession {
+    let (key, _) = load_signing_key(tpm);
+    let command = StartAuthSessionCmd {
+        symmetric,
+        ..start_auth_session_command()
+    };
+    start_session_with(tpm, (TpmHandle::RHNull, key), KEY_AUTH, &command)
+}
+
+#[test]
+fn session_encrypts_hash_parameters() {
+    // The data is not a multiple of the AES block size.
+    let data = [0x5A; 40];
+    for symmetric in [XOR_SHA256, AES_128_CFB] {
+        for in_place in [false, true] {
+            let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+            let mut session = start_encrypting_session(&mut tpm, symmetric);
+            let mut encrypted = data;
+            let nonce_tpm = session.nonce_tpm.clone();
+            crypt_parameter(
+                &session,
+                symmetric,
+                &NONCE_CALLER,
+                &nonce_tpm,
+                &mut encrypted,
+                false,
+            );
+            let command = HashCmd {
+                data: Tpm2bMaxBuffer::from_bytes(&encrypted).unwrap(),
+                hash_alg: TpmiAlgHash::SHA256,
+                hierarchy: TpmHandle::RHNull,
+            };
+            let attributes =
+                TpmaSession::CONTINUE_SESSION | TpmaSession::DECRYPT | TpmaSession::ENCRYPT;
+            let auth = session.authorize(attributes, b"", &cp_hash(&[], &command));
+            let request = build_session_request(&(), &[auth], &command);
+            let response = if in_place {
+                let mut buffer = request.clone();
+                buffer.resize(4096, 0xFF);
+                let size = tpm.execute_command_in_place(&mut buffer, request.len());
+                buffer.truncate(size);
+                buffer
+            } else {
+                execute_on(&mut tpm, &request)
+            };
+
+            // The HMAC covers the encrypted parameters.
+            let (parameters, sessions) = split_response(&response, 0);
+            session.check_response(&sessions[0], b"", &rp_hash(TpmCc::Hash, parameters));
+            let response = HashResp::try_unmarshal(&mut UnmarshalBuf::new(parameters)).unwrap();
+            let mut out_hash = response.out_hash.get_buffer().to_vec();
+            assert_ne!(out_hash, Sha256::digest(data).to_vec());
+            crypt_parameter(
+                &session,
+                symmetric,
+                &session.nonce_tpm,
+                &NONCE_CALLER,
+                &mut out_hash,
+                true,
+            );
+            assert_eq!(out_hash, Sha256::digest(data).to_vec());
+        }
+    }
+}
+
+#[test]
+fn start_auth_session_checks_symmetric() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let command = StartAuthSessionCmd {
+        symmetric: TpmtSymDef::Aes(TpmiAesKeyBits(128), TpmiAlgSymMode::ECB),
+        ..start_auth_session_command()
+    };
+    let request = build_request(&(TpmHandle::RHNull, TpmHandle::RHNull), &[], &command);
+    // TPM_RC_MODE for the fourth parameter.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x4C9);
+}
+
+#[test]
+fn parameter_encryption_checks_session_and_command() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let hash = HashCmd {
+        data: Tpm2bMaxBuffer::from_bytes(b"abc").unwrap(),
+        hash_alg: TpmiAlgHash::SHA256,
+        hierarchy: TpmHandle::RHNull,
+    };
+    let session = start_session(&mut tpm, TpmHandle::RHNull, b"");
+    let attributes = TpmaSession::CONTINUE_SESSION | TpmaSession::ENCRYPT;
+    let auth = session.authorize(attributes, b"", &cp_hash(&[], &hash));
+    let request = build_session_request(&(), &[auth], &hash);
+    // TPM_RC_SYMMETRIC for the first session, which has no symmetric algorithm.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x996);
+
+    let first = start_encrypting_session(&mut tpm, XOR_SHA256);
+    let attributes = TpmaSession::CONTINUE_SESSION | TpmaSession::DECRYPT;
+    let get_random = GetRandomCmd { bytes_requested: 8 };
+    let auth = first.authorize(attributes, b"", &cp_hash(&[], &get_random));
+    let request = build_session_request(&(), &[auth], &get_random);
+    // TPM_RC_ATTRIBUTES for the first session, as the first parameter is no TPM2B.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x982);
+
+    let second = start_encrypting_session(&mut tpm, XOR_SHA256);
+    let command_hash = cp_hash(&[], &hash);
+    let auths = [
+        first.authorize(attributes, b"", &command_hash),
+        second.authorize(attributes, b"", &command_hash),
+    ];
+    let request = build_session_request(&(), &auths, &hash);
+    // TPM_RC_ATTRIBUTES for the second session, as only one session may decrypt.
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0xA82);
+}
@@ -0,0 +1,426 @@
+extern crate std;
+use super::object::{ecc_public, load_ecc_key, load_external_request};
+use super::{build_request, execute_on, parse_response, response_code, TestDeps};
+use crate::tpmctx::TpmContext;
+use hex_literal::hex;
+use std::vec::Vec;
+use tpm2_rs_base::commands::{HashCmd, LoadExternalCmd, SignCmd, VerifySignatureCmd};
+use tpm2_rs_base::constants::{TpmHandle, TpmSt};
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bAuth, Tpm2bDigest, Tpm2bEccParameter, Tpm2bMaxBuffer,
+    Tpm2bPrivateKeyRsa, Tpm2bPublicKeyRsa, Tpm2bSensitiveData, Tpm2bSimple, TpmaObject,
+    TpmiAlgHash, TpmiRsaKeyBits, TpmsEmpty, TpmsKeyedHashParms, TpmsRsaParms, TpmsSchemeHash,
+    TpmsSignatureEcc, TpmsSignatureRsa, TpmtHa, TpmtKdfScheme, TpmtKeyedHashScheme, TpmtPublic,
+    TpmtRsaScheme, TpmtSensitive, TpmtSigScheme, TpmtSignature, TpmtSymDefObject, TpmtTkHashcheck,
+    TpmuSensitiveComposite,
+};
+
+/// The modulus of a 1024-bit RSA test key with the default public exponent.
+pub const RSA_MODULUS: [u8; 128] = hex!(
+    "bb5df88f38ea1c250b0f46091b6f79986f6e391978721e0087e22175ad6ce036"
+    "9f05ae1b944100de7f967a292ce0df0075dea31371b555279e5ca53b9d3b3da2"
+    "e2f5c97f9e356eddc5a2500caa76c04f52669dbc41cbecfe35248ac588e00143"
+    "dfcd7e1154db37caf042bd7764187aba8bcf24becd5f5de971ceef89b308e699"
+);
+/// One of the prime factors of [`RSA_MODULUS`].
+const RSA_PRIME: [u8; 64] = hex!(
+    "f2f64b89415a99ca69b101162a838fd73b9f72e3e6685e6fe4ebcaabdf7c2fcc"
+    "080102fcc4c4d0db5062bc0952985901655fd89a3d1f41507a74a434f66c7bb7"
+);
+/// The SHA-256 digest of "abc".
+const DIGEST: [u8; 32] = hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+/// The RSASSA-PKCS1-v1_5 signature of [`DIGEST`] with the RSA test key.
+const RSASSA_SIGNATURE: [u8; 128] = hex!(
+    "b63369899b258271c76d7d39dbad8f6ad26776201408ec9fa705bbb00c96591c"
+    "c95fb6c9d618b67fc6d884857f0d7e7d2db2388f787010114720989082f42df1"
+    "a870fcdc41bb5bd1c922e9399d6ad2d82ba8df667ee1415140b61555df5824d6"
+    "1063ebb71053790e4d95605dd2c15f43a74ca9f947dc58dc41daf6de680d2924"
+);
+/// An RSASSA-PSS signature of [`DIGEST`] with the RSA test key and a 32-byte salt.
+const RSAPSS_SIGNATURE: [u8; 128] = hex!(
+    "a26ce735a4955fa714d3068c677f51394a04ae5a14be9b9cb4d0ad599f0f4195"
+    "546daa03d7de564b8c0807c8a6c0a9147e79ad4d8f1c18df666a0beb76462edb"
+    "69ec161bbb4032c94cc865a9cc32edf2cf83ab5d78f113e60e6181a621da89f7"
+    "a9542f064dbecf2b37492d2544a6796307f3ee6764772549c4f9ae9fa62e36a7"
+);
+/// The HMAC key of the keyed hash test key.
+const HMAC_KEY: [u8; 32] = [0x0b; 32];
+/// The HMAC-SHA256 of [`DIGEST`] with [`HMAC_KEY`].
+const HMAC_SIGNATURE: [u8; 32] =
+    hex!("1e18c60af672a9a88fc69f853427d3283774b9f9672a5f83833ea5a463a47ccf");
+
+fn sign_attributes() -> TpmaObject {
+    TpmaObject::SIGN_ENCRYPT | TpmaObject::USER_WITH_AUTH
+}
+
+fn sha256() -> TpmsSchemeHash {
+    TpmsSchemeHash {
+        hash_alg: TpmiAlgHash::SHA256,
+    }
+}
+
+pub fn rsa_public(object_attributes: TpmaObject, scheme: TpmtRsaScheme) -> TpmtPublic {
+    TpmtPublic {
+        name_alg: TpmiAlgHash::SHA256,
+        object_attributes,
+        auth_policy: Default::default(),
+        parms_and_id: PublicParmsAndId::Rsa(
+            TpmsRsaParms {
+                symmetric: TpmtSymDefObject::Null(TpmsEmpty, TpmsEmpty),
+                scheme,
+                key_bits: TpmiRsaKeyBits(1024),
+                exponent: 0,
+            },
+            Tpm2bPublicKeyRsa::from_bytes(&RSA_MODULUS).unwrap(),
+        ),
+    }
+}
+
+pub fn rsa_sensitive() -> TpmtSensitive {
+    TpmtSensitive {
+        auth_value: Tpm2bAuth::from_bytes(&[]).unwrap(),
+        seed_value: Default::default(),
+        sensitive: TpmuSensitiveComposite::Rsa(Tpm2bPrivateKeyRsa::from_bytes(&RSA_PRIME).unwrap()),
+    }
+}
+
+fn hmac_public(object_attributes: TpmaObject) -> TpmtPublic {
+    TpmtPublic {
+        name_alg: TpmiAlgHash::SHA256,
+        object_attributes,
+        auth_policy: Default::default(),
+        parms_and_id: PublicParmsAndId::KeyedHash(
+            TpmsKeyedHashParms {
+                scheme: TpmtKeyedHashScheme::Hmac(sha256()),
+            },
+            // SHA-256 of the empty seed followed by the key.
+            Tpm2bDigest::from_bytes(&hex!(
+                "f0e38b830ebd8a506615ecd154330ec07ff6bf5030447b44e297db1d4b7514ac"
+            ))
+            .unwrap(),
+        ),
+    }
+}
+
+fn hmac_sensitive() -> TpmtSensitive {
+    TpmtSensitive {
+        auth_value: Tpm2bAuth::from_bytes(&[]).unwrap(),
+        seed_value: Default::default(),
+        sensitive: TpmuSensitiveComposite::Bits(Tpm2bSensitiveData::from_bytes(&HMAC_KEY).unwrap()),
+    }
+}
+
+/// Loads a key into `tpm` and returns its handle.
+fn load(
+    tpm: &mut TpmContext<TestDeps>,
+    public: &TpmtPublic,
+    sensitive: Option<&TpmtSensitive>,
+    hierarchy: TpmHandle,
+) -> TpmHandle {
+    let request = load_external_request(public, sensitive, hierarchy);
+    parse_response::<LoadExternalCmd>(&execute_on(tpm, &request)).0
+}
+
+fn null_ticket() -> TpmtTkHashcheck {
+    TpmtTkHashcheck {
+        tag: TpmSt::HashCheck,
+        hierarchy: TpmHandle::RHNull,
+        digest: Tpm2bDigest::from_bytes(&[]).unwrap(),
+    }
+}
+
+fn sign_request(key: TpmHandle, in_scheme: TpmtSigScheme, validation: TpmtTkHashcheck) -> Vec<u8> {
+    let command = SignCmd {
+        digest: Tpm2bDigest::from_bytes(&DIGEST).unwrap(),
+        in_scheme,
+        validation,
+    };
+    build_request(&key, &[b""], &command)
+}
+
+fn sign(tpm: &mut TpmContext<TestDeps>, key: TpmHandle, in_scheme: TpmtSigScheme) -> TpmtSignature {
+    let request = sign_request(key, in_scheme, null_ticket());
+    parse_response::<SignCmd>(&execute_on(tpm, &request))
+        .1
+        .signature
+}
+
+fn verify_request(key: TpmHandle, signature: TpmtSignature) -> Vec<u8> {
+    let command = VerifySignatureCmd {
+        digest: Tpm2bDigest::from_bytes(&DIGEST).unwrap(),
+        signature,
+    };
+    build_request(&key, &[], &command)
+}
+
+fn verify(tpm: &mut TpmContext<TestDeps>, key: TpmHandle, signature: TpmtSignature) -> u32 {
+    response_code(&execute_on(tpm, &verify_request(key, signature)))
+}
+
+fn rsa_signature(signature: &[u8]) -> TpmsSignatureRsa {
+    TpmsSignatureRsa {
+        hash: TpmiAlgHash::SHA256,
+        sig: Tpm2bPublicKeyRsa::from_bytes(signature).unwrap(),
+    }
+}
+
+#[test]
+fn sign_rsassa_known_answer() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = rsa_public(sign_attributes(), TpmtRsaScheme::Rsassa(sha256()));
+    let key = load(&mut tpm, &public, Some(&rsa_sensitive()), TpmHandle::RHNull);
+    let signature = sign(&mut tpm, key, TpmtSigScheme::Null(TpmsEmpty));
+    assert_eq!(
+        signature,
+        TpmtSignature::Rsassa(rsa_signature(&RSASSA_SIGNATURE))
+    );
+}
+
+#[test]
+fn verify_rsassa_and_rsapss() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = rsa_public(sign_attributes(), TpmtRsaScheme::Null(TpmsEmpty));
+    let key = load(&mut tpm, &public, None, TpmHandle::RHOwner);
+    let rsassa = TpmtSignature::Rsassa(rsa_signature(&RSASSA_SIGNATURE));
+    assert_eq!(verify(&mut tpm, key, rsassa), 0);
+    let rsapss = TpmtSignature::Rsapss(rsa_signature(&RSAPSS_SIGNATURE));
+    assert_eq!(verify(&mut tpm, key, rsapss), 0);
+    // The signature schemes are not interchangeable.
+    let wrong = TpmtSignature::Rsapss(rsa_signature(&RSASSA_SIGNATURE));
+    // TPM_RC_SIGNATURE + TPM_RC_P + TPM_RC_2
+    assert_eq!(verify(&mut tpm, key, wrong), 0x2DB);
+}
+
+#[test]
+fn sign_and_verify_rsapss() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = rsa_public(sign_attributes(), TpmtRsaScheme::Null(TpmsEmpty));
+    let key = load(&mut tpm, &public, Some(&rsa_sensitive()), TpmHandle::RHNull);
+    for hash_alg in [TpmiAlgHash::SHA256, TpmiAlgHash::SHA512] {
+        let digest = [0x5A; 64];
+        let digest = &digest[..if hash_alg == TpmiAlgHash::SHA256 {
+            32
+        } else {
+            64
+        }];
+        let command = SignCmd {
+            digest: Tpm2bDigest::from_bytes(digest).unwrap(),
+            in_scheme: TpmtSigScheme::Rsapss(TpmsSchemeHash { hash_alg }),
+            validation: null_ticket(),
+        };
+        let request = build_request(&key, &[b""], &command);
+        let signature = parse_response::<SignCmd>(&execute_on(&mut tpm, &request))
+            .1
+            .signature;
+        let command = VerifySignatureCmd {
+            digest: command.digest,
+            signature,
+        };
+        let request = build_request(&key, &[], &command);
+        assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0);
+    }
+}
+
+#[test]
+fn sign_and_verify_ecdsa_and_ecschnorr() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(sign_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let key = load_ecc_key(&mut tpm, &public, b"");
+    for scheme in [
+        TpmtSigScheme::Ecdsa(sha256()),
+        TpmtSigScheme::Ecschnorr(sha256()),
+    ] {
+        let signature = sign(&mut tpm, key, scheme);
+        assert_eq!(verify(&mut tpm, key, signature), 0);
+        let tampered = match signature {
+            TpmtSignature::Ecdsa(mut sig) => {
+                sig.signature_s = sig.signature_r;
+                TpmtSignature::Ecdsa(sig)
+            }
+            TpmtSignature::Ecschnorr(mut sig) => {
+                sig.signature_s = sig.signature_r;
+                TpmtSignature::Ecschnorr(sig)
+            }
+            _ => panic!("unexpected signature {signature:?}"),
+        };
+        // TPM_RC_SIGNATURE + TPM_RC_P + TPM_RC_2
+        assert_eq!(verify(&mut tpm, key, tampered), 0x2DB);
+    }
+}
+
+#[test]
+fn verify_ecdsa_known_answer() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(sign_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let key = load(&mut tpm, &public, None, TpmHandle::RHNull);
+    // [RFC 6979] A.2.5 with SHA-256 and message "sample".
+    let command = VerifySignatureCmd {
+        digest: Tpm2bDigest::from_bytes(&hex!(
+            "af2bdbe1aa9b6ec1e2ade1d694f41fc71a831d0268e9891562113d8a62add1bf"
+        ))
+        .unwrap(),
+        signature: TpmtSignature::Ecdsa(TpmsSignatureEcc {
+            hash: TpmiAlgHash::SHA256,
+            signature_r: Tpm2bEccParameter::from_bytes(&hex!(
+                "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716"
+            ))
+            .unwrap(),
+            signature_s: Tpm2bEccParameter::from_bytes(&hex!(
+                "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8"
+            ))
+            .unwrap(),
+        }),
+    };
+    let request = build_request(&key, &[], &command);
+    let (_, resp) = parse_response::<VerifySignatureCmd>(&execute_on(&mut tpm, &request));
+    // A key in the NULL hierarchy produces a NULL ticket.
+    assert_eq!(resp.validation.tag, TpmSt::Verified);
+    assert_eq!(resp.validation.hierarchy, TpmHandle::RHNull);
+    assert_eq!(resp.validation.digest.get_size(), 0);
+}
+
+#[test]
+fn sign_and_verify_hmac() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let key = load(
+        &mut tpm,
+        &hmac_public(sign_attributes()),
+        Some(&hmac_sensitive()),
+        TpmHandle::RHNull,
+    );
+    let signature = sign(&mut tpm, key, TpmtSigScheme::Hmac(sha256()));
+    assert_eq!(
+        signature,
+        TpmtSignature::Hmac(TpmtHa::Sha256(HMAC_SIGNATURE))
+    );
+    assert_eq!(verify(&mut tpm, key, signature), 0);
+    let wrong = TpmtSignature::Hmac(TpmtHa::Sha256(DIGEST));
+    // TPM_RC_SIGNATURE + TPM_RC_P + TPM_RC_2
+    assert_eq!(verify(&mut tpm, key, wrong), 0x2DB);
+}
+
+#[test]
+fn verify_signature_ticket() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = rsa_public(sign_attributes(), TpmtRsaScheme::Null(TpmsEmpty));
+    let key = load(&mut tpm, &public, None, TpmHandle::RHOwner);
+    let request = verify_request(key, TpmtSignature::Rsassa(rsa_signature(&RSASSA_SIGNATURE)));
+    let (_, first) = parse_response::<VerifySignatureCmd>(&execute_on(&mut tpm, &request));
+    assert_eq!(first.validation.tag, TpmSt::Verified);
+    assert_eq!(first.validation.hierarchy, TpmHandle::RHOwner);
+    assert_eq!(first.validation.digest.get_size(), 32);
+    // The ticket only depends on the digest and the key.
+    let (_, second) = parse_response::<VerifySignatureCmd>(&execute_on(&mut tpm, &request));
+    assert_eq!(first, second);
+}
+
+#[test]
+fn sign_scheme_conflicts_with_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = rsa_public(sign_attributes(), TpmtRsaScheme::Rsassa(sha256()));
+    let key = load(&mut tpm, &public, Some(&rsa_sensitive()), TpmHandle::RHNull);
+    let request = sign_request(key, TpmtSigScheme::Rsapss(sha256()), null_ticket());
+    // TPM_RC_SCHEME + TPM_RC_P + TPM_RC_2
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x2D2);
+    let request = sign_request(key, TpmtSigScheme::Ecdsa(sha256()), null_ticket());
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x2D2);
+}
+
+#[test]
+fn sign_without_scheme() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(sign_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let key = load_ecc_key(&mut tpm, &public, b"");
+    let request = sign_request(key, TpmtSigScheme::Null(TpmsEmpty), null_ticket());
+    // TPM_RC_SCHEME + TPM_RC_P + TPM_RC_2
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x2D2);
+}
+
+#[test]
+fn sign_digest_of_wrong_size() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = ecc_public(sign_attributes(), TpmtKdfScheme::Null(TpmsEmpty));
+    let key = load_ecc_key(&mut tpm, &public, b"");
+    let command = SignCmd {
+        digest: Tpm2bDigest::from_bytes(&DIGEST[..20]).unwrap(),
+        in_scheme: TpmtSigScheme::Ecdsa(sha256()),
+        validation: null_ticket(),
+    };
+    let request = build_request(&key, &[b""], &command);
+    // TPM_RC_SIZE + TPM_RC_P + TPM_RC_1
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x1D5);
+}
+
+#[test]
+fn sign_with_decryption_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let attributes = TpmaObject::DECRYPT | TpmaObject::USER_WITH_AUTH;
+    let public = ecc_public(attributes, TpmtKdfScheme::Null(TpmsEmpty));
+    let key = load_ecc_key(&mut tpm, &public, b"");
+    let request = sign_request(key, TpmtSigScheme::Ecdsa(sha256()), null_ticket());
+    // TPM_RC_KEY + TPM_RC_H + TPM_RC_1
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x19C);
+    let signature = TpmtSignature::Hmac(TpmtHa::Sha256(DIGEST));
+    // TPM_RC_ATTRIBUTES + TPM_RC_H + TPM_RC_1
+    assert_eq!(verify(&mut tpm, key, signature), 0x182);
+}
+
+#[test]
+fn sign_with_restricted_key_requires_ticket() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let attributes = sign_attributes() | TpmaObject::RESTRICTED;
+    let public = ecc_public(attributes, TpmtKdfScheme::Null(TpmsEmpty));
+    let key = load_ecc_key(&mut tpm, &public, b"");
+    let request = sign_request(key, TpmtSigScheme::Ecdsa(sha256()), null_ticket());
+    // TPM_RC_TICKET + TPM_RC_P + TPM_RC_3
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x3E0);
+
+    let hash = HashCmd {
+        data: Tpm2bMaxBuffer::from_bytes(b"abc").unwrap(),
+        hash_alg: TpmiAlgHash::SHA256,
+        hierarchy: TpmHandle::RHOwner,
+    };
+    let (_, resp) =
+        parse_response::<HashCmd>(&execute_on(&mut tpm, &build_request(&(), &[], &hash)));
+    assert_eq!(resp.out_hash.get_buffer(), DIGEST);
+    assert_eq!(resp.validation.hierarchy, TpmHandle::RHOwner);
+    let request = sign_request(key, TpmtSigScheme::Ecdsa(sha256()), resp.validation);
+    let (_, signed) = parse_response::<SignCmd>(&execute_on(&mut tpm, &request));
+    assert_eq!(verify(&mut tpm, key, signed.signature), 0);
+
+    // A ticket for another digest is rejected.
+    let mut forged = resp.validation;
+    let mut digest = [0; 32];
+    digest.copy_from_slice(forged.digest.get_buffer());
+    digest[0] ^= 1;
+    forged.digest = Tpm2bDigest::from_bytes(&digest).unwrap();
+    let request = sign_request(key, TpmtSigScheme::Ecdsa(sha256()), forged);
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x3E0);
+}
+
+#[test]
+fn hash_of_tpm_generated_data_has_null_ticket() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let hash = HashCmd {
+        data: Tpm2bMaxBuffer::from_bytes(&hex!("ff54434780170000")).unwrap(),
+        hash_alg: TpmiAlgHash::SHA256,
+        hierarchy: TpmHandle::RHOwner,
+    };
+    let (_, resp) =
+        parse_response::<HashCmd>(&execute_on(&mut tpm, &build_request(&(), &[], &hash)));
+    assert_eq!(resp.validation, null_ticket());
+}
+
+#[test]
+fn load_external_mismatched_rsa_prime() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let public = rsa_public(sign_attributes(), TpmtRsaScheme::Null(TpmsEmpty));
+    let mut sensitive = rsa_sensitive();
+    let mut prime = RSA_PRIME;
+    prime[63] ^= 2;
+    sensitive.sensitive =
+        TpmuSensitiveComposite::Rsa(Tpm2bPrivateKeyRsa::from_bytes(&prime).unwrap());
+    let request = load_external_request(&public, Some(&sensitive), TpmHandle::RHNull);
+    // TPM_RC_BINDING + TPM_RC_P + TPM_RC_1
+    assert_eq!(response_code(&execute_on(&mut tpm, &request)), 0x1E5);
+}
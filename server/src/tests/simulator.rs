@@ -0,0 +1,187 @@
+extern crate std;
+use super::TestDeps;
+use crate::simulator::{serve, Disconnect, Simulator};
+use hex_literal::hex;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::vec;
+use std::vec::Vec;
+
+const GET_RANDOM: [u8; 12] = hex!(
+    "8001" // tag
+    "0000000c" // size
+    "0000017B" // command code
+    "0004" // requested random bytes
+);
+
+const FAILURE: [u8; 10] = hex!(
+    "8001" // tag
+    "0000000a" // size
+    "00000101" // TPM_RC_FAILURE
+);
+
+/// The client end of a simulator port.
+struct Port<S>(S);
+
+impl<S: Read + Write> Port<S> {
+    fn read_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.0.read_exact(&mut bytes).unwrap();
+        u32::from_be_bytes(bytes)
+    }
+
+    /// Sends `code` followed by `payload` and returns the `size` bytes before the trailing zero.
+    fn send(&mut self, code: u32, payload: &[u8], size: usize) -> Vec<u8> {
+        self.0.write_all(&code.to_be_bytes()).unwrap();
+        self.0.write_all(payload).unwrap();
+        let mut response = vec![0; size];
+        self.0.read_exact(&mut response).unwrap();
+        assert_eq!(self.read_u32(), 0);
+        response
+    }
+
+    fn signal(&mut self, code: u32) {
+        self.send(code, &[], 0);
+    }
+
+    fn send_command(&mut self, locality: u8, command: &[u8]) -> Vec<u8> {
+        let mut payload = vec![locality];
+        payload.extend_from_slice(&(command.len() as u32).to_be_bytes());
+        payload.extend_from_slice(command);
+        self.0.write_all(&8u32.to_be_bytes()).unwrap();
+        self.0.write_all(&payload).unwrap();
+        let size = self.read_u32() as usize;
+        let mut response = vec![0; size];
+        self.0.read_exact(&mut response).unwrap();
+        assert_eq!(self.read_u32(), 0);
+        response
+    }
+}
+
+/// Serves `simulator` on a pair of Unix streams and returns the client ends of its TPM and
+/// platform ports.
+fn connect(
+    simulator: &Arc<Mutex<Simulator<TestDeps>>>,
+) -> (
+    Port<UnixStream>,
+    Port<UnixStream>,
+    thread::JoinHandle<Disconnect>,
+) {
+    let (tpm, tpm_server) = UnixStream::pair().unwrap();
+    let (platform, platform_server) = UnixStream::pair().unwrap();
+    let tpm_simulator = simulator.clone();
+    thread::spawn(move || Simulator::serve_tpm(&tpm_simulator, tpm_server).unwrap());
+    let platform_simulator = simulator.clone();
+    let platform_thread = thread::spawn(move || {
+        Simulator::serve_platform(&platform_simulator, platform_server).unwrap()
+    });
+    (Port(tpm), Port(platform), platform_thread)
+}
+
+fn new_simulator() -> Arc<Mutex<Simulator<TestDeps>>> {
+    Arc::new(Mutex::new(Simulator::new()))
+}
+
+#[test]
+fn simulator_runs_commands_while_powered_on() {
+    let simulator = new_simulator();
+    let (mut tpm, mut platform, _) = connect(&simulator);
+    assert_eq!(tpm.send_command(0, &GET_RANDOM), FAILURE);
+
+    platform.signal(1); // Power on
+    assert!(simulator.lock().unwrap().is_powered_on());
+    let response = tpm.send_command(0, &GET_RANDOM);
+    assert_eq!(&response[6..10], &[0; 4]);
+    assert_eq!(response.len(), 16);
+
+    platform.signal(2); // Power off
+    assert_eq!(tpm.send_command(0, &GET_RANDOM), FAILURE);
+}
+
+#[test]
+fn simulator_tracks_platform_signals() {
+    let simulator = new_simulator();
+    let (_tpm, mut platform, platform_thread) = connect(&simulator);
+    for (code, expected) in [(3, true), (4, false)] {
+        platform.signal(code);
+        assert_eq!(simulator.lock().unwrap().physical_presence(), expected);
+    }
+    for (code, expected) in [(9, true), (10, false)] {
+        platform.signal(code);
+        assert_eq!(simulator.lock().unwrap().cancel(), expected);
+    }
+    for (code, expected) in [(11, true), (12, false)] {
+        platform.signal(code);
+        assert_eq!(simulator.lock().unwrap().nv_available(), expected);
+    }
+
+    platform.0.write_all(&21u32.to_be_bytes()).unwrap(); // Stop
+    assert_eq!(platform_thread.join().unwrap(), Disconnect::Stop);
+}
+
+#[test]
+fn simulator_failure_mode_lasts_until_power_cycle() {
+    let simulator = new_simulator();
+    let (mut tpm, mut platform, _) = connect(&simulator);
+    platform.signal(1); // Power on
+    platform.signal(30); // Test failure mode
+    assert_eq!(tpm.send_command(0, &GET_RANDOM), FAILURE);
+    platform.signal(17); // Reset
+    assert_eq!(&tpm.send_command(0, &GET_RANDOM)[6..10], &[0; 4]);
+}
+
+#[test]
+fn simulator_reports_largest_command_and_response() {
+    let simulator = new_simulator();
+    let (mut tpm, mut platform, _) = connect(&simulator);
+    platform.signal(1); // Power on
+    tpm.send_command(3, &GET_RANDOM);
+
+    let sizes = platform.send(25, &[], 20);
+    assert_eq!(&sizes[..4], &16u32.to_be_bytes());
+    assert_eq!(&sizes[4..8], &12u32.to_ne_bytes());
+    assert_eq!(&sizes[8..12], &0x17Bu32.to_be_bytes());
+    assert_eq!(&sizes[12..16], &16u32.to_ne_bytes());
+    assert_eq!(&sizes[16..20], &[0; 4]);
+    // The sizes are reset once read.
+    assert_eq!(&platform.send(25, &[], 20)[4..], &[0; 16]);
+}
+
+#[test]
+fn simulator_handshake() {
+    let simulator = new_simulator();
+    let (mut tpm, _, _) = connect(&simulator);
+    let response = tpm.send(15, &1u32.to_be_bytes(), 8);
+    assert_eq!(response, hex!("00000001 0000000f"));
+}
+
+#[test]
+fn simulator_serves_tcp_clients_until_stopped() {
+    let tpm_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let platform_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let tpm_address = tpm_listener.local_addr().unwrap();
+    let platform_address = platform_listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        serve(
+            Simulator::<TestDeps>::new(),
+            &tpm_listener,
+            &platform_listener,
+        )
+    });
+
+    // Session end (20), then stop (21). The TPM stays powered on from one client to the next.
+    for (code, power_on) in [(20u32, true), (21, false)] {
+        let mut tpm = Port(TcpStream::connect(tpm_address).unwrap());
+        let mut platform = Port(TcpStream::connect(platform_address).unwrap());
+        if power_on {
+            platform.signal(1);
+        }
+        assert_eq!(&tpm.send_command(0, &GET_RANDOM)[6..10], &[0; 4]);
+        tpm.0.write_all(&code.to_be_bytes()).unwrap();
+        platform.0.write_all(&code.to_be_bytes()).unwrap();
+    }
+    server.join().unwrap().unwrap();
+}
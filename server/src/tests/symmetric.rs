@@ -0,0 +1,227 @@
+extern crate std;
+use super::object::load_external_request;
+use super::{build_request, execute_on, parse_response, response_code, TestDeps};
+use crate::tpmctx::TpmContext;
+use hex_literal::hex;
+use sha2::{Digest, Sha256};
+use std::vec::Vec;
+use tpm2_rs_base::commands::{EncryptDecrypt2Cmd, EncryptDecryptCmd, LoadExternalCmd};
+use tpm2_rs_base::constants::TpmHandle;
+use tpm2_rs_base::{
+    PublicParmsAndId, Tpm2bDigest, Tpm2bIv, Tpm2bMaxBuffer, Tpm2bSimple, Tpm2bSymKey, TpmaObject,
+    TpmiAesKeyBits, TpmiAlgHash, TpmiAlgSymMode, TpmiYesNo, TpmsSymCipherParms, TpmtPublic,
+    TpmtSensitive, TpmtSymDefObject, TpmuSensitiveComposite,
+};
+
+/// The AES-128 key, IV and plaintext from [NIST SP 800-38A] Appendix F.
+const KEY: [u8; 16] = hex!("2b7e151628aed2a6abf7158809cf4f3c");
+const IV: [u8; 16] = hex!("000102030405060708090a0b0c0d0e0f");
+const PLAIN_TEXT: [u8; 32] = hex!(
+    "6bc1bee22e409f96e93d7e117393172a"
+    "ae2d8a571e03ac9c9eb76fac45af8e51"
+);
+
+/// Loads [`KEY`] as an AES-128 key with the given default mode and returns its handle.
+fn load_aes_key(
+    tpm: &mut TpmContext<TestDeps>,
+    mode: TpmiAlgSymMode,
+    object_attributes: TpmaObject,
+) -> TpmHandle {
+    let public = TpmtPublic {
+        name_alg: TpmiAlgHash::SHA256,
+        object_attributes,
+        auth_policy: Default::default(),
+        parms_and_id: PublicParmsAndId::Sym(
+            TpmsSymCipherParms {
+                sym: TpmtSymDefObject::Aes(TpmiAesKeyBits(128), mode),
+            },
+            Tpm2bDigest::from_bytes(&Sha256::digest(KEY)).unwrap(),
+        ),
+    };
+    let sensitive = TpmtSensitive {
+        auth_value: Default::default(),
+        seed_value: Default::default(),
+        sensitive: TpmuSensitiveComposite::Sym(Tpm2bSymKey::from_bytes(&KEY).unwrap()),
+    };
+    let request = load_external_request(&public, Some(&sensitive), TpmHandle::RHNull);
+    parse_response::<LoadExternalCmd>(&execute_on(tpm, &request)).0
+}
+
+fn key_attributes() -> TpmaObject {
+    TpmaObject::DECRYPT | TpmaObject::SIGN_ENCRYPT | TpmaObject::USER_WITH_AUTH
+}
+
+fn encrypt_decrypt2(
+    tpm: &mut TpmContext<TestDeps>,
+    handle: TpmHandle,
+    decrypt: TpmiYesNo,
+    mode: TpmiAlgSymMode,
+    iv: &[u8],
+    data: &[u8],
+) -> Vec<u8> {
+    let command = EncryptDecrypt2Cmd {
+        in_data: Tpm2bMaxBuffer::from_bytes(data).unwrap(),
+        decrypt,
+        mode,
+        iv_in: Tpm2bIv::from_bytes(iv).unwrap(),
+    };
+    execute_on(tpm, &build_request(&handle, &[b""], &command))
+}
+
+/// Checks the [NIST SP 800-38A] test vector for `mode` in both directions.
+fn check_vector(mode: TpmiAlgSymMode, iv: &[u8], cipher_text: &[u8], iv_out: &[u8]) {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let handle = load_aes_key(&mut tpm, TpmiAlgSymMode::Null, key_attributes());
+
+    let response = encrypt_decrypt2(&mut tpm, handle, TpmiYesNo::NO, mode, iv, &PLAIN_TEXT);
+    let (_, encrypted) = parse_response::<EncryptDecrypt2Cmd>(&response);
+    assert_eq!(encrypted.out_data.get_buffer(), cipher_text);
+    assert_eq!(encrypted.iv_out.get_buffer(), iv_out);
+
+    let response = encrypt_decrypt2(&mut tpm, handle, TpmiYesNo::YES, mode, iv, cipher_text);
+    let (_, decrypted) = parse_response::<EncryptDecrypt2Cmd>(&response);
+    assert_eq!(decrypted.out_data.get_buffer(), PLAIN_TEXT);
+}
+
+#[test]
+fn encrypt_decrypt_aes_ecb() {
+    let cipher_text = hex!("3ad77bb40d7a3660a89ecaf32466ef97" "f5d3d58503b9699de785895a96fdbaaf");
+    check_vector(TpmiAlgSymMode::ECB, &[], &cipher_text, &[]);
+}
+
+#[test]
+fn encrypt_decrypt_aes_cbc() {
+    let cipher_text = hex!("7649abac8119b246cee98e9b12e9197d" "5086cb9b507219ee95db113a917678b2");
+    check_vector(TpmiAlgSymMode::CBC, &IV, &cipher_text, &cipher_text[16..]);
+}
+
+#[test]
+fn encrypt_decrypt_aes_cfb() {
+    let cipher_text = hex!("3b3fd92eb72dad20333449f8e83cfb4a" "c8a64537a0b3a93fcde3cdad9f1ce58b");
+    check_vector(TpmiAlgSymMode::CFB, &IV, &cipher_text, &cipher_text[16..]);
+}
+
+#[test]
+fn encrypt_decrypt_aes_ofb() {
+    let cipher_text = hex!("3b3fd92eb72dad20333449f8e83cfb4a" "7789508d16918f03f53c52dac54ed825");
+    // The IV is the keystream of the last block, i.e. the XOR of plaintext and ciphertext.
+    let iv_out: Vec<u8> = PLAIN_TEXT[16..]
+        .iter()
+        .zip(&cipher_text[16..])
+        .map(|(p, c)| p ^ c)
+        .collect();
+    check_vector(TpmiAlgSymMode::OFB, &IV, &cipher_text, &iv_out);
+}
+
+#[test]
+fn encrypt_decrypt_aes_ctr() {
+    let counter = hex!("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+    let cipher_text = hex!("874d6191b620e3261bef6864990db6ce" "9806f66b7970fdff8617187bb9fffdff");
+    let next_counter = hex!("f0f1f2f3f4f5f6f7f8f9fafbfcfdff01");
+    check_vector(TpmiAlgSymMode::CTR, &counter, &cipher_text, &next_counter);
+}
+
+#[test]
+fn encrypt_decrypt_chained_cfb() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let handle = load_aes_key(&mut tpm, TpmiAlgSymMode::CFB, key_attributes());
+    let expected = hex!("3b3fd92eb72dad20333449f8e83cfb4a" "c8a64537a0b3a93fcde3cdad9f1ce58b");
+
+    // Encrypting the plaintext in two calls gives the same result as a single call.
+    let mut iv = Tpm2bIv::from_bytes(&IV).unwrap();
+    let mut cipher_text = Vec::new();
+    for chunk in PLAIN_TEXT.chunks(16) {
+        let command = EncryptDecryptCmd {
+            decrypt: TpmiYesNo::NO,
+            mode: TpmiAlgSymMode::Null,
+            iv_in: iv,
+            in_data: Tpm2bMaxBuffer::from_bytes(chunk).unwrap(),
+        };
+        let response = execute_on(&mut tpm, &build_request(&handle, &[b""], &command));
+        let (_, resp) = parse_response::<EncryptDecryptCmd>(&response);
+        cipher_text.extend_from_slice(resp.out_data.get_buffer());
+        iv = resp.iv_out;
+    }
+    assert_eq!(cipher_text, expected);
+}
+
+#[test]
+fn encrypt_decrypt_mode_differs_from_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let handle = load_aes_key(&mut tpm, TpmiAlgSymMode::CFB, key_attributes());
+    let response = encrypt_decrypt2(
+        &mut tpm,
+        handle,
+        TpmiYesNo::NO,
+        TpmiAlgSymMode::CBC,
+        &IV,
+        &PLAIN_TEXT,
+    );
+    // TPM_RC_MODE + TPM_RC_P + TPM_RC_3
+    assert_eq!(response_code(&response), 0x3C9);
+}
+
+#[test]
+fn encrypt_decrypt_without_mode() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let handle = load_aes_key(&mut tpm, TpmiAlgSymMode::Null, key_attributes());
+    let response = encrypt_decrypt2(
+        &mut tpm,
+        handle,
+        TpmiYesNo::NO,
+        TpmiAlgSymMode::Null,
+        &IV,
+        &PLAIN_TEXT,
+    );
+    // TPM_RC_MODE + TPM_RC_P + TPM_RC_3
+    assert_eq!(response_code(&response), 0x3C9);
+}
+
+#[test]
+fn encrypt_decrypt_cbc_partial_block() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let handle = load_aes_key(&mut tpm, TpmiAlgSymMode::CBC, key_attributes());
+    let response = encrypt_decrypt2(
+        &mut tpm,
+        handle,
+        TpmiYesNo::NO,
+        TpmiAlgSymMode::Null,
+        &IV,
+        &PLAIN_TEXT[..20],
+    );
+    // TPM_RC_SIZE + TPM_RC_P + TPM_RC_1
+    assert_eq!(response_code(&response), 0x1D5);
+}
+
+#[test]
+fn encrypt_decrypt_short_iv() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let handle = load_aes_key(&mut tpm, TpmiAlgSymMode::CFB, key_attributes());
+    let response = encrypt_decrypt2(
+        &mut tpm,
+        handle,
+        TpmiYesNo::NO,
+        TpmiAlgSymMode::Null,
+        &IV[..8],
+        &PLAIN_TEXT,
+    );
+    // TPM_RC_SIZE + TPM_RC_P + TPM_RC_4
+    assert_eq!(response_code(&response), 0x4D5);
+}
+
+#[test]
+fn encrypt_with_decrypt_only_key() {
+    let mut tpm = TpmContext::<TestDeps>::new().unwrap();
+    let attributes = TpmaObject::DECRYPT | TpmaObject::USER_WITH_AUTH;
+    let handle = load_aes_key(&mut tpm, TpmiAlgSymMode::CFB, attributes);
+    let response = encrypt_decrypt2(
+        &mut tpm,
+        handle,
+        TpmiYesNo::NO,
+        TpmiAlgSymMode::Null,
+        &IV,
+        &PLAIN_TEXT,
+    );
+    // TPM_RC_ATTRIBUTES + TPM_RC_1
+    assert_eq!(response_code(&response), 0x182);
+}
@@ -0,0 +1,182 @@
+use tpm2_rs_base::constants::{TpmHandle, TpmSt};
+use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::{
+    Tpm2bDigest, Tpm2bName, Tpm2bSimple, TpmiAlgHash, TpmtTkCreation, TpmtTkHashcheck,
+    TpmtTkVerified,
+};
+
+use crate::crypto::{hmac::hmac, kdf::kdfa, Crypto};
+use crate::platform::{
+    crypto::{EntropySource, Hash},
+    TpmContextDeps,
+};
+
+/// The hash algorithm of the HMACs that protect tickets.
+const TICKET_HASH: TpmiAlgHash = TpmiAlgHash::SHA256;
+
+/// The size of a hierarchy proof.
+const PROOF_SIZE: usize = 32;
+
+/// The secret values that tickets of each hierarchy are HMACed with. They are drawn from the
+/// entropy source when the TPM is created, so tickets don't survive a restart of the TPM.
+pub struct HierarchyProofs {
+    platform: [u8; PROOF_SIZE],
+    owner: [u8; PROOF_SIZE],
+    endorsement: [u8; PROOF_SIZE],
+    null: [u8; PROOF_SIZE],
+}
+
+impl HierarchyProofs {
+    /// Draws new proofs for all hierarchies.
+    pub fn generate<Deps: TpmContextDeps>(crypto: &mut Crypto<Deps>) -> Self {
+        let mut proofs = Self {
+            platform: [0; PROOF_SIZE],
+            owner: [0; PROOF_SIZE],
+            endorsement: [0; PROOF_SIZE],
+            null: [0; PROOF_SIZE],
+        };
+        crypto.entropy.fill_entropy(&mut proofs.platform);
+        crypto.entropy.fill_entropy(&mut proofs.owner);
+        crypto.entropy.fill_entropy(&mut proofs.endorsement);
+        crypto.entropy.fill_entropy(&mut proofs.null);
+        proofs
+    }
+
+    /// Returns the proof of `hierarchy`, or `None` if the handle is not a hierarchy.
+    fn get(&self, hierarchy: TpmHandle) -> Option<&[u8]> {
+        match hierarchy {
+            TpmHandle::RHPlatform => Some(&self.platform),
+            TpmHandle::RHOwner => Some(&self.owner),
+            TpmHandle::RHEndorsement => Some(&self.endorsement),
+            TpmHandle::RHNull => Some(&self.null),
+            _ => None,
+        }
+    }
+
+    /// Computes the HMAC of a ticket of `hierarchy`, or returns an empty digest if the handle is
+    /// not a hierarchy.
+    fn ticket_digest<H: Hash>(
+        &self,
+        hierarchy: TpmHandle,
+        data: &[&[u8]],
+    ) -> Result<Tpm2bDigest, TpmRcError> {
+        let Some(proof) = self.get(hierarchy) else {
+            return Ok(Tpm2bDigest::default());
+        };
+        let digest = hmac::<H>(TICKET_HASH, proof, data)?;
+        Tpm2bDigest::from_bytes(digest.as_ref()).or(Err(TpmRcError::Failure))
+    }
+
+    /// Computes the ticket proving that the TPM computed `digest` with `hash_alg`, see
+    /// [TPM2.0 1.83] Part 2, 10.7.6.
+    pub fn hashcheck_ticket<H: Hash>(
+        &self,
+        hierarchy: TpmHandle,
+        hash_alg: TpmiAlgHash,
+        digest: &[u8],
+    ) -> Result<TpmtTkHashcheck, TpmRcError> {
+        let digest = self.ticket_digest::<H>(
+            hierarchy,
+            &[
+                &TpmSt::HashCheck.0.to_be_bytes(),
+                &hash_alg.0.to_be_bytes(),
+                digest,
+            ],
+        )?;
+        Ok(TpmtTkHashcheck {
+            tag: TpmSt::HashCheck,
+            hierarchy,
+            digest,
+        })
+    }
+
+    /// Computes the ticket proving that the TPM created the object named `object_name` with the
+    /// creation data digest `creation_hash`, see [TPM2.0 1.83] Part 2, 10.7.3.
+    pub fn creation_ticket<H: Hash>(
+        &self,
+        hierarchy: TpmHandle,
+        object_name: &Tpm2bName,
+        creation_hash: &[u8],
+    ) -> Result<TpmtTkCreation, TpmRcError> {
+        let digest = self.ticket_digest::<H>(
+            hierarchy,
+            &[
+                &TpmSt::Creation.0.to_be_bytes(),
+                object_name.get_buffer(),
+                creation_hash,
+            ],
+        )?;
+        Ok(TpmtTkCreation {
+            tag: TpmSt::Creation,
+            hierarchy,
+            digest,
+        })
+    }
+
+    /// Computes the values that are added to the firmware version, reset count and restart count
+    /// in attestations signed by the key named `signer_name` when it is not in the platform or
+    /// endorsement hierarchy, see [TPM2.0 1.83] Part 3, 18.1.
+    pub fn obfuscation<H: Hash>(
+        &self,
+        signer_name: &Tpm2bName,
+    ) -> Result<(u64, u32, u32), TpmRcError> {
+        let mut obfuscation = [0; 16];
+        kdfa::<H>(
+            TICKET_HASH,
+            &self.owner,
+            b"OBFUSCATE",
+            signer_name.get_buffer(),
+            &[],
+            &mut obfuscation,
+        )?;
+        let (firmware, counts) = obfuscation.split_at(8);
+        let (reset, restart) = counts.split_at(4);
+        Ok((
+            u64::from_be_bytes(firmware.try_into().or(Err(TpmRcError::Failure))?),
+            u32::from_be_bytes(reset.try_into().or(Err(TpmRcError::Failure))?),
+            u32::from_be_bytes(restart.try_into().or(Err(TpmRcError::Failure))?),
+        ))
+    }
+
+    /// Computes the ticket proving that the key named `key_name` produced a valid signature of
+    /// `digest`, see [TPM2.0 1.83] Part 2, 10.7.4.
+    pub fn verified_ticket<H: Hash>(
+        &self,
+        hierarchy: TpmHandle,
+        digest: &[u8],
+        key_name: &Tpm2bName,
+    ) -> Result<TpmtTkVerified, TpmRcError> {
+        let digest = self.ticket_digest::<H>(
+            hierarchy,
+            &[
+                &TpmSt::Verified.0.to_be_bytes(),
+                digest,
+                key_name.get_buffer(),
+            ],
+        )?;
+        Ok(TpmtTkVerified {
+            tag: TpmSt::Verified,
+            hierarchy,
+            digest,
+        })
+    }
+}
+
+/// Returns the NULL ticket that is produced when there is nothing to vouch for.
+pub fn null_hashcheck_ticket() -> TpmtTkHashcheck {
+    TpmtTkHashcheck {
+        tag: TpmSt::HashCheck,
+        hierarchy: TpmHandle::RHNull,
+        digest: Tpm2bDigest::default(),
+    }
+}
+
+/// Returns the NULL ticket that is produced when the verifying key has no hierarchy to vouch for
+/// it.
+pub fn null_verified_ticket() -> TpmtTkVerified {
+    TpmtTkVerified {
+        tag: TpmSt::Verified,
+        hierarchy: TpmHandle::RHNull,
+        digest: Tpm2bDigest::default(),
+    }
+}
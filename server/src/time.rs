@@ -0,0 +1,42 @@
+use tpm2_rs_base::{TpmiYesNo, TpmsClockInfo, TpmsTimeInfo};
+
+use crate::platform::Clock;
+
+/// Keeps track of the TPM's `Time` and `Clock`, see [TPM2.0 1.83] Part 1, 36.
+///
+/// This TPM has no non-volatile memory to persist `Clock` in, so `Clock` starts at zero whenever
+/// the TPM is created and always equals `Time`.
+pub struct TpmTime<C: Clock> {
+    clock: C,
+    /// The value of the platform clock when the TPM was created.
+    init: u64,
+}
+
+impl<C: Clock> TpmTime<C> {
+    /// Starts counting time from now.
+    pub fn new() -> Self {
+        let mut clock = C::instantiate();
+        let init = clock.now_ms();
+        Self { clock, init }
+    }
+
+    /// Returns the number of milliseconds since the TPM was created.
+    pub fn time(&mut self) -> u64 {
+        self.clock.now_ms().saturating_sub(self.init)
+    }
+
+    /// Returns `Time` together with the state of `Clock`.
+    pub fn time_info(&mut self) -> TpmsTimeInfo {
+        let time = self.time();
+        TpmsTimeInfo {
+            time,
+            clock_info: TpmsClockInfo {
+                clock: time,
+                // TPM2_Startup is not supported, so the TPM is never reset or restarted.
+                reset_count: 0,
+                restart_count: 0,
+                safe: TpmiYesNo::YES,
+            },
+        }
+    }
+}
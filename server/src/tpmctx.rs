@@ -1,14 +1,64 @@
 use crate::buffers::{InOutBuffer, SeparateBuffers};
-use crate::handler::CommandHandler;
+use crate::command::{CommandAttributes, MAX_HANDLES, MAX_SESSIONS, POSITIONS};
+use crate::crypto::constant_time_eq;
+use crate::crypto::symmetric::Direction;
+use crate::handler::{AuthError, CommandHandler};
 use crate::platform::{TpmBuffers, TpmContextDeps, TpmReadBuffer, TpmWriteBuffer};
-use crate::req_resp::RequestResponseCursor;
+use crate::req_resp::{RequestResponseCursor, RequestThenResponse, RESPONSE_HEADER_SIZE};
+use crate::session::{cp_hash, rp_hash, MIN_NONCE_SIZE};
 use crate::ServerError;
-use tpm2_rs_base::constants::TpmCc;
-use tpm2_rs_base::errors::TpmRcError;
+use tpm2_rs_base::constants::{TpmCc, TpmHandle, TpmSt};
+use tpm2_rs_base::errors::{ErrorType, TpmRcError};
+use tpm2_rs_base::marshal::Marshalable;
+use tpm2_rs_base::{
+    Tpm2bAuth, Tpm2bData, Tpm2bDigest, Tpm2bName, Tpm2bNonce, Tpm2bSimple, TpmaSession,
+    TpmiShAuthSession, TpmsAuthCommand, TpmsAuthResponse, TpmtSymDef,
+};
+
+/// A session in the authorization area of the command being executed.
+#[derive(Clone, Copy, Default)]
+struct CommandSession {
+    /// The session as the caller sent it.
+    auth: TpmsAuthCommand,
+    /// The authorization value that is part of the HMAC key of the session, which is empty if the
+    /// session does not authorize a handle or is bound to it.
+    auth_value: Tpm2bAuth,
+    /// The cpHash of the command computed with the hash algorithm of the session.
+    cp_hash: Tpm2bDigest,
+    /// Whether the session has no HMAC, as policy sessions that assert a password or have no key
+    /// to compute it with.
+    no_hmac: bool,
+}
+
+impl CommandSession {
+    fn handle(&self) -> TpmHandle {
+        TpmHandle(self.auth.session_handle.get())
+    }
+}
+
+/// Converts a failure to authorize the handle at `index` into the corresponding [`TpmRcError`].
+fn auth_error(error: AuthError, index: usize) -> TpmRcError {
+    let position = POSITIONS[index];
+    match error {
+        AuthError::Handle => TpmRcError::HandleFor(ErrorType::Handle, position),
+        AuthError::Unavailable => TpmRcError::AuthUnavailable,
+        AuthError::AuthFail => TpmRcError::AuthFailFor(ErrorType::Session, position),
+        AuthError::BadAuth => TpmRcError::BadAuthFor(ErrorType::Session, position),
+    }
+}
+
+/// Returns the index of the session that has `attribute` set, of which there is at most one.
+fn session_with(sessions: &[CommandSession], attribute: TpmaSession) -> Option<usize> {
+    sessions
+        .iter()
+        .position(|session| session.auth.session_attributes.contains(attribute))
+}
 
 /// The object that processes incoming TPM requests and produces the corresponding TPM response.
 pub struct TpmContext<Deps: TpmContextDeps> {
     handler: CommandHandler<Deps>,
+    /// The locality at which the next commands are sent.
+    locality: u8,
 }
 
 impl<Deps: TpmContextDeps> TpmContext<Deps> {
@@ -16,9 +66,21 @@ impl<Deps: TpmContextDeps> TpmContext<Deps> {
     pub fn new() -> Result<Self, ServerError> {
         Ok(Self {
             handler: CommandHandler::new()?,
+            locality: 0,
         })
     }
 
+    /// Sets the locality at which the next commands are sent, which is 0 by default. Localities 0
+    /// to 4 and the extended localities 32 to 255 are valid.
+    pub fn set_locality(&mut self, locality: u8) {
+        self.locality = locality;
+    }
+
+    /// Returns the locality at which the next commands are sent.
+    pub fn locality(&self) -> u8 {
+        self.locality
+    }
+
     /// Process a TPM request and writes the response in a separate buffer. Returns the number of
     /// bytes written to the response buffer.
     pub fn execute_command_separate(
@@ -48,41 +110,483 @@ impl<Deps: TpmContextDeps> TpmContext<Deps> {
     }
 
     fn fill_error(&mut self, response: &mut Deps::Response, error: TpmRcError) -> usize {
-        // TODO fill out more of the TPM header for an error
-        if response.write(6, &error.get().to_be_bytes()).is_err() {
-            return 0;
+        const ERROR_RESPONSE_SIZE: u32 = 10;
+        let header = [
+            &TpmSt::NoSessions.0.to_be_bytes()[..],
+            &ERROR_RESPONSE_SIZE.to_be_bytes(),
+            &error.get().to_be_bytes(),
+        ];
+        let mut offset = 0;
+        for field in header {
+            if response.write(offset, field).is_err() {
+                return 0;
+            }
+            offset += field.len();
+        }
+        offset
+    }
+
+    /// Parses the authorization area of the request and checks each session against the
+    /// handle it authorizes. Returns the sessions and their number.
+    fn authorize(
+        &mut self,
+        request: &mut RequestThenResponse<impl TpmBuffers>,
+        command_code: TpmCc,
+        handles: &[TpmHandle],
+        names: &[Tpm2bName],
+        command_attributes: CommandAttributes,
+    ) -> Result<([CommandSession; MAX_SESSIONS], usize), TpmRcError> {
+        let auth_size = request.read_be_u32().ok_or(TpmRcError::AuthMissing)? as usize;
+        let auth_end = request.position() + auth_size;
+        let mut sessions = [CommandSession::default(); MAX_SESSIONS];
+        let mut count = 0;
+        while request.position() < auth_end {
+            if count == MAX_SESSIONS {
+                return Err(TpmRcError::AuthContext);
+            }
+            sessions[count].auth = request.unmarshal()?;
+            count += 1;
+        }
+        if request.position() != auth_end {
+            return Err(TpmRcError::AuthSize);
+        }
+
+        let now = self.handler.time();
+        let mut audit_session = None;
+        for index in 0..count {
+            let (earlier, rest) = sessions.split_at_mut(index);
+            let session = &mut rest[0];
+            let position = POSITIONS[index];
+            let attributes = session.auth.session_attributes;
+            let authorizes = index < command_attributes.auth_handles;
+            if session.auth.session_handle == TpmiShAuthSession::RS_PW {
+                // Password sessions can only be used for authorization.
+                if !authorizes {
+                    return Err(TpmRcError::ValueFor(ErrorType::Session, position));
+                }
+                if attributes.intersects(!TpmaSession::CONTINUE_SESSION) {
+                    return Err(TpmRcError::AttributesFor(ErrorType::Session, position));
+                }
+                self.handler
+                    .authorize_password(handles[index], session.auth.hmac.get_buffer())
+                    .map_err(|error| auth_error(error, index))?;
+                continue;
+            }
+
+            let handle = session.handle();
+            let loaded = self
+                .handler
+                .session(handle)
+                .ok_or(TpmRcError::ReferenceFor(position))?;
+            if earlier.iter().any(|earlier| earlier.handle() == handle) {
+                return Err(TpmRcError::ValueFor(ErrorType::Session, position));
+            }
+            // Only one session may encrypt each direction, and only a first parameter that is a
+            // TPM2B.
+            for (flag, allowed) in [
+                (TpmaSession::DECRYPT, command_attributes.decrypt),
+                (TpmaSession::ENCRYPT, command_attributes.encrypt),
+            ] {
+                if attributes.contains(flag)
+                    && (!allowed
+                        || earlier
+                            .iter()
+                            .any(|earlier| earlier.auth.session_attributes.contains(flag)))
+                {
+                    return Err(TpmRcError::AttributesFor(ErrorType::Session, position));
+                }
+            }
+            if attributes.intersects(TpmaSession::DECRYPT | TpmaSession::ENCRYPT)
+                && matches!(loaded.symmetric, TpmtSymDef::Null(..))
+            {
+                return Err(TpmRcError::SymmetricFor(ErrorType::Session, position));
+            }
+            let audit = attributes.contains(TpmaSession::AUDIT);
+            if audit {
+                if audit_session.is_some() {
+                    return Err(TpmRcError::AttributesFor(ErrorType::Session, position));
+                }
+                audit_session = Some(handle);
+                if attributes.contains(TpmaSession::AUDIT_EXCLUSIVE)
+                    && !self.handler.is_exclusive_audit(handle)
+                {
+                    return Err(TpmRcError::Exclusive);
+                }
+            } else if attributes.intersects(TpmaSession::AUDIT_EXCLUSIVE | TpmaSession::AUDIT_RESET)
+                || !(authorizes
+                    || attributes.intersects(TpmaSession::DECRYPT | TpmaSession::ENCRYPT))
+            {
+                // Sessions that don't authorize a handle have to audit or encrypt.
+                return Err(TpmRcError::AttributesFor(ErrorType::Session, position));
+            }
+            let nonce_size = session.auth.nonce.get_size() as usize;
+            if nonce_size < MIN_NONCE_SIZE || nonce_size > loaded.nonce_tpm.get_size() as usize {
+                return Err(TpmRcError::SizeFor(ErrorType::Session, position));
+            }
+
+            let cp_hash = request.read_remaining(|parameters| {
+                cp_hash::<Deps::Hash>(loaded.auth_hash, command_code, names, parameters)
+            })??;
+            session.cp_hash =
+                Tpm2bDigest::from_bytes(cp_hash.as_ref()).or(Err(TpmRcError::Failure))?;
+
+            let mut no_da = true;
+            if authorizes && loaded.is_policy() {
+                loaded.check_policy::<Deps::Hash>(
+                    self.handler.auth_policy(handles[index]),
+                    command_code,
+                    cp_hash.as_ref(),
+                    names,
+                    now,
+                    position,
+                )?;
+                if !loaded.policy.allows_locality(self.locality) {
+                    return Err(TpmRcError::Locality);
+                }
+                if loaded.policy.password_needed {
+                    self.handler
+                        .authorize_policy_password(handles[index], session.auth.hmac.get_buffer())
+                        .map_err(|error| auth_error(error, index))?;
+                    session.no_hmac = true;
+                    continue;
+                }
+                // The authorization value is only part of the HMAC key if the policy asks for it.
+                if loaded.policy.auth_value_needed {
+                    let (auth_value, entity_no_da) = self
+                        .handler
+                        .policy_entity_auth_value(handles[index])
+                        .map_err(|error| auth_error(error, index))?;
+                    session.auth_value =
+                        Tpm2bAuth::from_bytes(auth_value).or(Err(TpmRcError::Failure))?;
+                    no_da = entity_no_da;
+                }
+            } else if authorizes {
+                // The authorization value is already part of the session key of a bound session.
+                let (auth_value, entity_no_da) = self
+                    .handler
+                    .auth_value(handles[index])
+                    .map_err(|error| auth_error(error, index))?;
+                if !loaded.is_bound_to(&names[index], auth_value) {
+                    session.auth_value =
+                        Tpm2bAuth::from_bytes(auth_value).or(Err(TpmRcError::Failure))?;
+                }
+                no_da = entity_no_da;
+            }
+            // A policy session without a key may omit the HMAC, which would not prove anything.
+            if loaded.is_policy()
+                && loaded.session_key.get_size() == 0
+                && session.auth_value.get_size() == 0
+                && session.auth.hmac.get_size() == 0
+            {
+                session.no_hmac = true;
+                continue;
+            }
+            let expected = loaded.hmac::<Deps::Hash>(
+                session.auth_value.get_buffer(),
+                cp_hash.as_ref(),
+                session.auth.nonce.get_buffer(),
+                loaded.nonce_tpm.get_buffer(),
+                attributes,
+            )?;
+            if !constant_time_eq(expected.as_ref(), session.auth.hmac.get_buffer()) {
+                return Err(auth_error(AuthError::wrong_auth(no_da), index));
+            }
+        }
+        Ok((sessions, count))
+    }
+
+    /// Computes the response of `session` with the new `nonce` to a successful command with the
+    /// response parameters in `rp_hash` and updates the state of the session.
+    fn respond(
+        &mut self,
+        session: &CommandSession,
+        nonce: Tpm2bNonce,
+        rp_hash: &[u8],
+    ) -> Result<TpmsAuthResponse, TpmRcError> {
+        let handle = session.handle();
+        let command_attributes = session.auth.session_attributes;
+        let mut attributes =
+            command_attributes & (TpmaSession::CONTINUE_SESSION | TpmaSession::AUDIT);
+        if command_attributes.contains(TpmaSession::AUDIT)
+            && self.handler.is_exclusive_audit(handle)
+        {
+            attributes |= TpmaSession::AUDIT_EXCLUSIVE;
+        }
+        let loaded = self
+            .handler
+            .session_mut(handle)
+            .ok_or(TpmRcError::Failure)?;
+        loaded.nonce_tpm = nonce;
+        if command_attributes.contains(TpmaSession::AUDIT) {
+            loaded.extend_audit::<Deps::Hash>(session.cp_hash.get_buffer(), rp_hash)?;
         }
-        10
+        let hmac = if session.no_hmac {
+            Tpm2bData::default()
+        } else {
+            let hmac = loaded.hmac::<Deps::Hash>(
+                session.auth_value.get_buffer(),
+                rp_hash,
+                nonce.get_buffer(),
+                session.auth.nonce.get_buffer(),
+                attributes,
+            )?;
+            Tpm2bData::from_bytes(hmac.as_ref()).or(Err(TpmRcError::Failure))?
+        };
+        Ok(TpmsAuthResponse {
+            nonce,
+            session_attributes: attributes,
+            hmac,
+        })
     }
 
     fn execute_command(&mut self, buffers: impl TpmBuffers) -> Result<usize, TpmRcError> {
         let request_size = buffers.get_request().len();
-        const CMD_HANDLER_RESPONSE_OFFSET: usize = 10;
-        let mut request_and_response =
-            RequestResponseCursor::new(buffers, CMD_HANDLER_RESPONSE_OFFSET);
+        let mut request_and_response = RequestResponseCursor::new(buffers, RESPONSE_HEADER_SIZE);
         let mut request = request_and_response.request();
-        let _session = request.read_be_u16().ok_or(TpmRcError::CommandSize)?;
+        let tag = TpmSt(request.read_be_u16().ok_or(TpmRcError::CommandSize)?);
         let size = request.read_be_u32().ok_or(TpmRcError::CommandSize)?;
         if size as usize != request_size {
             return Err(TpmRcError::CommandSize);
         }
-        let command_code = request.read_be_u32().ok_or(TpmRcError::CommandSize)?;
+        let command_code = TpmCc(request.read_be_u32().ok_or(TpmRcError::CommandSize)?);
+        if tag != TpmSt::NoSessions && tag != TpmSt::Sessions {
+            return Err(TpmRcError::BadTag);
+        }
+        let attributes = CommandAttributes::lookup(command_code).ok_or(TpmRcError::CommandCode)?;
 
-        // TODO, if _session is not NoSession, then parse session stuff here
+        let mut handles = [TpmHandle(0); MAX_HANDLES];
+        for handle in &mut handles[..attributes.handles] {
+            *handle = TpmHandle(request.read_be_u32().ok_or(TpmRcError::Insufficient)?);
+        }
+        let mut names = [Tpm2bName::default(); MAX_HANDLES];
+        for (name, handle) in names.iter_mut().zip(&handles[..attributes.handles]) {
+            *name = self.handler.entity_name(*handle)?;
+        }
+        let names = &names[..attributes.handles];
+        let (sessions, session_count) = if tag == TpmSt::Sessions {
+            self.authorize(&mut request, command_code, &handles, names, attributes)?
+        } else {
+            ([CommandSession::default(); MAX_SESSIONS], 0)
+        };
+        let sessions = &sessions[..session_count];
+        if sessions.len() < attributes.auth_handles {
+            return Err(TpmRcError::AuthMissing);
+        }
+        // The parameters may be overwritten by the response, so the cpHash for the command audit
+        // has to be computed up front.
+        let audit_cp_hash = request.read_remaining(|parameters| {
+            self.handler
+                .command_audit_alg(command_code, parameters)
+                .map(|alg| cp_hash::<Deps::Hash>(alg, command_code, names, parameters))
+                .transpose()
+        })??;
+        // The cpHashes cover the parameters as they were sent, so the first parameter is only
+        // decrypted now.
+        if let Some(index) = session_with(sessions, TpmaSession::DECRYPT) {
+            let session = &sessions[index];
+            let loaded = self
+                .handler
+                .session(session.handle())
+                .ok_or(TpmRcError::Failure)?;
+            request.modify_parameter(|data| {
+                loaded.crypt_parameter::<Deps::Hash, Deps::Cipher>(
+                    session.auth_value.get_buffer(),
+                    session.auth.nonce.get_buffer(),
+                    loaded.nonce_tpm.get_buffer(),
+                    Direction::Decrypt,
+                    data,
+                )
+            })?;
+        }
 
-        match TpmCc(command_code) {
+        // The response handles and parameter size are filled in by the handler and below.
+        let handle_area_size = attributes.response_handles * size_of::<u32>();
+        let parameter_offset = RESPONSE_HEADER_SIZE + handle_area_size;
+        let parameter_size_size = if sessions.is_empty() {
+            0
+        } else {
+            size_of::<u32>()
+        };
+        request.reserve_response(handle_area_size + parameter_size_size);
+
+        match command_code {
+            TpmCc::Certify => self.handler.certify(handles[0], handles[1], request),
+            TpmCc::CertifyCreation => self
+                .handler
+                .certify_creation(handles[0], handles[1], request),
+            TpmCc::ECCDecrypt => self.handler.ecc_decrypt(handles[0], request),
+            TpmCc::ECCEncrypt => self.handler.ecc_encrypt(handles[0], request),
+            TpmCc::ECCParameters => self.handler.ecc_parameters(request),
+            TpmCc::EncryptDecrypt => self.handler.encrypt_decrypt(handles[0], request),
+            TpmCc::EncryptDecrypt2 => self.handler.encrypt_decrypt2(handles[0], request),
+            TpmCc::FlushContext => self.handler.flush_context(request),
+            TpmCc::GetCommandAuditDigest => self
+                .handler
+                .get_command_audit_digest(handles[0], handles[1], request),
             TpmCc::GetRandom => self.handler.get_random(request),
+            TpmCc::GetSessionAuditDigest => self
+                .handler
+                .get_session_audit_digest(handles[0], handles[1], handles[2], request),
+            TpmCc::GetTime => self.handler.get_time(handles[0], handles[1], request),
+            TpmCc::Hash => self.handler.hash(request),
+            TpmCc::LoadExternal => self.handler.load_external(request),
+            TpmCc::PolicyAuthValue => self.handler.policy_auth_value(handles[0], request),
+            TpmCc::PolicyAuthorize => self.handler.policy_authorize(handles[0], request),
+            TpmCc::PolicyCommandCode => self.handler.policy_command_code(handles[0], request),
+            TpmCc::PolicyCounterTimer => self.handler.policy_counter_timer(handles[0], request),
+            TpmCc::PolicyCpHash => self.handler.policy_cp_hash(handles[0], request),
+            TpmCc::PolicyGetDigest => self.handler.policy_get_digest(handles[0], request),
+            TpmCc::PolicyLocality => self.handler.policy_locality(handles[0], request),
+            TpmCc::PolicyNameHash => self.handler.policy_name_hash(handles[0], request),
+            TpmCc::PolicyOR => self.handler.policy_or(handles[0], request),
+            TpmCc::PolicyPassword => self.handler.policy_password(handles[0], request),
+            TpmCc::PolicyPCR => self.handler.policy_pcr(handles[0], request),
+            TpmCc::PolicyRestart => self.handler.policy_restart(handles[0], request),
+            TpmCc::PolicySecret => self.handler.policy_secret(handles[0], handles[1], request),
+            TpmCc::PolicySigned => self.handler.policy_signed(handles[0], handles[1], request),
+            TpmCc::Quote => self.handler.quote(handles[0], request),
+            TpmCc::SetCommandCodeAuditStatus => self
+                .handler
+                .set_command_code_audit_status(handles[0], request),
+            TpmCc::Sign => self.handler.sign(handles[0], request),
+            TpmCc::StartAuthSession => self
+                .handler
+                .start_auth_session(handles[0], handles[1], request),
+            TpmCc::TestParams => self.handler.test_parms(request),
+            TpmCc::VerifySignature => self.handler.verify_signature(handles[0], request),
             _ => Err(TpmRcError::CommandCode),
         }?;
 
-        let response_size = request_and_response.last_response_byte_written();
+        let mut parameter_end = request_and_response.last_response_byte_written();
         let response = request_and_response.response();
-        // TODO add session information
-        let session_tag = 0x8001_u16;
+        let parameter_start = parameter_offset + parameter_size_size;
+        // Each session responds with a new nonce, which the response parameter is encrypted with.
+        let mut nonces = [Tpm2bNonce::default(); MAX_SESSIONS];
+        for (session, nonce) in sessions.iter().zip(&mut nonces) {
+            if session.auth.session_handle != TpmiShAuthSession::RS_PW {
+                let auth_hash = self
+                    .handler
+                    .session(session.handle())
+                    .ok_or(TpmRcError::Failure)?
+                    .auth_hash;
+                *nonce = self.handler.random_nonce(auth_hash)?;
+            }
+        }
+        if let Some(index) = session_with(sessions, TpmaSession::ENCRYPT) {
+            let session = &sessions[index];
+            let loaded = self
+                .handler
+                .session(session.handle())
+                .ok_or(TpmRcError::Failure)?;
+            let size = response
+                .read_be_u16(parameter_start)
+                .or(Err(TpmRcError::Failure))? as usize;
+            let data_start = parameter_start + size_of::<u16>();
+            if data_start + size > parameter_end {
+                return Err(TpmRcError::Failure);
+            }
+            let mut result = Err(TpmRcError::Failure);
+            response
+                .write_callback(data_start, size, |data| {
+                    result = loaded.crypt_parameter::<Deps::Hash, Deps::Cipher>(
+                        session.auth_value.get_buffer(),
+                        nonces[index].get_buffer(),
+                        session.auth.nonce.get_buffer(),
+                        Direction::Encrypt,
+                        data,
+                    );
+                })
+                .or(Err(TpmRcError::Failure))?;
+            result?;
+        }
+        let rp_hash_for = |alg| {
+            response
+                .read_callback(
+                    parameter_start,
+                    parameter_end - parameter_start,
+                    |parameters| rp_hash::<Deps::Hash>(alg, command_code, parameters),
+                )
+                .or(Err(TpmRcError::Failure))?
+        };
+        if let Some(cp_hash) = audit_cp_hash {
+            let rp_hash = rp_hash_for(self.handler.audit_alg())?;
+            self.handler
+                .audit_command(cp_hash.as_ref(), rp_hash.as_ref())?;
+        }
+        let audit_session = sessions.iter().find_map(|session| {
+            let attributes = session.auth.session_attributes;
+            attributes.contains(TpmaSession::AUDIT).then_some((
+                session.handle(),
+                attributes.contains(TpmaSession::AUDIT_RESET),
+            ))
+        });
+        self.handler.update_audit_exclusivity(audit_session)?;
+
+        let mut auth_responses = [TpmsAuthResponse::default(); MAX_SESSIONS];
+        for ((session, auth_response), nonce) in
+            sessions.iter().zip(&mut auth_responses).zip(nonces)
+        {
+            *auth_response = if session.auth.session_handle == TpmiShAuthSession::RS_PW {
+                // Password sessions always respond with an empty nonce and HMAC.
+                TpmsAuthResponse {
+                    session_attributes: TpmaSession::CONTINUE_SESSION,
+                    ..Default::default()
+                }
+            } else {
+                let auth_hash = self
+                    .handler
+                    .session(session.handle())
+                    .ok_or(TpmRcError::Failure)?
+                    .auth_hash;
+                let rp_hash = rp_hash_for(auth_hash)?;
+                self.respond(session, nonce, rp_hash.as_ref())?
+            };
+        }
+        // Sessions that the caller did not ask to continue end with the command, and the
+        // policies of policy sessions that continue start over.
+        for session in sessions {
+            let handle = session.handle();
+            if session.auth.session_handle == TpmiShAuthSession::RS_PW {
+                continue;
+            }
+            if !session
+                .auth
+                .session_attributes
+                .contains(TpmaSession::CONTINUE_SESSION)
+            {
+                self.handler.end_session(handle);
+            } else if self
+                .handler
+                .session(handle)
+                .is_some_and(|loaded| loaded.is_policy())
+            {
+                self.handler.restart_policy(handle)?;
+            }
+        }
+
+        if !sessions.is_empty() {
+            let parameter_size = parameter_end - parameter_start;
+            response
+                .write(parameter_offset, &(parameter_size as u32).to_be_bytes())
+                .or(Err(TpmRcError::Memory))?;
+            for auth_response in &auth_responses[..sessions.len()] {
+                let mut written = Err(TpmRcError::Memory);
+                let remaining = response.len().saturating_sub(parameter_end);
+                response
+                    .write_callback(parameter_end, remaining, |buffer| {
+                        written = auth_response
+                            .try_marshal(buffer)
+                            .or(Err(TpmRcError::Memory));
+                    })
+                    .or(Err(TpmRcError::Memory))?;
+                parameter_end += written?;
+            }
+        }
+        let response_size = parameter_end;
+
         response
-            .write(0, &(session_tag).to_be_bytes())
+            .write(0, &tag.0.to_be_bytes())
             .or(Err(TpmRcError::Memory))?;
-
         response
             .write(2, &(response_size as u32).to_be_bytes())
             .or(Err(TpmRcError::Memory))?;